@@ -17,20 +17,35 @@ import json
 import base64
 import tempfile
 import logging
+import re
+import threading
 import hashlib
 from pathlib import Path
 
-from flask import Flask, request, jsonify, send_file
+from flask import Flask, request, jsonify, send_file, has_request_context
 from flask_cors import CORS
 import torch
 import numpy as np
 import soundfile as sf
 
 # Configure logging
+class RequestIdFilter(logging.Filter):
+    """Tag log lines with the client's X-Request-Id, so a failed request
+    can be matched to the error the client reported."""
+
+    def filter(self, record):
+        record.request_id = '-'
+        if has_request_context():
+            record.request_id = request.headers.get('X-Request-Id', '-')
+        return True
+
+
 logging.basicConfig(
     level=logging.INFO,
-    format='%(asctime)s - %(name)s - %(levelname)s - %(message)s'
+    format='%(asctime)s - %(name)s - %(levelname)s - [%(request_id)s] %(message)s'
 )
+for handler in logging.getLogger().handlers:
+    handler.addFilter(RequestIdFilter())
 logger = logging.getLogger('openf5-server')
 
 app = Flask(__name__)
@@ -46,6 +61,58 @@ VOICE_DIR.mkdir(exist_ok=True)
 
 # Sample rate for F5-TTS
 SAMPLE_RATE = 24000
+SUPPORTED_LANGUAGES = ['EN', 'ZH']
+
+# Text per inference call; a cancelled job stops between calls
+BATCH_CHARS = 300
+
+# Synthesis jobs in progress, and the ones clients have cancelled. A
+# cancelled job stops at its next checkpoint instead of finishing on the GPU.
+active_jobs = set()
+cancelled_jobs = set()
+jobs_lock = threading.Lock()
+
+
+class JobCancelled(Exception):
+    """Raised at a checkpoint of a job the client cancelled."""
+
+
+def start_job(job_id):
+    """Track a job so it can be cancelled."""
+    if job_id:
+        with jobs_lock:
+            active_jobs.add(job_id)
+
+
+def check_cancelled(job_id):
+    """Raise JobCancelled if the client cancelled this job."""
+    if job_id:
+        with jobs_lock:
+            if job_id in cancelled_jobs:
+                raise JobCancelled(job_id)
+
+
+def finish_job(job_id):
+    """Stop tracking a job."""
+    if job_id:
+        with jobs_lock:
+            active_jobs.discard(job_id)
+            cancelled_jobs.discard(job_id)
+
+
+def split_batches(text):
+    """Split text at sentence ends into batches of about BATCH_CHARS."""
+    batches = []
+    current = ''
+    for sentence in re.split(r'(?<=[.!?\u3002\uff01\uff1f])\s*', text.strip()):
+        if current and len(current) + len(sentence) + 1 > BATCH_CHARS:
+            batches.append(current)
+            current = sentence
+        else:
+            current = f"{current} {sentence}".strip()
+    if current:
+        batches.append(current)
+    return batches
 
 
 def get_device():
@@ -98,6 +165,7 @@ def health():
     return jsonify({
         'status': 'healthy',
         'model': 'openf5_tts',
+        'model_version': 'OpenF5-TTS-Base',
         'license': 'Apache 2.0',
         'cuda_available': cuda_available,
         'gpu': gpu_name,
@@ -113,7 +181,7 @@ def info():
         'license': 'Apache 2.0',
         'weights': 'OpenF5 (Emilia-YODAS trained)',
         'capabilities': ['voice_cloning', 'tts', 'emotion_preservation'],
-        'supported_languages': ['EN', 'ZH'],
+        'supported_languages': SUPPORTED_LANGUAGES,
         'sample_rate': SAMPLE_RATE,
         'note': 'Uses flow-matching for high-quality voice cloning'
     })
@@ -214,19 +282,32 @@ def synthesize():
     - name: Name of a saved voice
     - OR audio: Base64 encoded reference audio
     - OR audio + transcript: Reference audio and its transcript
+    - language: (optional) Language code (default: EN)
     - speed: (optional) Speech speed (default: 1.0)
+    - seed: (optional) Random seed, to repeat a generation
+    - job_id: (optional) ID that POST /cancel/<job_id> stops
     """
+    job_id = None
     try:
         data = request.get_json()
 
         if not data:
             return jsonify({'error': 'JSON body required'}), 400
 
+        job_id = data.get('job_id')
+        start_job(job_id)
+
         text = data.get('text')
         if not text:
             return jsonify({'error': 'Text is required'}), 400
 
+        # The F5 base model is trained on both languages; no switch needed
+        language = data.get('language', 'EN').upper()
+        if language not in SUPPORTED_LANGUAGES:
+            return jsonify({'error': f"Unsupported language '{language}'"}), 400
+
         speed = data.get('speed', 1.0)
+        seed = data.get('seed')
 
         # Get reference audio and transcript
         ref_audio_path = None
@@ -279,13 +360,20 @@ def synthesize():
             ) as tmp_out:
                 output_path = tmp_out.name
 
-            # Run synthesis
-            audio_output, sr, _ = f5_model.infer(
-                ref_file=ref_audio_path,
-                ref_text=ref_text,
-                gen_text=text,
-                speed=speed
-            )
+            # Run synthesis a batch at a time, so cancelled jobs free the GPU
+            parts = []
+            sr = SAMPLE_RATE
+            for batch in split_batches(text):
+                check_cancelled(job_id)
+                part, sr, _ = f5_model.infer(
+                    ref_file=ref_audio_path,
+                    ref_text=ref_text,
+                    gen_text=batch,
+                    speed=speed,
+                    seed=seed
+                )
+                parts.append(part)
+            audio_output = np.concatenate(parts)
 
             # Save output
             sf.write(output_path, audio_output, sr)
@@ -309,12 +397,19 @@ def synthesize():
             if temp_ref_file and os.path.exists(temp_ref_file.name):
                 os.unlink(temp_ref_file.name)
 
+    except JobCancelled:
+        logger.info(f"Job {job_id} cancelled")
+        return jsonify({'error': 'Cancelled'}), 409
+
     except Exception as e:
         logger.error(f"Synthesis failed: {e}")
         import traceback
         traceback.print_exc()
         return jsonify({'error': str(e)}), 500
 
+    finally:
+        finish_job(job_id)
+
 
 @app.route('/voices', methods=['GET'])
 def list_voices():
@@ -336,6 +431,15 @@ def list_voices():
     return jsonify({'voices': voices})
 
 
+@app.route('/voices/<name>/embedding', methods=['GET'])
+def get_embedding(name):
+    """F5-TTS conditions on reference audio directly; there is no embedding."""
+    if not (VOICE_DIR / f"{name}.json").exists():
+        return jsonify({'error': f"Voice '{name}' not found"}), 404
+
+    return jsonify({'error': 'OpenF5 voices have no speaker embedding'}), 501
+
+
 @app.route('/voices/<name>', methods=['DELETE'])
 def delete_voice(name):
     """Delete a saved voice."""
@@ -352,6 +456,18 @@ def delete_voice(name):
     return jsonify({'success': True, 'deleted': name})
 
 
+@app.route('/cancel/<job_id>', methods=['POST'])
+def cancel(job_id):
+    """Stop a synthesis job at its next checkpoint."""
+    with jobs_lock:
+        if job_id not in active_jobs:
+            return jsonify({'error': f"Job '{job_id}' is not running"}), 404
+        cancelled_jobs.add(job_id)
+
+    logger.info(f"Cancelling job {job_id}")
+    return jsonify({'success': True, 'cancelled': job_id})
+
+
 if __name__ == '__main__':
     logger.info("Starting OpenF5-TTS server...")
     logger.info(f"PyTorch version: {torch.__version__}")
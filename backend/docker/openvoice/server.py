@@ -15,19 +15,33 @@ import json
 import base64
 import tempfile
 import logging
+import threading
 from pathlib import Path
 
-from flask import Flask, request, jsonify, send_file
+from flask import Flask, request, jsonify, send_file, has_request_context
 from flask_cors import CORS
 import torch
 import numpy as np
 import soundfile as sf
 
 # Configure logging
+class RequestIdFilter(logging.Filter):
+    """Tag log lines with the client's X-Request-Id, so a failed request
+    can be matched to the error the client reported."""
+
+    def filter(self, record):
+        record.request_id = '-'
+        if has_request_context():
+            record.request_id = request.headers.get('X-Request-Id', '-')
+        return True
+
+
 logging.basicConfig(
     level=logging.INFO,
-    format='%(asctime)s - %(name)s - %(levelname)s - %(message)s'
+    format='%(asctime)s - %(name)s - %(levelname)s - [%(request_id)s] %(message)s'
 )
+for handler in logging.getLogger().handlers:
+    handler.addFilter(RequestIdFilter())
 logger = logging.getLogger('openvoice-server')
 
 app = Flask(__name__)
@@ -35,13 +49,48 @@ CORS(app)
 
 # Global model instances
 tone_color_converter = None
-tts_model = None
+tts_models = {}
 device = None
 
+SUPPORTED_LANGUAGES = ['EN', 'ZH', 'JP', 'KR']
+
 # Voice storage
 VOICE_DIR = Path('/app/voices')
 VOICE_DIR.mkdir(exist_ok=True)
 
+# Synthesis jobs in progress, and the ones clients have cancelled. A
+# cancelled job stops at its next checkpoint instead of finishing on the GPU.
+active_jobs = set()
+cancelled_jobs = set()
+jobs_lock = threading.Lock()
+
+
+class JobCancelled(Exception):
+    """Raised at a checkpoint of a job the client cancelled."""
+
+
+def start_job(job_id):
+    """Track a job so it can be cancelled."""
+    if job_id:
+        with jobs_lock:
+            active_jobs.add(job_id)
+
+
+def check_cancelled(job_id):
+    """Raise JobCancelled if the client cancelled this job."""
+    if job_id:
+        with jobs_lock:
+            if job_id in cancelled_jobs:
+                raise JobCancelled(job_id)
+
+
+def finish_job(job_id):
+    """Stop tracking a job."""
+    if job_id:
+        with jobs_lock:
+            active_jobs.discard(job_id)
+            cancelled_jobs.discard(job_id)
+
 
 def get_device():
     """Detect and return the best available device."""
@@ -56,7 +105,7 @@ def get_device():
 
 def load_models():
     """Load OpenVoice and MeloTTS models."""
-    global tone_color_converter, tts_model, device
+    global tone_color_converter, device
 
     device = get_device()
     logger.info(f"Loading models on device: {device}")
@@ -64,7 +113,6 @@ def load_models():
     try:
         from openvoice.api import ToneColorConverter
         from openvoice import se_extractor
-        from melo.api import TTS
 
         # Load tone color converter
         ckpt_converter = 'checkpoints_v2/converter'
@@ -76,8 +124,7 @@ def load_models():
         logger.info("Tone color converter loaded")
 
         # Load MeloTTS for base synthesis
-        tts_model = TTS(language='EN', device=device)
-        logger.info("MeloTTS loaded")
+        get_tts_model('EN')
 
         logger.info("All models loaded successfully")
 
@@ -86,6 +133,16 @@ def load_models():
         raise
 
 
+def get_tts_model(language):
+    """Return the MeloTTS base model for a language, loading it on first use."""
+    if language not in tts_models:
+        from melo.api import TTS
+
+        tts_models[language] = TTS(language=language, device=device)
+        logger.info(f"MeloTTS loaded for {language}")
+    return tts_models[language]
+
+
 @app.route('/health', methods=['GET'])
 def health():
     """Health check endpoint."""
@@ -95,6 +152,7 @@ def health():
     return jsonify({
         'status': 'healthy',
         'model': 'openvoice_v2',
+        'model_version': 'checkpoints_v2',
         'cuda_available': cuda_available,
         'gpu': gpu_name,
         'device': str(device)
@@ -108,7 +166,7 @@ def info():
         'model': 'OpenVoice V2',
         'license': 'MIT',
         'capabilities': ['voice_cloning', 'tts'],
-        'supported_languages': ['EN', 'ZH', 'JP', 'KR'],
+        'supported_languages': SUPPORTED_LANGUAGES,
         'sample_rate': 24000
     })
 
@@ -194,19 +252,28 @@ def synthesize():
     - OR name: Name of a saved voice
     - language: (optional) Language code (default: EN)
     - speed: (optional) Speech speed (default: 1.0)
+    - seed: (optional) Random seed, to repeat a generation
+    - job_id: (optional) ID that POST /cancel/<job_id> stops
     """
+    job_id = None
     try:
         data = request.get_json()
 
         if not data:
             return jsonify({'error': 'JSON body required'}), 400
 
+        job_id = data.get('job_id')
+        start_job(job_id)
+
         text = data.get('text')
         if not text:
             return jsonify({'error': 'Text is required'}), 400
 
-        language = data.get('language', 'EN')
+        language = data.get('language', 'EN').upper()
+        if language not in SUPPORTED_LANGUAGES:
+            return jsonify({'error': f"Unsupported language '{language}'"}), 400
         speed = data.get('speed', 1.0)
+        seed = data.get('seed')
 
         # Get voice embedding
         if 'name' in data:
@@ -235,6 +302,10 @@ def synthesize():
         ).to(device)
 
         # Generate base audio with MeloTTS
+        check_cancelled(job_id)
+        if seed is not None:
+            torch.manual_seed(seed)
+        tts_model = get_tts_model(language)
         speaker_ids = tts_model.hps.data.spk2id
         speaker_id = list(speaker_ids.values())[0]  # Use first speaker
 
@@ -248,6 +319,8 @@ def synthesize():
             base_path = tmp_base.name
 
         try:
+            check_cancelled(job_id)
+
             # Get source speaker embedding from base audio
             from openvoice import se_extractor
             source_se, _ = se_extractor.get_se(
@@ -288,10 +361,91 @@ def synthesize():
         finally:
             os.unlink(base_path)
 
+    except JobCancelled:
+        logger.info(f"Job {job_id} cancelled")
+        return jsonify({'error': 'Cancelled'}), 409
+
     except Exception as e:
         logger.error(f"Synthesis failed: {e}")
         return jsonify({'error': str(e)}), 500
 
+    finally:
+        finish_job(job_id)
+
+
+@app.route('/convert', methods=['POST'])
+def convert():
+    """
+    Convert existing speech to a saved voice's tone color.
+
+    Lets audio generated by another model take on a voice extracted here.
+
+    Expects multipart form data:
+    - audio: Speech to convert (WAV)
+    - name: Name of a saved voice
+    """
+    try:
+        if 'audio' not in request.files:
+            return jsonify({'error': 'No audio file provided'}), 400
+        name = request.form.get('name')
+        if not name:
+            return jsonify({'error': 'Voice name is required'}), 400
+
+        voice_path = VOICE_DIR / f"{name}.json"
+        if not voice_path.exists():
+            return jsonify({'error': f"Voice '{name}' not found"}), 404
+        with open(voice_path) as f:
+            voice_data = json.load(f)
+        target_se = torch.from_numpy(
+            np.frombuffer(
+                base64.b64decode(voice_data['embedding']), dtype=np.float32
+            ).reshape(voice_data['shape'])
+        ).to(device)
+
+        with tempfile.NamedTemporaryFile(suffix='.wav', delete=False) as tmp_src:
+            request.files['audio'].save(tmp_src.name)
+            source_path = tmp_src.name
+
+        try:
+            from openvoice import se_extractor
+            source_se, _ = se_extractor.get_se(
+                source_path,
+                tone_color_converter,
+                vad=False
+            )
+
+            with tempfile.NamedTemporaryFile(suffix='.wav', delete=False) as tmp_out:
+                tone_color_converter.convert(
+                    audio_src_path=source_path,
+                    src_se=source_se,
+                    tgt_se=target_se,
+                    output_path=tmp_out.name
+                )
+                output_path = tmp_out.name
+
+            try:
+                audio_data, sample_rate = sf.read(output_path)
+                buffer = io.BytesIO()
+                sf.write(buffer, audio_data, sample_rate, format='WAV')
+                buffer.seek(0)
+
+                return send_file(
+                    buffer,
+                    mimetype='audio/wav',
+                    as_attachment=True,
+                    download_name='output.wav'
+                )
+
+            finally:
+                os.unlink(output_path)
+
+        finally:
+            os.unlink(source_path)
+
+    except Exception as e:
+        logger.error(f"Conversion failed: {e}")
+        return jsonify({'error': str(e)}), 500
+
 
 @app.route('/voices', methods=['GET'])
 def list_voices():
@@ -309,6 +463,82 @@ def list_voices():
     return jsonify({'voices': voices})
 
 
+@app.route('/voices/random', methods=['POST'])
+def random_voice():
+    """
+    Create a voice that belongs to no one.
+
+    The embedding is a random blend of the base speakers' tone colors,
+    nudged within their spread, so it stays plausible without matching
+    any of them.
+
+    Expects JSON:
+    - name: Name to save the voice as
+    - seed: (optional) Random seed, to draw the same voice again
+    """
+    try:
+        data = request.get_json() or {}
+        name = data.get('name')
+        if not name:
+            return jsonify({'error': 'Voice name is required'}), 400
+        voice_path = VOICE_DIR / f"{name}.json"
+        if voice_path.exists():
+            return jsonify({'error': f"Voice '{name}' already exists"}), 409
+
+        base_dir = Path('/app/openvoice/checkpoints_v2/base_speakers/ses')
+        bases = [
+            torch.load(path, map_location='cpu').numpy()
+            for path in sorted(base_dir.glob('*.pth'))
+        ]
+        if len(bases) < 2:
+            return jsonify({'error': 'Base speaker embeddings not found'}), 500
+        bases = np.stack(bases)
+
+        rng = np.random.default_rng(data.get('seed'))
+        weights = rng.dirichlet(np.ones(len(bases)))
+        blend = np.tensordot(weights, bases, axes=1)
+        spread = bases.std(axis=0)
+        embedding = (blend + rng.normal(0.0, 0.5, blend.shape) * spread).astype(np.float32)
+
+        with open(voice_path, 'w') as f:
+            json.dump({
+                'embedding': base64.b64encode(embedding.tobytes()).decode('utf-8'),
+                'shape': list(embedding.shape),
+                'transcript': '',
+                'model': 'openvoice_v2',
+                'synthetic': True
+            }, f)
+        logger.info(f"Random voice saved as: {name}")
+
+        return jsonify({
+            'name': name,
+            'transcript': '',
+            'model': 'openvoice_v2',
+            'duration': None
+        })
+
+    except Exception as e:
+        logger.error(f"Random voice failed: {e}")
+        return jsonify({'error': str(e)}), 500
+
+
+@app.route('/voices/<name>/embedding', methods=['GET'])
+def get_embedding(name):
+    """Return the tone color embedding of a saved voice."""
+    voice_path = VOICE_DIR / f"{name}.json"
+    if not voice_path.exists():
+        return jsonify({'error': f"Voice '{name}' not found"}), 404
+
+    with open(voice_path) as f:
+        data = json.load(f)
+
+    return jsonify({
+        'name': name,
+        'shape': data['shape'],
+        'embedding': data['embedding']
+    })
+
+
 @app.route('/voices/<name>', methods=['DELETE'])
 def delete_voice(name):
     """Delete a saved voice."""
@@ -320,6 +550,18 @@ def delete_voice(name):
     return jsonify({'success': True, 'deleted': name})
 
 
+@app.route('/cancel/<job_id>', methods=['POST'])
+def cancel(job_id):
+    """Stop a synthesis job at its next checkpoint."""
+    with jobs_lock:
+        if job_id not in active_jobs:
+            return jsonify({'error': f"Job '{job_id}' is not running"}), 404
+        cancelled_jobs.add(job_id)
+
+    logger.info(f"Cancelling job {job_id}")
+    return jsonify({'success': True, 'cancelled': job_id})
+
+
 if __name__ == '__main__':
     logger.info("Starting OpenVoice V2 server...")
     logger.info(f"PyTorch version: {torch.__version__}")
@@ -0,0 +1,110 @@
+//! Rule-based English grapheme-to-phoneme conversion.
+//!
+//! This is a rough spelling-based approximation producing ARPAbet symbols.
+//! It is good enough to drive mouth shapes, not to judge pronunciation.
+
+/// Multi-letter spellings, longest first.
+const RULES: &[(&str, &[&str])] = &[
+    ("ough", &["AO"]),
+    ("tch", &["CH"]),
+    ("igh", &["AY"]),
+    ("sh", &["SH"]),
+    ("ch", &["CH"]),
+    ("th", &["TH"]),
+    ("ph", &["F"]),
+    ("wh", &["W"]),
+    ("ck", &["K"]),
+    ("ng", &["NG"]),
+    ("qu", &["K", "W"]),
+    ("ee", &["IY"]),
+    ("ea", &["IY"]),
+    ("oo", &["UW"]),
+    ("ou", &["AW"]),
+    ("ow", &["OW"]),
+    ("oi", &["OY"]),
+    ("oy", &["OY"]),
+    ("ai", &["EY"]),
+    ("ay", &["EY"]),
+    ("au", &["AO"]),
+    ("aw", &["AO"]),
+    ("er", &["ER"]),
+    ("ir", &["ER"]),
+    ("ur", &["ER"]),
+    ("ar", &["AA", "R"]),
+    ("or", &["AO", "R"]),
+];
+
+fn letter(c: char) -> &'static [&'static str] {
+    match c {
+        'a' => &["AE"],
+        'b' => &["B"],
+        'c' | 'k' | 'q' => &["K"],
+        'd' => &["D"],
+        'e' => &["EH"],
+        'f' => &["F"],
+        'g' => &["G"],
+        'h' => &["HH"],
+        'i' => &["IH"],
+        'j' => &["JH"],
+        'l' => &["L"],
+        'm' => &["M"],
+        'n' => &["N"],
+        'o' => &["AA"],
+        'p' => &["P"],
+        'r' => &["R"],
+        's' => &["S"],
+        't' => &["T"],
+        'u' => &["AH"],
+        'v' => &["V"],
+        'w' => &["W"],
+        'x' => &["K", "S"],
+        'y' => &["Y"],
+        'z' => &["Z"],
+        _ => &[],
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// Convert one word to ARPAbet phonemes.
+///
+/// Letters outside a-z (digits, accents, punctuation) are ignored.
+pub fn phonemes(word: &str) -> Vec<&'static str> {
+    let mut letters: Vec<char> = word
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .filter(char::is_ascii_lowercase)
+        .collect();
+
+    // Silent final e: "make", "stone" (but not "the", "be")
+    if letters.len() > 3 && letters.last() == Some(&'e') && !is_vowel(letters[letters.len() - 2]) {
+        letters.pop();
+    }
+
+    let mut result = Vec::new();
+    let mut i = 0;
+
+    'outer: while i < letters.len() {
+        let rest: String = letters[i..].iter().collect();
+        for (spelling, sounds) in RULES {
+            if rest.starts_with(spelling) {
+                result.extend_from_slice(sounds);
+                i += spelling.len();
+                continue 'outer;
+            }
+        }
+
+        let c = letters[i];
+        let doubled = i > 0 && letters[i - 1] == c && !is_vowel(c);
+        if c == 'y' && i > 0 && i == letters.len() - 1 {
+            result.push("IY");
+        } else if !doubled {
+            result.extend_from_slice(letter(c));
+        }
+        i += 1;
+    }
+
+    result
+}
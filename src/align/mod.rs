@@ -0,0 +1,171 @@
+//! Phoneme and viseme timelines for lip sync.
+//!
+//! The input text is converted to phonemes with a rule-based G2P and timed
+//! against the pauses of the synthesized audio. Each phoneme maps to a
+//! viseme (mouth shape) so game and animation pipelines can drive lip sync
+//! from the CLI output.
+
+mod g2p;
+mod timeline;
+mod viseme;
+
+pub use g2p::phonemes;
+pub use timeline::{Span, Timeline, voiced_regions};
+pub use viseme::{SILENCE, viseme};
+
+use thiserror::Error;
+
+/// Errors that can occur when writing or reading a timeline.
+#[derive(Error, Debug)]
+pub enum AlignError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    const RATE: u32 = 16000;
+
+    /// Alternating tone and silence, e.g. `&[(0.5, true), (0.5, false)]`.
+    fn buffer(parts: &[(f64, bool)]) -> AudioBuffer {
+        let mut samples = Vec::new();
+        for &(seconds, voiced) in parts {
+            let n = (seconds * f64::from(RATE)) as usize;
+            samples.extend((0..n).map(|i| {
+                if voiced {
+                    0.5 * (i as f32 * 0.1).sin()
+                } else {
+                    0.0
+                }
+            }));
+        }
+        AudioBuffer::new(samples, RATE, 1)
+    }
+
+    // ===========================================
+    // G2P and viseme tests
+    // ===========================================
+
+    #[test]
+    fn test_phonemes_digraphs() {
+        assert_eq!(phonemes("ship"), vec!["SH", "IH", "P"]);
+        assert_eq!(phonemes("Thing"), vec!["TH", "IH", "NG"]);
+        assert_eq!(phonemes("cheese"), vec!["CH", "IY", "S"]);
+    }
+
+    #[test]
+    fn test_phonemes_silent_e_and_doubles() {
+        assert_eq!(phonemes("make"), vec!["M", "AE", "K"]);
+        assert_eq!(phonemes("the"), vec!["TH", "EH"]);
+        assert_eq!(phonemes("happy"), vec!["HH", "AE", "P", "IY"]);
+    }
+
+    #[test]
+    fn test_phonemes_ignores_non_letters() {
+        assert_eq!(phonemes("it's"), vec!["IH", "T", "S"]);
+        assert!(phonemes("42").is_empty());
+    }
+
+    #[test]
+    fn test_viseme_mapping() {
+        assert_eq!(viseme("M"), "PP");
+        assert_eq!(viseme("F"), "FF");
+        assert_eq!(viseme("IY"), "ih");
+        assert_eq!(viseme("OW"), "oh");
+        assert_eq!(viseme("?"), SILENCE);
+    }
+
+    // ===========================================
+    // Timeline tests
+    // ===========================================
+
+    #[test]
+    fn test_voiced_regions_split_on_pause() {
+        let audio = buffer(&[(0.2, false), (0.5, true), (0.5, false), (0.5, true)]);
+        let regions = voiced_regions(&audio);
+
+        assert_eq!(regions.len(), 2);
+        assert!((regions[0].0 - 0.2).abs() < 0.02);
+        assert!((regions[0].1 - 0.7).abs() < 0.02);
+        assert!((regions[1].0 - 1.2).abs() < 0.02);
+        assert!((regions[1].1 - 1.7).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_voiced_regions_merge_short_gaps() {
+        let audio = buffer(&[(0.5, true), (0.05, false), (0.5, true)]);
+        assert_eq!(voiced_regions(&audio).len(), 1);
+    }
+
+    #[test]
+    fn test_timeline_align() {
+        let audio = buffer(&[(0.5, true), (0.5, false), (0.5, true)]);
+        let timeline = Timeline::align(&audio, "Hello, world!");
+
+        assert!((timeline.duration - 1.5).abs() < 1e-9);
+        let words: Vec<_> = timeline.words.iter().map(|w| w.label.as_str()).collect();
+        assert_eq!(words, vec!["Hello", "world"]);
+
+        // Phonemes are ordered and stay within the audio
+        for pair in timeline.phonemes.windows(2) {
+            assert!(pair[0].start <= pair[1].start);
+        }
+        assert!(timeline.phonemes.iter().all(|p| p.end <= timeline.duration));
+
+        // Visemes cover the whole audio without gaps
+        assert_eq!(timeline.visemes.first().unwrap().start, 0.0);
+        assert_eq!(timeline.visemes.last().unwrap().end, timeline.duration);
+        for pair in timeline.visemes.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+            assert_ne!(pair[0].label, pair[1].label);
+        }
+        assert!(
+            timeline
+                .visemes
+                .iter()
+                .any(|v| v.label == SILENCE && v.start <= 0.51 && v.end >= 0.99)
+        );
+    }
+
+    #[test]
+    fn test_timeline_silent_audio() {
+        let timeline = Timeline::align(&buffer(&[(1.0, false)]), "Hello");
+
+        assert!(timeline.phonemes.is_empty());
+        assert_eq!(
+            timeline.visemes,
+            vec![Span {
+                label: SILENCE.to_string(),
+                start: 0.0,
+                end: 1.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_timeline_default_path() {
+        assert_eq!(
+            Timeline::default_path(Path::new("/out/line.wav")),
+            PathBuf::from("/out/line.visemes.json")
+        );
+    }
+
+    #[test]
+    fn test_timeline_write_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("t.json");
+
+        let timeline = Timeline::align(&buffer(&[(0.5, true)]), "Hi");
+        timeline.write(&path).unwrap();
+
+        assert_eq!(Timeline::load(&path).unwrap(), timeline);
+    }
+}
@@ -0,0 +1,206 @@
+//! Timing phonemes and visemes against synthesized audio.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::AlignError;
+use super::g2p::phonemes;
+use super::viseme::{SILENCE, is_vowel, viseme};
+use crate::audio::AudioBuffer;
+
+/// Analysis frame length in seconds.
+const FRAME: f64 = 0.01;
+
+/// Frames quieter than this fraction of the loudest frame count as silence.
+const SILENCE_RATIO: f32 = 0.05;
+
+/// Pauses shorter than this are treated as part of the surrounding speech.
+const MIN_GAP: f64 = 0.15;
+
+/// Vowels are held longer than consonants.
+const VOWEL_WEIGHT: f64 = 1.6;
+
+/// A labelled time range in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Span {
+    pub label: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Span {
+    fn new(label: &str, start: f64, end: f64) -> Self {
+        Self {
+            label: label.to_string(),
+            start: round_ms(start),
+            end: round_ms(end),
+        }
+    }
+}
+
+/// Word, phoneme, and viseme timing for one audio file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    /// Audio length in seconds.
+    pub duration: f64,
+    pub words: Vec<Span>,
+    pub phonemes: Vec<Span>,
+    /// Contiguous visemes covering the whole audio, `sil` in pauses.
+    pub visemes: Vec<Span>,
+}
+
+impl Timeline {
+    /// Align the phonemes of `text` to the speech in `audio`.
+    ///
+    /// Phonemes are spread over the non-silent stretches of the audio in
+    /// proportion to their typical length, so timing follows pauses and
+    /// overall pace but not individual syllables.
+    pub fn align(audio: &AudioBuffer, text: &str) -> Self {
+        let duration = audio.duration().as_secs_f64();
+        let regions = voiced_regions(audio);
+        let voiced: f64 = regions.iter().map(|(start, end)| end - start).sum();
+
+        let words: Vec<(&str, Vec<&str>)> = text
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+            .map(|w| (w, phonemes(w)))
+            .filter(|(_, p)| !p.is_empty())
+            .collect();
+
+        let weight = |p: &str| if is_vowel(p) { VOWEL_WEIGHT } else { 1.0 };
+        let total: f64 = words.iter().flat_map(|(_, p)| p).map(|p| weight(p)).sum();
+
+        let mut timeline = Self {
+            duration: round_ms(duration),
+            words: Vec::new(),
+            phonemes: Vec::new(),
+            visemes: Vec::new(),
+        };
+
+        if total > 0.0 && voiced > 0.0 {
+            let scale = voiced / total;
+            let mut cursor = 0.0;
+
+            for (word, sounds) in &words {
+                let first = timeline.phonemes.len();
+                for p in sounds {
+                    let (start, region) = to_real(&regions, cursor, false);
+                    cursor += weight(p) * scale;
+                    // A phoneme straddling a pause is cut off at the pause
+                    let end = to_real(&regions, cursor, true).0.min(regions[region].1);
+                    timeline.phonemes.push(Span::new(p, start, end));
+                }
+                let start = timeline.phonemes[first].start;
+                let end = timeline.phonemes.last().map_or(start, |p| p.end);
+                timeline.words.push(Span::new(word, start, end));
+            }
+        }
+
+        timeline.visemes = visemes(&timeline.phonemes, duration);
+        timeline
+    }
+
+    /// Default timeline location next to an output file (`out.wav` ->
+    /// `out.visemes.json`).
+    pub fn default_path(output: &Path) -> PathBuf {
+        output.with_extension("visemes.json")
+    }
+
+    /// Write the timeline as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<(), AlignError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read a timeline from disk.
+    pub fn load(path: &Path) -> Result<Self, AlignError> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Find the stretches of audio that contain sound, as (start, end) seconds.
+pub fn voiced_regions(audio: &AudioBuffer) -> Vec<(f64, f64)> {
+    let samples = audio.mono();
+    let frame_len = ((f64::from(audio.sample_rate) * FRAME) as usize).max(1);
+    let energy: Vec<f32> = samples
+        .chunks(frame_len)
+        .map(|frame| (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt())
+        .collect();
+
+    let peak = energy.iter().copied().fold(0.0, f32::max);
+    if peak <= 0.0 {
+        return Vec::new();
+    }
+    let threshold = peak * SILENCE_RATIO;
+
+    let mut regions: Vec<(f64, f64)> = Vec::new();
+    let mut start = None;
+    for (i, &e) in energy.iter().chain(std::iter::once(&0.0)).enumerate() {
+        let time = i as f64 * frame_len as f64 / f64::from(audio.sample_rate);
+        match (e > threshold, start) {
+            (true, None) => start = Some(time),
+            (false, Some(s)) => {
+                let end = time.min(audio.duration().as_secs_f64());
+                match regions.last_mut() {
+                    Some(last) if s - last.1 < MIN_GAP => last.1 = end,
+                    _ => regions.push((s, end)),
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    regions
+}
+
+/// Map a position in voiced time (pauses removed) to a position in the audio.
+///
+/// A position on a region boundary maps to the end of the earlier region
+/// when `end` is set and to the start of the later one otherwise. Also
+/// returns the index of the region.
+fn to_real(regions: &[(f64, f64)], voiced: f64, end: bool) -> (f64, usize) {
+    let mut remaining = voiced;
+    for (i, &(start, stop)) in regions.iter().enumerate() {
+        let len = stop - start;
+        let last = i == regions.len() - 1;
+        if remaining < len || (end && remaining <= len) || last {
+            return ((start + remaining).min(stop), i);
+        }
+        remaining -= len;
+    }
+    (0.0, 0)
+}
+
+/// Merge phonemes into visemes, filling every gap with silence.
+fn visemes(phonemes: &[Span], duration: f64) -> Vec<Span> {
+    let mut result: Vec<Span> = Vec::new();
+    let mut cursor = 0.0;
+
+    for p in phonemes {
+        let label = viseme(&p.label);
+        if p.start > cursor + 0.001 {
+            push_viseme(&mut result, SILENCE, cursor, p.start);
+        }
+        push_viseme(&mut result, label, p.start.max(cursor), p.end);
+        cursor = cursor.max(p.end);
+    }
+    if duration > cursor + 0.001 || result.is_empty() {
+        push_viseme(&mut result, SILENCE, cursor, duration);
+    }
+
+    result
+}
+
+fn push_viseme(visemes: &mut Vec<Span>, label: &str, start: f64, end: f64) {
+    match visemes.last_mut() {
+        Some(last) if last.label == label => last.end = round_ms(end),
+        _ => visemes.push(Span::new(label, start, end)),
+    }
+}
+
+fn round_ms(seconds: f64) -> f64 {
+    (seconds * 1000.0).round() / 1000.0
+}
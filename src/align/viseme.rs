@@ -0,0 +1,48 @@
+//! Phoneme-to-viseme mapping.
+
+/// Viseme shown when the mouth is at rest.
+pub const SILENCE: &str = "sil";
+
+/// Map an ARPAbet phoneme to one of the 15 standard visemes used by
+/// common lip-sync rigs (`sil`, `PP`, `FF`, `TH`, `DD`, `kk`, `CH`, `SS`,
+/// `nn`, `RR`, `aa`, `E`, `ih`, `oh`, `ou`).
+pub fn viseme(phoneme: &str) -> &'static str {
+    match phoneme {
+        "P" | "B" | "M" => "PP",
+        "F" | "V" => "FF",
+        "TH" | "DH" => "TH",
+        "T" | "D" => "DD",
+        "K" | "G" | "NG" | "HH" => "kk",
+        "CH" | "JH" | "SH" | "ZH" => "CH",
+        "S" | "Z" => "SS",
+        "N" | "L" => "nn",
+        "R" | "ER" => "RR",
+        "AA" | "AE" | "AH" | "AY" | "AW" => "aa",
+        "EH" | "EY" => "E",
+        "IH" | "IY" | "Y" => "ih",
+        "AO" | "OW" | "OY" => "oh",
+        "UW" | "UH" | "W" => "ou",
+        _ => SILENCE,
+    }
+}
+
+/// Returns true for phonemes that carry a vowel sound.
+pub fn is_vowel(phoneme: &str) -> bool {
+    matches!(
+        phoneme,
+        "AA" | "AE"
+            | "AH"
+            | "AY"
+            | "AW"
+            | "EH"
+            | "EY"
+            | "IH"
+            | "IY"
+            | "AO"
+            | "OW"
+            | "OY"
+            | "UW"
+            | "UH"
+            | "ER"
+    )
+}
@@ -0,0 +1,158 @@
+//! In-memory audio buffer with WAV encoding and decoding.
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+use super::AudioError;
+
+/// Interleaved floating-point audio samples in the range -1.0..=1.0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioBuffer {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl AudioBuffer {
+    /// Create a buffer from interleaved samples.
+    pub fn new(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            samples,
+            sample_rate,
+            channels,
+        }
+    }
+
+    /// Create a buffer of silence.
+    pub fn silence(duration: Duration, sample_rate: u32, channels: u16) -> Self {
+        let frames = silence_frames(duration, sample_rate);
+        Self::new(vec![0.0; frames * channels as usize], sample_rate, channels)
+    }
+
+    /// Decode a WAV file held in memory.
+    pub fn from_wav_bytes(data: &[u8]) -> Result<Self, AudioError> {
+        let mut reader = WavReader::new(Cursor::new(data))?;
+        let spec = reader.spec();
+
+        let samples = match spec.sample_format {
+            SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            SampleFormat::Int => {
+                let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|v| v as f32 / scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        Ok(Self::new(samples, spec.sample_rate, spec.channels))
+    }
+
+    /// Encode as a 16-bit PCM WAV file.
+    pub fn to_wav_bytes(&self) -> Result<Vec<u8>, AudioError> {
+        let spec = WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = WavWriter::new(&mut cursor, spec)?;
+            for &sample in &self.samples {
+                let value = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)).round() as i16;
+                writer.write_sample(value)?;
+            }
+            writer.finalize()?;
+        }
+
+        Ok(cursor.into_inner())
+    }
+
+    /// Number of sample frames (samples per channel).
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// Playback duration.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / f64::from(self.sample_rate.max(1)))
+    }
+
+    /// The audio from `start` to `end`, clamped to the buffer.
+    pub fn slice(&self, start: Duration, end: Duration) -> Self {
+        let channels = self.channels.max(1) as usize;
+        let frame = |at: Duration| {
+            ((at.as_secs_f64() * f64::from(self.sample_rate)).round() as usize).min(self.frames())
+        };
+        let (start, end) = (frame(start), frame(end));
+        Self::new(
+            self.samples[start * channels..end.max(start) * channels].to_vec(),
+            self.sample_rate,
+            self.channels,
+        )
+    }
+
+    /// Convert to another sample rate with linear interpolation.
+    pub fn resample(&self, sample_rate: u32) -> Self {
+        if sample_rate == self.sample_rate || self.samples.is_empty() {
+            return Self::new(self.samples.clone(), sample_rate, self.channels);
+        }
+
+        let channels = self.channels.max(1) as usize;
+        let frames = self.frames();
+        let ratio = f64::from(self.sample_rate) / f64::from(sample_rate);
+        let out_frames = (frames as f64 / ratio).round() as usize;
+
+        let mut samples = Vec::with_capacity(out_frames * channels);
+        for i in 0..out_frames {
+            let position = i as f64 * ratio;
+            let index = (position as usize).min(frames - 1);
+            let next = (index + 1).min(frames - 1);
+            let frac = (position - index as f64) as f32;
+            for c in 0..channels {
+                let a = self.samples[index * channels + c];
+                let b = self.samples[next * channels + c];
+                samples.push(a + (b - a) * frac);
+            }
+        }
+
+        Self::new(samples, sample_rate, self.channels)
+    }
+
+    /// Convert to another channel count. Downmixing to mono averages the
+    /// channels; otherwise source channels are repeated as needed.
+    pub fn remix(&self, channels: u16) -> Self {
+        if channels == self.channels {
+            return self.clone();
+        }
+        if channels == 1 {
+            return Self::new(self.mono(), self.sample_rate, 1);
+        }
+
+        let source = self.channels.max(1) as usize;
+        let samples = self
+            .samples
+            .chunks_exact(source)
+            .flat_map(|frame| (0..channels as usize).map(move |c| frame[c % source]))
+            .collect();
+        Self::new(samples, self.sample_rate, channels)
+    }
+
+    /// Samples mixed down to a single channel.
+    pub fn mono(&self) -> Vec<f32> {
+        let channels = self.channels.max(1) as usize;
+        self.samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    }
+}
+
+/// Number of frames a silence of `duration` occupies at `sample_rate`.
+pub(super) fn silence_frames(duration: Duration, sample_rate: u32) -> usize {
+    (duration.as_secs_f64() * f64::from(sample_rate)).round() as usize
+}
@@ -0,0 +1,65 @@
+//! Reproducible WAV files.
+//!
+//! Backends write WAV files with whatever chunks their audio library adds:
+//! `LIST` software and date entries, `id3 ` tags, `JUNK` padding, or
+//! placeholder sizes from a streaming writer. A canonical file keeps only
+//! the `fmt ` and `data` chunks, in that order and with exact sizes, so
+//! the same samples always give the same bytes.
+
+use super::AudioError;
+
+/// `WAVE_FORMAT_PCM`, whose `fmt ` chunk needs no extension.
+const FORMAT_PCM: u16 = 1;
+
+/// Rewrite `wav` with only its format and sample data.
+///
+/// Samples are copied, not re-encoded, so the audio is unchanged.
+pub fn canonical_wav(wav: &[u8]) -> Result<Vec<u8>, AudioError> {
+    if wav.len() < 12 || &wav[..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return Err(AudioError::Decode("not a RIFF WAVE file".into()));
+    }
+
+    let (mut fmt, mut data) = (None, None);
+    let mut pos = 12;
+    while pos + 8 <= wav.len() {
+        let id = &wav[pos..pos + 4];
+        let size = u32::from_le_bytes(wav[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let start = pos + 8;
+        // Streaming writers leave the data size as a placeholder
+        let end = start.saturating_add(size).min(wav.len());
+        match id {
+            b"fmt " if fmt.is_none() => fmt = Some(&wav[start..end]),
+            b"data" if data.is_none() => data = Some(&wav[start..end]),
+            _ => {}
+        }
+        pos = end + (size & 1);
+    }
+
+    let fmt = fmt.ok_or_else(|| AudioError::Decode("WAV has no fmt chunk".into()))?;
+    let data = data.ok_or_else(|| AudioError::Decode("WAV has no data chunk".into()))?;
+    if fmt.len() < 16 {
+        return Err(AudioError::Decode("WAV fmt chunk is truncated".into()));
+    }
+    // Plain PCM has no extension, so drop an empty cbSize some writers add
+    let fmt = if u16::from_le_bytes([fmt[0], fmt[1]]) == FORMAT_PCM {
+        &fmt[..16]
+    } else {
+        fmt
+    };
+
+    let pad = data.len() & 1;
+    let riff_size = 4 + 8 + fmt.len() + (fmt.len() & 1) + 8 + data.len() + pad;
+    let mut out = Vec::with_capacity(8 + riff_size);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(riff_size as u32).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    for (id, body) in [(b"fmt ", fmt), (b"data", data)] {
+        out.extend_from_slice(id);
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+        if body.len() & 1 == 1 {
+            out.push(0);
+        }
+    }
+    Ok(out)
+}
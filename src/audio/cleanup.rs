@@ -0,0 +1,170 @@
+//! Cleanup filtering for reference clips and generated speech.
+//!
+//! Some backends emit a DC offset or low-frequency rumble that inflates
+//! loudness measurements, and some microphones do the same to reference
+//! recordings. None of it is speech, so it is filtered out; harsh
+//! sibilance can optionally be tamed as well.
+
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::AudioBuffer;
+
+/// Default high-pass cutoff in Hz, below the lowest voices.
+pub const DEFAULT_HIGH_PASS: f32 = 70.0;
+
+/// Lowest frequency of the sibilant band the de-esser watches, in Hz.
+const SIBILANCE: f32 = 5000.0;
+
+/// Level of the sibilant band, relative to the whole signal, above which
+/// the de-esser turns it down.
+const SIBILANCE_LIMIT: f32 = 0.5;
+
+/// Which cleanup filters to apply.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cleanup {
+    /// High-pass cutoff in Hz, typically 60 to 80.
+    pub high_pass: f32,
+    /// Turn down sibilance that stands out from the rest of the speech.
+    pub de_ess: bool,
+}
+
+impl Default for Cleanup {
+    fn default() -> Self {
+        Self {
+            high_pass: DEFAULT_HIGH_PASS,
+            de_ess: false,
+        }
+    }
+}
+
+impl Cleanup {
+    /// Remove DC offset and rumble from every channel, then de-ess if
+    /// enabled. Audio sampled too low to carry sibilance is not de-essed.
+    pub fn apply(&self, buffer: &AudioBuffer) -> AudioBuffer {
+        let channels = buffer.channels.max(1) as usize;
+        let rate = buffer.sample_rate as f32;
+        let mut samples = buffer.samples.clone();
+
+        for channel in 0..channels {
+            let mut signal: Vec<f32> = samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect();
+            if signal.is_empty() {
+                continue;
+            }
+
+            // Removing the mean first keeps the filter from ringing on a
+            // step at the start
+            let mean = signal.iter().map(|&s| f64::from(s)).sum::<f64>() / signal.len() as f64;
+            for sample in &mut signal {
+                *sample -= mean as f32;
+            }
+            if self.high_pass > 0.0 && self.high_pass < rate / 2.0 {
+                Biquad::high_pass(self.high_pass, rate).run(&mut signal);
+            }
+            if self.de_ess && SIBILANCE * 1.2 < rate / 2.0 {
+                de_ess(&mut signal, rate);
+            }
+
+            for (sample, filtered) in samples
+                .iter_mut()
+                .skip(channel)
+                .step_by(channels)
+                .zip(signal)
+            {
+                *sample = filtered.clamp(-1.0, 1.0);
+            }
+        }
+        AudioBuffer::new(samples, buffer.sample_rate, buffer.channels)
+    }
+}
+
+/// Turn the signal down wherever its sibilant band is louder than
+/// [`SIBILANCE_LIMIT`] of the whole, just enough to bring it back to it.
+///
+/// The gain applies to all frequencies: subtracting a filtered band would
+/// leave its phase-shifted residue behind.
+fn de_ess(signal: &mut [f32], rate: f32) {
+    let mut band = signal.to_vec();
+    Biquad::high_pass(SIBILANCE, rate).run(&mut band);
+
+    let mut band_level = Follower::new(rate);
+    let mut level = Follower::new(rate);
+    for (sample, sibilance) in signal.iter_mut().zip(band) {
+        let band_level = band_level.next(sibilance.abs());
+        let limit = SIBILANCE_LIMIT * level.next(sample.abs());
+        if band_level > limit {
+            *sample *= limit / band_level;
+        }
+    }
+}
+
+/// Second-order IIR filter (RBJ cookbook), transposed direct form II.
+struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    state: [f32; 2],
+}
+
+impl Biquad {
+    /// Butterworth high-pass at `cutoff` Hz.
+    fn high_pass(cutoff: f32, rate: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff / rate;
+        let alpha = w0.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+        Self {
+            b: [
+                (1.0 + cos) / 2.0 / a0,
+                -(1.0 + cos) / a0,
+                (1.0 + cos) / 2.0 / a0,
+            ],
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+            state: [0.0; 2],
+        }
+    }
+
+    fn run(&mut self, signal: &mut [f32]) {
+        for sample in signal {
+            let x = *sample;
+            let y = self.b[0] * x + self.state[0];
+            self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+            self.state[1] = self.b[2] * x - self.a[1] * y;
+            *sample = y;
+        }
+    }
+}
+
+/// Envelope follower: fast attack so sibilants are caught at once, slow
+/// release so the gain does not flutter.
+struct Follower {
+    attack: f32,
+    release: f32,
+    level: f32,
+}
+
+impl Follower {
+    fn new(rate: f32) -> Self {
+        let coefficient = |seconds: f32| (-1.0 / (seconds * rate)).exp();
+        Self {
+            attack: coefficient(0.001),
+            release: coefficient(0.05),
+            level: 0.0,
+        }
+    }
+
+    fn next(&mut self, input: f32) -> f32 {
+        let coefficient = if input > self.level {
+            self.attack
+        } else {
+            self.release
+        };
+        self.level = coefficient * self.level + (1.0 - coefficient) * input;
+        self.level
+    }
+}
@@ -0,0 +1,143 @@
+//! Joining audio buffers end to end.
+
+use std::time::Duration;
+
+use super::buffer::silence_frames;
+use super::post::bleep;
+use super::{AudioBuffer, AudioError};
+
+/// A piece of an assembled timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// Decoded audio.
+    Clip(AudioBuffer),
+    /// Silence matching the format of the surrounding clips.
+    Silence(Duration),
+    /// A bleep tone matching the format of the surrounding clips.
+    Bleep(Duration),
+}
+
+impl Segment {
+    /// The segment's audio: a clip as it is, silence or a bleep in the
+    /// given format.
+    pub fn into_buffer(self, sample_rate: u32, channels: u16) -> AudioBuffer {
+        match self {
+            Segment::Clip(clip) => clip,
+            Segment::Silence(duration) => AudioBuffer::silence(duration, sample_rate, channels),
+            Segment::Bleep(duration) => bleep(duration, sample_rate, channels),
+        }
+    }
+
+    /// Length of the segment; silence and bleeps are exact.
+    pub fn duration(&self) -> Duration {
+        match self {
+            Segment::Clip(clip) => clip.duration(),
+            Segment::Silence(duration) | Segment::Bleep(duration) => *duration,
+        }
+    }
+}
+
+/// Concatenate buffers that share the same sample rate and channel count.
+pub fn concat(buffers: &[AudioBuffer]) -> Result<AudioBuffer, AudioError> {
+    let first = buffers.first().ok_or(AudioError::Empty)?;
+
+    let mut samples = Vec::with_capacity(buffers.iter().map(|b| b.samples.len()).sum());
+    for buffer in buffers {
+        if buffer.sample_rate != first.sample_rate || buffer.channels != first.channels {
+            return Err(AudioError::FormatMismatch(format!(
+                "{} Hz/{} ch vs {} Hz/{} ch",
+                buffer.sample_rate, buffer.channels, first.sample_rate, first.channels
+            )));
+        }
+        samples.extend_from_slice(&buffer.samples);
+    }
+
+    Ok(AudioBuffer::new(samples, first.sample_rate, first.channels))
+}
+
+/// Join clips, silences, and bleeps into one buffer.
+///
+/// Silence and bleeps take their sample rate and channel count from the
+/// first clip. At least one clip is required.
+pub fn assemble(segments: Vec<Segment>) -> Result<AudioBuffer, AudioError> {
+    let (sample_rate, channels) = clip_format(&segments).ok_or(AudioError::Empty)?;
+
+    let buffers: Vec<AudioBuffer> = segments
+        .into_iter()
+        .map(|segment| segment.into_buffer(sample_rate, channels))
+        .collect();
+
+    concat(&buffers)
+}
+
+/// Sample rate and channel count of the first clip.
+fn clip_format<'a>(segments: impl IntoIterator<Item = &'a Segment>) -> Option<(u32, u16)> {
+    segments.into_iter().find_map(|s| match s {
+        Segment::Clip(clip) => Some((clip.sample_rate, clip.channels)),
+        _ => None,
+    })
+}
+
+/// Lay clips out on one timeline, one track per label.
+///
+/// Returns a buffer per distinct label, in order of first appearance. Every
+/// track spans the whole timeline and lines up sample for sample with the
+/// [`assemble`] mixdown, holding silence wherever another label plays.
+/// The label of a [`Segment::Silence`] is ignored; a [`Segment::Bleep`]
+/// goes on the track of the clip before it, the voice it masks.
+pub fn assemble_tracks(
+    segments: Vec<(String, Segment)>,
+) -> Result<Vec<(String, AudioBuffer)>, AudioError> {
+    let (sample_rate, channels) =
+        clip_format(segments.iter().map(|(_, s)| s)).ok_or(AudioError::Empty)?;
+
+    // Start frame of each segment
+    let mut placed = Vec::with_capacity(segments.len());
+    let mut cursor = 0;
+    let mut speaking = None;
+    for (label, segment) in segments {
+        let label = match &segment {
+            Segment::Clip(_) => speaking.insert(label).clone(),
+            Segment::Bleep(_) => speaking.clone().unwrap_or(label),
+            Segment::Silence(_) => label,
+        };
+        let frames = match &segment {
+            Segment::Clip(clip) => {
+                if clip.sample_rate != sample_rate || clip.channels != channels {
+                    return Err(AudioError::FormatMismatch(format!(
+                        "{} Hz/{} ch vs {} Hz/{} ch",
+                        clip.sample_rate, clip.channels, sample_rate, channels
+                    )));
+                }
+                clip.frames()
+            }
+            Segment::Silence(duration) | Segment::Bleep(duration) => {
+                silence_frames(*duration, sample_rate)
+            }
+        };
+        placed.push((cursor, label, segment));
+        cursor += frames;
+    }
+
+    let width = channels as usize;
+    let mut tracks: Vec<(String, AudioBuffer)> = Vec::new();
+    for (start, label, segment) in placed {
+        let clip = match segment {
+            Segment::Silence(_) => continue,
+            segment => segment.into_buffer(sample_rate, channels),
+        };
+        let index = match tracks.iter().position(|(name, _)| *name == label) {
+            Some(index) => index,
+            None => {
+                let silence = vec![0.0; cursor * width];
+                tracks.push((label, AudioBuffer::new(silence, sample_rate, channels)));
+                tracks.len() - 1
+            }
+        };
+        let offset = start * width;
+        let len = clip.frames() * width;
+        tracks[index].1.samples[offset..offset + len].copy_from_slice(&clip.samples[..len]);
+    }
+
+    Ok(tracks)
+}
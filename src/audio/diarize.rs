@@ -0,0 +1,287 @@
+//! Telling the speakers of a recording apart.
+//!
+//! The recording is split at pauses into turns, long turns into windows
+//! short enough to hold one speaker, and each window is summarized by its
+//! [`Voiceprint`]. Windows are then clustered by similarity, after the
+//! features are standardized across the recording so that what all the
+//! speakers share (the room, the microphone) does not hide how they
+//! differ. Speakers are numbered from 1 in order of first appearance.
+
+use std::time::Duration;
+
+use super::{AudioBuffer, Voiceprint};
+
+/// Frame length for finding pauses, in seconds.
+const FRAME: f64 = 0.02;
+
+/// Frames quieter than this fraction of the loudest are pauses.
+const SILENCE_RATIO: f32 = 0.05;
+
+/// Pauses shorter than this do not end a turn, in seconds.
+const MIN_PAUSE: f64 = 0.25;
+
+/// Turns shorter than this are too short to tell who is speaking, in
+/// seconds.
+const MIN_TURN: f64 = 0.75;
+
+/// Longest stretch given a single voiceprint, in seconds; longer turns are
+/// split in case the speaker changes without a pause.
+const WINDOW: f64 = 1.5;
+
+/// Clusters whose average standardized similarity is below this are
+/// different speakers.
+const SAME_SPEAKER: f32 = 0.0;
+
+/// Silence between the turns of an isolated speaker.
+const TURN_GAP: Duration = Duration::from_millis(300);
+
+/// A stretch of audio spoken by one speaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Turn {
+    /// Speaker number, from 1.
+    pub speaker: usize,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// Who speaks when in a recording.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diarization {
+    /// Turns in order; pauses and very short utterances are left out.
+    pub turns: Vec<Turn>,
+}
+
+impl Diarization {
+    /// Number of speakers found.
+    pub fn speakers(&self) -> usize {
+        self.turns.iter().map(|t| t.speaker).max().unwrap_or(0)
+    }
+
+    /// Total time `speaker` speaks.
+    pub fn talk_time(&self, speaker: usize) -> Duration {
+        self.turns
+            .iter()
+            .filter(|t| t.speaker == speaker)
+            .map(|t| t.end - t.start)
+            .sum()
+    }
+
+    /// The turns of `speaker` joined with short pauses, or `None` if there
+    /// is no such speaker.
+    pub fn isolate(&self, buffer: &AudioBuffer, speaker: usize) -> Option<AudioBuffer> {
+        let channels = buffer.channels.max(1) as usize;
+        let frame = |at: Duration| {
+            ((at.as_secs_f64() * f64::from(buffer.sample_rate)).round() as usize)
+                .min(buffer.frames())
+        };
+        let gap = AudioBuffer::silence(TURN_GAP, buffer.sample_rate, buffer.channels);
+
+        let mut samples = Vec::new();
+        for turn in self.turns.iter().filter(|t| t.speaker == speaker) {
+            if !samples.is_empty() {
+                samples.extend_from_slice(&gap.samples);
+            }
+            samples.extend_from_slice(
+                &buffer.samples[frame(turn.start) * channels..frame(turn.end) * channels],
+            );
+        }
+        (!samples.is_empty())
+            .then(|| AudioBuffer::new(samples, buffer.sample_rate, buffer.channels))
+    }
+}
+
+/// Find who speaks when in `buffer`. With `speakers` set, exactly that
+/// many are told apart (fewer if there are not enough turns); otherwise
+/// their number is estimated.
+pub fn diarize(buffer: &AudioBuffer, speakers: Option<usize>) -> Diarization {
+    let windows: Vec<(f64, f64)> = voiced_spans(buffer)
+        .into_iter()
+        .flat_map(split_span)
+        .collect();
+    let prints: Vec<(f64, f64, Vec<f32>)> = windows
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let print = Voiceprint::from_buffer(&slice(buffer, start, end)).ok()?;
+            Some((start, end, print.features))
+        })
+        .collect();
+    if prints.is_empty() {
+        return Diarization::default();
+    }
+
+    let features = standardize(prints.iter().map(|(_, _, f)| f.clone()).collect());
+    let labels = cluster(&features, speakers);
+
+    let mut turns: Vec<Turn> = Vec::new();
+    for ((start, end, _), speaker) in prints.iter().zip(labels) {
+        let (start, end) = (
+            Duration::from_secs_f64(*start),
+            Duration::from_secs_f64(*end),
+        );
+        match turns.last_mut() {
+            // Windows split from the same turn join up again
+            Some(last) if last.speaker == speaker && last.end == start => last.end = end,
+            _ => turns.push(Turn {
+                speaker,
+                start,
+                end,
+            }),
+        }
+    }
+    Diarization { turns }
+}
+
+/// Spans of speech, in seconds, separated by pauses.
+fn voiced_spans(buffer: &AudioBuffer) -> Vec<(f64, f64)> {
+    let mono = buffer.mono();
+    let frame = ((FRAME * f64::from(buffer.sample_rate)) as usize).max(1);
+    let levels: Vec<f32> = mono
+        .chunks(frame)
+        .map(|f| (f.iter().map(|s| s * s).sum::<f32>() / f.len() as f32).sqrt())
+        .collect();
+    let loudest = levels.iter().copied().fold(0.0, f32::max);
+    if loudest == 0.0 {
+        return Vec::new();
+    }
+
+    let seconds = |frames: usize| (frames * frame) as f64 / f64::from(buffer.sample_rate);
+    let end_of_audio = buffer.duration().as_secs_f64();
+    let mut spans: Vec<(f64, f64)> = Vec::new();
+    let mut start = None;
+    for (i, level) in levels.iter().chain([&0.0]).enumerate() {
+        match (start, *level >= loudest * SILENCE_RATIO) {
+            (None, true) => start = Some(i),
+            (Some(first), false) => {
+                let span = (seconds(first), seconds(i).min(end_of_audio));
+                match spans.last_mut() {
+                    Some(last) if span.0 - last.1 < MIN_PAUSE => last.1 = span.1,
+                    _ => spans.push(span),
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    spans.retain(|(start, end)| end - start >= MIN_TURN);
+    spans
+}
+
+/// Cut a span into windows of about [`WINDOW`] seconds, none shorter than
+/// half of one.
+fn split_span((start, end): (f64, f64)) -> Vec<(f64, f64)> {
+    let count = ((end - start) / WINDOW).round().max(1.0) as usize;
+    let length = (end - start) / count as f64;
+    (0..count)
+        .map(|i| {
+            // Computed the same way as the next window's start, so the two
+            // meet exactly
+            let at = |i: usize| {
+                if i == count {
+                    end
+                } else {
+                    start + i as f64 * length
+                }
+            };
+            (at(i), at(i + 1))
+        })
+        .collect()
+}
+
+fn slice(buffer: &AudioBuffer, start: f64, end: f64) -> AudioBuffer {
+    let channels = buffer.channels.max(1) as usize;
+    let frame = |at: f64| ((at * f64::from(buffer.sample_rate)) as usize).min(buffer.frames());
+    AudioBuffer::new(
+        buffer.samples[frame(start) * channels..frame(end) * channels].to_vec(),
+        buffer.sample_rate,
+        buffer.channels,
+    )
+}
+
+/// Scale each feature to zero mean and unit spread across the recording.
+fn standardize(mut features: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+    let n = features.len() as f32;
+    for dim in 0..features[0].len() {
+        let mean = features.iter().map(|f| f[dim]).sum::<f32>() / n;
+        let spread = (features
+            .iter()
+            .map(|f| (f[dim] - mean).powi(2))
+            .sum::<f32>()
+            / n)
+            .sqrt();
+        for f in &mut features {
+            f[dim] = if spread > 1e-6 {
+                (f[dim] - mean) / spread
+            } else {
+                0.0
+            };
+        }
+    }
+    features
+}
+
+/// Average-linkage clustering by cosine similarity. Returns a speaker
+/// number for each item, numbered by first appearance.
+fn cluster(features: &[Vec<f32>], speakers: Option<usize>) -> Vec<usize> {
+    let n = features.len();
+    let similarity: Vec<Vec<f32>> = features
+        .iter()
+        .map(|a| features.iter().map(|b| cosine(a, b)).collect())
+        .collect();
+
+    let mut clusters: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    loop {
+        let done = match speakers {
+            Some(speakers) => clusters.len() <= speakers.max(1),
+            None => clusters.len() <= 1,
+        };
+        if done {
+            break;
+        }
+
+        let mut best = (f32::MIN, 0, 0);
+        for a in 0..clusters.len() {
+            for b in a + 1..clusters.len() {
+                let total: f32 = clusters[a]
+                    .iter()
+                    .flat_map(|&i| clusters[b].iter().map(move |&j| (i, j)))
+                    .map(|(i, j)| similarity[i][j])
+                    .sum();
+                let average = total / (clusters[a].len() * clusters[b].len()) as f32;
+                if average > best.0 {
+                    best = (average, a, b);
+                }
+            }
+        }
+        if speakers.is_none() && best.0 < SAME_SPEAKER {
+            break;
+        }
+        let merged = clusters.remove(best.2);
+        clusters[best.1].extend(merged);
+    }
+
+    let mut labels = vec![0; n];
+    for (cluster, members) in clusters.iter().enumerate() {
+        for &i in members {
+            labels[i] = cluster;
+        }
+    }
+    // Renumber by first appearance
+    let mut numbers: Vec<Option<usize>> = vec![None; clusters.len()];
+    let mut next = 0;
+    labels
+        .into_iter()
+        .map(|cluster| {
+            *numbers[cluster].get_or_insert_with(|| {
+                next += 1;
+                next
+            })
+        })
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 { 0.0 } else { dot / denom }
+}
@@ -0,0 +1,99 @@
+//! Comparing two renders of the same audio.
+//!
+//! A diff reports how much longer and louder the second file is, and a
+//! spectral similarity score: the mean cosine similarity of the two mel
+//! spectrograms, frame by frame, after stretching the second to the length
+//! of the first. Identical audio scores 1.0; a changed word or a different
+//! voice pulls the score down, while small timing drift barely does.
+
+use serde::Serialize;
+
+use super::AudioBuffer;
+use super::qa::integrated_loudness;
+use super::visualize::mel_spectrogram;
+
+/// Spectrogram levels this far below the loudest bin of either file count
+/// as silence.
+const DYNAMIC_RANGE_DB: f32 = 80.0;
+
+/// Differences between two audio files.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioDiff {
+    /// Seconds the second file is longer by (negative when shorter).
+    pub duration_delta: f64,
+    /// LU the second file is louder by; `None` when either is too short or
+    /// quiet to measure.
+    pub loudness_delta: Option<f64>,
+    /// Spectral similarity from 0.0 (unrelated) to 1.0 (identical).
+    pub similarity: f64,
+}
+
+impl AudioDiff {
+    /// Compare `b` against `a`.
+    pub fn compare(a: &AudioBuffer, b: &AudioBuffer) -> Self {
+        let duration_delta = b.duration().as_secs_f64() - a.duration().as_secs_f64();
+        let loudness_delta = integrated_loudness(a)
+            .zip(integrated_loudness(b))
+            .map(|(a, b)| b - a);
+
+        Self {
+            duration_delta: round(duration_delta, 3),
+            loudness_delta: loudness_delta.map(|d| round(d, 2)),
+            similarity: round(spectral_similarity(a, b), 4),
+        }
+    }
+
+    /// Returns true if the files are at least `threshold` similar.
+    pub fn passes(&self, threshold: f64) -> bool {
+        self.similarity >= threshold
+    }
+}
+
+fn spectral_similarity(a: &AudioBuffer, b: &AudioBuffer) -> f64 {
+    // Both at one rate, so their mel bands line up
+    let (a, b) = (
+        mel_spectrogram(a),
+        mel_spectrogram(&b.resample(a.sample_rate)),
+    );
+    if a.is_empty() || b.is_empty() {
+        return if a.len() == b.len() { 1.0 } else { 0.0 };
+    }
+
+    let loudest = a
+        .iter()
+        .chain(&b)
+        .flatten()
+        .fold(f32::NEG_INFINITY, |m, &db| m.max(db));
+    let floor = loudest - DYNAMIC_RANGE_DB;
+    let levels = |frame: &[f32]| -> Vec<f64> {
+        frame
+            .iter()
+            .map(|&db| f64::from((db - floor).max(0.0)))
+            .collect()
+    };
+
+    let total: f64 = (0..a.len())
+        .map(|i| {
+            // Frame of `b` at the same fraction of its length
+            let j = i * (b.len() - 1) / (a.len() - 1).max(1);
+            cosine(&levels(&a[i]), &levels(&b[j]))
+        })
+        .sum();
+    total / a.len() as f64
+}
+
+fn cosine(x: &[f64], y: &[f64]) -> f64 {
+    let dot: f64 = x.iter().zip(y).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    match (norm(x), norm(y)) {
+        // Silence in both files matches
+        (0.0, 0.0) => 1.0,
+        (0.0, _) | (_, 0.0) => 0.0,
+        (nx, ny) => dot / (nx * ny),
+    }
+}
+
+fn round(value: f64, places: i32) -> f64 {
+    let scale = 10f64.powi(places);
+    (value * scale).round() / scale
+}
@@ -0,0 +1,216 @@
+//! Mixing a background music or ambience bed under narration.
+
+use std::io::ErrorKind;
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::{AudioBuffer, AudioError};
+
+/// Analysis frame for detecting speech, in seconds.
+const FRAME_SECS: f64 = 0.02;
+
+/// Frames quieter than this fraction of the loudest frame are not speech.
+const SPEECH_RATIO: f32 = 0.05;
+
+/// Time for the bed to duck down once speech starts, in seconds.
+const ATTACK_SECS: f64 = 0.05;
+
+/// Time for the bed to recover once speech stops, in seconds.
+const RELEASE_SECS: f64 = 0.4;
+
+/// A background track mixed under narration, ducked while speech plays.
+#[derive(Debug, Clone)]
+pub struct Bed {
+    audio: AudioBuffer,
+    gain_db: f32,
+    duck_db: f32,
+}
+
+impl Bed {
+    /// Create a bed at -18 dB, ducked a further 12 dB during speech.
+    pub fn new(audio: AudioBuffer) -> Self {
+        Self {
+            audio,
+            gain_db: -18.0,
+            duck_db: -12.0,
+        }
+    }
+
+    /// Decode a bed from an MP3, Ogg Vorbis, FLAC, or WAV file.
+    pub fn load(path: &Path) -> Result<Self, AudioError> {
+        Ok(Self::new(decode_file(path)?))
+    }
+
+    /// Set the bed level relative to its original loudness.
+    pub fn with_gain(mut self, gain_db: f32) -> Self {
+        self.gain_db = gain_db;
+        self
+    }
+
+    /// Set the extra attenuation applied while speech plays.
+    pub fn with_ducking(mut self, duck_db: f32) -> Self {
+        self.duck_db = duck_db;
+        self
+    }
+
+    /// Mix the bed under `narration`, looping it to cover the full length.
+    ///
+    /// The result has the narration's format and length; peaks above full
+    /// scale are clipped.
+    pub fn mix_under(&self, narration: &AudioBuffer) -> AudioBuffer {
+        let bed = self
+            .audio
+            .resample(narration.sample_rate)
+            .remix(narration.channels);
+        if bed.samples.is_empty() {
+            return narration.clone();
+        }
+
+        let channels = narration.channels.max(1) as usize;
+        let gain = db_to_linear(self.gain_db);
+        let envelope = self.ducking_envelope(narration);
+
+        let samples = narration
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(i, &speech)| {
+                let background = bed.samples[i % bed.samples.len()];
+                (speech + background * gain * envelope[i / channels]).clamp(-1.0, 1.0)
+            })
+            .collect();
+
+        AudioBuffer::new(samples, narration.sample_rate, narration.channels)
+    }
+
+    /// Per-frame bed gain: 1.0 in pauses, the ducking level during speech,
+    /// with smoothed transitions that start before speech begins.
+    fn ducking_envelope(&self, narration: &AudioBuffer) -> Vec<f32> {
+        let rate = f64::from(narration.sample_rate.max(1));
+        let mono = narration.mono();
+        let frame_len = ((rate * FRAME_SECS) as usize).max(1);
+
+        let energy: Vec<f32> = mono
+            .chunks(frame_len)
+            .map(|f| (f.iter().map(|s| s * s).sum::<f32>() / f.len() as f32).sqrt())
+            .collect();
+        let threshold = energy.iter().copied().fold(0.0, f32::max) * SPEECH_RATIO;
+
+        // Look ahead by the attack time so the bed is down when speech starts
+        let lookahead = (ATTACK_SECS / FRAME_SECS).ceil() as usize;
+        let speaking: Vec<bool> = (0..energy.len())
+            .map(|i| {
+                energy[i..(i + lookahead + 1).min(energy.len())]
+                    .iter()
+                    .any(|&e| threshold > 0.0 && e > threshold)
+            })
+            .collect();
+
+        let ducked = db_to_linear(self.duck_db);
+        let attack = 1.0 - (-1.0 / (ATTACK_SECS * rate)).exp() as f32;
+        let release = 1.0 - (-1.0 / (RELEASE_SECS * rate)).exp() as f32;
+        let mut level = 1.0f32;
+
+        (0..mono.len())
+            .map(|i| {
+                let target = if speaking[i / frame_len] { ducked } else { 1.0 };
+                let rate = if target < level { attack } else { release };
+                level += (target - level) * rate;
+                level
+            })
+            .collect()
+    }
+}
+
+/// Convert decibels to a linear amplitude factor.
+pub fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Parse a gain such as `-18dB`, `-18 dB`, or `-18`.
+pub fn parse_db(input: &str) -> Result<f32, String> {
+    let trimmed = input.trim();
+    let number = trimmed
+        .strip_suffix("dB")
+        .or_else(|| trimmed.strip_suffix("db"))
+        .unwrap_or(trimmed)
+        .trim();
+
+    number
+        .parse::<f32>()
+        .ok()
+        .filter(|db| db.is_finite())
+        .ok_or_else(|| format!("invalid gain '{input}' (expected e.g. -18dB)"))
+}
+
+/// Decode an audio file of any supported container and codec.
+pub fn decode_file(path: &Path) -> Result<AudioBuffer, AudioError> {
+    let file = std::fs::File::open(path)?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(decode_error)?;
+    let mut format = probed.format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| AudioError::Decode("no audio track".to_string()))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(decode_error)?;
+
+    let mut samples = Vec::new();
+    let mut spec = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(decode_error(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let decoded_spec = *decoded.spec();
+                let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, decoded_spec);
+                buffer.copy_interleaved_ref(decoded);
+                samples.extend_from_slice(buffer.samples());
+                spec = Some(decoded_spec);
+            }
+            // A corrupt frame is skipped rather than failing the whole file
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(decode_error(e)),
+        }
+    }
+
+    let spec = spec.ok_or(AudioError::Empty)?;
+    Ok(AudioBuffer::new(
+        samples,
+        spec.rate,
+        spec.channels.count() as u16,
+    ))
+}
+
+fn decode_error(e: SymphoniaError) -> AudioError {
+    AudioError::Decode(e.to_string())
+}
@@ -0,0 +1,217 @@
+//! Playing finished audio through the system's command-line player, and
+//! recording reference clips with its recorder.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use super::AudioError;
+
+/// Players tried in order, with the arguments placed before the file path.
+#[cfg(target_os = "macos")]
+const PLAYERS: &[(&str, &[&str])] = &[("afplay", &[])];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const PLAYERS: &[(&str, &[&str])] = &[
+    ("pw-play", &[]),
+    ("paplay", &[]),
+    ("aplay", &["-q"]),
+    ("ffplay", &["-nodisp", "-autoexit", "-loglevel", "quiet"]),
+];
+
+/// Play a WAV file and wait for playback to finish.
+#[cfg(not(windows))]
+pub fn play_file(path: &Path) -> Result<(), AudioError> {
+    for (program, args) in PLAYERS {
+        let status = Command::new(program)
+            .args(*args)
+            .arg(path)
+            .stdin(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => {
+                return Err(AudioError::Playback(format!(
+                    "{program} exited with {status}"
+                )));
+            }
+            // Not installed; try the next player
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let names: Vec<&str> = PLAYERS.iter().map(|(program, _)| *program).collect();
+    Err(AudioError::Playback(format!(
+        "no audio player found (tried {})",
+        names.join(", ")
+    )))
+}
+
+/// Play a WAV file and wait for playback to finish.
+#[cfg(windows)]
+pub fn play_file(path: &Path) -> Result<(), AudioError> {
+    let script = format!(
+        "(New-Object Media.SoundPlayer '{}').PlaySync()",
+        crate::paths::long_path(path)
+            .display()
+            .to_string()
+            .replace('\'', "''")
+    );
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .stdin(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(AudioError::Playback(format!(
+            "powershell exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+/// Record `duration` of mono 24 kHz WAV from the default input device.
+///
+/// Uses ALSA's `arecord` or SoX's `rec`, whichever is installed.
+#[cfg(not(windows))]
+pub fn record_file(path: &Path, duration: Duration) -> Result<(), AudioError> {
+    let seconds = duration.as_secs().max(1).to_string();
+    let recorders: [(&str, Vec<&str>); 2] = [
+        (
+            "arecord",
+            vec![
+                "-q", "-f", "S16_LE", "-r", "24000", "-c", "1", "-d", &seconds,
+            ],
+        ),
+        ("rec", vec!["-q", "-r", "24000", "-c", "1", "-b", "16"]),
+    ];
+    for (program, args) in &recorders {
+        let mut command = Command::new(program);
+        command.args(args).arg(path).stdin(Stdio::null());
+        if *program == "rec" {
+            // SoX takes the duration as an effect after the file
+            command.args(["trim", "0", &seconds]);
+        }
+        match command.status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => {
+                return Err(AudioError::Recording(format!(
+                    "{program} exited with {status}"
+                )));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(AudioError::Recording(
+        "no audio recorder found (tried arecord, rec)".to_string(),
+    ))
+}
+
+/// Recording needs `arecord` or SoX, which Windows does not ship.
+#[cfg(windows)]
+pub fn record_file(_path: &Path, _duration: Duration) -> Result<(), AudioError> {
+    Err(AudioError::Recording(
+        "recording is not supported on Windows; record a WAV clip and pass its path".to_string(),
+    ))
+}
+
+/// Null sink the virtual microphone is fed from.
+pub const VIRTUAL_MIC_SINK: &str = "open_tts_mic";
+
+/// Source other applications pick as a microphone.
+pub const VIRTUAL_MIC_SOURCE: &str = "open_tts_mic_source";
+
+/// Play a WAV file into the virtual microphone, creating it when missing.
+///
+/// The microphone is a PulseAudio (or PipeWire, via `pipewire-pulse`) null
+/// sink with its monitor remapped as a source, so calls and OBS can select
+/// it like any other input. It lasts until the sound server restarts.
+#[cfg(target_os = "linux")]
+pub fn play_to_virtual_mic(path: &Path) -> Result<(), AudioError> {
+    ensure_virtual_mic()?;
+
+    let target = format!("--target={VIRTUAL_MIC_SINK}");
+    let device = format!("--device={VIRTUAL_MIC_SINK}");
+    for (program, arg) in [("pw-play", &target), ("paplay", &device)] {
+        let status = Command::new(program)
+            .arg(arg)
+            .arg(path)
+            .stdin(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => {
+                return Err(AudioError::Playback(format!(
+                    "{program} exited with {status}"
+                )));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(AudioError::Playback(
+        "no audio player found (tried pw-play, paplay)".to_string(),
+    ))
+}
+
+/// Virtual microphones need PulseAudio or PipeWire.
+#[cfg(not(target_os = "linux"))]
+pub fn play_to_virtual_mic(_path: &Path) -> Result<(), AudioError> {
+    Err(AudioError::Playback(
+        "the virtual microphone needs PulseAudio or PipeWire on Linux".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn ensure_virtual_mic() -> Result<(), AudioError> {
+    if !lists_device(&pactl(&["list", "short", "sinks"])?, VIRTUAL_MIC_SINK) {
+        pactl(&[
+            "load-module",
+            "module-null-sink",
+            &format!("sink_name={VIRTUAL_MIC_SINK}"),
+            "sink_properties=device.description=open-tts-rs-output",
+        ])?;
+    }
+    if !lists_device(&pactl(&["list", "short", "sources"])?, VIRTUAL_MIC_SOURCE) {
+        pactl(&[
+            "load-module",
+            "module-remap-source",
+            &format!("master={VIRTUAL_MIC_SINK}.monitor"),
+            &format!("source_name={VIRTUAL_MIC_SOURCE}"),
+            "source_properties=device.description=open-tts-rs-microphone",
+        ])?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn pactl(args: &[&str]) -> Result<String, AudioError> {
+    let output = Command::new("pactl")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AudioError::Playback(
+                "pactl not found; the virtual microphone needs PulseAudio or pipewire-pulse"
+                    .to_string(),
+            ),
+            _ => e.into(),
+        })?;
+    if !output.status.success() {
+        return Err(AudioError::Playback(format!(
+            "pactl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether `pactl list short` output has a device called `name`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(super) fn lists_device(listing: &str, name: &str) -> bool {
+    listing
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(name))
+}
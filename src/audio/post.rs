@@ -0,0 +1,213 @@
+//! Post-processing applied to finished audio.
+
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use super::{AudioBuffer, db_to_linear};
+
+/// Detection scores at or above this are reported as watermarked.
+///
+/// Unmarked audio scores as a standard normal variable, so a false
+/// positive at this threshold is roughly a one-in-a-million event.
+pub const WATERMARK_THRESHOLD: f32 = 5.0;
+
+/// Speech level [`Watermark::min_samples`] is worked out for, about -20 dBFS RMS.
+const NOMINAL_RMS: f32 = 0.1;
+
+/// Expected score of a clip of [`Watermark::min_samples`] at the nominal
+/// level, leaving a margin over the threshold.
+const MIN_EXPECTED_SCORE: f32 = 6.0;
+
+/// Keyed spread-spectrum watermark.
+///
+/// A pseudo-random +/-1 sequence derived from the key is added to the
+/// signal at low amplitude. Detection correlates the audio with the same
+/// sequence; without the key the mark is indistinguishable from noise.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    seed: u64,
+    strength: f32,
+}
+
+impl Watermark {
+    /// Create a watermark for a key with the default strength (about -54 dBFS).
+    pub fn new(key: &str) -> Self {
+        let digest = Sha256::digest(key.as_bytes());
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&digest[..8]);
+
+        Self {
+            seed: u64::from_le_bytes(seed),
+            strength: 0.002,
+        }
+    }
+
+    /// Set the amplitude of the mark (linear, full scale = 1.0).
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// Add the watermark to every channel of a buffer.
+    pub fn embed(&self, buffer: &mut AudioBuffer) {
+        let channels = buffer.channels.max(1) as usize;
+        let mut chips = Chips::new(self.seed);
+
+        for frame in buffer.samples.chunks_mut(channels) {
+            let chip = chips.next_chip() * self.strength;
+            for sample in frame {
+                *sample = (*sample + chip).clamp(-1.0, 1.0);
+            }
+        }
+    }
+
+    /// Correlation score of a buffer with this key's sequence.
+    ///
+    /// Roughly zero-mean with unit variance for unmarked audio; see
+    /// [`WATERMARK_THRESHOLD`].
+    pub fn score(&self, buffer: &AudioBuffer) -> f32 {
+        let samples = buffer.mono();
+        if samples.is_empty() {
+            return 0.0;
+        }
+
+        let mut chips = Chips::new(self.seed);
+        let correlation: f64 = samples
+            .iter()
+            .map(|&s| f64::from(s * chips.next_chip()))
+            .sum();
+        let energy: f64 = samples.iter().map(|&s| f64::from(s) * f64::from(s)).sum();
+        if energy == 0.0 {
+            return 0.0;
+        }
+
+        (correlation / energy.sqrt()) as f32
+    }
+
+    /// Fewest samples that reliably carry the mark.
+    ///
+    /// The score of marked audio grows with the square root of its length
+    /// and falls as its level rises. At the default strength, speech at
+    /// -20 dBFS RMS needs about 2 s at 44.1 kHz, 4 s at 24 kHz, or 11 s at
+    /// 8 kHz; louder speech needs longer.
+    pub fn min_samples(&self) -> usize {
+        let root = MIN_EXPECTED_SCORE * NOMINAL_RMS / self.strength;
+        (root * root).ceil() as usize
+    }
+
+    /// [`Watermark::min_samples`] as a duration at `sample_rate`.
+    pub fn min_duration(&self, sample_rate: u32) -> Duration {
+        Duration::from_secs_f64(self.min_samples() as f64 / f64::from(sample_rate.max(1)))
+    }
+
+    /// Returns true if the buffer carries this key's watermark.
+    pub fn detect(&self, buffer: &AudioBuffer) -> bool {
+        self.score(buffer) >= WATERMARK_THRESHOLD
+    }
+}
+
+/// Frequency of a bleep, the broadcast censor tone.
+const BLEEP_HZ: f32 = 1000.0;
+
+/// Peak level of a bleep, about -12 dBFS.
+const BLEEP_LEVEL: f32 = 0.25;
+
+/// Fade at either end of a bleep, so it starts and stops without a click.
+const BLEEP_FADE: Duration = Duration::from_millis(5);
+
+/// A tone of `duration` masking a word, in the format of the audio around
+/// it.
+pub fn bleep(duration: Duration, sample_rate: u32, channels: u16) -> AudioBuffer {
+    let mut buffer = AudioBuffer::silence(duration, sample_rate, channels);
+    let step = std::f32::consts::TAU * BLEEP_HZ / sample_rate.max(1) as f32;
+    for (i, frame) in buffer
+        .samples
+        .chunks_mut(channels.max(1) as usize)
+        .enumerate()
+    {
+        frame.fill((step * i as f32).sin() * BLEEP_LEVEL);
+    }
+    fade_in(&mut buffer, BLEEP_FADE);
+    fade_out(&mut buffer, BLEEP_FADE);
+    buffer
+}
+
+/// Gain and fades, the finishing touches on a file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Envelope {
+    pub fade_in: Duration,
+    pub fade_out: Duration,
+    /// Gain in dB; peaks pushed past full scale are clipped.
+    pub gain_db: f32,
+}
+
+impl Envelope {
+    /// Returns true if applying the envelope would change nothing.
+    pub fn is_flat(&self) -> bool {
+        self.fade_in.is_zero() && self.fade_out.is_zero() && self.gain_db == 0.0
+    }
+
+    pub fn apply(&self, buffer: &mut AudioBuffer) {
+        if self.gain_db != 0.0 {
+            let gain = db_to_linear(self.gain_db);
+            for sample in &mut buffer.samples {
+                *sample = (*sample * gain).clamp(-1.0, 1.0);
+            }
+        }
+        fade_in(buffer, self.fade_in);
+        fade_out(buffer, self.fade_out);
+    }
+}
+
+/// Ramp the start of a buffer up from silence over `duration`.
+pub fn fade_in(buffer: &mut AudioBuffer, duration: Duration) {
+    let (channels, fade) = fade_frames(buffer, duration);
+    for (i, frame) in buffer.samples.chunks_mut(channels).take(fade).enumerate() {
+        let gain = i as f32 / fade as f32;
+        for sample in frame {
+            *sample *= gain;
+        }
+    }
+}
+
+/// Ramp the end of a buffer down to silence over `duration`.
+pub fn fade_out(buffer: &mut AudioBuffer, duration: Duration) {
+    let (channels, fade) = fade_frames(buffer, duration);
+    let start = buffer.frames() - fade;
+    for (i, frame) in buffer.samples[start * channels..]
+        .chunks_mut(channels)
+        .enumerate()
+    {
+        let gain = 1.0 - (i + 1) as f32 / fade as f32;
+        for sample in frame {
+            *sample *= gain;
+        }
+    }
+}
+
+/// Channel count and length of a fade in frames, at most the whole buffer.
+fn fade_frames(buffer: &AudioBuffer, duration: Duration) -> (usize, usize) {
+    let frames = (duration.as_secs_f64() * f64::from(buffer.sample_rate)).round() as usize;
+    (buffer.channels.max(1) as usize, frames.min(buffer.frames()))
+}
+
+/// SplitMix64 stream of +/-1 chips.
+struct Chips {
+    state: u64,
+}
+
+impl Chips {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_chip(&mut self) -> f32 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        if z & 1 == 0 { 1.0 } else { -1.0 }
+    }
+}
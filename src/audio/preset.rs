@@ -0,0 +1,289 @@
+//! Named output settings: format, sample rate, and loudness together.
+//!
+//! `telephony` and `podcast` are built in; the `[preset.<name>]` config
+//! sections add more or replace them. A preset is applied in two steps:
+//! [`Preset::conform`] adjusts the audio, which stays PCM for playback, and
+//! [`Preset::encode`] produces the bytes of the output file.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use super::mix::db_to_linear;
+use super::qa::integrated_loudness;
+use super::raw::mulaw;
+use super::{AudioBuffer, AudioError};
+
+/// Names of the built-in presets.
+pub const BUILTIN_PRESETS: &[&str] = &["telephony", "podcast"];
+
+/// Highest sample peak after loudness normalization (-1 dBFS).
+const PEAK_CEILING: f32 = 0.891;
+
+/// Codec of the output file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// 16-bit PCM WAV.
+    #[default]
+    Pcm,
+    /// 8-bit G.711 μ-law WAV, as IVR and SIP systems expect.
+    Mulaw,
+    /// MP3, encoded by `lame` or `ffmpeg`.
+    Mp3,
+}
+
+/// Output settings bundled under a name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub encoding: Encoding,
+    /// Sample rate in Hz [default: the backend's].
+    pub sample_rate: Option<u32>,
+    /// Channel count [default: the backend's].
+    pub channels: Option<u16>,
+    /// Integrated loudness to normalize to, in LUFS.
+    pub loudness: Option<f64>,
+    /// MP3 bitrate in kbit/s [default: 128].
+    pub bitrate: Option<u32>,
+}
+
+impl Preset {
+    /// A built-in preset by name.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "telephony" => Some(Self {
+                encoding: Encoding::Mulaw,
+                sample_rate: Some(8000),
+                channels: Some(1),
+                ..Self::default()
+            }),
+            "podcast" => Some(Self {
+                encoding: Encoding::Mp3,
+                sample_rate: Some(44100),
+                loudness: Some(-16.0),
+                bitrate: Some(128),
+                ..Self::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// File extension of the encoded output.
+    pub fn extension(&self) -> &'static str {
+        match self.encoding {
+            Encoding::Pcm | Encoding::Mulaw => "wav",
+            Encoding::Mp3 => "mp3",
+        }
+    }
+
+    /// Returns true if the encoding keeps a [`Watermark`](super::Watermark)
+    /// readable. MP3 discards low-level noise the ear would not notice,
+    /// which is what the mark is made of; μ-law keeps it.
+    pub fn keeps_watermark(&self) -> bool {
+        self.encoding != Encoding::Mp3
+    }
+
+    /// Convert to the preset's channels and sample rate, then normalize
+    /// loudness. Normalization never raises peaks above -1 dBFS, so very
+    /// dynamic audio may end up quieter than the target.
+    pub fn conform(&self, buffer: &AudioBuffer) -> AudioBuffer {
+        let mut buffer = match self.channels {
+            Some(channels) => buffer.remix(channels),
+            None => buffer.clone(),
+        };
+        if let Some(rate) = self.sample_rate
+            && rate != buffer.sample_rate
+        {
+            if rate < buffer.sample_rate {
+                // Keep what the new rate cannot hold from folding back as noise
+                low_pass(&mut buffer, 0.45 * rate as f32);
+            }
+            buffer = buffer.resample(rate);
+        }
+
+        if let Some(target) = self.loudness
+            && let Some(measured) = integrated_loudness(&buffer)
+        {
+            let peak = buffer
+                .samples
+                .iter()
+                .fold(0.0f32, |peak, s| peak.max(s.abs()));
+            let mut gain = db_to_linear((target - measured) as f32);
+            if peak * gain > PEAK_CEILING {
+                gain = PEAK_CEILING / peak;
+            }
+            for sample in &mut buffer.samples {
+                *sample *= gain;
+            }
+        }
+        buffer
+    }
+
+    /// Bytes per second of the encoded output, for audio that arrives at
+    /// `sample_rate` with `channels` unless the preset sets its own.
+    pub fn bytes_per_second(&self, sample_rate: u32, channels: u16) -> f64 {
+        let samples = f64::from(self.sample_rate.unwrap_or(sample_rate))
+            * f64::from(self.channels.unwrap_or(channels));
+        match self.encoding {
+            Encoding::Pcm => samples * 2.0,
+            Encoding::Mulaw => samples,
+            Encoding::Mp3 => f64::from(self.bitrate.unwrap_or(128)) * 1000.0 / 8.0,
+        }
+    }
+
+    /// Encode `buffer` as the preset's output file.
+    pub fn encode(&self, buffer: &AudioBuffer) -> Result<Vec<u8>, AudioError> {
+        match self.encoding {
+            Encoding::Pcm => buffer.to_wav_bytes(),
+            Encoding::Mulaw => Ok(mulaw_wav(buffer)),
+            Encoding::Mp3 => encode_mp3(buffer, self.bitrate.unwrap_or(128)),
+        }
+    }
+}
+
+/// Windowed-sinc low-pass filter, applied to each channel in place.
+fn low_pass(buffer: &mut AudioBuffer, cutoff: f32) {
+    const TAPS: usize = 63;
+    let channels = buffer.channels.max(1) as usize;
+    let fc = cutoff / buffer.sample_rate as f32;
+    let center = (TAPS / 2) as f32;
+    let mut kernel: Vec<f32> = (0..TAPS)
+        .map(|i| {
+            let x = i as f32 - center;
+            let sinc = if x == 0.0 {
+                2.0 * fc
+            } else {
+                (2.0 * std::f32::consts::PI * fc * x).sin() / (std::f32::consts::PI * x)
+            };
+            let window =
+                0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (TAPS - 1) as f32).cos();
+            sinc * window
+        })
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|k| *k /= sum);
+
+    let frames = buffer.frames();
+    let source = buffer.samples.clone();
+    for frame in 0..frames {
+        for c in 0..channels {
+            let mut acc = 0.0;
+            for (k, weight) in kernel.iter().enumerate() {
+                let Some(at) = (frame + k).checked_sub(TAPS / 2) else {
+                    continue;
+                };
+                if at < frames {
+                    acc += source[at * channels + c] * weight;
+                }
+            }
+            buffer.samples[frame * channels + c] = acc;
+        }
+    }
+}
+
+/// A WAV file of 8-bit G.711 μ-law samples.
+fn mulaw_wav(buffer: &AudioBuffer) -> Vec<u8> {
+    let channels = buffer.channels.max(1);
+    let data: Vec<u8> = buffer.samples.iter().map(|&s| mulaw(s)).collect();
+    let padding = data.len() % 2;
+
+    let mut wav = Vec::with_capacity(58 + data.len() + padding);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&((50 + data.len() + padding) as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&18u32.to_le_bytes());
+    wav.extend_from_slice(&7u16.to_le_bytes()); // WAVE_FORMAT_MULAW
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&buffer.sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(buffer.sample_rate * u32::from(channels)).to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes()); // block align
+    wav.extend_from_slice(&8u16.to_le_bytes());
+    wav.extend_from_slice(&0u16.to_le_bytes());
+    // Formats other than PCM must give their length in frames
+    wav.extend_from_slice(b"fact");
+    wav.extend_from_slice(&4u32.to_le_bytes());
+    wav.extend_from_slice(&(buffer.frames() as u32).to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&data);
+    wav.resize(wav.len() + padding, 0);
+    wav
+}
+
+/// Encoders tried in order, with their arguments for WAV on standard input
+/// and MP3 on standard output.
+fn mp3_encoders(bitrate: u32) -> [(&'static str, Vec<String>); 2] {
+    [
+        (
+            "lame",
+            vec![
+                "--quiet".into(),
+                "-b".into(),
+                bitrate.to_string(),
+                "-".into(),
+                "-".into(),
+            ],
+        ),
+        (
+            "ffmpeg",
+            vec![
+                "-loglevel".into(),
+                "error".into(),
+                "-f".into(),
+                "wav".into(),
+                "-i".into(),
+                "pipe:0".into(),
+                "-b:a".into(),
+                format!("{bitrate}k"),
+                "-f".into(),
+                "mp3".into(),
+                "pipe:1".into(),
+            ],
+        ),
+    ]
+}
+
+fn encode_mp3(buffer: &AudioBuffer, bitrate: u32) -> Result<Vec<u8>, AudioError> {
+    let wav = buffer.to_wav_bytes()?;
+    for (program, args) in mp3_encoders(bitrate) {
+        let child = Command::new(program)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            // Not installed; try the next encoder
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        // Feed the encoder from another thread so neither pipe fills up
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = wav.clone();
+        let writer = std::thread::spawn(move || stdin.write_all(&input));
+        let mut mp3 = Vec::new();
+        child
+            .stdout
+            .take()
+            .expect("stdout is piped")
+            .read_to_end(&mut mp3)?;
+        let status = child.wait()?;
+        writer
+            .join()
+            .map_err(|_| AudioError::Encode(format!("{program} input thread panicked")))??;
+        if !status.success() || mp3.is_empty() {
+            return Err(AudioError::Encode(format!(
+                "{program} exited with {status}"
+            )));
+        }
+        return Ok(mp3);
+    }
+    Err(AudioError::Encode(
+        "no MP3 encoder found (tried lame, ffmpeg)".to_string(),
+    ))
+}
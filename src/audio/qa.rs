@@ -0,0 +1,388 @@
+//! Technical quality checks on finished audio.
+//!
+//! Loudness follows ITU-R BS.1770 (K-weighting, 400 ms gated blocks) and
+//! true peak is estimated with 4x oversampling.
+
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::AudioBuffer;
+
+/// Levels are floored here instead of reporting -inf for digital silence.
+const FLOOR_DB: f64 = -120.0;
+
+/// Samples at or above this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 0.999;
+
+/// Frame length for silence detection, in seconds.
+const SILENCE_FRAME: f64 = 0.01;
+
+/// Half-width of the interpolation kernel used for true peak.
+const TRUE_PEAK_TAPS: isize = 8;
+
+/// Limits a file must meet to pass QA.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QaThresholds {
+    /// Highest allowed true peak, in dBTP.
+    pub max_true_peak_db: f64,
+    /// Quietest allowed integrated loudness, in LUFS.
+    pub min_lufs: Option<f64>,
+    /// Loudest allowed integrated loudness, in LUFS.
+    pub max_lufs: Option<f64>,
+    /// Longest allowed silence, in seconds.
+    pub max_silence: f64,
+    /// Level below which audio counts as silence, in dBFS.
+    pub silence_db: f64,
+    /// Most clipped samples allowed.
+    pub max_clipped: usize,
+    /// Largest allowed DC offset (linear, full scale = 1.0).
+    pub max_dc_offset: f64,
+}
+
+impl Default for QaThresholds {
+    fn default() -> Self {
+        Self {
+            max_true_peak_db: -1.0,
+            min_lufs: None,
+            max_lufs: None,
+            max_silence: 3.0,
+            silence_db: -60.0,
+            max_clipped: 0,
+            max_dc_offset: 0.01,
+        }
+    }
+}
+
+/// Measurements of one audio file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QaMetrics {
+    /// Length in seconds.
+    pub duration: f64,
+    /// Sample peak, in dBFS.
+    pub peak_db: f64,
+    /// Inter-sample peak, in dBTP.
+    pub true_peak_db: f64,
+    /// Integrated loudness; `None` for audio shorter than one 400 ms block
+    /// or quieter than the -70 LUFS gate.
+    pub lufs: Option<f64>,
+    /// Silences longer than the threshold, as `[start, end]` seconds.
+    pub long_silences: Vec<[f64; 2]>,
+    pub clipped_samples: usize,
+    pub dc_offset: f64,
+}
+
+impl QaMetrics {
+    /// Measure a buffer. Silence detection uses the thresholds' level and
+    /// minimum length.
+    pub fn measure(buffer: &AudioBuffer, thresholds: &QaThresholds) -> Self {
+        let peak = buffer.samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        let dc_offset = if buffer.samples.is_empty() {
+            0.0
+        } else {
+            buffer.samples.iter().map(|&s| f64::from(s)).sum::<f64>() / buffer.samples.len() as f64
+        };
+
+        Self {
+            duration: round(buffer.duration().as_secs_f64(), 3),
+            peak_db: round(to_db(f64::from(peak)), 2),
+            true_peak_db: round(to_db(true_peak(buffer)), 2),
+            lufs: integrated_loudness(buffer).map(|l| round(l, 2)),
+            long_silences: long_silences(buffer, thresholds.silence_db, thresholds.max_silence),
+            clipped_samples: buffer
+                .samples
+                .iter()
+                .filter(|s| s.abs() >= CLIP_LEVEL)
+                .count(),
+            dc_offset: round(dc_offset, 5),
+        }
+    }
+
+    /// Describe every threshold the measurements violate.
+    pub fn failures(&self, thresholds: &QaThresholds) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        if self.true_peak_db > thresholds.max_true_peak_db {
+            failures.push(format!(
+                "true peak {:.1} dBTP above {:.1}",
+                self.true_peak_db, thresholds.max_true_peak_db
+            ));
+        }
+        if let (Some(min), Some(lufs)) = (thresholds.min_lufs, self.lufs)
+            && lufs < min
+        {
+            failures.push(format!("loudness {lufs:.1} LUFS below {min:.1}"));
+        }
+        if let (Some(max), Some(lufs)) = (thresholds.max_lufs, self.lufs)
+            && lufs > max
+        {
+            failures.push(format!("loudness {lufs:.1} LUFS above {max:.1}"));
+        }
+        for [start, end] in &self.long_silences {
+            failures.push(format!("silence from {start:.2}s to {end:.2}s"));
+        }
+        if self.clipped_samples > thresholds.max_clipped {
+            failures.push(format!("{} clipped samples", self.clipped_samples));
+        }
+        if self.dc_offset.abs() > thresholds.max_dc_offset {
+            failures.push(format!("DC offset {:.4}", self.dc_offset));
+        }
+
+        failures
+    }
+}
+
+/// QA result for one file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QaReport {
+    pub file: PathBuf,
+    #[serde(flatten)]
+    pub metrics: QaMetrics,
+    /// Empty when the file passed.
+    pub failures: Vec<String>,
+}
+
+impl QaReport {
+    /// Measure a decoded file and check it against the thresholds.
+    pub fn new(file: &Path, buffer: &AudioBuffer, thresholds: &QaThresholds) -> Self {
+        let metrics = QaMetrics::measure(buffer, thresholds);
+        let failures = metrics.failures(thresholds);
+
+        Self {
+            file: file.to_path_buf(),
+            metrics,
+            failures,
+        }
+    }
+
+    /// Returns true if no threshold was violated.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Indices of `reports`, best first: fewest threshold failures, then
+/// fewest clipped samples, least long silence, and lowest true peak.
+pub fn rank_reports(reports: &[QaReport]) -> Vec<usize> {
+    let silence = |r: &QaReport| -> f64 {
+        r.metrics
+            .long_silences
+            .iter()
+            .map(|[start, end]| end - start)
+            .sum()
+    };
+    let mut order: Vec<usize> = (0..reports.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&reports[a], &reports[b]);
+        a.failures
+            .len()
+            .cmp(&b.failures.len())
+            .then(a.metrics.clipped_samples.cmp(&b.metrics.clipped_samples))
+            .then(silence(a).total_cmp(&silence(b)))
+            .then(a.metrics.true_peak_db.total_cmp(&b.metrics.true_peak_db))
+    });
+    order
+}
+
+fn to_db(level: f64) -> f64 {
+    if level <= 0.0 {
+        FLOOR_DB
+    } else {
+        (20.0 * level.log10()).max(FLOOR_DB)
+    }
+}
+
+fn round(value: f64, places: i32) -> f64 {
+    let scale = 10f64.powi(places);
+    (value * scale).round() / scale
+}
+
+/// Split interleaved samples into per-channel signals.
+fn channels(buffer: &AudioBuffer) -> Vec<Vec<f64>> {
+    let n = buffer.channels.max(1) as usize;
+    (0..n)
+        .map(|c| {
+            buffer
+                .samples
+                .iter()
+                .skip(c)
+                .step_by(n)
+                .map(|&s| f64::from(s))
+                .collect()
+        })
+        .collect()
+}
+
+/// Highest magnitude of the signal reconstructed at 4x the sample rate.
+fn true_peak(buffer: &AudioBuffer) -> f64 {
+    // Hann-windowed sinc kernels for the three in-between positions
+    let kernels: Vec<Vec<f64>> = [0.25, 0.5, 0.75]
+        .iter()
+        .map(|&frac| {
+            (-TRUE_PEAK_TAPS + 1..=TRUE_PEAK_TAPS)
+                .map(|k| {
+                    let x = frac - k as f64;
+                    let sinc = (PI * x).sin() / (PI * x);
+                    let window = 0.5 + 0.5 * (PI * x / TRUE_PEAK_TAPS as f64).cos();
+                    sinc * window
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut peak = 0.0f64;
+    for signal in channels(buffer) {
+        for (i, &s) in signal.iter().enumerate() {
+            peak = peak.max(s.abs());
+            for kernel in &kernels {
+                let value: f64 = kernel
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(j, w)| {
+                        let index = i as isize + j as isize - TRUE_PEAK_TAPS + 1;
+                        usize::try_from(index)
+                            .ok()
+                            .and_then(|index| signal.get(index))
+                            .map(|x| x * w)
+                    })
+                    .sum();
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+    peak
+}
+
+/// Direct form I biquad.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+}
+
+impl Biquad {
+    fn apply(&self, signal: &[f64]) -> Vec<f64> {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        signal
+            .iter()
+            .map(|&x| {
+                let y = (self.b[0] * x + self.b[1] * x1 + self.b[2] * x2
+                    - self.a[1] * y1
+                    - self.a[2] * y2)
+                    / self.a[0];
+                (x2, x1, y2, y1) = (x1, x, y1, y);
+                y
+            })
+            .collect()
+    }
+}
+
+/// BS.1770 K-weighting: a high-shelf boost followed by a high-pass.
+fn k_weighting(rate: f64) -> [Biquad; 2] {
+    let shelf = {
+        let a = 10f64.powf(4.0 / 40.0);
+        let w0 = 2.0 * PI * 1500.0 / rate;
+        let alpha = w0.sin() / (2.0 * std::f64::consts::FRAC_1_SQRT_2);
+        let (cos, sqrt_a) = (w0.cos(), a.sqrt());
+        Biquad {
+            b: [
+                a * ((a + 1.0) + (a - 1.0) * cos + 2.0 * sqrt_a * alpha),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - 2.0 * sqrt_a * alpha),
+            ],
+            a: [
+                (a + 1.0) - (a - 1.0) * cos + 2.0 * sqrt_a * alpha,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - 2.0 * sqrt_a * alpha,
+            ],
+        }
+    };
+    let high_pass = {
+        let w0 = 2.0 * PI * 38.0 / rate;
+        let alpha = w0.sin() / (2.0 * 0.5);
+        let cos = w0.cos();
+        Biquad {
+            b: [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            a: [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        }
+    };
+    [shelf, high_pass]
+}
+
+/// Gated integrated loudness in LUFS.
+pub(super) fn integrated_loudness(buffer: &AudioBuffer) -> Option<f64> {
+    let rate = f64::from(buffer.sample_rate);
+    let block = (0.4 * rate) as usize;
+    let step = (0.1 * rate) as usize;
+    if block == 0 || buffer.frames() < block {
+        return None;
+    }
+
+    let [shelf, high_pass] = k_weighting(rate);
+    let weighted: Vec<Vec<f64>> = channels(buffer)
+        .iter()
+        .map(|signal| high_pass.apply(&shelf.apply(signal)))
+        .collect();
+
+    // Mean square of each block, summed over channels
+    let powers: Vec<f64> = (0..=(buffer.frames() - block) / step)
+        .map(|j| {
+            weighted
+                .iter()
+                .map(|signal| {
+                    signal[j * step..j * step + block]
+                        .iter()
+                        .map(|x| x * x)
+                        .sum::<f64>()
+                        / block as f64
+                })
+                .sum()
+        })
+        .collect();
+
+    let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+    let gated_mean = |threshold: f64| {
+        let kept: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|&p| p > 0.0 && loudness(p) > threshold)
+            .collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+
+    let absolute = gated_mean(-70.0)?;
+    gated_mean(loudness(absolute) - 10.0).map(loudness)
+}
+
+/// Runs of frames below `silence_db` lasting at least `min_length` seconds.
+fn long_silences(buffer: &AudioBuffer, silence_db: f64, min_length: f64) -> Vec<[f64; 2]> {
+    let samples = buffer.mono();
+    let rate = f64::from(buffer.sample_rate.max(1));
+    let frame_len = ((rate * SILENCE_FRAME) as usize).max(1);
+    let duration = samples.len() as f64 / rate;
+
+    let mut silences = Vec::new();
+    let mut start = None;
+    let frames = samples.chunks(frame_len).map(|frame| {
+        let power = frame.iter().map(|&s| f64::from(s).powi(2)).sum::<f64>() / frame.len() as f64;
+        to_db(power.sqrt()) < silence_db
+    });
+
+    for (i, silent) in frames.chain(std::iter::once(false)).enumerate() {
+        let time = (i * frame_len) as f64 / rate;
+        match (silent, start) {
+            (true, None) => start = Some(time),
+            (false, Some(s)) => {
+                let end = time.min(duration);
+                if end - s >= min_length {
+                    silences.push([round(s, 2), round(end, 2)]);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    silences
+}
@@ -0,0 +1,64 @@
+//! Headerless sample formats, as PBX sound directories store prompts.
+//!
+//! Asterisk picks a prompt file by extension and plays `.ulaw`, `.alaw`,
+//! and `.sln*` files without transcoding when the call uses that codec.
+
+use super::AudioBuffer;
+
+/// A headerless sample encoding. The sample rate is implied by the file
+/// extension, so the buffer must already be at the right rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawFormat {
+    /// 8-bit G.711 μ-law (`.ulaw`).
+    Mulaw,
+    /// 8-bit G.711 A-law (`.alaw`).
+    Alaw,
+    /// 16-bit signed little-endian linear PCM (`.sln`, `.sln16`, ...).
+    Slin,
+}
+
+impl RawFormat {
+    /// Encode the buffer's samples as they are, channels interleaved.
+    pub fn encode(self, buffer: &AudioBuffer) -> Vec<u8> {
+        match self {
+            Self::Mulaw => buffer.samples.iter().map(|&s| mulaw(s)).collect(),
+            Self::Alaw => buffer.samples.iter().map(|&s| alaw(s)).collect(),
+            Self::Slin => buffer
+                .samples
+                .iter()
+                .flat_map(|&s| ((s.clamp(-1.0, 1.0) * 32767.0) as i16).to_le_bytes())
+                .collect(),
+        }
+    }
+}
+
+/// Compress a sample with the G.711 μ-law curve.
+pub(super) fn mulaw(sample: f32) -> u8 {
+    const BIAS: i32 = 0x84;
+    const CLIP: i32 = 32635;
+
+    let pcm = (sample.clamp(-1.0, 1.0) * 32767.0) as i32;
+    let sign = if pcm < 0 { 0x80 } else { 0 };
+    let magnitude = pcm.abs().min(CLIP) + BIAS;
+    let mut exponent = 7;
+    while exponent > 0 && magnitude & (0x80 << exponent) == 0 {
+        exponent -= 1;
+    }
+    let mantissa = (magnitude >> (exponent + 3)) & 0x0F;
+    !(sign | (exponent << 4) | mantissa) as u8
+}
+
+/// Compress a sample with the G.711 A-law curve.
+pub(super) fn alaw(sample: f32) -> u8 {
+    let pcm = (sample.clamp(-1.0, 1.0) * 32767.0) as i32;
+    let sign = if pcm < 0 { 0 } else { 0x80 };
+    // A-law works on 13-bit magnitudes
+    let magnitude = (pcm.abs() >> 3).min(0x0FFF);
+    let code = if magnitude < 32 {
+        magnitude >> 1
+    } else {
+        let exponent = (31 - (magnitude as u32).leading_zeros()) as i32 - 4;
+        (exponent << 4) | ((magnitude >> exponent) & 0x0F)
+    };
+    ((sign | code) ^ 0x55) as u8
+}
@@ -0,0 +1,186 @@
+//! Destinations for finished audio.
+//!
+//! Every output target takes the same WAV bytes through [`AudioSink`], so a
+//! new target is one more implementation rather than another branch in the
+//! code that produces audio.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::icecast::IcecastTarget;
+use super::play::{VIRTUAL_MIC_SOURCE, play_file, play_to_virtual_mic};
+use super::preset::{Encoding, Preset};
+use super::{AudioBuffer, AudioError};
+use crate::scratch::{persist, scratch_file};
+
+/// A destination for finished WAV audio.
+pub trait AudioSink {
+    /// Deliver one complete WAV file.
+    fn write(&mut self, wav: &[u8]) -> Result<(), AudioError>;
+
+    /// Where the audio goes, for progress messages.
+    fn describe(&self) -> String;
+}
+
+impl<S: AudioSink + ?Sized> AudioSink for Box<S> {
+    fn write(&mut self, wav: &[u8]) -> Result<(), AudioError> {
+        (**self).write(wav)
+    }
+
+    fn describe(&self) -> String {
+        (**self).describe()
+    }
+}
+
+/// Saves audio to a file, replacing any existing one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSink {
+    path: PathBuf,
+    preset: Option<Preset>,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            preset: None,
+        }
+    }
+
+    /// Save in the preset's encoding rather than as the WAV written.
+    pub fn with_preset(mut self, preset: Option<Preset>) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AudioSink for FileSink {
+    fn write(&mut self, wav: &[u8]) -> Result<(), AudioError> {
+        match &self.preset {
+            Some(preset) if preset.encoding != Encoding::Pcm => {
+                let audio = AudioBuffer::from_wav_bytes(wav)?;
+                persist(&self.path, &preset.encode(&audio)?)?;
+            }
+            _ => persist(&self.path, wav)?,
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// Writes audio to standard output, for piping into other tools.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StdoutSink;
+
+impl AudioSink for StdoutSink {
+    fn write(&mut self, wav: &[u8]) -> Result<(), AudioError> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(wav)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        "standard output".to_string()
+    }
+}
+
+/// Keeps audio in memory; each write replaces the last.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemorySink {
+    pub wav: Vec<u8>,
+}
+
+impl AudioSink for MemorySink {
+    fn write(&mut self, wav: &[u8]) -> Result<(), AudioError> {
+        self.wav = wav.to_vec();
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+}
+
+/// Where a [`PlaybackSink`] sends audio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackDevice {
+    /// The default output device.
+    #[default]
+    Speakers,
+    /// The virtual microphone (see [`play_to_virtual_mic`]).
+    VirtualMic,
+}
+
+/// Plays audio and waits for it to finish.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlaybackSink {
+    device: PlaybackDevice,
+}
+
+impl PlaybackSink {
+    pub fn new(device: PlaybackDevice) -> Self {
+        Self { device }
+    }
+}
+
+impl AudioSink for PlaybackSink {
+    fn write(&mut self, wav: &[u8]) -> Result<(), AudioError> {
+        // The players read files, so stage the audio in a temporary one
+        static STAGED: AtomicUsize = AtomicUsize::new(0);
+        let path = scratch_file(&format!(
+            "play-{}.wav",
+            STAGED.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, wav)?;
+        let result = match self.device {
+            PlaybackDevice::Speakers => play_file(&path),
+            PlaybackDevice::VirtualMic => play_to_virtual_mic(&path),
+        };
+        let _ = std::fs::remove_file(&path);
+        result
+    }
+
+    fn describe(&self) -> String {
+        match self.device {
+            PlaybackDevice::Speakers => "speakers".to_string(),
+            PlaybackDevice::VirtualMic => format!("virtual microphone {VIRTUAL_MIC_SOURCE}"),
+        }
+    }
+}
+
+/// Streams audio live to an Icecast mount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcecastSink {
+    target: IcecastTarget,
+    name: String,
+}
+
+impl IcecastSink {
+    /// Stream to `target`, announcing the stream as `name`.
+    pub fn new(target: IcecastTarget, name: &str) -> Self {
+        Self {
+            target,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl AudioSink for IcecastSink {
+    fn write(&mut self, wav: &[u8]) -> Result<(), AudioError> {
+        self.target.stream(wav, &self.name)
+    }
+
+    fn describe(&self) -> String {
+        let target = &self.target;
+        format!("icecast://{}:{}{}", target.host, target.port, target.mount)
+    }
+}
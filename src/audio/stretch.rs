@@ -0,0 +1,133 @@
+//! Changing the length of audio without changing its pitch.
+//!
+//! WSOLA (waveform-similarity overlap-add): the output is built from
+//! overlapping windows of the input taken at a different rate than they
+//! are laid down, each one nudged to where it best continues the last, so
+//! pitch periods line up and speech keeps its voice.
+
+use std::time::Duration;
+
+use super::AudioBuffer;
+
+/// Stretches beyond this fraction of the original length (either way)
+/// start to sound processed.
+pub const MAX_CLEAN_STRETCH: f64 = 0.15;
+
+/// Window length, in seconds.
+const WINDOW: f64 = 0.03;
+
+/// How far a window may move to line up with the last, in seconds.
+const TOLERANCE: f64 = 0.01;
+
+/// Time-stretch `buffer` to exactly `duration`, keeping its pitch.
+pub fn time_stretch(buffer: &AudioBuffer, duration: Duration) -> AudioBuffer {
+    let rate = buffer.sample_rate.max(1);
+    let channels = buffer.channels.max(1) as usize;
+    let frames = buffer.frames();
+    let target = (duration.as_secs_f64() * f64::from(rate)).round() as usize;
+    if frames == target || frames == 0 || target == 0 {
+        let mut samples = buffer.samples.clone();
+        samples.resize(target * channels, 0.0);
+        return AudioBuffer::new(samples, rate, buffer.channels);
+    }
+
+    let window = ((WINDOW * f64::from(rate)) as usize / 2 * 2).max(16);
+    let hop = window / 2;
+    let tolerance = (TOLERANCE * f64::from(rate)) as isize;
+    let scale = frames as f64 / target as f64;
+    // Periodic Hann windows at half-window hops sum to one
+    let hann: Vec<f32> = (0..window)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / window as f32).cos())
+        .collect();
+
+    let mono = buffer.mono();
+    let mut output = vec![0.0f32; (target + window) * channels];
+    let mut weight = vec![0.0f32; target + window];
+    let mut previous: Option<isize> = None;
+    for start in (0..target).step_by(hop) {
+        let nominal = (start as f64 * scale).round() as isize;
+        let position = match previous {
+            None => nominal,
+            // Line up with the input that would have followed the last window
+            Some(previous) => {
+                best_position(&mono, nominal, previous + hop as isize, hop, tolerance)
+            }
+        };
+        previous = Some(position);
+        add_window(buffer, &hann, position, start, &mut output, &mut weight);
+    }
+
+    // Only the first and last half windows are covered less than once
+    for (frame, &w) in weight.iter().enumerate().take(target) {
+        if w > 1e-3 {
+            for c in 0..channels {
+                output[frame * channels + c] /= w;
+            }
+        }
+    }
+    output.truncate(target * channels);
+    AudioBuffer::new(output, rate, buffer.channels)
+}
+
+/// The input position within `tolerance` of `nominal` whose next `hop`
+/// frames of `mono` best match those from `natural`.
+fn best_position(
+    mono: &[f32],
+    nominal: isize,
+    natural: isize,
+    hop: usize,
+    tolerance: isize,
+) -> isize {
+    let at = |i: isize| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| mono.get(i))
+            .copied()
+            .unwrap_or(0.0)
+    };
+    let mut best = (f32::MIN, nominal);
+    for candidate in nominal - tolerance..=nominal + tolerance {
+        let correlation: f32 = (0..hop as isize)
+            .map(|i| at(candidate + i) * at(natural + i))
+            .sum();
+        if correlation > best.0 {
+            best = (correlation, candidate);
+        }
+    }
+    best.1
+}
+
+/// Add the window of `buffer` at input frame `position`, shaped by
+/// `hann`, to `output` at frame `start`, and its coverage to `weight`.
+fn add_window(
+    buffer: &AudioBuffer,
+    hann: &[f32],
+    position: isize,
+    start: usize,
+    output: &mut [f32],
+    weight: &mut [f32],
+) {
+    let channels = buffer.channels.max(1) as usize;
+    let frames = buffer.frames();
+    for (i, w) in hann.iter().enumerate() {
+        weight[start + i] += w;
+        let Ok(source) = usize::try_from(position + i as isize) else {
+            continue;
+        };
+        if source >= frames {
+            break;
+        }
+        for c in 0..channels {
+            output[(start + i) * channels + c] += buffer.samples[source * channels + c] * w;
+        }
+    }
+}
+
+/// How far `from` would be stretched to last `to`, as a fraction of its
+/// length: `0.1` is 10% longer, `-0.1` 10% shorter.
+pub fn stretch_amount(from: Duration, to: Duration) -> f64 {
+    if from.is_zero() {
+        return 0.0;
+    }
+    to.as_secs_f64() / from.as_secs_f64() - 1.0
+}
@@ -0,0 +1,145 @@
+//! Metadata tags for generated audio files.
+//!
+//! Tags are ID3v2.4: an `id3 ` chunk in WAV and AIFF files and a leading
+//! tag in MP3 files. FLAC and MP4 containers are not supported.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use chrono::Local;
+use id3::frame::ExtendedText;
+use id3::{Tag, TagLike, Version};
+use serde::{Deserialize, Serialize};
+
+use super::AudioError;
+
+/// Description of the user-defined text frame naming the generating tool.
+pub const GENERATED_BY: &str = "generated-by";
+
+/// Tag templates from the `[tags]` config section.
+///
+/// Values may contain `{voice}`, `{file}` (output file stem), `{chapter}`,
+/// and `{date}` placeholders. A value that renders empty is left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagTemplates {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+}
+
+impl Default for TagTemplates {
+    fn default() -> Self {
+        Self {
+            title: "{file}".to_string(),
+            artist: "{voice}".to_string(),
+            album: String::new(),
+        }
+    }
+}
+
+/// Values substituted into tag templates.
+#[derive(Debug, Clone, Copy)]
+pub struct TagContext<'a> {
+    pub voice: Option<&'a str>,
+    pub file: &'a Path,
+    pub chapter: Option<u32>,
+}
+
+impl TagTemplates {
+    /// Fill in the templates for one file.
+    pub fn render(&self, context: &TagContext, generated_by: &str) -> Metadata {
+        let render = |template: &str| {
+            let value = template
+                .replace("{voice}", context.voice.unwrap_or_default())
+                .replace(
+                    "{file}",
+                    &context
+                        .file
+                        .file_stem()
+                        .map(|s| s.to_string_lossy())
+                        .unwrap_or_default(),
+                )
+                .replace(
+                    "{chapter}",
+                    &context.chapter.map(|c| c.to_string()).unwrap_or_default(),
+                )
+                .replace("{date}", &Local::now().format("%Y-%m-%d").to_string());
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+
+        Metadata {
+            title: render(&self.title),
+            artist: render(&self.artist),
+            album: render(&self.album),
+            chapter: context.chapter,
+            generated_by: generated_by.to_string(),
+        }
+    }
+}
+
+/// Metadata embedded in a generated file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Written as the track number.
+    pub chapter: Option<u32>,
+    pub generated_by: String,
+}
+
+impl Metadata {
+    /// Write the tag into `path`, replacing any existing ID3 tag.
+    pub fn write(&self, path: &Path) -> Result<(), AudioError> {
+        let mut magic = [0u8; 12];
+        let read = File::open(path)?.read(&mut magic)?;
+        let magic = &magic[..read];
+        if magic.starts_with(b"fLaC") || magic.get(4..8) == Some(b"ftyp") {
+            return Err(AudioError::Tag(format!(
+                "unsupported container for ID3 tags: {}",
+                path.display()
+            )));
+        }
+
+        let mut tag = Tag::new();
+        if let Some(title) = &self.title {
+            tag.set_title(title);
+        }
+        if let Some(artist) = &self.artist {
+            tag.set_artist(artist);
+        }
+        if let Some(album) = &self.album {
+            tag.set_album(album);
+        }
+        if let Some(chapter) = self.chapter {
+            tag.set_track(chapter);
+        }
+        tag.add_frame(ExtendedText {
+            description: GENERATED_BY.to_string(),
+            value: self.generated_by.clone(),
+        });
+
+        tag.write_to_path(path, Version::Id3v24)
+            .map_err(|e| AudioError::Tag(e.to_string()))
+    }
+
+    /// Read the tag back from `path`.
+    pub fn read(path: &Path) -> Result<Self, AudioError> {
+        let tag = Tag::read_from_path(path).map_err(|e| AudioError::Tag(e.to_string()))?;
+
+        Ok(Self {
+            title: tag.title().map(str::to_string),
+            artist: tag.artist().map(str::to_string),
+            album: tag.album().map(str::to_string),
+            chapter: tag.track(),
+            generated_by: tag
+                .extended_texts()
+                .find(|t| t.description == GENERATED_BY)
+                .map(|t| t.value.clone())
+                .unwrap_or_default(),
+        })
+    }
+}
@@ -0,0 +1,39 @@
+//! Keeping audio within a length limit.
+
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use super::AudioBuffer;
+use super::post::fade_out;
+
+/// Length of the fade-out ending trimmed audio.
+pub const TRIM_FADE: Duration = Duration::from_millis(250);
+
+/// What to do with audio longer than its limit.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overlong {
+    /// Fail without writing the audio
+    #[default]
+    Error,
+    /// Cut the audio at the limit, fading it out
+    Trim,
+}
+
+/// Cut `buffer` to at most `max`, fading out over the last `fade` so it
+/// does not end in a click. Shorter audio is returned as is.
+pub fn trim_to(buffer: &AudioBuffer, max: Duration, fade: Duration) -> AudioBuffer {
+    let channels = buffer.channels.max(1) as usize;
+    let frames = (max.as_secs_f64() * f64::from(buffer.sample_rate)).round() as usize;
+    if frames >= buffer.frames() {
+        return buffer.clone();
+    }
+
+    let mut trimmed = AudioBuffer::new(
+        buffer.samples[..frames * channels].to_vec(),
+        buffer.sample_rate,
+        buffer.channels,
+    );
+    fade_out(&mut trimmed, fade);
+    trimmed
+}
@@ -0,0 +1,140 @@
+//! Waveform and mel spectrogram images for visual QA.
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+use plotters::prelude::*;
+
+use super::voiceprint::{fft, mel_filterbank};
+use super::{AudioBuffer, AudioError};
+
+const FRAME_SECS: f32 = 0.025;
+const HOP_SECS: f32 = 0.010;
+const MEL_BANDS: usize = 64;
+/// Spectrogram levels this far below the loudest bin render as black.
+const DYNAMIC_RANGE_DB: f32 = 80.0;
+
+const WIDTH: u32 = 1200;
+const WAVEFORM_HEIGHT: u32 = 200;
+const SPECTROGRAM_HEIGHT: u32 = 400;
+
+/// Log-power mel spectrogram: one row of `MEL_BANDS` decibel values per
+/// 10 ms frame, lowest band first.
+pub fn mel_spectrogram(buffer: &AudioBuffer) -> Vec<Vec<f32>> {
+    let samples = buffer.mono();
+    let rate = buffer.sample_rate as f32;
+    let frame_len = (FRAME_SECS * rate) as usize;
+    let hop = ((HOP_SECS * rate) as usize).max(1);
+    if frame_len < 2 || samples.len() < frame_len {
+        return Vec::new();
+    }
+
+    let n_fft = frame_len.next_power_of_two();
+    let filters = mel_filterbank(MEL_BANDS, n_fft, rate);
+    let window: Vec<f32> = (0..frame_len)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / (frame_len - 1) as f32).cos())
+        .collect();
+
+    samples
+        .windows(frame_len)
+        .step_by(hop)
+        .map(|frame| {
+            let mut re = vec![0.0; n_fft];
+            let mut im = vec![0.0; n_fft];
+            for (i, (s, w)) in frame.iter().zip(&window).enumerate() {
+                re[i] = s * w;
+            }
+            fft(&mut re, &mut im);
+
+            let power: Vec<f32> = (0..=n_fft / 2)
+                .map(|k| re[k] * re[k] + im[k] * im[k])
+                .collect();
+            filters
+                .iter()
+                .map(|f| f.iter().zip(&power).map(|(w, p)| w * p).sum::<f32>())
+                .map(|e: f32| 10.0 * e.max(1e-10).log10())
+                .collect()
+        })
+        .collect()
+}
+
+/// Render the waveform (top) and mel spectrogram (bottom) to an image.
+///
+/// The format follows the file extension (PNG, JPEG, or BMP).
+pub fn render_visualization(buffer: &AudioBuffer, path: &Path) -> Result<(), AudioError> {
+    let root =
+        BitMapBackend::new(path, (WIDTH, WAVEFORM_HEIGHT + SPECTROGRAM_HEIGHT)).into_drawing_area();
+    root.fill(&BLACK).map_err(render_error)?;
+    let (waveform, spectrogram) = root.split_vertically(WAVEFORM_HEIGHT);
+
+    draw_waveform(&waveform, &buffer.mono()).map_err(render_error)?;
+    draw_spectrogram(&spectrogram, &mel_spectrogram(buffer)).map_err(render_error)?;
+
+    root.present().map_err(render_error)?;
+    Ok(())
+}
+
+fn render_error(e: impl std::fmt::Display) -> AudioError {
+    AudioError::Render(e.to_string())
+}
+
+type Area<'a> = DrawingArea<BitMapBackend<'a>, plotters::coord::Shift>;
+type DrawResult<'a> =
+    Result<(), DrawingAreaErrorKind<<BitMapBackend<'a> as DrawingBackend>::ErrorType>>;
+
+/// One vertical min/max line per pixel column.
+fn draw_waveform<'a>(area: &Area<'a>, samples: &[f32]) -> DrawResult<'a> {
+    let mid = WAVEFORM_HEIGHT as i32 / 2;
+    area.draw(&PathElement::new(
+        vec![(0, mid), (WIDTH as i32, mid)],
+        RGBColor(60, 60, 60),
+    ))?;
+    if samples.is_empty() {
+        return Ok(());
+    }
+
+    let per_column = samples.len().div_ceil(WIDTH as usize);
+    for (x, column) in samples.chunks(per_column).enumerate() {
+        let (low, high) = column
+            .iter()
+            .fold((0.0f32, 0.0f32), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+        let to_y = |v: f32| mid - (v.clamp(-1.0, 1.0) * (mid - 1) as f32) as i32;
+        area.draw(&PathElement::new(
+            vec![(x as i32, to_y(high)), (x as i32, to_y(low))],
+            RGBColor(80, 200, 120),
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Frames stretched across the width, low frequencies at the bottom.
+fn draw_spectrogram<'a>(area: &Area<'a>, frames: &[Vec<f32>]) -> DrawResult<'a> {
+    let Some(peak) = frames.iter().flatten().copied().reduce(f32::max) else {
+        return Ok(());
+    };
+
+    for x in 0..WIDTH {
+        let frame = &frames[x as usize * frames.len() / WIDTH as usize];
+        for y in 0..SPECTROGRAM_HEIGHT {
+            let band =
+                (SPECTROGRAM_HEIGHT - 1 - y) as usize * MEL_BANDS / SPECTROGRAM_HEIGHT as usize;
+            let level =
+                ((frame[band] - peak + DYNAMIC_RANGE_DB) / DYNAMIC_RANGE_DB).clamp(0.0, 1.0);
+            area.draw_pixel((x as i32, y as i32), &heat(level))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Black through purple and orange to pale yellow.
+fn heat(level: f32) -> RGBColor {
+    let channel =
+        |start: f32, end: f32| (255.0 * (level - start) / (end - start)).clamp(0.0, 255.0) as u8;
+    RGBColor(
+        channel(0.0, 0.6),
+        channel(0.4, 1.0),
+        channel(0.75, 1.0).max(channel(0.0, 0.3) / 2),
+    )
+}
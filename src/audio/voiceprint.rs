@@ -0,0 +1,202 @@
+//! Spectral voiceprints for comparing speakers.
+//!
+//! A voiceprint summarizes a recording as the mean and spread of its
+//! mel-frequency cepstral coefficients over voiced frames. It is not a
+//! neural speaker-verification embedding, but it tracks timbre well enough
+//! to flag a clone that drifted far from its reference.
+
+use std::f32::consts::PI;
+
+use super::{AudioBuffer, AudioError};
+
+const FRAME_SECS: f32 = 0.025;
+const HOP_SECS: f32 = 0.010;
+const MEL_BANDS: usize = 26;
+const COEFFICIENTS: usize = 12;
+const MIN_HZ: f32 = 50.0;
+const MAX_HZ: f32 = 8000.0;
+/// Frames quieter than this fraction of the loudest frame are ignored.
+const SILENCE_RATIO: f32 = 0.05;
+
+/// Fixed-length speaker summary of a recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voiceprint {
+    /// Per-coefficient means followed by standard deviations.
+    pub features: Vec<f32>,
+}
+
+impl Voiceprint {
+    /// Compute the voiceprint of a buffer.
+    ///
+    /// Returns [`AudioError::Empty`] when the audio has no voiced frames.
+    pub fn from_buffer(buffer: &AudioBuffer) -> Result<Self, AudioError> {
+        let samples = buffer.mono();
+        let rate = buffer.sample_rate as f32;
+        let frame_len = (FRAME_SECS * rate) as usize;
+        let hop = ((HOP_SECS * rate) as usize).max(1);
+        if frame_len < 2 || samples.len() < frame_len {
+            return Err(AudioError::Empty);
+        }
+
+        let n_fft = frame_len.next_power_of_two();
+        let filters = mel_filterbank(MEL_BANDS, n_fft, rate);
+        let window: Vec<f32> = (0..frame_len)
+            .map(|i| 0.54 - 0.46 * (2.0 * PI * i as f32 / (frame_len - 1) as f32).cos())
+            .collect();
+
+        let frames: Vec<&[f32]> = samples.windows(frame_len).step_by(hop).collect();
+        let energies: Vec<f32> = frames.iter().map(|f| rms(f)).collect();
+        let loudest = energies.iter().cloned().fold(0.0, f32::max);
+        if loudest == 0.0 {
+            return Err(AudioError::Empty);
+        }
+
+        let mut cepstra = Vec::new();
+        for (frame, energy) in frames.iter().zip(&energies) {
+            if *energy < loudest * SILENCE_RATIO {
+                continue;
+            }
+            let mut re = vec![0.0; n_fft];
+            let mut im = vec![0.0; n_fft];
+            re[0] = frame[0] * window[0];
+            for i in 1..frame_len {
+                re[i] = (frame[i] - 0.97 * frame[i - 1]) * window[i];
+            }
+            fft(&mut re, &mut im);
+
+            let power: Vec<f32> = (0..=n_fft / 2)
+                .map(|k| re[k] * re[k] + im[k] * im[k])
+                .collect();
+            let log_mel: Vec<f32> = filters
+                .iter()
+                .map(|f| f.iter().zip(&power).map(|(w, p)| w * p).sum::<f32>())
+                .map(|e: f32| e.max(1e-10).ln())
+                .collect();
+            cepstra.push(dct(&log_mel));
+        }
+
+        let n = cepstra.len() as f32;
+        let mut features = vec![0.0; COEFFICIENTS * 2];
+        for c in &cepstra {
+            for (i, v) in c.iter().enumerate() {
+                features[i] += v / n;
+            }
+        }
+        for c in &cepstra {
+            for (i, v) in c.iter().enumerate() {
+                features[COEFFICIENTS + i] += (v - features[i]).powi(2) / n;
+            }
+        }
+        for v in &mut features[COEFFICIENTS..] {
+            *v = v.sqrt();
+        }
+
+        Ok(Self { features })
+    }
+
+    /// Cosine similarity between two voiceprints, clamped to 0.0..=1.0.
+    pub fn similarity(&self, other: &Self) -> f32 {
+        let dot: f32 = self
+            .features
+            .iter()
+            .zip(&other.features)
+            .map(|(a, b)| a * b)
+            .sum();
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let denom = norm(&self.features) * norm(&other.features);
+        if denom == 0.0 {
+            return 0.0;
+        }
+        (dot / denom).clamp(0.0, 1.0)
+    }
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt()
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular filters over the `n_fft / 2 + 1` power-spectrum bins.
+pub(super) fn mel_filterbank(bands: usize, n_fft: usize, rate: f32) -> Vec<Vec<f32>> {
+    let max_hz = MAX_HZ.min(rate / 2.0);
+    let (low, high) = (hz_to_mel(MIN_HZ), hz_to_mel(max_hz));
+    let edges: Vec<f32> = (0..bands + 2)
+        .map(|i| mel_to_hz(low + (high - low) * i as f32 / (bands + 1) as f32))
+        .collect();
+    let bin_hz = rate / n_fft as f32;
+
+    (0..bands)
+        .map(|m| {
+            let (left, center, right) = (edges[m], edges[m + 1], edges[m + 2]);
+            (0..=n_fft / 2)
+                .map(|k| {
+                    let hz = k as f32 * bin_hz;
+                    if hz <= left || hz >= right {
+                        0.0
+                    } else if hz <= center {
+                        (hz - left) / (center - left)
+                    } else {
+                        (right - hz) / (right - center)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// DCT-II of the log mel energies, skipping the loudness term c0.
+fn dct(log_mel: &[f32]) -> Vec<f32> {
+    let n = log_mel.len() as f32;
+    (1..=COEFFICIENTS)
+        .map(|k| {
+            log_mel
+                .iter()
+                .enumerate()
+                .map(|(i, e)| e * (PI * k as f32 * (i as f32 + 0.5) / n).cos())
+                .sum()
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT; the length must be a power of two.
+pub(super) fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_re, w_im) = ((angle * k as f32).cos(), (angle * k as f32).sin());
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -1,80 +1,195 @@
 //! HTTP client for backend communication.
 
+use std::collections::HashMap;
+use std::io::Read;
 use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::cli::Model;
+use sha2::{Digest, Sha256};
 
-use super::Backend;
-use super::types::{BackendError, HealthResponse, SynthesizeRequest, VoiceInfo, VoicesResponse};
+use super::message::{ApiRequest, ApiResponse, Body, GradioPoll, Part, parse_retry_after};
+use super::middleware::new_request_id;
+use super::protocol::{GRADIO_POLL_ATTEMPTS, Protocol};
+use super::types::{
+    BackendError, Capabilities, EmbeddingResponse, HealthResponse, Progress, SynthesisEvent,
+    SynthesizeRequest, VoiceInfo, VoicesResponse,
+};
+use super::{Backend, Model};
 
 /// HTTP-based backend client.
+///
+/// Sends [`Protocol`] requests with a blocking client, reading reference
+/// audio from disk. Clones share the connection pool and the cache of uploaded reference
+/// audio.
+#[derive(Clone)]
 pub struct HttpBackend {
-    base_url: String,
+    protocol: Protocol,
     client: reqwest::blocking::Client,
-    model: Model,
+    headers: reqwest::header::HeaderMap,
+    timeout: Option<Duration>,
+    /// Gradio server paths of uploaded files, by SHA-256 of their content.
+    uploads: Arc<Mutex<HashMap<String, String>>>,
 }
 
 impl HttpBackend {
-    /// Create a new HTTP backend client.
+    /// Create a new HTTP backend client on the model's default port.
     pub fn new(model: Model, host: &str) -> Self {
-        let port = model.port();
-        let base_url = format!("http://{host}:{port}");
+        Self::with_port(model, host, model.port())
+    }
 
+    /// Create a new HTTP backend client on a specific port.
+    pub fn with_port(model: Model, host: &str, port: u16) -> Self {
         Self {
-            base_url,
+            protocol: Protocol::new(model, format!("http://{host}:{port}")),
             client: reqwest::blocking::Client::new(),
-            model,
+            headers: reqwest::header::HeaderMap::new(),
+            timeout: None,
+            uploads: Arc::default(),
         }
     }
 
-    /// Get the base URL for this backend.
-    pub fn base_url(&self) -> &str {
-        &self.base_url
-    }
+    /// Send `Authorization: Bearer <token>` with every request, for
+    /// backends behind an authenticating reverse proxy.
+    pub fn with_token(mut self, token: &str) -> Result<Self, BackendError> {
+        let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {token}"))
+            .map_err(|_| BackendError::RequestFailed("Invalid token".to_string()))?;
+        value.set_sensitive(true);
 
-    /// Upload a file to Gradio backend, returns the server path.
-    fn gradio_upload(&self, audio_path: &Path) -> Result<String, BackendError> {
-        let url = format!("{}/gradio_api/upload", self.base_url);
+        self.headers.insert(reqwest::header::AUTHORIZATION, value);
+        self.rebuild()
+    }
 
-        let audio_data = std::fs::read(audio_path)
-            .map_err(|_| BackendError::FileNotFound(audio_path.display().to_string()))?;
+    /// Give up on requests that take longer than `timeout`.
+    ///
+    /// Long texts can take minutes on slow GPUs; the default is 30 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<Self, BackendError> {
+        self.timeout = Some(timeout);
+        self.rebuild()
+    }
 
-        let file_name = audio_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("audio.wav");
+    /// Reject responses that differ from the expected schema (see
+    /// [`Protocol::with_strict`]).
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.protocol = self.protocol.with_strict(strict);
+        self
+    }
 
-        let file_part = reqwest::blocking::multipart::Part::bytes(audio_data)
-            .file_name(file_name.to_string())
-            .mime_str("audio/wav")
+    fn rebuild(mut self) -> Result<Self, BackendError> {
+        let mut builder =
+            reqwest::blocking::Client::builder().default_headers(self.headers.clone());
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        self.client = builder
+            .build()
             .map_err(|e| BackendError::RequestFailed(e.to_string()))?;
+        Ok(self)
+    }
 
-        let form = reqwest::blocking::multipart::Form::new().part("files", file_part);
+    /// Get the base URL for this backend.
+    pub fn base_url(&self) -> &str {
+        self.protocol.base_url()
+    }
 
-        let response = self
-            .client
-            .post(&url)
-            .multipart(form)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
+    fn send(&self, request: ApiRequest) -> Result<ApiResponse, BackendError> {
+        read_response(self.wait(request, None)?, None)
+    }
 
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Upload failed: {}",
-                response.status()
-            )));
+    /// Send a request and wait for the response head.
+    ///
+    /// With `progress`, `Generating` is reported every second meanwhile,
+    /// timed from the given start.
+    fn wait(
+        &self,
+        request: ApiRequest,
+        progress: Option<(&Progress, Instant)>,
+    ) -> Result<reqwest::blocking::Response, BackendError> {
+        let mut builder = self.client.request(request.method, &request.url);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
         }
+        let builder = match request.body {
+            Body::Empty => builder,
+            Body::Json(body) => builder.json(&body),
+            Body::Multipart(parts) => {
+                let mut form = reqwest::blocking::multipart::Form::new();
+                for part in parts {
+                    form = match part {
+                        Part::Text { name, value } => form.text(name, value),
+                        Part::Audio {
+                            name,
+                            file_name,
+                            bytes,
+                        } => {
+                            let file = reqwest::blocking::multipart::Part::bytes(bytes)
+                                .file_name(file_name)
+                                .mime_str("audio/wav")
+                                .map_err(|e| BackendError::RequestFailed(e.to_string()))?;
+                            form.part(name, file)
+                        }
+                    };
+                }
+                builder.multipart(form)
+            }
+        };
 
-        let paths: Vec<String> = response
-            .json()
-            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+        let response = match progress {
+            None => builder.send(),
+            Some((progress, started)) => thread::scope(|scope| {
+                let (done, waiting) = mpsc::channel::<()>();
+                progress.emit(SynthesisEvent::Generating {
+                    elapsed: started.elapsed(),
+                });
+                scope.spawn(move || {
+                    while let Err(RecvTimeoutError::Timeout) =
+                        waiting.recv_timeout(Duration::from_secs(1))
+                    {
+                        progress.emit(SynthesisEvent::Generating {
+                            elapsed: started.elapsed(),
+                        });
+                    }
+                });
+                let response = builder.send();
+                drop(done);
+                response
+            }),
+        };
+        response.map_err(|e| BackendError::ConnectionFailed(e.to_string()))
+    }
 
-        paths
-            .into_iter()
-            .next()
-            .ok_or_else(|| BackendError::InvalidResponse("No path returned".to_string()))
+    /// Upload a file to Gradio backend, returns the server path.
+    ///
+    /// Content uploaded before by this client is not sent again.
+    fn gradio_upload(
+        &self,
+        audio_path: &Path,
+        request_id: Option<&str>,
+        progress: Option<&Progress>,
+    ) -> Result<String, BackendError> {
+        let (audio_data, file_name) = read_audio(audio_path)?;
+        let digest = format!("{:x}", Sha256::digest(&audio_data));
+        if let Some(path) = self.uploads.lock().unwrap().get(&digest) {
+            return Ok(path.clone());
+        }
+
+        let report = |pct| {
+            if let Some(progress) = progress {
+                progress.emit(SynthesisEvent::Uploading { pct });
+            }
+        };
+        report(0);
+        let upload = self
+            .protocol
+            .gradio_upload(audio_data, &file_name)
+            .with_request_id(request_id);
+        let response = self.send(upload)?;
+        let path = self.protocol.parse_upload(&response)?;
+        report(100);
+        self.uploads.lock().unwrap().insert(digest, path.clone());
+        Ok(path)
     }
 
     /// Call Gradio generate endpoint and wait for result.
@@ -83,177 +198,119 @@ impl HttpBackend {
         text: &str,
         audio_path: Option<&str>,
         transcript: Option<&str>,
+        request_id: Option<&str>,
+        progress: Option<&Progress>,
     ) -> Result<Vec<u8>, BackendError> {
-        let url = format!("{}/gradio_api/call/generate", self.base_url);
-
-        // Build the data array for Gradio
-        // Order: [target_text, prompt_audio, prompt_text, cfg, timesteps, normalize]
-        let audio_value = match audio_path {
-            Some(path) => serde_json::json!({
-                "path": path,
-                "meta": {"_type": "gradio.FileData"}
-            }),
-            None => serde_json::Value::Null,
-        };
-
-        let body = serde_json::json!({
-            "data": [
-                text,
-                audio_value,
-                transcript.unwrap_or(""),
-                2.0,  // CFG value
-                10,   // Inference timesteps
-                false // Text normalization
-            ]
-        });
-
-        let response = self
-            .client
-            .post(&url)
-            .json(&body)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Generate call failed: {}",
-                response.status()
-            )));
-        }
-
-        #[derive(serde::Deserialize)]
-        struct EventResponse {
-            event_id: String,
+        let request = self
+            .protocol
+            .gradio_generate(text, audio_path, transcript)
+            .with_request_id(request_id);
+        let event_id = self.protocol.parse_generate(&self.send(request)?)?;
+        if let Some(progress) = progress {
+            progress.emit(SynthesisEvent::Queued {
+                job_id: event_id.clone(),
+            });
         }
 
-        let event: EventResponse = response
-            .json()
-            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
-
-        // Poll for result with timeout
-        let poll_url = format!(
-            "{}/gradio_api/call/generate/{}",
-            self.base_url, event.event_id
-        );
-        let mut attempts = 0;
-        let max_attempts = 60; // 60 seconds max
-
-        loop {
+        let started = Instant::now();
+        for _ in 0..GRADIO_POLL_ATTEMPTS {
             thread::sleep(Duration::from_secs(1));
-            attempts += 1;
-
-            let poll_response = self
-                .client
-                .get(&poll_url)
-                .send()
-                .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-            let text = poll_response
-                .text()
-                .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
-
-            // Parse SSE response
-            if text.contains("event: complete") {
-                // Extract the data line
-                for line in text.lines() {
-                    if let Some(data) = line.strip_prefix("data: ") {
-                        let parsed: serde_json::Value = serde_json::from_str(data)
-                            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
-
-                        if let Some(url) = parsed
-                            .as_array()
-                            .and_then(|a| a.first())
-                            .and_then(|v| v.get("url"))
-                            .and_then(|u| u.as_str())
-                        {
-                            // Download the audio file
-                            return self.download_audio(url);
-                        }
-                    }
-                }
-                return Err(BackendError::InvalidResponse(
-                    "No audio URL in response".to_string(),
-                ));
-            }
 
-            if text.contains("event: error") {
-                return Err(BackendError::BackendError("Generation failed".to_string()));
-            }
-
-            if attempts >= max_attempts {
-                return Err(BackendError::RequestFailed(
-                    "Generation timed out".to_string(),
-                ));
+            let poll = self
+                .protocol
+                .gradio_poll(&event_id)
+                .with_request_id(request_id);
+            let response = self.wait(poll, progress.map(|p| (p, started)))?;
+            if let GradioPoll::Complete(url) =
+                self.protocol.parse_poll(&read_response(response, None)?)?
+            {
+                let download = self.protocol.download(&url).with_request_id(request_id);
+                let download = self.wait(download, None)?;
+                return self
+                    .protocol
+                    .parse_audio(read_response(download, progress)?);
             }
         }
+        Err(BackendError::RequestFailed(
+            "Generation timed out".to_string(),
+        ))
     }
+}
 
-    /// Download audio from URL.
-    fn download_audio(&self, url: &str) -> Result<Vec<u8>, BackendError> {
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Download failed: {}",
-                response.status()
-            )));
-        }
-
-        response
+/// Read a response body, reporting `Downloading` as it arrives.
+fn read_response(
+    mut response: reqwest::blocking::Response,
+    progress: Option<&Progress>,
+) -> Result<ApiResponse, BackendError> {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after);
+    let Some(progress) = progress else {
+        let body = response
             .bytes()
-            .map(|b| b.to_vec())
-            .map_err(|e| BackendError::InvalidResponse(e.to_string()))
+            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+        return Ok(ApiResponse {
+            status,
+            body: body.to_vec(),
+            retry_after,
+        });
+    };
+
+    let total = response.content_length().filter(|&n| n > 0);
+    let mut body = Vec::with_capacity(total.unwrap_or(0) as usize);
+    let mut buffer = vec![0; 64 * 1024];
+    let mut reported = 0;
+    progress.emit(SynthesisEvent::Downloading { pct: 0 });
+    loop {
+        let read = response
+            .read(&mut buffer)
+            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&buffer[..read]);
+        if let Some(total) = total {
+            let pct = (body.len() as u64 * 100 / total).min(99) as u8;
+            if pct > reported {
+                progress.emit(SynthesisEvent::Downloading { pct });
+                reported = pct;
+            }
+        }
     }
+    progress.emit(SynthesisEvent::Downloading { pct: 100 });
+    Ok(ApiResponse {
+        status,
+        body,
+        retry_after,
+    })
+}
+
+/// Read reference audio and its file name for upload.
+fn read_audio(audio_path: &Path) -> Result<(Vec<u8>, String), BackendError> {
+    let audio_data = std::fs::read(audio_path)
+        .map_err(|_| BackendError::FileNotFound(audio_path.display().to_string()))?;
+    Ok((audio_data, crate::paths::upload_name(audio_path)))
 }
 
 impl Backend for HttpBackend {
+    fn capabilities(&self) -> Capabilities {
+        self.protocol.capabilities()
+    }
+
     fn health(&self) -> Result<HealthResponse, BackendError> {
-        if self.model.is_gradio() {
-            // For Gradio backends, check /config endpoint
-            let url = format!("{}/config", self.base_url);
-            let response = self
-                .client
-                .get(&url)
-                .send()
-                .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-            if response.status().is_success() {
-                return Ok(HealthResponse {
-                    status: "healthy".to_string(),
-                    model: self.model.name().to_string(),
-                    cuda_available: true,
-                    gpu: None,
-                    device: "cuda".to_string(),
-                });
+        let response = self.send(self.protocol.health())?;
+        let mut health = self.protocol.parse_health(&response)?;
+        for request in self.protocol.device_info() {
+            if let Ok(response) = self.send(request)
+                && self.protocol.parse_device_info(&response, &mut health)
+            {
+                break;
             }
-            return Err(BackendError::RequestFailed(format!(
-                "Status: {}",
-                response.status()
-            )));
         }
-
-        let url = format!("{}/health", self.base_url);
-
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Status: {}",
-                response.status()
-            )));
-        }
-
-        response
-            .json()
-            .map_err(|e| BackendError::InvalidResponse(e.to_string()))
+        Ok(health)
     }
 
     fn extract_voice(
@@ -262,70 +319,24 @@ impl Backend for HttpBackend {
         transcript: &str,
         name: Option<String>,
     ) -> Result<VoiceInfo, BackendError> {
-        if self.model.is_gradio() {
-            // For Gradio backends, just verify the file exists
+        let (audio_data, file_name) = read_audio(audio_path)?;
+        match self
+            .protocol
+            .extract_voice(audio_data, &file_name, transcript, name.clone())
+        {
+            Some(request) => self.protocol.parse_voice(&self.send(request)?),
             // Voice cloning happens at synthesis time
-            if !audio_path.exists() {
-                return Err(BackendError::FileNotFound(audio_path.display().to_string()));
-            }
-
-            return Ok(VoiceInfo {
-                name: name.unwrap_or_else(|| "default".to_string()),
-                transcript: transcript.to_string(),
-                model: self.model.name().to_string(),
-                duration: None,
-            });
+            None => Ok(self.protocol.unsaved_voice(transcript, name)),
         }
-
-        let url = format!("{}/extract_voice", self.base_url);
-
-        // Read audio file
-        let audio_data = std::fs::read(audio_path)
-            .map_err(|_| BackendError::FileNotFound(audio_path.display().to_string()))?;
-
-        let file_name = audio_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("audio.wav");
-
-        // Build multipart form
-        let file_part = reqwest::blocking::multipart::Part::bytes(audio_data)
-            .file_name(file_name.to_string())
-            .mime_str("audio/wav")
-            .map_err(|e| BackendError::RequestFailed(e.to_string()))?;
-
-        let mut form = reqwest::blocking::multipart::Form::new()
-            .part("audio", file_part)
-            .text("transcript", transcript.to_string());
-
-        if let Some(n) = name {
-            form = form.text("name", n);
-        }
-
-        let response = self
-            .client
-            .post(&url)
-            .multipart(form)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Status: {}",
-                response.status()
-            )));
-        }
-
-        response
-            .json()
-            .map_err(|e| BackendError::InvalidResponse(e.to_string()))
     }
 
     fn synthesize(&self, request: &SynthesizeRequest) -> Result<Vec<u8>, BackendError> {
-        if self.model.is_gradio() {
+        if self.protocol.model().is_gradio() {
             // For Gradio backends, upload reference audio and generate
+            let progress = request.progress.as_ref();
+            let request_id = request.request_id.as_deref();
             let server_path = match &request.reference_audio {
-                Some(path) => Some(self.gradio_upload(path)?),
+                Some(path) => Some(self.gradio_upload(path, request_id, progress)?),
                 None => None,
             };
 
@@ -333,82 +344,66 @@ impl Backend for HttpBackend {
                 &request.text,
                 server_path.as_deref(),
                 request.reference_transcript.as_deref(),
+                request_id,
+                progress,
             );
         }
 
-        let url = format!("{}/synthesize", self.base_url);
-
-        let response = self
-            .client
-            .post(&url)
-            .json(request)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Status: {}",
-                response.status()
-            )));
+        // Tag the request so a cancelled run can stop it server-side
+        let job_id = request
+            .job_id
+            .clone()
+            .or_else(|| request.request_id.clone())
+            .unwrap_or_else(new_request_id);
+        let request = SynthesizeRequest {
+            job_id: Some(job_id),
+            ..request.clone()
+        };
+        let progress = request.progress.as_ref();
+        if let (Some(progress), Some(job_id)) = (progress, &request.job_id) {
+            progress.emit(SynthesisEvent::Queued {
+                job_id: job_id.clone(),
+            });
         }
-
-        response
-            .bytes()
-            .map(|b| b.to_vec())
-            .map_err(|e| BackendError::InvalidResponse(e.to_string()))
+        let started = progress.map(|p| (p, Instant::now()));
+        let synthesize = self
+            .protocol
+            .synthesize(&request)?
+            .with_request_id(request.request_id.as_deref());
+        let response = self.wait(synthesize, started)?;
+        self.protocol
+            .parse_audio(read_response(response, progress)?)
     }
 
     fn list_voices(&self) -> Result<VoicesResponse, BackendError> {
-        if self.model.is_gradio() {
-            // Gradio backends don't persist voices
-            return Ok(VoicesResponse { voices: vec![] });
+        match self.protocol.list_voices() {
+            Some(request) => self.protocol.parse_voices(&self.send(request)?),
+            None => Ok(VoicesResponse::default()),
         }
-
-        let url = format!("{}/voices", self.base_url);
-
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Status: {}",
-                response.status()
-            )));
-        }
-
-        response
-            .json()
-            .map_err(|e| BackendError::InvalidResponse(e.to_string()))
     }
 
     fn delete_voice(&self, name: &str) -> Result<(), BackendError> {
-        if self.model.is_gradio() {
-            // Gradio backends don't persist voices
-            return Err(BackendError::VoiceNotFound(name.to_string()));
-        }
-
-        let url = format!("{}/voices/{name}", self.base_url);
+        let response = self.send(self.protocol.delete_voice(name)?)?;
+        self.protocol.parse_delete(name, &response)
+    }
 
-        let response = self
-            .client
-            .delete(&url)
-            .send()
-            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
+    fn get_embedding(&self, name: &str) -> Result<EmbeddingResponse, BackendError> {
+        let response = self.send(self.protocol.get_embedding(name)?)?;
+        self.protocol.parse_embedding(name, &response)
+    }
 
-        if response.status().as_u16() == 404 {
-            return Err(BackendError::VoiceNotFound(name.to_string()));
-        }
+    fn cancel(&self, job_id: &str) -> Result<(), BackendError> {
+        let response = self.send(self.protocol.cancel(job_id))?;
+        self.protocol.parse_cancel(&response)
+    }
 
-        if !response.status().is_success() {
-            return Err(BackendError::RequestFailed(format!(
-                "Status: {}",
-                response.status()
-            )));
-        }
+    fn convert_voice(&self, audio: &[u8], voice: &str) -> Result<Vec<u8>, BackendError> {
+        let response = self.send(self.protocol.convert_voice(audio.to_vec(), voice)?)?;
+        self.protocol.parse_conversion(voice, response)
+    }
 
-        Ok(())
+    fn random_voice(&self, name: &str, seed: Option<u64>) -> Result<VoiceInfo, BackendError> {
+        let response = self.send(self.protocol.random_voice(name, seed)?)?;
+        self.protocol.parse_random_voice(name, &response)
     }
 }
@@ -0,0 +1,149 @@
+//! Chaining two backends: one generates speech, the other re-voices it.
+//!
+//! OpenF5 clones the atmosphere and delivery of a reference well, while
+//! OpenVoice's tone-color converter matches its timbre closely. A
+//! [`CompositeBackend`] generates with the first and converts the result
+//! with the second, so a voice must be extracted on both; extracting
+//! through the composite does that.
+
+use std::path::Path;
+
+use super::Backend;
+use super::types::{
+    BackendError, Capabilities, EmbeddingResponse, HealthResponse, SynthesizeRequest, VoiceInfo,
+    VoicesResponse,
+};
+
+/// A generator backend whose output is converted to the requested voice by
+/// a converter backend.
+#[derive(Debug, Clone)]
+pub struct CompositeBackend<G, C> {
+    generator: G,
+    converter: C,
+}
+
+impl<G: Backend, C: Backend> CompositeBackend<G, C> {
+    pub fn new(generator: G, converter: C) -> Self {
+        Self {
+            generator,
+            converter,
+        }
+    }
+
+    /// The backend generating base audio.
+    pub fn generator(&self) -> &G {
+        &self.generator
+    }
+
+    /// The backend converting it to the target voice.
+    pub fn converter(&self) -> &C {
+        &self.converter
+    }
+}
+
+impl<G: Backend, C: Backend> Backend for CompositeBackend<G, C> {
+    /// The generator's health, once the converter is also up.
+    fn health(&self) -> Result<HealthResponse, BackendError> {
+        let generator = self.generator.health()?;
+        let converter = self.converter.health()?;
+        let model_version = match (&generator.model_version, &converter.model_version) {
+            (None, None) => None,
+            (g, c) => Some(format!(
+                "{} + {}",
+                g.as_deref().unwrap_or("?"),
+                c.as_deref().unwrap_or("?")
+            )),
+        };
+        Ok(HealthResponse {
+            model: format!("{} + {}", generator.model, converter.model),
+            model_version,
+            ..generator
+        })
+    }
+
+    fn extract_voice(
+        &self,
+        audio_path: &Path,
+        transcript: &str,
+        name: Option<String>,
+    ) -> Result<VoiceInfo, BackendError> {
+        let info = self.generator.extract_voice(audio_path, transcript, name)?;
+        // Saved under the generator's name, which may have chosen one
+        let converted =
+            self.converter
+                .extract_voice(audio_path, transcript, Some(info.name.clone()))?;
+        Ok(VoiceInfo {
+            model: format!("{} + {}", info.model, converted.model),
+            ..info
+        })
+    }
+
+    /// Generate, then convert to the requested voice. Without a voice there
+    /// is nothing to convert to, and the generator's audio is returned.
+    fn synthesize(&self, request: &SynthesizeRequest) -> Result<Vec<u8>, BackendError> {
+        let audio = self.generator.synthesize(request)?;
+        match &request.voice_name {
+            Some(voice) => self.converter.convert_voice(&audio, voice),
+            None => Ok(audio),
+        }
+    }
+
+    /// Voices saved on both backends.
+    fn list_voices(&self) -> Result<VoicesResponse, BackendError> {
+        let mut voices = self.generator.list_voices()?;
+        let converted = self.converter.list_voices()?;
+        voices
+            .voices
+            .retain(|v| converted.voices.iter().any(|c| c.name == v.name));
+        Ok(voices)
+    }
+
+    /// Delete from both backends; a voice missing from one is still
+    /// deleted from the other.
+    fn delete_voice(&self, name: &str) -> Result<(), BackendError> {
+        match (
+            self.generator.delete_voice(name),
+            self.converter.delete_voice(name),
+        ) {
+            (Err(BackendError::VoiceNotFound(_)), Ok(()))
+            | (Ok(()), Err(BackendError::VoiceNotFound(_))) => Ok(()),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+            (Ok(()), Ok(())) => Ok(()),
+        }
+    }
+
+    /// The converter's embedding, which sets the timbre of the output.
+    fn get_embedding(&self, name: &str) -> Result<EmbeddingResponse, BackendError> {
+        self.converter.get_embedding(name)
+    }
+
+    /// What the generator supports, except streaming: conversion needs the
+    /// whole generation. Voices persist only if both backends keep them.
+    fn capabilities(&self) -> Capabilities {
+        let generator = self.generator.capabilities();
+        Capabilities {
+            streaming: false,
+            persistent_voices: generator.persistent_voices
+                && self.converter.capabilities().persistent_voices,
+            ..generator
+        }
+    }
+
+    fn cancel(&self, job_id: &str) -> Result<(), BackendError> {
+        self.generator.cancel(job_id)
+    }
+
+    fn convert_voice(&self, audio: &[u8], voice: &str) -> Result<Vec<u8>, BackendError> {
+        self.converter.convert_voice(audio, voice)
+    }
+
+    /// Unsupported: the generator could only clone the new voice from a
+    /// sample of it, so create it on the converter's model instead.
+    fn random_voice(&self, _name: &str, _seed: Option<u64>) -> Result<VoiceInfo, BackendError> {
+        Err(BackendError::Unsupported(
+            "random voices cannot be created for a chain; create one without --chain, \
+             then clone it from its sample"
+                .to_string(),
+        ))
+    }
+}
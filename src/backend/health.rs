@@ -0,0 +1,60 @@
+//! Waiting for a backend to come up.
+
+use std::time::{Duration, Instant};
+
+use super::{Backend, BackendError, HealthResponse};
+
+/// How [`wait_for_health`] polls a backend that is not ready yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthWait {
+    /// Longest to wait before giving up.
+    pub timeout: Duration,
+    /// Delay before the second check, doubled after each failed one.
+    pub initial_delay: Duration,
+    /// Longest delay between checks.
+    pub max_delay: Duration,
+}
+
+impl HealthWait {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Check `backend`'s health until it reports ready, backing off between
+/// checks, so a container still loading its models is waited for rather
+/// than failed. `waiting` is told each error and the delay before the next
+/// check. Errors no retry can fix, such as a rejected token, end the wait
+/// at once; otherwise the last error is returned when the timeout passes.
+pub fn wait_for_health<B: Backend + ?Sized>(
+    backend: &B,
+    wait: &HealthWait,
+    mut waiting: impl FnMut(&BackendError, Duration),
+) -> Result<HealthResponse, BackendError> {
+    let deadline = Instant::now() + wait.timeout;
+    let mut delay = wait.initial_delay;
+    loop {
+        let error = match backend.health() {
+            Ok(health) if health.is_ready() => return Ok(health),
+            Ok(health) => BackendError::ServerBusy {
+                message: format!("{} is {}", health.model, health.status),
+                retry_after: None,
+            },
+            Err(e) if e.is_permanent() => return Err(e),
+            Err(e) => e,
+        };
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(error);
+        }
+        let pause = error.retry_after().unwrap_or(delay).min(deadline - now);
+        waiting(&error, pause);
+        std::thread::sleep(pause);
+        delay = (delay * 2).min(wait.max_delay);
+    }
+}
@@ -0,0 +1,147 @@
+//! Transport-independent HTTP messages exchanged with the backend servers.
+//!
+//! [`Protocol`](super::Protocol) builds [`ApiRequest`]s and reads
+//! [`ApiResponse`]s; the clients only move them over the wire.
+
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+
+use super::types::BackendError;
+
+/// One part of a multipart form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    Text {
+        name: String,
+        value: String,
+    },
+    /// A WAV file.
+    Audio {
+        name: String,
+        file_name: String,
+        bytes: Vec<u8>,
+    },
+}
+
+/// Body of a request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Body {
+    Empty,
+    Json(serde_json::Value),
+    Multipart(Vec<Part>),
+}
+
+/// Header carrying the request ID of the synthesis a request belongs to.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// A request ready to be sent by any HTTP client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Body,
+}
+
+impl ApiRequest {
+    pub(super) fn get(url: String) -> Self {
+        Self {
+            method: Method::GET,
+            url,
+            headers: Vec::new(),
+            body: Body::Empty,
+        }
+    }
+
+    /// Name the synthesis this request belongs to in [`REQUEST_ID_HEADER`],
+    /// so it can be found in the server's logs.
+    pub fn with_request_id(mut self, request_id: Option<&str>) -> Self {
+        if let Some(id) = request_id {
+            self.headers
+                .push((REQUEST_ID_HEADER.to_string(), id.to_string()));
+        }
+        self
+    }
+}
+
+/// A response as received by any HTTP client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+    /// The `Retry-After` header, read with [`parse_retry_after`].
+    pub retry_after: Option<Duration>,
+}
+
+/// A `Retry-After` header value. Only the delay-seconds form is read; HTTP
+/// dates are ignored, since few model servers send them.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// State of a Gradio generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GradioPoll {
+    /// Still generating; poll again.
+    Pending,
+    /// Finished; the audio can be downloaded from this URL.
+    Complete(String),
+}
+
+/// Fail with the error `response` describes unless it succeeded.
+pub(super) fn check_status(response: &ApiResponse, context: &str) -> Result<(), BackendError> {
+    if response.status.is_success() {
+        return Ok(());
+    }
+    let message = match error_detail(&response.body) {
+        Some(detail) => format!("{context}: {}: {detail}", response.status),
+        None => format!("{context}: {}", response.status),
+    };
+    let retry_after = response.retry_after;
+    Err(match response.status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => BackendError::Unauthorized(message),
+        StatusCode::PAYLOAD_TOO_LARGE => BackendError::PayloadTooLarge(message),
+        StatusCode::TOO_MANY_REQUESTS => BackendError::RateLimited {
+            message,
+            retry_after,
+        },
+        StatusCode::SERVICE_UNAVAILABLE => BackendError::ServerBusy {
+            message,
+            retry_after,
+        },
+        _ => BackendError::RequestFailed(message),
+    })
+}
+
+/// The reason an error response gives: FastAPI's `detail` (a string, or a
+/// list of validation errors), an `error` string or object, or a short
+/// plain-text body.
+fn error_detail(body: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim();
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        // Proxies answer with HTML pages, which make poor messages
+        return (!text.is_empty() && text.len() <= 200 && !text.starts_with('<'))
+            .then(|| text.to_string());
+    };
+    let field = json.get("detail").or_else(|| json.get("error"))?;
+    let detail = match field {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(errors) => errors
+            .iter()
+            .map(|e| e.get("msg").and_then(|m| m.as_str()).unwrap_or("invalid"))
+            .collect::<Vec<_>>()
+            .join("; "),
+        serde_json::Value::Object(error) => error.get("message")?.as_str()?.to_string(),
+        _ => return None,
+    };
+    (!detail.is_empty()).then_some(detail)
+}
+
+/// Check `response` and parse its body as JSON.
+pub(super) fn parse_json<T: DeserializeOwned>(response: &ApiResponse) -> Result<T, BackendError> {
+    check_status(response, "Status")?;
+    serde_json::from_slice(&response.body).map_err(|e| BackendError::InvalidResponse(e.to_string()))
+}
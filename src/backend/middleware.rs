@@ -0,0 +1,97 @@
+//! Layers wrapped around a [`Backend`].
+//!
+//! [`RequestIds`] gives every synthesis a request ID. The HTTP backend sends
+//! it as `X-Request-Id`, the model servers log it, and errors name it, so a
+//! chunk that failed in a long batch can be found in the server's logs.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::Backend;
+use super::types::{
+    BackendError, Capabilities, EmbeddingResponse, HealthResponse, SynthesizeRequest, VoiceInfo,
+    VoicesResponse,
+};
+
+/// A request ID unique across processes and calls.
+pub fn new_request_id() -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "{}-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Assigns a request ID to every synthesis that lacks one and names it in
+/// any error the inner backend returns.
+#[derive(Debug, Clone)]
+pub struct RequestIds<B> {
+    inner: B,
+}
+
+impl<B: Backend> RequestIds<B> {
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+}
+
+impl<B: Backend> Backend for RequestIds<B> {
+    fn health(&self) -> Result<HealthResponse, BackendError> {
+        self.inner.health()
+    }
+
+    fn extract_voice(
+        &self,
+        audio_path: &Path,
+        transcript: &str,
+        name: Option<String>,
+    ) -> Result<VoiceInfo, BackendError> {
+        self.inner.extract_voice(audio_path, transcript, name)
+    }
+
+    fn synthesize(&self, request: &SynthesizeRequest) -> Result<Vec<u8>, BackendError> {
+        let request_id = request.request_id.clone().unwrap_or_else(new_request_id);
+        let request = SynthesizeRequest {
+            request_id: Some(request_id.clone()),
+            ..request.clone()
+        };
+        self.inner
+            .synthesize(&request)
+            .map_err(|e| e.with_request_id(&request_id))
+    }
+
+    fn list_voices(&self) -> Result<VoicesResponse, BackendError> {
+        self.inner.list_voices()
+    }
+
+    fn delete_voice(&self, name: &str) -> Result<(), BackendError> {
+        self.inner.delete_voice(name)
+    }
+
+    fn get_embedding(&self, name: &str) -> Result<EmbeddingResponse, BackendError> {
+        self.inner.get_embedding(name)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+
+    fn cancel(&self, job_id: &str) -> Result<(), BackendError> {
+        self.inner.cancel(job_id)
+    }
+
+    fn convert_voice(&self, audio: &[u8], voice: &str) -> Result<Vec<u8>, BackendError> {
+        self.inner.convert_voice(audio, voice)
+    }
+
+    fn random_voice(&self, name: &str, seed: Option<u64>) -> Result<VoiceInfo, BackendError> {
+        self.inner.random_voice(name, seed)
+    }
+}
@@ -1,20 +1,60 @@
 //! Backend communication with TTS model servers.
 //!
 //! Provides traits and implementations for communicating with the
-//! Docker-based TTS backends (OpenVoice V2 and OpenF5-TTS).
+//! Docker-based TTS backends (OpenVoice V2, OpenF5-TTS, and VoxCPM), and a
+//! [`BackendRegistry`] for selecting backends by name.
+//!
+//! Request building lives in the transport-agnostic [`Protocol`], shared by
+//! the blocking [`HttpBackend`] and the async [`WebBackend`]. Only the latter
+//! and the protocol are built for `wasm32`.
+//!
+//! Layers in [`middleware`] wrap any backend, such as [`RequestIds`] for
+//! tracing a synthesis through server logs. A [`CompositeBackend`] chains
+//! two backends, converting one model's speech to another's voice.
+//! [`wait_for_health`] polls a backend that is still starting until it is
+//! ready.
 
+#[cfg(not(target_arch = "wasm32"))]
 mod client;
+mod composite;
+#[cfg(not(target_arch = "wasm32"))]
+mod health;
+mod message;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod middleware;
+mod model;
+mod protocol;
+#[cfg(not(target_arch = "wasm32"))]
+mod registry;
+mod schema;
 mod types;
+mod web;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub use client::HttpBackend;
-pub use types::{BackendError, HealthResponse, SynthesizeRequest, VoiceInfo, VoicesResponse};
-
-use crate::cli::Model;
+pub use composite::CompositeBackend;
+#[cfg(not(target_arch = "wasm32"))]
+pub use health::{HealthWait, wait_for_health};
+pub use message::{
+    ApiRequest, ApiResponse, Body, GradioPoll, Part, REQUEST_ID_HEADER, parse_retry_after,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use middleware::RequestIds;
+pub use model::Model;
+pub use protocol::Protocol;
+#[cfg(not(target_arch = "wasm32"))]
+pub use registry::{BackendEntry, BackendRegistry, Connection};
+pub use types::{
+    BackendError, Capabilities, EmbeddingResponse, HealthResponse, Progress, SpeedRange,
+    SynthesisEvent, SynthesizeRequest, VoiceInfo, VoicesResponse,
+};
+pub use web::WebBackend;
 
 /// Trait for TTS backend communication.
 ///
 /// This trait abstracts the HTTP communication with the TTS servers,
-/// allowing for mock implementations in tests.
+/// allowing for mock implementations in tests. It is object-safe, so
+/// backends chosen at runtime can be used as [`DynBackend`].
 #[cfg_attr(test, mockall::automock)]
 pub trait Backend: Send + Sync {
     /// Check backend health status.
@@ -47,9 +87,149 @@ pub trait Backend: Send + Sync {
 
     /// Delete a saved voice.
     fn delete_voice(&self, name: &str) -> Result<(), BackendError>;
+
+    /// Fetch the speaker embedding of a saved voice.
+    ///
+    /// Returns [`BackendError::Unsupported`] for models that condition on
+    /// reference audio instead of an embedding.
+    fn get_embedding(&self, name: &str) -> Result<EmbeddingResponse, BackendError>;
+
+    /// Features this backend supports, so requests it cannot honor are
+    /// rejected before they are sent.
+    fn capabilities(&self) -> Capabilities;
+
+    /// Stop generating `job_id`, freeing the server's GPU.
+    ///
+    /// Job IDs are announced by [`SynthesisEvent::Queued`]. Cancelling a
+    /// job that already finished succeeds.
+    fn cancel(&self, job_id: &str) -> Result<(), BackendError>;
+
+    /// Convert existing speech to the tone color of saved voice `voice`.
+    ///
+    /// Returns [`BackendError::Unsupported`] for models without a
+    /// tone-color converter.
+    fn convert_voice(&self, audio: &[u8], voice: &str) -> Result<Vec<u8>, BackendError>;
+
+    /// Create and save a synthetic voice that imitates no real speaker.
+    /// The same `seed` draws the same voice.
+    ///
+    /// Returns [`BackendError::Unsupported`] for models that only clone.
+    fn random_voice(&self, name: &str, seed: Option<u64>) -> Result<VoiceInfo, BackendError>;
+}
+
+/// A backend chosen at runtime, as returned by [`BackendRegistry::connect`].
+pub type DynBackend = Box<dyn Backend>;
+
+/// A boxed backend, as returned by [`BackendRegistry::connect`].
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    fn health(&self) -> Result<HealthResponse, BackendError> {
+        (**self).health()
+    }
+
+    fn extract_voice(
+        &self,
+        audio_path: &std::path::Path,
+        transcript: &str,
+        name: Option<String>,
+    ) -> Result<VoiceInfo, BackendError> {
+        (**self).extract_voice(audio_path, transcript, name)
+    }
+
+    fn synthesize(&self, request: &SynthesizeRequest) -> Result<Vec<u8>, BackendError> {
+        (**self).synthesize(request)
+    }
+
+    fn list_voices(&self) -> Result<VoicesResponse, BackendError> {
+        (**self).list_voices()
+    }
+
+    fn delete_voice(&self, name: &str) -> Result<(), BackendError> {
+        (**self).delete_voice(name)
+    }
+
+    fn get_embedding(&self, name: &str) -> Result<EmbeddingResponse, BackendError> {
+        (**self).get_embedding(name)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        (**self).capabilities()
+    }
+
+    fn cancel(&self, job_id: &str) -> Result<(), BackendError> {
+        (**self).cancel(job_id)
+    }
+
+    fn convert_voice(&self, audio: &[u8], voice: &str) -> Result<Vec<u8>, BackendError> {
+        (**self).convert_voice(audio, voice)
+    }
+
+    fn random_voice(&self, name: &str, seed: Option<u64>) -> Result<VoiceInfo, BackendError> {
+        (**self).random_voice(name, seed)
+    }
+}
+
+/// A shared backend, so one connection can serve several engines.
+impl<B: Backend + ?Sized> Backend for std::sync::Arc<B> {
+    fn health(&self) -> Result<HealthResponse, BackendError> {
+        (**self).health()
+    }
+
+    fn extract_voice(
+        &self,
+        audio_path: &std::path::Path,
+        transcript: &str,
+        name: Option<String>,
+    ) -> Result<VoiceInfo, BackendError> {
+        (**self).extract_voice(audio_path, transcript, name)
+    }
+
+    fn synthesize(&self, request: &SynthesizeRequest) -> Result<Vec<u8>, BackendError> {
+        (**self).synthesize(request)
+    }
+
+    fn list_voices(&self) -> Result<VoicesResponse, BackendError> {
+        (**self).list_voices()
+    }
+
+    fn delete_voice(&self, name: &str) -> Result<(), BackendError> {
+        (**self).delete_voice(name)
+    }
+
+    fn get_embedding(&self, name: &str) -> Result<EmbeddingResponse, BackendError> {
+        (**self).get_embedding(name)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        (**self).capabilities()
+    }
+
+    fn cancel(&self, job_id: &str) -> Result<(), BackendError> {
+        (**self).cancel(job_id)
+    }
+
+    fn convert_voice(&self, audio: &[u8], voice: &str) -> Result<Vec<u8>, BackendError> {
+        (**self).convert_voice(audio, voice)
+    }
+
+    fn random_voice(&self, name: &str, seed: Option<u64>) -> Result<VoiceInfo, BackendError> {
+        (**self).random_voice(name, seed)
+    }
+}
+
+/// A mock backend that supports every feature, for engine tests.
+#[cfg(test)]
+pub(crate) fn mock_backend() -> MockBackend {
+    let mut mock = MockBackend::new();
+    mock.expect_capabilities()
+        .return_const(Capabilities::unrestricted());
+    mock
 }
 
 /// Create a backend for the specified model.
+///
+/// Prefer [`BackendRegistry`], which also covers registered third-party
+/// backends.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn create_backend(model: Model, host: &str) -> HttpBackend {
     HttpBackend::new(model, host)
 }
@@ -58,6 +238,7 @@ pub fn create_backend(model: Model, host: &str) -> HttpBackend {
 mod tests {
     use super::*;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     // ===========================================
     // Backend trait tests with mocks (TDD - RED phase)
@@ -71,9 +252,11 @@ mod tests {
             Ok(HealthResponse {
                 status: "healthy".to_string(),
                 model: "openvoice_v2".to_string(),
-                cuda_available: true,
+                model_version: None,
+                cuda_available: Some(true),
                 gpu: Some("NVIDIA RTX 5060".to_string()),
                 device: "cuda:0".to_string(),
+                raw: Default::default(),
             })
         });
 
@@ -82,7 +265,7 @@ mod tests {
 
         let health = result.unwrap();
         assert_eq!(health.status, "healthy");
-        assert!(health.cuda_available);
+        assert_eq!(health.cuda_available, Some(true));
     }
 
     #[test]
@@ -103,6 +286,89 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_wait_for_health_until_ready() {
+        let mut mock = MockBackend::new();
+        let mut seq = mockall::Sequence::new();
+        mock.expect_health()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| {
+                Err(BackendError::ConnectionFailed(
+                    "Connection refused".to_string(),
+                ))
+            });
+        mock.expect_health()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| {
+                Ok(HealthResponse {
+                    status: "loading".to_string(),
+                    model: "OpenF5-TTS".to_string(),
+                    ..Default::default()
+                })
+            });
+        mock.expect_health()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| {
+                Ok(HealthResponse {
+                    status: "healthy".to_string(),
+                    ..Default::default()
+                })
+            });
+
+        let wait = HealthWait {
+            timeout: Duration::from_secs(5),
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        };
+        let mut waits = Vec::new();
+        let health = wait_for_health(&mock, &wait, |e, delay| waits.push((e.to_string(), delay)));
+
+        assert_eq!(health.unwrap().status, "healthy");
+        assert_eq!(
+            waits,
+            vec![
+                (
+                    "Connection failed: Connection refused".to_string(),
+                    Duration::from_millis(1)
+                ),
+                (
+                    "Server busy: OpenF5-TTS is loading".to_string(),
+                    Duration::from_millis(2)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wait_for_health_gives_up() {
+        let mut mock = MockBackend::new();
+        mock.expect_health().returning(|| {
+            Err(BackendError::ConnectionFailed(
+                "Connection refused".to_string(),
+            ))
+        });
+        let wait = HealthWait {
+            timeout: Duration::from_millis(20),
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        };
+        let result = wait_for_health(&mock, &wait, |_, _| {});
+        assert!(matches!(result, Err(BackendError::ConnectionFailed(_))));
+
+        // A rejected token will not start working, so it is not waited out
+        let mut mock = MockBackend::new();
+        mock.expect_health()
+            .times(1)
+            .returning(|| Err(BackendError::Unauthorized("bad token".to_string())));
+        let result = wait_for_health(&mock, &HealthWait::new(Duration::from_secs(60)), |_, _| {
+            panic!("should not wait")
+        });
+        assert!(matches!(result, Err(BackendError::Unauthorized(_))));
+    }
+
     #[test]
     fn test_mock_backend_list_voices() {
         let mut mock = MockBackend::new();
@@ -115,14 +381,17 @@ mod tests {
                         transcript: "Hello world".to_string(),
                         model: "openvoice_v2".to_string(),
                         duration: None,
+                        raw: Default::default(),
                     },
                     VoiceInfo {
                         name: "another_voice".to_string(),
                         transcript: "Another sample".to_string(),
                         model: "openf5_tts".to_string(),
                         duration: Some(5.2),
+                        raw: Default::default(),
                     },
                 ],
+                raw: Default::default(),
             })
         });
 
@@ -151,6 +420,7 @@ mod tests {
                     transcript: "Hello world".to_string(),
                     model: "openvoice_v2".to_string(),
                     duration: Some(3.5),
+                    raw: Default::default(),
                 })
             });
 
@@ -183,8 +453,13 @@ mod tests {
             text: "Hello world".to_string(),
             voice_name: Some("my_voice".to_string()),
             speed: 1.0,
+            language: None,
             reference_audio: None,
             reference_transcript: None,
+            progress: None,
+            job_id: None,
+            request_id: None,
+            seed: None,
         };
 
         let result = mock.synthesize(&request);
@@ -239,4 +514,625 @@ mod tests {
         let backend = create_backend(Model::OpenF5, "localhost");
         assert_eq!(backend.base_url(), "http://localhost:9288");
     }
+
+    #[test]
+    fn test_gradio_backend_capabilities() {
+        let openvoice = HttpBackend::new(Model::OpenVoice, "localhost").capabilities();
+        assert!(openvoice.speed);
+        assert!(openvoice.persistent_voices);
+        assert!(openvoice.language && openvoice.seed);
+
+        let voxcpm = HttpBackend::new(Model::VoxCPM, "localhost").capabilities();
+        assert!(!voxcpm.speed);
+        assert!(!voxcpm.persistent_voices);
+        assert!(!voxcpm.language && !voxcpm.seed);
+    }
+
+    #[test]
+    fn test_http_backend_with_port() {
+        let backend = HttpBackend::with_port(Model::OpenVoice, "gpu-box", 19280);
+        assert_eq!(backend.base_url(), "http://gpu-box:19280");
+    }
+
+    #[test]
+    fn test_http_backend_with_token() {
+        assert!(
+            HttpBackend::new(Model::OpenVoice, "localhost")
+                .with_token("abc123")
+                .is_ok()
+        );
+        assert!(matches!(
+            HttpBackend::new(Model::OpenVoice, "localhost").with_token("bad\ntoken"),
+            Err(BackendError::RequestFailed(_))
+        ));
+    }
+
+    // ===========================================
+    // Registry tests
+    // ===========================================
+
+    #[test]
+    fn test_builtin_registry_lists_models() {
+        let registry = BackendRegistry::builtin();
+        let names: Vec<&str> = registry.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["ov", "of", "vc"]);
+
+        let voxcpm = registry.get("vc").unwrap();
+        assert_eq!(voxcpm.default_port, 8700);
+        assert!(!voxcpm.capabilities.persistent_voices);
+        assert!(registry.get("ov").unwrap().capabilities.persistent_voices);
+    }
+
+    #[test]
+    fn test_registry_connects_registered_backend() {
+        let mut registry = BackendRegistry::builtin();
+        registry.register(BackendEntry::new(
+            "mock",
+            "Test backend",
+            7000,
+            Capabilities::default(),
+            |connection| {
+                let port = connection.port;
+                let mut mock = MockBackend::new();
+                mock.expect_delete_voice()
+                    .returning(move |_| Err(BackendError::BackendError(port.to_string())));
+                Ok(Box::new(mock))
+            },
+        ));
+
+        let backend = registry
+            .connect("mock", "localhost", None, None, false)
+            .unwrap();
+        let err = backend.delete_voice("x").unwrap_err();
+        assert_eq!(err.to_string(), "Backend error: 7000");
+
+        let err = registry
+            .connect("nope", "localhost", None, None, false)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("available: ov, of, vc, mock"));
+    }
+
+    #[test]
+    fn test_register_replaces_same_name() {
+        let mut registry = BackendRegistry::builtin();
+        registry.register(BackendEntry::new(
+            "ov",
+            "Patched OpenVoice",
+            1234,
+            Capabilities::default(),
+            |_| Err(BackendError::Unsupported("test".to_string())),
+        ));
+
+        assert_eq!(registry.entries().len(), 3);
+        assert_eq!(registry.get("ov").unwrap().default_port, 1234);
+    }
+
+    // ===========================================
+    // Protocol tests
+    // ===========================================
+
+    fn response(status: u16, body: &str) -> ApiResponse {
+        ApiResponse {
+            status: reqwest::StatusCode::from_u16(status).unwrap(),
+            body: body.as_bytes().to_vec(),
+            retry_after: None,
+        }
+    }
+
+    #[test]
+    fn test_protocol_builds_requests() {
+        let protocol = Protocol::new(Model::OpenVoice, "http://gpu-box:9280/");
+        assert_eq!(protocol.base_url(), "http://gpu-box:9280");
+
+        let health = protocol.health();
+        assert_eq!(health.method, reqwest::Method::GET);
+        assert_eq!(health.url, "http://gpu-box:9280/health");
+
+        let mut request = SynthesizeRequest::new("Hello");
+        request.voice_name = Some("narrator".to_string());
+        let synth = protocol.synthesize(&request).unwrap();
+        assert_eq!(synth.method, reqwest::Method::POST);
+        let Body::Json(body) = synth.body else {
+            panic!("expected a JSON body");
+        };
+        assert_eq!(body["text"], "Hello");
+        assert_eq!(body["name"], "narrator");
+
+        let extract = protocol
+            .extract_voice(b"RIFF".to_vec(), "ref.wav", "hi", Some("me".to_string()))
+            .unwrap();
+        let Body::Multipart(parts) = extract.body else {
+            panic!("expected a multipart body");
+        };
+        assert_eq!(parts.len(), 3);
+        assert!(matches!(&parts[0], Part::Audio { file_name, .. } if file_name == "ref.wav"));
+
+        let delete = protocol.delete_voice("me").unwrap();
+        assert_eq!(delete.method, reqwest::Method::DELETE);
+        assert_eq!(delete.url, "http://gpu-box:9280/voices/me");
+    }
+
+    #[test]
+    fn test_protocol_parses_responses() {
+        let protocol = Protocol::new(Model::OpenF5, "http://localhost:9288");
+
+        let voices = protocol
+            .parse_voices(&response(
+                200,
+                r#"{"voices": [{"name": "a", "transcript": "t", "model": "of"}]}"#,
+            ))
+            .unwrap();
+        assert_eq!(voices.voices[0].name, "a");
+
+        let err = protocol.parse_voices(&response(500, "")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Request failed: Status: 500 Internal Server Error"
+        );
+        assert!(matches!(
+            protocol.parse_voices(&response(200, "not json")),
+            Err(BackendError::InvalidResponse(_))
+        ));
+        assert!(matches!(
+            protocol.parse_delete("a", &response(404, "")),
+            Err(BackendError::VoiceNotFound(name)) if name == "a"
+        ));
+        assert!(matches!(
+            protocol.parse_embedding("a", &response(501, "")),
+            Err(BackendError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_protocol_gradio_flow() {
+        let protocol = Protocol::new(Model::VoxCPM, "http://localhost:8700");
+
+        assert_eq!(protocol.health().url, "http://localhost:8700/config");
+        assert!(protocol.parse_health(&response(200, "{}")).is_ok());
+        assert!(protocol.list_voices().is_none());
+        assert!(
+            protocol
+                .extract_voice(Vec::new(), "ref.wav", "hi", None)
+                .is_none()
+        );
+        assert!(protocol.get_embedding("a").is_err());
+
+        let upload = protocol.parse_upload(&response(200, r#"["/tmp/gradio/ref.wav"]"#));
+        assert_eq!(upload.unwrap(), "/tmp/gradio/ref.wav");
+
+        let generate = protocol.gradio_generate("Hi", Some("/tmp/gradio/ref.wav"), None);
+        let Body::Json(body) = generate.body else {
+            panic!("expected a JSON body");
+        };
+        assert_eq!(body["data"][1]["path"], "/tmp/gradio/ref.wav");
+
+        let event = protocol.parse_generate(&response(200, r#"{"event_id": "e1"}"#));
+        assert_eq!(event.unwrap(), "e1");
+        assert_eq!(
+            protocol.gradio_poll("e1").url,
+            "http://localhost:8700/gradio_api/call/generate/e1"
+        );
+
+        let pending = protocol.parse_poll(&response(200, "event: heartbeat\ndata: null\n"));
+        assert_eq!(pending.unwrap(), GradioPoll::Pending);
+        let complete = protocol.parse_poll(&response(
+            200,
+            "event: complete\ndata: [{\"url\": \"http://localhost:8700/file=out.wav\"}]\n",
+        ));
+        assert_eq!(
+            complete.unwrap(),
+            GradioPoll::Complete("http://localhost:8700/file=out.wav".to_string())
+        );
+        assert!(matches!(
+            protocol.parse_poll(&response(200, "event: error\ndata: null\n")),
+            Err(BackendError::BackendError(_))
+        ));
+    }
+
+    #[test]
+    fn test_protocol_gradio_device_info() {
+        let protocol = Protocol::new(Model::VoxCPM, "http://localhost:8700");
+        let mut health = protocol.parse_health(&response(200, "{}")).unwrap();
+        assert_eq!(health.device, "unknown");
+        assert_eq!(health.cuda_available, None);
+
+        let urls: Vec<_> = protocol.device_info().into_iter().map(|r| r.url).collect();
+        assert_eq!(
+            urls,
+            [
+                "http://localhost:8700/sysinfo",
+                "http://localhost:8700/info"
+            ]
+        );
+
+        // Gradio's own /info lists endpoints, not hardware
+        let info = response(200, r#"{"named_endpoints": {}, "unnamed_endpoints": {}}"#);
+        assert!(!protocol.parse_device_info(&info, &mut health));
+        assert!(!protocol.parse_device_info(&response(404, "{}"), &mut health));
+        assert_eq!(health.device, "unknown");
+
+        let sysinfo = response(
+            200,
+            r#"{"cuda_available": false, "device": "cpu", "torch": "2.4"}"#,
+        );
+        assert!(protocol.parse_device_info(&sysinfo, &mut health));
+        assert_eq!(health.cuda_available, Some(false));
+        assert_eq!(health.device, "cpu");
+        assert_eq!(health.gpu, None);
+
+        let openvoice = Protocol::new(Model::OpenVoice, "http://localhost:9280");
+        assert!(openvoice.device_info().is_empty());
+    }
+
+    #[test]
+    fn test_protocol_classifies_errors() {
+        let protocol = Protocol::new(Model::OpenVoice, "http://localhost:9280");
+        let failed =
+            |status, body: &str| protocol.parse_health(&response(status, body)).unwrap_err();
+
+        let err = failed(500, r#"{"detail": "CUDA out of memory"}"#);
+        assert_eq!(
+            err.to_string(),
+            "Request failed: Status: 500 Internal Server Error: CUDA out of memory"
+        );
+        assert!(err.is_overload());
+        let err = failed(422, r#"{"detail": [{"msg": "field required"}]}"#);
+        assert!(
+            err.to_string()
+                .ends_with("Unprocessable Entity: field required")
+        );
+        let err = failed(502, "<html><body>Bad Gateway</body></html>");
+        assert_eq!(err.to_string(), "Request failed: Status: 502 Bad Gateway");
+
+        assert!(matches!(
+            failed(401, r#"{"error": "bad token"}"#),
+            BackendError::Unauthorized(m) if m.ends_with("bad token")
+        ));
+        assert!(matches!(failed(413, ""), BackendError::PayloadTooLarge(_)));
+        assert!(failed(413, "").is_permanent());
+        assert!(!failed(502, "").is_overload());
+
+        let mut busy = response(503, r#"{"error": {"message": "loading model"}}"#);
+        busy.retry_after = parse_retry_after(" 30 ");
+        let err = protocol.parse_health(&busy).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Server busy: Status: 503 Service Unavailable: loading model"
+        );
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(30)));
+        assert!(err.is_overload());
+        let err = err.with_request_id("r1");
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(30)));
+
+        assert!(matches!(
+            failed(429, "slow down"),
+            BackendError::RateLimited { message, retry_after: None } if message.ends_with("slow down")
+        ));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[test]
+    fn test_protocol_tolerates_schema_drift() {
+        let protocol = Protocol::new(Model::OpenVoice, "http://localhost:9280");
+        let body = r#"{"status": "ok", "model": "ov", "device": "cuda:0", "vram_mb": 8192}"#;
+
+        let health = protocol.parse_health(&response(200, body)).unwrap();
+        assert_eq!(health.cuda_available, None);
+        assert_eq!(health.raw["vram_mb"], 8192);
+        // Unknown fields are passed through, e.g. by serve mode's /health
+        assert_eq!(serde_json::to_value(&health).unwrap()["vram_mb"], 8192);
+
+        let voices = r#"{"voices": [{"name": "amy", "language": "EN"}], "total": 1}"#;
+        let voices = protocol.parse_voices(&response(200, voices)).unwrap();
+        assert_eq!(voices.voices[0].transcript, "");
+        assert_eq!(voices.voices[0].raw["language"], "EN");
+        assert_eq!(voices.raw["total"], 1);
+
+        let strict = protocol.with_strict(true);
+        let err = strict.parse_health(&response(200, body)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid response: schema mismatch: missing cuda_available, missing gpu, unknown vram_mb"
+        );
+        let voices =
+            r#"{"voices": [{"name": "amy", "transcript": "Hi", "model": "ov", "age": 3}]}"#;
+        let err = strict.parse_voices(&response(200, voices)).unwrap_err();
+        assert!(err.to_string().ends_with("unknown voices[0].age"));
+        let voice = r#"{"name": "amy", "transcript": "Hi", "model": "ov", "duration": 2.5}"#;
+        assert!(strict.parse_voice(&response(200, voice)).is_ok());
+    }
+
+    #[test]
+    fn test_web_backend_shares_protocol() {
+        let web = WebBackend::new(Model::VoxCPM, "http://localhost:8700");
+        let http = HttpBackend::new(Model::VoxCPM, "localhost");
+
+        assert_eq!(web.base_url(), http.base_url());
+        assert_eq!(web.capabilities(), http.capabilities());
+        assert!(web.with_token("bad\ntoken").is_err());
+    }
+
+    // ===========================================
+    // Progress tests
+    // ===========================================
+
+    #[test]
+    fn test_http_backend_reports_generation_and_download() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        std::thread::spawn(move || {
+            let request = server.recv().unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(1200));
+            request
+                .respond(tiny_http::Response::from_data(vec![7u8; 300_000]))
+                .unwrap();
+        });
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let mut request = SynthesizeRequest::new("Hello");
+        request.progress = Some(Progress::new(move |event| seen.lock().unwrap().push(event)));
+
+        let backend = HttpBackend::with_port(Model::OpenVoice, "127.0.0.1", port);
+        let audio = backend.synthesize(&request).unwrap();
+        assert_eq!(audio.len(), 300_000);
+
+        let events = events.lock().unwrap();
+        let generating = events
+            .iter()
+            .filter(|e| matches!(e, SynthesisEvent::Generating { .. }))
+            .count();
+        assert!(generating >= 2, "{events:?}");
+        assert!(matches!(
+            events.first(),
+            Some(SynthesisEvent::Queued { .. })
+        ));
+        assert!(events.contains(&SynthesisEvent::Downloading { pct: 0 }));
+        assert_eq!(
+            events.last(),
+            Some(&SynthesisEvent::Downloading { pct: 100 })
+        );
+    }
+
+    #[test]
+    fn test_protocol_cancel_requests() {
+        let protocol = Protocol::new(Model::OpenF5, "http://localhost:9288");
+        let cancel = protocol.cancel("job-1");
+        assert_eq!(cancel.method, reqwest::Method::POST);
+        assert_eq!(cancel.url, "http://localhost:9288/cancel/job-1");
+        assert!(protocol.parse_cancel(&response(200, "{}")).is_ok());
+        assert!(protocol.parse_cancel(&response(404, "")).is_ok());
+        assert!(protocol.parse_cancel(&response(500, "")).is_err());
+
+        let gradio = Protocol::new(Model::VoxCPM, "http://localhost:8700");
+        let cancel = gradio.cancel("e1");
+        assert_eq!(cancel.url, "http://localhost:8700/gradio_api/cancel");
+        assert_eq!(
+            cancel.body,
+            Body::Json(serde_json::json!({"event_id": "e1"}))
+        );
+    }
+
+    #[test]
+    fn test_http_backend_cancels_announced_job() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let mut synthesize = server.recv().unwrap();
+            let mut body = String::new();
+            synthesize.as_reader().read_to_string(&mut body).unwrap();
+            synthesize
+                .respond(tiny_http::Response::from_data(b"RIFF".to_vec()))
+                .unwrap();
+
+            let cancel = server.recv().unwrap();
+            let url = cancel.url().to_string();
+            cancel.respond(tiny_http::Response::empty(200)).unwrap();
+
+            let sent: serde_json::Value = serde_json::from_str(&body).unwrap();
+            (sent["job_id"].as_str().unwrap().to_string(), url)
+        });
+
+        let queued = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let seen = queued.clone();
+        let mut request = SynthesizeRequest::new("Hello");
+        request.progress = Some(Progress::new(move |event| {
+            if let SynthesisEvent::Queued { job_id } = event {
+                *seen.lock().unwrap() = Some(job_id);
+            }
+        }));
+
+        let backend = HttpBackend::with_port(Model::OpenF5, "127.0.0.1", port);
+        backend.synthesize(&request).unwrap();
+        let job_id = queued.lock().unwrap().clone().unwrap();
+        backend.cancel(&job_id).unwrap();
+
+        let (sent, url) = handle.join().unwrap();
+        assert_eq!(sent, job_id);
+        assert_eq!(url, format!("/cancel/{job_id}"));
+    }
+
+    #[test]
+    fn test_request_ids_assigns_id_and_tags_errors() {
+        let mut mock = mock_backend();
+        mock.expect_synthesize()
+            .withf(|req| req.request_id.is_some())
+            .times(1)
+            .returning(|_| {
+                Err(BackendError::RequestFailed(
+                    "CUDA out of memory".to_string(),
+                ))
+            });
+
+        let backend = RequestIds::new(mock);
+        let err = backend
+            .synthesize(&SynthesizeRequest::new("Hello"))
+            .unwrap_err();
+        let BackendError::RequestFailed(message) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert!(message.starts_with("CUDA out of memory (request "));
+    }
+
+    #[test]
+    fn test_request_ids_keeps_callers_id() {
+        let mut mock = mock_backend();
+        mock.expect_synthesize()
+            .withf(|req| req.request_id.as_deref() == Some("chunk-7"))
+            .times(1)
+            .returning(|_| Err(BackendError::VoiceNotFound("narrator".to_string())));
+
+        let mut request = SynthesizeRequest::new("Hello");
+        request.request_id = Some("chunk-7".to_string());
+        let err = RequestIds::new(mock).synthesize(&request).unwrap_err();
+        // The voice name is the payload, so it is not tagged
+        assert!(matches!(err, BackendError::VoiceNotFound(name) if name == "narrator"));
+    }
+
+    #[test]
+    fn test_http_backend_sends_request_id_header() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let header = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv(REQUEST_ID_HEADER))
+                .map(|h| h.value.to_string());
+            request
+                .respond(tiny_http::Response::from_data(b"RIFF".to_vec()))
+                .unwrap();
+            header
+        });
+
+        let mut request = SynthesizeRequest::new("Hello");
+        request.request_id = Some("chunk-7".to_string());
+        HttpBackend::with_port(Model::OpenVoice, "127.0.0.1", port)
+            .synthesize(&request)
+            .unwrap();
+
+        assert_eq!(handle.join().unwrap().as_deref(), Some("chunk-7"));
+    }
+
+    #[test]
+    fn test_protocol_convert_voice() {
+        let protocol = Protocol::new(Model::OpenVoice, "http://gpu-box:9280");
+        let convert = protocol
+            .convert_voice(b"RIFF".to_vec(), "narrator")
+            .unwrap();
+        assert_eq!(convert.url, "http://gpu-box:9280/convert");
+        let Body::Multipart(parts) = convert.body else {
+            panic!("expected a multipart body");
+        };
+        assert!(matches!(&parts[1], Part::Text { value, .. } if value == "narrator"));
+        assert!(matches!(
+            protocol.parse_conversion("narrator", response(404, "{}")),
+            Err(BackendError::VoiceNotFound(_))
+        ));
+
+        let protocol = Protocol::new(Model::OpenF5, "http://gpu-box:9288");
+        assert!(matches!(
+            protocol.convert_voice(b"RIFF".to_vec(), "narrator"),
+            Err(BackendError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_composite_backend_converts_generated_audio() {
+        let mut generator = MockBackend::new();
+        generator
+            .expect_synthesize()
+            .withf(|req| req.voice_name.as_deref() == Some("narrator"))
+            .times(1)
+            .returning(|_| Ok(b"base".to_vec()));
+        generator.expect_capabilities().returning(|| Capabilities {
+            seed: false,
+            ..Capabilities::unrestricted()
+        });
+        let mut converter = MockBackend::new();
+        converter
+            .expect_convert_voice()
+            .withf(|audio, voice| audio == b"base" && voice == "narrator")
+            .times(1)
+            .returning(|_, _| Ok(b"converted".to_vec()));
+        converter
+            .expect_capabilities()
+            .return_const(Capabilities::unrestricted());
+
+        let backend = CompositeBackend::new(generator, converter);
+        let mut request = SynthesizeRequest::new("Hello");
+        request.voice_name = Some("narrator".to_string());
+        assert_eq!(backend.synthesize(&request).unwrap(), b"converted");
+
+        let capabilities = backend.capabilities();
+        assert!(!capabilities.streaming);
+        assert!(!capabilities.seed);
+        assert!(capabilities.persistent_voices);
+    }
+
+    #[test]
+    fn test_composite_backend_extracts_on_both() {
+        let voice = |model: &str| VoiceInfo {
+            name: "narrator".to_string(),
+            transcript: "Hello".to_string(),
+            model: model.to_string(),
+            duration: None,
+            raw: Default::default(),
+        };
+        let mut generator = MockBackend::new();
+        generator
+            .expect_extract_voice()
+            .times(1)
+            .returning(move |_, _, _| Ok(voice("OpenF5-TTS")));
+        generator
+            .expect_delete_voice()
+            .returning(|name| Err(BackendError::VoiceNotFound(name.to_string())));
+        let mut converter = MockBackend::new();
+        converter
+            .expect_extract_voice()
+            .withf(|_, _, name| name.as_deref() == Some("narrator"))
+            .times(1)
+            .returning(move |_, _, _| Ok(voice("OpenVoice V2")));
+        converter.expect_delete_voice().returning(|_| Ok(()));
+
+        let backend = CompositeBackend::new(generator, converter);
+        let info = backend
+            .extract_voice(PathBuf::from("ref.wav").as_path(), "Hello", None)
+            .unwrap();
+        assert_eq!(info.name, "narrator");
+        assert_eq!(info.model, "OpenF5-TTS + OpenVoice V2");
+
+        // Missing from one backend only still counts as deleted
+        backend.delete_voice("narrator").unwrap();
+    }
+
+    #[test]
+    fn test_protocol_random_voice() {
+        let protocol = Protocol::new(Model::OpenVoice, "http://gpu-box:9280");
+        let random = protocol.random_voice("stranger1", Some(7)).unwrap();
+        assert_eq!(random.url, "http://gpu-box:9280/voices/random");
+        let Body::Json(body) = random.body else {
+            panic!("expected a JSON body");
+        };
+        assert_eq!(body["name"], "stranger1");
+        assert_eq!(body["seed"], 7);
+
+        let voice = protocol
+            .parse_random_voice("stranger1", &response(200, r#"{"name":"stranger1"}"#))
+            .unwrap();
+        assert_eq!(voice.name, "stranger1");
+        let err = protocol
+            .parse_random_voice("stranger1", &response(409, "{}"))
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+
+        for model in [Model::OpenF5, Model::VoxCPM] {
+            assert!(matches!(
+                Protocol::new(model, "http://gpu-box").random_voice("stranger1", None),
+                Err(BackendError::Unsupported(_))
+            ));
+        }
+    }
 }
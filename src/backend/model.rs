@@ -0,0 +1,89 @@
+//! Model servers the built-in backends talk to.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// TTS model selection.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Model {
+    /// OpenVoice V2 (MIT license, fast)
+    #[default]
+    #[value(name = "ov")]
+    #[serde(rename = "ov")]
+    OpenVoice,
+
+    /// OpenF5-TTS (Apache 2.0, atmospheric cloning)
+    #[value(name = "of")]
+    #[serde(rename = "of")]
+    OpenF5,
+
+    /// VoxCPM (end-to-end TTS from ModelBest)
+    #[value(name = "vc")]
+    #[serde(rename = "vc")]
+    VoxCPM,
+}
+
+impl Model {
+    /// Returns the CLI argument string for this model.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Model::OpenVoice => "ov",
+            Model::OpenF5 => "of",
+            Model::VoxCPM => "vc",
+        }
+    }
+
+    /// Returns the backend server port for this model.
+    pub fn port(&self) -> u16 {
+        match self {
+            Model::OpenVoice => 9280,
+            Model::OpenF5 => 9288,
+            Model::VoxCPM => 8700,
+        }
+    }
+
+    /// Returns the human-readable name of the model.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Model::OpenVoice => "OpenVoice V2",
+            Model::OpenF5 => "OpenF5-TTS",
+            Model::VoxCPM => "VoxCPM",
+        }
+    }
+
+    /// SPDX identifier of the model's license.
+    pub fn license(&self) -> &'static str {
+        match self {
+            Model::OpenVoice => "MIT",
+            Model::OpenF5 | Model::VoxCPM => "Apache-2.0",
+        }
+    }
+
+    /// Longest text sent to this model's server in one request, in
+    /// characters. Longer text is split at sentence boundaries; the
+    /// OpenVoice and OpenF5 servers batch long text themselves, while
+    /// VoxCPM degrades on long inputs.
+    pub fn max_text_length(&self) -> usize {
+        match self {
+            Model::OpenVoice | Model::OpenF5 => 2000,
+            Model::VoxCPM => 500,
+        }
+    }
+
+    /// Returns true if this model can convert speech from other models to
+    /// one of its voices.
+    pub fn converts_voices(&self) -> bool {
+        matches!(self, Model::OpenVoice)
+    }
+
+    /// Returns true if this model's voices are embeddings that can be drawn
+    /// at random rather than cloned.
+    pub fn random_voices(&self) -> bool {
+        matches!(self, Model::OpenVoice)
+    }
+
+    /// Returns true if this model uses Gradio API.
+    pub fn is_gradio(&self) -> bool {
+        matches!(self, Model::VoxCPM)
+    }
+}
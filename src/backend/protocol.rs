@@ -0,0 +1,496 @@
+//! Requests and responses of the backend servers, independent of transport.
+//!
+//! [`Protocol`] builds the request for each backend operation and interprets
+//! the response, but sends nothing and touches no files. The blocking
+//! [`HttpBackend`](super::HttpBackend) and the async
+//! [`WebBackend`](super::WebBackend) (which uses `fetch` in the browser) both
+//! send its requests, so native and web clients speak exactly the same
+//! protocol.
+
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+
+use super::Model;
+use super::message::{ApiRequest, ApiResponse, Body, GradioPoll, Part, check_status, parse_json};
+use super::schema::Schema;
+use super::types::{
+    BackendError, Capabilities, EmbeddingResponse, HealthResponse, SpeedRange, SynthesizeRequest,
+    UNKNOWN_DEVICE, VoiceInfo, VoicesResponse,
+};
+
+/// Times a Gradio generation is polled before giving up.
+pub const GRADIO_POLL_ATTEMPTS: u32 = 60;
+
+/// Request building and response parsing for one model server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Protocol {
+    model: Model,
+    base_url: String,
+    strict: bool,
+}
+
+impl Protocol {
+    /// Speak to the server for `model` at `base_url` (e.g. `http://gpu-box:9280`).
+    pub fn new(model: Model, base_url: impl Into<String>) -> Self {
+        Self {
+            model,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            strict: false,
+        }
+    }
+
+    /// Reject health and voice responses with missing or unknown fields,
+    /// for developers keeping a server in step with this client. By default
+    /// missing fields take their defaults and unknown ones are kept in `raw`.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// What the server supports.
+    pub fn capabilities(&self) -> Capabilities {
+        model_capabilities(self.model)
+    }
+
+    pub fn health(&self) -> ApiRequest {
+        if self.model.is_gradio() {
+            // Gradio servers have no health route; /config answers when up
+            return ApiRequest::get(format!("{}/config", self.base_url));
+        }
+        ApiRequest::get(format!("{}/health", self.base_url))
+    }
+
+    pub fn parse_health(&self, response: &ApiResponse) -> Result<HealthResponse, BackendError> {
+        if self.model.is_gradio() {
+            check_status(response, "Status")?;
+            return Ok(HealthResponse {
+                status: "healthy".to_string(),
+                model: self.model.name().to_string(),
+                model_version: None,
+                cuda_available: None,
+                gpu: None,
+                device: UNKNOWN_DEVICE.to_string(),
+                raw: Default::default(),
+            });
+        }
+        self.parse_schema(response)
+    }
+
+    /// Routes that may describe the server's device, tried in order after
+    /// [`Protocol::health`]. Only Gradio servers need them: the bundled ones
+    /// may add a `/sysinfo` route, and some apps extend Gradio's `/info`.
+    pub fn device_info(&self) -> Vec<ApiRequest> {
+        if !self.model.is_gradio() {
+            return Vec::new();
+        }
+        ["sysinfo", "info"]
+            .iter()
+            .map(|route| ApiRequest::get(format!("{}/{route}", self.base_url)))
+            .collect()
+    }
+
+    /// Fill in `health` from a [`Protocol::device_info`] response. Returns
+    /// false, leaving `health` alone, when the response has no device fields.
+    pub fn parse_device_info(&self, response: &ApiResponse, health: &mut HealthResponse) -> bool {
+        #[derive(Deserialize)]
+        struct DeviceInfo {
+            cuda_available: Option<bool>,
+            gpu: Option<String>,
+            device: Option<String>,
+        }
+
+        if !response.status.is_success() {
+            return false;
+        }
+        let Ok(info) = serde_json::from_slice::<DeviceInfo>(&response.body) else {
+            return false;
+        };
+        if info.cuda_available.is_none() && info.gpu.is_none() && info.device.is_none() {
+            return false;
+        }
+        health.cuda_available = info.cuda_available.or(health.cuda_available);
+        health.gpu = info.gpu.or(health.gpu.take());
+        if let Some(device) = info.device {
+            health.device = device;
+        }
+        true
+    }
+
+    /// Extract a voice from reference audio.
+    ///
+    /// Returns `None` for Gradio servers, which clone at synthesis time; use
+    /// [`Protocol::unsaved_voice`] instead.
+    pub fn extract_voice(
+        &self,
+        audio: Vec<u8>,
+        file_name: &str,
+        transcript: &str,
+        name: Option<String>,
+    ) -> Option<ApiRequest> {
+        if self.model.is_gradio() {
+            return None;
+        }
+
+        let mut parts = vec![
+            Part::Audio {
+                name: "audio".to_string(),
+                file_name: file_name.to_string(),
+                bytes: audio,
+            },
+            Part::Text {
+                name: "transcript".to_string(),
+                value: transcript.to_string(),
+            },
+        ];
+        if let Some(name) = name {
+            parts.push(Part::Text {
+                name: "name".to_string(),
+                value: name,
+            });
+        }
+
+        Some(ApiRequest {
+            method: Method::POST,
+            url: format!("{}/extract_voice", self.base_url),
+            headers: Vec::new(),
+            body: Body::Multipart(parts),
+        })
+    }
+
+    /// The voice a Gradio server "extracts": nothing is saved server-side.
+    pub fn unsaved_voice(&self, transcript: &str, name: Option<String>) -> VoiceInfo {
+        VoiceInfo {
+            name: name.unwrap_or_else(|| "default".to_string()),
+            transcript: transcript.to_string(),
+            model: self.model.name().to_string(),
+            duration: None,
+            raw: Default::default(),
+        }
+    }
+
+    pub fn parse_voice(&self, response: &ApiResponse) -> Result<VoiceInfo, BackendError> {
+        self.parse_schema(response)
+    }
+
+    /// Synthesize with a saved voice. Gradio servers use
+    /// [`Protocol::gradio_generate`] instead.
+    pub fn synthesize(&self, request: &SynthesizeRequest) -> Result<ApiRequest, BackendError> {
+        let body = serde_json::to_value(request)
+            .map_err(|e| BackendError::RequestFailed(e.to_string()))?;
+        Ok(ApiRequest {
+            method: Method::POST,
+            url: format!("{}/synthesize", self.base_url),
+            headers: Vec::new(),
+            body: Body::Json(body),
+        })
+    }
+
+    /// WAV bytes from a synthesis or download response.
+    pub fn parse_audio(&self, response: ApiResponse) -> Result<Vec<u8>, BackendError> {
+        check_status(&response, "Status")?;
+        Ok(response.body)
+    }
+
+    /// List saved voices; `None` for Gradio servers, which save none.
+    pub fn list_voices(&self) -> Option<ApiRequest> {
+        (!self.model.is_gradio()).then(|| ApiRequest::get(format!("{}/voices", self.base_url)))
+    }
+
+    pub fn parse_voices(&self, response: &ApiResponse) -> Result<VoicesResponse, BackendError> {
+        self.parse_schema(response)
+    }
+
+    fn parse_schema<T: Schema>(&self, response: &ApiResponse) -> Result<T, BackendError> {
+        if !self.strict {
+            return parse_json(response);
+        }
+        let value: serde_json::Value = parse_json(response)?;
+        let drift = T::drift(&value, "");
+        if !drift.is_empty() {
+            return Err(BackendError::InvalidResponse(format!(
+                "schema mismatch: {}",
+                drift.join(", ")
+            )));
+        }
+        serde_json::from_value(value).map_err(|e| BackendError::InvalidResponse(e.to_string()))
+    }
+
+    pub fn delete_voice(&self, name: &str) -> Result<ApiRequest, BackendError> {
+        if self.model.is_gradio() {
+            // Gradio backends don't persist voices
+            return Err(BackendError::VoiceNotFound(name.to_string()));
+        }
+        Ok(ApiRequest {
+            method: Method::DELETE,
+            url: format!("{}/voices/{name}", self.base_url),
+            headers: Vec::new(),
+            body: Body::Empty,
+        })
+    }
+
+    pub fn parse_delete(&self, name: &str, response: &ApiResponse) -> Result<(), BackendError> {
+        if response.status == StatusCode::NOT_FOUND {
+            return Err(BackendError::VoiceNotFound(name.to_string()));
+        }
+        check_status(response, "Status")
+    }
+
+    pub fn get_embedding(&self, name: &str) -> Result<ApiRequest, BackendError> {
+        if self.model.is_gradio() {
+            return Err(self.no_embeddings());
+        }
+        Ok(ApiRequest::get(format!(
+            "{}/voices/{name}/embedding",
+            self.base_url
+        )))
+    }
+
+    pub fn parse_embedding(
+        &self,
+        name: &str,
+        response: &ApiResponse,
+    ) -> Result<EmbeddingResponse, BackendError> {
+        match response.status {
+            StatusCode::NOT_FOUND => Err(BackendError::VoiceNotFound(name.to_string())),
+            StatusCode::NOT_IMPLEMENTED => Err(self.no_embeddings()),
+            _ => parse_json(response),
+        }
+    }
+
+    /// Convert speech to the tone color of a saved voice. Only OpenVoice
+    /// has a tone-color converter.
+    pub fn convert_voice(&self, audio: Vec<u8>, voice: &str) -> Result<ApiRequest, BackendError> {
+        if !self.model.converts_voices() {
+            return Err(BackendError::Unsupported(format!(
+                "{} has no tone-color converter",
+                self.model.name()
+            )));
+        }
+        Ok(ApiRequest {
+            method: Method::POST,
+            url: format!("{}/convert", self.base_url),
+            headers: Vec::new(),
+            body: Body::Multipart(vec![
+                Part::Audio {
+                    name: "audio".to_string(),
+                    file_name: "speech.wav".to_string(),
+                    bytes: audio,
+                },
+                Part::Text {
+                    name: "name".to_string(),
+                    value: voice.to_string(),
+                },
+            ]),
+        })
+    }
+
+    /// WAV bytes from a conversion response.
+    pub fn parse_conversion(
+        &self,
+        voice: &str,
+        response: ApiResponse,
+    ) -> Result<Vec<u8>, BackendError> {
+        if response.status == StatusCode::NOT_FOUND {
+            return Err(BackendError::VoiceNotFound(voice.to_string()));
+        }
+        self.parse_audio(response)
+    }
+
+    /// Create a synthetic voice. Only OpenVoice draws voices at random.
+    pub fn random_voice(&self, name: &str, seed: Option<u64>) -> Result<ApiRequest, BackendError> {
+        if !self.model.random_voices() {
+            return Err(BackendError::Unsupported(format!(
+                "{} cannot create random voices",
+                self.model.name()
+            )));
+        }
+        Ok(ApiRequest {
+            method: Method::POST,
+            url: format!("{}/voices/random", self.base_url),
+            headers: Vec::new(),
+            body: Body::Json(serde_json::json!({ "name": name, "seed": seed })),
+        })
+    }
+
+    pub fn parse_random_voice(
+        &self,
+        name: &str,
+        response: &ApiResponse,
+    ) -> Result<VoiceInfo, BackendError> {
+        if response.status == StatusCode::CONFLICT {
+            return Err(BackendError::RequestFailed(format!(
+                "voice '{name}' already exists on the server"
+            )));
+        }
+        check_status(response, "Status")?;
+        self.parse_voice(response)
+    }
+
+    /// Upload reference audio to a Gradio server.
+    pub fn gradio_upload(&self, audio: Vec<u8>, file_name: &str) -> ApiRequest {
+        ApiRequest {
+            method: Method::POST,
+            url: format!("{}/gradio_api/upload", self.base_url),
+            headers: Vec::new(),
+            body: Body::Multipart(vec![Part::Audio {
+                name: "files".to_string(),
+                file_name: file_name.to_string(),
+                bytes: audio,
+            }]),
+        }
+    }
+
+    /// The server path of an uploaded file.
+    pub fn parse_upload(&self, response: &ApiResponse) -> Result<String, BackendError> {
+        check_status(response, "Upload failed")?;
+        let paths: Vec<String> = parse_json(response)?;
+        paths
+            .into_iter()
+            .next()
+            .ok_or_else(|| BackendError::InvalidResponse("No path returned".to_string()))
+    }
+
+    /// Start a Gradio generation, cloning from uploaded reference audio.
+    pub fn gradio_generate(
+        &self,
+        text: &str,
+        audio_path: Option<&str>,
+        transcript: Option<&str>,
+    ) -> ApiRequest {
+        // Order: [target_text, prompt_audio, prompt_text, cfg, timesteps, normalize]
+        let audio_value = match audio_path {
+            Some(path) => serde_json::json!({
+                "path": path,
+                "meta": {"_type": "gradio.FileData"}
+            }),
+            None => serde_json::Value::Null,
+        };
+
+        ApiRequest {
+            method: Method::POST,
+            url: format!("{}/gradio_api/call/generate", self.base_url),
+            headers: Vec::new(),
+            body: Body::Json(serde_json::json!({
+                "data": [
+                    text,
+                    audio_value,
+                    transcript.unwrap_or(""),
+                    2.0,  // CFG value
+                    10,   // Inference timesteps
+                    false // Text normalization
+                ]
+            })),
+        }
+    }
+
+    /// The event ID to poll for a started generation.
+    pub fn parse_generate(&self, response: &ApiResponse) -> Result<String, BackendError> {
+        #[derive(Deserialize)]
+        struct EventResponse {
+            event_id: String,
+        }
+
+        check_status(response, "Generate call failed")?;
+        let event: EventResponse = parse_json(response)?;
+        Ok(event.event_id)
+    }
+
+    pub fn gradio_poll(&self, event_id: &str) -> ApiRequest {
+        ApiRequest::get(format!(
+            "{}/gradio_api/call/generate/{event_id}",
+            self.base_url
+        ))
+    }
+
+    /// Read the server-sent events of a poll.
+    pub fn parse_poll(&self, response: &ApiResponse) -> Result<GradioPoll, BackendError> {
+        let text = String::from_utf8_lossy(&response.body);
+
+        if text.contains("event: complete") {
+            for line in text.lines() {
+                if let Some(data) = line.strip_prefix("data: ") {
+                    let parsed: serde_json::Value = serde_json::from_str(data)
+                        .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+
+                    if let Some(url) = parsed
+                        .as_array()
+                        .and_then(|a| a.first())
+                        .and_then(|v| v.get("url"))
+                        .and_then(|u| u.as_str())
+                    {
+                        return Ok(GradioPoll::Complete(url.to_string()));
+                    }
+                }
+            }
+            return Err(BackendError::InvalidResponse(
+                "No audio URL in response".to_string(),
+            ));
+        }
+
+        if text.contains("event: error") {
+            return Err(BackendError::BackendError("Generation failed".to_string()));
+        }
+        Ok(GradioPoll::Pending)
+    }
+
+    /// Stop a generation: a Gradio event ID, or the `job_id` sent with a
+    /// synthesis request.
+    pub fn cancel(&self, job_id: &str) -> ApiRequest {
+        if self.model.is_gradio() {
+            return ApiRequest {
+                method: Method::POST,
+                url: format!("{}/gradio_api/cancel", self.base_url),
+                headers: Vec::new(),
+                body: Body::Json(serde_json::json!({ "event_id": job_id })),
+            };
+        }
+        ApiRequest {
+            method: Method::POST,
+            url: format!("{}/cancel/{job_id}", self.base_url),
+            headers: Vec::new(),
+            body: Body::Empty,
+        }
+    }
+
+    pub fn parse_cancel(&self, response: &ApiResponse) -> Result<(), BackendError> {
+        // The job already finished, or never reached the server
+        if response.status == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check_status(response, "Cancel failed")
+    }
+
+    /// Download generated audio; parse with [`Protocol::parse_audio`].
+    pub fn download(&self, url: &str) -> ApiRequest {
+        ApiRequest::get(url.to_string())
+    }
+
+    fn no_embeddings(&self) -> BackendError {
+        BackendError::Unsupported(format!("{} has no speaker embeddings", self.model.name()))
+    }
+}
+
+/// What the server for `model` supports.
+pub(super) fn model_capabilities(model: Model) -> Capabilities {
+    Capabilities {
+        // Gradio servers clone from reference audio on every request and
+        // take no speed, language, or seed parameter
+        persistent_voices: !model.is_gradio(),
+        streaming: false,
+        styles: false,
+        speed: !model.is_gradio(),
+        speed_range: SpeedRange::DEFAULT,
+        phonemes: false,
+        language: !model.is_gradio(),
+        seed: !model.is_gradio(),
+        max_text_length: Some(model.max_text_length()),
+    }
+}
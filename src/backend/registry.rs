@@ -0,0 +1,153 @@
+//! Registry of the backends the CLI and library can connect to.
+//!
+//! Each backend registers a name, its default port, what it supports, and
+//! how to connect to it. The built-in model servers are registered by
+//! [`BackendRegistry::builtin`]; applications can add their own.
+
+use std::sync::Arc;
+
+use clap::ValueEnum;
+
+use super::Backend;
+use super::Model;
+use super::client::HttpBackend;
+use super::middleware::RequestIds;
+use super::protocol::model_capabilities;
+use super::types::{BackendError, Capabilities};
+
+/// Where and how to reach a backend server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    pub host: String,
+    pub port: u16,
+    /// Bearer token for servers behind an authenticating proxy.
+    pub token: Option<String>,
+    /// Reject responses that differ from the expected schema.
+    pub strict: bool,
+}
+
+type Constructor = dyn Fn(&Connection) -> Result<Box<dyn Backend>, BackendError> + Send + Sync;
+
+/// A backend that can be selected by name.
+#[derive(Clone)]
+pub struct BackendEntry {
+    pub name: String,
+    pub description: String,
+    pub default_port: u16,
+    pub capabilities: Capabilities,
+    connect: Arc<Constructor>,
+}
+
+impl BackendEntry {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        default_port: u16,
+        capabilities: Capabilities,
+        connect: impl Fn(&Connection) -> Result<Box<dyn Backend>, BackendError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            default_port,
+            capabilities,
+            connect: Arc::new(connect),
+        }
+    }
+
+    /// Connect to a server of this kind.
+    pub fn connect(&self, connection: &Connection) -> Result<Box<dyn Backend>, BackendError> {
+        (self.connect)(connection)
+    }
+}
+
+impl std::fmt::Debug for BackendEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackendEntry")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("default_port", &self.default_port)
+            .field("capabilities", &self.capabilities)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Backends available by name, in registration order.
+#[derive(Debug, Clone, Default)]
+pub struct BackendRegistry {
+    entries: Vec<BackendEntry>,
+}
+
+impl BackendRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry of the built-in model servers.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        for &model in Model::value_variants() {
+            registry.register(BackendEntry::new(
+                model.as_str(),
+                model.name(),
+                model.port(),
+                model_capabilities(model),
+                move |connection| {
+                    let backend = HttpBackend::with_port(model, &connection.host, connection.port)
+                        .with_strict(connection.strict);
+                    let backend = match &connection.token {
+                        Some(token) => backend.with_token(token)?,
+                        None => backend,
+                    };
+                    Ok(Box::new(RequestIds::new(backend)))
+                },
+            ));
+        }
+        registry
+    }
+
+    /// Add a backend, replacing any registered under the same name.
+    pub fn register(&mut self, entry: BackendEntry) {
+        match self.entries.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Look up a backend by name.
+    pub fn get(&self, name: &str) -> Option<&BackendEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// All registered backends.
+    pub fn entries(&self) -> &[BackendEntry] {
+        &self.entries
+    }
+
+    /// Connect to the backend registered as `name`, on its default port
+    /// unless `port` is given. `strict` rejects responses that differ from
+    /// the expected schema.
+    pub fn connect(
+        &self,
+        name: &str,
+        host: &str,
+        port: Option<u16>,
+        token: Option<String>,
+        strict: bool,
+    ) -> Result<Box<dyn Backend>, BackendError> {
+        let entry = self.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.entries.iter().map(|e| e.name.as_str()).collect();
+            BackendError::Unsupported(format!(
+                "unknown backend '{name}' (available: {})",
+                known.join(", ")
+            ))
+        })?;
+        entry.connect(&Connection {
+            host: host.to_string(),
+            port: port.unwrap_or(entry.default_port),
+            token,
+            strict,
+        })
+    }
+}
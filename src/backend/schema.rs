@@ -0,0 +1,63 @@
+//! The fields each backend response is expected to have, so strict mode
+//! can report a server whose responses drifted from them.
+
+use serde::de::DeserializeOwned;
+
+use super::types::{HealthResponse, VoiceInfo, VoicesResponse};
+
+/// Fields of a response type, for [`Protocol::with_strict`](super::Protocol::with_strict).
+pub(super) trait Schema: DeserializeOwned {
+    /// Fields the server always sends.
+    const REQUIRED: &[&str];
+    /// Fields the server may leave out.
+    const OPTIONAL: &[&str] = &[];
+
+    /// How `value` departs from the schema, naming fields after `path`.
+    fn drift(value: &serde_json::Value, path: &str) -> Vec<String> {
+        field_drift(value, path, Self::REQUIRED, Self::OPTIONAL)
+    }
+}
+
+fn field_drift(
+    value: &serde_json::Value,
+    path: &str,
+    required: &[&str],
+    optional: &[&str],
+) -> Vec<String> {
+    let Some(object) = value.as_object() else {
+        return vec![format!("{path}<not an object>")];
+    };
+    let missing = required
+        .iter()
+        .filter(|field| !object.contains_key(**field))
+        .map(|field| format!("missing {path}{field}"));
+    let unknown = object
+        .keys()
+        .filter(|key| !required.contains(&key.as_str()) && !optional.contains(&key.as_str()))
+        .map(|key| format!("unknown {path}{key}"));
+    missing.chain(unknown).collect()
+}
+
+impl Schema for HealthResponse {
+    const REQUIRED: &[&str] = &["status", "model", "cuda_available", "gpu", "device"];
+    const OPTIONAL: &[&str] = &["model_version"];
+}
+
+impl Schema for VoiceInfo {
+    const REQUIRED: &[&str] = &["name", "transcript", "model"];
+    const OPTIONAL: &[&str] = &["duration"];
+}
+
+impl Schema for VoicesResponse {
+    const REQUIRED: &[&str] = &["voices"];
+
+    fn drift(value: &serde_json::Value, path: &str) -> Vec<String> {
+        let mut drift = field_drift(value, path, Self::REQUIRED, Self::OPTIONAL);
+        if let Some(voices) = value.get("voices").and_then(|v| v.as_array()) {
+            for (i, voice) in voices.iter().enumerate() {
+                drift.extend(VoiceInfo::drift(voice, &format!("{path}voices[{i}].")));
+            }
+        }
+        drift
+    }
+}
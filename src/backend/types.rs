@@ -1,6 +1,10 @@
 //! Backend request/response types.
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use thiserror::Error;
 
 /// Errors that can occur when communicating with the backend.
@@ -23,32 +27,306 @@ pub enum BackendError {
 
     #[error("Backend error: {0}")]
     BackendError(String),
+
+    #[error("Not supported by this backend: {0}")]
+    Unsupported(String),
+
+    /// 401 or 403: the token is missing or wrong.
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// 413: the text or reference audio is too large for the server.
+    #[error("Request too large: {0}")]
+    PayloadTooLarge(String),
+
+    /// 429: too many requests, with the server's `Retry-After` if sent.
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    /// 503: the server is overloaded or still loading its model.
+    #[error("Server busy: {message}")]
+    ServerBusy {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+}
+
+impl BackendError {
+    /// Name the request that failed in the message. Voice and file errors
+    /// are left alone, since their payload is the missing name.
+    pub fn with_request_id(self, request_id: &str) -> Self {
+        let tag = |message: String| format!("{message} (request {request_id})");
+        match self {
+            Self::ConnectionFailed(m) => Self::ConnectionFailed(tag(m)),
+            Self::RequestFailed(m) => Self::RequestFailed(tag(m)),
+            Self::InvalidResponse(m) => Self::InvalidResponse(tag(m)),
+            Self::BackendError(m) => Self::BackendError(tag(m)),
+            Self::Unsupported(m) => Self::Unsupported(tag(m)),
+            Self::Unauthorized(m) => Self::Unauthorized(tag(m)),
+            Self::PayloadTooLarge(m) => Self::PayloadTooLarge(tag(m)),
+            Self::RateLimited {
+                message,
+                retry_after,
+            } => Self::RateLimited {
+                message: tag(message),
+                retry_after,
+            },
+            Self::ServerBusy {
+                message,
+                retry_after,
+            } => Self::ServerBusy {
+                message: tag(message),
+                retry_after,
+            },
+            other @ (Self::VoiceNotFound(_) | Self::FileNotFound(_)) => other,
+        }
+    }
+
+    /// Whether the server ran out of GPU memory or is overloaded, so the
+    /// request may succeed with less work in flight.
+    pub fn is_overload(&self) -> bool {
+        match self {
+            Self::ServerBusy { .. } => true,
+            Self::RequestFailed(message) | Self::BackendError(message) => {
+                let message = message.to_ascii_lowercase();
+                [
+                    "out of memory",
+                    "outofmemoryerror",
+                    "cublas_status_alloc_failed",
+                ]
+                .iter()
+                .any(|sign| message.contains(sign))
+            }
+            _ => false,
+        }
+    }
+
+    /// How long the server asked to be left alone before a retry.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } | Self::ServerBusy { retry_after, .. } => {
+                *retry_after
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether sending the same request again cannot succeed.
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            Self::VoiceNotFound(_)
+                | Self::FileNotFound(_)
+                | Self::Unsupported(_)
+                | Self::Unauthorized(_)
+                | Self::PayloadTooLarge(_)
+        )
+    }
+}
+
+/// Speeds a backend accepts, as multiples of normal speed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeedRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl SpeedRange {
+    /// 0.5x to 2.0x, past which the bundled servers garble speech.
+    pub const DEFAULT: Self = Self { min: 0.5, max: 2.0 };
+
+    /// Any positive speed.
+    pub const ANY: Self = Self {
+        min: 0.0,
+        max: f32::MAX,
+    };
+
+    pub fn contains(&self, speed: f32) -> bool {
+        speed > 0.0 && (self.min..=self.max).contains(&speed)
+    }
+}
+
+impl Default for SpeedRange {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl std::fmt::Display for SpeedRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x to {}x", self.min, self.max)
+    }
+}
+
+/// Features a backend supports beyond plain synthesis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Extracted voices are saved on the server and synthesized by name.
+    pub persistent_voices: bool,
+    /// Audio can be received while it is still being generated.
+    pub streaming: bool,
+    /// Speaking styles or emotions can be selected.
+    pub styles: bool,
+    /// Speech speed can be changed.
+    pub speed: bool,
+    /// Speeds accepted when `speed` is set.
+    #[serde(default)]
+    pub speed_range: SpeedRange,
+    /// Phoneme segments are accepted as SSML `<phoneme>` tags.
+    pub phonemes: bool,
+    /// The language to speak can be selected.
+    pub language: bool,
+    /// A random seed makes generations repeatable.
+    pub seed: bool,
+    /// Longest text accepted in one request, in characters.
+    pub max_text_length: Option<usize>,
+}
+
+impl Capabilities {
+    /// Every feature, with no text length limit.
+    pub fn unrestricted() -> Self {
+        Self {
+            persistent_voices: true,
+            streaming: true,
+            styles: true,
+            speed: true,
+            speed_range: SpeedRange::ANY,
+            phonemes: true,
+            language: true,
+            seed: true,
+            max_text_length: None,
+        }
+    }
 }
 
 /// Health check response from backend.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Like the other response types, missing fields take their defaults and
+/// unknown ones are kept in `raw`, so a newer server does not break an
+/// older client. [`Protocol::with_strict`](super::Protocol::with_strict)
+/// rejects both instead.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct HealthResponse {
     pub status: String,
     pub model: String,
-    pub cuda_available: bool,
+    /// Version or checkpoint of the loaded model, when the server says.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+    /// Whether the server can use CUDA; `None` when it does not say.
+    pub cuda_available: Option<bool>,
     pub gpu: Option<String>,
+    /// Device the model runs on, or [`UNKNOWN_DEVICE`].
     pub device: String,
+    /// Fields this client does not know, passed through as sent.
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
+impl HealthResponse {
+    /// Whether the server says it can synthesize. Servers still loading
+    /// their model may answer with a status such as `loading`.
+    pub fn is_ready(&self) -> bool {
+        !matches!(
+            self.status.to_ascii_lowercase().as_str(),
+            "loading" | "starting" | "initializing" | "unhealthy" | "error"
+        )
+    }
+
+    /// The loaded model with its version, if known, as in
+    /// `openf5_tts (OpenF5-TTS-Base)`. A batch job stops when this changes.
+    pub fn loaded_model(&self) -> String {
+        match &self.model_version {
+            Some(version) => format!("{} ({version})", self.model),
+            None => self.model.clone(),
+        }
+    }
+}
+
+/// Device of a server that does not report one.
+pub const UNKNOWN_DEVICE: &str = "unknown";
+
 /// Voice information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoiceInfo {
     pub name: String,
+    #[serde(default)]
     pub transcript: String,
+    #[serde(default)]
     pub model: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub duration: Option<f32>,
+    /// Fields this client does not know, passed through as sent.
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
 }
 
 /// Response from list voices endpoint.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct VoicesResponse {
     pub voices: Vec<VoiceInfo>,
+    /// Fields this client does not know, passed through as sent.
+    #[serde(flatten)]
+    pub raw: Map<String, Value>,
+}
+
+/// Speaker embedding of a saved voice, as returned by the backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingResponse {
+    pub name: String,
+    pub shape: Vec<usize>,
+    /// Base64-encoded little-endian float32 values
+    pub embedding: String,
+}
+
+/// A step of a synthesis, for front ends showing real progress.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SynthesisEvent {
+    /// Reference audio is being uploaded.
+    Uploading { pct: u8 },
+    /// The request was submitted as `job_id`, which
+    /// [`Backend::cancel`](super::Backend::cancel) accepts.
+    Queued { job_id: String },
+    /// The server is generating audio, for `elapsed` so far.
+    Generating { elapsed: Duration },
+    /// Generated audio is being received.
+    Downloading { pct: u8 },
+    /// Speech chunk `i` of `of` is finished; `i` counts from 1.
+    ChunkDone { i: usize, of: usize },
+    /// The server ran out of memory or was overloaded, so the request is
+    /// retried after `wait` with at most `limit` requests in flight and
+    /// its text halved `splits` times.
+    BackingOff {
+        limit: Option<usize>,
+        splits: u32,
+        wait: Duration,
+    },
+    /// Synthesis was paused; the next request waits until it is resumed.
+    Paused,
+}
+
+/// Receives [`SynthesisEvent`]s, possibly from another thread.
+#[derive(Clone)]
+pub struct Progress(Arc<dyn Fn(SynthesisEvent) + Send + Sync>);
+
+impl Progress {
+    pub fn new(callback: impl Fn(SynthesisEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub fn emit(&self, event: SynthesisEvent) {
+        (self.0)(event)
+    }
+}
+
+impl std::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Progress")
+    }
 }
 
 /// Request for speech synthesis.
@@ -59,12 +337,29 @@ pub struct SynthesizeRequest {
     pub voice_name: Option<String>,
     #[serde(default = "default_speed")]
     pub speed: f32,
+    /// Language code such as `EN` or `ZH` (backends default to English)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
     /// Reference audio path (for Gradio backends like VoxCPM)
     #[serde(skip)]
     pub reference_audio: Option<std::path::PathBuf>,
     /// Reference transcript (for Gradio backends like VoxCPM)
     #[serde(skip)]
     pub reference_transcript: Option<String>,
+    /// Receives upload, generation, and download progress
+    #[serde(skip)]
+    pub progress: Option<Progress>,
+    /// Identifies the generation so it can be cancelled; the HTTP backend
+    /// assigns one when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// Sent as `X-Request-Id` and named in errors, to trace this synthesis
+    /// through server logs (see [`RequestIds`](super::RequestIds))
+    #[serde(skip)]
+    pub request_id: Option<String>,
+    /// Random seed for models that sample, so a generation can be repeated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 fn default_speed() -> f32 {
@@ -78,8 +373,13 @@ impl SynthesizeRequest {
             text: text.into(),
             voice_name: None,
             speed: 1.0,
+            language: None,
             reference_audio: None,
             reference_transcript: None,
+            progress: None,
+            job_id: None,
+            request_id: None,
+            seed: None,
         }
     }
 
@@ -95,6 +395,18 @@ impl SynthesizeRequest {
         self
     }
 
+    /// Set the random seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the language code.
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
     /// Set reference audio path (for Gradio backends).
     pub fn with_reference_audio(mut self, path: std::path::PathBuf) -> Self {
         self.reference_audio = Some(path);
@@ -130,6 +442,25 @@ mod tests {
         assert_eq!(request.text, "Hello");
         assert_eq!(request.voice_name, None);
         assert_eq!(request.speed, 1.0);
+        assert_eq!(request.language, None);
+    }
+
+    #[test]
+    fn test_synthesize_request_language_serialization() {
+        let json = serde_json::to_value(SynthesizeRequest::new("Hi")).unwrap();
+        assert!(json.get("language").is_none());
+
+        let json = serde_json::to_value(SynthesizeRequest::new("Hi").with_language("ZH")).unwrap();
+        assert_eq!(json["language"], "ZH");
+    }
+
+    #[test]
+    fn test_synthesize_request_seed_serialization() {
+        let json = serde_json::to_value(SynthesizeRequest::new("Hi")).unwrap();
+        assert!(json.get("seed").is_none());
+
+        let json = serde_json::to_value(SynthesizeRequest::new("Hi").with_seed(42)).unwrap();
+        assert_eq!(json["seed"], 42);
     }
 
     #[test]
@@ -144,7 +475,7 @@ mod tests {
 
         let response: HealthResponse = serde_json::from_str(json).unwrap();
         assert_eq!(response.status, "healthy");
-        assert!(response.cuda_available);
+        assert_eq!(response.cuda_available, Some(true));
         assert_eq!(response.gpu, Some("NVIDIA RTX 5060".to_string()));
     }
 
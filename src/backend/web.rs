@@ -0,0 +1,245 @@
+//! Async backend client for web front ends.
+//!
+//! Sends the same [`Protocol`] requests as [`HttpBackend`](super::HttpBackend)
+//! with reqwest's async client, which uses `fetch` when compiled to
+//! `wasm32`. Browsers have no file system, so reference audio is passed as
+//! bytes rather than paths.
+
+use super::Model;
+use super::message::{ApiRequest, ApiResponse, Body, GradioPoll, Part, parse_retry_after};
+use super::protocol::{GRADIO_POLL_ATTEMPTS, Protocol};
+use super::types::{
+    BackendError, Capabilities, EmbeddingResponse, HealthResponse, SynthesisEvent,
+    SynthesizeRequest, VoiceInfo, VoicesResponse,
+};
+
+/// Async HTTP backend client.
+#[derive(Clone)]
+pub struct WebBackend {
+    protocol: Protocol,
+    client: reqwest::Client,
+}
+
+impl WebBackend {
+    /// Create a client for the server for `model` at `base_url`.
+    pub fn new(model: Model, base_url: &str) -> Self {
+        Self {
+            protocol: Protocol::new(model, base_url),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send `Authorization: Bearer <token>` with every request.
+    pub fn with_token(mut self, token: &str) -> Result<Self, BackendError> {
+        let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {token}"))
+            .map_err(|_| BackendError::RequestFailed("Invalid token".to_string()))?;
+        value.set_sensitive(true);
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+        self.client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .map_err(|e| BackendError::RequestFailed(e.to_string()))?;
+        Ok(self)
+    }
+
+    /// Reject responses that differ from the expected schema (see
+    /// [`Protocol::with_strict`]).
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.protocol = self.protocol.with_strict(strict);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.protocol.base_url()
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.protocol.capabilities()
+    }
+
+    pub async fn health(&self) -> Result<HealthResponse, BackendError> {
+        let response = self.send(self.protocol.health()).await?;
+        let mut health = self.protocol.parse_health(&response)?;
+        for request in self.protocol.device_info() {
+            if let Ok(response) = self.send(request).await
+                && self.protocol.parse_device_info(&response, &mut health)
+            {
+                break;
+            }
+        }
+        Ok(health)
+    }
+
+    /// Extract a voice from WAV bytes.
+    pub async fn extract_voice(
+        &self,
+        audio: Vec<u8>,
+        file_name: &str,
+        transcript: &str,
+        name: Option<String>,
+    ) -> Result<VoiceInfo, BackendError> {
+        match self
+            .protocol
+            .extract_voice(audio, file_name, transcript, name.clone())
+        {
+            Some(request) => self.protocol.parse_voice(&self.send(request).await?),
+            None => Ok(self.protocol.unsaved_voice(transcript, name)),
+        }
+    }
+
+    /// Synthesize speech. Gradio servers clone from `reference_audio` (WAV
+    /// bytes); `request.reference_audio` is ignored.
+    pub async fn synthesize(
+        &self,
+        request: &SynthesizeRequest,
+        reference_audio: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, BackendError> {
+        if !self.protocol.model().is_gradio() {
+            let synthesize = self
+                .protocol
+                .synthesize(request)?
+                .with_request_id(request.request_id.as_deref());
+            let response = self.send(synthesize).await?;
+            return self.protocol.parse_audio(response);
+        }
+
+        let request_id = request.request_id.as_deref();
+        let server_path = match reference_audio {
+            Some(audio) => {
+                let upload = self
+                    .protocol
+                    .gradio_upload(audio, "reference.wav")
+                    .with_request_id(request_id);
+                Some(self.protocol.parse_upload(&self.send(upload).await?)?)
+            }
+            None => None,
+        };
+        let generate = self
+            .protocol
+            .gradio_generate(
+                &request.text,
+                server_path.as_deref(),
+                request.reference_transcript.as_deref(),
+            )
+            .with_request_id(request_id);
+        let event_id = self.protocol.parse_generate(&self.send(generate).await?)?;
+        if let Some(progress) = &request.progress {
+            progress.emit(SynthesisEvent::Queued {
+                job_id: event_id.clone(),
+            });
+        }
+
+        // The poll response is an event stream that ends when generation
+        // does, so no delay is needed between polls
+        for _ in 0..GRADIO_POLL_ATTEMPTS {
+            let poll = self
+                .protocol
+                .gradio_poll(&event_id)
+                .with_request_id(request_id);
+            let response = self.send(poll).await?;
+            if let GradioPoll::Complete(url) = self.protocol.parse_poll(&response)? {
+                let download = self.protocol.download(&url).with_request_id(request_id);
+                let download = self.send(download).await?;
+                return self.protocol.parse_audio(download);
+            }
+        }
+        Err(BackendError::RequestFailed(
+            "Generation timed out".to_string(),
+        ))
+    }
+
+    pub async fn list_voices(&self) -> Result<VoicesResponse, BackendError> {
+        match self.protocol.list_voices() {
+            Some(request) => self.protocol.parse_voices(&self.send(request).await?),
+            None => Ok(VoicesResponse::default()),
+        }
+    }
+
+    pub async fn delete_voice(&self, name: &str) -> Result<(), BackendError> {
+        let response = self.send(self.protocol.delete_voice(name)?).await?;
+        self.protocol.parse_delete(name, &response)
+    }
+
+    pub async fn get_embedding(&self, name: &str) -> Result<EmbeddingResponse, BackendError> {
+        let response = self.send(self.protocol.get_embedding(name)?).await?;
+        self.protocol.parse_embedding(name, &response)
+    }
+
+    /// Stop a generation: a Gradio event ID announced by
+    /// [`SynthesisEvent::Queued`], or the `job_id` set on a request.
+    pub async fn cancel(&self, job_id: &str) -> Result<(), BackendError> {
+        let response = self.send(self.protocol.cancel(job_id)).await?;
+        self.protocol.parse_cancel(&response)
+    }
+
+    /// Convert speech to the tone color of saved voice `voice`.
+    pub async fn convert_voice(&self, audio: &[u8], voice: &str) -> Result<Vec<u8>, BackendError> {
+        let response = self
+            .send(self.protocol.convert_voice(audio.to_vec(), voice)?)
+            .await?;
+        self.protocol.parse_conversion(voice, response)
+    }
+
+    /// Create a synthetic voice that imitates no real speaker.
+    pub async fn random_voice(
+        &self,
+        name: &str,
+        seed: Option<u64>,
+    ) -> Result<VoiceInfo, BackendError> {
+        let response = self.send(self.protocol.random_voice(name, seed)?).await?;
+        self.protocol.parse_random_voice(name, &response)
+    }
+
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse, BackendError> {
+        let mut builder = self.client.request(request.method, &request.url);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+        let builder = match request.body {
+            Body::Empty => builder,
+            Body::Json(body) => builder.json(&body),
+            Body::Multipart(parts) => {
+                let mut form = reqwest::multipart::Form::new();
+                for part in parts {
+                    form = match part {
+                        Part::Text { name, value } => form.text(name, value),
+                        Part::Audio {
+                            name,
+                            file_name,
+                            bytes,
+                        } => {
+                            let file = reqwest::multipart::Part::bytes(bytes)
+                                .file_name(file_name)
+                                .mime_str("audio/wav")
+                                .map_err(|e| BackendError::RequestFailed(e.to_string()))?;
+                            form.part(name, file)
+                        }
+                    };
+                }
+                builder.multipart(form)
+            }
+        };
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| BackendError::ConnectionFailed(e.to_string()))?;
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| BackendError::InvalidResponse(e.to_string()))?;
+        Ok(ApiResponse {
+            status,
+            body: body.to_vec(),
+            retry_after,
+        })
+    }
+}
@@ -0,0 +1,60 @@
+//! Assembling a finished job's chunk audio into its output.
+
+use std::time::Duration;
+
+use crate::audio::{AudioBuffer, Segment, assemble, assemble_tracks};
+use crate::text::Chunk;
+
+use super::BatchError;
+use super::job::{ChunkStatus, Job};
+
+/// Join the finished chunk audio of a job in order.
+///
+/// Skipped chunks become silence of their estimated spoken length so the
+/// timeline of the remaining audio is preserved.
+pub fn assemble_job(job: &Job) -> Result<AudioBuffer, BatchError> {
+    let segments = job_segments(job)?;
+    Ok(assemble(segments.into_iter().map(|(_, s)| s).collect())?)
+}
+
+/// Split the finished audio of a job into one aligned track per voice.
+///
+/// Each track has the length of [`assemble_job`]'s output; see
+/// [`assemble_tracks`].
+pub fn assemble_job_tracks(job: &Job) -> Result<Vec<(String, AudioBuffer)>, BatchError> {
+    Ok(assemble_tracks(job_segments(job)?)?)
+}
+
+/// Where each chunk of a finished job starts in [`assemble_job`]'s output,
+/// followed by where the last one ends.
+pub fn job_offsets(job: &Job) -> Result<Vec<Duration>, BatchError> {
+    let mut offsets = vec![Duration::ZERO];
+    for (_, segment) in job_segments(job)? {
+        offsets.push(offsets[offsets.len() - 1] + segment.duration());
+    }
+    Ok(offsets)
+}
+
+fn job_segments(job: &Job) -> Result<Vec<(String, Segment)>, BatchError> {
+    let mut segments = Vec::with_capacity(job.chunks.len());
+
+    for entry in &job.chunks {
+        let track = entry.chunk.track().to_string();
+        match (&entry.chunk, &entry.output, entry.status) {
+            (Chunk::Pause(duration), _, _) => segments.push((track, Segment::Silence(*duration))),
+            (Chunk::Bleep(duration), _, _) => segments.push((track, Segment::Bleep(*duration))),
+            (chunk, _, ChunkStatus::Skipped) => {
+                segments.push((track, Segment::Silence(chunk.estimated_duration())));
+            }
+            (Chunk::Speech { .. }, Some(path), _) => {
+                let wav = std::fs::read(path)?;
+                segments.push((track, Segment::Clip(AudioBuffer::from_wav_bytes(&wav)?)));
+            }
+            (Chunk::Speech { .. }, None, _) => {
+                return Err(BatchError::Incomplete(job.id.clone()));
+            }
+        }
+    }
+
+    Ok(segments)
+}
@@ -0,0 +1,237 @@
+//! Persistent job manifests for batch synthesis.
+
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::BatchError;
+use super::shard::Shard;
+use crate::text::Chunk;
+
+/// Progress of a single chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkStatus {
+    Pending,
+    Done,
+    Failed,
+    /// Failed and replaced by placeholder silence.
+    Skipped,
+}
+
+/// A chunk of a batch job and where its audio was written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobChunk {
+    pub index: usize,
+    pub chunk: Chunk,
+    pub status: ChunkStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Request ID of the last synthesis, as sent to the backend.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Where a chapter of a job's text begins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobChapter {
+    pub title: String,
+    /// Index of the chapter's first chunk.
+    pub start: usize,
+}
+
+/// A batch synthesis job, persisted as `job.json` in its job directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub created_at: String,
+    /// Final assembled output file.
+    pub output: PathBuf,
+    pub chunks: Vec<JobChunk>,
+    /// Chapters the output is split into, if any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<JobChapter>,
+    /// The share of the chunks this job synthesizes when the job is
+    /// split across machines; the output is then assembled by merging.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+    /// Model the backend had loaded when the job started, as its
+    /// [`loaded_model`](crate::backend::HealthResponse::loaded_model).
+    /// Running the job checks the backend still has it when the job starts
+    /// and after the backend is lost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend_model: Option<String>,
+}
+
+impl Job {
+    /// Create a job with every chunk pending.
+    pub fn new(id: impl Into<String>, chunks: Vec<Chunk>, output: PathBuf) -> Self {
+        let chunks = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, chunk)| JobChunk {
+                index,
+                chunk,
+                status: ChunkStatus::Pending,
+                output: None,
+                error: None,
+                request_id: None,
+            })
+            .collect();
+
+        Self {
+            id: id.into(),
+            created_at: Utc::now().to_rfc3339(),
+            output,
+            chunks,
+            chapters: Vec::new(),
+            shard: None,
+            backend_model: None,
+        }
+    }
+
+    /// Number of chunks that have finished.
+    pub fn done_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|c| c.status == ChunkStatus::Done)
+            .count()
+    }
+
+    /// Number of chunks that are done or were skipped.
+    pub fn finished_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|c| matches!(c.status, ChunkStatus::Done | ChunkStatus::Skipped))
+            .count()
+    }
+
+    /// Returns true once every chunk has been synthesized or skipped.
+    pub fn is_complete(&self) -> bool {
+        self.finished_count() == self.chunks.len()
+    }
+
+    /// Mark speech chunk `index` of a finished job pending again, so the
+    /// next run synthesizes it alone and splices it into the output.
+    pub fn reopen_chunk(&mut self, index: usize) -> Result<(), BatchError> {
+        if !self.is_complete() {
+            return Err(BatchError::Incomplete(self.id.clone()));
+        }
+        let entry = self
+            .chunks
+            .get_mut(index)
+            .filter(|c| matches!(c.chunk, Chunk::Speech { .. }))
+            .ok_or_else(|| BatchError::ChunkNotFound(self.id.clone(), index))?;
+        entry.status = ChunkStatus::Pending;
+        entry.error = None;
+        Ok(())
+    }
+}
+
+/// Stores job manifests and chunk audio under `~/.open-tts-rs/jobs/<id>/`.
+pub struct JobStore {
+    jobs_dir: PathBuf,
+}
+
+impl JobStore {
+    /// Create a JobStore with the default directory.
+    pub fn new() -> Self {
+        let jobs_dir = dirs::home_dir()
+            .expect("Could not find home directory")
+            .join(".open-tts-rs")
+            .join("jobs");
+
+        Self { jobs_dir }
+    }
+
+    /// Create a JobStore with a custom directory.
+    pub fn with_dir(jobs_dir: PathBuf) -> Self {
+        Self { jobs_dir }
+    }
+
+    /// Get the jobs directory path.
+    pub fn jobs_dir(&self) -> PathBuf {
+        self.jobs_dir.clone()
+    }
+
+    /// Directory holding a job's manifest and chunk audio.
+    pub fn job_dir(&self, id: &str) -> PathBuf {
+        self.jobs_dir.join(id)
+    }
+
+    /// Path of the audio file for one chunk.
+    pub fn chunk_path(&self, id: &str, index: usize) -> PathBuf {
+        self.job_dir(id).join(format!("chunk-{index:05}.wav"))
+    }
+
+    fn manifest_path(&self, id: &str) -> PathBuf {
+        self.job_dir(id).join("job.json")
+    }
+
+    fn validate_id(id: &str) -> Result<(), BatchError> {
+        if id.is_empty() || id.contains('/') || id.contains('\\') || id.contains("..") {
+            return Err(BatchError::InvalidJobId(id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Create and persist a new job with a timestamp-based ID.
+    pub fn create(&self, chunks: Vec<Chunk>, output: &Path) -> Result<Job, BatchError> {
+        let base = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut id = base.clone();
+        let mut suffix = 1;
+        // Claimed by creating the directory, as shards started together
+        // on one machine create their jobs in the same second
+        std::fs::create_dir_all(&self.jobs_dir)?;
+        loop {
+            match std::fs::create_dir(self.job_dir(&id)) {
+                Ok(()) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    suffix += 1;
+                    id = format!("{base}-{suffix}");
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let output = std::path::absolute(output)?;
+        let job = Job::new(id, chunks, output);
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    /// Persist a job manifest atomically.
+    pub fn save(&self, job: &Job) -> Result<(), BatchError> {
+        Self::validate_id(&job.id)?;
+        std::fs::create_dir_all(self.job_dir(&job.id))?;
+
+        let path = self.manifest_path(&job.id);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(job)?)?;
+        std::fs::rename(tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Load a job manifest by ID.
+    pub fn load(&self, id: &str) -> Result<Job, BatchError> {
+        Self::validate_id(id)?;
+
+        let path = self.manifest_path(id);
+        if !path.exists() {
+            return Err(BatchError::JobNotFound(id.to_string()));
+        }
+
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,1353 @@
+//! Batch synthesis of long text with checkpointing.
+//!
+//! Long input is split into sentence chunks that are synthesized one at a
+//! time. A job manifest recording each chunk's status and audio file is
+//! saved after every chunk so an interrupted job can be resumed. A failing
+//! chunk can abort the job, be retried, or be skipped with placeholder
+//! silence according to the [`ErrorPolicy`]. Optionally each chunk is
+//! verified for dropouts and cutoffs and re-synthesized when one is found.
+//!
+//! A job's output can be split into parts at its chapters and every so many
+//! minutes ([`plan_parts`]).
+//!
+//! Before a job starts its writes can be checked against the free disk
+//! space ([`check_space`]); a disk that fills up while it runs holds the
+//! job until space is freed.
+//!
+//! A large job can be split across machines: each runs one [`Shard`] of
+//! it, synthesizing its share of the chunks, and the shards' manifests
+//! are merged into the output afterwards.
+//!
+//! A [`Sheet`] is the other kind of batch: a CSV file whose rows are each
+//! synthesized to their own output file, and a job stream reads JSON-lines
+//! jobs from a pipe and answers each with a JSON-lines result. A
+//! [`PromptSet`] is synthesized into an IVR prompt directory for a PBX.
+
+mod assemble;
+mod job;
+mod prompts;
+mod retry;
+mod runner;
+mod shard;
+mod sheet;
+mod space;
+mod split;
+mod stream;
+mod verify;
+
+pub use assemble::{assemble_job, assemble_job_tracks, job_offsets};
+pub use job::{ChunkStatus, Job, JobChapter, JobChunk, JobStore};
+pub use prompts::{Pbx, Prompt, PromptFile, PromptOptions, PromptResult, PromptSet, run_prompts};
+pub use runner::{ErrorPolicy, FailedChunk, JobReport, RetriedChunk, RunOptions, run_job};
+pub use shard::Shard;
+pub(crate) use sheet::in_parallel;
+pub use sheet::{RowResult, Sheet, SheetRow, run_sheet};
+pub use space::{LOW_SPACE, LowSpace, check_space, format_size, free_space, job_space};
+pub use split::{Part, m3u_index, plan_parts};
+pub use stream::{StreamJob, StreamOptions, StreamResult, StreamSummary, open_input, run_stream};
+pub use verify::{Anomaly, detect_anomaly};
+
+use thiserror::Error;
+
+use crate::audio::AudioError;
+use crate::engine::TTSError;
+use crate::text::TextError;
+
+/// Errors that can occur while running batch jobs.
+#[derive(Error, Debug)]
+pub enum BatchError {
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+
+    #[error("Invalid job ID: {0}")]
+    InvalidJobId(String),
+
+    #[error("Job {0} has unfinished chunks")]
+    Incomplete(String),
+
+    #[error("Job {0} has no speech chunk {1}")]
+    ChunkNotFound(String, usize),
+
+    #[error("Invalid batch sheet: {0}")]
+    InvalidSheet(String),
+
+    #[error("Invalid prompt file: {0}")]
+    InvalidPrompts(String),
+
+    #[error(
+        "Backend model changed from {expected} to {found} mid-job; load {expected} again and \
+         resume, or start a new job"
+    )]
+    ModelChanged { expected: String, found: String },
+
+    #[error(
+        "Not enough disk space in {}: about {} is needed but {} is free",
+        path.display(),
+        format_size(*needed + LOW_SPACE),
+        format_size(*free)
+    )]
+    DiskSpace {
+        path: std::path::PathBuf,
+        needed: u64,
+        free: u64,
+    },
+
+    #[error("Synthesis failed: {0}")]
+    TTSError(#[from] TTSError),
+
+    #[error("Text error: {0}")]
+    TextError(#[from] TextError),
+
+    #[error("Audio processing error: {0}")]
+    AudioError(#[from] AudioError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioBuffer, Preset};
+    use crate::backend::{BackendError, HealthResponse, MockBackend, mock_backend};
+    use crate::engine::TTSEngine;
+    use crate::journal::{Event, Journals, Run};
+    use crate::text::Chunk;
+    use crate::voice::VoiceManager;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn speech(text: &str) -> Chunk {
+        Chunk::Speech {
+            text: text.to_string(),
+            voice: None,
+            speed: 1.0,
+        }
+    }
+
+    fn tone_wav(samples: usize) -> Vec<u8> {
+        AudioBuffer::new(vec![0.25; samples], 1000, 1)
+            .to_wav_bytes()
+            .unwrap()
+    }
+
+    fn engine(backend: MockBackend, temp_dir: &TempDir) -> TTSEngine<MockBackend> {
+        TTSEngine::new(
+            backend,
+            VoiceManager::with_dir(temp_dir.path().join("voices")),
+        )
+    }
+
+    // ===========================================
+    // JobStore tests
+    // ===========================================
+
+    #[test]
+    fn test_job_store_default_directory() {
+        let store = JobStore::new();
+        let expected = dirs::home_dir().unwrap().join(".open-tts-rs").join("jobs");
+        assert_eq!(store.jobs_dir(), expected);
+    }
+
+    #[test]
+    fn test_job_store_create_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().to_path_buf());
+
+        let job = store
+            .create(
+                vec![speech("One."), speech("Two.")],
+                &temp_dir.path().join("out.wav"),
+            )
+            .unwrap();
+
+        let loaded = store.load(&job.id).unwrap();
+        assert_eq!(loaded, job);
+        assert_eq!(loaded.chunks.len(), 2);
+        assert!(
+            loaded
+                .chunks
+                .iter()
+                .all(|c| c.status == ChunkStatus::Pending)
+        );
+    }
+
+    #[test]
+    fn test_job_store_unique_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().to_path_buf());
+        let output = temp_dir.path().join("out.wav");
+
+        let a = store.create(vec![speech("A")], &output).unwrap();
+        let b = store.create(vec![speech("B")], &output).unwrap();
+        assert_ne!(a.id, b.id);
+    }
+
+    #[test]
+    fn test_job_store_load_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().to_path_buf());
+
+        let result = store.load("20240101-000000");
+        assert!(matches!(result.unwrap_err(), BatchError::JobNotFound(_)));
+    }
+
+    #[test]
+    fn test_job_store_rejects_traversal() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().to_path_buf());
+
+        let result = store.load("../etc");
+        assert!(matches!(result.unwrap_err(), BatchError::InvalidJobId(_)));
+    }
+
+    // ===========================================
+    // run_job tests
+    // ===========================================
+
+    #[test]
+    fn test_run_job_writes_output_and_marks_done() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(2)
+            .returning(|_| Ok(tone_wav(100)));
+        let engine = engine(backend, &temp_dir);
+
+        let chunks = vec![
+            speech("One."),
+            Chunk::Pause(Duration::from_millis(50)),
+            speech("Two."),
+        ];
+        let mut job = store.create(chunks, &output).unwrap();
+        let mut calls = Vec::new();
+        let options = RunOptions::default();
+        run_job(&engine, &store, &mut job, &options, |done, total| {
+            calls.push((done, total))
+        })
+        .unwrap();
+
+        assert!(job.is_complete());
+        assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+        assert!(store.load(&job.id).unwrap().is_complete());
+
+        let audio = AudioBuffer::from_wav_bytes(&std::fs::read(&output).unwrap()).unwrap();
+        assert_eq!(audio.samples.len(), 250);
+    }
+
+    #[test]
+    fn test_run_job_records_request_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+
+        let sent = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = sent.clone();
+        let mut backend = mock_backend();
+        backend.expect_synthesize().times(2).returning(move |req| {
+            seen.lock().unwrap().push(req.request_id.clone().unwrap());
+            Ok(tone_wav(100))
+        });
+        let engine = engine(backend, &temp_dir);
+
+        let chunks = vec![
+            speech("One."),
+            Chunk::Pause(Duration::from_millis(50)),
+            speech("Two."),
+        ];
+        let mut job = store
+            .create(chunks, &temp_dir.path().join("out.wav"))
+            .unwrap();
+        run_job(&engine, &store, &mut job, &RunOptions::default(), |_, _| {}).unwrap();
+
+        let sent = sent.lock().unwrap();
+        assert_ne!(sent[0], sent[1]);
+        assert_eq!(job.chunks[0].request_id.as_ref(), Some(&sent[0]));
+        assert_eq!(job.chunks[1].request_id, None);
+        assert_eq!(job.chunks[2].request_id.as_ref(), Some(&sent[1]));
+        assert_eq!(store.load(&job.id).unwrap(), job);
+    }
+
+    #[test]
+    fn test_run_job_failure_is_checkpointed_and_resumable() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+        let chunks = vec![speech("One."), speech("Two."), speech("Three.")];
+        let mut job = store.create(chunks, &output).unwrap();
+
+        // First run: chunk two fails (backend outage)
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text == "One.")
+            .times(1)
+            .returning(|_| Ok(tone_wav(10)));
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Two.")
+            .times(1)
+            .returning(|_| Err(BackendError::ConnectionFailed("down".to_string())));
+        let options = RunOptions::default();
+        let result = run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        );
+        assert!(matches!(result.unwrap_err(), BatchError::TTSError(_)));
+
+        let saved = store.load(&job.id).unwrap();
+        assert_eq!(saved.chunks[0].status, ChunkStatus::Done);
+        assert_eq!(saved.chunks[1].status, ChunkStatus::Failed);
+        assert!(saved.chunks[1].error.is_some());
+        assert_eq!(saved.chunks[2].status, ChunkStatus::Pending);
+        assert!(!output.exists());
+
+        // Resume: only the unfinished chunks are synthesized
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text != "One.")
+            .times(2)
+            .returning(|_| Ok(tone_wav(10)));
+        let mut job = store.load(&job.id).unwrap();
+        run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert!(job.is_complete());
+        let audio = AudioBuffer::from_wav_bytes(&std::fs::read(&output).unwrap()).unwrap();
+        assert_eq!(audio.samples.len(), 30);
+    }
+
+    fn fail_second_chunk() -> MockBackend {
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text != "Bad.")
+            .returning(|_| Ok(tone_wav(1000)));
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Bad.")
+            .returning(|_| Err(BackendError::BackendError("500".to_string())));
+        backend
+    }
+
+    fn healthy(model: &'static str) -> impl Fn() -> Result<HealthResponse, BackendError> {
+        move || {
+            Ok(HealthResponse {
+                status: "healthy".to_string(),
+                model: model.to_string(),
+                model_version: Some("v1".to_string()),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn test_run_job_checks_backend_model_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+
+        let mut backend = mock_backend();
+        backend
+            .expect_health()
+            .times(1)
+            .returning(healthy("openf5_tts"));
+        backend
+            .expect_synthesize()
+            .times(3)
+            .returning(|_| Ok(tone_wav(100)));
+        let engine = engine(backend, &temp_dir);
+
+        let chunks = vec![speech("One."), speech("Two."), speech("Three.")];
+        let mut job = store.create(chunks, &output).unwrap();
+        job.backend_model = Some("openf5_tts (v1)".to_string());
+        run_job(&engine, &store, &mut job, &RunOptions::default(), |_, _| {}).unwrap();
+    }
+
+    #[test]
+    fn test_run_job_stops_when_backend_model_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+
+        let mut backend = mock_backend();
+        let mut seq = mockall::Sequence::new();
+        backend
+            .expect_health()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(healthy("openf5_tts"));
+        backend
+            .expect_synthesize()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(tone_wav(100)));
+        // The backend restarts with other weights during the second chunk
+        backend
+            .expect_synthesize()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Err(BackendError::ConnectionFailed("refused".to_string())));
+        backend
+            .expect_synthesize()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(tone_wav(100)));
+        backend
+            .expect_health()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(healthy("openvoice_v2"));
+        let engine = engine(backend, &temp_dir);
+
+        let mut job = store
+            .create(vec![speech("One."), speech("Two.")], &output)
+            .unwrap();
+        job.backend_model = Some("openf5_tts (v1)".to_string());
+        let options = RunOptions {
+            on_error: ErrorPolicy::Retry,
+            retry_delay: Duration::from_millis(1),
+            ..RunOptions::default()
+        };
+        let result = run_job(&engine, &store, &mut job, &options, |_, _| {});
+
+        match result {
+            Err(BatchError::ModelChanged { expected, found }) => {
+                assert_eq!(expected, "openf5_tts (v1)");
+                assert_eq!(found, "openvoice_v2 (v1)");
+            }
+            other => panic!("expected a model change, got {other:?}"),
+        }
+        let saved = store.load(&job.id).unwrap();
+        assert_eq!(saved.chunks[0].status, ChunkStatus::Done);
+        assert_eq!(saved.chunks[1].status, ChunkStatus::Pending);
+        assert_eq!(saved.backend_model.as_deref(), Some("openf5_tts (v1)"));
+    }
+
+    #[test]
+    fn test_run_job_skip_policy_inserts_silence_and_reports() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+        let bad = Chunk::Speech {
+            text: "Bad.".to_string(),
+            voice: None,
+            speed: 0.5,
+        };
+        let mut job = store
+            .create(vec![speech("Good."), bad, speech("Fine.")], &output)
+            .unwrap();
+
+        let options = RunOptions {
+            on_error: ErrorPolicy::Skip,
+            ..RunOptions::default()
+        };
+        let report = run_job(
+            &engine(fail_second_chunk(), &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].index, 1);
+        assert_eq!(report.skipped[0].text, "Bad.");
+        assert!(report.skipped[0].error.contains("500"));
+        assert!(job.is_complete());
+        assert_eq!(job.chunks[1].status, ChunkStatus::Skipped);
+
+        // One word at speed 0.5 is estimated at 0.8s = 800 samples at 1 kHz
+        let audio = AudioBuffer::from_wav_bytes(&std::fs::read(&output).unwrap()).unwrap();
+        assert_eq!(audio.samples.len(), 1000 + 800 + 1000);
+        assert_eq!(audio.samples[1400], 0.0);
+    }
+
+    #[test]
+    fn test_run_job_retry_policy_recovers() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let mut job = store
+            .create(vec![speech("Flaky.")], &temp_dir.path().join("out.wav"))
+            .unwrap();
+
+        let mut backend = mock_backend();
+        let mut calls = 0;
+        backend.expect_synthesize().times(3).returning(move |_| {
+            calls += 1;
+            if calls < 3 {
+                Err(BackendError::ConnectionFailed("reset".to_string()))
+            } else {
+                Ok(tone_wav(10))
+            }
+        });
+
+        let journal = Journals::with_dir(temp_dir.path().join("logs"))
+            .start(Vec::new())
+            .unwrap();
+        let options = RunOptions {
+            on_error: ErrorPolicy::Retry,
+            max_retries: 3,
+            retry_delay: Duration::ZERO,
+            journal: Some(journal.clone()),
+            ..RunOptions::default()
+        };
+        let report = run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert!(report.skipped.is_empty());
+        assert!(job.is_complete());
+
+        let events: Vec<Event> = Run::load(journal.path())
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|entry| entry.event)
+            .skip(1)
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert!(
+            matches!(&events[0], Event::ChunkRetrying { chunk: 0, error, .. } if error.contains("reset"))
+        );
+        assert!(matches!(
+            &events[2],
+            Event::ChunkDone {
+                chunk: 0,
+                request_id: Some(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_run_job_retry_policy_gives_up() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let mut job = store
+            .create(vec![speech("Bad.")], &temp_dir.path().join("out.wav"))
+            .unwrap();
+
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(3)
+            .returning(|_| Err(BackendError::BackendError("500".to_string())));
+
+        let options = RunOptions {
+            on_error: ErrorPolicy::Retry,
+            max_retries: 2,
+            retry_delay: Duration::ZERO,
+            ..RunOptions::default()
+        };
+        let result = run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        );
+
+        assert!(result.is_err());
+        assert_eq!(job.chunks[0].status, ChunkStatus::Failed);
+    }
+
+    #[test]
+    fn test_run_job_retry_policy_skips_permanent_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let mut job = store
+            .create(vec![speech("Secret.")], &temp_dir.path().join("out.wav"))
+            .unwrap();
+
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(1)
+            .returning(|_| Err(BackendError::Unauthorized("Status: 401".to_string())));
+
+        let options = RunOptions {
+            on_error: ErrorPolicy::Retry,
+            max_retries: 3,
+            retry_delay: Duration::ZERO,
+            ..RunOptions::default()
+        };
+        let result = run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_rerender_replaces_one_chunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+        let chunks = vec![
+            speech("One."),
+            Chunk::Pause(Duration::from_millis(50)),
+            speech("Two."),
+        ];
+        let mut job = store.create(chunks, &output).unwrap();
+
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(2)
+            .returning(|_| Ok(tone_wav(100)));
+        let options = RunOptions::default();
+        run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+        let first = std::fs::read(job.chunks[0].output.as_ref().unwrap()).unwrap();
+
+        // Only the reopened chunk is sent again, and the output is rebuilt
+        job.reopen_chunk(2).unwrap();
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(1)
+            .withf(|request| request.text == "Two." && request.seed == Some(7))
+            .returning(|_| Ok(tone_wav(300)));
+        let engine = engine(backend, &temp_dir).with_seed(Some(7));
+        run_job(&engine, &store, &mut job, &options, |_, _| {}).unwrap();
+
+        assert!(job.is_complete());
+        assert_eq!(
+            std::fs::read(job.chunks[0].output.as_ref().unwrap()).unwrap(),
+            first
+        );
+        let audio = AudioBuffer::from_wav_bytes(&std::fs::read(&output).unwrap()).unwrap();
+        assert_eq!(audio.samples.len(), 450);
+    }
+
+    #[test]
+    fn test_reopen_chunk_errors() {
+        let mut job = Job::new("j", vec![speech("One.")], "out.wav".into());
+        assert!(matches!(
+            job.reopen_chunk(0).unwrap_err(),
+            BatchError::Incomplete(_)
+        ));
+
+        let chunks = vec![speech("One."), Chunk::Pause(Duration::from_millis(50))];
+        let mut job = Job::new("j", chunks, "out.wav".into());
+        for chunk in &mut job.chunks {
+            chunk.status = ChunkStatus::Done;
+        }
+        for index in [1, 2] {
+            assert!(matches!(
+                job.reopen_chunk(index).unwrap_err(),
+                BatchError::ChunkNotFound(_, i) if i == index
+            ));
+        }
+        job.reopen_chunk(0).unwrap();
+        assert_eq!(job.chunks[0].status, ChunkStatus::Pending);
+    }
+
+    #[test]
+    fn test_assemble_job_incomplete() {
+        let job = Job::new("j", vec![speech("One.")], "out.wav".into());
+        assert!(matches!(
+            assemble_job(&job).unwrap_err(),
+            BatchError::Incomplete(_)
+        ));
+    }
+
+    // ===========================================
+    // Disk space tests
+    // ===========================================
+
+    #[test]
+    fn test_job_space_estimates_pending_chunks_and_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        // Five words at 2.5 words per second, then one second of pause
+        let chunks = vec![
+            speech("One two three four five."),
+            Chunk::Pause(Duration::from_secs(1)),
+            speech("Six seven eight nine ten."),
+        ];
+        let mut job = Job::new("j", chunks, temp_dir.path().join("out/book.wav"));
+        job.chunks[0].status = ChunkStatus::Done;
+
+        let needs = job_space(&store, &job, false, None);
+        assert_eq!(
+            needs,
+            vec![
+                (store.job_dir("j"), 96_000),
+                (temp_dir.path().join("out"), 240_000)
+            ]
+        );
+        assert_eq!(job_space(&store, &job, true, None)[1].1, 480_000);
+
+        // The output is assembled at 24 kHz, then saved as 8 kHz mu-law
+        let telephony = Preset::builtin("telephony").unwrap();
+        assert_eq!(
+            job_space(&store, &job, true, Some(&telephony))[1].1,
+            280_000
+        );
+        let podcast = Preset::builtin("podcast").unwrap();
+        let stereo = Preset {
+            sample_rate: Some(48_000),
+            channels: Some(2),
+            ..Preset::default()
+        };
+        assert_eq!(job_space(&store, &job, false, Some(&podcast))[1].1, 240_000);
+        assert_eq!(job_space(&store, &job, false, Some(&stereo))[1].1, 960_000);
+
+        job.shard = Some("1/2".parse().unwrap());
+        assert_eq!(job_space(&store, &job, false, None).len(), 1);
+
+        assert!(free_space(&temp_dir.path().join("not/yet/made")).is_some());
+        check_space(&needs).unwrap();
+        let err = check_space(&[(temp_dir.path().to_path_buf(), u64::MAX / 2)]).unwrap_err();
+        assert!(matches!(&err, BatchError::DiskSpace { path, .. } if path == temp_dir.path()));
+        assert!(err.to_string().starts_with("Not enough disk space in"));
+        assert_eq!(format_size(512 * 1024), "512 KB");
+        assert_eq!(format_size(48 * 1024 * 1024), "48.0 MB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024 / 4), "1.25 GB");
+    }
+
+    #[test]
+    fn test_write_waits_out_a_full_disk() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut warnings = Vec::new();
+        let mut attempts = 0;
+        let written = space::write_when_space(
+            temp_dir.path(),
+            100,
+            Duration::from_millis(1),
+            |free| warnings.push(free),
+            || {
+                attempts += 1;
+                match attempts {
+                    1..=2 => Err(std::io::Error::from(std::io::ErrorKind::StorageFull).into()),
+                    _ => Ok(attempts),
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(warnings.len(), 1);
+
+        // Other errors are not waited out
+        let err = space::write_when_space(
+            temp_dir.path(),
+            100,
+            Duration::from_millis(1),
+            |_| panic!("not a full disk"),
+            || -> Result<(), BatchError> { Err(std::io::Error::other("denied").into()) },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("denied"));
+    }
+
+    // ===========================================
+    // Verification tests
+    // ===========================================
+
+    /// Alternating tone and silence sections at 1 kHz; with `fade`, each
+    /// tone section fades out over its last 50 ms.
+    fn clip(parts: &[(f64, bool)], fade: bool) -> AudioBuffer {
+        let mut samples = Vec::new();
+        for &(seconds, voiced) in parts {
+            let n = (seconds * 1000.0) as usize;
+            samples.extend((0..n).map(|i| {
+                if !voiced {
+                    return 0.0;
+                }
+                let level = if fade {
+                    ((n - i) as f32 / 50.0).min(1.0)
+                } else {
+                    1.0
+                };
+                0.5 * level * if i % 2 == 0 { 1.0 } else { -1.0 }
+            }));
+        }
+        AudioBuffer::new(samples, 1000, 1)
+    }
+
+    #[test]
+    fn test_detect_anomaly_clean_clip() {
+        let audio = clip(
+            &[(0.2, false), (1.0, true), (0.3, false), (1.0, true)],
+            true,
+        );
+        assert_eq!(detect_anomaly(&audio, Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn test_detect_anomaly_gap() {
+        let audio = clip(&[(1.0, true), (2.0, false), (1.0, true)], true);
+        assert_eq!(
+            detect_anomaly(&audio, Duration::from_secs(1)),
+            Some(Anomaly::Gap {
+                start: 1.0,
+                end: 3.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_detect_anomaly_cutoff_and_silence() {
+        let audio = clip(&[(1.0, true)], false);
+        assert_eq!(
+            detect_anomaly(&audio, Duration::from_secs(1)),
+            Some(Anomaly::Cutoff)
+        );
+
+        let silence = clip(&[(1.0, false)], false);
+        assert_eq!(
+            detect_anomaly(&silence, Duration::from_secs(1)),
+            Some(Anomaly::Silent)
+        );
+    }
+
+    #[test]
+    fn test_run_job_verify_retries_anomalous_chunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let mut job = store
+            .create(
+                vec![speech("Fine."), speech("Broken.")],
+                &temp_dir.path().join("out.wav"),
+            )
+            .unwrap();
+
+        let good = clip(&[(0.5, true)], true).to_wav_bytes().unwrap();
+        let cut = clip(&[(0.5, true)], false).to_wav_bytes().unwrap();
+        let mut backend = mock_backend();
+        let clean = good.clone();
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Fine.")
+            .times(1)
+            .returning(move |_| Ok(clean.clone()));
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Broken." && req.speed == 1.0)
+            .times(1)
+            .returning(move |_| Ok(cut.clone()));
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Broken." && req.speed != 1.0)
+            .times(1)
+            .returning(move |_| Ok(good.clone()));
+
+        let options = RunOptions {
+            verify: true,
+            ..RunOptions::default()
+        };
+        let report = run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert_eq!(
+            report.retried,
+            vec![RetriedChunk {
+                index: 1,
+                text: "Broken.".to_string(),
+                anomaly: "abrupt cutoff".to_string(),
+                attempts: 2,
+                resolved: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_run_job_verify_keeps_first_attempt_when_unresolved() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+        let mut job = store.create(vec![speech("Broken.")], &output).unwrap();
+
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(3)
+            .returning(|_| Ok(tone_wav(500)));
+
+        let options = RunOptions {
+            verify: true,
+            ..RunOptions::default()
+        };
+        let report = run_job(
+            &engine(backend, &temp_dir),
+            &store,
+            &mut job,
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+
+        assert_eq!(report.retried.len(), 1);
+        assert_eq!(report.retried[0].attempts, 3);
+        assert!(!report.retried[0].resolved);
+        assert!(job.is_complete());
+        assert!(output.exists());
+    }
+
+    // ===========================================
+    // Sheet tests
+    // ===========================================
+
+    fn write_sheet(temp_dir: &TempDir, contents: &str) -> std::path::PathBuf {
+        let path = temp_dir.path().join("jobs.csv");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_sheet_read_fills_placeholders_and_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_sheet(
+            &temp_dir,
+            "text,output,voice,speed,ext\n\
+             Press {{ ext }} for sales.,menu-{{ext}}.wav,amy,0.9,1\n\
+             Goodbye.,bye.wav,,,\n",
+        );
+
+        let sheet = Sheet::read(&path, Some(temp_dir.path())).unwrap();
+        assert_eq!(
+            sheet.rows,
+            vec![
+                SheetRow {
+                    line: 2,
+                    text: "Press 1 for sales.".to_string(),
+                    voice: Some("amy".to_string()),
+                    speed: Some(0.9),
+                    output: temp_dir.path().join("menu-1.wav"),
+                },
+                SheetRow {
+                    line: 3,
+                    text: "Goodbye.".to_string(),
+                    voice: None,
+                    speed: None,
+                    output: temp_dir.path().join("bye.wav"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sheet_read_rejects_bad_sheets() {
+        let temp_dir = TempDir::new().unwrap();
+        for contents in [
+            "text,voice\nHello.,amy\n",
+            "text,output\nHello.,same.wav\nBye.,same.wav\n",
+            "text,output,speed\nHello.,a.wav,fast\n",
+            "text,output\nHello {{ name }}.,a.wav\n",
+        ] {
+            let path = write_sheet(&temp_dir, contents);
+            assert!(
+                matches!(Sheet::read(&path, None), Err(BatchError::InvalidSheet(_))),
+                "{contents}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_sheet_records_failures_and_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_sheet(
+            &temp_dir,
+            "text,output,voice\nOne.,out/one.wav,\nTwo.,two.wav,bob\nThree.,three.wav,\n",
+        );
+        let sheet = Sheet::read(&path, Some(temp_dir.path())).unwrap();
+
+        // Row two's voice was never saved
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(2)
+            .returning(|_| Ok(tone_wav(500)));
+        let engine = engine(backend, &temp_dir);
+
+        let finished = std::sync::atomic::AtomicUsize::new(0);
+        let results = run_sheet(&engine, &sheet, None, 1.0, 2, |_, _| {
+            finished.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+        assert_eq!(finished.into_inner(), 3);
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_done());
+        assert!((results[0].seconds - 0.5).abs() < 1e-9);
+        assert!(temp_dir.path().join("out/one.wav").exists());
+        assert_eq!(results[1].voice.as_deref(), Some("bob"));
+        assert!(
+            results[1]
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("Voice not found: bob")
+        );
+        assert!(results[1].chunks.is_empty());
+        assert!(results[2].is_done());
+
+        let results_path = temp_dir.path().join("jobs.results.csv");
+        sheet.write_results(&results_path, &results).unwrap();
+        let written = std::fs::read_to_string(&results_path).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines[0], "text,output,voice,status,seconds,error");
+        assert_eq!(lines[1], "One.,out/one.wav,,done,0.50,");
+        assert!(lines[2].starts_with("Two.,two.wav,bob,failed,0.00,"));
+        assert_eq!(lines.len(), 4);
+    }
+
+    // ===========================================
+    // Stream tests
+    // ===========================================
+
+    #[test]
+    fn test_run_stream_answers_every_job() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Hello." && (req.speed - 1.2).abs() < 1e-6)
+            .times(1)
+            .returning(|_| Ok(tone_wav(250)));
+        let engine = engine(backend, &temp_dir);
+
+        let input = concat!(
+            r#"{"id": "a1", "text": "Hello.", "speed": 1.2, "output": "a1.wav"}"#,
+            "\n\n",
+            "not json\n",
+            r#"{"text": "Hi.", "voice": "nobody", "output": "a2.wav"}"#,
+            "\n",
+        );
+        let options = StreamOptions {
+            output_dir: Some(temp_dir.path().to_path_buf()),
+            ..StreamOptions::default()
+        };
+        let mut output = Vec::new();
+        let synthesized = std::sync::atomic::AtomicUsize::new(0);
+        let summary = run_stream(
+            &engine,
+            std::io::Cursor::new(input),
+            &mut output,
+            &options,
+            |_| {
+                synthesized.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            },
+        )
+        .unwrap();
+
+        assert_eq!(summary, StreamSummary { done: 1, failed: 2 });
+        assert_eq!(synthesized.into_inner(), 1);
+        assert!(temp_dir.path().join("a1.wav").exists());
+
+        let results: Vec<StreamResult> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].id.as_deref(), Some("a1"));
+        assert_eq!(results[0].status, "done");
+        assert!((results[0].seconds - 0.25).abs() < 1e-9);
+        assert_eq!(results[1].line, 3);
+        assert!(
+            results[1]
+                .error
+                .as_ref()
+                .unwrap()
+                .starts_with("invalid job")
+        );
+        assert_eq!(results[2].line, 4);
+        assert_eq!(results[2].status, "failed");
+        assert_eq!(results[2].output, Some(temp_dir.path().join("a2.wav")));
+    }
+
+    // ===========================================
+    // Prompt set tests
+    // ===========================================
+
+    #[test]
+    fn test_prompt_set_parse() {
+        let set = PromptSet::parse(
+            "welcome = \"Thank you for calling.\"\n\
+             main-menu = { text = \"Press one.\", voice = \"amy\" }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            set.prompts,
+            vec![
+                Prompt {
+                    id: "main-menu".to_string(),
+                    text: "Press one.".to_string(),
+                    voice: Some("amy".to_string()),
+                },
+                Prompt {
+                    id: "welcome".to_string(),
+                    text: "Thank you for calling.".to_string(),
+                    voice: None,
+                },
+            ]
+        );
+
+        for contents in [
+            "",
+            "\"../etc\" = \"Hello.\"",
+            "welcome = \" \"",
+            "welcome = { text = \"Hi.\", speed = 2 }",
+        ] {
+            assert!(PromptSet::parse(contents).is_err(), "{contents}");
+        }
+    }
+
+    #[test]
+    fn test_pbx_prompt_layouts() {
+        let asterisk: Vec<_> = Pbx::Asterisk
+            .files("welcome", "en-US", "amy")
+            .into_iter()
+            .map(|f| (f.path, f.sample_rate))
+            .collect();
+        assert_eq!(
+            asterisk,
+            vec![
+                ("en/custom/welcome.ulaw".into(), 8000),
+                ("en/custom/welcome.alaw".into(), 8000),
+                ("en/custom/welcome.sln16".into(), 16000),
+            ]
+        );
+
+        let freeswitch = Pbx::Freeswitch.files("welcome", "fr_CA", "amy");
+        assert_eq!(freeswitch.len(), 4);
+        assert_eq!(
+            freeswitch[0].path,
+            std::path::PathBuf::from("fr/ca/amy/custom/8000/welcome.wav")
+        );
+        assert_eq!(freeswitch[3].sample_rate, 48000);
+        assert!(freeswitch.iter().all(|f| f.format.is_none()));
+        assert_eq!(
+            Pbx::Freeswitch.files("welcome", "de", "amy")[1].path,
+            std::path::PathBuf::from("de/amy/custom/16000/welcome.wav")
+        );
+    }
+
+    #[test]
+    fn test_run_prompts_writes_every_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let set = PromptSet::parse(
+            "bye = \"Goodbye.\"\nhold = { text = \"Please hold.\", voice = \"bob\" }\n",
+        )
+        .unwrap();
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(1)
+            .returning(|_| Ok(tone_wav(500)));
+        let engine = engine(backend, &temp_dir);
+        let options = PromptOptions {
+            pbx: Pbx::Asterisk,
+            output_dir: temp_dir.path().join("sounds"),
+            locale: "en-US".to_string(),
+            voice: None,
+            speed: 1.0,
+            workers: 2,
+        };
+
+        let results = run_prompts(&engine, &set, &options, |_| {});
+        assert!(results[0].is_done());
+        assert!((results[0].seconds - 0.5).abs() < 1e-9);
+        let dir = temp_dir.path().join("sounds/en/custom");
+        assert_eq!(results[0].files[0], dir.join("bye.ulaw"));
+        assert_eq!(std::fs::read(dir.join("bye.ulaw")).unwrap().len(), 4000);
+        assert_eq!(std::fs::read(dir.join("bye.alaw")).unwrap().len(), 4000);
+        assert_eq!(std::fs::read(dir.join("bye.sln16")).unwrap().len(), 16000);
+
+        // The hold prompt's voice was never saved
+        assert!(
+            results[1]
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("Voice not found: bob")
+        );
+        assert!(results[1].files.is_empty());
+
+        let manifest = crate::manifest::Manifest::for_prompts(&results, "mock").unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        assert_eq!(manifest.files[2].text, "Goodbye.");
+        assert!((manifest.files[2].duration - 0.5).abs() < 1e-9);
+    }
+
+    // ===========================================
+    // Split tests
+    // ===========================================
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_plan_parts_at_chapters_and_every() {
+        // Ten 10-second chunks
+        let offsets: Vec<Duration> = (0..=10).map(|i| secs(i * 10)).collect();
+        let chapters = vec![
+            JobChapter {
+                title: "Chapter 1".to_string(),
+                start: 0,
+            },
+            JobChapter {
+                title: "Chapter 2".to_string(),
+                start: 3,
+            },
+        ];
+
+        let parts = plan_parts(&chapters, &offsets, secs(100), None);
+        assert_eq!(
+            parts,
+            vec![
+                Part {
+                    title: "Chapter 1".to_string(),
+                    start: secs(0),
+                    end: secs(30),
+                },
+                Part {
+                    title: "Chapter 2".to_string(),
+                    start: secs(30),
+                    end: secs(100),
+                },
+            ]
+        );
+
+        let parts = plan_parts(&chapters, &offsets, secs(100), Some(secs(34)));
+        let spans: Vec<(String, u64, u64)> = parts
+            .iter()
+            .map(|p| (p.title.clone(), p.start.as_secs(), p.end.as_secs()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("Chapter 1".to_string(), 0, 30),
+                ("Chapter 2 (part 1)".to_string(), 30, 60),
+                ("Chapter 2 (part 2)".to_string(), 60, 90),
+                ("Chapter 2 (part 3)".to_string(), 90, 100),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_parts_by_time_only() {
+        let offsets = [secs(0), secs(250), secs(590), secs(640), secs(1300)];
+        let parts = plan_parts(&[], &offsets, secs(1300), Some(secs(600)));
+        let titles: Vec<&str> = parts.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["Part 1", "Part 2", "Part 3"]);
+        assert_eq!(parts[1].start, secs(590));
+        assert_eq!(parts[2].start, secs(1300).min(secs(640)));
+
+        assert_eq!(
+            m3u_index(&[(parts[0].clone(), "book-01.wav".to_string())]),
+            "#EXTM3U\n#EXTINF:590,Part 1\nbook-01.wav\n"
+        );
+    }
+
+    // ===========================================
+    // Shard tests
+    // ===========================================
+
+    #[test]
+    fn test_shard_parse_and_chunks() {
+        let shard: Shard = "2/3".parse().unwrap();
+        assert_eq!(shard, Shard { index: 2, count: 3 });
+        assert_eq!(shard.to_string(), "2/3");
+        for invalid in ["0/3", "4/3", "2", "a/b", "1/0"] {
+            assert!(invalid.parse::<Shard>().is_err(), "{invalid}");
+        }
+
+        // Pauses are skipped when dealing out the speech chunks
+        let chunks = vec![
+            speech("One."),
+            Chunk::Pause(Duration::from_millis(50)),
+            speech("Two."),
+            speech("Three."),
+            speech("Four."),
+            speech("Five."),
+        ];
+        assert_eq!(shard.chunks(&chunks), vec![2, 5]);
+        let covered: usize = (1..=3)
+            .map(|index| Shard { index, count: 3 }.chunks(&chunks).len())
+            .sum();
+        assert_eq!(covered, 5);
+
+        assert_eq!(
+            shard.dir(std::path::Path::new("out/book.wav")),
+            std::path::Path::new("out/book.shard-2-of-3")
+        );
+    }
+
+    #[test]
+    fn test_run_job_shard_synthesizes_its_chunks_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("out.wav");
+
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .times(2)
+            .returning(|_| Ok(tone_wav(100)));
+        let engine = engine(backend, &temp_dir);
+
+        let chunks = vec![speech("One."), speech("Two."), speech("Three.")];
+        let mut job = store.create(chunks, &output).unwrap();
+        job.shard = Some(Shard { index: 1, count: 2 });
+        let mut calls = Vec::new();
+        run_job(
+            &engine,
+            &store,
+            &mut job,
+            &RunOptions::default(),
+            |done, total| calls.push((done, total)),
+        )
+        .unwrap();
+
+        assert_eq!(calls, vec![(1, 2), (2, 2)]);
+        let statuses: Vec<ChunkStatus> = job.chunks.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            vec![ChunkStatus::Done, ChunkStatus::Pending, ChunkStatus::Done]
+        );
+        assert!(!output.exists());
+        assert_eq!(store.load(&job.id).unwrap().shard, job.shard);
+    }
+}
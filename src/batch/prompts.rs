@@ -0,0 +1,270 @@
+//! IVR prompt sets for Asterisk and FreeSWITCH.
+//!
+//! A prompt file maps prompt IDs to text, as TOML:
+//!
+//! ```toml
+//! welcome = "Thank you for calling."
+//! main-menu = { text = "For sales, press one.", voice = "amy" }
+//! ```
+//!
+//! Each prompt is synthesized once and written in every format and sample
+//! rate the PBX looks for, under its usual directory layout, so the output
+//! directory can be copied into its sounds directory as is.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::BatchError;
+use super::sheet::in_parallel;
+use crate::audio::{AudioBuffer, Preset, RawFormat};
+use crate::backend::Backend;
+use crate::engine::TTSEngine;
+use crate::text::{Chunk, chunk_text};
+
+/// Sample rates FreeSWITCH picks prompt files from, by the call's rate.
+const FREESWITCH_RATES: [u32; 4] = [8000, 16000, 32000, 48000];
+
+/// Directory prompts go in below the language, keeping them apart from
+/// the PBX's stock sounds.
+const PROMPT_DIR: &str = "custom";
+
+/// PBX whose sounds layout and formats a prompt set is written for.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pbx {
+    /// `<lang>/custom/<id>.ulaw`, `.alaw`, and `.sln16`
+    #[default]
+    Asterisk,
+    /// `<lang>/<region>/<voice>/custom/<rate>/<id>.wav` at 8, 16, 32, and 48 kHz
+    Freeswitch,
+}
+
+/// One file of a prompt: where it goes and how it is encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptFile {
+    /// Path relative to the output directory.
+    pub path: PathBuf,
+    pub sample_rate: u32,
+    /// Headerless samples, or `None` for a 16-bit WAV.
+    pub format: Option<RawFormat>,
+}
+
+impl Pbx {
+    /// Files written for prompt `id`, for a `locale` such as `en-US` and
+    /// the name of the voice speaking the set.
+    pub fn files(self, id: &str, locale: &str, voice: &str) -> Vec<PromptFile> {
+        let locale = locale.to_lowercase().replace('_', "-");
+        let mut subtags = locale.split('-').filter(|s| !s.is_empty());
+        let language = subtags.next().unwrap_or("en");
+
+        match self {
+            // Asterisk looks prompts up by language alone (`en`, not `en_US`)
+            Self::Asterisk => [
+                ("ulaw", 8000, RawFormat::Mulaw),
+                ("alaw", 8000, RawFormat::Alaw),
+                ("sln16", 16000, RawFormat::Slin),
+            ]
+            .into_iter()
+            .map(|(extension, sample_rate, format)| PromptFile {
+                path: Path::new(language)
+                    .join(PROMPT_DIR)
+                    .join(format!("{id}.{extension}")),
+                sample_rate,
+                format: Some(format),
+            })
+            .collect(),
+            Self::Freeswitch => {
+                let mut dir = PathBuf::from(language);
+                if let Some(region) = subtags.next() {
+                    dir.push(region);
+                }
+                dir.push(voice);
+                dir.push(PROMPT_DIR);
+                FREESWITCH_RATES
+                    .into_iter()
+                    .map(|sample_rate| PromptFile {
+                        path: dir.join(sample_rate.to_string()).join(format!("{id}.wav")),
+                        sample_rate,
+                        format: None,
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// One prompt of a set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub id: String,
+    pub text: String,
+    /// Voice for this prompt, instead of the set's.
+    pub voice: Option<String>,
+}
+
+/// A prompt as written in the file: its text, or a table with options.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Definition {
+    Text(String),
+    Full(FullDefinition),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FullDefinition {
+    text: String,
+    #[serde(default)]
+    voice: Option<String>,
+}
+
+/// Prompts read from a prompt file, ordered by ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptSet {
+    pub prompts: Vec<Prompt>,
+}
+
+impl PromptSet {
+    /// Read a prompt file.
+    pub fn read(path: &Path) -> Result<Self, BatchError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+            .map_err(|e| BatchError::InvalidPrompts(format!("{}: {e}", path.display())))
+    }
+
+    /// Parse prompt definitions from TOML.
+    pub fn parse(toml: &str) -> Result<Self, String> {
+        let definitions: BTreeMap<String, Definition> =
+            toml::from_str(toml).map_err(|e| e.message().to_string())?;
+        if definitions.is_empty() {
+            return Err("no prompts".to_string());
+        }
+
+        let mut prompts = Vec::with_capacity(definitions.len());
+        for (id, definition) in definitions {
+            // IDs become file names and the PBX's playback arguments
+            if !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!(
+                    "prompt ID '{id}' may only use letters, digits, '-', and '_'"
+                ));
+            }
+            let (text, voice) = match definition {
+                Definition::Text(text) => (text, None),
+                Definition::Full(FullDefinition { text, voice }) => (text, voice),
+            };
+            if text.trim().is_empty() {
+                return Err(format!("prompt '{id}' has no text"));
+            }
+            prompts.push(Prompt { id, text, voice });
+        }
+        Ok(Self { prompts })
+    }
+}
+
+/// How a prompt set is synthesized and laid out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptOptions {
+    pub pbx: Pbx,
+    pub output_dir: PathBuf,
+    /// Language of the prompts, e.g. `en-US`.
+    pub locale: String,
+    /// Voice for prompts that do not name one.
+    pub voice: Option<String>,
+    pub speed: f32,
+    /// Number of prompts synthesized at once.
+    pub workers: usize,
+}
+
+/// What happened to one prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptResult {
+    pub id: String,
+    pub text: String,
+    /// Voice the prompt was spoken with.
+    pub voice: Option<String>,
+    /// Chunks synthesized for the prompt; empty when it failed.
+    pub chunks: Vec<Chunk>,
+    /// Length of the prompt's audio.
+    pub seconds: f64,
+    /// Files written, under the output directory.
+    pub files: Vec<PathBuf>,
+    pub error: Option<String>,
+}
+
+impl PromptResult {
+    pub fn is_done(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Synthesize every prompt and write its files, calling `on_prompt` as
+/// each one finishes. A failed prompt is recorded rather than stopping the
+/// others.
+///
+/// Results are returned in prompt order.
+pub fn run_prompts<B: Backend>(
+    engine: &TTSEngine<B>,
+    set: &PromptSet,
+    options: &PromptOptions,
+    on_prompt: impl Fn(&PromptResult) + Sync,
+) -> Vec<PromptResult> {
+    in_parallel(&set.prompts, options.workers, |prompt| {
+        let voice = prompt.voice.as_deref().or(options.voice.as_deref());
+        let mut result = PromptResult {
+            id: prompt.id.clone(),
+            text: prompt.text.clone(),
+            voice: voice.map(str::to_string),
+            chunks: Vec::new(),
+            seconds: 0.0,
+            files: Vec::new(),
+            error: None,
+        };
+        match write_prompt(engine, prompt, voice, options) {
+            Ok((chunks, seconds, files)) => {
+                result.chunks = chunks;
+                result.seconds = seconds;
+                result.files = files;
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        on_prompt(&result);
+        result
+    })
+}
+
+fn write_prompt<B: Backend>(
+    engine: &TTSEngine<B>,
+    prompt: &Prompt,
+    voice: Option<&str>,
+    options: &PromptOptions,
+) -> Result<(Vec<Chunk>, f64, Vec<PathBuf>), BatchError> {
+    let chunks = chunk_text(&prompt.text, voice, options.speed)?;
+    let audio = AudioBuffer::from_wav_bytes(&engine.synthesize_chunks(&chunks)?)?;
+
+    let voice_dir = options.voice.as_deref().unwrap_or("default");
+    let mut files = Vec::new();
+    for file in options.pbx.files(&prompt.id, &options.locale, voice_dir) {
+        let conformed = Preset {
+            sample_rate: Some(file.sample_rate),
+            channels: Some(1),
+            ..Preset::default()
+        }
+        .conform(&audio);
+        let data = match file.format {
+            Some(format) => format.encode(&conformed),
+            None => conformed.to_wav_bytes()?,
+        };
+
+        let path = options.output_dir.join(&file.path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, data)?;
+        files.push(path);
+    }
+    Ok((chunks, audio.duration().as_secs_f64(), files))
+}
@@ -0,0 +1,105 @@
+//! Synthesizing one chunk of a job: retrying failures under the error
+//! policy, and re-synthesizing audio that verification finds anomalous.
+
+use crate::audio::AudioBuffer;
+use crate::backend::Backend;
+use crate::engine::{TTSEngine, TTSError};
+
+use super::runner::{ErrorPolicy, RunOptions};
+use super::verify::detect_anomaly;
+
+/// Speed adjustments tried, in order, when a chunk's audio is anomalous.
+const SPEED_NUDGES: [f32; 2] = [0.95, 1.05];
+
+/// Outcome of re-synthesizing an anomalous chunk: (anomaly, attempts, resolved).
+pub(super) type Retry = (String, usize, bool);
+
+/// Synthesize a chunk and, when verification is enabled, retry it at
+/// nudged speeds until the audio passes.
+pub(super) fn synthesize_verified<B: Backend>(
+    engine: &TTSEngine<B>,
+    text: &str,
+    voice: &Option<String>,
+    speed: f32,
+    request_id: &str,
+    options: &RunOptions,
+    retrying: &dyn Fn(&TTSError),
+) -> Result<(Vec<u8>, Option<Retry>), TTSError> {
+    let wav = synthesize_with_retry(engine, text, voice, speed, request_id, options, retrying)?;
+    if !options.verify {
+        return Ok((wav, None));
+    }
+
+    let check = |wav: &[u8]| -> Result<_, TTSError> {
+        let audio = AudioBuffer::from_wav_bytes(wav)?;
+        Ok(detect_anomaly(&audio, options.max_gap))
+    };
+    let Some(anomaly) = check(&wav)? else {
+        return Ok((wav, None));
+    };
+
+    // Backends without speed control get plain retries, relying on sampling
+    // to produce a different take
+    let can_nudge = engine.capabilities().speed;
+    let mut attempts = 1;
+    for nudge in SPEED_NUDGES {
+        attempts += 1;
+        let retry_speed = if can_nudge {
+            (speed * nudge).clamp(0.5, 2.0)
+        } else {
+            speed
+        };
+        let retry = synthesize_with_retry(
+            engine,
+            text,
+            voice,
+            retry_speed,
+            request_id,
+            options,
+            retrying,
+        )?;
+        if check(&retry)?.is_none() {
+            return Ok((retry, Some((anomaly.to_string(), attempts, true))));
+        }
+    }
+
+    Ok((wav, Some((anomaly.to_string(), attempts, false))))
+}
+
+fn synthesize_with_retry<B: Backend>(
+    engine: &TTSEngine<B>,
+    text: &str,
+    voice: &Option<String>,
+    speed: f32,
+    request_id: &str,
+    options: &RunOptions,
+    retrying: &dyn Fn(&TTSError),
+) -> Result<Vec<u8>, TTSError> {
+    let retries = match options.on_error {
+        ErrorPolicy::Retry => options.max_retries,
+        _ => 0,
+    };
+    let mut delay = options.retry_delay;
+    let mut attempt = 0;
+
+    loop {
+        match engine.synthesize_traced(text, voice.clone(), speed, request_id) {
+            Ok(wav) => return Ok(wav),
+            // A missing voice will not appear by retrying
+            Err(e @ TTSError::VoiceNotFound(_)) => return Err(e),
+            Err(TTSError::BackendError(e)) if e.is_permanent() => return Err(e.into()),
+            Err(e) if attempt >= retries => return Err(e),
+            Err(e) => {
+                attempt += 1;
+                retrying(&e);
+                // Wait at least as long as a rate-limited or busy server asks
+                let wait = match &e {
+                    TTSError::BackendError(e) => e.retry_after().map_or(delay, |w| w.max(delay)),
+                    _ => delay,
+                };
+                std::thread::sleep(wait);
+                delay *= 2;
+            }
+        }
+    }
+}
@@ -0,0 +1,421 @@
+//! Executing batch jobs chunk by chunk.
+
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::backend::middleware::new_request_id;
+use crate::backend::{Backend, BackendError};
+use crate::engine::{TTSEngine, TTSError};
+use crate::journal::{Event, Journal};
+use crate::text::Chunk;
+
+use super::BatchError;
+use super::assemble::assemble_job;
+use super::job::{ChunkStatus, Job, JobStore};
+use super::retry::synthesize_verified;
+use super::space::{LOW_SPACE, LowSpace, wait_for_space, write_when_space};
+
+/// Fail when the backend has a different model loaded than `expected`.
+/// Returns false when the health check itself fails, which is left to the
+/// synthesis that follows to retry or report.
+fn check_model<B: Backend>(engine: &TTSEngine<B>, expected: &str) -> Result<bool, BatchError> {
+    match engine.health_check() {
+        Ok(health) if health.loaded_model() != expected => Err(BatchError::ModelChanged {
+            expected: expected.to_string(),
+            found: health.loaded_model(),
+        }),
+        Ok(_) => Ok(true),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Whether `e` means the backend went away or is restarting, so it may
+/// come back with another model loaded.
+fn lost_backend(e: &TTSError) -> bool {
+    matches!(
+        e,
+        TTSError::BackendError(BackendError::ConnectionFailed(_) | BackendError::ServerBusy { .. })
+    )
+}
+
+/// What to do when a chunk fails to synthesize.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop the job at the first failure
+    #[default]
+    Abort,
+    /// Record the failure, insert placeholder silence, and continue
+    Skip,
+    /// Retry the chunk, then stop the job if it still fails
+    Retry,
+}
+
+/// Options controlling a batch run.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub on_error: ErrorPolicy,
+    /// Extra attempts per chunk under [`ErrorPolicy::Retry`].
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled after each attempt.
+    pub retry_delay: Duration,
+    /// Check each chunk for silences and cutoffs, re-synthesizing at
+    /// slightly different speeds when one is found.
+    pub verify: bool,
+    /// Longest pause allowed inside a chunk when verifying.
+    pub max_gap: Duration,
+    /// Journal each chunk's outcome is recorded to.
+    pub journal: Option<Journal>,
+    /// Told when the job waits for disk space.
+    pub on_low_space: Option<LowSpace>,
+    /// How often a job waiting for disk space checks again.
+    pub space_recheck: Duration,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            on_error: ErrorPolicy::Abort,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(2),
+            verify: false,
+            max_gap: Duration::from_secs(1),
+            journal: None,
+            on_low_space: None,
+            space_recheck: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A chunk that was skipped after failing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedChunk {
+    pub index: usize,
+    pub text: String,
+    pub error: String,
+}
+
+/// A chunk that was re-synthesized because its audio was anomalous.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetriedChunk {
+    pub index: usize,
+    pub text: String,
+    /// What was wrong with the first attempt.
+    pub anomaly: String,
+    /// Total syntheses, including the first.
+    pub attempts: usize,
+    /// False if every attempt was anomalous; the first one is kept.
+    pub resolved: bool,
+}
+
+/// Summary of a completed batch run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobReport {
+    /// Chunks replaced by placeholder silence.
+    pub skipped: Vec<FailedChunk>,
+    /// Chunks re-synthesized after verification found an anomaly.
+    pub retried: Vec<RetriedChunk>,
+}
+
+/// Synthesize every unfinished chunk of a job, then assemble the output.
+///
+/// The manifest is saved after each chunk, so an interrupted run can be
+/// continued by calling this again with the reloaded job; previously
+/// skipped chunks are attempted again. `progress` is called with
+/// (finished, total) after each chunk. When the disk runs low or fills
+/// up, the job waits for space rather than failing.
+///
+/// A shard of a job synthesizes only its own chunks and assembles
+/// nothing; the output is made by merging the shards.
+pub fn run_job<B: Backend>(
+    engine: &TTSEngine<B>,
+    store: &JobStore,
+    job: &mut Job,
+    options: &RunOptions,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<JobReport, BatchError> {
+    let todo = plan(engine, job)?;
+    let mut run = JobRun {
+        engine,
+        store,
+        options,
+        job_dir: store.job_dir(&job.id),
+        retried: Vec::new(),
+        recheck_model: Cell::new(false),
+    };
+    run.check_model(job)?;
+
+    for &i in &todo {
+        if job.chunks[i].status == ChunkStatus::Done {
+            continue;
+        }
+        run.preflight(job, i)?;
+        run.run_chunk(job, i)?;
+        progress(finished(job, &todo), todo.len());
+    }
+    run.finalize(job)?;
+
+    Ok(JobReport {
+        skipped: skipped_chunks(job),
+        retried: run.retried,
+    })
+}
+
+/// Check the job's chunks and pick the ones this run synthesizes: all of
+/// them, or a shard's share.
+fn plan<B: Backend>(engine: &TTSEngine<B>, job: &Job) -> Result<Vec<usize>, BatchError> {
+    let chunks: Vec<Chunk> = job.chunks.iter().map(|c| c.chunk.clone()).collect();
+    engine.check_chunks(&chunks)?;
+    Ok(match job.shard {
+        Some(shard) => shard.chunks(&chunks),
+        None => (0..job.chunks.len()).collect(),
+    })
+}
+
+/// How many of the chunks in `todo` are finished.
+fn finished(job: &Job, todo: &[usize]) -> usize {
+    todo.iter()
+        .filter(|&&c| {
+            matches!(
+                job.chunks[c].status,
+                ChunkStatus::Done | ChunkStatus::Skipped
+            )
+        })
+        .count()
+}
+
+/// One run of a job, and the chunks it re-synthesized so far.
+struct JobRun<'a, B: Backend> {
+    engine: &'a TTSEngine<B>,
+    store: &'a JobStore,
+    options: &'a RunOptions,
+    job_dir: PathBuf,
+    retried: Vec<RetriedChunk>,
+    /// Set when the backend was lost, and until its model is checked again.
+    recheck_model: Cell<bool>,
+}
+
+impl<B: Backend> JobRun<'_, B> {
+    fn record(&self, event: Event) {
+        if let Some(journal) = &self.options.journal {
+            journal.record(event);
+        }
+    }
+
+    fn low_space(&self, job: &Job, dir: &Path, free: u64) {
+        self.record(Event::DiskFull {
+            job: job.id.clone(),
+            path: dir.to_path_buf(),
+            free,
+        });
+        if let Some(on_low_space) = &self.options.on_low_space {
+            on_low_space.warn(dir, free);
+        }
+    }
+
+    /// Check the backend has the job's model loaded. The model is checked
+    /// once when the job starts, and again only after the backend was lost.
+    fn check_model(&self, job: &Job) -> Result<(), BatchError> {
+        let Some(expected) = &job.backend_model else {
+            return Ok(());
+        };
+        let checked = check_model(self.engine, expected)?;
+        self.recheck_model.set(!checked);
+        Ok(())
+    }
+
+    /// Before a speech chunk, wait for disk space, and check the model
+    /// again if the backend was lost since it was last checked.
+    fn preflight(&self, job: &Job, i: usize) -> Result<(), BatchError> {
+        if !matches!(job.chunks[i].chunk, Chunk::Speech { .. }) {
+            return Ok(());
+        }
+        wait_for_space(
+            &self.job_dir,
+            LOW_SPACE,
+            self.options.space_recheck,
+            |free| self.low_space(job, &self.job_dir, free),
+        );
+        if self.recheck_model.get() {
+            self.check_model(job)?;
+        }
+        Ok(())
+    }
+
+    /// Synthesize chunk `i`, record the outcome, and save the job.
+    fn run_chunk(&mut self, job: &mut Job, i: usize) -> Result<(), BatchError> {
+        let request_id = new_request_id();
+        let result = match job.chunks[i].chunk.clone() {
+            Chunk::Speech { text, voice, speed } => self
+                .synthesize(job, i, &text, &voice, speed, &request_id)
+                .map(Some),
+            Chunk::Pause(_) | Chunk::Bleep(_) => Ok(None),
+        };
+        if let Chunk::Speech { .. } = job.chunks[i].chunk {
+            job.chunks[i].request_id = Some(request_id);
+        }
+        if result.as_ref().is_err_and(lost_backend) {
+            self.recheck_model.set(true);
+        }
+        // Audio from a backend that came back with another model is dropped
+        if result.is_ok() && self.recheck_model.get() {
+            self.check_model(job)?;
+        }
+        self.store_result(job, i, result)?;
+        self.write(job, &self.job_dir, 0, || self.store.save(job))
+    }
+
+    /// Synthesize a speech chunk, noting a re-synthesis when verification
+    /// asked for one.
+    fn synthesize(
+        &mut self,
+        job: &Job,
+        i: usize,
+        text: &str,
+        voice: &Option<String>,
+        speed: f32,
+        request_id: &str,
+    ) -> Result<Vec<u8>, TTSError> {
+        let retrying = |e: &TTSError| {
+            if lost_backend(e) {
+                self.recheck_model.set(true);
+            }
+            self.record(Event::ChunkRetrying {
+                job: job.id.clone(),
+                chunk: i,
+                error: e.to_string(),
+            })
+        };
+        let (wav, retry) = synthesize_verified(
+            self.engine,
+            text,
+            voice,
+            speed,
+            request_id,
+            self.options,
+            &retrying,
+        )?;
+        if let Some((anomaly, attempts, resolved)) = retry {
+            self.record(Event::ChunkResynthesized {
+                job: job.id.clone(),
+                chunk: i,
+                anomaly: anomaly.clone(),
+                attempts,
+                resolved,
+            });
+            self.retried.push(RetriedChunk {
+                index: i,
+                text: text.to_string(),
+                anomaly,
+                attempts,
+                resolved,
+            });
+        }
+        Ok(wav)
+    }
+
+    /// Write a chunk's audio, or mark it skipped or failed under the error
+    /// policy; a failure saves the job and stops it.
+    fn store_result(
+        &self,
+        job: &mut Job,
+        i: usize,
+        result: Result<Option<Vec<u8>>, TTSError>,
+    ) -> Result<(), BatchError> {
+        match result {
+            Ok(Some(wav)) => self.store_audio(job, i, &wav),
+            Ok(None) => {
+                job.chunks[i].status = ChunkStatus::Done;
+                Ok(())
+            }
+            Err(e) => self.store_failure(job, i, e),
+        }
+    }
+
+    fn store_audio(&self, job: &mut Job, i: usize, wav: &[u8]) -> Result<(), BatchError> {
+        let path = self.store.chunk_path(&job.id, job.chunks[i].index);
+        self.write(job, &self.job_dir, wav.len() as u64, || {
+            Ok(std::fs::write(&path, wav)?)
+        })?;
+        let entry = &mut job.chunks[i];
+        entry.output = Some(path);
+        entry.status = ChunkStatus::Done;
+        entry.error = None;
+        let request_id = entry.request_id.clone();
+        self.record(Event::ChunkDone {
+            job: job.id.clone(),
+            chunk: i,
+            request_id,
+        });
+        Ok(())
+    }
+
+    fn store_failure(&self, job: &mut Job, i: usize, e: TTSError) -> Result<(), BatchError> {
+        let entry = &mut job.chunks[i];
+        entry.error = Some(e.to_string());
+        if self.options.on_error == ErrorPolicy::Skip {
+            entry.status = ChunkStatus::Skipped;
+            entry.output = None;
+            self.record(Event::ChunkSkipped {
+                job: job.id.clone(),
+                chunk: i,
+                error: e.to_string(),
+            });
+            return Ok(());
+        }
+        entry.status = ChunkStatus::Failed;
+        self.record(Event::ChunkFailed {
+            job: job.id.clone(),
+            chunk: i,
+            error: e.to_string(),
+        });
+        self.store.save(job)?;
+        Err(e.into())
+    }
+
+    /// Assemble the output of a whole job; a shard leaves that to merging.
+    fn finalize(&self, job: &Job) -> Result<(), BatchError> {
+        if job.shard.is_some() {
+            return Ok(());
+        }
+        let wav = assemble_job(job)?.to_wav_bytes()?;
+        let dir = job.output.parent().unwrap_or(Path::new("."));
+        self.write(job, dir, wav.len() as u64, || {
+            Ok(crate::scratch::persist(&job.output, &wav)?)
+        })
+    }
+
+    /// Write `bytes` into `dir` with `write`, waiting out a full disk.
+    fn write(
+        &self,
+        job: &Job,
+        dir: &Path,
+        bytes: u64,
+        write: impl FnMut() -> Result<(), BatchError>,
+    ) -> Result<(), BatchError> {
+        write_when_space(
+            dir,
+            bytes,
+            self.options.space_recheck,
+            |free| self.low_space(job, dir, free),
+            write,
+        )
+    }
+}
+
+fn skipped_chunks(job: &Job) -> Vec<FailedChunk> {
+    job.chunks
+        .iter()
+        .filter(|c| c.status == ChunkStatus::Skipped)
+        .map(|c| FailedChunk {
+            index: c.index,
+            text: match &c.chunk {
+                Chunk::Speech { text, .. } => text.clone(),
+                Chunk::Pause(_) | Chunk::Bleep(_) => String::new(),
+            },
+            error: c.error.clone().unwrap_or_default(),
+        })
+        .collect()
+}
@@ -0,0 +1,67 @@
+//! Splitting a job across machines.
+//!
+//! A job run as shard `K/N` synthesizes only every Nth speech chunk,
+//! starting with the Kth, and leaves the rest pending. Every machine that
+//! reads the same input with the same options plans the same chunks, so
+//! the shards together cover the job exactly once; their manifests are
+//! merged afterwards into the full output.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::text::Chunk;
+
+/// One of `count` equal shares of a job, numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    /// Indexes of the speech chunks this shard synthesizes. Speech chunks
+    /// are dealt out in turn, so each shard gets a similar amount of text
+    /// from every part of the job; pauses need no synthesis and belong to
+    /// none.
+    pub fn chunks(&self, chunks: &[Chunk]) -> Vec<usize> {
+        chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| matches!(chunk, Chunk::Speech { .. }))
+            .enumerate()
+            .filter(|(n, _)| n % self.count == self.index - 1)
+            .map(|(_, (i, _))| i)
+            .collect()
+    }
+
+    /// Directory the shard's audio and manifest are written to, next to
+    /// the job's output (`book.wav` -> `book.shard-2-of-4`).
+    pub fn dir(&self, output: &Path) -> PathBuf {
+        let suffix = format!(".shard-{}-of-{}", self.index, self.count);
+        output.with_file_name(crate::paths::stem_with(output, &suffix))
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected K/N with 1 <= K <= N, got '{s}'");
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index: usize = index.trim().parse().map_err(|_| invalid())?;
+        let count: usize = count.trim().parse().map_err(|_| invalid())?;
+        if index == 0 || index > count {
+            return Err(invalid());
+        }
+        Ok(Self { index, count })
+    }
+}
@@ -0,0 +1,269 @@
+//! CSV-driven batches: one output file per row.
+//!
+//! A sheet's `text` and `output` columns are required; `voice` and `speed`
+//! override the defaults for their row when not empty. Any other column is
+//! a variable for `{{ name }}` placeholders in the row's text and output.
+//! Rows are synthesized by a pool of workers, and a failed row is recorded
+//! rather than stopping the others.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::audio::AudioBuffer;
+use crate::backend::Backend;
+use crate::engine::TTSEngine;
+use crate::text::{Chunk, Variables, chunk_text};
+
+use super::BatchError;
+
+/// Columns with a meaning of their own; the rest are template variables.
+const TEXT: &str = "text";
+const OUTPUT: &str = "output";
+const VOICE: &str = "voice";
+const SPEED: &str = "speed";
+
+/// One row of a sheet, with its placeholders filled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetRow {
+    /// Line of the row in the CSV file.
+    pub line: u64,
+    pub text: String,
+    pub voice: Option<String>,
+    pub speed: Option<f32>,
+    pub output: PathBuf,
+}
+
+/// What happened to one row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowResult {
+    pub output: PathBuf,
+    pub text: String,
+    /// Voice the row was spoken with.
+    pub voice: Option<String>,
+    /// Chunks synthesized for the row; empty when it failed.
+    pub chunks: Vec<Chunk>,
+    /// Length of the written audio.
+    pub seconds: f64,
+    pub error: Option<String>,
+}
+
+impl RowResult {
+    pub fn is_done(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A CSV file of rows to synthesize.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    headers: Vec<String>,
+    records: Vec<Vec<String>>,
+    pub rows: Vec<SheetRow>,
+}
+
+impl Sheet {
+    /// Read a sheet, resolving relative outputs against `output_dir` when
+    /// one is given.
+    pub fn read(path: &Path, output_dir: Option<&Path>) -> Result<Self, BatchError> {
+        let invalid = |e: csv::Error| BatchError::InvalidSheet(format!("{}: {e}", path.display()));
+        let mut reader = csv::Reader::from_path(path).map_err(invalid)?;
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(invalid)?
+            .iter()
+            .map(|h| h.trim().to_string())
+            .collect();
+        for required in [TEXT, OUTPUT] {
+            if !headers.iter().any(|h| h == required) {
+                return Err(BatchError::InvalidSheet(format!(
+                    "{}: no '{required}' column",
+                    path.display()
+                )));
+            }
+        }
+
+        let mut sheet = Self {
+            headers,
+            records: Vec::new(),
+            rows: Vec::new(),
+        };
+        let mut outputs = HashSet::new();
+        for record in reader.records() {
+            let record = record.map_err(invalid)?;
+            let line = record.position().map_or(0, |p| p.line());
+            let fields: Vec<String> = record.iter().map(str::to_string).collect();
+            let mut row = sheet
+                .parse_row(line, &fields)
+                .map_err(|e| BatchError::InvalidSheet(format!("{}:{line}: {e}", path.display())))?;
+            if let Some(dir) = output_dir {
+                row.output = dir.join(&row.output);
+            }
+            if !outputs.insert(row.output.clone()) {
+                return Err(BatchError::InvalidSheet(format!(
+                    "{}:{line}: output {} is already written by an earlier row",
+                    path.display(),
+                    row.output.display()
+                )));
+            }
+            sheet.records.push(fields);
+            sheet.rows.push(row);
+        }
+        Ok(sheet)
+    }
+
+    fn parse_row(&self, line: u64, fields: &[String]) -> Result<SheetRow, String> {
+        let field = |name: &str| {
+            self.headers
+                .iter()
+                .position(|h| h == name)
+                .and_then(|i| fields.get(i))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+
+        let mut variables = Variables::new();
+        for (name, value) in self.headers.iter().zip(fields) {
+            if ![TEXT, OUTPUT, VOICE, SPEED].contains(&name.as_str()) {
+                variables.set(name, value).map_err(|e| e.to_string())?;
+            }
+        }
+        let render = |column: &str| match field(column) {
+            Some(template) => variables.render(template).map_err(|e| e.to_string()),
+            None => Err(format!("empty '{column}'")),
+        };
+        let speed = field(SPEED)
+            .map(|s| {
+                s.parse::<f32>()
+                    .map_err(|_| format!("speed '{s}' is not a number"))
+            })
+            .transpose()?;
+
+        Ok(SheetRow {
+            line,
+            text: render(TEXT)?,
+            voice: field(VOICE).map(str::to_string),
+            speed,
+            output: PathBuf::from(render(OUTPUT)?),
+        })
+    }
+
+    /// Write the sheet back with `status`, `seconds`, and `error` columns
+    /// appended from `results`, one per row.
+    pub fn write_results(&self, path: &Path, results: &[RowResult]) -> Result<(), BatchError> {
+        let invalid = |e: csv::Error| BatchError::InvalidSheet(format!("{}: {e}", path.display()));
+        let mut writer = csv::Writer::from_path(path).map_err(invalid)?;
+
+        let mut headers = self.headers.clone();
+        headers.extend(["status", "seconds", "error"].map(String::from));
+        writer.write_record(&headers).map_err(invalid)?;
+        for (record, result) in self.records.iter().zip(results) {
+            let mut record = record.clone();
+            record.resize(self.headers.len(), String::new());
+            record.push(if result.is_done() { "done" } else { "failed" }.to_string());
+            record.push(format!("{:.2}", result.seconds));
+            record.push(result.error.clone().unwrap_or_default());
+            writer.write_record(&record).map_err(invalid)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Synthesize every row with `workers` rows in flight at once, calling
+/// `on_row` as each one finishes. Rows without a voice or speed use
+/// `voice` and `speed`.
+///
+/// Results are returned in row order.
+pub fn run_sheet<B: Backend>(
+    engine: &TTSEngine<B>,
+    sheet: &Sheet,
+    voice: Option<&str>,
+    speed: f32,
+    workers: usize,
+    on_row: impl Fn(&SheetRow, &RowResult) + Sync,
+) -> Vec<RowResult> {
+    in_parallel(&sheet.rows, workers, |row| {
+        let result = run_row(engine, row, voice, speed);
+        on_row(row, &result);
+        result
+    })
+}
+
+/// Run `work` on every item with `workers` threads taking the next item as
+/// they finish, returning the results in item order.
+pub(crate) fn in_parallel<T: Sync, R: Send>(
+    items: &[T],
+    workers: usize,
+    work: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, items.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(i) else {
+                        break;
+                    };
+                    let result = work(item);
+                    results.lock().unwrap()[i] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item is run"))
+        .collect()
+}
+
+/// Synthesize one row to its output file, recording any failure.
+pub(super) fn run_row<B: Backend>(
+    engine: &TTSEngine<B>,
+    row: &SheetRow,
+    voice: Option<&str>,
+    speed: f32,
+) -> RowResult {
+    let voice = row.voice.as_deref().or(voice);
+    let mut result = RowResult {
+        output: row.output.clone(),
+        text: row.text.clone(),
+        voice: voice.map(str::to_string),
+        chunks: Vec::new(),
+        seconds: 0.0,
+        error: None,
+    };
+
+    match synthesize_row(engine, row, voice, speed) {
+        Ok((chunks, seconds)) => {
+            result.chunks = chunks;
+            result.seconds = seconds;
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+fn synthesize_row<B: Backend>(
+    engine: &TTSEngine<B>,
+    row: &SheetRow,
+    voice: Option<&str>,
+    speed: f32,
+) -> Result<(Vec<Chunk>, f64), BatchError> {
+    let chunks = chunk_text(&row.text, voice, row.speed.unwrap_or(speed))?;
+    let wav = engine.synthesize_chunks(&chunks)?;
+    let seconds = AudioBuffer::from_wav_bytes(&wav)?.duration().as_secs_f64();
+
+    if let Some(dir) = row.output.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&row.output, wav)?;
+    Ok((chunks, seconds))
+}
@@ -0,0 +1,207 @@
+//! Disk space: what a job will write, and waiting out a full disk.
+//!
+//! A long job writes every chunk's audio and then the assembled output.
+//! Before it starts, [`check_space`] compares an estimate of those writes
+//! with the space free where they go. While it runs, a disk that fills up
+//! holds the job until space is freed instead of failing it mid-write.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::BatchError;
+use super::job::{ChunkStatus, Job, JobStore};
+use crate::audio::Preset;
+use crate::text::Chunk;
+
+/// Sample rate and channels of the audio the backends send, which chunk
+/// files keep: mono at 24 kHz, the highest rate the bundled backends
+/// produce.
+const BACKEND_SAMPLE_RATE: u32 = 24_000;
+const BACKEND_CHANNELS: u16 = 1;
+
+/// Space kept free on top of what a job needs; a running job waits when
+/// less than this is left.
+pub const LOW_SPACE: u64 = 64 * 1024 * 1024;
+
+type Warn = dyn Fn(&Path, u64) + Send + Sync;
+
+/// Called with the directory and the bytes free when a running job starts
+/// waiting for disk space.
+#[derive(Clone)]
+pub struct LowSpace(Arc<Warn>);
+
+impl LowSpace {
+    pub fn new(callback: impl Fn(&Path, u64) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub fn warn(&self, dir: &Path, free: u64) {
+        (self.0)(dir, free)
+    }
+}
+
+impl std::fmt::Debug for LowSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LowSpace")
+    }
+}
+
+/// A size for people: `512 KB`, `48.0 MB`, `1.25 GB`.
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    match bytes {
+        b if b < KB * KB => format!("{:.0} KB", b / KB),
+        b if b < KB * KB * KB => format!("{:.1} MB", b / KB / KB),
+        b => format!("{:.2} GB", b / KB / KB / KB),
+    }
+}
+
+/// Bytes free to the current user on the filesystem holding `path`, or
+/// `None` when it cannot be told. A path that does not exist yet is
+/// measured at its nearest existing ancestor.
+pub fn free_space(path: &Path) -> Option<u64> {
+    filesystem(path).map(|(_, free)| free)
+}
+
+/// The filesystem holding `path` and the bytes free on it.
+#[cfg(unix)]
+fn filesystem(path: &Path) -> Option<(u64, u64)> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::path::absolute(path).ok()?;
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let c_path = CString::new(existing.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is NUL-terminated and `stat` is written on success
+    if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: statvfs returned 0, so it filled `stat`
+    let stat = unsafe { stat.assume_init() };
+    #[allow(clippy::unnecessary_cast)]
+    let free = stat.f_bavail as u64 * stat.f_frsize as u64;
+    #[allow(clippy::unnecessary_cast)]
+    Some((stat.f_fsid as u64, free))
+}
+
+/// Free space cannot be measured on this platform.
+#[cfg(not(unix))]
+fn filesystem(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+fn audio_bytes<'a>(chunks: impl IntoIterator<Item = &'a Chunk>, per_second: f64) -> u64 {
+    let seconds: f64 = chunks
+        .into_iter()
+        .map(|chunk| chunk.estimated_duration().as_secs_f64())
+        .sum();
+    (seconds * per_second) as u64
+}
+
+/// Bytes a job has yet to write, by directory: the audio of its unfinished
+/// speech chunks in its job directory and, unless it is a shard, the
+/// output next to the output file. The output is assembled in the
+/// backend's format, then saved in `preset`'s, and saved again as parts
+/// when it is split.
+pub fn job_space(
+    store: &JobStore,
+    job: &Job,
+    split: bool,
+    preset: Option<&Preset>,
+) -> Vec<(PathBuf, u64)> {
+    let raw = Preset::default().bytes_per_second(BACKEND_SAMPLE_RATE, BACKEND_CHANNELS);
+    let pending = job
+        .chunks
+        .iter()
+        .filter(|c| c.status != ChunkStatus::Done && matches!(c.chunk, Chunk::Speech { .. }))
+        .map(|c| &c.chunk);
+    let mut needs = vec![(store.job_dir(&job.id), audio_bytes(pending, raw))];
+    if job.shard.is_none() {
+        let saved = preset.map_or(raw, |p| {
+            p.bytes_per_second(BACKEND_SAMPLE_RATE, BACKEND_CHANNELS)
+        });
+        let chunks = || job.chunks.iter().map(|c| &c.chunk);
+        let (assembled, output) = (audio_bytes(chunks(), raw), audio_bytes(chunks(), saved));
+        let dir = match job.output.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let parts = if split { output } else { 0 };
+        needs.push((dir, assembled.max(output) + parts));
+    }
+    needs
+}
+
+/// Fail when what `needs` will write, plus [`LOW_SPACE`], is more than is
+/// free. Directories on one filesystem share its free space; those whose
+/// free space cannot be measured are not checked.
+pub fn check_space(needs: &[(PathBuf, u64)]) -> Result<(), BatchError> {
+    let mut filesystems: Vec<(u64, u64, &Path, u64)> = Vec::new();
+    for (dir, bytes) in needs {
+        let Some((id, free)) = filesystem(dir) else {
+            continue;
+        };
+        match filesystems.iter_mut().find(|(fs, ..)| *fs == id) {
+            Some((_, _, _, needed)) => *needed += bytes,
+            None => filesystems.push((id, free, dir, *bytes)),
+        }
+    }
+    for (_, free, dir, needed) in filesystems {
+        if needed + LOW_SPACE > free {
+            return Err(BatchError::DiskSpace {
+                path: dir.to_path_buf(),
+                needed,
+                free,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Wait until `dir` has `needed` bytes free, calling `warn` before the
+/// first wait.
+pub(crate) fn wait_for_space(
+    dir: &Path,
+    needed: u64,
+    recheck: Duration,
+    mut warn: impl FnMut(u64),
+) {
+    let mut warned = false;
+    while let Some(free) = free_space(dir).filter(|&free| free < needed) {
+        if !warned {
+            warn(free);
+            warned = true;
+        }
+        std::thread::sleep(recheck);
+    }
+}
+
+/// Run `write`, and whenever it fails on a full disk, warn, wait for
+/// `needed` bytes and [`LOW_SPACE`] to be free in `dir`, and try again.
+pub(crate) fn write_when_space<T>(
+    dir: &Path,
+    needed: u64,
+    recheck: Duration,
+    mut warn: impl FnMut(u64),
+    mut write: impl FnMut() -> Result<T, BatchError>,
+) -> Result<T, BatchError> {
+    let mut warned = false;
+    loop {
+        match write() {
+            Err(BatchError::IoError(e)) if e.kind() == std::io::ErrorKind::StorageFull => {
+                if !warned {
+                    warn(free_space(dir).unwrap_or(0));
+                    warned = true;
+                }
+                // Free space can read as enough while quotas or reserved
+                // blocks still refuse the write, so always wait a while
+                std::thread::sleep(recheck);
+                wait_for_space(dir, needed + LOW_SPACE, recheck, |_| {});
+            }
+            result => return result,
+        }
+    }
+}
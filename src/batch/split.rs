@@ -0,0 +1,108 @@
+//! Splitting a job's output into chapter and time-based parts.
+//!
+//! A multi-hour recording is easier to handle in pieces. Parts start at
+//! the job's chapters, and with a maximum length, long chapters are cut
+//! again at the chunk boundary nearest each multiple of it, so no part
+//! starts mid-sentence.
+
+use std::time::Duration;
+
+use super::job::JobChapter;
+
+/// One piece of a job's output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub title: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+impl Part {
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+/// The parts of an output of length `total`, from the start time of each
+/// chunk in it (`offsets`), its chapters, and the longest a part should
+/// run before it is cut at a chunk boundary (`every`).
+pub fn plan_parts(
+    chapters: &[JobChapter],
+    offsets: &[Duration],
+    total: Duration,
+    every: Option<Duration>,
+) -> Vec<Part> {
+    let offset = |chunk: usize| offsets.get(chunk).copied().unwrap_or(total).min(total);
+    let mut sections: Vec<(String, Duration)> = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| {
+            let start = if i == 0 {
+                Duration::ZERO
+            } else {
+                offset(chapter.start)
+            };
+            (chapter.title.clone(), start)
+        })
+        .collect();
+    if sections.is_empty() {
+        sections.push((String::new(), Duration::ZERO));
+    }
+
+    let mut parts = Vec::new();
+    for (i, (title, start)) in sections.iter().enumerate() {
+        let end = sections.get(i + 1).map_or(total, |(_, next)| *next);
+        if end <= *start {
+            continue;
+        }
+        let mut cuts = vec![*start];
+        if let Some(every) = every.filter(|e| !e.is_zero()) {
+            let mut last = *start;
+            while last + every < end {
+                let target = last + every;
+                let nearest = offsets
+                    .iter()
+                    .copied()
+                    .filter(|b| *b > last && *b < end)
+                    .min_by_key(|b| b.abs_diff(target));
+                match nearest {
+                    Some(cut) => {
+                        cuts.push(cut);
+                        last = cut;
+                    }
+                    None => break,
+                }
+            }
+        }
+        cuts.push(end);
+
+        let pieces = cuts.len() - 1;
+        for (n, pair) in cuts.windows(2).enumerate() {
+            let title = match (title.is_empty(), pieces) {
+                (false, 1) => title.clone(),
+                (false, _) => format!("{title} (part {})", n + 1),
+                (true, _) => format!("Part {}", parts.len() + 1),
+            };
+            parts.push(Part {
+                title,
+                start: pair[0],
+                end: pair[1],
+            });
+        }
+    }
+    parts
+}
+
+/// An extended M3U playlist of the parts, in order, with each file named
+/// relative to the playlist.
+pub fn m3u_index(parts: &[(Part, String)]) -> String {
+    let mut index = String::from("#EXTM3U\n");
+    for (part, file) in parts {
+        index.push_str(&format!(
+            "#EXTINF:{},{}\n{file}\n",
+            part.duration().as_secs_f64().round() as u64,
+            part.title
+        ));
+    }
+    index
+}
@@ -0,0 +1,201 @@
+//! JSON-lines job streams, for orchestrators driving synthesis over a pipe.
+//!
+//! Each input line is a job such as
+//! `{"id": "a1", "text": "Hello.", "voice": "amy", "output": "a1.wav"}`,
+//! and a result line is written for each job as it finishes:
+//! `{"line": 1, "id": "a1", "output": "a1.wav", "status": "done", "seconds": 0.8}`.
+//! A job that fails, or a line that is not a valid job, gets a result with
+//! `"status": "failed"` and an `error`; the stream carries on.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use super::BatchError;
+use super::sheet::{RowResult, SheetRow, run_row};
+use crate::backend::Backend;
+use crate::engine::TTSEngine;
+use crate::text::Preprocessor;
+
+/// One job read from the stream.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamJob {
+    /// Caller's identifier, echoed in the result.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub text: String,
+    #[serde(default)]
+    pub voice: Option<String>,
+    #[serde(default)]
+    pub speed: Option<f32>,
+    pub output: PathBuf,
+}
+
+/// The result line written for one job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamResult {
+    /// Line of the job in the input.
+    pub line: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    /// `done` or `failed`.
+    pub status: String,
+    /// Length of the written audio.
+    pub seconds: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Options controlling a stream run.
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    /// Voice for jobs that do not name one.
+    pub voice: Option<String>,
+    /// Speed for jobs that do not give one.
+    pub speed: f32,
+    /// Directory relative outputs are written under.
+    pub output_dir: Option<PathBuf>,
+    /// Jobs synthesized at once.
+    pub workers: usize,
+    /// Applied to each job's text before it is synthesized.
+    pub preprocessor: Preprocessor,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            speed: 1.0,
+            output_dir: None,
+            workers: 1,
+            preprocessor: Preprocessor::new(),
+        }
+    }
+}
+
+/// Jobs finished by a stream run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamSummary {
+    pub done: usize,
+    pub failed: usize,
+}
+
+/// Run every job read from `input` until it ends, writing a result line to
+/// `output` as each finishes and calling `on_job` for each synthesized job.
+///
+/// With more than one worker, results are written in the order jobs finish.
+pub fn run_stream<B: Backend>(
+    engine: &TTSEngine<B>,
+    input: impl BufRead + Send,
+    output: impl Write + Send,
+    options: &StreamOptions,
+    on_job: impl Fn(&RowResult) + Sync,
+) -> Result<StreamSummary, BatchError> {
+    let input = Mutex::new((0, input.lines()));
+    let output = Mutex::new(output);
+    let summary = Mutex::new(StreamSummary::default());
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..options.workers.max(1))
+            .map(|_| {
+                scope.spawn(|| -> Result<(), BatchError> {
+                    loop {
+                        let (line, text) = {
+                            let mut input = input.lock().unwrap();
+                            let Some(text) = input.1.next().transpose()? else {
+                                return Ok(());
+                            };
+                            input.0 += 1;
+                            (input.0, text)
+                        };
+                        if text.trim().is_empty() {
+                            continue;
+                        }
+
+                        let result = run_line(engine, line, &text, options, &on_job);
+                        {
+                            let mut summary = summary.lock().unwrap();
+                            match result.error {
+                                None => summary.done += 1,
+                                Some(_) => summary.failed += 1,
+                            }
+                        }
+                        let mut output = output.lock().unwrap();
+                        serde_json::to_writer(&mut *output, &result)?;
+                        writeln!(output)?;
+                        output.flush()?;
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().expect("stream worker panicked"))
+    })?;
+
+    Ok(summary.into_inner().unwrap())
+}
+
+fn run_line<B: Backend>(
+    engine: &TTSEngine<B>,
+    line: u64,
+    text: &str,
+    options: &StreamOptions,
+    on_job: &impl Fn(&RowResult),
+) -> StreamResult {
+    let job: StreamJob = match serde_json::from_str(text) {
+        Ok(job) => job,
+        Err(e) => {
+            return StreamResult {
+                line,
+                id: None,
+                output: None,
+                status: "failed".to_string(),
+                seconds: 0.0,
+                error: Some(format!("invalid job: {e}")),
+            };
+        }
+    };
+
+    let output = match &options.output_dir {
+        Some(dir) => dir.join(&job.output),
+        None => job.output,
+    };
+    let row = SheetRow {
+        line,
+        text: options.preprocessor.process(&job.text),
+        voice: job.voice,
+        speed: job.speed,
+        output,
+    };
+    let result = run_row(engine, &row, options.voice.as_deref(), options.speed);
+    if result.is_done() {
+        on_job(&result);
+    }
+
+    StreamResult {
+        line,
+        id: job.id,
+        output: Some(result.output.clone()),
+        status: if result.is_done() { "done" } else { "failed" }.to_string(),
+        seconds: result.seconds,
+        error: result.error,
+    }
+}
+
+/// Open `-` as standard input, anything else as a file.
+pub fn open_input(path: &Path) -> Result<Box<dyn BufRead + Send>, BatchError> {
+    if path == Path::new("-") {
+        Ok(Box::new(std::io::BufReader::new(std::io::stdin())))
+    } else {
+        Ok(Box::new(std::io::BufReader::new(std::fs::File::open(
+            path,
+        )?)))
+    }
+}
@@ -0,0 +1,70 @@
+//! Verifying synthesized chunks for common model failures.
+//!
+//! Some models (notably F5) occasionally produce a clip that goes silent
+//! halfway through or stops mid-word. Those clips decode fine and would
+//! otherwise slip into the assembled output unnoticed.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::align::voiced_regions;
+use crate::audio::AudioBuffer;
+
+/// Length of the clip ending inspected for an abrupt cutoff, in seconds.
+const TAIL_SECS: f64 = 0.02;
+
+/// A tail louder than this fraction of the loudest frame counts as cut off.
+const CUTOFF_RATIO: f32 = 0.3;
+
+/// A defect found in a synthesized chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// No audible speech at all.
+    Silent,
+    /// A pause inside the clip longer than the allowed gap, in seconds.
+    Gap { start: f64, end: f64 },
+    /// Speech is still at full level when the clip ends.
+    Cutoff,
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::Silent => write!(f, "silent clip"),
+            Anomaly::Gap { start, end } => write!(f, "gap from {start:.2}s to {end:.2}s"),
+            Anomaly::Cutoff => write!(f, "abrupt cutoff"),
+        }
+    }
+}
+
+/// Check a clip for silence, mid-clip gaps longer than `max_gap`, and an
+/// abrupt ending. Leading and trailing silence is not a gap.
+pub fn detect_anomaly(audio: &AudioBuffer, max_gap: Duration) -> Option<Anomaly> {
+    let regions = voiced_regions(audio);
+    if regions.is_empty() {
+        return Some(Anomaly::Silent);
+    }
+
+    let max_gap = max_gap.as_secs_f64();
+    if let Some(gap) = regions
+        .windows(2)
+        .find(|pair| pair[1].0 - pair[0].1 > max_gap)
+    {
+        return Some(Anomaly::Gap {
+            start: gap[0].1,
+            end: gap[1].0,
+        });
+    }
+
+    let samples = audio.mono();
+    let tail_len = ((f64::from(audio.sample_rate) * TAIL_SECS) as usize).max(1);
+    let rms =
+        |frame: &[f32]| (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+    let loudest = samples.chunks(tail_len).map(rms).fold(0.0, f32::max);
+    let tail = &samples[samples.len().saturating_sub(tail_len)..];
+    if rms(tail) > loudest * CUTOFF_RATIO {
+        return Some(Anomaly::Cutoff);
+    }
+
+    None
+}
@@ -0,0 +1,305 @@
+//! The subcommands of `open-tts-rs`.
+
+use chrono::NaiveTime;
+use clap::Subcommand;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{LogsCommand, ReportFormat, UsageCommand, VoicesCommand};
+use crate::batch::Pbx;
+use crate::project::{Schedule, parse_time_of_day};
+use crate::text::parse_duration;
+
+/// Long-running modes.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Keep backend connections, uploaded references, and the voice store warm;
+    /// `-g` calls forward to a running daemon automatically
+    Daemon,
+
+    /// Hold the running daemon's synthesis between chunks, freeing the GPU until `resume`
+    Pause,
+
+    /// Let the running daemon's paused synthesis continue
+    Resume,
+
+    /// List the available backends with their default ports and capabilities
+    Backends,
+
+    /// Find running backends, test a sample synthesis, optionally clone a first voice,
+    /// and save a config profile
+    Setup,
+
+    /// Serve synthesis over HTTP, with WebSocket streaming at /stream and a job queue at /jobs
+    Serve {
+        /// Address to listen on
+        #[arg(long, value_name = "ADDR", default_value = crate::server::DEFAULT_LISTEN)]
+        listen: String,
+
+        /// Directory holding queued jobs and their audio [default: ~/.open-tts-rs/queue]
+        #[arg(long, value_name = "DIR")]
+        queue_dir: Option<PathBuf>,
+
+        /// Number of queued jobs synthesized at once
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        workers: usize,
+
+        /// Most queued jobs one client may have running at once
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        max_per_client: usize,
+
+        /// Proxy address trusted to set X-Client-Id and job priority (repeatable)
+        #[arg(long, value_name = "IP")]
+        trusted_proxy: Vec<IpAddr>,
+
+        /// Host job callbacks may reach even on a private or loopback address (repeatable)
+        #[arg(long, value_name = "HOST")]
+        callback_host: Vec<String>,
+    },
+
+    /// Speak messages from an MQTT topic and publish the audio (Home Assistant and
+    /// other home automation systems)
+    Mqtt {
+        /// Broker to connect to, with optional credentials
+        #[arg(long, value_name = "URL", default_value = "mqtt://localhost:1883")]
+        broker: String,
+
+        /// Topic to read text from
+        #[arg(long, value_name = "TOPIC", default_value = crate::server::DEFAULT_TOPIC)]
+        topic: String,
+
+        /// Topic to publish audio on (failures go to <TOPIC>/error)
+        #[arg(long, value_name = "TOPIC", default_value = crate::server::DEFAULT_RESPONSE_TOPIC)]
+        response_topic: String,
+
+        /// Save audio here and publish its path instead of the WAV bytes
+        #[arg(long, value_name = "DIR")]
+        save_dir: Option<PathBuf>,
+
+        /// URL the save directory is served at, published alongside the path
+        #[arg(long, value_name = "URL", requires = "save_dir")]
+        url_base: Option<String>,
+    },
+
+    /// Speak what the microphone hears again in the -n voice (a live voice changer);
+    /// --to-virtual-mic sends it to calls instead of the speakers
+    Relay {
+        /// Whisper server with the OpenAI transcription API
+        #[arg(long, value_name = "URL", default_value = crate::relay::DEFAULT_ASR_URL)]
+        asr_url: String,
+
+        /// Transcription model the server should use
+        #[arg(long, value_name = "MODEL", default_value = "whisper-1")]
+        asr_model: String,
+
+        /// Length of each recorded segment; shorter is faster but splits more words
+        #[arg(long, value_name = "DURATION", default_value = "4s", value_parser = parse_duration)]
+        segment: Duration,
+    },
+
+    /// Follow a log file and speak the lines matching --filter as alerts
+    Tail {
+        /// Log file to follow; truncation and rotation are handled
+        file: PathBuf,
+
+        /// Speak the alerts; without it they are only printed
+        #[arg(long)]
+        speak: bool,
+
+        /// Only lines matching this regular expression, e.g. 'ERROR|CRITICAL'
+        #[arg(long, value_name = "REGEX")]
+        filter: Option<String>,
+
+        /// Shortest time between alerts; lines held back meanwhile are counted in the next
+        #[arg(long, value_name = "DURATION", default_value = "5s", value_parser = parse_duration)]
+        interval: Duration,
+
+        /// Skip lines like one spoken this recently (numbers such as timestamps are ignored)
+        #[arg(long, value_name = "DURATION", default_value = "60s", value_parser = parse_duration)]
+        dedup: Duration,
+
+        /// Also alert on the lines already in the file
+        #[arg(long)]
+        from_start: bool,
+    },
+
+    /// Build a project's chapters, re-synthesizing only what changed since the last build
+    Build {
+        /// Project directory [default: the nearest directory with a project.toml]
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+
+    /// Build a project at the times of a schedule, writing a completion report after each build
+    Schedule {
+        /// Project directory [default: the nearest directory with a project.toml]
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+
+        /// Build every day at this local time, e.g. 02:00 [default: the project's [schedule]]
+        #[arg(long, value_name = "HH:MM", value_parser = parse_time_of_day, conflicts_with = "cron")]
+        at: Option<NaiveTime>,
+
+        /// Build at the times of a cron expression, e.g. "0 2 * * 1-5"
+        #[arg(long, value_name = "SPEC")]
+        cron: Option<Schedule>,
+
+        /// Start no more chapters after this local time, e.g. 06:00; the rest wait for the
+        /// next build
+        #[arg(long, value_name = "HH:MM", value_parser = parse_time_of_day)]
+        until: Option<NaiveTime>,
+
+        /// Sentences synthesized at once [default: the project's, else 1]
+        #[arg(long, value_name = "N")]
+        workers: Option<usize>,
+
+        /// Where to write the completion report [default: <output dir>/build-report.json]
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+
+        /// Build once, at the next scheduled time, then exit
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Synthesize each row of a CSV file, or each job of a JSON-lines stream, to its own
+    /// output file
+    Batch {
+        /// CSV file of rows to synthesize (columns: text, output, and optionally voice,
+        /// speed, and variables for {{name}} placeholders)
+        #[arg(long, value_name = "FILE", required_unless_present = "jsonl")]
+        csv: Option<PathBuf>,
+
+        /// JSON-lines jobs to synthesize ("-" for stdin), writing a JSON-lines result per
+        /// job to stdout
+        #[arg(long, value_name = "FILE", conflicts_with = "csv")]
+        jsonl: Option<PathBuf>,
+
+        /// Directory relative output paths are written under [default: current directory]
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+
+        /// Number of rows synthesized at once
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        workers: usize,
+
+        /// The CSV with status, seconds, and error columns added [default: <csv>.results.csv]
+        #[arg(long, value_name = "FILE", requires = "csv")]
+        results: Option<PathBuf>,
+    },
+
+    /// Synthesize an IVR prompt set from a TOML file of prompt IDs and text, in the
+    /// formats and directory layout of a PBX, with a manifest
+    Prompts {
+        /// Prompt file (`id = "text"` per line)
+        file: PathBuf,
+
+        /// PBX to write the prompt set for
+        #[arg(long, value_enum, default_value = "asterisk")]
+        pbx: Pbx,
+
+        /// Directory to write the prompt set to, laid out like the PBX's sounds directory
+        #[arg(long, value_name = "DIR", default_value = "prompts")]
+        output_dir: PathBuf,
+
+        /// Language of the prompts, e.g. en-US or fr-CA [default: --locale, or en-US]
+        #[arg(long, value_name = "TAG")]
+        language: Option<String>,
+
+        /// Number of prompts synthesized at once
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        workers: usize,
+    },
+
+    /// Assemble the output of an -i job run with --shard from the directories or
+    /// manifests of all its shards
+    Merge {
+        /// Shard directories (<output>.shard-K-of-N) or their manifest.json files
+        #[arg(value_name = "SHARD", required = true)]
+        shards: Vec<PathBuf>,
+    },
+
+    /// Synthesize one chunk of a finished -i job again and splice it into the output
+    Rerender {
+        /// Job ID, as printed when the job started
+        #[arg(long, value_name = "ID")]
+        job: String,
+
+        /// Index of the chunk, as in its file name (chunk-00042.wav is 42)
+        #[arg(long, value_name = "N")]
+        chunk: usize,
+
+        /// Random seed for the new take [default: random]
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+
+    /// Report words likely to be mispronounced, with suggested lexicon entries
+    Lint {
+        /// Text files to check [default: the chapters of the current project]
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// Project directory [default: the nearest directory with a project.toml]
+        #[arg(long, value_name = "DIR", conflicts_with = "files")]
+        dir: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+
+    /// Predict audio length and generation time from past usage
+    Estimate {
+        /// Text files to estimate [default: the text given by -g]
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+
+    /// Compare two audio files: duration and loudness deltas and spectral similarity
+    Diff {
+        /// Reference audio, such as a render from before a backend upgrade
+        a: PathBuf,
+
+        /// Audio to compare against it
+        b: PathBuf,
+
+        /// Exit non-zero when the similarity is below this score (0.0 to 1.0)
+        #[arg(long, value_name = "SCORE")]
+        threshold: Option<f64>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+
+    /// Check an audio file for the watermark of --watermark-key; exits
+    /// non-zero when it is absent
+    VerifyWatermark {
+        /// Audio file to check (WAV, mu-law WAV, MP3, FLAC, or Ogg)
+        file: PathBuf,
+    },
+
+    /// Characters synthesized and audio generated, per project and voice
+    Usage {
+        #[command(subcommand)]
+        command: UsageCommand,
+    },
+
+    /// The journals of past runs: jobs started and resumed, each chunk's outcome, files written
+    Logs {
+        #[command(subcommand)]
+        command: LogsCommand,
+    },
+
+    /// Deleted voices, library backups, voice packs, embeddings, and remote sync
+    Voices {
+        #[command(subcommand)]
+        command: VoicesCommand,
+    },
+}
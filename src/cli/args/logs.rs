@@ -0,0 +1,20 @@
+//! `open-tts-rs logs` subcommands.
+
+use clap::Subcommand;
+
+use super::ReportFormat;
+
+/// `logs` subcommands.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum LogsCommand {
+    /// Show every run of a batch job, or the last run
+    Show {
+        /// Job ID, as printed when the job started [default: the last run]
+        #[arg(value_name = "JOB")]
+        job: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+}
@@ -0,0 +1,71 @@
+//! The `-r` reference argument.
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Parsed reference audio with transcript.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    /// Path to the audio file.
+    pub audio_path: PathBuf,
+    /// Transcript of the audio content.
+    pub transcript: String,
+}
+
+/// Errors that can occur when parsing a reference string.
+#[derive(Error, Debug)]
+pub enum ReferenceParseError {
+    #[error("Invalid format: {0}. Expected 'file.wav;transcript text'")]
+    InvalidFormat(String),
+
+    #[error("Audio file not found: {0}")]
+    FileNotFound(PathBuf),
+
+    #[error("Transcript cannot be empty")]
+    EmptyTranscript,
+}
+
+impl Reference {
+    /// Parse a reference from "file.wav;transcript" format.
+    ///
+    /// # Arguments
+    /// * `input` - String in format "path/to/audio.wav;transcript text"
+    ///
+    /// # Returns
+    /// * `Ok(Reference)` if parsing succeeds
+    /// * `Err(ReferenceParseError)` if parsing fails
+    ///
+    /// # Examples
+    /// ```
+    /// use open_tts_rs::cli::Reference;
+    /// let reference = Reference::parse("audio.wav;Hello world");
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, ReferenceParseError> {
+        // Split on first semicolon only (transcript may contain semicolons)
+        let parts: Vec<&str> = input.splitn(2, ';').collect();
+
+        if parts.len() != 2 {
+            return Err(ReferenceParseError::InvalidFormat(
+                "Missing semicolon separator".to_string(),
+            ));
+        }
+
+        let audio_path = PathBuf::from(parts[0].trim());
+        let transcript = parts[1].trim().to_string();
+
+        // Validate file exists
+        if !audio_path.exists() {
+            return Err(ReferenceParseError::FileNotFound(audio_path));
+        }
+
+        // Validate transcript is not empty
+        if transcript.is_empty() {
+            return Err(ReferenceParseError::EmptyTranscript);
+        }
+
+        Ok(Self {
+            audio_path,
+            transcript,
+        })
+    }
+}
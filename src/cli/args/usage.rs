@@ -0,0 +1,21 @@
+//! `open-tts-rs usage` subcommands.
+
+use chrono::NaiveDate;
+use clap::Subcommand;
+
+use super::ReportFormat;
+
+/// Usage ledger commands.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum UsageCommand {
+    /// Summarize the usage ledger
+    Report {
+        /// Only count usage on or after this date (YYYY-MM-DD, UTC)
+        #[arg(long, value_name = "DATE")]
+        since: Option<NaiveDate>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+}
@@ -0,0 +1,83 @@
+//! `open-tts-rs voices` subcommands.
+
+use clap::Subcommand;
+use std::path::PathBuf;
+
+use super::ReportFormat;
+
+/// Voice trash, backup, install, export, and creation commands.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum VoicesCommand {
+    /// List deleted voices still in the trash
+    Trash,
+
+    /// Bring a deleted voice back, or restore a library backup, extracting
+    /// voices on the backend again
+    Restore {
+        /// Name of the deleted voice
+        #[arg(required_unless_present = "archive", conflicts_with = "archive")]
+        name: Option<String>,
+
+        /// Backup archive to restore the whole library from
+        #[arg(long, value_name = "FILE")]
+        archive: Option<PathBuf>,
+    },
+
+    /// Create a synthetic voice that imitates no real speaker; --seed draws
+    /// the same one again
+    Random {
+        /// Name to save the voice as
+        #[arg(long, value_name = "NAME")]
+        save: String,
+    },
+
+    /// Summarize the licenses and obligations of the voices a generation
+    /// manifest used, or of every saved voice
+    LicenseReport {
+        /// Manifest written by a batch, build, or prompts run
+        manifest: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+
+    /// Install the voices of a voice pack (.ottsvpack) from a URL or file,
+    /// extracting each on the backend
+    Install {
+        /// URL or path of the pack
+        source: String,
+
+        /// SHA-256 of the pack as published by its author; required for URLs
+        /// [default for files: read from <FILE>.sha256]
+        #[arg(long, value_name = "HEX")]
+        sha256: Option<String>,
+    },
+
+    /// Export a voice's speaker embedding for other ML tools; models without
+    /// one get a local voiceprint of the reference
+    Embedding {
+        /// Name of the saved voice
+        name: String,
+
+        /// File to write, .npy or .json
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Upload the local voice store to the remote configured in the config file
+    #[cfg(feature = "remote")]
+    PushRemote,
+
+    /// Download the voice store from the remote configured in the config file
+    #[cfg(feature = "remote")]
+    PullRemote,
+
+    /// Back up voice metadata, reference audio, config, and word lists to a tar archive
+    Backup {
+        /// Archive to write; compressed with zstd when it ends in .zst
+        /// [default: voices-YYYY-MM-DD.tar.zst]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
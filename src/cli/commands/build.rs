@@ -0,0 +1,228 @@
+//! Building a project, once or on a schedule.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+
+use crate::backend::Backend;
+use crate::cli::{Args, Command};
+use crate::engine::TTSEngine;
+use crate::project::{
+    BuildOptions, BuildReport, ChapterReport, ChapterSummary, Project, Schedule, build_project,
+};
+use crate::usage::UsageRecord;
+
+/// Build a project's changed chapters and record their usage.
+pub fn run_build<B: Backend>(
+    engine: &TTSEngine<B>,
+    project: &Project,
+    args: &Args,
+    options: &BuildOptions,
+    mut on_chapter: impl FnMut(&ChapterReport),
+) -> Result<()> {
+    super::warn_glossary_conflicts(&project.preprocessor()?.glossary_conflicts());
+    println!(
+        "Building {} ({} chapters)",
+        project.root().display(),
+        project.file().chapters.len()
+    );
+    let mut built = 0;
+    // Chapters are built one after another, so each took the time since
+    // the last report
+    let mut started = Instant::now();
+    build_project(engine, project, args.model, options, |chapter| {
+        let status = if chapter.built {
+            built += 1;
+            format!(
+                "{} synthesized, {} cached",
+                chapter.synthesized.len(),
+                chapter.cached
+            )
+        } else {
+            "up to date".to_string()
+        };
+        let output = chapter
+            .output
+            .strip_prefix(project.root())
+            .unwrap_or(&chapter.output);
+        println!("\r  {:<40}", format!("{}: {status}", output.display()));
+        super::record_usage(Ok(UsageRecord::for_chunks(
+            &chapter.synthesized,
+            chapter.seconds,
+            Some(started.elapsed()),
+            args.model.name(),
+            args.project.as_deref(),
+        )));
+        started = Instant::now();
+        on_chapter(chapter);
+    })
+    .context("Build failed")?;
+    println!("Built {built} chapter(s)");
+    Ok(())
+}
+
+/// When `schedule` builds a project and where it reports.
+pub struct ScheduledBuild {
+    schedule: Schedule,
+    /// Local time after which no more chapters are started.
+    until: Option<NaiveTime>,
+    workers: usize,
+    report: PathBuf,
+    once: bool,
+}
+
+impl ScheduledBuild {
+    /// The scheduled build `command` asks for, taking what it leaves out
+    /// from the project's `[schedule]`; `None` unless it is `schedule`.
+    pub fn from_command(project: &Project, command: Option<&Command>) -> Result<Option<Self>> {
+        let Some(Command::Schedule {
+            at,
+            cron,
+            until,
+            workers,
+            report,
+            once,
+            ..
+        }) = command
+        else {
+            return Ok(None);
+        };
+        let config = &project.file().schedule;
+        let schedule = match (at, cron) {
+            (Some(at), _) => Schedule::daily(*at),
+            (None, Some(cron)) => cron.clone(),
+            (None, None) => config
+                .schedule()
+                .map_err(anyhow::Error::msg)?
+                .context("No schedule: give --at or --cron, or set [schedule] in project.toml")?,
+        };
+        Ok(Some(Self {
+            schedule,
+            until: match until {
+                Some(until) => Some(*until),
+                None => config.until().map_err(anyhow::Error::msg)?,
+            },
+            workers: workers.or(config.workers).unwrap_or(1),
+            report: report
+                .clone()
+                .unwrap_or_else(|| project.output_dir().join("build-report.json")),
+            once: *once,
+        }))
+    }
+}
+
+/// Build a project at each time of its schedule, writing a completion
+/// report after each build. A failed build is reported and the next one
+/// still runs, unless `--once` was given.
+pub fn run_schedule<B: Backend>(
+    engine: &TTSEngine<B>,
+    project: &Project,
+    scheduled: &ScheduledBuild,
+    args: &Args,
+) -> Result<()> {
+    loop {
+        let next = scheduled
+            .schedule
+            .next_after(chrono::Local::now().naive_local())
+            .with_context(|| format!("Schedule '{}' never runs", scheduled.schedule))?;
+        println!(
+            "Next build of {} at {}",
+            project.root().display(),
+            next.format("%Y-%m-%d %H:%M")
+        );
+        sleep_until(next);
+
+        let report = scheduled_build(engine, project, scheduled, args);
+        if let Some(parent) = scheduled.report.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&scheduled.report, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Failed to write {}", scheduled.report.display()))?;
+        match &report.error {
+            Some(error) => eprintln!("Scheduled build failed: {error}"),
+            None => println!(
+                "Scheduled build done: {} built, {} up to date, {} deferred, {:.1}s of audio",
+                report.built(),
+                report.chapters.len() - report.built(),
+                report.deferred.len(),
+                report.seconds()
+            ),
+        }
+        println!("Report saved to {}", scheduled.report.display());
+
+        if scheduled.once {
+            return match report.error {
+                Some(error) => anyhow::bail!("Scheduled build failed: {error}"),
+                None => Ok(()),
+            };
+        }
+    }
+}
+
+/// Sleep until a local time, waking each minute so a clock change or a
+/// suspended machine does not oversleep by much.
+fn sleep_until(time: chrono::NaiveDateTime) {
+    loop {
+        let Ok(left) = (time - chrono::Local::now().naive_local()).to_std() else {
+            return;
+        };
+        if left.is_zero() {
+            return;
+        }
+        std::thread::sleep(left.min(Duration::from_secs(60)));
+    }
+}
+
+/// Run one scheduled build, stopping before any chapter once the
+/// `until` time has passed.
+fn scheduled_build<B: Backend>(
+    engine: &TTSEngine<B>,
+    project: &Project,
+    scheduled: &ScheduledBuild,
+    args: &Args,
+) -> BuildReport {
+    let started_at = chrono::Local::now();
+    let until = scheduled.until.and_then(|until| {
+        // The next `until` after the start, so a window of 22:00 to 06:00
+        // closes the following morning
+        let start = started_at.naive_local();
+        let mut end = start.date().and_time(until);
+        if end <= start {
+            end += chrono::TimeDelta::days(1);
+        }
+        Some(Instant::now() + (end - start).to_std().ok()?)
+    });
+    let options = BuildOptions {
+        workers: scheduled.workers,
+        until,
+    };
+
+    let mut chapters = Vec::new();
+    let result = run_build(engine, project, args, &options, |chapter| {
+        chapters.push(ChapterSummary::new(chapter, project.root()));
+    });
+    let deferred = match result {
+        Ok(()) => project.file().chapters[chapters.len()..]
+            .iter()
+            .map(|chapter| {
+                let output = project.output_path(chapter);
+                output
+                    .strip_prefix(project.root())
+                    .map(Path::to_path_buf)
+                    .unwrap_or(output)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    BuildReport {
+        project: project.name(),
+        started_at: started_at.to_rfc3339(),
+        finished_at: chrono::Local::now().to_rfc3339(),
+        chapters,
+        deferred,
+        error: result.err().map(|e| format!("{e:#}")),
+    }
+}
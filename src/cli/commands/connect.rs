@@ -0,0 +1,282 @@
+//! Connecting the engine to a backend and reporting its progress.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use super::post::cleanup;
+use crate::backend::{
+    Backend, BackendError, BackendRegistry, CompositeBackend, HealthWait, SynthesisEvent,
+    wait_for_health,
+};
+use crate::cli::Args;
+use crate::config::{Config, Profile};
+use crate::engine::{Pause, TTSEngine};
+use crate::scratch;
+use crate::server::{BackendTarget, EngineOptions};
+use crate::voice::VoiceManager;
+
+/// The backend `args` and the profile point at.
+pub fn backend_target(args: &Args, profile: &Profile) -> BackendTarget {
+    BackendTarget {
+        model: args.model,
+        host: args
+            .host
+            .clone()
+            .or(profile.host.clone())
+            .unwrap_or_else(|| "localhost".to_string()),
+        port: profile.port(args.model).unwrap_or(args.model.port()),
+        token: profile.token.clone(),
+        strict: args.strict,
+    }
+}
+
+/// Connect to the backend, chained to a converter under `--chain`, and
+/// set the engine up from the arguments and config.
+pub fn connect_engine(
+    registry: &BackendRegistry,
+    target: &BackendTarget,
+    profile: &Profile,
+    voice_manager: VoiceManager,
+    args: &Args,
+    config: &Config,
+) -> Result<TTSEngine> {
+    let mut backend = connect_backend(registry, target)?;
+    if let Some(chain) = args.chain {
+        let converter = BackendTarget {
+            model: chain.converter,
+            port: profile
+                .port(chain.converter)
+                .unwrap_or(chain.converter.port()),
+            ..target.clone()
+        };
+        backend = Box::new(CompositeBackend::new(
+            backend,
+            connect_backend(registry, &converter)?,
+        ));
+    }
+    if let Some(timeout) = args.wait_for_backend {
+        wait_for_backend(backend.as_ref(), target, timeout)?;
+    }
+    Ok(engine_options(args, config)
+        .apply(TTSEngine::new(backend, voice_manager))
+        .with_pause(Some(pause_on_signal(Pause::new())?)))
+}
+
+/// How `args` and the config set the engine up, here or in the daemon.
+pub(super) fn engine_options(args: &Args, config: &Config) -> EngineOptions {
+    EngineOptions {
+        language: args.language.clone(),
+        require_consent: args.require_consent || config.require_consent,
+        project: args.project.clone(),
+        unlock: args.unlock,
+        max_text_length: config.max_text_length(args.model),
+        model_license: Some(model_license(args)),
+        cleanup: cleanup(args),
+        wait_for_backend: args.wait_for_backend,
+    }
+}
+
+/// Show synthesis progress on the terminal, and cancel the backend's
+/// running job through `canceller` on Ctrl-C.
+pub fn show_progress_of(engine: TTSEngine, canceller: Box<dyn Backend>) -> Result<TTSEngine> {
+    let running = Arc::new(Mutex::new(None));
+    cancel_on_interrupt(canceller, running.clone())?;
+    Ok(engine.with_progress(move |event| {
+        match &event {
+            SynthesisEvent::Queued { job_id } => *running.lock().unwrap() = Some(job_id.clone()),
+            SynthesisEvent::Downloading { .. }
+            | SynthesisEvent::ChunkDone { .. }
+            | SynthesisEvent::BackingOff { .. } => *running.lock().unwrap() = None,
+            _ => {}
+        }
+        show_progress(event);
+    }))
+}
+
+/// SPDX license of the model synthesizing, or of both models of a chain.
+fn model_license(args: &Args) -> String {
+    let Some(chain) = args.chain else {
+        return args.model.license().to_string();
+    };
+    let mut licenses = vec![chain.generator.license(), chain.converter.license()];
+    licenses.sort_unstable();
+    licenses.dedup();
+    licenses.join(" AND ")
+}
+
+/// Connect to the backend a target names.
+pub fn connect_backend(
+    registry: &BackendRegistry,
+    target: &BackendTarget,
+) -> Result<Box<dyn Backend>, BackendError> {
+    registry.connect(
+        target.model.as_str(),
+        &target.host,
+        Some(target.port),
+        target.token.clone(),
+        target.strict,
+    )
+}
+
+/// Block until the backend passes its health check, reporting each retry.
+fn wait_for_backend(
+    backend: &dyn Backend,
+    target: &BackendTarget,
+    timeout: Duration,
+) -> Result<()> {
+    let address = format!("{}:{}", target.host, target.port);
+    wait_for_health(backend, &HealthWait::new(timeout), |e, delay| {
+        eprintln!(
+            "Waiting for backend at {address}: {e}; checking again in {:.1}s",
+            delay.as_secs_f32()
+        )
+    })
+    .with_context(|| {
+        format!(
+            "Backend at {address} was not ready after {}s",
+            timeout.as_secs_f32()
+        )
+    })?;
+    Ok(())
+}
+
+pub fn list_backends(registry: &BackendRegistry) {
+    println!("Available backends:");
+    for entry in registry.entries() {
+        let caps = entry.capabilities;
+        let features: Vec<String> = [
+            (caps.persistent_voices, "persistent voices".to_string()),
+            (caps.speed, format!("speed {}", caps.speed_range)),
+            (caps.streaming, "streaming".to_string()),
+            (caps.styles, "styles".to_string()),
+            (caps.phonemes, "phonemes".to_string()),
+            (caps.language, "language".to_string()),
+            (caps.seed, "seed".to_string()),
+        ]
+        .into_iter()
+        .filter_map(|(supported, name)| supported.then_some(name))
+        .collect();
+        println!(
+            "  {:<4} {:<14} port {:<5}  {}",
+            entry.name,
+            entry.description,
+            entry.default_port,
+            if features.is_empty() {
+                "-".to_string()
+            } else {
+                features.join(", ")
+            }
+        );
+    }
+}
+
+/// On Ctrl-C, cancel the backend job being generated so the server stops
+/// using the GPU for it, then exit.
+fn cancel_on_interrupt(
+    backend: Box<dyn Backend>,
+    running: Arc<Mutex<Option<String>>>,
+) -> Result<()> {
+    ctrlc::set_handler(move || {
+        if let Some(job_id) = running.lock().unwrap().take() {
+            println!();
+            match backend.cancel(&job_id) {
+                Ok(()) => eprintln!("Cancelled backend job {job_id}"),
+                Err(e) => eprintln!("Failed to cancel backend job {job_id}: {e}"),
+            }
+        }
+        scratch::cleanup();
+        std::process::exit(130);
+    })
+    .context("Failed to install the Ctrl-C handler")
+}
+
+/// On SIGUSR1, hold synthesis between chunks; on SIGUSR2, continue it.
+/// Returns `pause` for the engine to wait on.
+#[cfg(unix)]
+pub(super) fn pause_on_signal(pause: Pause) -> Result<Pause> {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    // The handler only writes the signal to a pipe, which a thread reads in
+    // order, so a pause and resume sent back to back are both acted on
+    static PIPE: AtomicI32 = AtomicI32::new(-1);
+    extern "C" fn record(signal: libc::c_int) {
+        let byte = signal as u8;
+        // SAFETY: `write` is async-signal-safe and `byte` outlives the call
+        unsafe { libc::write(PIPE.load(Ordering::SeqCst), (&raw const byte).cast(), 1) };
+    }
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors `pipe` fills in
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        anyhow::bail!("Failed to create the pause signal pipe");
+    }
+    let [read_fd, write_fd] = fds;
+    PIPE.store(write_fd, Ordering::SeqCst);
+    let handler = record as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    for signal in [libc::SIGUSR1, libc::SIGUSR2] {
+        // SAFETY: `record` only writes to a pipe, which is async-signal-safe
+        if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+            anyhow::bail!("Failed to install the SIGUSR1/SIGUSR2 handlers");
+        }
+    }
+
+    let watched = pause.clone();
+    std::thread::spawn(move || {
+        let mut byte = 0u8;
+        loop {
+            // SAFETY: `read_fd` stays open for the life of the process
+            match unsafe { libc::read(read_fd, (&raw mut byte).cast(), 1) } {
+                1 => {}
+                -1 if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted => {
+                    continue;
+                }
+                _ => break,
+            }
+            match libc::c_int::from(byte) {
+                libc::SIGUSR1 if watched.pause() => eprintln!(
+                    "\nPausing after the chunks in flight; kill -USR2 {} to resume",
+                    std::process::id()
+                ),
+                libc::SIGUSR2 if watched.resume() => eprintln!("\nResuming"),
+                _ => {}
+            }
+        }
+    });
+    Ok(pause)
+}
+
+/// Signals are unavailable on this platform; synthesis is never paused.
+#[cfg(not(unix))]
+pub(super) fn pause_on_signal(pause: Pause) -> Result<Pause> {
+    Ok(pause)
+}
+
+/// Show synthesis progress on one line, overwriting the previous step.
+pub(super) fn show_progress(event: SynthesisEvent) {
+    let status = match event {
+        SynthesisEvent::Uploading { pct } => format!("Uploading reference audio: {pct}%"),
+        SynthesisEvent::Queued { job_id } => format!("Queued on the backend as {job_id}"),
+        SynthesisEvent::Generating { elapsed } => {
+            format!("Generating: {}s", elapsed.as_secs())
+        }
+        SynthesisEvent::Downloading { pct } => format!("Downloading audio: {pct}%"),
+        SynthesisEvent::ChunkDone { i, of } => format!("Progress: {i}/{of} chunks"),
+        SynthesisEvent::BackingOff {
+            limit,
+            splits,
+            wait,
+        } => {
+            let at_once = limit.map_or(String::new(), |limit| format!(", {limit} at a time"));
+            format!(
+                "Backend out of memory; retrying in {:.0}s in up to {} pieces{at_once}",
+                wait.as_secs_f32(),
+                1 << splits
+            )
+        }
+        SynthesisEvent::Paused => "Paused".to_string(),
+    };
+    print!("\r  {status:<40}");
+    let _ = std::io::stdout().flush();
+}
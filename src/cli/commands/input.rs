@@ -0,0 +1,256 @@
+//! Reading the text to speak and the arguments that shape it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::speak::is_icecast;
+use crate::cli::{Args, Command};
+use crate::config::{Config, Profile};
+use crate::ingest::read_decoded;
+use crate::project::Project;
+use crate::text::{
+    Document, Glossary, MarkupOptions, Pacing, Preprocessor, Profanity, ReplaceRules, Screenplay,
+    ScreenplayOptions, Variables,
+};
+
+/// The project `build` or `schedule` works in, opened with its defaults
+/// applied to `args`.
+pub fn command_project(args: &mut Args) -> Result<Option<Project>> {
+    match &args.command {
+        Some(Command::Build { dir }) | Some(Command::Schedule { dir, .. }) => {
+            open_project(dir.clone(), args).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Take the generator of a `--chain` as the model, and the text to speak
+/// from the clipboard or a web article when asked to.
+pub fn read_input(args: &mut Args) -> Result<()> {
+    if let Some(chain) = args.chain {
+        args.model = chain.generator;
+    }
+    if args.from_clipboard {
+        args.generate = Some(read_clipboard()?);
+    }
+    if let Some(url) = &args.from_url {
+        args.generate = Some(read_article(url)?);
+    }
+    Ok(())
+}
+
+/// Text currently on the system clipboard.
+fn read_clipboard() -> Result<String> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .context("Failed to read text from the clipboard")?;
+    if text.trim().is_empty() {
+        anyhow::bail!("The clipboard has no text to speak");
+    }
+    Ok(text)
+}
+
+/// The article on the page at `url`, as text to read aloud.
+#[cfg(feature = "web")]
+fn read_article(url: &str) -> Result<String> {
+    let article = crate::ingest::web::fetch_article(url)?;
+    println!(
+        "Reading: {} ({} words)",
+        article.title.as_deref().unwrap_or(url),
+        article.word_count()
+    );
+    Ok(article.to_speech())
+}
+
+#[cfg(not(feature = "web"))]
+fn read_article(_url: &str) -> Result<String> {
+    anyhow::bail!("--from-url needs a build with `--features web`")
+}
+
+/// Fill in defaults from the selected config profile.
+///
+/// The profile voice only applies when not extracting, so `-r` without `-n`
+/// never overwrites it.
+pub fn apply_profile(args: &mut Args, profile: &Profile) -> Result<()> {
+    if args.name.is_none() && args.reference.is_none() {
+        args.name = profile.voice.clone();
+    }
+    if args.project.is_none() {
+        args.project = profile.project.clone();
+    }
+    if let Some(dir) = &profile.output_dir
+        && !is_icecast(&args.output)
+    {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create output dir: {}", dir.display()))?;
+        args.output = profile.output_path(&args.output);
+    }
+    Ok(())
+}
+
+/// Load the project for `build` and apply the settings it pins.
+///
+/// The project's model always wins, so every build of it sounds the same;
+/// `--language` and `--project` still override it.
+fn open_project(dir: Option<PathBuf>, args: &mut Args) -> Result<Project> {
+    let project = match dir {
+        Some(dir) => Project::open(&dir)?,
+        None => Project::find(&std::env::current_dir()?)?,
+    };
+    let file = project.file();
+    if let Some(model) = file.model {
+        args.model = model;
+    }
+    args.language = args.language.clone().or(file.language.clone());
+    args.project = args.project.clone().or(project.name());
+    Ok(project)
+}
+
+/// A screenplay as tagged dialogue, with characters cast from `--cast` and
+/// the config's `[cast]`. Every character must have a voice.
+pub(super) fn read_screenplay(path: &Path, args: &Args, config: &Config) -> Result<String> {
+    let script = read_decoded(path)
+        .with_context(|| format!("Failed to read input file: {}", path.display()))?;
+    let screenplay = Screenplay::parse(&script);
+
+    let mut cast = config.cast.clone();
+    for entry in &args.cast {
+        let (name, voice) = entry
+            .split_once('=')
+            .with_context(|| format!("Invalid --cast {entry}: expected NAME=VOICE"))?;
+        cast.insert(name.trim().to_string(), voice.trim().to_string());
+    }
+    let uncast: Vec<&str> = screenplay
+        .characters()
+        .into_iter()
+        .filter(|c| !cast.keys().any(|name| name.eq_ignore_ascii_case(c)))
+        .collect();
+    if !uncast.is_empty() {
+        anyhow::bail!(
+            "No voice for {}; cast them with --cast NAME=VOICE or under [cast] in the config",
+            uncast.join(", ")
+        );
+    }
+
+    Ok(screenplay.to_text(&ScreenplayOptions {
+        cast,
+        scene_headings: !args.skip_scene_headings && !args.dialogue_only,
+        action: !args.dialogue_only,
+    }))
+}
+
+/// Fill `{{ name }}` placeholders from `--var` and `--vars-csv`.
+///
+/// Returns the text with the arguments to render it with: one pair per CSV
+/// row, with `-o` rendered from the same row, or the text unchanged when no
+/// variables were given.
+pub(super) fn personalize(template: &str, args: &Args) -> Result<Vec<(String, Args)>> {
+    if args.var.is_empty() && args.vars_csv.is_none() {
+        return Ok(vec![(template.to_string(), args.clone())]);
+    }
+
+    let defaults = Variables::parse(&args.var).context("Invalid --var")?;
+    let Some(csv) = &args.vars_csv else {
+        return Ok(vec![(defaults.render(template)?, args.clone())]);
+    };
+    let rows = Variables::from_csv(csv)?;
+    let output = args.output.to_string_lossy();
+
+    let mut outputs = std::collections::HashSet::new();
+    let mut renders = Vec::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        let variables = defaults.merged(row);
+        let row_args = Args {
+            output: PathBuf::from(
+                variables
+                    .render(&output)
+                    .with_context(|| format!("Row {} of {}", i + 1, csv.display()))?,
+            ),
+            ..args.clone()
+        };
+        if !outputs.insert(row_args.output.clone()) {
+            anyhow::bail!(
+                "Rows of {} share the output {}; use a placeholder in -o, e.g. -o 'clip-{{{{name}}}}.wav'",
+                csv.display(),
+                row_args.output.display()
+            );
+        }
+        let text = variables
+            .render(template)
+            .with_context(|| format!("Row {} of {}", i + 1, csv.display()))?;
+        renders.push((text, row_args));
+    }
+    Ok(renders)
+}
+
+/// Pauses between sentences and paragraphs from `--sentence-pause` and
+/// `--paragraph-pause`.
+/// Apply a source's front matter and voice annotations. The front matter
+/// voice is used when no voice was given on the command line.
+pub(super) fn apply_document(source: &str, mut args: Args) -> Result<(String, Args)> {
+    let document = Document::parse(source)?;
+    if args.name.is_none() && args.reference.is_none() {
+        args.name = document.voice;
+    }
+    Ok((document.text, args))
+}
+
+pub(super) fn pacing(args: &Args) -> Pacing {
+    Pacing {
+        sentence: args.sentence_pause.unwrap_or_default(),
+        paragraph: args.paragraph_pause.unwrap_or_default(),
+    }
+}
+
+/// Build the text preprocessor from config and command-line options,
+/// warning about glossary entries that will not apply as written.
+pub(super) fn build_preprocessor(args: &Args, config: &Config) -> Result<Preprocessor> {
+    let mut rules = ReplaceRules::parse(&[config.replace.clone(), args.replace.clone()].concat())
+        .context("Invalid replacement rule")?;
+    for path in &config.lexicon_files {
+        rules.load_lexicon(path)?;
+    }
+    let markup = MarkupOptions {
+        strip_markdown: args.strip_markup || config.strip_markup,
+        emoji: args.emoji.or(config.emoji).unwrap_or_default(),
+    };
+    let mut glossary = Glossary::from_entries(&config.glossary).context("Invalid config")?;
+    let mut conflicts = Vec::new();
+    for path in config.glossary_files.iter().chain(&args.glossary) {
+        conflicts.extend(glossary.merge(&Glossary::load(path)?));
+    }
+
+    let preprocessor = Preprocessor::new()
+        .with_markup(markup)
+        .with_rules(rules)
+        .with_glossary(glossary)
+        .with_locale(args.locale.or(config.locale))
+        .with_profanity(build_profanity(args, config)?);
+    conflicts.extend(preprocessor.glossary_conflicts());
+    super::warn_glossary_conflicts(&conflicts);
+    Ok(preprocessor)
+}
+
+/// The profanity list of the config and `--profanity` files, if masking
+/// is on.
+fn build_profanity(args: &Args, config: &Config) -> Result<Option<Profanity>> {
+    let Some(mask) = args.mask_profanity.or(config.mask_profanity) else {
+        if !args.profanity.is_empty() {
+            eprintln!("Warning: --profanity has no effect without --mask-profanity");
+        }
+        return Ok(None);
+    };
+    let mut profanity = Profanity::new(mask);
+    profanity
+        .extend(&config.profanity)
+        .context("Invalid config")?;
+    for path in config.profanity_files.iter().chain(&args.profanity) {
+        profanity.load(path)?;
+    }
+    if profanity.is_empty() {
+        eprintln!("Warning: no words to mask; list them in [profanity] or a --profanity file");
+    }
+    Ok(Some(profanity))
+}
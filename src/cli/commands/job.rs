@@ -0,0 +1,453 @@
+//! Synthesizing `-i` text as a resumable batch job.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::PostProcess;
+use super::connect::show_progress;
+use super::input::{apply_document, build_preprocessor, pacing, personalize, read_screenplay};
+use super::speak::{check_qa, deliver, playback_sinks, run_qa};
+use crate::align::Timeline;
+use crate::audio::{AudioBuffer, QaReport, render_visualization};
+use crate::backend::{Backend, SynthesisEvent};
+use crate::batch::{
+    ChunkStatus, Job, JobReport, JobStore, LowSpace, RunOptions, assemble_job_tracks, check_space,
+    format_size, job_space, run_job,
+};
+use crate::cli::Args;
+use crate::config::Config;
+use crate::engine::TTSEngine;
+use crate::ingest::{is_fountain, read_text};
+use crate::journal::{Event, Journal};
+use crate::manifest::{Manifest, merge_shards};
+use crate::scratch::persist;
+use crate::text::Chunk;
+use crate::usage::UsageRecord;
+
+/// Load the job `--resume` names, saying how its last run went.
+pub fn load_resumed(
+    job_id: &str,
+    args: &Args,
+    journal: Option<&Journal>,
+) -> Result<(JobStore, Job)> {
+    let store = JobStore::new();
+    let job = store
+        .load(job_id)
+        .with_context(|| format!("Failed to load job '{job_id}'"))?;
+    println!(
+        "Resuming job {} ({}/{} chunks done)",
+        job.id,
+        job.done_count(),
+        job.chunks.len()
+    );
+    super::note_resume(journal, &job, args.model.name());
+    Ok((store, job))
+}
+
+/// Load a job with one of its chunks reopened for `rerender`.
+pub fn reopen_chunk(
+    job_id: &str,
+    chunk: usize,
+    seed: Option<u64>,
+    journal: Option<&Journal>,
+) -> Result<(JobStore, Job)> {
+    let store = JobStore::new();
+    let mut job = store
+        .load(job_id)
+        .with_context(|| format!("Failed to load job '{job_id}'"))?;
+    job.reopen_chunk(chunk)?;
+    println!("Re-rendering chunk {chunk} of job {}", job.id);
+    super::note_rerender(journal, &job.id, chunk, seed);
+    Ok((store, job))
+}
+
+/// Synthesize an `-i` text file as a batch job per recipient, or as
+/// shards across `--hosts`.
+pub fn synthesize_file<B: Backend>(
+    engine: &TTSEngine<B>,
+    path: &Path,
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    journal: Option<&Journal>,
+) -> Result<()> {
+    if !args.hosts.is_empty() {
+        let shards = super::run_shards(args)?;
+        return merge(engine, &shards, args, config, post, journal);
+    }
+    let template = if is_fountain(path) {
+        read_screenplay(path, args, config)?
+    } else {
+        read_text(path).with_context(|| format!("Failed to read input file: {}", path.display()))?
+    };
+    let preprocessor = build_preprocessor(args, config)?;
+    let chapter_pattern = super::chapter_pattern(args, config)?;
+    for (text, args) in personalize(&template, args)? {
+        let (text, args) = apply_document(&text, args)?;
+        let (chunks, chapters) = super::text_chunks(
+            &text,
+            &args,
+            &preprocessor,
+            chapter_pattern.as_ref(),
+            pacing(&args),
+        )?;
+
+        let store = JobStore::new();
+        let mut job = super::start_job(&store, chunks, chapters, &args)?;
+        super::record(journal, super::job_started(&job, &args));
+        run_batch(engine, &store, &mut job, &args, config, post, journal)?;
+    }
+    Ok(())
+}
+
+/// Run a batch job to the end, then save its audio and manifest, or its
+/// shard manifest under `--shard`.
+pub fn run_batch<B: Backend>(
+    engine: &TTSEngine<B>,
+    store: &JobStore,
+    job: &mut Job,
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    journal: Option<&Journal>,
+) -> Result<()> {
+    prepare_job(engine, store, job, args, post)?;
+    let report = run_chunks(engine, store, job, args, journal)?;
+    if let Some(shard) = job.shard {
+        let manifest = super::finish_shard(job, shard, args.model.name(), &report)?;
+        note_written(journal, job, &manifest);
+        super::record(
+            journal,
+            Event::JobFinished {
+                job: job.id.clone(),
+            },
+        );
+        return Ok(());
+    }
+    finish_job(job, &report, args, config, post, journal)
+}
+
+/// Pin the model the job runs with, so swapping it mid-job stops the job,
+/// and check there is room for its audio.
+fn prepare_job<B: Backend>(
+    engine: &TTSEngine<B>,
+    store: &JobStore,
+    job: &mut Job,
+    args: &Args,
+    post: &PostProcess,
+) -> Result<()> {
+    if job.backend_model.is_none()
+        && job
+            .chunks
+            .iter()
+            .any(|c| c.status != ChunkStatus::Done && matches!(c.chunk, Chunk::Speech { .. }))
+    {
+        job.backend_model = loaded_model(engine);
+        store.save(job).context("Failed to save batch job")?;
+    }
+    if !args.skip_space_check {
+        let split = !job.chapters.is_empty() || args.split_every.is_some();
+        check_space(&job_space(store, job, split, post.preset.as_ref())).with_context(|| {
+            format!(
+                "Job {} not started; free up space or pass --skip-space-check, then continue \
+                 with --resume {}",
+                job.id, job.id
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Synthesize the chunks not yet done, recording their usage, and whether
+/// the job stopped.
+fn run_chunks<B: Backend>(
+    engine: &TTSEngine<B>,
+    store: &JobStore,
+    job: &mut Job,
+    args: &Args,
+    journal: Option<&Journal>,
+) -> Result<JobReport> {
+    let pending: Vec<usize> = (0..job.chunks.len())
+        .filter(|&i| job.chunks[i].status != ChunkStatus::Done)
+        .collect();
+    let result = run_job(
+        engine,
+        store,
+        job,
+        &run_options(args, journal),
+        |done, total| show_progress(SynthesisEvent::ChunkDone { i: done, of: total }),
+    );
+    println!();
+    // Chunks finished before a failure are billed too; a resumed run skips them
+    super::record_usage(UsageRecord::for_job(
+        job,
+        &pending,
+        args.model.name(),
+        args.project.as_deref(),
+    ));
+
+    if let Err(e) = &result {
+        super::record(
+            journal,
+            Event::JobStopped {
+                job: job.id.clone(),
+                error: e.to_string(),
+            },
+        );
+    }
+    result.with_context(|| {
+        format!(
+            "Job {} stopped; continue with --resume {}, or see what happened with \
+             `open-tts-rs logs show {}`",
+            job.id, job.id, job.id
+        )
+    })
+}
+
+/// How `args` asks for failed chunks and low disk space to be handled.
+fn run_options(args: &Args, journal: Option<&Journal>) -> RunOptions {
+    RunOptions {
+        on_error: args.on_error,
+        max_retries: args.max_retries,
+        verify: args.verify_chunks,
+        journal: journal.cloned(),
+        on_low_space: Some(LowSpace::new(|dir, free| {
+            eprintln!(
+                "\nWarning: disk nearly full ({} free in {}); waiting for space before the \
+                 next write",
+                format_size(free),
+                dir.display()
+            )
+        })),
+        ..RunOptions::default()
+    }
+}
+
+/// Post-process and save a finished job's audio, with its parts, manifest,
+/// and extras, then report the chunks that failed or were retried.
+fn finish_job(
+    job: &Job,
+    report: &JobReport,
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    journal: Option<&Journal>,
+) -> Result<()> {
+    let audio = save_audio(job, args, config, post, journal)?;
+    let mut players = playback_sinks(args);
+    if !players.is_empty() {
+        deliver(&mut players, &audio)?;
+    }
+
+    let qa_reports = job_qa(job, args, config)?;
+    let manifest = write_manifest(job, args, qa_reports.as_deref())?;
+    note_written(journal, job, &manifest);
+    write_extras(job, args)?;
+    print_report(job, report);
+
+    super::record(
+        journal,
+        Event::JobFinished {
+            job: job.id.clone(),
+        },
+    );
+    if let (Some(reports), Some(report)) = (&qa_reports, &args.qa_report) {
+        check_qa(reports, report)?;
+    }
+    Ok(())
+}
+
+/// Post-process the job's audio in place, tag it, and write its parts.
+/// Returns the processed audio.
+fn save_audio(
+    job: &Job,
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    journal: Option<&Journal>,
+) -> Result<Vec<u8>> {
+    let mut audio = fs::read(&job.output)?;
+    // Parts are cut from the audio before it is marked, then marked each
+    let mut unmarked = audio.clone();
+    if !post.is_empty() {
+        unmarked = post.apply_unmarked(&audio)?;
+        audio = post.mark(&unmarked)?;
+        persist(&job.output, &post.encode(&audio)?)
+            .with_context(|| format!("Failed to write audio to: {}", job.output.display()))?;
+    }
+
+    println!("Audio saved to: {}", job.output.display());
+    note_written(journal, job, &job.output);
+    super::tag_output(args, config, &job.output)?;
+    for part in super::write_parts(job, &unmarked, args, config, post)? {
+        note_written(journal, job, &part);
+    }
+    Ok(audio)
+}
+
+/// QA reports on each chunk and the whole job, under `--qa-report`.
+fn job_qa(job: &Job, args: &Args, config: &Config) -> Result<Option<Vec<QaReport>>> {
+    let Some(report) = &args.qa_report else {
+        return Ok(None);
+    };
+    let mut files: Vec<PathBuf> = job.chunks.iter().filter_map(|c| c.output.clone()).collect();
+    files.push(job.output.clone());
+    Ok(Some(run_qa(&files, &config.qa, report)?))
+}
+
+/// Write the job's manifest, with its QA results, returning its path.
+fn write_manifest(job: &Job, args: &Args, qa_reports: Option<&[QaReport]>) -> Result<PathBuf> {
+    let manifest_path = args
+        .manifest
+        .clone()
+        .unwrap_or_else(|| Manifest::default_path(&job.output));
+    Manifest::for_job(job, args.model.name())
+        .and_then(|mut manifest| {
+            if let Some(reports) = qa_reports {
+                manifest.annotate_qa(reports);
+            }
+            manifest.write(&manifest_path)
+        })
+        .with_context(|| format!("Failed to write manifest: {}", manifest_path.display()))?;
+    println!("Manifest saved to: {}", manifest_path.display());
+    Ok(manifest_path)
+}
+
+/// Write the voice tracks, visemes, and waveform image `args` asks for.
+fn write_extras(job: &Job, args: &Args) -> Result<()> {
+    if let Some(dir) = &args.tracks {
+        let tracks = assemble_job_tracks(job).context("Failed to assemble voice tracks")?;
+        write_tracks(dir, &tracks)?;
+    }
+    if args.visemes {
+        let chunks: Vec<Chunk> = job.chunks.iter().map(|c| c.chunk.clone()).collect();
+        write_visemes(&job.output, &chunks)?;
+    }
+    if let Some(image) = &args.visualize {
+        visualize(&job.output, image)?;
+    }
+    Ok(())
+}
+
+/// List the chunks replaced with silence or re-synthesized for anomalies.
+fn print_report(job: &Job, report: &JobReport) {
+    if !report.skipped.is_empty() {
+        println!(
+            "{} chunk(s) failed and were replaced with silence:",
+            report.skipped.len()
+        );
+        for failed in &report.skipped {
+            println!("  #{}: \"{}\"", failed.index, failed.text);
+            println!("    Error: {}", failed.error);
+        }
+        println!("Retry them with --resume {}", job.id);
+    }
+
+    if !report.retried.is_empty() {
+        println!(
+            "{} chunk(s) had audio anomalies and were re-synthesized:",
+            report.retried.len()
+        );
+        for retried in &report.retried {
+            let outcome = if retried.resolved {
+                format!("fixed after {} attempts", retried.attempts)
+            } else {
+                format!("still anomalous after {} attempts", retried.attempts)
+            };
+            println!("  #{}: \"{}\"", retried.index, retried.text);
+            println!("    {} ({outcome})", retried.anomaly);
+        }
+    }
+}
+
+/// Journal a file the job wrote.
+fn note_written(journal: Option<&Journal>, job: &Job, file: &Path) {
+    super::record(
+        journal,
+        Event::OutputWritten {
+            job: Some(job.id.clone()),
+            file: file.to_path_buf(),
+        },
+    );
+}
+
+/// The model the backend has loaded, or `None` with a warning when its
+/// health check fails.
+pub(super) fn loaded_model<B: Backend>(engine: &TTSEngine<B>) -> Option<String> {
+    match engine.health_check() {
+        Ok(health) => Some(health.loaded_model()),
+        Err(e) => {
+            eprintln!("Warning: could not read the backend's loaded model: {e}");
+            None
+        }
+    }
+}
+
+/// Assemble the output of a sharded job from its shards' manifests.
+pub fn merge<B: Backend>(
+    engine: &TTSEngine<B>,
+    shards: &[PathBuf],
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    journal: Option<&Journal>,
+) -> Result<()> {
+    let store = JobStore::new();
+    let mut job = merge_shards(shards, &store, &args.output).context("Failed to merge shards")?;
+    println!(
+        "Merging {} shard(s) as job {} ({} chunks)",
+        shards.len(),
+        job.id,
+        job.chunks.len()
+    );
+    super::record(journal, super::job_started(&job, args));
+    run_batch(engine, &store, &mut job, args, config, post, journal)
+}
+
+/// Write the lip-sync timeline for the spoken text of `chunks` next to `output`.
+pub(super) fn write_visemes(output: &Path, chunks: &[Chunk]) -> Result<()> {
+    let text = chunks
+        .iter()
+        .filter_map(|chunk| match chunk {
+            Chunk::Speech { text, .. } => Some(text.as_str()),
+            Chunk::Pause(_) | Chunk::Bleep(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let audio = AudioBuffer::from_wav_bytes(&fs::read(output)?)?;
+    let path = Timeline::default_path(output);
+    Timeline::align(&audio, &text)
+        .write(&path)
+        .with_context(|| format!("Failed to write visemes: {}", path.display()))?;
+    println!("Visemes saved to: {}", path.display());
+
+    Ok(())
+}
+
+/// Write one WAV per voice track into `dir`.
+///
+/// Tracks are left unprocessed so they can be mixed independently later.
+pub(super) fn write_tracks(dir: &Path, tracks: &[(String, AudioBuffer)]) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create tracks dir: {}", dir.display()))?;
+    for (voice, track) in tracks {
+        let path = dir.join(format!("{}.wav", voice.replace(['/', '\\'], "_")));
+        persist(&path, &track.to_wav_bytes()?)
+            .with_context(|| format!("Failed to write track: {}", path.display()))?;
+        println!("Track saved to: {}", path.display());
+    }
+    Ok(())
+}
+
+/// Render the waveform and spectrogram of `output` to `image`.
+pub(super) fn visualize(output: &Path, image: &Path) -> Result<()> {
+    let audio = AudioBuffer::from_wav_bytes(&fs::read(output)?)?;
+    render_visualization(&audio, image)
+        .with_context(|| format!("Failed to write image: {}", image.display()))?;
+    println!("Visualization saved to: {}", image.display());
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! The run journal and `logs show`.
+
+use anyhow::{Context, Result};
+
+use crate::batch::Job;
+use crate::cli::{Args, LogsCommand, ReportFormat};
+use crate::journal::{Event, JobHistory, Journal, Journals, Outcome};
+
+/// Start this invocation's journal. A journal that cannot be written is
+/// warned about and skipped, as it only records what happened.
+pub fn open_journal() -> Option<Journal> {
+    match Journals::new().start(std::env::args().skip(1).collect()) {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            eprintln!("Warning: failed to start the run journal: {e}");
+            None
+        }
+    }
+}
+
+/// Add `event` to the journal, if there is one.
+pub fn record(journal: Option<&Journal>, event: Event) {
+    if let Some(journal) = journal {
+        journal.record(event);
+    }
+}
+
+/// The journal entry for starting `job`.
+pub fn job_started(job: &Job, args: &Args) -> Event {
+    Event::JobStarted {
+        job: job.id.clone(),
+        output: job.output.clone(),
+        chunks: job.chunks.len(),
+        model: args.model.name().to_string(),
+    }
+}
+
+/// What the journals say about a job; unreadable journals are warned
+/// about and treated as empty.
+pub fn job_history(job: &str) -> JobHistory {
+    Journals::new().job_history(job).unwrap_or_else(|e| {
+        eprintln!("Warning: failed to read the run journals: {e}");
+        JobHistory::default()
+    })
+}
+
+/// Print the journal of a job's runs, or of the last run.
+pub fn logs(command: &LogsCommand) -> Result<()> {
+    let LogsCommand::Show { job, format } = command;
+    let journals = Journals::new();
+    let runs = match job {
+        Some(job) => {
+            let history = journals.job_history(job).with_context(|| {
+                format!("Failed to read journals in: {}", journals.dir().display())
+            })?;
+            if history.runs.is_empty() {
+                anyhow::bail!("No journal mentions job '{job}'");
+            }
+            history.runs
+        }
+        None => journals
+            .latest()
+            .with_context(|| format!("Failed to read journals in: {}", journals.dir().display()))?
+            .into_iter()
+            .collect(),
+    };
+    match format {
+        ReportFormat::Table => {
+            let tables: Vec<String> = runs.iter().map(|run| run.to_table()).collect();
+            print!("{}", tables.join("\n"));
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&runs)?),
+    }
+    Ok(())
+}
+
+/// Say how a resumed job's last run ended, warn when it ran with another
+/// model, and journal the resume.
+pub fn note_resume(journal: Option<&Journal>, job: &Job, model: &str) {
+    let history = job_history(&job.id);
+    match history.outcome() {
+        Some(Outcome::Stopped(error)) => println!("The last run stopped: {error}"),
+        Some(Outcome::Interrupted(Some(chunk))) => {
+            println!("The last run was interrupted after chunk {chunk}")
+        }
+        Some(Outcome::Interrupted(None)) => {
+            println!("The last run was interrupted before finishing a chunk")
+        }
+        Some(Outcome::Finished) | None => {}
+    }
+    if let Some(last) = history.model()
+        && last != model
+    {
+        eprintln!(
+            "Warning: job {} was last run with {last}; resuming with {model} mixes two models",
+            job.id
+        );
+    }
+    record(
+        journal,
+        Event::JobResumed {
+            job: job.id.clone(),
+            done: job.done_count(),
+            chunks: job.chunks.len(),
+            model: model.to_string(),
+        },
+    );
+}
+
+/// List the seeds already tried for a re-rendered chunk, warn when `seed`
+/// is one of them, and journal the re-render.
+pub fn note_rerender(journal: Option<&Journal>, job: &str, chunk: usize, seed: Option<u64>) {
+    let tried = job_history(job).reopen_seeds(chunk);
+    if !tried.is_empty() {
+        let seeds: Vec<String> = tried.iter().map(u64::to_string).collect();
+        println!("Seeds tried before for this chunk: {}", seeds.join(", "));
+    }
+    if let Some(seed) = seed
+        && tried.contains(&seed)
+    {
+        eprintln!("Warning: seed {seed} was already tried for chunk {chunk}");
+    }
+    record(
+        journal,
+        Event::ChunkReopened {
+            job: job.to_string(),
+            chunk,
+            seed,
+        },
+    );
+}
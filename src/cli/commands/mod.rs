@@ -0,0 +1,67 @@
+//! Subcommand handlers. `main` parses the arguments and loads the config,
+//! then dispatches here.
+
+mod build;
+mod connect;
+mod input;
+mod job;
+mod logs;
+mod post;
+mod reference;
+mod report;
+mod service;
+mod setup;
+mod shard;
+mod sheet;
+mod speak;
+mod split;
+mod tags;
+mod voices;
+mod watermark;
+
+pub use build::{ScheduledBuild, run_build, run_schedule};
+pub use connect::{
+    backend_target, connect_backend, connect_engine, list_backends, show_progress_of,
+};
+pub use input::{apply_profile, command_project, read_input};
+pub use job::{load_resumed, merge, reopen_chunk, run_batch, synthesize_file};
+pub use logs::{job_started, logs, note_rerender, note_resume, open_journal, record};
+pub use post::PostProcess;
+pub use reference::prepare_voice;
+pub use report::{diff, estimate, lint, usage};
+pub use service::{
+    Forward, daemon_forward, is_service, run_daemon, run_service, set_daemon_paused,
+};
+pub use setup::{confirm, setup, suggest_setup};
+pub use shard::{finish_shard, run_shards, start_job};
+pub use sheet::{
+    SheetBatch, prompt_options, run_job_stream, run_prompt_set, run_sheet_batch, stream_options,
+};
+pub use speak::{generate, icecast_output};
+pub use split::{chapter_pattern, text_chunks, write_parts};
+pub use tags::{tag_output, tags_requested};
+#[cfg(feature = "remote")]
+pub use voices::sync_remote;
+pub use voices::{
+    delete_voice, export_embedding, install_pack, list_voices, open_voice_manager, trash_retention,
+    voices,
+};
+pub use watermark::verify_watermark;
+
+use crate::text::GlossaryConflict;
+use crate::usage::{Ledger, UsageError, UsageRecord};
+
+/// Append to the usage ledger. A ledger that cannot be written is reported
+/// but does not fail the synthesis that was already paid for.
+pub fn record_usage(records: Result<Vec<UsageRecord>, UsageError>) {
+    if let Err(e) = records.and_then(|records| Ledger::new().record(&records)) {
+        eprintln!("Warning: failed to record usage: {e}");
+    }
+}
+
+/// Warn about glossary entries that will not apply as written.
+pub fn warn_glossary_conflicts(conflicts: &[GlossaryConflict]) {
+    for conflict in conflicts {
+        eprintln!("Warning: {conflict}");
+    }
+}
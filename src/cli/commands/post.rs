@@ -0,0 +1,222 @@
+//! Post-processing of synthesized audio before it is saved.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::audio::{
+    AudioBuffer, Bed, Cleanup, Encoding, Envelope, MAX_CLEAN_STRETCH, Overlong, Preset, TRIM_FADE,
+    Watermark, canonical_wav, stretch_amount, time_stretch, trim_to,
+};
+use crate::cli::Args;
+use crate::config::Config;
+
+/// Processing applied to synthesized audio before it is saved.
+pub struct PostProcess {
+    pub bed: Option<Bed>,
+    pub watermark: Option<Watermark>,
+    /// Strip metadata chunks for byte-identical output.
+    pub reproducible: bool,
+    /// Sample rate, loudness, and encoding of the saved file.
+    pub preset: Option<Preset>,
+    /// Length to time-stretch the speech to.
+    pub target_duration: Option<Duration>,
+    /// Longest the speech may be, and what to do when it is longer.
+    pub max_duration: Option<(Duration, Overlong)>,
+    /// Filtering of rumble and sibilance.
+    pub cleanup: Option<Cleanup>,
+    /// Gain and fades.
+    pub envelope: Envelope,
+}
+
+impl PostProcess {
+    /// The post-processing `args` and the config ask for, refusing
+    /// combinations that would undo part of it.
+    pub fn from_args(args: &Args, config: &Config) -> Result<Self> {
+        let post = PostProcess {
+            bed: args
+                .bed
+                .as_deref()
+                .map(|path| {
+                    Bed::load(path)
+                        .map(|bed| bed.with_gain(args.bed_gain))
+                        .with_context(|| format!("Failed to load bed: {}", path.display()))
+                })
+                .transpose()?,
+            watermark: args
+                .watermark_key
+                .as_deref()
+                .or(config.watermark_key.as_deref())
+                .map(Watermark::new),
+            reproducible: args.reproducible,
+            preset: args
+                .preset
+                .as_deref()
+                .map(|name| config.preset(name))
+                .transpose()?,
+            target_duration: args.target_duration,
+            max_duration: args.max_duration.map(|max| (max, args.on_overlong)),
+            cleanup: cleanup(args),
+            envelope: Envelope {
+                fade_in: args.fade_in.unwrap_or_default(),
+                fade_out: args.fade_out.unwrap_or_default(),
+                gain_db: args.gain.unwrap_or_default(),
+            },
+        };
+        post.check_preset(args)?;
+        Ok(post)
+    }
+
+    /// Refuse a watermark the preset would remove, and warn where the preset
+    /// disagrees with the output name or `--gain`.
+    fn check_preset(&self, args: &Args) -> Result<()> {
+        let Some(preset) = &self.preset else {
+            return Ok(());
+        };
+        let name = args.preset.as_deref().unwrap_or_default();
+        if self.watermark.is_some() && !preset.keeps_watermark() {
+            anyhow::bail!(
+                "--preset {name} encodes MP3, which removes the watermark; \
+                 use a WAV or telephony preset with a watermark key"
+            );
+        }
+        if args.output.extension().and_then(|e| e.to_str()) != Some(preset.extension()) {
+            eprintln!(
+                "Warning: --preset {name} writes {} audio to {}",
+                preset.extension().to_uppercase(),
+                args.output.display()
+            );
+        }
+        if args.gain.is_some() && preset.loudness.is_some() {
+            eprintln!("Warning: --preset {name} normalizes loudness, undoing --gain");
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bed.is_none()
+            && self.watermark.is_none()
+            && !self.reproducible
+            && self.preset.is_none()
+            && self.target_duration.is_none()
+            && self.max_duration.is_none()
+            && self.cleanup.is_none()
+            && self.envelope.is_flat()
+    }
+
+    /// Clean the speech up and fit it to its slot before the bed is mixed under it, then
+    /// fade the whole mix and let the preset level it; the watermark goes
+    /// on last, and only with presets that keep it.
+    pub fn apply(&self, wav: &[u8]) -> Result<Vec<u8>> {
+        self.mark(&self.apply_unmarked(wav)?)
+    }
+
+    /// [`PostProcess::apply`] short of the watermark, for audio that is cut
+    /// up before it is marked, since the mark is only found from the start.
+    pub fn apply_unmarked(&self, wav: &[u8]) -> Result<Vec<u8>> {
+        if self.bed.is_none()
+            && self.preset.is_none()
+            && self.target_duration.is_none()
+            && self.max_duration.is_none()
+            && self.cleanup.is_none()
+            && self.envelope.is_flat()
+        {
+            return canonical_wav(wav).context("Failed to rewrite audio for --reproducible");
+        }
+        let mut buffer = AudioBuffer::from_wav_bytes(wav)
+            .context("Failed to decode audio for post-processing")?;
+        if let Some(cleanup) = &self.cleanup {
+            buffer = cleanup.apply(&buffer);
+        }
+        buffer = self.fit(buffer)?;
+        if let Some(bed) = &self.bed {
+            buffer = bed.mix_under(&buffer);
+        }
+        self.envelope.apply(&mut buffer);
+        if let Some(preset) = &self.preset {
+            buffer = preset.conform(&buffer);
+        }
+        self.finish(buffer)
+    }
+
+    /// Add the watermark to processed audio, if there is one.
+    pub fn mark(&self, wav: &[u8]) -> Result<Vec<u8>> {
+        let Some(watermark) = &self.watermark else {
+            return Ok(wav.to_vec());
+        };
+        let mut buffer =
+            AudioBuffer::from_wav_bytes(wav).context("Failed to decode audio to watermark")?;
+        watermark.embed(&mut buffer);
+        self.finish(buffer)
+    }
+
+    fn finish(&self, buffer: AudioBuffer) -> Result<Vec<u8>> {
+        let wav = buffer.to_wav_bytes()?;
+        if self.reproducible {
+            return Ok(canonical_wav(&wav)?);
+        }
+        Ok(wav)
+    }
+
+    /// Stretch the speech to `--target-duration` and hold it to
+    /// `--max-duration`.
+    fn fit(&self, mut buffer: AudioBuffer) -> Result<AudioBuffer> {
+        if let Some(target) = self.target_duration {
+            let amount = stretch_amount(buffer.duration(), target);
+            println!(
+                "  Stretching {:.2}s to {:.2}s ({:+.1}%)",
+                buffer.duration().as_secs_f64(),
+                target.as_secs_f64(),
+                amount * 100.0
+            );
+            if amount.abs() > MAX_CLEAN_STRETCH {
+                eprintln!(
+                    "Warning: stretching by more than {:.0}% may sound processed; \
+                     multiplying -s by {:.2} would get closer without it",
+                    MAX_CLEAN_STRETCH * 100.0,
+                    1.0 / (1.0 + amount)
+                );
+            }
+            buffer = time_stretch(&buffer, target);
+        }
+        if let Some((max, overlong)) = self.max_duration
+            && buffer.duration() > max
+        {
+            match overlong {
+                Overlong::Error => anyhow::bail!(
+                    "Generated audio is {:.2}s, longer than --max-duration {:.2}s \
+                     (use --on-overlong trim to cut it)",
+                    buffer.duration().as_secs_f64(),
+                    max.as_secs_f64()
+                ),
+                Overlong::Trim => {
+                    println!(
+                        "  Trimming {:.2}s to {:.2}s",
+                        buffer.duration().as_secs_f64(),
+                        max.as_secs_f64()
+                    );
+                    buffer = trim_to(&buffer, max, TRIM_FADE);
+                }
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// The bytes to save for `wav` once applied, in the preset's encoding.
+    pub fn encode(&self, wav: &[u8]) -> Result<Vec<u8>> {
+        match &self.preset {
+            Some(preset) if preset.encoding != Encoding::Pcm => {
+                Ok(preset.encode(&AudioBuffer::from_wav_bytes(wav)?)?)
+            }
+            _ => Ok(wav.to_vec()),
+        }
+    }
+}
+
+/// Filtering of rumble and sibilance, when asked for.
+pub(super) fn cleanup(args: &Args) -> Option<Cleanup> {
+    (args.cleanup || args.de_ess).then_some(Cleanup {
+        high_pass: args.high_pass,
+        de_ess: args.de_ess,
+    })
+}
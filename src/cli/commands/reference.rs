@@ -0,0 +1,231 @@
+//! Preparing the reference voice a synthesis speaks with.
+
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::voices::{print_access, print_consent, record_access, record_consent};
+use crate::audio::{decode_file, diarize};
+use crate::backend::Backend;
+use crate::cli::{Args, Reference};
+use crate::engine::TTSEngine;
+use crate::voice;
+
+/// Fetch, isolate, or reuse the reference voice, then extract it or check
+/// the named voice, recording consent, access, and license as asked.
+/// Returns false once that was all there was to do.
+pub fn prepare_voice<B: Backend>(engine: &TTSEngine<B>, args: &mut Args) -> Result<bool> {
+    if let Some(ref_str) = &args.reference
+        && ref_str.trim_start().starts_with("ytdlp:")
+    {
+        args.reference = Some(fetch_video_reference(engine, ref_str)?);
+    }
+    if let (Some(ref_str), Some(speaker)) = (&args.reference, args.speaker) {
+        let reference = Reference::parse(ref_str)?;
+        let isolated = isolate_speaker(&reference.audio_path, speaker.into(), args.speakers)?;
+        args.reference = Some(format!("{};{}", isolated.display(), reference.transcript));
+    }
+    if let Some(ref_str) = &args.reference
+        && let Some(existing) = reuse_duplicate(engine, ref_str, args.name.as_deref())?
+    {
+        args.reference = None;
+        args.name = Some(existing);
+    }
+
+    let more = args.generate.is_some() || args.input_file.is_some();
+    if let Some(ref_str) = &args.reference {
+        extract_reference(engine, ref_str, args)?;
+        return Ok(more);
+    }
+    if let Some(name) = &args.name {
+        let recorded = use_voice(engine, name, args)?;
+        return Ok(more || !recorded);
+    }
+    Ok(true)
+}
+
+/// Extract a voice from the `-r` reference and record what it was given
+/// with.
+fn extract_reference<B: Backend>(engine: &TTSEngine<B>, ref_str: &str, args: &Args) -> Result<()> {
+    let reference = Reference::parse(ref_str)?;
+    let voice_info = engine
+        .extract_voice(
+            &reference.audio_path,
+            &reference.transcript,
+            args.name.clone(),
+        )
+        .context("Failed to extract voice from reference audio")?;
+
+    println!("Voice extracted: {}", voice_info.name);
+    println!("  Transcript: {}", voice_info.transcript);
+    println!("  Model: {}", voice_info.model);
+    if let Some(language) = &args.language {
+        println!("  Language: {}", language.to_uppercase());
+    }
+
+    if let Some(consent) = record_consent(engine, &voice_info.name, args)? {
+        print_consent(&consent, "  ");
+    }
+    if let Some(license) = &args.reference_license {
+        engine.record_reference_license(&voice_info.name, license)?;
+        println!("  Reference license: {license}");
+    }
+    if record_access(engine, &voice_info.name, args)? {
+        print_access(
+            &engine.voice_manager().load_metadata(&voice_info.name)?,
+            "  ",
+        );
+    }
+
+    if args.score {
+        let score = engine
+            .score_voice(&voice_info.name, &reference.audio_path)
+            .context("Failed to score cloned voice")?;
+        println!("  Similarity: {:.0}%", score * 100.0);
+    }
+    if let Some(duration) = voice_info.duration {
+        println!("  Duration: {:.2}s", duration);
+    }
+    Ok(())
+}
+
+/// Check the `-n` voice exists and record consent, access, or license for
+/// it. Returns whether anything was recorded.
+fn use_voice<B: Backend>(engine: &TTSEngine<B>, name: &str, args: &Args) -> Result<bool> {
+    engine
+        .voice_manager()
+        .load_metadata(name)
+        .with_context(|| format!("Voice '{}' not found", name))?;
+    println!("Using voice: {name}");
+
+    let consent = record_consent(engine, name, args)?;
+    if let Some(consent) = &consent {
+        println!("Consent recorded for: {name}");
+        print_consent(consent, "  ");
+    }
+    let restricted = record_access(engine, name, args)?;
+    if restricted {
+        println!("Access updated for: {name}");
+        print_access(&engine.voice_manager().load_metadata(name)?, "  ");
+    }
+    if let Some(license) = &args.reference_license {
+        engine.record_reference_license(name, license)?;
+        println!("Reference license recorded for {name}: {license}");
+    }
+    Ok(consent.is_some() || restricted || args.reference_license.is_some())
+}
+
+/// Warn when a reference clip already backs another voice, and offer to
+/// use that voice instead when running interactively.
+///
+/// Returns the voice to reuse.
+/// Download the section of a video named by a `ytdlp:VIDEO_ID@START-END;transcript`
+/// reference, returning the reference with the clip's path in its place.
+#[cfg(feature = "ytdlp")]
+fn fetch_video_reference<B: Backend>(engine: &TTSEngine<B>, reference: &str) -> Result<String> {
+    use crate::voice::ytdlp::VideoClip;
+
+    let (source, transcript) = reference
+        .split_once(';')
+        .context("Invalid reference: expected 'ytdlp:VIDEO_ID@START-END;transcript'")?;
+    let clip = VideoClip::parse(source)?;
+
+    eprintln!("==================================================================");
+    eprintln!(" WARNING: you are cloning a voice from someone else's video.");
+    eprintln!(" Their voice is theirs. Make sure you have the speaker's consent");
+    eprintln!(" and the rights to the recording, and respect the platform's");
+    eprintln!(" terms of service, before you use or share this voice.");
+    eprintln!("==================================================================");
+
+    println!(
+        "Fetching {} from {:.1}s to {:.1}s...",
+        clip.url(),
+        clip.start.as_secs_f64(),
+        clip.end.as_secs_f64()
+    );
+    let path = clip
+        .fetch(&engine.voice_manager().voices_dir().join(".downloads"))
+        .context("Failed to fetch the reference clip")?;
+    println!("  Saved to {}", path.display());
+    Ok(format!("{};{transcript}", path.display()))
+}
+
+#[cfg(not(feature = "ytdlp"))]
+fn fetch_video_reference<B: Backend>(_engine: &TTSEngine<B>, _reference: &str) -> Result<String> {
+    anyhow::bail!("ytdlp: references need open-tts-rs built with --features ytdlp")
+}
+
+/// Write the turns of one speaker of a reference clip next to it, as
+/// `<stem>.speaker<N>.wav`, and return that file.
+fn isolate_speaker(path: &Path, speaker: usize, speakers: Option<u16>) -> Result<PathBuf> {
+    let audio = decode_file(path)
+        .with_context(|| format!("Failed to read reference audio: {}", path.display()))?;
+    let found = diarize(&audio, speakers.map(usize::from));
+    println!("Speakers in {}:", path.display());
+    for n in 1..=found.speakers() {
+        let first = found.turns.iter().find(|t| t.speaker == n).map(|t| t.start);
+        println!(
+            "  {n}: {:.1}s of speech, first at {:.1}s",
+            found.talk_time(n).as_secs_f64(),
+            first.unwrap_or_default().as_secs_f64()
+        );
+    }
+
+    let Some(isolated) = found.isolate(&audio, speaker) else {
+        anyhow::bail!(
+            "--speaker {speaker}: only {} speaker(s) found in {}; set --speakers if that is too few",
+            found.speakers(),
+            path.display()
+        );
+    };
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("reference");
+    let output = path.with_file_name(format!("{stem}.speaker{speaker}.wav"));
+    fs::write(&output, isolated.to_wav_bytes()?)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    println!(
+        "Isolated speaker {speaker} ({:.1}s) to {}",
+        isolated.duration().as_secs_f64(),
+        output.display()
+    );
+    Ok(output)
+}
+
+fn reuse_duplicate<B: Backend>(
+    engine: &TTSEngine<B>,
+    reference: &str,
+    name: Option<&str>,
+) -> Result<Option<String>> {
+    let reference = Reference::parse(reference)?;
+    // An unreadable clip is reported by the extraction itself
+    let Ok(Some(duplicate)) =
+        voice::find_duplicate(engine.voice_manager(), &reference.audio_path, name)
+    else {
+        return Ok(None);
+    };
+
+    let how = if duplicate.identical {
+        "identical to".to_string()
+    } else {
+        format!("{:.0}% similar to", duplicate.similarity * 100.0)
+    };
+    eprintln!(
+        "Warning: {} is {how} the reference of voice '{}'; use -n {} to reuse it",
+        reference.audio_path.display(),
+        duplicate.voice,
+        duplicate.voice
+    );
+    if !std::io::stdin().is_terminal() {
+        return Ok(None);
+    }
+
+    let question = format!(
+        "Reuse voice '{}' instead of cloning again?",
+        duplicate.voice
+    );
+    Ok(super::confirm(&question)?.then_some(duplicate.voice))
+}
@@ -0,0 +1,209 @@
+//! Offline reports: usage, estimates, lint findings, and audio diffs.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::input::build_preprocessor;
+use crate::audio::{AudioDiff, decode_file};
+use crate::cli::{Args, ReportFormat, UsageCommand};
+use crate::config::Config;
+use crate::ingest::read_text;
+use crate::project::Project;
+use crate::text::{Document, Linter, Preprocessor, chunk_text};
+use crate::usage::{Basis, Ledger};
+
+/// Print the usage ledger totals.
+pub fn usage(command: &UsageCommand) -> Result<()> {
+    let UsageCommand::Report { since, format } = command;
+    let ledger = Ledger::new();
+    let report = ledger
+        .report(*since)
+        .with_context(|| format!("Failed to read usage ledger: {}", ledger.path().display()))?;
+    match format {
+        ReportFormat::Table => print!("{}", report.to_table()),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+/// Predict how long `files`, or the text given by -g, will take to say and
+/// to generate, from the usage ledger.
+pub fn estimate(
+    files: &[PathBuf],
+    format: ReportFormat,
+    args: &Args,
+    config: &Config,
+) -> Result<()> {
+    let text = if files.is_empty() {
+        args.generate
+            .clone()
+            .context("Nothing to estimate; pass text files or -g")?
+    } else {
+        let mut texts = Vec::new();
+        for file in files {
+            texts.push(
+                read_text(file).with_context(|| format!("Failed to read {}", file.display()))?,
+            );
+        }
+        texts.join("\n\n")
+    };
+    let text = build_preprocessor(args, config)?.process(&text);
+    let chunks =
+        chunk_text(&text, args.name.as_deref(), args.speed).context("Invalid inline tag")?;
+
+    let ledger = Ledger::new();
+    let estimate = ledger
+        .estimate(&chunks, args.model.name())
+        .with_context(|| format!("Failed to read usage ledger: {}", ledger.path().display()))?;
+    match format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&estimate)?),
+        ReportFormat::Table => {
+            println!("Characters: {}", estimate.characters);
+            println!(
+                "Audio:      {} ({})",
+                format_seconds(estimate.audio_seconds),
+                describe_basis(estimate.rate_basis)
+            );
+            match estimate.generation_seconds {
+                Some(seconds) => println!(
+                    "Generation: {} ({})",
+                    format_seconds(seconds),
+                    describe_basis(estimate.rtf_basis)
+                ),
+                None => println!("Generation: unknown (no timed syntheses in the usage ledger)"),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn describe_basis(basis: Basis) -> &'static str {
+    match basis {
+        Basis::VoiceAndModel => "from this voice on this model",
+        Basis::Voice => "from this voice on other models",
+        Basis::Model => "from other voices on this model",
+        Basis::Ledger => "from the whole usage ledger",
+        Basis::Default => "typical narration rate; no usage history",
+    }
+}
+
+/// `1h 02m 03s`, `2m 03s`, or `3.4s`.
+fn format_seconds(seconds: f64) -> String {
+    let whole = seconds.round() as u64;
+    match (whole / 3600, whole / 60 % 60, whole % 60) {
+        (0, 0, _) => format!("{seconds:.1}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, s) => format!("{h}h {m:02}m {s:02}s"),
+    }
+}
+
+/// Texts to lint, each with the name findings are reported under.
+type Sources = Vec<(String, String)>;
+
+/// Report words in a project's chapters, or in `files`, that are likely to
+/// be mispronounced. Sources are checked after normalization, so words the
+/// lexicon already respells are not reported.
+pub fn lint(
+    files: &[PathBuf],
+    dir: Option<PathBuf>,
+    format: ReportFormat,
+    args: &Args,
+    config: &Config,
+) -> Result<()> {
+    let (sources, preprocessor, mut linter): (Sources, _, _) = if files.is_empty() {
+        project_sources(dir)?
+    } else {
+        let sources = files
+            .iter()
+            .map(|f| {
+                let text =
+                    read_text(f).with_context(|| format!("Failed to read {}", f.display()))?;
+                Ok((f.display().to_string(), text))
+            })
+            .collect::<Result<_>>()?;
+        (sources, build_preprocessor(args, config)?, Linter::new())
+    };
+
+    for (name, text) in &sources {
+        let document = Document::parse(text).with_context(|| format!("Invalid {name}"))?;
+        linter.add(name, &preprocessor.process(&document.text));
+    }
+
+    let report = linter.report();
+    match format {
+        ReportFormat::Table if report.is_empty() => println!("No words to check"),
+        ReportFormat::Table => {
+            print!("{}", report.to_table());
+            let lexicon = report.lexicon();
+            if !lexicon.is_empty() {
+                println!(
+                    "\nSuggested lexicon entries (check each by ear):\n\n[lexicon]\n{lexicon}"
+                );
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    if !report.is_empty() {
+        anyhow::bail!("{} words to check", report.findings.len());
+    }
+    Ok(())
+}
+
+/// The chapters of the project in `dir`, or the one around the working
+/// directory, with the project's preprocessor and a linter that knows its
+/// lexicon and glossary.
+fn project_sources(dir: Option<PathBuf>) -> Result<(Sources, Preprocessor, Linter)> {
+    let project = match dir {
+        Some(dir) => Project::open(&dir)?,
+        None => Project::find(&std::env::current_dir()?)?,
+    };
+    let sources = project
+        .file()
+        .chapters
+        .iter()
+        .map(|chapter| {
+            let name = match chapter.chapter {
+                Some(n) => format!("{} (chapter {n})", chapter.source.display()),
+                None => chapter.source.display().to_string(),
+            };
+            Ok((name, project.source_text(chapter)?))
+        })
+        .collect::<Result<_>>()?;
+    let file = project.file();
+    let linter = Linter::new().with_known(file.lexicon.values().chain(file.glossary.values()));
+    let preprocessor = project.preprocessor()?;
+    super::warn_glossary_conflicts(&preprocessor.glossary_conflicts());
+    Ok((sources, preprocessor, linter))
+}
+
+/// Compare two audio files, failing below `threshold` similarity.
+pub fn diff(a: &Path, b: &Path, threshold: Option<f64>, format: ReportFormat) -> Result<()> {
+    let decode = |path: &Path| {
+        decode_file(path).with_context(|| format!("Failed to read audio: {}", path.display()))
+    };
+    let diff = AudioDiff::compare(&decode(a)?, &decode(b)?);
+
+    match format {
+        ReportFormat::Table => {
+            println!("Duration:   {:+.3}s", diff.duration_delta);
+            match diff.loudness_delta {
+                Some(delta) => println!("Loudness:   {delta:+.2} LU"),
+                None => println!("Loudness:   -"),
+            }
+            println!("Similarity: {:.4}", diff.similarity);
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+    if let Some(threshold) = threshold
+        && !diff.passes(threshold)
+    {
+        anyhow::bail!(
+            "{} differs from {}: similarity {:.4} below {threshold}",
+            b.display(),
+            a.display(),
+            diff.similarity
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,312 @@
+//! Long-running services: the daemon, the HTTP and MQTT servers, the
+//! relay, and `tail`.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use super::connect::{connect_backend, engine_options, pause_on_signal};
+use super::input::build_preprocessor;
+use crate::audio::{AudioSink, PlaybackDevice, PlaybackSink};
+use crate::backend::{Backend, BackendRegistry};
+use crate::cli::{Args, Command};
+use crate::config::Config;
+use crate::engine::TTSEngine;
+use crate::monitor::{AlertOptions, Alerter, Decision, LogFollower};
+use crate::relay::{RelayEvent, RelayOptions, WhisperClient, record_segments, relay};
+use crate::server::{
+    BackendTarget, Daemon, DaemonClient, EngineOptions, JobQueue, MqttBridge, MqttSettings, Server,
+};
+use crate::text::Chunk;
+use crate::voice::VoiceManager;
+
+/// Whether `args` asks for a long-running service rather than one
+/// synthesis.
+pub fn is_service(args: &Args) -> bool {
+    matches!(
+        args.command,
+        Some(
+            Command::Serve { .. }
+                | Command::Mqtt { .. }
+                | Command::Relay { .. }
+                | Command::Tail { .. }
+        )
+    )
+}
+
+/// Run the service that [`is_service`] found in `args`.
+pub fn run_service(engine: TTSEngine, args: &Args, config: &Config) -> Result<()> {
+    match &args.command {
+        Some(Command::Serve {
+            listen,
+            queue_dir,
+            workers,
+            max_per_client,
+            trusted_proxy,
+            callback_host,
+        }) => {
+            let queue_dir = queue_dir.clone().unwrap_or_else(JobQueue::default_dir);
+            let queue = JobQueue::open(&queue_dir, *max_per_client)?;
+            let server = Server::new(engine)
+                .with_queue(queue, *workers)
+                .with_trusted_proxies(trusted_proxy.clone())
+                .with_callback_hosts(callback_host.clone());
+            run_server(server, listen)
+        }
+        Some(Command::Mqtt {
+            broker,
+            topic,
+            response_topic,
+            save_dir,
+            url_base,
+        }) => {
+            let settings = MqttSettings {
+                topic: topic.clone(),
+                response_topic: response_topic.clone(),
+                output_dir: save_dir.clone(),
+                url_base: url_base.clone(),
+                ..MqttSettings::from_url(broker)?
+            };
+            run_mqtt(engine, settings)
+        }
+        Some(Command::Relay {
+            asr_url,
+            asr_model,
+            segment,
+        }) => {
+            let transcriber =
+                WhisperClient::new(asr_url, asr_model.clone()).with_language(args.language.clone());
+            run_relay(&engine, &transcriber, *segment, args)
+        }
+        Some(Command::Tail { .. }) => tail(&engine, args, config),
+        command => unreachable!("not a service: {command:?}"),
+    }
+}
+
+/// The running daemon to synthesize `-g` text with, unless `args` asks for
+/// something else or something it cannot do.
+pub fn daemon_forward(
+    args: &Args,
+    config: &Config,
+    socket: &Path,
+    target: &BackendTarget,
+) -> Option<Forward> {
+    // Only -g text goes through the daemon, which does not pass seeds or
+    // chains on
+    if args.generate.is_none()
+        || args.no_daemon
+        || args.seed.is_some()
+        || args.takes.is_some()
+        || args.chain.is_some()
+    {
+        return None;
+    }
+    DaemonClient::detect(socket).map(|client| Forward {
+        client,
+        target: target.clone(),
+        options: engine_options(args, config),
+    })
+}
+
+/// A running daemon, the backend it should synthesize with, and the engine
+/// setup it should use.
+pub struct Forward {
+    client: DaemonClient,
+    target: BackendTarget,
+    options: EngineOptions,
+}
+
+impl Forward {
+    pub(super) fn synthesize(&self, chunks: Vec<Chunk>) -> Result<Vec<u8>> {
+        Ok(self
+            .client
+            .synthesize(self.target.clone(), chunks, self.options.clone())?)
+    }
+}
+
+/// Serve synthesis requests on the control socket until killed.
+pub fn run_daemon(
+    registry: BackendRegistry,
+    voice_manager: VoiceManager,
+    socket: &Path,
+) -> Result<()> {
+    let daemon = Daemon::new(voice_manager, move |target: &BackendTarget| {
+        connect_backend(&registry, target)
+    });
+    pause_on_signal(daemon.pause().clone())?;
+    let listener = daemon
+        .bind(socket)
+        .with_context(|| format!("Failed to listen on {}", socket.display()))?;
+
+    println!(
+        "Daemon listening on {} (pid {})",
+        socket.display(),
+        std::process::id()
+    );
+    daemon.serve(listener)?;
+    Ok(())
+}
+
+/// Pause or resume synthesis in the running daemon.
+pub fn set_daemon_paused(socket: &Path, paused: bool) -> Result<()> {
+    let client = DaemonClient::detect(socket)
+        .with_context(|| format!("No daemon is listening on {}", socket.display()))?;
+    if client.set_paused(paused)? {
+        println!("Daemon paused; chunks in flight finish, then synthesis waits for `resume`");
+    } else {
+        println!("Daemon resumed");
+    }
+    Ok(())
+}
+
+fn run_server(server: Server<impl Backend + 'static>, listen: &str) -> Result<()> {
+    let http = server.bind(listen)?;
+    println!("Serving on http://{listen} (WebSocket streaming at /stream, job queue at /jobs)");
+    server.serve(http);
+    Ok(())
+}
+
+fn run_mqtt(engine: TTSEngine, settings: MqttSettings) -> Result<()> {
+    println!(
+        "Listening on {}:{} topic {} (audio to {})",
+        settings.host, settings.port, settings.topic, settings.response_topic
+    );
+    MqttBridge::new(engine, settings).run()?;
+    Ok(())
+}
+
+/// Relay the microphone through the ASR server and the backend until
+/// interrupted.
+fn run_relay(
+    engine: &TTSEngine,
+    transcriber: &WhisperClient,
+    segment: std::time::Duration,
+    args: &Args,
+) -> Result<()> {
+    let device = if args.to_virtual_mic {
+        PlaybackDevice::VirtualMic
+    } else {
+        PlaybackDevice::Speakers
+    };
+    let mut sink = PlaybackSink::new(device);
+    let options = RelayOptions {
+        voice: args.name.clone(),
+        speed: args.speed,
+        ..RelayOptions::default()
+    };
+    println!(
+        "Relaying the microphone to {} in {}-second segments; Ctrl+C to stop",
+        sink.describe(),
+        segment.as_secs_f32()
+    );
+    if device == PlaybackDevice::Speakers {
+        eprintln!("Warning: use headphones, or the microphone will hear the relayed voice");
+    }
+    relay(
+        engine,
+        transcriber,
+        &options,
+        record_segments(segment),
+        &mut sink,
+        |event| match event {
+            RelayEvent::Heard(text) => println!("> {text}"),
+            RelayEvent::Failed(e) => eprintln!("Warning: {e}"),
+            RelayEvent::Quiet | RelayEvent::Spoke(_) => {}
+        },
+    )?;
+    Ok(())
+}
+
+/// Follow the log `tail` names, speaking its alerts with `--speak`.
+fn tail(engine: &TTSEngine, args: &Args, config: &Config) -> Result<()> {
+    let Some(Command::Tail {
+        file,
+        speak,
+        filter,
+        interval,
+        dedup,
+        from_start,
+    }) = &args.command
+    else {
+        unreachable!("not tail: {:?}", args.command);
+    };
+    let follower = LogFollower::open(file, *from_start)
+        .with_context(|| format!("Failed to open: {}", file.display()))?;
+    let alerter = alerter(filter.as_deref(), *interval, *dedup)?;
+    run_tail(
+        speak.then_some(engine),
+        follower,
+        alerter,
+        file,
+        args,
+        config,
+    )
+}
+
+/// The alerter for `tail`, speaking lines that match `filter`.
+fn alerter(filter: Option<&str>, interval: Duration, dedup: Duration) -> Result<Alerter> {
+    let options = AlertOptions {
+        filter: filter
+            .map(regex::Regex::new)
+            .transpose()
+            .context("Invalid --filter")?,
+        min_interval: interval,
+        dedup_window: dedup,
+        ..AlertOptions::default()
+    };
+    Ok(Alerter::new(options))
+}
+
+/// Print each alert from the followed log, and speak it when given an
+/// engine, until interrupted.
+fn run_tail(
+    engine: Option<&TTSEngine>,
+    mut follower: LogFollower,
+    mut alerter: Alerter,
+    file: &Path,
+    args: &Args,
+    config: &Config,
+) -> Result<()> {
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+    let preprocessor = build_preprocessor(args, config)?;
+    let mut sink = PlaybackSink::new(if args.to_virtual_mic {
+        PlaybackDevice::VirtualMic
+    } else {
+        PlaybackDevice::Speakers
+    });
+    match engine {
+        Some(_) => println!("Speaking alerts from {}; Ctrl+C to stop", file.display()),
+        None => println!("Following {}; add --speak to hear alerts", file.display()),
+    }
+
+    loop {
+        let lines = follower
+            .poll()
+            .with_context(|| format!("Failed to read: {}", file.display()))?;
+        for line in lines {
+            let Decision::Speak(text) = alerter.check(&line, std::time::Instant::now()) else {
+                continue;
+            };
+            println!("> {text}");
+            let Some(engine) = engine else {
+                continue;
+            };
+            // Log text is spoken as is, never parsed for inline tags
+            let chunk = Chunk::Speech {
+                text: preprocessor.process(&text),
+                voice: args.name.clone(),
+                speed: args.speed,
+            };
+            let spoken = engine
+                .synthesize_chunks(&[chunk])
+                .map_err(anyhow::Error::from)
+                .and_then(|wav| Ok(sink.write(&wav)?));
+            if let Err(e) = spoken {
+                eprintln!("Warning: could not speak alert: {e}");
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,230 @@
+//! The first-run `setup` wizard.
+
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::audio::{play_file, record_file};
+use crate::backend::{Backend, BackendError, BackendRegistry};
+use crate::cli::{Args, Command};
+use crate::config::{
+    Config, DEFAULT_PROFILE, Discovery, Profile, READ_ALOUD, SAMPLE_TEXT, discover, save_profile,
+};
+use crate::engine::TTSEngine;
+use crate::voice::VoiceManager;
+
+/// Point a user at `setup` when there is no config file yet.
+pub fn suggest_setup(config_path: &Path, args: &Args) {
+    if !config_path.exists()
+        && args.command != Some(Command::Setup)
+        && std::io::stdin().is_terminal()
+    {
+        eprintln!(
+            "No config file yet; run `open-tts-rs setup` to find your backends and create one."
+        );
+    }
+}
+
+/// Walk a new user through finding a backend, cloning an optional first
+/// voice, hearing a sample, and saving a config profile.
+pub fn setup(
+    registry: &BackendRegistry,
+    voice_manager: VoiceManager,
+    config_path: &Path,
+    args: &Args,
+) -> Result<()> {
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("setup asks questions; run it in a terminal");
+    }
+    let config = Config::load(config_path)?;
+    let name = ask("Profile name", DEFAULT_PROFILE)?;
+    let mut profile = config.profile.get(&name).cloned().unwrap_or_default();
+    let host = args
+        .host
+        .as_deref()
+        .or(profile.host.as_deref())
+        .unwrap_or("localhost")
+        .to_string();
+    let host = ask("Backend host", &host)?;
+    profile.host = Some(host.clone());
+
+    let found = find_backends(registry, &host, &mut profile)?;
+    let running: Vec<&Discovery> = found.iter().filter(|d| d.is_up()).collect();
+    let Some(chosen) = choose_backend(&running)? else {
+        save_profile(config_path, &name, &profile)?;
+        println!("No backend answered. Start one with Docker (see backend/README.md),");
+        println!("then run `open-tts-rs setup` again.");
+        println!("Saved profile '{name}' to {}", config_path.display());
+        return Ok(());
+    };
+    let engine = connect_chosen(registry, chosen, &host, &profile, voice_manager)?;
+
+    if confirm("Clone a first voice from a clip of your own?")?
+        && let Some(voice) = first_voice(&engine, config_path)?
+    {
+        profile.voice = Some(voice);
+    }
+    play_sample(&engine, chosen, profile.voice.clone(), config_path)?;
+
+    save_profile(config_path, &name, &profile)?;
+    println!("Saved profile '{name}' to {}", config_path.display());
+    if chosen.backend != args.model.as_str() {
+        println!(
+            "Pass `-m {}` to synthesize with {}.",
+            chosen.backend, chosen.description
+        );
+    }
+    Ok(())
+}
+
+/// Look for backends on `host`, asking for a bearer token if one wants it,
+/// and list what was found.
+fn find_backends(
+    registry: &BackendRegistry,
+    host: &str,
+    profile: &mut Profile,
+) -> Result<Vec<Discovery>> {
+    println!("Looking for backends on {host}...");
+    let mut found = discover(registry, host, profile);
+    if found
+        .iter()
+        .any(|d| matches!(d.health, Err(BackendError::Unauthorized(_))))
+    {
+        let token = ask("A backend wants a bearer token (blank to skip)", "")?;
+        if !token.is_empty() {
+            profile.token = Some(token);
+            found = discover(registry, host, profile);
+        }
+    }
+    for discovery in &found {
+        let status = match &discovery.health {
+            Ok(health) => format!("running on {}", health.device),
+            Err(e) => format!("not available ({e})"),
+        };
+        println!(
+            "  {:<4} {:<14} port {:<5}  {status}",
+            discovery.backend, discovery.description, discovery.port
+        );
+    }
+    Ok(found)
+}
+
+/// The running backend to use, asking when there is more than one.
+fn choose_backend<'a>(running: &[&'a Discovery]) -> Result<Option<&'a Discovery>> {
+    let Some(first) = running.first() else {
+        return Ok(None);
+    };
+    if running.len() == 1 {
+        return Ok(Some(*first));
+    }
+    let backend = ask("Backend to use", &first.backend)?;
+    let chosen = running
+        .iter()
+        .find(|d| d.backend == backend)
+        .with_context(|| format!("'{backend}' is not a running backend"))?;
+    Ok(Some(*chosen))
+}
+
+/// An engine on the backend the user chose.
+fn connect_chosen(
+    registry: &BackendRegistry,
+    chosen: &Discovery,
+    host: &str,
+    profile: &Profile,
+    voice_manager: VoiceManager,
+) -> Result<TTSEngine> {
+    let backend = registry.connect(
+        &chosen.backend,
+        host,
+        Some(chosen.port),
+        profile.token.clone(),
+        false,
+    )?;
+    Ok(TTSEngine::new(backend, voice_manager))
+}
+
+/// Synthesize a sample next to the config and offer to play it. A failed
+/// sample is a warning, so setup can still save the profile.
+fn play_sample<B: Backend>(
+    engine: &TTSEngine<B>,
+    chosen: &Discovery,
+    voice: Option<String>,
+    config_path: &Path,
+) -> Result<()> {
+    let sample = config_path.with_file_name("setup-sample.wav");
+    println!("Synthesizing a sample with {}...", chosen.description);
+    match engine.synthesize(SAMPLE_TEXT, voice, 1.0) {
+        Ok(wav) => {
+            if let Some(dir) = sample.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&sample, wav)?;
+            println!("Sample saved to {}", sample.display());
+            if confirm("Play it?")?
+                && let Err(e) = play_file(&sample)
+            {
+                eprintln!("Warning: {e}");
+            }
+        }
+        Err(e) => eprintln!("Warning: sample synthesis failed: {e}"),
+    }
+    Ok(())
+}
+
+/// Extract the wizard's first voice from a recorded or existing clip.
+///
+/// Failures are warnings, so setup can still save the profile.
+fn first_voice<B: Backend>(engine: &TTSEngine<B>, config_path: &Path) -> Result<Option<String>> {
+    let (clip, transcript) = if ask("Record a clip now (r) or use a WAV file (f)", "r")? == "f" {
+        let clip = PathBuf::from(ask("Path to the WAV clip", "")?);
+        (clip, ask("What is said in the clip", "")?)
+    } else {
+        let clip = config_path.with_file_name("setup-reference.wav");
+        if let Some(dir) = clip.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        println!("You will have 10 seconds to read this aloud:\n\n  {READ_ALOUD}\n");
+        ask("Press Enter to start recording", "")?;
+        if let Err(e) = record_file(&clip, Duration::from_secs(10)) {
+            eprintln!("Warning: {e}");
+            return Ok(None);
+        }
+        (clip, READ_ALOUD.to_string())
+    };
+
+    let name = ask("Voice name", "my_voice")?;
+    match engine.extract_voice(&clip, &transcript, Some(name)) {
+        Ok(voice) => {
+            println!("Voice extracted: {}", voice.name);
+            Ok(Some(voice.name))
+        }
+        Err(e) => {
+            eprintln!("Warning: could not extract the voice: {e}");
+            Ok(None)
+        }
+    }
+}
+
+/// Ask a question on the terminal; a blank answer takes `default`.
+fn ask(question: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        eprint!("{question}: ");
+    } else {
+        eprint!("{question} [{default}]: ");
+    }
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no.
+pub fn confirm(question: &str) -> Result<bool> {
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
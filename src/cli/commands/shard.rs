@@ -0,0 +1,144 @@
+//! Sharding an -i job across machines.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+
+use anyhow::{Context, Result};
+
+use crate::batch::{Job, JobChapter, JobReport, JobStore, Shard};
+use crate::cli::Args;
+use crate::manifest::write_shard;
+use crate::text::Chunk;
+
+/// Run an -i job as one shard per `--hosts` entry, each in a child
+/// process of this program with the same arguments, logging to its shard
+/// directory. Returns the shard directories once every shard succeeded.
+pub fn run_shards(args: &Args) -> Result<Vec<PathBuf>> {
+    let program = std::env::current_exe().context("Failed to find the open-tts-rs executable")?;
+    let argv = without_hosts(std::env::args_os().skip(1));
+    let count = args.hosts.len();
+
+    let mut children = Vec::with_capacity(count);
+    for (i, host) in args.hosts.iter().enumerate() {
+        let shard = Shard {
+            index: i + 1,
+            count,
+        };
+        let (dir, log, child) = spawn_shard(&program, &argv, shard, host, &args.output)?;
+        children.push((shard, host, dir, log, child));
+    }
+
+    let mut dirs = Vec::with_capacity(count);
+    let mut failed = Vec::new();
+    for (shard, host, dir, log, mut child) in children {
+        if child.wait()?.success() {
+            println!("Shard {shard} on {host} finished");
+            dirs.push(dir);
+        } else {
+            failed.push(format!("{shard} on {host} (see {})", log.display()));
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Shard(s) failed: {}; finish each with --resume and the job ID in its log, then run \
+             `merge` with the shard directories",
+            failed.join(", ")
+        );
+    }
+    Ok(dirs)
+}
+
+/// Start `shard` on `host`, returning its directory, its log, and the
+/// running child.
+fn spawn_shard(
+    program: &Path,
+    argv: &[OsString],
+    shard: Shard,
+    host: &str,
+    output: &Path,
+) -> Result<(PathBuf, PathBuf, Child)> {
+    let dir = shard.dir(output);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    let log_path = dir.join("shard.log");
+    let log = fs::File::create(&log_path)
+        .with_context(|| format!("Failed to create log: {}", log_path.display()))?;
+    let child = std::process::Command::new(program)
+        .args(argv)
+        .arg("--shard")
+        .arg(shard.to_string())
+        .arg("--host")
+        .arg(host)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .spawn()
+        .with_context(|| format!("Failed to start shard {shard}"))?;
+    println!(
+        "Shard {shard} running on {host} (log: {})",
+        log_path.display()
+    );
+    Ok((dir, log_path, child))
+}
+
+/// Command-line arguments without `--hosts`, to pass on to a shard.
+fn without_hosts(argv: impl Iterator<Item = OsString>) -> Vec<OsString> {
+    let mut kept = Vec::new();
+    let mut skip_value = false;
+    for arg in argv {
+        if std::mem::take(&mut skip_value) {
+            continue;
+        }
+        match arg.to_str() {
+            Some("--hosts") => skip_value = true,
+            Some(a) if a.starts_with("--hosts=") => {}
+            _ => kept.push(arg),
+        }
+    }
+    kept
+}
+
+/// Create the batch job for an -i text, with its chapters and, under
+/// `--shard`, the shard of it this process renders.
+pub fn start_job(
+    store: &JobStore,
+    chunks: Vec<Chunk>,
+    chapters: Vec<JobChapter>,
+    args: &Args,
+) -> Result<Job> {
+    let share = args.shard.map(|shard| shard.chunks(&chunks).len());
+    let mut job = store
+        .create(chunks, &args.output)
+        .context("Failed to create batch job")?;
+    if !chapters.is_empty() || args.shard.is_some() {
+        job.chapters = chapters;
+        job.shard = args.shard;
+        store.save(&job).context("Failed to create batch job")?;
+    }
+    match (args.shard, share) {
+        (Some(shard), Some(share)) => println!(
+            "Started job {} as shard {shard} ({share} of {} chunks)",
+            job.id,
+            job.chunks.len()
+        ),
+        _ => println!("Started job {} ({} chunks)", job.id, job.chunks.len()),
+    }
+    Ok(job)
+}
+
+/// Write the manifest of a finished shard for `merge`, returning its path.
+pub fn finish_shard(job: &Job, shard: Shard, model: &str, report: &JobReport) -> Result<PathBuf> {
+    let manifest = write_shard(job, shard, model)
+        .with_context(|| format!("Failed to write shard {shard} of job {}", job.id))?;
+    println!("Shard {shard} saved to: {}", manifest.display());
+    if !report.skipped.is_empty() {
+        println!(
+            "{} chunk(s) failed; retry them with --resume {} before merging",
+            report.skipped.len(),
+            job.id
+        );
+    }
+    Ok(manifest)
+}
@@ -0,0 +1,276 @@
+//! Batch input: CSV sheets, PBX prompt files, and JSONL job streams.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::input::build_preprocessor;
+use super::job::loaded_model;
+use crate::backend::Backend;
+use crate::batch::{
+    PromptOptions, PromptSet, RowResult, Sheet, StreamOptions, open_input, run_prompts, run_sheet,
+    run_stream,
+};
+use crate::cli::{Args, Command};
+use crate::config::Config;
+use crate::engine::TTSEngine;
+use crate::manifest::{Manifest, ManifestError};
+use crate::text::Chunk;
+use crate::usage::UsageRecord;
+
+/// The input and options of `batch --jsonl`, if that is the command.
+pub fn stream_options<'a>(
+    args: &'a Args,
+    config: &Config,
+) -> Result<Option<(&'a Path, StreamOptions)>> {
+    let Some(Command::Batch {
+        jsonl: Some(input),
+        output_dir,
+        workers,
+        ..
+    }) = &args.command
+    else {
+        return Ok(None);
+    };
+    let options = StreamOptions {
+        voice: args.name.clone(),
+        speed: args.speed,
+        output_dir: output_dir.clone(),
+        workers: *workers,
+        preprocessor: build_preprocessor(args, config)?,
+    };
+    Ok(Some((input, options)))
+}
+
+/// The options of `prompts`, if that is the command, with its prompt file.
+pub fn prompt_options<'a>(args: &'a Args, config: &Config) -> Option<(&'a Path, PromptOptions)> {
+    let Some(Command::Prompts {
+        file,
+        pbx,
+        output_dir,
+        language,
+        workers,
+    }) = &args.command
+    else {
+        return None;
+    };
+    let options = PromptOptions {
+        pbx: *pbx,
+        output_dir: output_dir.clone(),
+        locale: language.clone().unwrap_or_else(|| {
+            args.locale
+                .or(config.locale)
+                .map_or_else(|| "en-US".to_string(), |l| l.to_string())
+        }),
+        voice: args.name.clone(),
+        speed: args.speed,
+        workers: *workers,
+    };
+    Some((file, options))
+}
+
+/// Where `batch --csv` reads its rows and writes its results.
+pub struct SheetBatch<'a> {
+    csv: &'a Path,
+    output_dir: Option<&'a Path>,
+    workers: usize,
+    results: PathBuf,
+}
+
+impl<'a> SheetBatch<'a> {
+    /// The sheet of `batch --csv`, if that is the command. Results go next
+    /// to the sheet unless `--results` says otherwise.
+    pub fn from_command(command: Option<&'a Command>) -> Option<Self> {
+        let Some(Command::Batch {
+            csv: Some(csv),
+            output_dir,
+            workers,
+            results,
+            ..
+        }) = command
+        else {
+            return None;
+        };
+        Some(SheetBatch {
+            csv,
+            output_dir: output_dir.as_deref(),
+            workers: *workers,
+            results: results
+                .clone()
+                .unwrap_or_else(|| csv.with_extension("results.csv")),
+        })
+    }
+}
+
+/// Synthesize each row of a CSV sheet, then write the results sheet and a
+/// manifest of the files written.
+pub fn run_sheet_batch<B: Backend>(
+    engine: &TTSEngine<B>,
+    batch: &SheetBatch,
+    args: &Args,
+    config: &Config,
+) -> Result<()> {
+    let sheet = read_sheet(batch, args, config)?;
+    println!(
+        "Synthesizing {} rows of {}",
+        sheet.rows.len(),
+        batch.csv.display()
+    );
+    let results = run_sheet(
+        engine,
+        &sheet,
+        args.name.as_deref(),
+        args.speed,
+        batch.workers,
+        |row, result| match &result.error {
+            None => println!("\r  {}: {:.1}s", result.output.display(), result.seconds),
+            Some(e) => println!("\r  {} (line {}): {e}", result.output.display(), row.line),
+        },
+    );
+    record_parallel(results.iter().map(|r| (&r.chunks[..], r.seconds)), args);
+
+    write_results(&sheet, batch, &results)?;
+
+    let manifest_path = args
+        .manifest
+        .clone()
+        .unwrap_or_else(|| Manifest::default_path(batch.csv));
+    write_manifest(
+        engine,
+        Manifest::for_rows(&results, args.model.name()),
+        &manifest_path,
+    )?;
+
+    let failed = results.iter().filter(|r| !r.is_done()).count();
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} rows failed; see {}",
+            results.len(),
+            batch.results.display()
+        );
+    }
+    Ok(())
+}
+
+/// Read the sheet of `batch`, with its text preprocessed.
+fn read_sheet(batch: &SheetBatch, args: &Args, config: &Config) -> Result<Sheet> {
+    let mut sheet = Sheet::read(batch.csv, batch.output_dir)?;
+    let preprocessor = build_preprocessor(args, config)?;
+    for row in &mut sheet.rows {
+        row.text = preprocessor.process(&row.text);
+    }
+    Ok(sheet)
+}
+
+/// Write the results sheet, the input sheet with each row's outcome.
+fn write_results(sheet: &Sheet, batch: &SheetBatch, results: &[RowResult]) -> Result<()> {
+    sheet
+        .write_results(&batch.results, results)
+        .with_context(|| format!("Failed to write results: {}", batch.results.display()))?;
+    println!("Results saved to: {}", batch.results.display());
+    Ok(())
+}
+
+/// Synthesize a prompt file into a PBX prompt directory, with a manifest
+/// of the files written.
+pub fn run_prompt_set<B: Backend>(
+    engine: &TTSEngine<B>,
+    file: &Path,
+    options: &PromptOptions,
+    args: &Args,
+    config: &Config,
+) -> Result<()> {
+    let mut set = PromptSet::read(file)?;
+    let preprocessor = build_preprocessor(args, config)?;
+    for prompt in &mut set.prompts {
+        prompt.text = preprocessor.process(&prompt.text);
+    }
+
+    println!(
+        "Synthesizing {} prompts into {}",
+        set.prompts.len(),
+        options.output_dir.display()
+    );
+    let results = run_prompts(engine, &set, options, |result| match &result.error {
+        None => println!(
+            "  {}: {:.1}s, {} files",
+            result.id,
+            result.seconds,
+            result.files.len()
+        ),
+        Some(e) => println!("  {}: {e}", result.id),
+    });
+    record_parallel(results.iter().map(|r| (&r.chunks[..], r.seconds)), args);
+
+    let manifest_path = args
+        .manifest
+        .clone()
+        .unwrap_or_else(|| options.output_dir.join("manifest.json"));
+    fs::create_dir_all(&options.output_dir)?;
+    let manifest = Manifest::for_prompts(&results, args.model.name());
+    write_manifest(engine, manifest, &manifest_path)?;
+
+    let failed = results.iter().filter(|r| !r.is_done()).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} prompts failed", results.len());
+    }
+    Ok(())
+}
+
+/// Record the usage of chunks synthesized in parallel, whose generation
+/// time is unknown.
+fn record_parallel<'a>(runs: impl Iterator<Item = (&'a [Chunk], f64)>, args: &Args) {
+    super::record_usage(Ok(runs
+        .flat_map(|(chunks, seconds)| {
+            UsageRecord::for_chunks(
+                chunks,
+                seconds,
+                None,
+                args.model.name(),
+                args.project.as_deref(),
+            )
+        })
+        .collect()));
+}
+
+/// Write a batch manifest with the model the backend has loaded.
+fn write_manifest<B: Backend>(
+    engine: &TTSEngine<B>,
+    manifest: Result<Manifest, ManifestError>,
+    path: &Path,
+) -> Result<()> {
+    manifest
+        .and_then(|mut manifest| {
+            manifest.backend_model = loaded_model(engine);
+            manifest.write(path)
+        })
+        .with_context(|| format!("Failed to write manifest: {}", path.display()))?;
+    println!("Manifest saved to: {}", path.display());
+    Ok(())
+}
+
+/// Run JSON-lines jobs from `input`, writing a result line per job to stdout.
+pub fn run_job_stream<B: Backend>(
+    engine: &TTSEngine<B>,
+    input: &Path,
+    options: &StreamOptions,
+    args: &Args,
+) -> Result<()> {
+    let input = open_input(input).with_context(|| format!("Failed to open {}", input.display()))?;
+    let summary = run_stream(engine, input, std::io::stdout(), options, |result| {
+        super::record_usage(Ok(UsageRecord::for_chunks(
+            &result.chunks,
+            result.seconds,
+            None,
+            args.model.name(),
+            args.project.as_deref(),
+        )))
+    })?;
+
+    eprintln!("{} job(s) done, {} failed", summary.done, summary.failed);
+    if summary.failed > 0 {
+        anyhow::bail!("{} job(s) failed", summary.failed);
+    }
+    Ok(())
+}
@@ -0,0 +1,395 @@
+//! Speaking `-g` text to files, speakers, and streams.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+
+use super::PostProcess;
+use super::input::{apply_document, build_preprocessor, pacing, personalize};
+use super::job::{visualize, write_tracks, write_visemes};
+use super::service::Forward;
+use crate::audio::{
+    AudioBuffer, AudioSink, FileSink, IcecastSink, IcecastTarget, PlaybackDevice, PlaybackSink,
+    Preset, QaReport, QaThresholds, concat, decode_file, rank_reports,
+};
+use crate::backend::Backend;
+use crate::cli::Args;
+use crate::config::Config;
+use crate::engine::TTSEngine;
+use crate::journal::{Event, Journal};
+use crate::text::{Chunk, Pacing, chunk_text, pace};
+use crate::usage::UsageRecord;
+
+/// Speak the `-g` text, once per recipient, in one take or several.
+pub fn generate(
+    mut engine: TTSEngine,
+    daemon: Option<&Forward>,
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    icecast: &Option<IcecastTarget>,
+    journal: Option<&Journal>,
+) -> Result<()> {
+    let template = args.generate.as_deref().unwrap_or_default();
+    let preprocessor = build_preprocessor(args, config)?;
+    for (text, args) in personalize(template, args)? {
+        let (text, args) = apply_document(&text, args)?;
+        let text = preprocessor.process(&text);
+        if let Some(takes) = args.takes {
+            engine = speak_takes(engine, takes, &text, &args, config, post, journal)?;
+            continue;
+        }
+        speak(&engine, daemon, &text, &args, config, post, icecast)?;
+        if icecast.is_none() {
+            super::record(
+                journal,
+                Event::OutputWritten {
+                    job: None,
+                    file: args.output.clone(),
+                },
+            );
+        }
+        if let Some(report) = &args.qa_report {
+            let reports = run_qa(std::slice::from_ref(&args.output), &config.qa, report)?;
+            check_qa(&reports, report)?;
+        }
+    }
+    Ok(())
+}
+
+/// Speak `text` in `takes` takes with consecutive seeds, then rank and
+/// check them as asked. Returns the engine, seeded for the last take.
+fn speak_takes(
+    mut engine: TTSEngine,
+    takes: u32,
+    text: &str,
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    journal: Option<&Journal>,
+) -> Result<TTSEngine> {
+    let first_seed = args.seed.unwrap_or_else(random_seed);
+    let mut outputs = Vec::new();
+    for take in 1..=takes {
+        let seed = first_seed.wrapping_add(u64::from(take - 1));
+        let take_args = Args {
+            output: take_path(&args.output, take),
+            ..args.clone()
+        };
+        println!("Take {take}/{takes} (seed {seed})");
+        engine = engine.with_seed(Some(seed));
+        speak(&engine, None, text, &take_args, config, post, &None)?;
+        super::record(
+            journal,
+            Event::OutputWritten {
+                job: None,
+                file: take_args.output.clone(),
+            },
+        );
+        outputs.push((take_args.output, seed));
+    }
+    let files: Vec<PathBuf> = outputs.iter().map(|(file, _)| file.clone()).collect();
+    if args.rank_takes {
+        rank_takes(&outputs, &config.qa)?;
+    }
+    if let Some(report) = &args.qa_report {
+        let reports = run_qa(&files, &config.qa, report)?;
+        check_qa(&reports, report)?;
+    }
+    Ok(engine)
+}
+
+pub(super) fn is_icecast(output: &Path) -> bool {
+    output.to_str().is_some_and(IcecastTarget::is_url)
+}
+
+/// The Icecast mount named by `--output`, if any.
+///
+/// A stream has no file to tag or analyze afterwards, so options
+/// that need one are rejected.
+pub fn icecast_output(args: &Args) -> Result<Option<IcecastTarget>> {
+    let Some(url) = args.output.to_str().filter(|o| IcecastTarget::is_url(o)) else {
+        return Ok(None);
+    };
+
+    let needs_file = [
+        (args.input_file.is_some(), "--input-file"),
+        (args.tag, "--tag"),
+        (args.visemes, "--visemes"),
+        (args.visualize.is_some(), "--visualize"),
+        (args.qa_report.is_some(), "--qa-report"),
+        (args.takes.is_some(), "--takes"),
+    ];
+    if let Some((_, flag)) = needs_file.iter().find(|(set, _)| *set) {
+        anyhow::bail!("{flag} needs a file output, not an Icecast stream");
+    }
+    Ok(Some(IcecastTarget::from_url(url)?))
+}
+
+/// Where `--output` sends audio: an Icecast stream or a file.
+fn output_sink(
+    args: &Args,
+    icecast: Option<IcecastTarget>,
+    preset: Option<Preset>,
+) -> Box<dyn AudioSink> {
+    match icecast {
+        Some(target) => Box::new(IcecastSink::new(
+            target,
+            args.name.as_deref().unwrap_or("open-tts-rs"),
+        )),
+        None => Box::new(FileSink::new(&args.output).with_preset(preset)),
+    }
+}
+
+/// Players for `--play` and `--to-virtual-mic`, run after the output is written.
+pub(super) fn playback_sinks(args: &Args) -> Vec<Box<dyn AudioSink>> {
+    let mut sinks: Vec<Box<dyn AudioSink>> = Vec::new();
+    if args.play {
+        sinks.push(Box::new(PlaybackSink::new(PlaybackDevice::Speakers)));
+    }
+    if args.to_virtual_mic {
+        sinks.push(Box::new(PlaybackSink::new(PlaybackDevice::VirtualMic)));
+    }
+    sinks
+}
+
+/// Check audio files against the QA thresholds and write the JSON report.
+pub(super) fn run_qa(
+    files: &[PathBuf],
+    thresholds: &QaThresholds,
+    report: &Path,
+) -> Result<Vec<QaReport>> {
+    let mut reports = Vec::with_capacity(files.len());
+    for file in files {
+        // Decoded by container, as --preset may have written μ-law or MP3
+        let audio = decode_file(file)
+            .with_context(|| format!("Failed to read audio: {}", file.display()))?;
+        reports.push(QaReport::new(file, &audio, thresholds));
+    }
+
+    fs::write(report, serde_json::to_string_pretty(&reports)?)
+        .with_context(|| format!("Failed to write QA report: {}", report.display()))?;
+
+    let passed = reports.iter().filter(|r| r.passed()).count();
+    println!("QA: {passed}/{} file(s) passed", reports.len());
+    for failed in reports.iter().filter(|r| !r.passed()) {
+        println!(
+            "  FAIL {}: {}",
+            failed.file.display(),
+            failed.failures.join("; ")
+        );
+    }
+    println!("QA report saved to: {}", report.display());
+
+    Ok(reports)
+}
+
+/// Synthesize `text` to `--output`, then tag it and write the visemes and
+/// visualization asked for.
+fn speak<B: Backend>(
+    engine: &TTSEngine<B>,
+    daemon: Option<&Forward>,
+    text: &str,
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+    icecast: &Option<IcecastTarget>,
+) -> Result<()> {
+    let mut sinks = vec![output_sink(args, icecast.clone(), post.preset.clone())];
+    // --stream plays while generating instead of once the output is written
+    let mut players = playback_sinks(args);
+    if !args.stream {
+        sinks.append(&mut players);
+    }
+    generate_speech(engine, daemon, &mut sinks, &mut players, text, args, post)?;
+    super::tag_output(args, config, &args.output)?;
+    if args.visemes {
+        let chunks = chunk_text(text, None, 1.0).context("Invalid inline tag")?;
+        write_visemes(&args.output, &chunks)?;
+    }
+    if let Some(image) = &args.visualize {
+        visualize(&args.output, image)?;
+    }
+    Ok(())
+}
+
+/// `out.wav` becomes `out.take2.wav` for take 2.
+fn take_path(output: &Path, take: u32) -> PathBuf {
+    crate::paths::with_stem_suffix(output, &format!(".take{take}"))
+}
+
+/// A first seed for --takes without --seed. It is printed with each take,
+/// so a favourite can be generated again.
+fn random_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    u64::from(nanos % 1_000_000)
+}
+
+/// List takes best first by their QA measurements.
+fn rank_takes(takes: &[(PathBuf, u64)], thresholds: &QaThresholds) -> Result<()> {
+    let mut reports = Vec::with_capacity(takes.len());
+    for (file, _) in takes {
+        let audio = AudioBuffer::from_wav_bytes(&fs::read(file)?)
+            .with_context(|| format!("Failed to read audio: {}", file.display()))?;
+        reports.push(QaReport::new(file, &audio, thresholds));
+    }
+
+    println!("Takes, best first:");
+    for (rank, i) in rank_reports(&reports).into_iter().enumerate() {
+        let (report, seed) = (&reports[i], takes[i].1);
+        let lufs = report
+            .metrics
+            .lufs
+            .map_or("-".to_string(), |lufs| format!("{lufs:.1} LUFS"));
+        let verdict = if report.passed() {
+            "passed".to_string()
+        } else {
+            report.failures.join("; ")
+        };
+        println!(
+            "  {}. {} (seed {seed}): {lufs}, peak {:.1} dBTP, {verdict}",
+            rank + 1,
+            report.file.display(),
+            report.metrics.true_peak_db
+        );
+    }
+    Ok(())
+}
+
+/// Exit with an error when any file failed QA.
+pub(super) fn check_qa(reports: &[QaReport], report: &Path) -> Result<()> {
+    let failed = reports.iter().filter(|r| !r.passed()).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} file(s) failed QA; see {}", report.display());
+    }
+    Ok(())
+}
+
+/// Synthesize `-g` text, through the daemon when one is running.
+///
+/// `--tracks` needs the per-voice clips and always runs in-process, as
+/// does `--stream`, which plays to `players` sentence by sentence.
+fn generate_speech<B: Backend>(
+    engine: &TTSEngine<B>,
+    daemon: Option<&Forward>,
+    sinks: &mut [Box<dyn AudioSink>],
+    players: &mut [Box<dyn AudioSink>],
+    text: &str,
+    args: &Args,
+    post: &PostProcess,
+) -> Result<()> {
+    println!("Generating speech...");
+    if let Some(ref name) = args.name {
+        println!("  Voice: {}", name);
+    }
+    println!("  Speed: {:.1}x", args.speed);
+
+    let mut chunks =
+        chunk_text(text, args.name.as_deref(), args.speed).context("Invalid inline tag")?;
+    // Short text is sent whole unless pauses were asked for, or it is
+    // streamed a sentence at a time
+    if pacing(args) != Pacing::default() || args.stream {
+        chunks = pace(chunks, pacing(args));
+    }
+    let started = Instant::now();
+    let audio_data = synthesize_text(engine, daemon, players, &chunks, args)?;
+    let elapsed = started.elapsed();
+    let audio_data = if post.is_empty() {
+        audio_data
+    } else {
+        post.apply(&audio_data)?
+    };
+    let seconds = AudioBuffer::from_wav_bytes(&audio_data)?
+        .duration()
+        .as_secs_f64();
+    super::record_usage(Ok(UsageRecord::for_chunks(
+        &chunks,
+        seconds,
+        Some(elapsed),
+        args.model.name(),
+        args.project.as_deref(),
+    )));
+
+    deliver(sinks, &audio_data)?;
+    println!("  Size: {} bytes", audio_data.len());
+
+    Ok(())
+}
+
+/// Synthesize `chunks` the way [`generate_speech`] describes.
+fn synthesize_text<B: Backend>(
+    engine: &TTSEngine<B>,
+    daemon: Option<&Forward>,
+    players: &mut [Box<dyn AudioSink>],
+    chunks: &[Chunk],
+    args: &Args,
+) -> Result<Vec<u8>> {
+    let audio_data = match (&args.tracks, daemon) {
+        _ if args.stream => stream_speech(engine, chunks, players, args.stream_workers)?,
+        (Some(dir), _) => {
+            let (mixdown, tracks) = engine
+                .synthesize_tracks(chunks)
+                .context("Failed to synthesize speech")?;
+            write_tracks(dir, &tracks)?;
+            mixdown.to_wav_bytes()?
+        }
+        (None, Some(daemon)) => {
+            println!("  Using daemon");
+            daemon
+                .synthesize(chunks.to_vec())
+                .context("Failed to synthesize speech in the daemon")?
+        }
+        (None, None) => engine
+            .synthesize_chunks(chunks)
+            .context("Failed to synthesize speech")?,
+    };
+    if args.tracks.is_some() || args.stream || daemon.is_none() {
+        // End the progress line
+        println!();
+    }
+    Ok(audio_data)
+}
+
+/// Play each sentence of `chunks` as soon as it is synthesized, with
+/// `workers` sentences generating at once, and return the whole recording.
+fn stream_speech<B: Backend>(
+    engine: &TTSEngine<B>,
+    chunks: &[Chunk],
+    players: &mut [Box<dyn AudioSink>],
+    workers: usize,
+) -> Result<Vec<u8>> {
+    let mut pieces = Vec::new();
+    let mut played = Ok(());
+    let streamed = engine.synthesize_streaming_ahead(chunks, workers, |audio| {
+        played = audio
+            .to_wav_bytes()
+            .map_err(anyhow::Error::from)
+            .and_then(|wav| {
+                players.iter_mut().try_for_each(|player| {
+                    player
+                        .write(&wav)
+                        .with_context(|| format!("Failed to play audio on: {}", player.describe()))
+                })
+            });
+        pieces.push(audio);
+        played.is_ok()
+    });
+    played?;
+    streamed.context("Failed to synthesize speech")?;
+    Ok(concat(&pieces)?.to_wav_bytes()?)
+}
+
+/// Send finished audio to each sink in turn.
+pub(super) fn deliver(sinks: &mut [Box<dyn AudioSink>], wav: &[u8]) -> Result<()> {
+    for sink in sinks {
+        let destination = sink.describe();
+        sink.write(wav)
+            .with_context(|| format!("Failed to write audio to: {destination}"))?;
+        println!("Audio sent to: {destination}");
+    }
+    Ok(())
+}
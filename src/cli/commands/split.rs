@@ -0,0 +1,143 @@
+//! Splitting a batch text at its chapters, and its finished audio at
+//! chapters and every `--split-every`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use super::{PostProcess, tag_output, tags_requested};
+use crate::audio::AudioBuffer;
+use crate::batch::{Job, JobChapter, job_offsets, m3u_index, plan_parts};
+use crate::cli::Args;
+use crate::config::Config;
+use crate::scratch::persist;
+use crate::text::{
+    ChapterText, Chunk, DEFAULT_CHAPTER_PATTERN, Pacing, Preprocessor, chunk_text, pace,
+    split_chapters,
+};
+
+/// The pattern of chapter headings when splitting by chapter.
+pub fn chapter_pattern(args: &Args, config: &Config) -> Result<Option<Regex>> {
+    if !args.split_chapters && args.chapter_pattern.is_none() {
+        return Ok(None);
+    }
+    let pattern = args
+        .chapter_pattern
+        .as_deref()
+        .or(config.chapter_pattern.as_deref())
+        .unwrap_or(DEFAULT_CHAPTER_PATTERN);
+    let regex =
+        Regex::new(pattern).with_context(|| format!("Invalid chapter pattern: {pattern}"))?;
+    Ok(Some(regex))
+}
+
+/// Paced chunks of a batch text and, when splitting by chapter, the chunk
+/// each chapter starts at.
+pub fn text_chunks(
+    text: &str,
+    args: &Args,
+    preprocessor: &Preprocessor,
+    chapter_pattern: Option<&Regex>,
+    pacing: Pacing,
+) -> Result<(Vec<Chunk>, Vec<JobChapter>)> {
+    let sections = match chapter_pattern {
+        Some(pattern) => split_chapters(text, pattern),
+        None => vec![ChapterText {
+            title: String::new(),
+            text: text.to_string(),
+        }],
+    };
+    if chapter_pattern.is_some() && sections.iter().all(|s| s.title.is_empty()) {
+        eprintln!("Warning: no chapter headings matched; the output is not split by chapter");
+    }
+
+    let mut chunks = Vec::new();
+    let mut chapters = Vec::new();
+    for section in sections {
+        if !section.title.is_empty() {
+            chapters.push(JobChapter {
+                title: section.title,
+                start: chunks.len(),
+            });
+        }
+        let text = preprocessor.process(&section.text);
+        let section_chunks =
+            chunk_text(&text, args.name.as_deref(), args.speed).context("Invalid inline tag")?;
+        chunks.extend(pace(section_chunks, pacing));
+    }
+    Ok((chunks, chapters))
+}
+
+/// Split a finished job's processed audio at its chapters and every
+/// `--split-every`, writing `<stem>-01.<ext>`, ... and a `<stem>.m3u` index
+/// next to the output. Tagged parts are numbered as chapters. `audio` is
+/// not yet watermarked; each part is marked on its own, so every part
+/// verifies.
+pub fn write_parts(
+    job: &Job,
+    audio: &[u8],
+    args: &Args,
+    config: &Config,
+    post: &PostProcess,
+) -> Result<Vec<PathBuf>> {
+    if job.chapters.is_empty() && args.split_every.is_none() {
+        return Ok(Vec::new());
+    }
+    let buffer =
+        AudioBuffer::from_wav_bytes(audio).context("Failed to decode audio for splitting")?;
+    let offsets = scaled_offsets(job, buffer.duration())?;
+    let parts = plan_parts(&job.chapters, &offsets, buffer.duration(), args.split_every);
+
+    let extension = job.output.extension().unwrap_or("wav".as_ref());
+    let mut index = Vec::with_capacity(parts.len());
+    let mut written = Vec::with_capacity(parts.len() + 1);
+    for (i, part) in parts.into_iter().enumerate() {
+        let mut file = crate::paths::stem_with(&job.output, &format!("-{:02}.", i + 1));
+        file.push(extension);
+        let path = job.output.with_file_name(&file);
+        let wav = post.mark(&buffer.slice(part.start, part.end).to_wav_bytes()?)?;
+        persist(&path, &post.encode(&wav)?)
+            .with_context(|| format!("Failed to write audio to: {}", path.display()))?;
+        tag_part(&path, i + 1, args, config)?;
+        index.push((part, file.to_string_lossy().into_owned()));
+        written.push(path);
+    }
+
+    let index_path = job.output.with_extension("m3u");
+    fs::write(&index_path, m3u_index(&index))
+        .with_context(|| format!("Failed to write index: {}", index_path.display()))?;
+    println!(
+        "Split into {} parts, indexed in: {}",
+        index.len(),
+        index_path.display()
+    );
+    written.push(index_path);
+    Ok(written)
+}
+
+/// Tag a written part as chapter `number`, when any tags were asked for.
+fn tag_part(path: &Path, number: usize, args: &Args, config: &Config) -> Result<()> {
+    if !tags_requested(args) {
+        return Ok(());
+    }
+    let args = Args {
+        tag_chapter: u32::try_from(number).ok(),
+        ..args.clone()
+    };
+    tag_output(&args, config, path)
+}
+
+/// Where each chunk of `job` starts in its saved audio of `duration`.
+fn scaled_offsets(job: &Job, duration: Duration) -> Result<Vec<Duration>> {
+    let offsets = job_offsets(job).context("Failed to read chunk audio for splitting")?;
+    // Post-processing such as --target-duration changes the length
+    let assembled = offsets.last().copied().unwrap_or_default();
+    let scale = match assembled.is_zero() {
+        true => 1.0,
+        false => duration.as_secs_f64() / assembled.as_secs_f64(),
+    };
+    Ok(offsets.iter().map(|o| o.mul_f64(scale)).collect())
+}
@@ -0,0 +1,53 @@
+//! Metadata tags on finished outputs.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::audio::TagContext;
+use crate::cli::Args;
+use crate::config::Config;
+
+/// Embed metadata in a finished output when `--tag` or a `--tag-*` option is set.
+///
+/// `--tag-*` values override the templates from the `[tags]` config section.
+pub fn tag_output(args: &Args, config: &Config, path: &Path) -> Result<()> {
+    if !tags_requested(args) {
+        return Ok(());
+    }
+
+    let mut templates = config.tags.clone();
+    for (template, value) in [
+        (&mut templates.title, &args.tag_title),
+        (&mut templates.artist, &args.tag_artist),
+        (&mut templates.album, &args.tag_album),
+    ] {
+        if let Some(value) = value {
+            *template = value.clone();
+        }
+    }
+
+    let context = TagContext {
+        voice: args.name.as_deref(),
+        file: path,
+        chapter: args.tag_chapter,
+    };
+    let generated_by = format!(
+        "open-tts-rs {} ({})",
+        env!("CARGO_PKG_VERSION"),
+        args.model.name()
+    );
+    templates
+        .render(&context, &generated_by)
+        .write(path)
+        .with_context(|| format!("Failed to tag: {}", path.display()))?;
+
+    println!("Tagged: {}", path.display());
+    Ok(())
+}
+
+/// Whether `--tag` or any `--tag-*` option asks for metadata.
+pub fn tags_requested(args: &Args) -> bool {
+    let overrides = [&args.tag_title, &args.tag_artist, &args.tag_album];
+    args.tag || args.tag_chapter.is_some() || overrides.iter().any(|o| o.is_some())
+}
@@ -0,0 +1,412 @@
+//! The voice library: listing, deleting, consent and access, and the
+//! `voices` subcommands.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::backend::Backend;
+use crate::cli::{Args, ReportFormat, VoicesCommand};
+use crate::config::Config;
+use crate::engine::TTSEngine;
+use crate::manifest::Manifest;
+use crate::voice::{
+    self, Consent, DEFAULT_TRASH_DAYS, EmbeddingSource, LicenseReport, VoiceManager, VoicePack,
+};
+
+/// Download a voice pack, verify it, and install each of its voices.
+pub fn install_pack<B: Backend>(
+    engine: &TTSEngine<B>,
+    source: &str,
+    sha256: Option<&str>,
+) -> Result<()> {
+    let expected = voice::expected_checksum(source, sha256)
+        .context("Pass the pack's published checksum with --sha256 to verify it")?;
+    println!("Downloading voice pack: {source}");
+    let archive = voice::fetch(source).with_context(|| format!("Failed to fetch {source}"))?;
+    voice::verify_checksum(&archive, &expected)?;
+
+    let pack = VoicePack::from_archive(&archive).context("Failed to unpack voice pack")?;
+    println!(
+        "Installing {} {} ({} voices)",
+        pack.manifest.name,
+        pack.manifest.version,
+        pack.manifest.voices.len()
+    );
+
+    for voice in &pack.manifest.voices {
+        let clip = pack.clip(&voice.name).with_context(|| {
+            format!("Voice pack lists '{}' but holds no clip for it", voice.name)
+        })?;
+        engine
+            .install_voice(
+                &voice.name,
+                &voice.transcript,
+                clip,
+                voice.language.as_deref(),
+            )
+            .with_context(|| format!("Failed to install voice '{}'", voice.name))?;
+        println!("  Installed: {}", voice.name);
+    }
+
+    Ok(())
+}
+
+/// Write a voice's speaker embedding to `output` as `.npy` or `.json`.
+pub fn export_embedding<B: Backend>(
+    engine: &TTSEngine<B>,
+    name: &str,
+    output: &Path,
+) -> Result<()> {
+    let embedding = engine
+        .speaker_embedding(name)
+        .with_context(|| format!("Failed to get embedding for voice '{name}'"))?;
+    embedding
+        .write(output)
+        .with_context(|| format!("Failed to write embedding: {}", output.display()))?;
+
+    let source = match embedding.source {
+        EmbeddingSource::Backend => "model embedding",
+        EmbeddingSource::Voiceprint => "local voiceprint",
+    };
+    println!("Embedding for '{name}' saved to: {}", output.display());
+    println!("  Shape: {:?} ({source})", embedding.shape);
+    Ok(())
+}
+
+/// Upload the local voice store to the configured remote, or download it.
+#[cfg(feature = "remote")]
+pub fn sync_remote<B: Backend>(engine: &TTSEngine<B>, config: &Config, push: bool) -> Result<()> {
+    use crate::voice::remote;
+
+    let remote_config = config
+        .remote
+        .as_ref()
+        .context("No [remote] section in the config file")?;
+    let store = remote::open_remote(remote_config).context("Failed to open remote store")?;
+
+    let report = if push {
+        remote::push(engine.voice_manager(), store.as_ref()).context("Failed to push voices")?
+    } else {
+        remote::pull(engine.voice_manager(), store.as_ref()).context("Failed to pull voices")?
+    };
+
+    let direction = if push { "Uploaded" } else { "Downloaded" };
+    println!("{direction} {} file(s)", report.transferred.len());
+    for name in &report.transferred {
+        println!("  {name}");
+    }
+    if report.unchanged > 0 {
+        println!("{} file(s) already up to date", report.unchanged);
+    }
+
+    Ok(())
+}
+
+/// Open the voice store, unlocking it when encrypted or when `--encrypt` is set.
+///
+/// The passphrase comes from `OPEN_TTS_PASSPHRASE` or an interactive prompt.
+pub fn open_voice_manager(encrypt: bool) -> Result<VoiceManager> {
+    let manager = VoiceManager::new();
+    if !encrypt && !manager.is_encrypted() {
+        return Ok(manager);
+    }
+
+    let passphrase = match std::env::var("OPEN_TTS_PASSPHRASE") {
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password("Voice store passphrase: ")
+            .context("Failed to read passphrase")?,
+    };
+    let manager = manager
+        .unlock(&passphrase)
+        .context("Failed to unlock voice store")?;
+
+    if encrypt {
+        let count = manager
+            .encrypt_all()
+            .context("Failed to encrypt voice store")?;
+        if count > 0 {
+            println!("Encrypted {count} voice file(s)");
+        }
+    }
+
+    Ok(manager)
+}
+
+/// Record the consent attestation given on the command line, if any.
+pub(super) fn record_consent<B: Backend>(
+    engine: &TTSEngine<B>,
+    name: &str,
+    args: &Args,
+) -> Result<Option<Consent>> {
+    let Some(file) = &args.consent_file else {
+        return Ok(None);
+    };
+
+    let consent = Consent::from_file(
+        file,
+        args.consent_speaker.clone(),
+        args.consent_license.clone(),
+    )
+    .with_context(|| format!("Failed to read consent file: {}", file.display()))?;
+    engine
+        .record_consent(name, consent.clone())
+        .with_context(|| format!("Failed to record consent for '{name}'"))?;
+
+    Ok(Some(consent))
+}
+
+/// Apply `--lock`, `--allow-use`, and `--unrestrict` to a voice.
+///
+/// Returns whether anything was changed.
+pub(super) fn record_access<B: Backend>(
+    engine: &TTSEngine<B>,
+    name: &str,
+    args: &Args,
+) -> Result<bool> {
+    if !args.lock && args.allow_use.is_empty() && !args.unrestrict {
+        return Ok(false);
+    }
+    engine
+        .restrict_voice(name, args.lock, args.allow_use.clone())
+        .with_context(|| format!("Failed to update access for '{name}'"))?;
+    Ok(true)
+}
+
+pub(super) fn print_access(metadata: &voice::VoiceMetadata, indent: &str) {
+    if metadata.locked {
+        println!("{indent}Locked: requires --unlock");
+    }
+    if !metadata.allowed_uses.is_empty() {
+        println!("{indent}Allowed uses: {}", metadata.allowed_uses.join(", "));
+    }
+    if !metadata.locked && metadata.allowed_uses.is_empty() {
+        println!("{indent}Access: unrestricted");
+    }
+}
+
+pub(super) fn print_consent(consent: &Consent, indent: &str) {
+    let speaker = consent.speaker.as_deref().unwrap_or("unnamed speaker");
+    match &consent.license {
+        Some(license) => println!("{indent}Consent: {speaker} ({license})"),
+        None => println!("{indent}Consent: {speaker}"),
+    }
+    let status = if consent.verify() {
+        "verified"
+    } else {
+        "MODIFIED OR MISSING"
+    };
+    println!("{indent}  File: {} [{status}]", consent.file.display());
+}
+
+pub fn list_voices<B: Backend>(engine: &TTSEngine<B>) -> Result<()> {
+    let voices = engine.list_voices().context("Failed to list voices")?;
+
+    if voices.is_empty() {
+        println!("No voices found.");
+        return Ok(());
+    }
+
+    let manager = engine.voice_manager();
+    println!("Available voices:");
+    for voice in voices {
+        println!("  {} ({})", voice.name, voice.model);
+        println!("    Transcript: {}", voice.transcript);
+        if let Some(duration) = voice.duration {
+            println!("    Duration: {:.2}s", duration);
+        }
+        let metadata = manager.load_metadata(&voice.name).ok();
+        match metadata.as_ref().and_then(|m| m.consent.as_ref()) {
+            Some(consent) => print_consent(consent, "    "),
+            None => println!("    Consent: none recorded"),
+        }
+        if let Some(metadata) = metadata.filter(|m| m.locked || !m.allowed_uses.is_empty()) {
+            print_access(&metadata, "    ");
+        }
+    }
+
+    Ok(())
+}
+
+pub fn delete_voice<B: Backend>(
+    engine: &TTSEngine<B>,
+    name: &str,
+    retention: chrono::Duration,
+) -> Result<()> {
+    engine
+        .delete_voice(name)
+        .with_context(|| format!("Failed to delete voice '{}'", name))?;
+
+    println!(
+        "Voice '{}' moved to the trash; bring it back with: open-tts-rs voices restore {}",
+        name, name
+    );
+    purge_trash(engine, retention)
+}
+
+/// Voices named in a generation manifest, each once, in order of use.
+fn manifest_voices(path: &Path) -> Result<Vec<String>> {
+    let manifest = Manifest::load(path)
+        .with_context(|| format!("Failed to read manifest: {}", path.display()))?;
+    let mut voices: Vec<String> = Vec::new();
+    for voice in manifest
+        .files
+        .iter()
+        .chain(manifest.output.as_ref())
+        .filter_map(|entry| entry.voice.clone())
+    {
+        if !voices.contains(&voice) {
+            voices.push(voice);
+        }
+    }
+    Ok(voices)
+}
+
+/// List or restore deleted voices, back up the library, report licenses,
+/// or create a random voice.
+pub fn voices<B: Backend>(
+    engine: &TTSEngine<B>,
+    command: &VoicesCommand,
+    config: &Config,
+    config_path: &Path,
+    seed: Option<u64>,
+) -> Result<()> {
+    let retention = trash_retention(config);
+    purge_trash(engine, retention)?;
+    match command {
+        VoicesCommand::Trash => list_trash(engine, retention)?,
+        VoicesCommand::Restore {
+            archive: Some(archive),
+            ..
+        } => restore_library(engine, archive, config_path)?,
+        VoicesCommand::Restore {
+            name,
+            archive: None,
+        } => restore_voice(engine, name.as_deref())?,
+        VoicesCommand::Random { save } => {
+            let info = engine
+                .random_voice(save, seed)
+                .with_context(|| format!("Failed to create random voice '{save}'"))?;
+            println!("Random voice '{}' created ({}).", info.name, info.model);
+            println!("  Sample transcript: {}", info.transcript);
+        }
+        VoicesCommand::LicenseReport { manifest, format } => {
+            license_report(engine, manifest.as_deref(), *format)?
+        }
+        VoicesCommand::Install { source, sha256 } => {
+            super::install_pack(engine, source, sha256.as_deref())?;
+        }
+        VoicesCommand::Embedding { name, output } => {
+            super::export_embedding(engine, name, output)?;
+        }
+        #[cfg(feature = "remote")]
+        VoicesCommand::PushRemote => super::sync_remote(engine, config, true)?,
+        #[cfg(feature = "remote")]
+        VoicesCommand::PullRemote => super::sync_remote(engine, config, false)?,
+        VoicesCommand::Backup { output } => {
+            let output = output.clone().unwrap_or_else(voice::default_backup_path);
+            backup(engine, config_path, &output)?
+        }
+    }
+    Ok(())
+}
+
+/// Bring the voice `name` back from the trash.
+fn restore_voice<B: Backend>(engine: &TTSEngine<B>, name: Option<&str>) -> Result<()> {
+    let name = name.context("voices restore needs a voice name or --archive")?;
+    engine
+        .restore_voice(name)
+        .with_context(|| format!("Failed to restore voice '{name}'"))?;
+    println!("Voice '{name}' restored.");
+    Ok(())
+}
+
+/// List the voices in the trash and when each is purged.
+fn list_trash<B: Backend>(engine: &TTSEngine<B>, retention: chrono::Duration) -> Result<()> {
+    let trash = engine.voice_manager().list_trash()?;
+    if trash.is_empty() {
+        println!("The trash is empty.");
+    }
+    for (name, deleted) in trash {
+        let purge = (deleted + retention).format("%Y-%m-%d");
+        println!(
+            "  {name:<24} deleted {}, purged after {purge}",
+            deleted.format("%Y-%m-%d %H:%M")
+        );
+    }
+    Ok(())
+}
+
+/// Print the licenses of every voice, or of the voices a manifest used.
+fn license_report<B: Backend>(
+    engine: &TTSEngine<B>,
+    manifest: Option<&Path>,
+    format: ReportFormat,
+) -> Result<()> {
+    let names = manifest.map(manifest_voices).transpose()?;
+    let report = LicenseReport::build(engine.voice_manager(), names.as_deref())?;
+    match format {
+        ReportFormat::Table => print!("{}", report.to_table()),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+/// Back up the voice library and config to `output`.
+fn backup<B: Backend>(engine: &TTSEngine<B>, config_path: &Path, output: &Path) -> Result<()> {
+    let manifest = voice::backup(engine.voice_manager(), config_path, output)
+        .with_context(|| format!("Failed to write backup: {}", output.display()))?;
+    println!(
+        "Backed up {} voices ({} files) to {}",
+        manifest.voices().len(),
+        manifest.files.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Restore a voice library backup, extracting restored voices on backends
+/// that keep them.
+fn restore_library<B: Backend>(
+    engine: &TTSEngine<B>,
+    archive: &Path,
+    config_path: &Path,
+) -> Result<()> {
+    let manager = engine.voice_manager();
+    let (manifest, report) = voice::restore_backup(manager, config_path, archive)
+        .with_context(|| format!("Failed to restore backup: {}", archive.display()))?;
+    for path in &report.skipped {
+        eprintln!("Warning: kept existing {path}");
+    }
+
+    for name in report.voices() {
+        let registered = manager
+            .load_metadata(name)
+            .map_err(Into::into)
+            .and_then(|metadata| engine.register_voice(&metadata));
+        if let Err(e) = registered {
+            eprintln!("Warning: voice '{name}' restored but not extracted on the backend: {e}");
+        }
+    }
+    println!(
+        "Restored {} of {} voices ({} files) from {}",
+        report.voices().len(),
+        manifest.voices().len(),
+        report.restored.len(),
+        archive.display()
+    );
+    Ok(())
+}
+
+/// How long deleted voices stay in the trash.
+pub fn trash_retention(config: &Config) -> chrono::Duration {
+    chrono::Duration::days(i64::from(config.trash_days.unwrap_or(DEFAULT_TRASH_DAYS)))
+}
+
+/// Permanently delete voices trashed longer than `retention` ago.
+fn purge_trash<B: Backend>(engine: &TTSEngine<B>, retention: chrono::Duration) -> Result<()> {
+    for name in engine.voice_manager().purge_trash(retention)? {
+        println!("Purged voice '{name}' from the trash.");
+    }
+    Ok(())
+}
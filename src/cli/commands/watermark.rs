@@ -0,0 +1,32 @@
+//! `verify-watermark`: check a file for the mark of a key.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::audio::{WATERMARK_THRESHOLD, Watermark, decode_file};
+
+/// Report whether the audio file at `path` carries `watermark`, failing
+/// when it does not. Any format the output presets write can be read.
+pub fn verify_watermark(watermark: &Watermark, path: &Path) -> Result<()> {
+    let buffer =
+        decode_file(path).with_context(|| format!("Failed to decode audio: {}", path.display()))?;
+
+    let score = watermark.score(&buffer);
+    println!("Watermark score: {score:.1} (threshold {WATERMARK_THRESHOLD:.1})");
+    if score >= WATERMARK_THRESHOLD {
+        println!("Watermark present: {}", path.display());
+        return Ok(());
+    }
+
+    if buffer.frames() < watermark.min_samples() {
+        bail!(
+            "{} is too short to verify: {:.1}s, where a watermark needs about {:.1}s at {} Hz",
+            path.display(),
+            buffer.duration().as_secs_f64(),
+            watermark.min_duration(buffer.sample_rate).as_secs_f64(),
+            buffer.sample_rate
+        );
+    }
+    bail!("No watermark found for this key in {}", path.display());
+}
@@ -1,8 +1,13 @@
 //! CLI argument parsing and validation.
 
 mod args;
+pub mod commands;
 
-pub use args::{Args, Model, Reference, ReferenceParseError};
+pub use crate::backend::Model;
+pub use args::{
+    Args, Chain, Command, LogsCommand, Reference, ReferenceParseError, ReportFormat, UsageCommand,
+    VoicesCommand,
+};
 
 #[cfg(test)]
 mod tests {
@@ -127,4 +132,481 @@ mod tests {
         let model = Model::OpenF5;
         assert_eq!(model.port(), 9288);
     }
+
+    // ===========================================
+    // Args tests
+    // ===========================================
+
+    #[test]
+    fn test_stream_needs_a_player() {
+        use clap::Parser;
+
+        let parse = |flags: &[&str]| {
+            Args::try_parse_from([&["open-tts-rs", "-g", "Hello."], flags].concat())
+        };
+        assert!(parse(&["--stream"]).is_err());
+        assert!(parse(&["--stream-workers", "3"]).is_err());
+        assert!(parse(&["--play", "--stream", "--takes", "2"]).is_err());
+
+        let args = parse(&["--play", "--to-virtual-mic", "--stream"]).unwrap();
+        assert!(args.stream);
+        assert_eq!(args.stream_workers, 2);
+        assert!(parse(&["--to-virtual-mic", "--stream", "--stream-workers", "4"]).is_ok());
+    }
+
+    #[test]
+    fn test_speaker_needs_a_reference() {
+        use clap::Parser;
+
+        let parse = |flags: &[&str]| {
+            Args::try_parse_from([&["open-tts-rs", "-n", "guest"], flags].concat())
+        };
+        assert!(parse(&["--speaker", "2"]).is_err());
+        assert!(parse(&["-r", "talk.wav;Hi.", "--speakers", "2"]).is_err());
+        assert!(parse(&["-r", "talk.wav;Hi.", "--speaker", "0"]).is_err());
+
+        let args = parse(&["-r", "talk.wav;Hi.", "--speaker", "2", "--speakers", "3"]).unwrap();
+        assert_eq!((args.speaker, args.speakers), (Some(2), Some(3)));
+    }
+
+    #[test]
+    fn test_chain_parses_generator_and_converter() {
+        use clap::Parser;
+
+        let chain = Chain::parse("of+ov").unwrap();
+        assert_eq!(chain.generator, Model::OpenF5);
+        assert_eq!(chain.converter, Model::OpenVoice);
+        assert!(Chain::parse("ov+of").is_err());
+        assert!(Chain::parse("ov+ov").is_err());
+        assert!(Chain::parse("of").is_err());
+
+        let parse = |flags: &[&str]| {
+            Args::try_parse_from([&["open-tts-rs", "-g", "Hello."], flags].concat())
+        };
+        assert_eq!(
+            parse(&["--chain", "vc+ov"])
+                .unwrap()
+                .chain
+                .unwrap()
+                .generator,
+            Model::VoxCPM
+        );
+        assert!(parse(&["--chain", "of+ov", "-m", "of"]).is_err());
+    }
+
+    #[test]
+    fn test_screenplay_cast_and_narration() {
+        use clap::Parser;
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "-i",
+            "pilot.fountain",
+            "--cast",
+            "AHAB=gravel",
+            "--cast",
+            "STARBUCK=amy-2024",
+            "--dialogue-only",
+        ])
+        .unwrap();
+        assert_eq!(args.cast, vec!["AHAB=gravel", "STARBUCK=amy-2024"]);
+        assert!(args.dialogue_only);
+        assert!(!args.skip_scene_headings);
+    }
+
+    #[test]
+    fn test_mask_profanity() {
+        use crate::text::ProfanityMask;
+        use clap::Parser;
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "-i",
+            "story.txt",
+            "--mask-profanity",
+            "substitute",
+            "--profanity",
+            "kids.toml",
+        ])
+        .unwrap();
+        assert_eq!(args.mask_profanity, Some(ProfanityMask::Substitute));
+        assert_eq!(args.profanity, vec![std::path::PathBuf::from("kids.toml")]);
+        assert!(Args::try_parse_from(["open-tts-rs", "--mask-profanity", "mute"]).is_err());
+    }
+
+    #[test]
+    fn test_voices_random_needs_a_name() {
+        use clap::Parser;
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "--seed",
+            "7",
+            "voices",
+            "random",
+            "--save",
+            "stranger1",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Voices {
+                command: VoicesCommand::Random {
+                    save: "stranger1".to_string()
+                }
+            })
+        );
+        assert_eq!(args.seed, Some(7));
+        assert!(Args::try_parse_from(["open-tts-rs", "voices", "random"]).is_err());
+    }
+
+    #[test]
+    fn test_verify_watermark_takes_key_after_subcommand() {
+        use clap::Parser;
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "verify-watermark",
+            "welcome.mp3",
+            "--watermark-key",
+            "studio",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::VerifyWatermark {
+                file: PathBuf::from("welcome.mp3")
+            })
+        );
+        assert_eq!(args.watermark_key.as_deref(), Some("studio"));
+        assert!(Args::try_parse_from(["open-tts-rs", "--verify-watermark", "a.wav"]).is_err());
+    }
+
+    #[test]
+    fn test_voices_install() {
+        use clap::Parser;
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "voices",
+            "install",
+            "https://example.com/narrators.ottsvpack",
+            "--sha256",
+            "abc123",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Voices {
+                command: VoicesCommand::Install {
+                    source: "https://example.com/narrators.ottsvpack".to_string(),
+                    sha256: Some("abc123".to_string()),
+                }
+            })
+        );
+        assert!(Args::try_parse_from(["open-tts-rs", "--install-pack", "a.ottsvpack"]).is_err());
+    }
+
+    #[cfg(feature = "remote")]
+    #[test]
+    fn test_voices_push_and_pull_remote() {
+        use clap::Parser;
+
+        let push = Args::try_parse_from(["open-tts-rs", "voices", "push-remote"]).unwrap();
+        assert_eq!(
+            push.command,
+            Some(Command::Voices {
+                command: VoicesCommand::PushRemote
+            })
+        );
+        let pull = Args::try_parse_from(["open-tts-rs", "voices", "pull-remote"]).unwrap();
+        assert_eq!(
+            pull.command,
+            Some(Command::Voices {
+                command: VoicesCommand::PullRemote
+            })
+        );
+        assert!(Args::try_parse_from(["open-tts-rs", "--push-remote"]).is_err());
+    }
+
+    #[test]
+    fn test_voices_restore_takes_name_or_archive() {
+        use clap::Parser;
+
+        let restore = |flags: &[&str]| {
+            Args::try_parse_from([&["open-tts-rs", "voices", "restore"], flags].concat())
+                .map(|args| args.command)
+        };
+        assert_eq!(
+            restore(&["--archive", "library.tar.zst"]).unwrap(),
+            Some(Command::Voices {
+                command: VoicesCommand::Restore {
+                    name: None,
+                    archive: Some(PathBuf::from("library.tar.zst")),
+                }
+            })
+        );
+        assert_eq!(
+            restore(&["library.tar.zst"]).unwrap(),
+            Some(Command::Voices {
+                command: VoicesCommand::Restore {
+                    name: Some("library.tar.zst".to_string()),
+                    archive: None,
+                }
+            })
+        );
+        assert!(restore(&[]).is_err());
+        assert!(restore(&["old", "--archive", "library.tar.zst"]).is_err());
+    }
+
+    #[test]
+    fn test_voices_embedding() {
+        use clap::Parser;
+
+        let args =
+            Args::try_parse_from(["open-tts-rs", "voices", "embedding", "amy", "-o", "amy.npy"])
+                .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Voices {
+                command: VoicesCommand::Embedding {
+                    name: "amy".to_string(),
+                    output: PathBuf::from("amy.npy"),
+                }
+            })
+        );
+        assert!(Args::try_parse_from(["open-tts-rs", "voices", "embedding", "amy"]).is_err());
+    }
+
+    #[test]
+    fn test_shards_and_merge() {
+        use crate::batch::Shard;
+        use clap::Parser;
+
+        let args =
+            Args::try_parse_from(["open-tts-rs", "-i", "book.txt", "--shard", "2/4"]).unwrap();
+        assert_eq!(args.shard, Some(Shard { index: 2, count: 4 }));
+        assert!(Args::try_parse_from(["open-tts-rs", "-i", "book.txt", "--shard", "5/4"]).is_err());
+        assert!(Args::try_parse_from(["open-tts-rs", "--shard", "1/2"]).is_err());
+        assert!(Args::try_parse_from(["open-tts-rs", "-g", "Hi", "--shard", "1/2"]).is_err());
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "-i",
+            "book.txt",
+            "--hosts",
+            "gpu1,gpu2",
+            "--hosts",
+            "gpu3",
+        ])
+        .unwrap();
+        assert_eq!(args.hosts, vec!["gpu1", "gpu2", "gpu3"]);
+        assert!(
+            Args::try_parse_from([
+                "open-tts-rs",
+                "-i",
+                "book.txt",
+                "--hosts",
+                "gpu1,gpu2",
+                "--host",
+                "gpu1"
+            ])
+            .is_err()
+        );
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "-o",
+            "book.wav",
+            "merge",
+            "book.shard-1-of-2",
+            "book.shard-2-of-2",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Merge {
+                shards: vec!["book.shard-1-of-2".into(), "book.shard-2-of-2".into()]
+            })
+        );
+        assert!(Args::try_parse_from(["open-tts-rs", "merge"]).is_err());
+    }
+
+    #[test]
+    fn test_wait_for_backend() {
+        use clap::Parser;
+        use std::time::Duration;
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "--wait-for-backend",
+            "120s",
+            "-i",
+            "book.txt",
+            "-o",
+            "book.wav",
+        ])
+        .unwrap();
+        assert_eq!(args.wait_for_backend, Some(Duration::from_secs(120)));
+        assert!(
+            Args::try_parse_from(["open-tts-rs", "--wait-for-backend", "soon", "-g", "Hi"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_schedule() {
+        use chrono::NaiveTime;
+        use clap::Parser;
+
+        let args = Args::try_parse_from([
+            "open-tts-rs",
+            "schedule",
+            "--at",
+            "02:00",
+            "--until",
+            "06:30",
+            "--workers",
+            "4",
+        ])
+        .unwrap();
+        let Some(Command::Schedule {
+            at,
+            cron,
+            until,
+            workers,
+            once,
+            ..
+        }) = args.command
+        else {
+            panic!("expected schedule");
+        };
+        assert_eq!(at, NaiveTime::from_hms_opt(2, 0, 0));
+        assert_eq!(cron, None);
+        assert_eq!(until, NaiveTime::from_hms_opt(6, 30, 0));
+        assert_eq!(workers, Some(4));
+        assert!(!once);
+
+        let args =
+            Args::try_parse_from(["open-tts-rs", "schedule", "--cron", "0 2 * * 1-5", "--once"])
+                .unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Schedule { cron: Some(cron), once: true, .. })
+                if cron.to_string() == "0 2 * * 1-5"
+        ));
+
+        for bad in [
+            ["--at", "2am"].as_slice(),
+            &["--cron", "0 2 * *"],
+            &["--at", "02:00", "--cron", "@daily"],
+        ] {
+            let argv = ["open-tts-rs", "schedule"].iter().chain(bad);
+            assert!(Args::try_parse_from(argv).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_logs_show() {
+        use clap::Parser;
+
+        let args =
+            Args::try_parse_from(["open-tts-rs", "logs", "show", "20250101-120000"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Logs {
+                command: LogsCommand::Show {
+                    job: Some("20250101-120000".to_string()),
+                    format: ReportFormat::Table
+                }
+            })
+        );
+
+        let args =
+            Args::try_parse_from(["open-tts-rs", "logs", "show", "--format", "json"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Logs {
+                command: LogsCommand::Show {
+                    job: None,
+                    format: ReportFormat::Json
+                }
+            })
+        );
+    }
+
+    // ===========================================
+    // Command handler tests
+    // ===========================================
+
+    #[test]
+    fn test_text_chunks_marks_chapters() {
+        use crate::config::Config;
+        use crate::text::{Pacing, Preprocessor};
+        use clap::Parser;
+
+        let args = Args::try_parse_from(["open-tts-rs", "--split-chapters"]).unwrap();
+        let pattern = commands::chapter_pattern(&args, &Config::default())
+            .unwrap()
+            .unwrap();
+        let text = "Chapter 1\nOne.\n\nChapter 2\nTwo.";
+        let (chunks, chapters) = commands::text_chunks(
+            text,
+            &args,
+            &Preprocessor::new(),
+            Some(&pattern),
+            Pacing::default(),
+        )
+        .unwrap();
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Chapter 1", "Chapter 2"]);
+        assert_eq!(chapters[0].start, 0);
+        assert!(chapters[1].start > 0 && chapters[1].start < chunks.len());
+
+        let plain = Args::try_parse_from(["open-tts-rs"]).unwrap();
+        assert!(
+            commands::chapter_pattern(&plain, &Config::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_write_parts_watermarks_each_part() {
+        use crate::audio::{AudioBuffer, Watermark};
+        use crate::batch::{Job, JobChapter};
+        use crate::config::Config;
+        use crate::text::Chunk;
+        use clap::Parser;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let args = Args::try_parse_from(["open-tts-rs", "--watermark-key", "studio-key"]).unwrap();
+        let config = Config::default();
+        let post = commands::PostProcess::from_args(&args, &config).unwrap();
+
+        let pause = Chunk::Pause(Duration::from_secs(3));
+        let mut job = Job::new("j", vec![pause.clone(), pause], dir.path().join("book.wav"));
+        job.chapters = ["One", "Two"]
+            .iter()
+            .enumerate()
+            .map(|(start, title)| JobChapter {
+                title: title.to_string(),
+                start,
+            })
+            .collect();
+        let samples = (0..6 * 44100)
+            .map(|i| 0.15 * (i as f32 * 0.0627).sin())
+            .collect();
+        let audio = AudioBuffer::new(samples, 44100, 1).to_wav_bytes().unwrap();
+
+        let unmarked = post.apply_unmarked(&audio).unwrap();
+        let parts = commands::write_parts(&job, &unmarked, &args, &config, &post).unwrap();
+        assert_eq!(parts.len(), 3);
+        let mark = Watermark::new("studio-key");
+        for part in &parts[..2] {
+            let wav = std::fs::read(part).unwrap();
+            assert!(mark.detect(&AudioBuffer::from_wav_bytes(&wav).unwrap()));
+        }
+    }
 }
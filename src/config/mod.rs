@@ -0,0 +1,357 @@
+//! User configuration.
+//!
+//! Settings are read from a TOML file (default `~/.open-tts-rs/config.toml`)
+//! and merged with command-line arguments by the CLI. `open-tts-rs setup`
+//! writes a first profile (see [`save_profile`]).
+
+mod settings;
+mod setup;
+
+pub use settings::{Config, ConfigError, Profile, RemoteConfig};
+pub use setup::{DEFAULT_PROFILE, Discovery, READ_ALOUD, SAMPLE_TEXT, discover, save_profile};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_config_default_path() {
+        let expected = dirs::home_dir()
+            .unwrap()
+            .join(".open-tts-rs")
+            .join("config.toml");
+        assert_eq!(Config::default_path(), expected);
+    }
+
+    #[test]
+    fn test_config_missing_file_is_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::load(&temp_dir.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_config_parse_replace_rules() {
+        let config = Config::parse(
+            r#"
+            replace = ["s/GmbH/gee em be ha/", "s/\\*//g"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.replace.len(), 2);
+        assert_eq!(config.replace[0], "s/GmbH/gee em be ha/");
+    }
+
+    #[test]
+    fn test_config_parse_glossary() {
+        let config = Config::parse(
+            r#"
+            [glossary]
+            K8s = "kubernetes"
+            SQL = "sequel"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.glossary.len(), 2);
+        assert_eq!(config.glossary["K8s"], "kubernetes");
+    }
+
+    #[test]
+    fn test_config_parse_profanity() {
+        let config = Config::parse(
+            r#"
+            mask_profanity = "skip"
+
+            [profanity]
+            damn = "darn"
+            "frak*" = ""
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.mask_profanity,
+            Some(crate::text::ProfanityMask::Skip)
+        );
+        assert_eq!(config.profanity["damn"], "darn");
+        assert_eq!(config.profanity.len(), 2);
+        assert!(Config::parse("mask_profanity = \"mute\"").is_err());
+    }
+
+    #[test]
+    fn test_config_parse_locale() {
+        let config = Config::parse("locale = \"de-DE\"").unwrap();
+        assert_eq!(config.locale, Some(crate::text::Locale::DeDe));
+        assert!(Config::parse("locale = \"xx\"").is_err());
+    }
+
+    #[test]
+    fn test_config_load_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, "replace = [\"s/a/b/\"]\n").unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.replace, vec!["s/a/b/".to_string()]);
+    }
+
+    #[test]
+    fn test_config_word_lists_relative_to_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "lexicon_files = [\"names.toml\"]\nprofanity_files = [\"/etc/kids.toml\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(
+            config.lexicon_files,
+            vec![temp_dir.path().join("names.toml")]
+        );
+        assert_eq!(
+            config.profanity_files,
+            vec![PathBuf::from("/etc/kids.toml")]
+        );
+        let kinds: Vec<_> = config.word_lists().iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, vec!["lexicon", "glossary", "profanity"]);
+    }
+
+    #[test]
+    fn test_config_invalid_toml() {
+        let result = Config::parse("replace = [");
+        assert!(matches!(result.unwrap_err(), ConfigError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_config_parse_markup_options() {
+        let config = Config::parse("strip_markup = true\nemoji = \"verbalize\"\n").unwrap();
+
+        assert!(config.strip_markup);
+        assert_eq!(config.emoji, Some(crate::text::EmojiMode::Verbalize));
+    }
+
+    #[test]
+    fn test_config_parse_require_consent() {
+        assert!(!Config::default().require_consent);
+        assert!(
+            Config::parse("require_consent = true")
+                .unwrap()
+                .require_consent
+        );
+    }
+
+    #[test]
+    fn test_config_parse_watermark_key() {
+        let config = Config::parse("watermark_key = \"studio\"").unwrap();
+        assert_eq!(config.watermark_key.as_deref(), Some("studio"));
+    }
+
+    #[test]
+    fn test_config_parse_max_text_length() {
+        let config = Config::parse("[max_text_length]\nvc = 300\n").unwrap();
+        assert_eq!(config.max_text_length(crate::cli::Model::VoxCPM), Some(300));
+        assert_eq!(config.max_text_length(crate::cli::Model::OpenF5), None);
+    }
+
+    #[test]
+    fn test_config_parse_remote() {
+        let config = Config::parse(
+            "[remote]\nkind = \"s3\"\nbucket = \"voices\"\nregion = \"eu-west-1\"\nprefix = \"team/\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.remote,
+            Some(RemoteConfig::S3 {
+                bucket: "voices".to_string(),
+                region: "eu-west-1".to_string(),
+                endpoint: None,
+                prefix: "team/".to_string(),
+                access_key: None,
+                secret_key: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_config_parse_profiles() {
+        let config = Config::parse(
+            r#"
+            default_profile = "home"
+
+            [profile.home]
+            host = "localhost"
+
+            [profile.office]
+            host = "tts.office.lan"
+            token = "secret"
+            voice = "narrator"
+            output_dir = "/srv/audio"
+
+            [profile.office.ports]
+            ov = 19280
+            "#,
+        )
+        .unwrap();
+
+        let office = config.profile(Some("office")).unwrap().unwrap();
+        assert_eq!(office.host.as_deref(), Some("tts.office.lan"));
+        assert_eq!(office.token.as_deref(), Some("secret"));
+        assert_eq!(office.voice.as_deref(), Some("narrator"));
+        assert_eq!(office.port(crate::cli::Model::OpenVoice), Some(19280));
+        assert_eq!(office.port(crate::cli::Model::OpenF5), None);
+
+        let home = config.profile(None).unwrap().unwrap();
+        assert_eq!(home.host.as_deref(), Some("localhost"));
+    }
+
+    #[test]
+    fn test_config_profile_selection() {
+        assert_eq!(Config::default().profile(None).unwrap(), None);
+        assert!(matches!(
+            Config::default().profile(Some("lab")),
+            Err(ConfigError::UnknownProfile(name)) if name == "lab"
+        ));
+    }
+
+    #[test]
+    fn test_profile_output_path() {
+        let profile = Profile {
+            output_dir: Some(PathBuf::from("/srv/audio")),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            profile.output_path(Path::new("out.wav")),
+            PathBuf::from("/srv/audio/out.wav")
+        );
+        assert_eq!(
+            profile.output_path(Path::new("/tmp/out.wav")),
+            PathBuf::from("/tmp/out.wav")
+        );
+        assert_eq!(
+            Profile::default().output_path(Path::new("out.wav")),
+            PathBuf::from("out.wav")
+        );
+    }
+
+    #[test]
+    fn test_config_parse_qa_thresholds() {
+        let config = Config::parse("[qa]\nmin_lufs = -20.0\nmax_silence = 1.5\n").unwrap();
+
+        assert_eq!(config.qa.min_lufs, Some(-20.0));
+        assert_eq!(config.qa.max_silence, 1.5);
+        assert_eq!(
+            config.qa.max_true_peak_db,
+            crate::audio::QaThresholds::default().max_true_peak_db
+        );
+    }
+
+    #[test]
+    fn test_config_parse_tag_templates() {
+        let config = Config::parse("[tags]\nalbum = \"The Book\"\n").unwrap();
+
+        assert_eq!(config.tags.album, "The Book");
+        assert_eq!(config.tags.artist, "{voice}");
+    }
+
+    #[test]
+    fn test_config_presets() {
+        use crate::audio::{Encoding, Preset};
+
+        let config = Config::parse(
+            "[preset.ivr]\nencoding = \"mulaw\"\nsample_rate = 16000\n\n[preset.podcast]\nloudness = -19.0\n",
+        )
+        .unwrap();
+
+        let ivr = config.preset("ivr").unwrap();
+        assert_eq!(ivr.encoding, Encoding::Mulaw);
+        assert_eq!(ivr.sample_rate, Some(16000));
+        assert_eq!(config.preset("podcast").unwrap().loudness, Some(-19.0));
+        assert_eq!(
+            config.preset("telephony").unwrap(),
+            Preset::builtin("telephony").unwrap()
+        );
+        assert_eq!(
+            config.preset("radio").unwrap_err().to_string(),
+            "Unknown preset: radio (available: ivr, podcast, telephony)"
+        );
+    }
+
+    // ===========================================
+    // Setup tests
+    // ===========================================
+
+    #[test]
+    fn test_save_profile_keeps_other_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("config.toml");
+        let profile = Profile {
+            host: Some("gpu-box".to_string()),
+            voice: Some("narrator".to_string()),
+            ..Default::default()
+        };
+
+        save_profile(&path, DEFAULT_PROFILE, &profile).unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.default_profile.as_deref(), Some("default"));
+        assert_eq!(config.profile(None).unwrap(), Some(&profile));
+
+        std::fs::write(
+            &path,
+            "strip_markup = true\ndefault_profile = \"work\"\n\n[profile.work]\nhost = \"10.0.0.5\"\n",
+        )
+        .unwrap();
+        save_profile(&path, "home", &profile).unwrap();
+        let config = Config::load(&path).unwrap();
+        assert!(config.strip_markup);
+        assert_eq!(config.default_profile.as_deref(), Some("work"));
+        assert_eq!(config.profile["work"].host.as_deref(), Some("10.0.0.5"));
+        assert_eq!(config.profile["home"], profile);
+    }
+
+    #[test]
+    fn test_discover_reports_each_backend() {
+        use crate::backend::{
+            BackendEntry, BackendError, BackendRegistry, Capabilities, HealthResponse, MockBackend,
+        };
+
+        let mut registry = BackendRegistry::new();
+        for (name, port) in [("up", 7000), ("down", 7001)] {
+            registry.register(BackendEntry::new(
+                name,
+                name,
+                port,
+                Capabilities::default(),
+                |connection| {
+                    let port = connection.port;
+                    let mut mock = MockBackend::new();
+                    mock.expect_health().returning(move || match port {
+                        7000 | 7100 => Ok(HealthResponse::default()),
+                        _ => Err(BackendError::ConnectionFailed("refused".to_string())),
+                    });
+                    Ok(Box::new(mock))
+                },
+            ));
+        }
+
+        let found = discover(&registry, "localhost", &Profile::default());
+        let up: Vec<_> = found
+            .iter()
+            .map(|d| (d.backend.as_str(), d.is_up()))
+            .collect();
+        assert_eq!(up, [("up", true), ("down", false)]);
+
+        let mut profile = Profile::default();
+        profile.ports.insert("down".to_string(), 7100);
+        let found = discover(&registry, "localhost", &profile);
+        assert_eq!(found[1].port, 7100);
+        assert!(found[1].is_up());
+    }
+}
@@ -0,0 +1,276 @@
+//! Configuration file loading.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::audio::{BUILTIN_PRESETS, Preset, QaThresholds, TagTemplates};
+use crate::cli::Model;
+use crate::text::{EmojiMode, Locale, ProfanityMask};
+
+/// Errors that can occur when loading configuration.
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid config file: {0}")]
+    ParseError(#[from] toml::de::Error),
+
+    #[error("Unknown profile: {0}")]
+    UnknownProfile(String),
+
+    #[error("Unknown preset: {name} (available: {available})")]
+    UnknownPreset { name: String, available: String },
+
+    #[error("Invalid config file: {0}")]
+    Invalid(String),
+
+    #[error("Could not write config: {0}")]
+    WriteError(#[from] toml::ser::Error),
+}
+
+/// User configuration loaded from `~/.open-tts-rs/config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Text substitution rules in sed syntax (`s/pattern/replacement/flags`).
+    pub replace: Vec<String>,
+
+    /// Spoken forms of acronyms and jargon (`K8s = "kubernetes"`), applied
+    /// after the `replace` rules.
+    pub glossary: BTreeMap<String, String>,
+
+    /// Words masked by `mask_profanity`, each with the milder word said
+    /// instead when substituting (`damn = "darn"`; empty for "beep").
+    pub profanity: BTreeMap<String, String>,
+
+    /// Profanity masking: "bleep", "skip", or "substitute"; unset leaves
+    /// the words as written.
+    pub mask_profanity: Option<ProfanityMask>,
+
+    /// TOML files of spoken forms of words the model mispronounces
+    /// (`Tolkien = "tol keen"`), matched as whole words after the
+    /// `replace` rules. Relative paths are read from the config file's
+    /// directory, as are those of the other file lists.
+    pub lexicon_files: Vec<PathBuf>,
+
+    /// TOML files of acronym spoken forms, added to `glossary` as with
+    /// `--glossary`.
+    pub glossary_files: Vec<PathBuf>,
+
+    /// TOML files of words to mask, added to `profanity` as with
+    /// `--profanity`.
+    pub profanity_files: Vec<PathBuf>,
+
+    /// Saved voice for each screenplay character (`AHAB = "gravel"`),
+    /// matched case-insensitively.
+    pub cast: BTreeMap<String, String>,
+
+    /// Locale numbers, currencies, and units are read in, such as `de-DE`.
+    pub locale: Option<Locale>,
+
+    /// Strip markdown syntax, code fences, and URLs from input text.
+    pub strip_markup: bool,
+
+    /// Emoji handling: "keep", "strip", or "verbalize".
+    pub emoji: Option<EmojiMode>,
+
+    /// Refuse to synthesize with voices that have no consent record.
+    pub require_consent: bool,
+
+    /// Regex matching chapter heading lines for `--split-chapters`.
+    pub chapter_pattern: Option<String>,
+
+    /// Key for watermarking generated audio; unset disables watermarking.
+    pub watermark_key: Option<String>,
+
+    /// Days deleted voices stay in the trash before being purged
+    /// [default: 30].
+    pub trash_days: Option<u32>,
+
+    /// Directory intermediate files are written under [default: the
+    /// system temp directory].
+    pub temp_dir: Option<PathBuf>,
+
+    /// Remote voice store used by `voices push-remote` and `voices pull-remote`.
+    pub remote: Option<RemoteConfig>,
+
+    /// Pass/fail limits for `--qa-report`.
+    pub qa: QaThresholds,
+
+    /// Metadata templates for `--tag`.
+    pub tags: TagTemplates,
+
+    /// Output presets (`[preset.ivr]`) selected with `--preset`, adding to
+    /// or replacing the built-in `telephony` and `podcast`.
+    pub preset: BTreeMap<String, Preset>,
+
+    /// Longest text sent in one request, keyed by model flag (`ov`, `of`,
+    /// `vc`), overriding the built-in limits. Longer text is split at
+    /// sentence boundaries and the audio joined.
+    pub max_text_length: BTreeMap<String, usize>,
+
+    /// Profile used when `--profile` is not given.
+    pub default_profile: Option<String>,
+
+    /// Named environments (`[profile.office]`) selected with `--profile`.
+    pub profile: BTreeMap<String, Profile>,
+}
+
+/// Connection settings and defaults for one environment.
+///
+/// Command-line arguments take precedence over every profile field.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Backend host address.
+    pub host: Option<String>,
+
+    /// Backend ports keyed by model flag (`ov`, `of`, `vc`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub ports: BTreeMap<String, u16>,
+
+    /// Bearer token sent with every backend request.
+    pub token: Option<String>,
+
+    /// Voice used when `-n` is not given.
+    pub voice: Option<String>,
+
+    /// Directory that relative output paths are written to.
+    pub output_dir: Option<PathBuf>,
+
+    /// Project usage is recorded under when `--project` is not given.
+    pub project: Option<String>,
+}
+
+impl Profile {
+    /// Backend port for a model, if the profile overrides it.
+    pub fn port(&self, model: Model) -> Option<u16> {
+        self.ports.get(model.as_str()).copied()
+    }
+
+    /// Resolve an output path against the profile's output directory.
+    ///
+    /// Absolute paths are returned unchanged.
+    pub fn output_path(&self, output: &Path) -> PathBuf {
+        match &self.output_dir {
+            Some(dir) if output.is_relative() => dir.join(output),
+            _ => output.to_path_buf(),
+        }
+    }
+}
+
+/// Location and credentials of a remote voice store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RemoteConfig {
+    /// A WebDAV collection, e.g. Nextcloud or Apache mod_dav.
+    Webdav {
+        /// Collection URL; voices are stored directly inside it.
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    /// An S3 or S3-compatible bucket (path-style addressing).
+    S3 {
+        bucket: String,
+        region: String,
+        /// Custom endpoint for MinIO, R2, etc. [default: AWS]
+        endpoint: Option<String>,
+        /// Key prefix, e.g. "voices/".
+        #[serde(default)]
+        prefix: String,
+        /// Falls back to `AWS_ACCESS_KEY_ID`.
+        access_key: Option<String>,
+        /// Falls back to `AWS_SECRET_ACCESS_KEY`.
+        secret_key: Option<String>,
+    },
+}
+
+impl Config {
+    /// Returns the default config file path.
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir()
+            .expect("Could not find home directory")
+            .join(".open-tts-rs")
+            .join("config.toml")
+    }
+
+    /// Load configuration from a file.
+    ///
+    /// A missing file yields the default configuration.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(path)?;
+        let mut config = Self::parse(&contents)?;
+        if let Some(dir) = path.parent() {
+            for file in config.word_list_files_mut() {
+                *file = dir.join(&*file);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The lexicon, glossary, and profanity files, keyed by kind.
+    pub fn word_lists(&self) -> [(&'static str, &[PathBuf]); 3] {
+        [
+            ("lexicon", &self.lexicon_files),
+            ("glossary", &self.glossary_files),
+            ("profanity", &self.profanity_files),
+        ]
+    }
+
+    fn word_list_files_mut(&mut self) -> impl Iterator<Item = &mut PathBuf> {
+        self.lexicon_files
+            .iter_mut()
+            .chain(&mut self.glossary_files)
+            .chain(&mut self.profanity_files)
+    }
+
+    /// Select a profile by name, falling back to `default_profile`.
+    ///
+    /// Returns `None` when neither is set.
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<&Profile>, ConfigError> {
+        let Some(name) = name.or(self.default_profile.as_deref()) else {
+            return Ok(None);
+        };
+
+        self.profile
+            .get(name)
+            .map(Some)
+            .ok_or_else(|| ConfigError::UnknownProfile(name.to_string()))
+    }
+
+    /// Parse configuration from TOML text.
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Look up an output preset, preferring the config's over a built-in.
+    pub fn preset(&self, name: &str) -> Result<Preset, ConfigError> {
+        if let Some(preset) = self.preset.get(name) {
+            return Ok(preset.clone());
+        }
+        Preset::builtin(name).ok_or_else(|| {
+            let mut available: Vec<&str> = BUILTIN_PRESETS.to_vec();
+            available.extend(self.preset.keys().map(String::as_str));
+            available.sort_unstable();
+            available.dedup();
+            ConfigError::UnknownPreset {
+                name: name.to_string(),
+                available: available.join(", "),
+            }
+        })
+    }
+
+    /// Request length limit for a model, if the config overrides it.
+    pub fn max_text_length(&self, model: Model) -> Option<usize> {
+        self.max_text_length.get(model.as_str()).copied()
+    }
+}
@@ -0,0 +1,91 @@
+//! Pieces of the first-run `setup` wizard that need no terminal.
+//!
+//! The CLI asks the questions; this module finds the backends that answer
+//! and writes the chosen profile without disturbing the rest of the file.
+
+use std::path::Path;
+
+use super::settings::{ConfigError, Profile};
+use crate::backend::{BackendError, BackendRegistry, HealthResponse};
+
+/// Profile the wizard writes when the user does not name one.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Text synthesized to check that the chosen backend works.
+pub const SAMPLE_TEXT: &str = "Hello! Your text to speech setup is working.";
+
+/// Script read aloud when recording a first voice, which doubles as its
+/// transcript.
+pub const READ_ALOUD: &str = "The quick brown fox jumps over the lazy dog. \
+    I am recording this sample so my voice can be cloned.";
+
+/// How a registered backend answered a health check.
+#[derive(Debug)]
+pub struct Discovery {
+    /// Registry name, as passed to `-m`.
+    pub backend: String,
+    pub description: String,
+    pub port: u16,
+    pub health: Result<HealthResponse, BackendError>,
+}
+
+impl Discovery {
+    pub fn is_up(&self) -> bool {
+        self.health.is_ok()
+    }
+}
+
+/// Health-check every backend in `registry` on `host`, at the profile's
+/// port for it or its default.
+pub fn discover(registry: &BackendRegistry, host: &str, profile: &Profile) -> Vec<Discovery> {
+    registry
+        .entries()
+        .iter()
+        .map(|entry| {
+            let port = profile
+                .ports
+                .get(&entry.name)
+                .copied()
+                .unwrap_or(entry.default_port);
+            let health = registry
+                .connect(&entry.name, host, Some(port), profile.token.clone(), false)
+                .and_then(|backend| backend.health());
+            Discovery {
+                backend: entry.name.clone(),
+                description: entry.description.clone(),
+                port,
+                health,
+            }
+        })
+        .collect()
+}
+
+/// Save `profile` as `[profile.<name>]` in the config file at `path`,
+/// creating the file if needed.
+///
+/// Other settings and profiles are kept, though comments are not. The
+/// profile becomes `default_profile` unless another one already is.
+pub fn save_profile(path: &Path, name: &str, profile: &Profile) -> Result<(), ConfigError> {
+    let mut table = match std::fs::read_to_string(path) {
+        Ok(contents) => contents.parse::<toml::Table>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let profiles = table
+        .entry("profile")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    let toml::Value::Table(profiles) = profiles else {
+        return Err(ConfigError::Invalid("`profile` is not a table".to_string()));
+    };
+    profiles.insert(name.to_string(), toml::Value::try_from(profile)?);
+    table
+        .entry("default_profile")
+        .or_insert_with(|| toml::Value::String(name.to_string()));
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, toml::to_string_pretty(&table)?)?;
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//! Builder for engines connected to the built-in model servers.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::tts::{TTSEngine, TTSError};
+use crate::audio::Cleanup;
+use crate::backend::{HttpBackend, Model, Progress, RequestIds, SynthesisEvent};
+use crate::voice::VoiceManager;
+
+/// Configures a [`TTSEngine`] without the CLI's wiring.
+///
+/// ```no_run
+/// use open_tts_rs::{Model, TTSEngine};
+///
+/// let engine = TTSEngine::builder()
+///     .model(Model::OpenF5)
+///     .host("gpu-box")
+///     .timeout(std::time::Duration::from_secs(120))
+///     .build()?;
+/// let wav = engine.synthesize("Hello there.", None, 1.0)?;
+/// # Ok::<(), open_tts_rs::TTSError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TTSEngineBuilder {
+    model: Model,
+    host: String,
+    port: Option<u16>,
+    token: Option<String>,
+    timeout: Option<Duration>,
+    strict: bool,
+    voices_dir: Option<PathBuf>,
+    passphrase: Option<String>,
+    language: Option<String>,
+    require_consent: bool,
+    project: Option<String>,
+    unlocked: bool,
+    max_text_length: Option<usize>,
+    seed: Option<u64>,
+    cleanup: Option<Cleanup>,
+    progress: Option<Progress>,
+}
+
+impl Default for TTSEngineBuilder {
+    fn default() -> Self {
+        Self {
+            model: Model::default(),
+            host: "localhost".to_string(),
+            port: None,
+            token: None,
+            timeout: None,
+            strict: false,
+            voices_dir: None,
+            passphrase: None,
+            language: None,
+            require_consent: false,
+            project: None,
+            unlocked: false,
+            max_text_length: None,
+            seed: None,
+            cleanup: None,
+            progress: None,
+        }
+    }
+}
+
+impl TTSEngineBuilder {
+    /// Start from the CLI defaults: OpenVoice on localhost, voices in
+    /// `~/.open-tts-rs/voices`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Server port [default: the model's port].
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Bearer token for servers behind an authenticating proxy.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Request timeout [default: 30 seconds].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail on backend responses with missing or unknown fields
+    /// [default: tolerate them].
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Voice store directory [default: `~/.open-tts-rs/voices`].
+    pub fn voices_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.voices_dir = Some(dir.into());
+        self
+    }
+
+    /// Passphrase unlocking an encrypted voice store.
+    pub fn passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.passphrase = Some(passphrase.into());
+        self
+    }
+
+    /// Language code such as `EN` or `ZH` [default: each voice's language].
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Refuse cloned voices without a consent record.
+    pub fn require_consent(mut self, require: bool) -> Self {
+        self.require_consent = require;
+        self
+    }
+
+    /// Project synthesis is for, checked against each voice's allowed uses.
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Allow locked voices.
+    pub fn unlock(mut self, unlocked: bool) -> Self {
+        self.unlocked = unlocked;
+        self
+    }
+
+    /// Longest text sent in one request [default: the model's limit];
+    /// longer text is split at sentence boundaries.
+    pub fn max_text_length(mut self, max: usize) -> Self {
+        self.max_text_length = Some(max);
+        self
+    }
+
+    /// Random seed sent with every request [default: none, so takes vary].
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Filter reference clips before they are uploaded [default: as recorded].
+    pub fn cleanup(mut self, cleanup: Cleanup) -> Self {
+        self.cleanup = Some(cleanup);
+        self
+    }
+
+    /// Report each step of every synthesis (see [`TTSEngine::with_progress`]).
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(SynthesisEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Progress::new(callback));
+        self
+    }
+
+    /// Create the engine. No request is sent until it is used.
+    ///
+    /// Every synthesis is given a request ID (see [`RequestIds`]).
+    pub fn build(self) -> Result<TTSEngine<RequestIds<HttpBackend>>, TTSError> {
+        let port = self.port.unwrap_or(self.model.port());
+        let mut backend =
+            HttpBackend::with_port(self.model, &self.host, port).with_strict(self.strict);
+        if let Some(token) = &self.token {
+            backend = backend.with_token(token)?;
+        }
+        if let Some(timeout) = self.timeout {
+            backend = backend.with_timeout(timeout)?;
+        }
+
+        let mut voice_manager = match self.voices_dir {
+            Some(dir) => VoiceManager::with_dir(dir),
+            None => VoiceManager::new(),
+        };
+        if let Some(passphrase) = &self.passphrase {
+            voice_manager = voice_manager.unlock(passphrase)?;
+        }
+
+        let mut engine = TTSEngine::new(RequestIds::new(backend), voice_manager)
+            .with_language(self.language)
+            .with_require_consent(self.require_consent)
+            .with_project(self.project)
+            .with_unlocked(self.unlocked)
+            .with_max_text_length(self.max_text_length)
+            .with_seed(self.seed)
+            .with_cleanup(self.cleanup)
+            .with_model_license(Some(self.model.license().to_string()));
+        if let Some(progress) = self.progress {
+            engine = engine.with_progress(move |event| progress.emit(event));
+        }
+        Ok(engine)
+    }
+}
+
+impl TTSEngine<RequestIds<HttpBackend>> {
+    /// Configure an engine for one of the built-in model servers.
+    pub fn builder() -> TTSEngineBuilder {
+        TTSEngineBuilder::new()
+    }
+}
@@ -0,0 +1,168 @@
+//! Sending one synthesis request: waiting out a pause, backing off under
+//! memory pressure, and splitting text longer than the backend takes.
+
+use super::planner::{Intent, check_speed, plan};
+use super::tts::{TTSEngine, TTSError};
+use crate::audio::{AudioBuffer, Segment, assemble};
+use crate::backend::{Backend, SynthesisEvent};
+use crate::text::split_to_length;
+use crate::voice::VoiceMetadata;
+
+impl<B: Backend> TTSEngine<B> {
+    pub(super) fn synthesize_request(
+        &self,
+        text: &str,
+        voice_name: Option<String>,
+        speed: f32,
+        request_id: Option<String>,
+    ) -> Result<Vec<u8>, TTSError> {
+        if let Some(pause) = &self.pause {
+            pause.wait(|| {
+                if let Some(progress) = &self.progress {
+                    progress.emit(SynthesisEvent::Paused);
+                }
+            });
+        }
+        let Some(pressure) = &self.pressure else {
+            return self.synthesize_limited(text, voice_name, speed, request_id, None);
+        };
+        loop {
+            let permit = pressure.acquire();
+            let limit = permit.text_limit(text);
+            match self.synthesize_limited(
+                text,
+                voice_name.clone(),
+                speed,
+                request_id.clone(),
+                limit,
+            ) {
+                Ok(wav) => {
+                    permit.succeeded();
+                    return Ok(wav);
+                }
+                Err(TTSError::BackendError(e)) if e.is_overload() => {
+                    let Some(wait) = permit.overloaded() else {
+                        return Err(e.into());
+                    };
+                    let wait = e.retry_after().map_or(wait, |w| w.max(wait));
+                    if let Some(progress) = &self.progress {
+                        progress.emit(SynthesisEvent::BackingOff {
+                            limit: pressure.limit(),
+                            splits: pressure.splits(),
+                            wait,
+                        });
+                    }
+                    std::thread::sleep(wait);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Synthesize in one request, or in pieces when `text` is longer than
+    /// the backend takes or than `limit`.
+    fn synthesize_limited(
+        &self,
+        text: &str,
+        voice_name: Option<String>,
+        speed: f32,
+        request_id: Option<String>,
+        limit: Option<usize>,
+    ) -> Result<Vec<u8>, TTSError> {
+        let capabilities = self.capabilities();
+        check_speed(&capabilities, speed)?;
+        let max = match (capabilities.max_text_length, limit) {
+            (Some(max), Some(limit)) => Some(max.min(limit)),
+            (max, limit) => max.or(limit),
+        };
+        if let Some(max) = max
+            && text.chars().count() > max
+        {
+            return self.synthesize_split(text, voice_name, speed, request_id, max);
+        }
+
+        let (metadata, language) = self.load_voice(voice_name.as_deref())?;
+        // Decrypted copies are removed when `reference` is dropped
+        let reference = match metadata.as_ref().and_then(|m| m.audio_path.as_deref()) {
+            Some(path) if !capabilities.persistent_voices && path.exists() => {
+                Some(self.voice_manager.reference_audio(path)?)
+            }
+            _ => None,
+        };
+        let intent = Intent {
+            text,
+            voice: voice_name.as_deref(),
+            reference: reference
+                .as_ref()
+                .zip(metadata.as_ref())
+                .map(|(audio, meta)| (audio.path(), meta.transcript.as_str())),
+            speed,
+            language: language.as_deref(),
+            seed: self.seed,
+        };
+
+        let mut request = plan(&intent, &capabilities)?.request;
+        request.progress = self.progress.clone();
+        request.request_id = request_id;
+        Ok(self.backend.synthesize(&request)?)
+    }
+
+    /// The metadata of the voice `name`, once it passes the consent and
+    /// access checks, and the language to speak it in: the engine's, or
+    /// else the one the voice was recorded in.
+    fn load_voice(
+        &self,
+        name: Option<&str>,
+    ) -> Result<(Option<VoiceMetadata>, Option<String>), TTSError> {
+        let language = self.language.clone();
+        let Some(name) = name else {
+            return Ok((None, language));
+        };
+        let meta = self
+            .voice_manager
+            .load_metadata(name)
+            .map_err(|_| TTSError::VoiceNotFound(name.to_string()))?;
+        if self.require_consent && meta.consent.is_none() && !meta.synthetic {
+            return Err(TTSError::ConsentRequired(meta.name.clone()));
+        }
+        self.check_access(&meta)?;
+        let language = match (language, &meta.language) {
+            (Some(requested), Some(recorded)) if !requested.eq_ignore_ascii_case(recorded) => {
+                return Err(TTSError::LanguageMismatch {
+                    voice: meta.name.clone(),
+                    recorded: recorded.clone(),
+                    requested,
+                });
+            }
+            (None, recorded) => recorded.clone(),
+            (requested, _) => requested,
+        };
+        Ok((Some(meta), language))
+    }
+
+    /// Synthesize text too long for one request piece by piece, split at
+    /// sentence boundaries, and join the audio.
+    fn synthesize_split(
+        &self,
+        text: &str,
+        voice_name: Option<String>,
+        speed: f32,
+        request_id: Option<String>,
+        max: usize,
+    ) -> Result<Vec<u8>, TTSError> {
+        let clips = split_to_length(text, max)
+            .iter()
+            .map(|piece| {
+                let wav = self.synthesize_limited(
+                    piece,
+                    voice_name.clone(),
+                    speed,
+                    request_id.clone(),
+                    None,
+                )?;
+                Ok(Segment::Clip(AudioBuffer::from_wav_bytes(&wav)?))
+            })
+            .collect::<Result<Vec<_>, TTSError>>()?;
+        Ok(assemble(clips)?.to_wav_bytes()?)
+    }
+}
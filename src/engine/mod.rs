@@ -3,15 +3,34 @@
 //! This module provides the main engine that coordinates between
 //! the CLI, VoiceManager, and Backend to perform TTS operations.
 
+mod builder;
+mod limit;
+mod pause;
+pub mod planner;
+mod pressure;
+mod stream;
 mod tts;
+mod voices;
 
+pub use builder::TTSEngineBuilder;
+pub use pause::Pause;
+pub use pressure::{MAX_SPLITS, Pressure};
 pub use tts::{TTSEngine, TTSError};
 
 #[cfg(test)]
 mod tests {
+    use super::planner::{Dropped, Intent};
     use super::*;
-    use crate::backend::{BackendError, HealthResponse, MockBackend, VoiceInfo, VoicesResponse};
-    use crate::voice::{VoiceManager, VoiceMetadata};
+    use crate::audio::{AudioBuffer, Cleanup};
+    use crate::backend::{
+        BackendError, Capabilities, EmbeddingResponse, HealthResponse, MockBackend, SpeedRange,
+        SynthesisEvent, VoiceInfo, VoicesResponse, mock_backend,
+    };
+    use crate::cli::Model;
+    use crate::text::Chunk;
+    use crate::voice::{Consent, EmbeddingSource, VoiceError, VoiceManager, VoiceMetadata};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
     use tempfile::TempDir;
 
     // ===========================================
@@ -22,15 +41,17 @@ mod tests {
     fn test_engine_health_check_success() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mut mock_backend = MockBackend::new();
+        let mut mock_backend = mock_backend();
 
         mock_backend.expect_health().times(1).returning(|| {
             Ok(HealthResponse {
                 status: "healthy".to_string(),
                 model: "openvoice_v2".to_string(),
-                cuda_available: true,
+                model_version: None,
+                cuda_available: Some(true),
                 gpu: Some("NVIDIA RTX 5060".to_string()),
                 device: "cuda:0".to_string(),
+                raw: Default::default(),
             })
         });
 
@@ -46,7 +67,7 @@ mod tests {
     fn test_engine_health_check_failure() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mut mock_backend = MockBackend::new();
+        let mut mock_backend = mock_backend();
 
         mock_backend.expect_health().times(1).returning(|| {
             Err(BackendError::ConnectionFailed(
@@ -64,7 +85,7 @@ mod tests {
     fn test_engine_extract_voice_and_save() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mut mock_backend = MockBackend::new();
+        let mut mock_backend = mock_backend();
 
         // Create a test audio file
         let audio_path = temp_dir.path().join("test.wav");
@@ -79,6 +100,7 @@ mod tests {
                     transcript: "Hello world".to_string(),
                     model: "openvoice_v2".to_string(),
                     duration: Some(3.5),
+                    raw: Default::default(),
                 })
             });
 
@@ -95,11 +117,54 @@ mod tests {
         assert_eq!(metadata.transcript, "Hello world");
     }
 
+    #[test]
+    fn test_engine_extract_voice_cleans_reference() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        let mut mock_backend = mock_backend();
+
+        // A clip sitting on a DC offset
+        let audio_path = temp_dir.path().join("ref.wav");
+        let offset = AudioBuffer::new(vec![0.25; 16000], 16000, 1);
+        std::fs::write(&audio_path, offset.to_wav_bytes().unwrap()).unwrap();
+
+        mock_backend
+            .expect_extract_voice()
+            .withf(|path, _, _| {
+                let uploaded = AudioBuffer::from_wav_bytes(&std::fs::read(path).unwrap()).unwrap();
+                uploaded.samples.iter().all(|s| s.abs() < 0.01)
+            })
+            .times(1)
+            .returning(|_, transcript, name| {
+                Ok(VoiceInfo {
+                    name: name.unwrap(),
+                    transcript: transcript.to_string(),
+                    model: "openvoice_v2".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+
+        let engine =
+            TTSEngine::new(mock_backend, voice_manager).with_cleanup(Some(Cleanup::default()));
+        engine
+            .extract_voice(&audio_path, "Hello", Some("amy".to_string()))
+            .unwrap();
+
+        // The cleaned clip is kept, and the temporary copy is gone
+        let metadata = engine.voice_manager().load_metadata("amy").unwrap();
+        let stored = metadata.audio_path.unwrap();
+        assert!(stored.starts_with(temp_dir.path().join("voices")));
+        let kept = AudioBuffer::from_wav_bytes(&std::fs::read(&stored).unwrap()).unwrap();
+        assert!(kept.samples.iter().all(|s| s.abs() < 0.01));
+        assert!(!crate::scratch::scratch_file("ref-clean.wav").exists());
+    }
+
     #[test]
     fn test_engine_synthesize_with_voice() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mut mock_backend = MockBackend::new();
+        let mut mock_backend = mock_backend();
 
         // Save voice metadata first
         let metadata = VoiceMetadata {
@@ -108,6 +173,12 @@ mod tests {
             model: "openvoice_v2".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
         voice_manager.save_metadata(&metadata).unwrap();
 
@@ -128,7 +199,7 @@ mod tests {
     fn test_engine_synthesize_voice_not_found() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mock_backend = MockBackend::new();
+        let mock_backend = mock_backend();
 
         let engine = TTSEngine::new(mock_backend, voice_manager);
         let result = engine.synthesize("Generate this text", Some("nonexistent".to_string()), 1.0);
@@ -141,7 +212,7 @@ mod tests {
     fn test_engine_list_voices() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mut mock_backend = MockBackend::new();
+        let mut mock_backend = mock_backend();
 
         // Save local voice
         let metadata = VoiceMetadata {
@@ -150,6 +221,12 @@ mod tests {
             model: "openvoice_v2".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
         voice_manager.save_metadata(&metadata).unwrap();
 
@@ -160,7 +237,9 @@ mod tests {
                     transcript: "Backend".to_string(),
                     model: "openvoice_v2".to_string(),
                     duration: Some(2.0),
+                    raw: Default::default(),
                 }],
+                raw: Default::default(),
             })
         });
 
@@ -177,7 +256,7 @@ mod tests {
     fn test_engine_delete_voice() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mut mock_backend = MockBackend::new();
+        let mut mock_backend = mock_backend();
 
         // Save local voice
         let metadata = VoiceMetadata {
@@ -186,6 +265,12 @@ mod tests {
             model: "openvoice_v2".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
         voice_manager.save_metadata(&metadata).unwrap();
 
@@ -208,7 +293,7 @@ mod tests {
     fn test_engine_synthesize_default_voice() {
         let temp_dir = TempDir::new().unwrap();
         let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
-        let mut mock_backend = MockBackend::new();
+        let mut mock_backend = mock_backend();
 
         mock_backend
             .expect_synthesize()
@@ -221,4 +306,1492 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    // ===========================================
+    // Chunked synthesis tests
+    // ===========================================
+
+    fn speech(text: &str, voice: Option<&str>) -> Chunk {
+        Chunk::Speech {
+            text: text.to_string(),
+            voice: voice.map(str::to_string),
+            speed: 1.0,
+        }
+    }
+
+    fn tone_wav(samples: usize) -> Vec<u8> {
+        AudioBuffer::new(vec![0.5; samples], 1000, 1)
+            .to_wav_bytes()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_engine_synthesize_single_chunk_passthrough() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_synthesize()
+            .times(1)
+            .returning(|_| Ok(b"RIFF raw backend bytes".to_vec()));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let audio = engine.synthesize_chunks(&[speech("Hi", None)]).unwrap();
+
+        assert_eq!(audio, b"RIFF raw backend bytes");
+    }
+
+    #[test]
+    fn test_engine_synthesize_chunks_joins_with_pauses() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        for name in ["alice", "bob"] {
+            voice_manager
+                .save_metadata(&VoiceMetadata {
+                    name: name.to_string(),
+                    transcript: "Reference".to_string(),
+                    model: "openvoice_v2".to_string(),
+                    created_at: "2024-01-01T00:00:00Z".to_string(),
+                    audio_path: None,
+                    language: None,
+                    consent: None,
+                    locked: false,
+                    allowed_uses: Vec::new(),
+                    synthetic: false,
+                    license: None,
+                })
+                .unwrap();
+        }
+
+        let mut mock_backend = mock_backend();
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.voice_name.as_deref() == Some("alice"))
+            .times(1)
+            .returning(|_| Ok(tone_wav(100)));
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.voice_name.as_deref() == Some("bob"))
+            .times(1)
+            .returning(|_| Ok(tone_wav(200)));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let chunks = vec![
+            Chunk::Pause(Duration::from_millis(50)),
+            speech("One", Some("alice")),
+            Chunk::Pause(Duration::from_millis(250)),
+            speech("Two", Some("bob")),
+        ];
+        let audio = engine.synthesize_chunks(&chunks).unwrap();
+
+        let buffer = AudioBuffer::from_wav_bytes(&audio).unwrap();
+        assert_eq!(buffer.samples.len(), 50 + 100 + 250 + 200);
+        assert_eq!(buffer.samples[0], 0.0);
+        assert!(buffer.samples[60] > 0.4);
+        assert_eq!(buffer.samples[200], 0.0);
+    }
+
+    #[test]
+    fn test_engine_synthesize_tracks_per_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        voice_manager
+            .save_metadata(&VoiceMetadata {
+                name: "alice".to_string(),
+                transcript: "Reference".to_string(),
+                model: "openvoice_v2".to_string(),
+                created_at: "2024-01-01T00:00:00Z".to_string(),
+                audio_path: None,
+                language: None,
+                consent: None,
+                locked: false,
+                allowed_uses: Vec::new(),
+                synthetic: false,
+                license: None,
+            })
+            .unwrap();
+
+        let mut mock_backend = mock_backend();
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.voice_name.as_deref() == Some("alice"))
+            .times(1)
+            .returning(|_| Ok(tone_wav(100)));
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.voice_name.is_none())
+            .times(1)
+            .returning(|_| Ok(tone_wav(200)));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let chunks = vec![
+            speech("One", Some("alice")),
+            Chunk::Pause(Duration::from_millis(50)),
+            speech("Two", None),
+        ];
+        let (mixdown, tracks) = engine.synthesize_tracks(&chunks).unwrap();
+
+        assert_eq!(mixdown.samples.len(), 100 + 50 + 200);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].0, "alice");
+        assert_eq!(tracks[1].0, "default");
+        for (_, track) in &tracks {
+            assert_eq!(track.samples.len(), mixdown.samples.len());
+        }
+        assert!(tracks[0].1.samples[50] > 0.4);
+        assert_eq!(tracks[0].1.samples[200], 0.0);
+        assert_eq!(tracks[1].1.samples[50], 0.0);
+        assert!(tracks[1].1.samples[200] > 0.4);
+    }
+
+    #[test]
+    fn test_engine_synthesize_streaming_emits_in_first_clip_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock_backend = mock_backend();
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.text == "One")
+            .returning(|_| Ok(tone_wav(100)));
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Two")
+            .returning(|_| {
+                Ok(AudioBuffer::new(vec![0.5; 400], 2000, 2)
+                    .to_wav_bytes()
+                    .unwrap())
+            });
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let chunks = vec![
+            Chunk::Pause(Duration::from_millis(50)),
+            speech("One", None),
+            Chunk::Pause(Duration::from_millis(20)),
+            speech("Two", None),
+        ];
+        let mut pieces = Vec::new();
+        engine
+            .synthesize_streaming(&chunks, |audio| {
+                pieces.push(audio);
+                true
+            })
+            .unwrap();
+
+        let lengths: Vec<usize> = pieces.iter().map(|p| p.samples.len()).collect();
+        assert_eq!(lengths, vec![50, 100, 20, 100]);
+        assert!(
+            pieces
+                .iter()
+                .all(|p| p.sample_rate == 1000 && p.channels == 1)
+        );
+
+        let mut emitted = 0;
+        engine
+            .synthesize_streaming(&chunks, |_| {
+                emitted += 1;
+                false
+            })
+            .unwrap();
+        assert_eq!(emitted, 1);
+    }
+
+    #[test]
+    fn test_engine_synthesize_streaming_ahead_keeps_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock_backend = mock_backend();
+        // The first sentence finishes last
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.text == "One")
+            .returning(|_| {
+                std::thread::sleep(Duration::from_millis(100));
+                Ok(tone_wav(100))
+            });
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Two")
+            .returning(|_| Ok(tone_wav(200)));
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Three")
+            .returning(|_| Err(BackendError::BackendError("500".to_string())));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let chunks = vec![
+            speech("One", None),
+            Chunk::Pause(Duration::from_millis(20)),
+            speech("Two", None),
+        ];
+        let mut lengths = Vec::new();
+        engine
+            .synthesize_streaming_ahead(&chunks, 3, |audio| {
+                lengths.push(audio.samples.len());
+                true
+            })
+            .unwrap();
+        assert_eq!(lengths, vec![100, 20, 200]);
+
+        let chunks = vec![speech("Two", None), speech("Three", None)];
+        let mut emitted = 0;
+        let err = engine
+            .synthesize_streaming_ahead(&chunks, 2, |_| {
+                emitted += 1;
+                true
+            })
+            .unwrap_err();
+        assert!(matches!(err, TTSError::BackendError(_)));
+        assert_eq!(emitted, 1);
+    }
+
+    #[test]
+    fn test_builder_defaults_to_model_port() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = TTSEngine::builder()
+            .model(Model::OpenF5)
+            .voices_dir(temp_dir.path())
+            .build()
+            .unwrap();
+
+        assert_eq!(engine.backend().inner().base_url(), "http://localhost:9288");
+        assert_eq!(engine.voice_manager().voices_dir(), temp_dir.path());
+    }
+
+    #[test]
+    fn test_builder_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = TTSEngineBuilder::new()
+            .model(Model::VoxCPM)
+            .host("gpu-box")
+            .port(18700)
+            .token("secret")
+            .timeout(Duration::from_secs(300))
+            .voices_dir(temp_dir.path())
+            .build()
+            .unwrap();
+
+        assert_eq!(engine.backend().inner().base_url(), "http://gpu-box:18700");
+        assert!(!engine.capabilities().speed);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_token() {
+        let result = TTSEngine::builder().token("bad\ntoken").build();
+        assert!(matches!(result.err().unwrap(), TTSError::BackendError(_)));
+    }
+
+    #[test]
+    fn test_engines_with_different_backends_share_a_type() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = MockBackend::new();
+        mock.expect_capabilities()
+            .return_const(Capabilities::default());
+        mock.expect_synthesize()
+            .times(1)
+            .returning(|_| Ok(b"RIFF wav audio data".to_vec()));
+
+        let engines: Vec<TTSEngine> = vec![
+            TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf())).boxed(),
+            TTSEngine::builder()
+                .model(Model::OpenF5)
+                .voices_dir(temp_dir.path())
+                .build()
+                .unwrap()
+                .boxed(),
+        ];
+
+        assert!(!engines[0].capabilities().speed);
+        assert!(engines[1].capabilities().speed);
+        assert!(engines[0].synthesize("Hello", None, 1.0).is_ok());
+        assert!(matches!(
+            engines[0].synthesize("Hello", None, 1.5),
+            Err(TTSError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_engine_passes_phonemes_only_to_backends_that_accept_them() {
+        let temp_dir = TempDir::new().unwrap();
+        for (phonemes, expected) in [
+            (
+                true,
+                r#"The <phoneme alphabet="ipa" ph="ˈkwɒləti">KWOL-uh-tee</phoneme>."#,
+            ),
+            (false, "The KWOL-uh-tee."),
+        ] {
+            let mut mock = MockBackend::new();
+            mock.expect_capabilities().return_const(Capabilities {
+                phonemes,
+                ..Capabilities::default()
+            });
+            mock.expect_synthesize()
+                .times(1)
+                .withf(move |request| request.text == expected)
+                .returning(|_| Ok(b"RIFF wav audio data".to_vec()));
+
+            let engine =
+                TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()));
+            assert!(
+                engine
+                    .synthesize("The [[ph: ˈkwɒləti]].", None, 1.0)
+                    .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_engine_sends_seed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = MockBackend::new();
+        mock.expect_capabilities().return_const(Capabilities {
+            seed: true,
+            ..Capabilities::default()
+        });
+        mock.expect_synthesize()
+            .times(1)
+            .withf(|request| request.seed == Some(7))
+            .returning(|_| Ok(b"RIFF wav audio data".to_vec()));
+
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_seed(Some(7));
+        assert!(engine.synthesize("Hello", None, 1.0).is_ok());
+    }
+
+    fn limited_backend() -> MockBackend {
+        let mut mock = MockBackend::new();
+        mock.expect_capabilities().return_const(Capabilities {
+            max_text_length: Some(10),
+            ..Capabilities::default()
+        });
+        mock.expect_synthesize().never();
+        mock
+    }
+
+    #[test]
+    fn test_engine_reports_finished_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = mock_backend();
+        mock.expect_synthesize()
+            .times(2)
+            .withf(|request| request.progress.is_some())
+            .returning(|_| {
+                Ok(AudioBuffer::new(vec![0.25; 100], 1000, 1)
+                    .to_wav_bytes()
+                    .unwrap())
+            });
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_progress(move |event| seen.lock().unwrap().push(event));
+
+        let chunks = vec![
+            Chunk::Speech {
+                text: "One.".to_string(),
+                voice: None,
+                speed: 1.0,
+            },
+            Chunk::Pause(Duration::from_millis(100)),
+            Chunk::Speech {
+                text: "Two.".to_string(),
+                voice: None,
+                speed: 1.0,
+            },
+        ];
+        engine.synthesize_chunks(&chunks).unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                SynthesisEvent::ChunkDone { i: 1, of: 2 },
+                SynthesisEvent::ChunkDone { i: 2, of: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_engine_rejects_unsupported_speed() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let engine = TTSEngine::new(limited_backend(), voice_manager);
+
+        let err = engine.synthesize("Hello", None, 1.5).unwrap_err();
+        assert!(matches!(err, TTSError::Unsupported(_)));
+        assert!(err.to_string().contains("speed 1.5x"));
+    }
+
+    #[test]
+    fn test_engine_checks_every_chunk_before_synthesizing() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let engine = TTSEngine::new(limited_backend(), voice_manager);
+
+        let mut chunks = vec![speech("Short.", None), speech("Too fast.", None)];
+        if let Chunk::Speech { speed, .. } = &mut chunks[1] {
+            *speed = 1.2;
+        }
+        let err = engine.synthesize_chunks(&chunks).unwrap_err();
+        assert!(err.to_string().contains("speed 1.2x"));
+    }
+
+    #[test]
+    fn test_engine_rejects_speed_outside_backend_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = MockBackend::new();
+        mock.expect_capabilities().return_const(Capabilities {
+            speed: true,
+            speed_range: SpeedRange { min: 0.8, max: 1.5 },
+            ..Capabilities::default()
+        });
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()));
+
+        assert!(engine.check_speed(0.8).is_ok());
+        assert!(engine.check_speed(1.5).is_ok());
+        let err = engine.check_speed(0.5).unwrap_err();
+        assert!(matches!(err, TTSError::Unsupported(_)));
+        assert!(err.to_string().contains("accepts 0.8x to 1.5x"));
+        assert!(engine.check_speed(2.0).is_err());
+    }
+
+    #[test]
+    fn test_engine_splits_text_over_the_length_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = MockBackend::new();
+        mock.expect_capabilities().return_const(Capabilities {
+            max_text_length: Some(1000),
+            ..Capabilities::default()
+        });
+        let texts = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = texts.clone();
+        mock.expect_synthesize().returning(move |request| {
+            seen.lock().unwrap().push(request.text.clone());
+            Ok(tone_wav(100))
+        });
+
+        // The engine's limit overrides the backend's
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_max_text_length(Some(12));
+        assert_eq!(engine.capabilities().max_text_length, Some(12));
+
+        let wav = engine
+            .synthesize("Short one. Another one.", None, 1.0)
+            .unwrap();
+        assert_eq!(*texts.lock().unwrap(), vec!["Short one.", "Another one."]);
+        assert_eq!(
+            AudioBuffer::from_wav_bytes(&wav).unwrap().samples.len(),
+            200
+        );
+
+        texts.lock().unwrap().clear();
+        engine.synthesize("Fits fine.", None, 1.0).unwrap();
+        assert_eq!(*texts.lock().unwrap(), vec!["Fits fine."]);
+    }
+
+    #[test]
+    fn test_engine_backs_off_when_out_of_memory() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = mock_backend();
+        let texts = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = texts.clone();
+        mock.expect_synthesize().returning(move |request| {
+            let mut texts = seen.lock().unwrap();
+            texts.push(request.text.clone());
+            match texts.len() {
+                1 => Err(BackendError::RequestFailed(
+                    "Status: 500 Internal Server Error: CUDA out of memory".to_string(),
+                )),
+                _ => Ok(tone_wav(100)),
+            }
+        });
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let emitted = events.clone();
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_pressure(Some(Pressure::new().with_delay(Duration::from_millis(1))))
+            .with_progress(move |event| emitted.lock().unwrap().push(event));
+
+        let text = "The first sentence is fairly long here. The second one is about as long.";
+        let wav = engine.synthesize(text, None, 1.0).unwrap();
+        assert_eq!(
+            *texts.lock().unwrap(),
+            vec![
+                text,
+                "The first sentence is fairly long here.",
+                "The second one is about as long."
+            ]
+        );
+        assert_eq!(
+            AudioBuffer::from_wav_bytes(&wav).unwrap().samples.len(),
+            200
+        );
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![SynthesisEvent::BackingOff {
+                limit: Some(1),
+                splits: 1,
+                wait: Duration::from_millis(1),
+            }]
+        );
+
+        // Full-length requests come back first, then concurrency
+        for _ in 0..3 {
+            engine.synthesize("Hi.", None, 1.0).unwrap();
+        }
+        let pressure = engine.pressure().unwrap();
+        assert_eq!((pressure.splits(), pressure.limit()), (0, Some(1)));
+        for _ in 0..4 {
+            engine.synthesize("Hi.", None, 1.0).unwrap();
+        }
+        assert_eq!((pressure.splits(), pressure.limit()), (0, None));
+    }
+
+    #[test]
+    fn test_engine_gives_up_backing_off() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = mock_backend();
+        mock.expect_synthesize()
+            .times(MAX_SPLITS as usize + 1)
+            .returning(|_| {
+                Err(BackendError::RequestFailed(
+                    "CUDA out of memory".to_string(),
+                ))
+            });
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_pressure(Some(Pressure::new().with_delay(Duration::from_millis(1))));
+
+        let err = engine.synthesize("Hi.", None, 1.0).unwrap_err();
+        assert!(err.to_string().contains("CUDA out of memory"));
+
+        // Other failures are not backed off from
+        let mut mock = mock_backend();
+        mock.expect_synthesize().times(1).returning(|_| {
+            Err(BackendError::ConnectionFailed(
+                "Connection refused".to_string(),
+            ))
+        });
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_pressure(Some(Pressure::new()));
+        assert!(engine.synthesize("Hi.", None, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_engine_waits_while_paused() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex};
+
+        let temp_dir = TempDir::new().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let sent = requests.clone();
+        let mut mock = mock_backend();
+        mock.expect_synthesize().returning(move |_| {
+            sent.fetch_add(1, Ordering::SeqCst);
+            Ok(tone_wav(100))
+        });
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let emitted = events.clone();
+        let pause = Pause::new();
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_pause(Some(pause.clone()))
+            .with_progress(move |event| emitted.lock().unwrap().push(event));
+
+        engine.synthesize("Before.", None, 1.0).unwrap();
+        assert!(pause.pause());
+        assert!(!pause.pause());
+        std::thread::scope(|s| {
+            let held = s.spawn(|| engine.synthesize("After.", None, 1.0));
+            std::thread::sleep(Duration::from_millis(50));
+            assert_eq!(requests.load(Ordering::SeqCst), 1);
+            assert!(pause.resume());
+            held.join().unwrap().unwrap();
+        });
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(!pause.is_paused() && !pause.resume());
+        assert_eq!(*events.lock().unwrap(), vec![SynthesisEvent::Paused]);
+    }
+
+    #[test]
+    fn test_engine_paused_callback_may_resume() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut mock = mock_backend();
+        mock.expect_synthesize().returning(|_| Ok(tone_wav(100)));
+        let pause = Pause::new();
+        let resumer = pause.clone();
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+            .with_pause(Some(pause.clone()))
+            .with_progress(move |event| {
+                if event == SynthesisEvent::Paused {
+                    assert!(resumer.is_paused());
+                    assert!(resumer.resume());
+                }
+            });
+
+        assert!(pause.pause());
+        engine.synthesize("Hi.", None, 1.0).unwrap();
+        assert!(!pause.is_paused());
+    }
+
+    #[test]
+    fn test_engine_synthesize_to_sink() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock_backend = mock_backend();
+        mock_backend
+            .expect_synthesize()
+            .returning(|_| Ok(tone_wav(100)));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let mut sink = crate::audio::MemorySink::default();
+        engine
+            .synthesize_to(&[speech("One", None)], &mut sink)
+            .unwrap();
+
+        assert_eq!(sink.wav, tone_wav(100));
+    }
+
+    #[test]
+    fn test_engine_synthesize_chunks_only_pauses() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let engine = TTSEngine::new(mock_backend(), voice_manager);
+
+        let result = engine.synthesize_chunks(&[Chunk::Pause(Duration::from_secs(1))]);
+        assert!(matches!(result.unwrap_err(), TTSError::EmptyText));
+    }
+
+    #[test]
+    fn test_engine_bleeps_in_the_clip_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock_backend = mock_backend();
+        mock_backend.expect_synthesize().times(1).returning(|_| {
+            Ok(AudioBuffer::new(vec![0.0; 80], 8000, 2)
+                .to_wav_bytes()
+                .unwrap())
+        });
+
+        // A bleep before the first clip waits for its format
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let chunks = vec![Chunk::Bleep(Duration::from_millis(100)), speech("it", None)];
+        let buffer =
+            AudioBuffer::from_wav_bytes(&engine.synthesize_chunks(&chunks).unwrap()).unwrap();
+
+        assert_eq!((buffer.sample_rate, buffer.channels), (8000, 2));
+        assert_eq!(buffer.samples.len(), 800 * 2 + 80);
+        assert!(buffer.samples[..1600].iter().any(|s| s.abs() > 0.2));
+        assert!(buffer.samples[1600..].iter().all(|&s| s == 0.0));
+    }
+
+    // ===========================================
+    // Language tests
+    // ===========================================
+
+    fn save_voice(voice_manager: &VoiceManager, name: &str, language: Option<&str>) {
+        let metadata = VoiceMetadata {
+            name: name.to_string(),
+            transcript: "Reference".to_string(),
+            model: "openvoice_v2".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            audio_path: None,
+            language: language.map(str::to_string),
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
+        };
+        voice_manager.save_metadata(&metadata).unwrap();
+    }
+
+    #[test]
+    fn test_engine_synthesize_forwards_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.language.as_deref() == Some("JP"))
+            .times(1)
+            .returning(|_| Ok(b"RIFF".to_vec()));
+
+        let engine =
+            TTSEngine::new(mock_backend, voice_manager).with_language(Some("jp".to_string()));
+        assert!(engine.synthesize("Konnichiwa", None, 1.0).is_ok());
+    }
+
+    #[test]
+    fn test_engine_synthesize_uses_voice_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        save_voice(&voice_manager, "mei", Some("ZH"));
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.language.as_deref() == Some("ZH"))
+            .times(1)
+            .returning(|_| Ok(b"RIFF".to_vec()));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        assert!(
+            engine
+                .synthesize("Ni hao", Some("mei".to_string()), 1.0)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_engine_synthesize_language_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        save_voice(&voice_manager, "mei", Some("ZH"));
+
+        let engine =
+            TTSEngine::new(mock_backend(), voice_manager).with_language(Some("KR".to_string()));
+        let result = engine.synthesize("Hello", Some("mei".to_string()), 1.0);
+
+        assert!(matches!(
+            result.unwrap_err(),
+            TTSError::LanguageMismatch { recorded, requested, .. } if recorded == "ZH" && requested == "KR"
+        ));
+    }
+
+    #[test]
+    fn test_engine_extract_voice_records_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let voices_dir = temp_dir.path().join("voices");
+        let voice_manager = VoiceManager::with_dir(voices_dir.clone());
+        let audio_path = temp_dir.path().join("ref.wav");
+        std::fs::write(&audio_path, b"RIFF").unwrap();
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_extract_voice()
+            .returning(|_, transcript, name| {
+                Ok(VoiceInfo {
+                    name: name.unwrap(),
+                    transcript: transcript.to_string(),
+                    model: "openvoice_v2".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+
+        let engine =
+            TTSEngine::new(mock_backend, voice_manager).with_language(Some("zh".to_string()));
+        engine
+            .extract_voice(&audio_path, "Ni hao", Some("mei".to_string()))
+            .unwrap();
+
+        let metadata = VoiceManager::with_dir(voices_dir)
+            .load_metadata("mei")
+            .unwrap();
+        assert_eq!(metadata.language.as_deref(), Some("ZH"));
+    }
+
+    // ===========================================
+    // Clone scoring tests
+    // ===========================================
+
+    fn sine_wav(freq: f32) -> Vec<u8> {
+        let samples = (0..16000)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / 16000.0).sin() * 0.5)
+            .collect();
+        AudioBuffer::new(samples, 16000, 1).to_wav_bytes().unwrap()
+    }
+
+    #[test]
+    fn test_engine_score_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        save_voice(&voice_manager, "amy", None);
+        let reference = temp_dir.path().join("ref.wav");
+        std::fs::write(&reference, sine_wav(220.0)).unwrap();
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| req.text == "Reference" && req.voice_name.as_deref() == Some("amy"))
+            .times(1)
+            .returning(|_| Ok(sine_wav(220.0)));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let score = engine.score_voice("amy", &reference).unwrap();
+        assert!(score > 0.99);
+    }
+
+    #[test]
+    fn test_engine_score_voice_missing_reference() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        save_voice(&voice_manager, "amy", None);
+
+        let engine = TTSEngine::new(mock_backend(), voice_manager);
+        let result = engine.score_voice("amy", &temp_dir.path().join("missing.wav"));
+        assert!(matches!(result.unwrap_err(), TTSError::AudioNotFound(_)));
+    }
+
+    // ===========================================
+    // Speaker embedding tests
+    // ===========================================
+
+    #[test]
+    fn test_engine_speaker_embedding_from_backend() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_get_embedding()
+            .withf(|name| name == "amy")
+            .returning(|name| {
+                Ok(EmbeddingResponse {
+                    name: name.to_string(),
+                    shape: vec![1, 2],
+                    embedding: "AACAPwAAAMA=".to_string(),
+                })
+            });
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let embedding = engine.speaker_embedding("amy").unwrap();
+        assert_eq!(embedding.source, EmbeddingSource::Backend);
+        assert_eq!(embedding.values, vec![1.0, -2.0]);
+    }
+
+    #[test]
+    fn test_engine_speaker_embedding_falls_back_to_voiceprint() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        let reference = temp_dir.path().join("ref.wav");
+        std::fs::write(&reference, sine_wav(220.0)).unwrap();
+        voice_manager
+            .save_metadata(&VoiceMetadata {
+                name: "amy".to_string(),
+                transcript: "Reference".to_string(),
+                model: "OpenF5-TTS".to_string(),
+                created_at: "2024-01-01T00:00:00Z".to_string(),
+                audio_path: Some(reference),
+                language: None,
+                consent: None,
+                locked: false,
+                allowed_uses: Vec::new(),
+                synthetic: false,
+                license: None,
+            })
+            .unwrap();
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_get_embedding()
+            .returning(|_| Err(BackendError::Unsupported("OpenF5-TTS".to_string())));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        let embedding = engine.speaker_embedding("amy").unwrap();
+        assert_eq!(embedding.source, EmbeddingSource::Voiceprint);
+        assert_eq!(embedding.shape, vec![embedding.values.len()]);
+    }
+
+    #[test]
+    fn test_engine_speaker_embedding_unsupported_without_audio() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        save_voice(&voice_manager, "amy", None);
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_get_embedding()
+            .returning(|_| Err(BackendError::Unsupported("VoxCPM".to_string())));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        assert!(matches!(
+            engine.speaker_embedding("amy").unwrap_err(),
+            TTSError::BackendError(BackendError::Unsupported(_))
+        ));
+    }
+
+    // ===========================================
+    // Consent tests
+    // ===========================================
+
+    #[test]
+    fn test_engine_require_consent_refuses_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        save_voice(&voice_manager, "amy", None);
+
+        let engine = TTSEngine::new(mock_backend(), voice_manager).with_require_consent(true);
+        let result = engine.synthesize("Hello", Some("amy".to_string()), 1.0);
+        assert!(matches!(result.unwrap_err(), TTSError::ConsentRequired(name) if name == "amy"));
+    }
+
+    #[test]
+    fn test_engine_record_consent_allows_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        save_voice(&voice_manager, "amy", None);
+        let file = temp_dir.path().join("signed.txt");
+        std::fs::write(&file, "I agree.").unwrap();
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_synthesize()
+            .times(1)
+            .returning(|_| Ok(b"RIFF".to_vec()));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager).with_require_consent(true);
+        engine
+            .record_consent("amy", Consent::from_file(&file, None, None).unwrap())
+            .unwrap();
+        assert!(
+            engine
+                .synthesize("Hello", Some("amy".to_string()), 1.0)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_engine_record_consent_unknown_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let file = temp_dir.path().join("signed.txt");
+        std::fs::write(&file, "I agree.").unwrap();
+
+        let engine = TTSEngine::new(mock_backend(), voice_manager);
+        let consent = Consent::from_file(&file, None, None).unwrap();
+        assert!(matches!(
+            engine.record_consent("ghost", consent).unwrap_err(),
+            TTSError::VoiceNotFound(_)
+        ));
+    }
+
+    // ===========================================
+    // Access control tests
+    // ===========================================
+
+    #[test]
+    fn test_engine_locked_voice_requires_unlock() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        save_voice(&voice_manager, "client", None);
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_synthesize()
+            .times(1)
+            .returning(|_| Ok(b"RIFF".to_vec()));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        engine.restrict_voice("client", true, Vec::new()).unwrap();
+        let result = engine.synthesize("Hello", Some("client".to_string()), 1.0);
+        assert!(matches!(result.unwrap_err(), TTSError::VoiceLocked(name) if name == "client"));
+
+        let engine = engine.with_unlocked(true);
+        assert!(
+            engine
+                .synthesize("Hello", Some("client".to_string()), 1.0)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_engine_allowed_uses_limit_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        save_voice(&voice_manager, "client", None);
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_synthesize()
+            .times(1)
+            .returning(|_| Ok(b"RIFF".to_vec()));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        engine
+            .restrict_voice("client", false, vec!["acme-ads".to_string()])
+            .unwrap();
+        let voice = || Some("client".to_string());
+
+        let result = engine.synthesize("Hello", voice(), 1.0);
+        assert!(matches!(
+            result.unwrap_err(),
+            TTSError::UseNotAllowed { .. }
+        ));
+
+        let engine = engine.with_project(Some("podcast".to_string()));
+        let result = engine.synthesize("Hello", voice(), 1.0);
+        assert!(matches!(
+            result.unwrap_err(),
+            TTSError::UseNotAllowed { allowed, .. } if allowed == "acme-ads"
+        ));
+
+        let engine = engine.with_project(Some("ACME-ads".to_string()));
+        assert!(engine.synthesize("Hello", voice(), 1.0).is_ok());
+    }
+
+    // ===========================================
+    // Encrypted store tests
+    // ===========================================
+
+    #[test]
+    fn test_engine_encrypted_store_reference_audio() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().join("voices"))
+            .unlock("pw")
+            .unwrap();
+        let source = temp_dir.path().join("ref.wav");
+        std::fs::write(&source, b"RIFF reference").unwrap();
+        let mut mock_backend = MockBackend::new();
+        mock_backend
+            .expect_capabilities()
+            .return_const(Capabilities {
+                persistent_voices: false,
+                ..Capabilities::unrestricted()
+            });
+
+        mock_backend
+            .expect_extract_voice()
+            .returning(|_, transcript, name| {
+                Ok(VoiceInfo {
+                    name: name.unwrap(),
+                    transcript: transcript.to_string(),
+                    model: "VoxCPM".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+        mock_backend
+            .expect_synthesize()
+            .withf(|req| {
+                // The backend sees a decrypted copy, not the stored file
+                let path = req.reference_audio.as_ref().unwrap();
+                std::fs::read(path).unwrap() == b"RIFF reference"
+            })
+            .times(1)
+            .returning(|_| Ok(b"RIFF".to_vec()));
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        engine
+            .extract_voice(&source, "Hi", Some("amy".to_string()))
+            .unwrap();
+
+        let stored = temp_dir.path().join("voices").join("amy.wav");
+        assert_ne!(std::fs::read(&stored).unwrap(), b"RIFF reference");
+        assert!(
+            engine
+                .synthesize("Hello", Some("amy".to_string()), 1.0)
+                .is_ok()
+        );
+    }
+
+    // ===========================================
+    // Voice pack tests
+    // ===========================================
+
+    #[test]
+    fn test_engine_install_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        let voices_dir = temp_dir.path().join("voices");
+        let voice_manager = VoiceManager::with_dir(voices_dir.clone());
+        let mut mock_backend = mock_backend();
+
+        mock_backend
+            .expect_extract_voice()
+            .withf(|path, transcript, name| {
+                std::fs::read(path).unwrap() == b"RIFF amy"
+                    && transcript == "Hello."
+                    && name.as_deref() == Some("amy")
+            })
+            .returning(|_, transcript, name| {
+                Ok(VoiceInfo {
+                    name: name.unwrap(),
+                    transcript: transcript.to_string(),
+                    model: "openvoice_v2".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+
+        let engine = TTSEngine::new(mock_backend, voice_manager);
+        engine
+            .install_voice("amy", "Hello.", b"RIFF amy", Some("zh"))
+            .unwrap();
+
+        let metadata = engine.voice_manager().load_metadata("amy").unwrap();
+        assert_eq!(metadata.language.as_deref(), Some("ZH"));
+        assert_eq!(metadata.audio_path, Some(voices_dir.join("amy.wav")));
+    }
+
+    // ===========================================
+    // Emulated voice persistence tests
+    // ===========================================
+
+    /// A backend that clones from reference audio on every request.
+    fn stateless_backend() -> MockBackend {
+        let mut mock = MockBackend::new();
+        mock.expect_capabilities().return_const(Capabilities {
+            persistent_voices: false,
+            ..Capabilities::unrestricted()
+        });
+        mock.expect_list_voices().never();
+        mock.expect_delete_voice().never();
+        mock.expect_extract_voice()
+            .returning(|_, transcript, name| {
+                Ok(VoiceInfo {
+                    name: name.unwrap_or_default(),
+                    transcript: transcript.to_string(),
+                    model: "voxcpm".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+        mock
+    }
+
+    #[test]
+    fn test_stateless_backend_voice_kept_locally() {
+        let temp_dir = TempDir::new().unwrap();
+        let voices_dir = temp_dir.path().join("voices");
+        let source = temp_dir.path().join("amy-take1.wav");
+        std::fs::write(&source, b"RIFF reference").unwrap();
+
+        let mut mock = stateless_backend();
+        let stored = voices_dir.join("amy.wav");
+        let expected = stored.clone();
+        mock.expect_synthesize()
+            .withf(move |request| {
+                request.reference_audio.as_deref() == Some(expected.as_path())
+                    && request.reference_transcript.as_deref() == Some("I agree.")
+            })
+            .times(1)
+            .returning(|_| Ok(b"RIFF audio".to_vec()));
+
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(voices_dir));
+        engine
+            .extract_voice(&source, "I agree.", Some("amy".to_string()))
+            .unwrap();
+
+        // The clip outlives the file it was extracted from
+        std::fs::remove_file(&source).unwrap();
+        assert_eq!(std::fs::read(&stored).unwrap(), b"RIFF reference");
+        engine
+            .synthesize("Hello", Some("amy".to_string()), 1.0)
+            .unwrap();
+
+        let voices = engine.list_voices().unwrap();
+        assert_eq!(voices.len(), 1);
+        assert_eq!(voices[0].name, "amy");
+        assert_eq!(voices[0].transcript, "I agree.");
+
+        engine.delete_voice("amy").unwrap();
+        assert!(!stored.exists());
+        assert!(engine.list_voices().unwrap().is_empty());
+        assert!(matches!(
+            engine.delete_voice("amy").unwrap_err(),
+            TTSError::VoiceNotFound(_)
+        ));
+    }
+
+    #[test]
+    fn test_stateless_backend_requires_reference() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        voice_manager
+            .save_metadata(&VoiceMetadata {
+                name: "amy".to_string(),
+                transcript: "I agree.".to_string(),
+                model: "voxcpm".to_string(),
+                created_at: "2024-01-01T00:00:00Z".to_string(),
+                audio_path: Some(temp_dir.path().join("moved.wav")),
+                language: None,
+                consent: None,
+                locked: false,
+                allowed_uses: Vec::new(),
+                synthetic: false,
+                license: None,
+            })
+            .unwrap();
+
+        let mut mock = stateless_backend();
+        mock.expect_synthesize().never();
+        let engine = TTSEngine::new(mock, voice_manager);
+
+        let err = engine
+            .synthesize("Hello", Some("amy".to_string()), 1.0)
+            .unwrap_err();
+        assert!(matches!(err, TTSError::ReferenceMissing(_)));
+        assert!(engine.list_voices().unwrap().is_empty());
+    }
+
+    // ===========================================
+    // Request planner tests
+    // ===========================================
+
+    #[test]
+    fn test_plan_sends_everything_a_backend_supports() {
+        let intent = Intent {
+            voice: Some("amy"),
+            reference: Some((Path::new("amy.wav"), "I agree.")),
+            speed: 1.2,
+            language: Some("ZH"),
+            seed: Some(7),
+            ..Intent::new("Hello")
+        };
+        let plan = planner::plan(&intent, &Capabilities::unrestricted()).unwrap();
+
+        assert!(plan.dropped.is_empty());
+        assert_eq!(plan.request.voice_name.as_deref(), Some("amy"));
+        assert_eq!(plan.request.reference_audio, None);
+        assert_eq!(plan.request.speed, 1.2);
+        assert_eq!(plan.request.language.as_deref(), Some("ZH"));
+        assert_eq!(plan.request.seed, Some(7));
+    }
+
+    #[test]
+    fn test_plan_for_stateless_backend() {
+        let capabilities = Capabilities {
+            persistent_voices: false,
+            speed: false,
+            max_text_length: Some(500),
+            ..Capabilities::default()
+        };
+        let intent = Intent {
+            voice: Some("amy"),
+            reference: Some((Path::new("amy.wav"), "I agree.")),
+            language: Some("ZH"),
+            seed: Some(7),
+            ..Intent::new("The [[ph: N IY1 CH AH0]].")
+        };
+        let plan = planner::plan(&intent, &capabilities).unwrap();
+
+        assert_eq!(plan.request.text, "The NEE-chuh.");
+        assert_eq!(plan.request.voice_name, None);
+        assert_eq!(
+            plan.request.reference_audio.as_deref(),
+            Some(Path::new("amy.wav"))
+        );
+        assert_eq!(
+            plan.request.reference_transcript.as_deref(),
+            Some("I agree.")
+        );
+        assert_eq!((plan.request.language, plan.request.seed), (None, None));
+        assert_eq!(
+            plan.dropped,
+            vec![Dropped::Language("ZH".to_string()), Dropped::Seed(7)]
+        );
+        assert!(plan.dropped[1].to_string().contains("seed 7 is ignored"));
+
+        let err = planner::plan(
+            &Intent {
+                speed: 1.5,
+                ..intent
+            },
+            &capabilities,
+        )
+        .unwrap_err();
+        assert!(matches!(err, TTSError::Unsupported(_)));
+        let err = planner::plan(
+            &Intent {
+                reference: None,
+                ..intent
+            },
+            &capabilities,
+        )
+        .unwrap_err();
+        assert!(matches!(err, TTSError::ReferenceMissing(_)));
+    }
+
+    #[test]
+    fn test_engine_reports_dropped_parameters() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = |capabilities| {
+            let mut mock = MockBackend::new();
+            mock.expect_capabilities().return_const(capabilities);
+            TTSEngine::new(mock, VoiceManager::with_dir(temp_dir.path().to_path_buf()))
+                .with_language(Some("de".to_string()))
+                .with_seed(Some(3))
+        };
+
+        assert!(
+            engine(Capabilities::unrestricted())
+                .dropped_parameters()
+                .is_empty()
+        );
+        assert_eq!(
+            engine(Capabilities::default()).dropped_parameters(),
+            vec![Dropped::Language("DE".to_string()), Dropped::Seed(3)]
+        );
+    }
+
+    // ===========================================
+    // Trash tests
+    // ===========================================
+
+    #[test]
+    fn test_engine_restores_deleted_voice_on_backend() {
+        let temp_dir = TempDir::new().unwrap();
+        let voices_dir = temp_dir.path().join("voices");
+        let source = temp_dir.path().join("recording.wav");
+        std::fs::write(&source, b"RIFF recording").unwrap();
+
+        let mut mock = mock_backend();
+        let stored = voices_dir.join("amy.wav");
+        let paths = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = paths.clone();
+        mock.expect_extract_voice()
+            .times(2)
+            .returning(move |path, transcript, name| {
+                seen.lock().unwrap().push(std::fs::read(path).unwrap());
+                Ok(VoiceInfo {
+                    name: name.unwrap(),
+                    transcript: transcript.to_string(),
+                    model: "openvoice_v2".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+        mock.expect_delete_voice()
+            .withf(|name| name == "amy")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let engine = TTSEngine::new(mock, VoiceManager::with_dir(voices_dir));
+        engine
+            .extract_voice(&source, "I agree.", Some("amy".to_string()))
+            .unwrap();
+        engine
+            .record_consent(
+                "amy",
+                Consent {
+                    file: PathBuf::from("consent.pdf"),
+                    sha256: "ab".to_string(),
+                    speaker: Some("Amy".to_string()),
+                    license: None,
+                    recorded_at: "2024-01-01T00:00:00Z".to_string(),
+                },
+            )
+            .unwrap();
+        engine.delete_voice("amy").unwrap();
+        std::fs::remove_file(&source).unwrap();
+
+        let restored = engine.restore_voice("amy").unwrap();
+        assert_eq!(restored.audio_path, Some(stored));
+        assert!(restored.consent.is_some());
+        assert_eq!(
+            *paths.lock().unwrap(),
+            vec![b"RIFF recording".to_vec(), b"RIFF recording".to_vec()]
+        );
+        assert!(matches!(
+            engine.restore_voice("amy").unwrap_err(),
+            TTSError::VoiceNotFound(_)
+        ));
+    }
+
+    #[test]
+    fn test_engine_random_voice_keeps_sample_and_needs_no_consent() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock = mock_backend();
+        mock.expect_random_voice()
+            .withf(|name, seed| name == "stranger1" && *seed == Some(7))
+            .times(1)
+            .returning(|name, _| {
+                Ok(VoiceInfo {
+                    name: name.to_string(),
+                    transcript: String::new(),
+                    model: "openvoice_v2".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+        mock.expect_synthesize()
+            .withf(|req| req.voice_name.as_deref() == Some("stranger1"))
+            .times(2)
+            .returning(|_| Ok(b"RIFF sample".to_vec()));
+
+        let engine = TTSEngine::new(mock, voice_manager).with_require_consent(true);
+        let info = engine.random_voice("stranger1", Some(7)).unwrap();
+        assert!(!info.transcript.is_empty());
+
+        let metadata = engine.voice_manager().load_metadata("stranger1").unwrap();
+        assert!(metadata.synthetic);
+        assert_eq!(metadata.transcript, info.transcript);
+        let sample = std::fs::read(metadata.audio_path.unwrap()).unwrap();
+        assert_eq!(sample, b"RIFF sample");
+
+        engine
+            .synthesize("Hello", Some("stranger1".to_string()), 1.0)
+            .unwrap();
+        assert!(matches!(
+            engine.random_voice("stranger1", None).unwrap_err(),
+            TTSError::VoiceError(VoiceError::AlreadyExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_engine_random_voice_removed_when_sample_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut mock = mock_backend();
+        mock.expect_random_voice().times(1).returning(|name, _| {
+            Ok(VoiceInfo {
+                name: name.to_string(),
+                transcript: String::new(),
+                model: "openvoice_v2".to_string(),
+                duration: None,
+                raw: Default::default(),
+            })
+        });
+        mock.expect_synthesize()
+            .returning(|_| Err(BackendError::RequestFailed("out of memory".to_string())));
+        mock.expect_delete_voice()
+            .withf(|name| name == "stranger1")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let engine = TTSEngine::new(mock, voice_manager);
+        assert!(engine.random_voice("stranger1", None).is_err());
+        assert!(engine.voice_manager().load_metadata("stranger1").is_err());
+    }
+
+    #[test]
+    fn test_engine_records_model_and_reference_licenses() {
+        let temp_dir = TempDir::new().unwrap();
+        let voice_manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        let audio_path = temp_dir.path().join("ref.wav");
+        std::fs::write(&audio_path, b"RIFF fake wav data").unwrap();
+
+        let mut mock = mock_backend();
+        mock.expect_extract_voice()
+            .times(1)
+            .returning(|_, transcript, name| {
+                Ok(VoiceInfo {
+                    name: name.unwrap(),
+                    transcript: transcript.to_string(),
+                    model: "openvoice_v2".to_string(),
+                    duration: None,
+                    raw: Default::default(),
+                })
+            });
+
+        let engine = TTSEngine::new(mock, voice_manager)
+            .with_model_license(Some(Model::OpenVoice.license().to_string()));
+        engine
+            .extract_voice(&audio_path, "Hello", Some("amy".to_string()))
+            .unwrap();
+        engine.record_reference_license("amy", "CC0-1.0").unwrap();
+
+        let license = engine
+            .voice_manager()
+            .load_metadata("amy")
+            .unwrap()
+            .license
+            .unwrap();
+        assert_eq!(license.model.as_deref(), Some("MIT"));
+        assert_eq!(license.reference.as_deref(), Some("CC0-1.0"));
+        assert!(matches!(
+            engine.record_reference_license("nobody", "CC0-1.0"),
+            Err(TTSError::VoiceNotFound(_))
+        ));
+    }
 }
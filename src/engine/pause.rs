@@ -0,0 +1,51 @@
+//! Pausing synthesis between requests.
+//!
+//! A long render can yield the GPU for a while without being stopped: once
+//! [`Pause`] is paused, requests already sent finish and are kept, but no
+//! new one is sent until it is resumed. Nothing is cancelled, so a batch
+//! job picks up at the chunk it had reached.
+
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Holds new requests while paused, shared by every clone.
+#[derive(Debug, Clone, Default)]
+pub struct Pause {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold new requests. Returns false if already paused.
+    pub fn pause(&self) -> bool {
+        !std::mem::replace(&mut *self.state.0.lock().unwrap(), true)
+    }
+
+    /// Let held requests go. Returns false if not paused.
+    pub fn resume(&self) -> bool {
+        let resumed = std::mem::replace(&mut *self.state.0.lock().unwrap(), false);
+        self.state.1.notify_all();
+        resumed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.state.0.lock().unwrap()
+    }
+
+    /// Block while paused, calling `paused` first if it has to wait.
+    ///
+    /// The lock is released around `paused`, so it may pause or resume.
+    pub(crate) fn wait(&self, paused: impl FnOnce()) {
+        if !self.is_paused() {
+            return;
+        }
+        paused();
+        let (state, resumed) = &*self.state;
+        let mut held = state.lock().unwrap();
+        while *held {
+            held = resumed.wait(held).unwrap();
+        }
+    }
+}
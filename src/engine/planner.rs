@@ -0,0 +1,130 @@
+//! Planning backend requests.
+//!
+//! The planner turns what was asked for (text, voice, speed, language, and
+//! seed) into the request a backend can honor, given its [`Capabilities`]:
+//!
+//! - a voice goes by name to servers that keep voices, and as reference
+//!   audio and transcript to servers that clone on every request,
+//! - phoneme segments go as SSML to backends that accept them, and are
+//!   respelled on the client otherwise,
+//! - a speed the backend cannot set, or that is outside its range, is an
+//!   error, since changing speed on the client would change pitch too,
+//! - a language or seed the backend ignores is left out of the request and
+//!   reported as [`Dropped`], so callers can warn instead of the setting
+//!   silently doing nothing.
+
+use std::fmt;
+use std::path::Path;
+
+use super::TTSError;
+use crate::backend::{Capabilities, SynthesizeRequest};
+use crate::text::render_phonemes;
+
+/// What a synthesis was asked to do.
+#[derive(Debug, Clone, Copy)]
+pub struct Intent<'a> {
+    pub text: &'a str,
+    /// Saved voice name.
+    pub voice: Option<&'a str>,
+    /// The voice's reference clip and its transcript, when readable.
+    pub reference: Option<(&'a Path, &'a str)>,
+    pub speed: f32,
+    pub language: Option<&'a str>,
+    pub seed: Option<u64>,
+}
+
+impl<'a> Intent<'a> {
+    /// Plain text in the default voice.
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            voice: None,
+            reference: None,
+            speed: 1.0,
+            language: None,
+            seed: None,
+        }
+    }
+}
+
+/// A setting left out of the request because the backend ignores it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dropped {
+    Language(String),
+    Seed(u64),
+}
+
+impl fmt::Display for Dropped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Language(language) => write!(
+                f,
+                "language {language} is ignored; the backend speaks the language of the text"
+            ),
+            Self::Seed(seed) => write!(
+                f,
+                "seed {seed} is ignored; every generation varies on this backend"
+            ),
+        }
+    }
+}
+
+/// The request to send, and what it leaves out.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub request: SynthesizeRequest,
+    pub dropped: Vec<Dropped>,
+}
+
+/// Build the request `capabilities` allow for `intent`.
+pub fn plan(intent: &Intent, capabilities: &Capabilities) -> Result<Plan, TTSError> {
+    check_speed(capabilities, intent.speed)?;
+
+    let mut request = SynthesizeRequest::new(render_phonemes(intent.text, capabilities.phonemes))
+        .with_speed(intent.speed);
+    let mut dropped = Vec::new();
+
+    if let Some(voice) = intent.voice {
+        if capabilities.persistent_voices {
+            request = request.with_voice(voice);
+        } else {
+            let (audio, transcript) = intent
+                .reference
+                .ok_or_else(|| TTSError::ReferenceMissing(voice.to_string()))?;
+            request = request
+                .with_reference_audio(audio.to_path_buf())
+                .with_reference_transcript(transcript);
+        }
+    }
+    match (intent.language, capabilities.language) {
+        (Some(language), true) => request = request.with_language(language),
+        (Some(language), false) => dropped.push(Dropped::Language(language.to_string())),
+        (None, _) => {}
+    }
+    match (intent.seed, capabilities.seed) {
+        (Some(seed), true) => request = request.with_seed(seed),
+        (Some(seed), false) => dropped.push(Dropped::Seed(seed)),
+        (None, _) => {}
+    }
+
+    Ok(Plan { request, dropped })
+}
+
+/// Reject a speed the backend cannot set, or sets outside its range.
+pub(super) fn check_speed(capabilities: &Capabilities, speed: f32) -> Result<(), TTSError> {
+    if (speed - 1.0).abs() <= f32::EPSILON {
+        return Ok(());
+    }
+    if !capabilities.speed {
+        return Err(TTSError::Unsupported(format!(
+            "speed {speed}x (this backend only speaks at 1.0x)"
+        )));
+    }
+    let range = capabilities.speed_range;
+    if !range.contains(speed) {
+        return Err(TTSError::Unsupported(format!(
+            "speed {speed}x (this backend accepts {range})"
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Backing off when the backend's GPU runs short of memory.
+//!
+//! A backend that shares its GPU with other work can fail a request that
+//! fit a moment earlier. Each out-of-memory or overload error steps
+//! [`Pressure`] up: the number of requests in flight is halved and the
+//! text of each request is cut into more, shorter pieces. A run of
+//! successes steps it back down, restoring full-length requests first and
+//! then concurrency.
+
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// Most times the text of a request is halved.
+pub const MAX_SPLITS: u32 = 3;
+
+/// Shortest piece text is cut into, in characters.
+const MIN_PIECE: usize = 40;
+
+/// Successes in a row before easing off by one step.
+const RAMP_UP: u32 = 4;
+
+/// How hard requests back off, shared by every thread using one engine.
+#[derive(Debug)]
+pub struct Pressure {
+    state: Mutex<State>,
+    freed: Condvar,
+    delay: Duration,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+    /// Most requests ever in flight at once.
+    peak: usize,
+    /// Most requests allowed in flight; `None` for no limit.
+    limit: Option<usize>,
+    /// Times each request's text is halved.
+    splits: u32,
+    /// Bumped at each step up, so the requests that were already in
+    /// flight do not each step up again when they fail too.
+    generation: u64,
+    successes: u32,
+}
+
+impl Default for Pressure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pressure {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            freed: Condvar::new(),
+            delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Wait `delay` times the number of splits before retrying a request
+    /// that overloaded the backend [default: 1s].
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Most requests allowed in flight, or `None` when not limited.
+    pub fn limit(&self) -> Option<usize> {
+        self.state.lock().unwrap().limit
+    }
+
+    /// Times the text of each request is halved.
+    pub fn splits(&self) -> u32 {
+        self.state.lock().unwrap().splits
+    }
+
+    /// Wait for a free slot under the limit.
+    pub(crate) fn acquire(&self) -> Permit<'_> {
+        let mut state = self.state.lock().unwrap();
+        while state.limit.is_some_and(|limit| state.in_flight >= limit) {
+            state = self.freed.wait(state).unwrap();
+        }
+        state.in_flight += 1;
+        state.peak = state.peak.max(state.in_flight);
+        Permit {
+            pressure: self,
+            generation: state.generation,
+            splits: state.splits,
+        }
+    }
+}
+
+/// A request in flight under [`Pressure`].
+pub(crate) struct Permit<'a> {
+    pressure: &'a Pressure,
+    generation: u64,
+    splits: u32,
+}
+
+impl Permit<'_> {
+    /// The longest piece of `text` to send in one request, if it is to be
+    /// split at all.
+    pub(crate) fn text_limit(&self, text: &str) -> Option<usize> {
+        (self.splits > 0).then(|| (text.chars().count() >> self.splits).max(MIN_PIECE))
+    }
+
+    /// The request succeeded; after enough in a row, ease off a step.
+    pub(crate) fn succeeded(self) {
+        let mut state = self.pressure.state.lock().unwrap();
+        state.successes += 1;
+        if state.successes < RAMP_UP {
+            return;
+        }
+        state.successes = 0;
+        if state.splits > 0 {
+            state.splits -= 1;
+        } else if let Some(limit) = state.limit {
+            state.limit = (limit + 1 < state.peak).then_some(limit + 1);
+            self.pressure.freed.notify_all();
+        }
+    }
+
+    /// The backend ran out of memory. Steps up unless another request
+    /// already did since this one was sent, and returns how long to wait
+    /// before retrying, or `None` when fully backed off already.
+    pub(crate) fn overloaded(self) -> Option<Duration> {
+        let mut state = self.pressure.state.lock().unwrap();
+        state.successes = 0;
+        if self.generation == state.generation {
+            let halved = (state.in_flight / 2).max(1);
+            if state.splits == MAX_SPLITS && state.limit.is_some_and(|limit| limit <= halved) {
+                return None;
+            }
+            state.limit = Some(state.limit.map_or(halved, |limit| limit.min(halved)));
+            state.splits = (state.splits + 1).min(MAX_SPLITS);
+            state.generation += 1;
+        }
+        Some(self.pressure.delay * state.splits)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.pressure.state.lock().unwrap().in_flight -= 1;
+        self.pressure.freed.notify_one();
+    }
+}
@@ -0,0 +1,164 @@
+//! Streaming synthesis, emitting each chunk's audio as soon as it is ready.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+use super::tts::{TTSEngine, TTSError, speech_count};
+use crate::audio::{AudioBuffer, Segment};
+use crate::backend::Backend;
+use crate::text::Chunk;
+
+impl<B: Backend> TTSEngine<B> {
+    /// Synthesize chunks one at a time, passing each piece of audio to
+    /// `emit` as soon as it is ready.
+    ///
+    /// Every piece has the format of the first clip: later clips are
+    /// converted to it and pauses become silence, with pauses before the
+    /// first clip held back until its format is known. Stops early, without
+    /// an error, once `emit` returns `false`.
+    pub fn synthesize_streaming(
+        &self,
+        chunks: &[Chunk],
+        emit: impl FnMut(AudioBuffer) -> bool,
+    ) -> Result<(), TTSError> {
+        self.check_chunks(chunks)?;
+        self.stream_clips(
+            chunks,
+            |chunk| match chunk {
+                Chunk::Speech { text, voice, speed } => {
+                    self.synthesize(text, voice.clone(), *speed)
+                }
+                Chunk::Pause(_) | Chunk::Bleep(_) => unreachable!("only speech is synthesized"),
+            },
+            emit,
+        )
+    }
+
+    /// Like [`synthesize_streaming`](Self::synthesize_streaming), but with up
+    /// to `workers` speech chunks synthesizing at once in the background.
+    ///
+    /// Pieces are still emitted in order, and `emit` may take its time (to
+    /// play a sentence, say) while the following ones are generated. After
+    /// an error or a `false` from `emit`, no further chunks are started.
+    pub fn synthesize_streaming_ahead(
+        &self,
+        chunks: &[Chunk],
+        workers: usize,
+        emit: impl FnMut(AudioBuffer) -> bool,
+    ) -> Result<(), TTSError> {
+        self.check_chunks(chunks)?;
+        let speech: Vec<usize> = (0..chunks.len())
+            .filter(|&i| matches!(chunks[i], Chunk::Speech { .. }))
+            .collect();
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            for _ in 0..workers.clamp(1, speech.len().max(1)) {
+                let sender = sender.clone();
+                let (speech, next, stop) = (&speech, &next, &stop);
+                scope.spawn(move || self.synthesize_queued(chunks, speech, next, stop, &sender));
+            }
+            drop(sender);
+
+            // Clips arrive in the order they finish; hold them until their turn
+            let mut finished = HashMap::new();
+            let mut position = 0;
+            let streamed = self.stream_clips(
+                chunks,
+                |_| {
+                    let i = speech[position];
+                    position += 1;
+                    loop {
+                        if let Some(wav) = finished.remove(&i) {
+                            return wav;
+                        }
+                        let (j, wav) = receiver
+                            .recv()
+                            .expect("a worker synthesizes every speech chunk");
+                        finished.insert(j, wav);
+                    }
+                },
+                emit,
+            );
+            stop.store(true, Ordering::Relaxed);
+            streamed
+        })
+    }
+
+    /// Take the next of the `speech` chunks until none are left or `stop`
+    /// is set, and send each one's WAV with its index.
+    fn synthesize_queued(
+        &self,
+        chunks: &[Chunk],
+        speech: &[usize],
+        next: &AtomicUsize,
+        stop: &AtomicBool,
+        sender: &mpsc::Sender<(usize, Result<Vec<u8>, TTSError>)>,
+    ) {
+        while !stop.load(Ordering::Relaxed) {
+            let Some(&i) = speech.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                break;
+            };
+            let Chunk::Speech { text, voice, speed } = &chunks[i] else {
+                unreachable!("only speech is queued");
+            };
+            let wav = self.synthesize(text, voice.clone(), *speed);
+            if sender.send((i, wav)).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Emit `chunks` as audio in order, getting each speech chunk's WAV
+    /// from `synthesize`. See [`synthesize_streaming`](Self::synthesize_streaming).
+    fn stream_clips(
+        &self,
+        chunks: &[Chunk],
+        mut synthesize: impl FnMut(&Chunk) -> Result<Vec<u8>, TTSError>,
+        mut emit: impl FnMut(AudioBuffer) -> bool,
+    ) -> Result<(), TTSError> {
+        let mut format = None;
+        // Pauses and bleeps before the first clip wait for its format
+        let mut held = Vec::new();
+        let total = speech_count(chunks);
+        let mut done = 0;
+
+        for chunk in chunks {
+            let segment = match chunk {
+                Chunk::Pause(duration) => Segment::Silence(*duration),
+                Chunk::Bleep(duration) => Segment::Bleep(*duration),
+                Chunk::Speech { .. } => {
+                    let wav = synthesize(chunk)?;
+                    done += 1;
+                    self.chunk_done(done, total);
+                    let clip = AudioBuffer::from_wav_bytes(&wav)?;
+                    let (rate, channels) = *format.get_or_insert((clip.sample_rate, clip.channels));
+                    for segment in held.drain(..) {
+                        if !emit(Segment::into_buffer(segment, rate, channels)) {
+                            return Ok(());
+                        }
+                    }
+                    Segment::Clip(clip.resample(rate).remix(channels))
+                }
+            };
+            let audio = match format {
+                Some((rate, channels)) => segment.into_buffer(rate, channels),
+                None => {
+                    held.push(segment);
+                    continue;
+                }
+            };
+            if !emit(audio) {
+                return Ok(());
+            }
+        }
+
+        if format.is_none() {
+            return Err(TTSError::EmptyText);
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,18 @@
 //! TTS Engine implementation.
 
-use std::path::Path;
-
-use chrono::Utc;
 use thiserror::Error;
 
-use crate::backend::{Backend, BackendError, HealthResponse, SynthesizeRequest, VoiceInfo};
-use crate::voice::{VoiceError, VoiceManager, VoiceMetadata};
+use super::pause::Pause;
+use super::planner::{Dropped, Intent, check_speed, plan};
+use super::pressure::Pressure;
+use crate::audio::{
+    AudioBuffer, AudioError, AudioSink, Cleanup, Segment, assemble, assemble_tracks,
+};
+use crate::backend::{
+    Backend, BackendError, Capabilities, DynBackend, HealthResponse, Progress, SynthesisEvent,
+};
+use crate::text::Chunk;
+use crate::voice::{VoiceError, VoiceManager};
 
 /// Errors that can occur during TTS operations.
 #[derive(Error, Debug)]
@@ -22,12 +28,58 @@ pub enum TTSError {
 
     #[error("Audio file not found: {0}")]
     AudioNotFound(String),
+
+    #[error("Audio processing error: {0}")]
+    AudioError(#[from] AudioError),
+
+    #[error("Nothing to synthesize")]
+    EmptyText,
+
+    #[error("Voice '{voice}' was recorded in {recorded}, but {requested} was requested")]
+    LanguageMismatch {
+        voice: String,
+        recorded: String,
+        requested: String,
+    },
+
+    #[error("Voice '{0}' has no recorded consent (see --consent-file)")]
+    ConsentRequired(String),
+
+    #[error("Voice '{0}' is locked; pass --unlock to use it")]
+    VoiceLocked(String),
+
+    #[error("Voice '{voice}' may only be used in {allowed}, not {requested}")]
+    UseNotAllowed {
+        voice: String,
+        allowed: String,
+        requested: String,
+    },
+
+    #[error("Not supported by this backend: {0}")]
+    Unsupported(String),
+
+    #[error("Voice '{0}' has no stored reference audio; extract it again with -r")]
+    ReferenceMissing(String),
 }
 
 /// The main TTS engine that orchestrates between components.
-pub struct TTSEngine<B: Backend> {
-    backend: B,
-    voice_manager: VoiceManager,
+///
+/// Generic over its backend; the default, [`DynBackend`], lets engines for
+/// different backends share one type.
+pub struct TTSEngine<B: Backend = DynBackend> {
+    pub(super) backend: B,
+    pub(super) voice_manager: VoiceManager,
+    pub(super) language: Option<String>,
+    pub(super) require_consent: bool,
+    pub(super) project: Option<String>,
+    pub(super) unlocked: bool,
+    pub(super) max_text_length: Option<usize>,
+    pub(super) seed: Option<u64>,
+    pub(super) cleanup: Option<Cleanup>,
+    pub(super) model_license: Option<String>,
+    pub(super) progress: Option<Progress>,
+    pub(super) pressure: Option<Pressure>,
+    pub(super) pause: Option<Pause>,
 }
 
 impl<B: Backend> TTSEngine<B> {
@@ -36,96 +88,293 @@ impl<B: Backend> TTSEngine<B> {
         Self {
             backend,
             voice_manager,
+            language: None,
+            require_consent: false,
+            project: None,
+            unlocked: false,
+            max_text_length: None,
+            seed: None,
+            cleanup: None,
+            model_license: None,
+            progress: None,
+            pressure: None,
+            pause: None,
         }
     }
 
-    /// Check backend health status.
-    pub fn health_check(&self) -> Result<HealthResponse, TTSError> {
-        Ok(self.backend.health()?)
+    /// Set the language used for synthesis and recorded on extracted voices.
+    ///
+    /// Codes are normalized to upper case (`en` -> `EN`).
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language.map(|l| l.to_uppercase());
+        self
+    }
+
+    /// Refuse to synthesize with cloned voices that have no consent record.
+    pub fn with_require_consent(mut self, require: bool) -> Self {
+        self.require_consent = require;
+        self
+    }
+
+    /// Set the project synthesis is for, checked against each voice's
+    /// allowed uses.
+    pub fn with_project(mut self, project: Option<String>) -> Self {
+        self.project = project;
+        self
+    }
+
+    /// Allow synthesizing with locked voices.
+    pub fn with_unlocked(mut self, unlocked: bool) -> Self {
+        self.unlocked = unlocked;
+        self
+    }
+
+    /// Send `seed` with every request, so models that sample give the same
+    /// take again; `None` lets each generation vary.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Split requests longer than `max` characters, overriding the limit
+    /// the backend reports.
+    pub fn with_max_text_length(mut self, max: Option<usize>) -> Self {
+        self.max_text_length = max;
+        self
+    }
+
+    /// Filter reference clips with `cleanup` before they are uploaded;
+    /// the cleaned clip is the one kept in the voice store.
+    pub fn with_cleanup(mut self, cleanup: Option<Cleanup>) -> Self {
+        self.cleanup = cleanup;
+        self
     }
 
-    /// Extract voice from reference audio and save it.
+    /// Record `license` as the model license of voices extracted from now
+    /// on, e.g. [`Model::license`](crate::backend::Model::license).
+    pub fn with_model_license(mut self, license: Option<String>) -> Self {
+        self.model_license = license;
+        self
+    }
+
+    /// Report each step of every synthesis to `callback`.
     ///
-    /// This uploads the voice to the backend and saves metadata locally.
-    pub fn extract_voice(
-        &self,
-        audio_path: &Path,
-        transcript: &str,
-        name: Option<String>,
-    ) -> Result<VoiceInfo, TTSError> {
-        // Verify audio file exists
-        if !audio_path.exists() {
-            return Err(TTSError::AudioNotFound(audio_path.display().to_string()));
-        }
+    /// `callback` may run on a helper thread while the engine waits for the
+    /// backend.
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(SynthesisEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Progress::new(callback));
+        self
+    }
 
-        // Extract voice on backend
-        let voice_info = self
-            .backend
-            .extract_voice(audio_path, transcript, name.clone())?;
-
-        // Save metadata locally (include audio path for Gradio backends)
-        let metadata = VoiceMetadata {
-            name: voice_info.name.clone(),
-            transcript: voice_info.transcript.clone(),
-            model: voice_info.model.clone(),
-            created_at: Utc::now().to_rfc3339(),
-            audio_path: Some(audio_path.to_path_buf()),
-        };
-        self.voice_manager.save_metadata(&metadata)?;
+    /// Back off under `pressure` when the backend runs out of GPU memory or
+    /// is overloaded, retrying with fewer requests in flight and shorter
+    /// text, then ramping back up as requests succeed.
+    pub fn with_pressure(mut self, pressure: Option<Pressure>) -> Self {
+        self.pressure = pressure;
+        self
+    }
+
+    /// How hard requests are backing off, if they do.
+    pub fn pressure(&self) -> Option<&Pressure> {
+        self.pressure.as_ref()
+    }
+
+    /// Hold each request while `pause` is paused. Requests already sent
+    /// finish, so a batch stops cleanly between chunks.
+    pub fn with_pause(mut self, pause: Option<Pause>) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// The local voice store.
+    pub fn voice_manager(&self) -> &VoiceManager {
+        &self.voice_manager
+    }
+
+    /// The backend requests are sent to.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
 
-        Ok(voice_info)
+    /// Erase the backend type, so this engine can sit alongside engines for
+    /// other backends.
+    pub fn boxed(self) -> TTSEngine
+    where
+        B: 'static,
+    {
+        TTSEngine {
+            backend: Box::new(self.backend),
+            voice_manager: self.voice_manager,
+            language: self.language,
+            require_consent: self.require_consent,
+            project: self.project,
+            unlocked: self.unlocked,
+            max_text_length: self.max_text_length,
+            seed: self.seed,
+            cleanup: self.cleanup,
+            model_license: self.model_license,
+            progress: self.progress,
+            pressure: self.pressure,
+            pause: self.pause,
+        }
+    }
+
+    /// Check backend health status.
+    pub fn health_check(&self) -> Result<HealthResponse, TTSError> {
+        Ok(self.backend.health()?)
     }
 
     /// Synthesize speech from text.
     ///
     /// If a voice name is provided, it must exist locally or on the backend.
+    /// Without an explicit language, the voice's recorded language is used;
+    /// requesting a different one is an error.
     pub fn synthesize(
         &self,
         text: &str,
         voice_name: Option<String>,
         speed: f32,
     ) -> Result<Vec<u8>, TTSError> {
-        // Load voice metadata if specified
-        let metadata = match &voice_name {
-            Some(name) => Some(
-                self.voice_manager
-                    .load_metadata(name)
-                    .map_err(|_| TTSError::VoiceNotFound(name.clone()))?,
-            ),
-            None => None,
-        };
+        self.synthesize_request(text, voice_name, speed, None)
+    }
+
+    /// [`synthesize`](Self::synthesize) under a caller-chosen request ID,
+    /// which is sent to the server and named in errors so the call can be
+    /// found in backend logs.
+    pub fn synthesize_traced(
+        &self,
+        text: &str,
+        voice_name: Option<String>,
+        speed: f32,
+        request_id: &str,
+    ) -> Result<Vec<u8>, TTSError> {
+        self.synthesize_request(text, voice_name, speed, Some(request_id.to_string()))
+    }
 
-        let mut request = SynthesizeRequest {
-            text: text.to_string(),
-            voice_name,
-            speed,
-            reference_audio: None,
-            reference_transcript: None,
+    /// Settings this engine sends that its backend ignores, for warning
+    /// before synthesizing.
+    pub fn dropped_parameters(&self) -> Vec<Dropped> {
+        let intent = Intent {
+            language: self.language.as_deref(),
+            seed: self.seed,
+            ..Intent::new("")
         };
+        plan(&intent, &self.capabilities()).map_or_else(|_| Vec::new(), |plan| plan.dropped)
+    }
 
-        // Add reference audio/transcript for Gradio backends
-        if let Some(meta) = metadata {
-            request.reference_audio = meta.audio_path;
-            request.reference_transcript = Some(meta.transcript);
+    /// Features the backend supports, with the text length limit set by
+    /// [`with_max_text_length`](Self::with_max_text_length) if any.
+    pub fn capabilities(&self) -> Capabilities {
+        let mut capabilities = self.backend.capabilities();
+        if self.max_text_length.is_some() {
+            capabilities.max_text_length = self.max_text_length;
         }
+        capabilities
+    }
 
-        Ok(self.backend.synthesize(&request)?)
+    /// Check every chunk against the backend's capabilities before any
+    /// audio is generated, so a long job does not fail halfway through.
+    pub fn check_chunks(&self, chunks: &[Chunk]) -> Result<(), TTSError> {
+        for chunk in chunks {
+            if let Chunk::Speech { speed, .. } = chunk {
+                self.check_speed(*speed)?;
+            }
+        }
+        Ok(())
     }
 
-    /// List all available voices from the backend.
-    pub fn list_voices(&self) -> Result<Vec<VoiceInfo>, TTSError> {
-        let response = self.backend.list_voices()?;
-        Ok(response.voices)
+    /// Check that the backend can speak at `speed`.
+    pub fn check_speed(&self, speed: f32) -> Result<(), TTSError> {
+        check_speed(&self.capabilities(), speed)
     }
 
-    /// Delete a voice from both backend and local storage.
-    pub fn delete_voice(&self, name: &str) -> Result<(), TTSError> {
-        // Delete from backend
-        self.backend.delete_voice(name)?;
+    /// Synthesize a sequence of chunks and join them into one WAV file.
+    ///
+    /// A single speech chunk is returned exactly as the backend produced it.
+    /// Otherwise each clip is decoded, pauses become silence, and the result
+    /// is re-encoded as 16-bit PCM.
+    pub fn synthesize_chunks(&self, chunks: &[Chunk]) -> Result<Vec<u8>, TTSError> {
+        self.check_chunks(chunks)?;
+        if let [Chunk::Speech { text, voice, speed }] = chunks {
+            let wav = self.synthesize(text, voice.clone(), *speed)?;
+            self.chunk_done(1, 1);
+            return Ok(wav);
+        }
 
-        // Delete local metadata (ignore if not found locally)
-        let _ = self.voice_manager.delete_local(name);
+        let segments = self.synthesize_segments(chunks)?;
+        Ok(assemble(segments.into_iter().map(|(_, s)| s).collect())?.to_wav_bytes()?)
+    }
 
+    /// Synthesize chunks and deliver the joined WAV to `sink`.
+    pub fn synthesize_to(
+        &self,
+        chunks: &[Chunk],
+        sink: &mut dyn AudioSink,
+    ) -> Result<(), TTSError> {
+        let wav = self.synthesize_chunks(chunks)?;
+        sink.write(&wav)?;
         Ok(())
     }
+
+    /// Synthesize a dialogue as a mixdown plus one aligned track per voice.
+    ///
+    /// Tracks are named after the voice (`default` when none is set), all
+    /// have the mixdown's length, and hold silence while other voices speak.
+    pub fn synthesize_tracks(
+        &self,
+        chunks: &[Chunk],
+    ) -> Result<(AudioBuffer, Vec<(String, AudioBuffer)>), TTSError> {
+        let segments = self.synthesize_segments(chunks)?;
+        let mixdown = assemble(segments.iter().map(|(_, s)| s.clone()).collect())?;
+        let tracks = assemble_tracks(segments)?;
+        Ok((mixdown, tracks))
+    }
+
+    /// Synthesize each speech chunk, labelled with its voice.
+    fn synthesize_segments(&self, chunks: &[Chunk]) -> Result<Vec<(String, Segment)>, TTSError> {
+        self.check_chunks(chunks)?;
+        let total = speech_count(chunks);
+        let mut done = 0;
+        let mut segments = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            segments.push(match chunk {
+                Chunk::Speech { text, voice, speed } => {
+                    let wav = self.synthesize(text, voice.clone(), *speed)?;
+                    done += 1;
+                    self.chunk_done(done, total);
+                    let clip = AudioBuffer::from_wav_bytes(&wav)?;
+                    (chunk.track().to_string(), Segment::Clip(clip))
+                }
+                Chunk::Pause(duration) => (chunk.track().to_string(), Segment::Silence(*duration)),
+                Chunk::Bleep(duration) => (chunk.track().to_string(), Segment::Bleep(*duration)),
+            });
+        }
+
+        if !segments.iter().any(|(_, s)| matches!(s, Segment::Clip(_))) {
+            return Err(TTSError::EmptyText);
+        }
+
+        Ok(segments)
+    }
+
+    pub(super) fn chunk_done(&self, i: usize, of: usize) {
+        if let Some(progress) = &self.progress {
+            progress.emit(SynthesisEvent::ChunkDone { i, of });
+        }
+    }
+
+    /// Stop a backend job announced by [`SynthesisEvent::Queued`].
+    pub fn cancel(&self, job_id: &str) -> Result<(), TTSError> {
+        Ok(self.backend.cancel(job_id)?)
+    }
+}
+
+pub(super) fn speech_count(chunks: &[Chunk]) -> usize {
+    chunks
+        .iter()
+        .filter(|c| matches!(c, Chunk::Speech { .. }))
+        .count()
 }
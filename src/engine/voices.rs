@@ -0,0 +1,381 @@
+//! Managing the voice library through the engine: extracting, installing,
+//! restricting, scoring, and deleting voices.
+
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+
+use super::tts::{TTSEngine, TTSError};
+use crate::audio::{AudioBuffer, AudioError, Cleanup, Voiceprint, decode_file};
+use crate::backend::{Backend, BackendError, SynthesizeRequest, VoiceInfo};
+use crate::voice::{
+    Consent, EmbeddingSource, ReferenceAudio, SpeakerEmbedding, VoiceError, VoiceLicense,
+    VoiceMetadata,
+};
+
+/// Spoken by a new random voice to give it reference audio; long enough for
+/// a clone to pick up its timbre.
+const RANDOM_VOICE_SAMPLE: &str = "This voice was drawn at random and belongs to no one. \
+    It can read stories, announcements, and instructions in a calm, clear way.";
+
+impl<B: Backend> TTSEngine<B> {
+    /// Extract voice from reference audio and save it.
+    ///
+    /// This uploads the voice to the backend and saves metadata locally.
+    /// Backends that keep no voices (see
+    /// [`Capabilities::persistent_voices`](crate::backend::Capabilities::persistent_voices))
+    /// clone from the reference on every request, so the clip is copied into
+    /// the store where later synthesis can find it.
+    pub fn extract_voice(
+        &self,
+        audio_path: &Path,
+        transcript: &str,
+        name: Option<String>,
+    ) -> Result<VoiceInfo, TTSError> {
+        // Verify audio file exists
+        if !audio_path.exists() {
+            return Err(TTSError::AudioNotFound(audio_path.display().to_string()));
+        }
+        let cleaned = self
+            .cleanup
+            .map(|cleanup| clean_reference(audio_path, &cleanup))
+            .transpose()?;
+        let audio_path = cleaned.as_ref().map_or(audio_path, ReferenceAudio::path);
+
+        // Extract voice on backend
+        let voice_info = self
+            .backend
+            .extract_voice(audio_path, transcript, name.clone())?;
+
+        let stored_audio = self.keep_reference(&voice_info.name, audio_path, cleaned.is_some())?;
+
+        // Save metadata locally (include audio path for Gradio backends)
+        let metadata = VoiceMetadata {
+            name: voice_info.name.clone(),
+            transcript: voice_info.transcript.clone(),
+            model: voice_info.model.clone(),
+            created_at: Utc::now().to_rfc3339(),
+            audio_path: Some(stored_audio),
+            language: self.language.clone(),
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: self.recorded_license(),
+        };
+        self.voice_manager.save_metadata(&metadata)?;
+
+        Ok(voice_info)
+    }
+
+    /// Where the reference of the voice `name` is kept: a copy in the store
+    /// when the backend will not keep it, an encrypted one when the store is
+    /// encrypted, and the `cleaned` one since it is about to be removed;
+    /// otherwise `audio_path` itself.
+    fn keep_reference(
+        &self,
+        name: &str,
+        audio_path: &Path,
+        cleaned: bool,
+    ) -> Result<PathBuf, TTSError> {
+        if cleaned || self.voice_manager.is_unlocked() || !self.capabilities().persistent_voices {
+            Ok(self.voice_manager.store_audio(name, audio_path)?)
+        } else {
+            Ok(audio_path.to_path_buf())
+        }
+    }
+
+    /// Create a voice that imitates no real speaker, drawn at random on the
+    /// backend; the same `seed` draws the same voice.
+    ///
+    /// A sample of it is kept as its reference audio, so it can be extracted
+    /// again like a cloned voice, or cloned on other models.
+    pub fn random_voice(&self, name: &str, seed: Option<u64>) -> Result<VoiceInfo, TTSError> {
+        if self.voice_manager.load_metadata(name).is_ok() {
+            return Err(VoiceError::AlreadyExists(name.to_string()).into());
+        }
+        let voice_info = self.backend.random_voice(name, seed)?;
+
+        let mut request = SynthesizeRequest::new(RANDOM_VOICE_SAMPLE);
+        request.voice_name = Some(voice_info.name.clone());
+        request.language = self.language.clone();
+        request.seed = seed;
+        let sample = match self.backend.synthesize(&request) {
+            Ok(sample) => sample,
+            Err(e) => {
+                // Without a sample the voice could never be restored
+                let _ = self.backend.delete_voice(&voice_info.name);
+                return Err(e.into());
+            }
+        };
+        let stored_audio = self.voice_manager.save_audio(&voice_info.name, &sample)?;
+
+        self.voice_manager.save_metadata(&VoiceMetadata {
+            name: voice_info.name.clone(),
+            transcript: RANDOM_VOICE_SAMPLE.to_string(),
+            model: voice_info.model.clone(),
+            created_at: Utc::now().to_rfc3339(),
+            audio_path: Some(stored_audio),
+            language: self.language.clone(),
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: true,
+            license: self.recorded_license(),
+        })?;
+        Ok(VoiceInfo {
+            transcript: RANDOM_VOICE_SAMPLE.to_string(),
+            ..voice_info
+        })
+    }
+
+    /// Install a voice from reference audio bytes, e.g. from a voice pack.
+    ///
+    /// The clip is kept in the voice store so it outlives the download, and
+    /// the pack's language, if given, overrides the engine's.
+    pub fn install_voice(
+        &self,
+        name: &str,
+        transcript: &str,
+        clip: &[u8],
+        language: Option<&str>,
+    ) -> Result<VoiceInfo, TTSError> {
+        let stored = self.voice_manager.save_audio(name, clip)?;
+        let reference = self.voice_manager.reference_audio(&stored)?;
+        let info = self.extract_voice(reference.path(), transcript, Some(name.to_string()))?;
+
+        if let Some(language) = language {
+            let mut metadata = self.voice_manager.load_metadata(&info.name)?;
+            metadata.language = Some(language.to_uppercase());
+            self.voice_manager.save_metadata(&metadata)?;
+        }
+
+        Ok(info)
+    }
+
+    /// Attach a consent attestation to a saved voice.
+    pub fn record_consent(&self, name: &str, consent: Consent) -> Result<(), TTSError> {
+        let mut metadata = self
+            .voice_manager
+            .load_metadata(name)
+            .map_err(|_| TTSError::VoiceNotFound(name.to_string()))?;
+        metadata.consent = Some(consent);
+        self.voice_manager.save_metadata(&metadata)?;
+        Ok(())
+    }
+
+    /// Record the license of a saved voice's reference clip, e.g.
+    /// `CC-BY-4.0`.
+    pub fn record_reference_license(&self, name: &str, license: &str) -> Result<(), TTSError> {
+        let mut metadata = self
+            .voice_manager
+            .load_metadata(name)
+            .map_err(|_| TTSError::VoiceNotFound(name.to_string()))?;
+        metadata
+            .license
+            .get_or_insert_with(Default::default)
+            .reference = Some(license.to_string());
+        self.voice_manager.save_metadata(&metadata)?;
+        Ok(())
+    }
+
+    /// Licenses known when a voice is created.
+    fn recorded_license(&self) -> Option<VoiceLicense> {
+        self.model_license.as_ref().map(|model| VoiceLicense {
+            model: Some(model.clone()),
+            reference: None,
+        })
+    }
+
+    /// Lock a saved voice, and limit the projects it may be used in; an
+    /// empty list allows any.
+    pub fn restrict_voice(
+        &self,
+        name: &str,
+        locked: bool,
+        allowed_uses: Vec<String>,
+    ) -> Result<(), TTSError> {
+        let mut metadata = self
+            .voice_manager
+            .load_metadata(name)
+            .map_err(|_| TTSError::VoiceNotFound(name.to_string()))?;
+        metadata.locked = locked;
+        metadata.allowed_uses = allowed_uses;
+        self.voice_manager.save_metadata(&metadata)?;
+        Ok(())
+    }
+
+    /// Refuse a locked voice unless unlocked, and a voice restricted to
+    /// other projects.
+    pub(super) fn check_access(&self, metadata: &VoiceMetadata) -> Result<(), TTSError> {
+        if metadata.locked && !self.unlocked {
+            return Err(TTSError::VoiceLocked(metadata.name.clone()));
+        }
+        let allowed = &metadata.allowed_uses;
+        let permitted = self.project.as_ref().is_some_and(|project| {
+            allowed
+                .iter()
+                .any(|use_| use_.eq_ignore_ascii_case(project))
+        });
+        if !allowed.is_empty() && !permitted {
+            return Err(TTSError::UseNotAllowed {
+                voice: metadata.name.clone(),
+                allowed: allowed.join(", "),
+                requested: match &self.project {
+                    Some(project) => format!("project '{project}'"),
+                    None => "work outside a project (see --project)".to_string(),
+                },
+            });
+        }
+        Ok(())
+    }
+
+    /// Score how closely a saved voice matches its reference recording.
+    ///
+    /// Synthesizes the voice's own transcript and compares the voiceprints
+    /// of the result and the reference WAV. Returns a similarity in
+    /// 0.0..=1.0, where higher means a closer match.
+    pub fn score_voice(&self, name: &str, reference: &Path) -> Result<f32, TTSError> {
+        let metadata = self
+            .voice_manager
+            .load_metadata(name)
+            .map_err(|_| TTSError::VoiceNotFound(name.to_string()))?;
+        let reference = std::fs::read(reference)
+            .map_err(|_| TTSError::AudioNotFound(reference.display().to_string()))?;
+
+        let synthesized = self.synthesize(&metadata.transcript, Some(name.to_string()), 1.0)?;
+        let expected = Voiceprint::from_buffer(&AudioBuffer::from_wav_bytes(&reference)?)?;
+        let actual = Voiceprint::from_buffer(&AudioBuffer::from_wav_bytes(&synthesized)?)?;
+
+        Ok(expected.similarity(&actual))
+    }
+
+    /// Get the speaker embedding of a saved voice.
+    ///
+    /// Uses the backend's own embedding when the model has one; otherwise
+    /// falls back to a voiceprint of the locally recorded reference audio.
+    pub fn speaker_embedding(&self, name: &str) -> Result<SpeakerEmbedding, TTSError> {
+        let unsupported = match self.backend.get_embedding(name) {
+            Ok(response) => {
+                return Ok(SpeakerEmbedding::from_base64(
+                    response.name,
+                    response.shape,
+                    &response.embedding,
+                )?);
+            }
+            Err(e @ BackendError::Unsupported(_)) => e,
+            Err(e) => return Err(e.into()),
+        };
+
+        let metadata = self
+            .voice_manager
+            .load_metadata(name)
+            .map_err(|_| TTSError::VoiceNotFound(name.to_string()))?;
+        let Some(audio_path) = metadata.audio_path else {
+            return Err(unsupported.into());
+        };
+        let wav = self
+            .voice_manager
+            .read_audio(&audio_path)
+            .map_err(|_| TTSError::AudioNotFound(audio_path.display().to_string()))?;
+        let print = Voiceprint::from_buffer(&AudioBuffer::from_wav_bytes(&wav)?)?;
+
+        Ok(SpeakerEmbedding {
+            name: name.to_string(),
+            source: EmbeddingSource::Voiceprint,
+            shape: vec![print.features.len()],
+            values: print.features,
+        })
+    }
+
+    /// List all available voices from the backend.
+    ///
+    /// For backends that keep no voices, these are the local voices with a
+    /// stored reference clip.
+    pub fn list_voices(&self) -> Result<Vec<VoiceInfo>, TTSError> {
+        if !self.capabilities().persistent_voices {
+            let mut voices: Vec<VoiceInfo> = self
+                .voice_manager
+                .list_local()?
+                .into_iter()
+                .filter(|meta| meta.audio_path.as_deref().is_some_and(Path::exists))
+                .map(|meta| VoiceInfo {
+                    name: meta.name,
+                    transcript: meta.transcript,
+                    model: meta.model,
+                    duration: None,
+                    raw: Default::default(),
+                })
+                .collect();
+            voices.sort_by(|a, b| a.name.cmp(&b.name));
+            return Ok(voices);
+        }
+
+        let response = self.backend.list_voices()?;
+        Ok(response.voices)
+    }
+
+    /// Delete a voice from the backend and move its local copy to the
+    /// trash (see [`VoiceManager::trash`](crate::voice::VoiceManager::trash)).
+    ///
+    /// For backends that keep no voices, only the local copy is moved.
+    pub fn delete_voice(&self, name: &str) -> Result<(), TTSError> {
+        if !self.capabilities().persistent_voices {
+            return self.voice_manager.trash(name).map_err(|e| match e {
+                VoiceError::NotFound(name) => TTSError::VoiceNotFound(name),
+                e => e.into(),
+            });
+        }
+
+        // Delete from backend
+        self.backend.delete_voice(name)?;
+
+        // Trash local metadata (ignore if not found locally)
+        match self.voice_manager.trash(name) {
+            Ok(()) | Err(VoiceError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Bring a deleted voice back from the trash.
+    ///
+    /// Backends that keep voices extract it again from its reference audio;
+    /// the local metadata, consent included, is kept as it was.
+    pub fn restore_voice(&self, name: &str) -> Result<VoiceMetadata, TTSError> {
+        let metadata = self.voice_manager.restore(name).map_err(|e| match e {
+            VoiceError::NotFound(name) => TTSError::VoiceNotFound(name),
+            e => e.into(),
+        })?;
+
+        self.register_voice(&metadata)?;
+        Ok(metadata)
+    }
+
+    /// Extract a saved voice on backends that keep voices, from its stored
+    /// reference. Other backends need nothing.
+    pub fn register_voice(&self, metadata: &VoiceMetadata) -> Result<(), TTSError> {
+        if self.capabilities().persistent_voices {
+            let Some(path) = metadata.audio_path.as_deref().filter(|p| p.exists()) else {
+                return Err(TTSError::ReferenceMissing(metadata.name.clone()));
+            };
+            let reference = self.voice_manager.reference_audio(path)?;
+            self.backend.extract_voice(
+                reference.path(),
+                &metadata.transcript,
+                Some(metadata.name.clone()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a cleaned copy of a reference clip to a private temporary file.
+fn clean_reference(path: &Path, cleanup: &Cleanup) -> Result<ReferenceAudio, TTSError> {
+    let audio = cleanup.apply(&decode_file(path)?);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("voice");
+    let temp = crate::scratch::scratch_file(&format!("{stem}-clean.wav"));
+    // Owned before it is written, so a failed write is cleaned up too
+    let reference = ReferenceAudio::Temporary(temp);
+    std::fs::write(reference.path(), audio.to_wav_bytes()?).map_err(AudioError::from)?;
+    Ok(reference)
+}
@@ -0,0 +1,422 @@
+//! Word manuscripts (`.docx`).
+//!
+//! A `.docx` file is a zip archive whose `word/document.xml` holds the
+//! paragraphs. Headings are recognized by their style (`Title`,
+//! `Heading 1`, ..., or any style with an outline level), which also works
+//! for documents written in a localized Word, whose style IDs are
+//! translated but whose built-in style names are not. Manuscripts that
+//! mark chapters only by typing "Chapter 3" on a line of its own have
+//! those lines taken as headings instead.
+//!
+//! [`Manuscript::to_text`] writes the result as the Markdown-style text
+//! the rest of the pipeline reads: blank lines between paragraphs and
+//! `#` before headings.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use roxmltree::{Document, Node};
+
+use super::IngestError;
+
+const WORDPROCESSING: [&str; 2] = [
+    "http://schemas.openxmlformats.org/wordprocessingml/2006/main",
+    "http://purl.oclc.org/ooxml/wordprocessingml/main",
+];
+const MARKUP_COMPATIBILITY: &str = "http://schemas.openxmlformats.org/markup-compatibility/2006";
+
+/// A line that reads like an unstyled chapter heading.
+static CHAPTER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(chapter|prologue|epilogue|interlude|part|book)\b").unwrap()
+});
+
+/// One paragraph of a manuscript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// A heading; level 0 is the document title, 1 a top-level heading.
+    Heading {
+        level: u8,
+        text: String,
+    },
+    Paragraph(String),
+}
+
+/// The paragraphs and headings of a Word document, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manuscript {
+    pub blocks: Vec<Block>,
+}
+
+impl Manuscript {
+    /// Read a `.docx` file.
+    pub fn read(path: &Path) -> Result<Self, IngestError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parse the bytes of a `.docx` file.
+    pub fn parse(docx: &[u8]) -> Result<Self, IngestError> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(docx))
+            .map_err(|e| IngestError::InvalidDocx(e.to_string()))?;
+        let document = read_part(&mut archive, "word/document.xml")?
+            .ok_or_else(|| IngestError::InvalidDocx("no word/document.xml".to_string()))?;
+        let styles = match read_part(&mut archive, "word/styles.xml")? {
+            Some(xml) => heading_styles(&xml)?,
+            None => HashMap::new(),
+        };
+
+        let xml =
+            Document::parse(&document).map_err(|e| IngestError::InvalidDocx(e.to_string()))?;
+        let body = xml
+            .descendants()
+            .find(|n| is(n, "body"))
+            .ok_or_else(|| IngestError::InvalidDocx("no document body".to_string()))?;
+
+        let mut blocks = Vec::new();
+        for paragraph in body.descendants().filter(|n| is(n, "p")) {
+            if in_fallback(&paragraph) || nearest_paragraph(&paragraph.parent()).is_some() {
+                continue;
+            }
+            let text = paragraph_text(&paragraph);
+            // Scene breaks such as "#" or "* * *" only separate paragraphs
+            if !text.chars().any(char::is_alphanumeric) {
+                continue;
+            }
+            blocks.push(match heading_level(&paragraph, &styles) {
+                Some(level) => Block::Heading { level, text },
+                None => Block::Paragraph(text),
+            });
+        }
+
+        let styled = blocks.iter().any(|b| matches!(b, Block::Heading { .. }));
+        if !styled {
+            for block in &mut blocks {
+                if let Block::Paragraph(text) = block
+                    && looks_like_chapter(text)
+                {
+                    *block = Block::Heading {
+                        level: 1,
+                        text: std::mem::take(text),
+                    };
+                }
+            }
+        }
+        Ok(Self { blocks })
+    }
+
+    /// The chapters: the manuscript split at its chapter headings, each
+    /// starting with its heading. Chapter headings are those of the
+    /// highest level used more than once, so a book title or a single
+    /// "Part One" above the chapters does not count as one. Text before
+    /// the first chapter, such as a title page, belongs to none.
+    pub fn chapters(&self) -> Vec<Manuscript> {
+        let mut counts: HashMap<u8, usize> = HashMap::new();
+        for block in &self.blocks {
+            if let Block::Heading { level, .. } = block {
+                *counts.entry(*level).or_default() += 1;
+            }
+        }
+        let level = counts
+            .iter()
+            .filter(|(_, count)| **count > 1)
+            .map(|(level, _)| *level)
+            .min()
+            .or_else(|| counts.keys().copied().min());
+        let Some(level) = level else {
+            return Vec::new();
+        };
+
+        let mut chapters: Vec<Manuscript> = Vec::new();
+        for block in &self.blocks {
+            match block {
+                Block::Heading { level: l, .. } if *l == level => chapters.push(Manuscript {
+                    blocks: vec![block.clone()],
+                }),
+                // Headings above the chapter level start something else
+                Block::Heading { level: l, .. } if *l < level => {}
+                _ => {
+                    if let Some(chapter) = chapters.last_mut() {
+                        chapter.blocks.push(block.clone());
+                    }
+                }
+            }
+        }
+        chapters
+    }
+
+    /// The text, with a blank line between paragraphs and headings
+    /// written as Markdown headings.
+    pub fn to_text(&self) -> String {
+        let blocks: Vec<String> = self
+            .blocks
+            .iter()
+            .map(|block| match block {
+                Block::Heading { level, text } => {
+                    format!("{} {text}", "#".repeat(usize::from((*level).max(1))))
+                }
+                Block::Paragraph(text) => text.clone(),
+            })
+            .collect();
+        blocks.join("\n\n")
+    }
+}
+
+fn read_part(
+    archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<Option<String>, IngestError> {
+    let mut part = match archive.by_name(name) {
+        Ok(part) => part,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(IngestError::InvalidDocx(e.to_string())),
+    };
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)?;
+    Ok(Some(xml))
+}
+
+fn is(node: &Node, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().name() == name
+        && node
+            .tag_name()
+            .namespace()
+            .is_some_and(|ns| WORDPROCESSING.contains(&ns))
+}
+
+/// A `w:` attribute of `node`.
+fn attribute<'a>(node: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name() == name && a.namespace().is_some_and(|ns| WORDPROCESSING.contains(&ns)))
+        .map(|a| a.value())
+}
+
+fn child<'a, 'input>(node: &Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| is(n, name))
+}
+
+/// Whether `node` is in the fallback of an `mc:AlternateContent`, which
+/// repeats its content for older readers.
+fn in_fallback(node: &Node) -> bool {
+    node.ancestors().any(|n| {
+        n.tag_name().name() == "Fallback" && n.tag_name().namespace() == Some(MARKUP_COMPATIBILITY)
+    })
+}
+
+fn nearest_paragraph<'a, 'input>(node: &Option<Node<'a, 'input>>) -> Option<Node<'a, 'input>> {
+    node.and_then(|n| n.ancestors().find(|a| is(a, "p")))
+}
+
+/// The visible text of a paragraph, with whitespace collapsed. Text boxes
+/// anchored in it are paragraphs of their own, and deleted revisions and
+/// field codes are separate elements, so neither is included.
+fn paragraph_text(paragraph: &Node) -> String {
+    let mut text = String::new();
+    for node in paragraph.descendants() {
+        if nearest_paragraph(&Some(node)) != Some(*paragraph) || in_fallback(&node) {
+            continue;
+        }
+        if node
+            .ancestors()
+            .find(|a| is(a, "r"))
+            .is_some_and(|run| is_hidden(&run))
+        {
+            continue;
+        }
+        match node.tag_name().name() {
+            _ if !node.is_element() => {}
+            "t" if is(&node, "t") => text.push_str(node.text().unwrap_or_default()),
+            "tab" | "br" | "cr" if is(&node, node.tag_name().name()) => text.push(' '),
+            "noBreakHyphen" if is(&node, "noBreakHyphen") => text.push('-'),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Whether a run is formatted as hidden text.
+fn is_hidden(run: &Node) -> bool {
+    child(run, "rPr")
+        .and_then(|properties| child(&properties, "vanish"))
+        .is_some_and(|vanish| !matches!(attribute(&vanish, "val"), Some("0" | "false" | "off")))
+}
+
+/// Heading level of a paragraph: its own outline level, else its style's.
+fn heading_level(paragraph: &Node, styles: &HashMap<String, u8>) -> Option<u8> {
+    let properties = child(paragraph, "pPr")?;
+    if let Some(level) = child(&properties, "outlineLvl").and_then(|l| outline_level(&l)) {
+        return Some(level);
+    }
+    let style = child(&properties, "pStyle").and_then(|s| attribute(&s, "val"))?;
+    styles.get(style).copied()
+}
+
+/// `w:outlineLvl` 0 is a top-level heading; 9 means body text.
+fn outline_level(node: &Node) -> Option<u8> {
+    attribute(node, "val")?
+        .parse::<u8>()
+        .ok()
+        .filter(|level| *level < 9)
+        .map(|level| level + 1)
+}
+
+/// Heading level of each paragraph style that is a heading, by style ID.
+fn heading_styles(xml: &str) -> Result<HashMap<String, u8>, IngestError> {
+    let document = Document::parse(xml).map_err(|e| IngestError::InvalidDocx(e.to_string()))?;
+    // Style ID -> (own level, style it is based on)
+    let mut styles: HashMap<&str, (Option<u8>, Option<&str>)> = HashMap::new();
+    for style in document.descendants().filter(|n| is(n, "style")) {
+        if attribute(&style, "type") != Some("paragraph") {
+            continue;
+        }
+        let Some(id) = attribute(&style, "styleId") else {
+            continue;
+        };
+        let name = child(&style, "name")
+            .and_then(|n| attribute(&n, "val"))
+            .unwrap_or(id)
+            .to_lowercase();
+        let level = if name == "title" {
+            Some(0)
+        } else if let Some(n) = name.strip_prefix("heading ") {
+            n.parse().ok()
+        } else {
+            child(&style, "pPr")
+                .and_then(|p| child(&p, "outlineLvl"))
+                .and_then(|l| outline_level(&l))
+        };
+        let based_on = child(&style, "basedOn").and_then(|b| attribute(&b, "val"));
+        styles.insert(id, (level, based_on));
+    }
+
+    let mut levels = HashMap::new();
+    for &id in styles.keys() {
+        // Follow basedOn, guarding against cycles
+        let mut current = Some(id);
+        for _ in 0..styles.len() {
+            let Some((level, based_on)) = current.and_then(|c| styles.get(c)) else {
+                break;
+            };
+            if let Some(level) = level {
+                levels.insert(id.to_string(), *level);
+                break;
+            }
+            current = *based_on;
+        }
+    }
+    Ok(levels)
+}
+
+/// Whether an unstyled paragraph is a chapter heading typed as text:
+/// short, starting like one, and not a sentence.
+fn looks_like_chapter(text: &str) -> bool {
+    text.chars().count() <= 60
+        && text.split_whitespace().count() <= 8
+        && CHAPTER.is_match(text)
+        && !text.ends_with(['.', ',', ';', '?', '!'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const W: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006""#;
+
+    /// A `.docx` with `body` as the document body and `styles` as the
+    /// style definitions.
+    fn docx(body: &str, styles: Option<&str>) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("word/document.xml", options).unwrap();
+        write!(
+            zip,
+            r#"<w:document {W}><w:body>{body}</w:body></w:document>"#
+        )
+        .unwrap();
+        if let Some(styles) = styles {
+            zip.start_file("word/styles.xml", options).unwrap();
+            write!(zip, r#"<w:styles {W}>{styles}</w:styles>"#).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn p(style: &str, text: &str) -> String {
+        format!(
+            r#"<w:p><w:pPr><w:pStyle w:val="{style}"/></w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>"#
+        )
+    }
+
+    // German Word: translated style IDs, English built-in names
+    const STYLES: &str = r#"
+        <w:style w:type="paragraph" w:styleId="Titel"><w:name w:val="Title"/></w:style>
+        <w:style w:type="paragraph" w:styleId="berschrift1"><w:name w:val="heading 1"/></w:style>
+        <w:style w:type="paragraph" w:styleId="Kapitel"><w:name w:val="Kapitel"/><w:basedOn w:val="berschrift1"/></w:style>
+        <w:style w:type="paragraph" w:styleId="Standard"><w:name w:val="Normal"/></w:style>"#;
+
+    #[test]
+    fn test_manuscript_headings_and_paragraphs() {
+        let body = [
+            p("Titel", "Moby-Dick"),
+            p("berschrift1", "Loomings"),
+            r#"<w:p><w:r><w:t xml:space="preserve">Call me </w:t></w:r><w:r><w:rPr><w:i/></w:rPr><w:t>Ishmael</w:t></w:r><w:r><w:t>.</w:t></w:r><w:r><w:rPr><w:vanish/></w:rPr><w:t>editor's note</w:t></w:r><w:del><w:r><w:delText>Cut.</w:delText></w:r></w:del></w:p>"#.to_string(),
+            p("Standard", "* * *"),
+            r#"<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Some</w:t><w:tab/><w:t>years ago</w:t></w:r></w:p></w:tc></w:tr></w:tbl>"#.to_string(),
+            p("Kapitel", "The Carpet-Bag"),
+            p("Standard", "I stuffed a shirt or two."),
+        ]
+        .concat();
+        let manuscript = Manuscript::parse(&docx(&body, Some(STYLES))).unwrap();
+
+        assert_eq!(
+            manuscript.to_text(),
+            "# Moby-Dick\n\n# Loomings\n\nCall me Ishmael.\n\nSome years ago\n\n\
+             # The Carpet-Bag\n\nI stuffed a shirt or two."
+        );
+
+        let chapters = manuscript.chapters();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(
+            chapters[0].to_text(),
+            "# Loomings\n\nCall me Ishmael.\n\nSome years ago"
+        );
+        assert_eq!(
+            chapters[1].blocks[0],
+            Block::Heading {
+                level: 1,
+                text: "The Carpet-Bag".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_manuscript_detects_typed_chapter_headings() {
+        let body = [
+            p("Normal", "A Novel"),
+            p("Normal", "CHAPTER ONE"),
+            p("Normal", "Chapter one was the hardest to write."),
+            p("Normal", "Chapter 2: The Storm"),
+            p("Normal", "Rain."),
+        ]
+        .concat();
+        let manuscript = Manuscript::parse(&docx(&body, None)).unwrap();
+
+        let chapters = manuscript.chapters();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(
+            chapters[0].to_text(),
+            "# CHAPTER ONE\n\nChapter one was the hardest to write."
+        );
+        assert_eq!(chapters[1].to_text(), "# Chapter 2: The Storm\n\nRain.");
+    }
+
+    #[test]
+    fn test_manuscript_rejects_other_files() {
+        assert!(matches!(
+            Manuscript::parse(b"plain text"),
+            Err(IngestError::InvalidDocx(_))
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+//! Character encodings of input text.
+//!
+//! Manuscripts arrive as UTF-8 with or without a byte order mark, as
+//! UTF-16 saved by Windows editors, or in a legacy 8-bit encoding. Each is
+//! decoded to text before synthesis, so none is read as mojibake.
+
+use std::path::Path;
+
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+
+use super::IngestError;
+
+/// The encoding `bytes` are most likely in: the one its byte order mark
+/// names, then UTF-16 without a byte order mark if every other byte is
+/// mostly NUL (which UTF-8 text never is, though it would be valid), then
+/// UTF-8 if they are valid UTF-8, and otherwise Windows-1252, the
+/// superset of Latin-1 that legacy Western text is in.
+pub fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if let Some(encoding) = utf16_without_bom(bytes) {
+        return encoding;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+/// UTF-16 of mostly Latin text has a NUL as the high byte of most code
+/// units and rarely as the low byte.
+fn utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = bytes.len() / 2;
+    let nul_even = bytes.iter().step_by(2).filter(|&&b| b == 0).count();
+    let nul_odd = bytes.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    match (nul_even, nul_odd) {
+        (even, odd) if odd > units / 2 && even <= units / 20 => Some(UTF_16LE),
+        (even, odd) if even > units / 2 && odd <= units / 20 => Some(UTF_16BE),
+        _ => None,
+    }
+}
+
+/// Decode `bytes` in the encoding [`detect_encoding`] finds, dropping any
+/// byte order mark. Returns the text and the encoding it was in.
+pub fn decode_text(bytes: &[u8]) -> (String, &'static Encoding) {
+    let encoding = detect_encoding(bytes);
+    let (text, _) = encoding.decode_with_bom_removal(bytes);
+    (text.into_owned(), encoding)
+}
+
+/// Read a text file in whatever encoding it is in.
+pub fn read_decoded(path: &Path) -> Result<String, IngestError> {
+    Ok(decode_text(&std::fs::read(path)?).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+        let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        units
+            .flat_map(|u| match big_endian {
+                true => u.to_be_bytes(),
+                false => u.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_utf8_with_and_without_bom() {
+        assert_eq!(
+            decode_text("Café au lait.".as_bytes()),
+            ("Café au lait.".to_string(), UTF_8)
+        );
+        let (text, encoding) = decode_text(b"\xEF\xBB\xBF---\ntitle: Caf\xC3\xA9\n---\n");
+        assert_eq!(text, "---\ntitle: Café\n---\n");
+        assert_eq!(encoding, UTF_8);
+    }
+
+    #[test]
+    fn test_utf16_with_bom() {
+        let text = "Chapter 1\n\u{201C}Où est-il?\u{201D}\n";
+        assert_eq!(
+            decode_text(&utf16(text, false, true)),
+            (text.to_string(), UTF_16LE)
+        );
+        assert_eq!(
+            decode_text(&utf16(text, true, true)),
+            (text.to_string(), UTF_16BE)
+        );
+    }
+
+    #[test]
+    fn test_utf16_without_bom() {
+        let text = "It was a bright cold day in April, and the clocks were striking thirteen.";
+        assert_eq!(
+            decode_text(&utf16(text, false, false)),
+            (text.to_string(), UTF_16LE)
+        );
+        assert_eq!(
+            decode_text(&utf16(text, true, false)),
+            (text.to_string(), UTF_16BE)
+        );
+    }
+
+    #[test]
+    fn test_latin1_fallback() {
+        // "Ça coûte 5 €" in Windows-1252
+        let (text, encoding) = decode_text(b"\xC7a co\xFBte 5 \x80");
+        assert_eq!(text, "Ça coûte 5 €");
+        assert_eq!(encoding, WINDOWS_1252);
+    }
+
+    #[test]
+    fn test_read_decoded() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("book.txt");
+        std::fs::write(&path, utf16("Hello.", false, true)).unwrap();
+        assert_eq!(read_decoded(&path).unwrap(), "Hello.");
+        assert!(read_decoded(&dir.path().join("missing.txt")).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Text input from outside sources.
+//!
+//! [`read_text`] reads an input file, taking the paragraphs and headings
+//! out of a Word manuscript ([`docx`]) and the speeches out of a Fountain
+//! screenplay ([`Screenplay`]). With the `web` feature, [`web`]
+//! fetches a page and extracts its article, leaving out navigation,
+//! sidebars, and comments, so it can be read aloud. Plain text and
+//! screenplays may be in any common encoding; [`encoding`] decodes them.
+
+pub mod docx;
+pub mod encoding;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use docx::{Block, Manuscript};
+pub use encoding::{decode_text, detect_encoding, read_decoded};
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::text::{Screenplay, ScreenplayOptions};
+
+/// Errors that can occur when ingesting text.
+#[derive(Error, Debug)]
+pub enum IngestError {
+    #[error("Failed to fetch {url}: {message}")]
+    Fetch { url: String, message: String },
+
+    #[error("No article text found at {0}")]
+    NoArticle(String),
+
+    #[error("Invalid Word document: {0}")]
+    InvalidDocx(String),
+
+    #[error("No chapter {chapter}; found {found} chapter headings")]
+    NoChapter { chapter: usize, found: usize },
+
+    #[error("Chapters can only be taken from a .docx manuscript")]
+    NotAManuscript,
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Whether `path` names a Word document.
+pub fn is_docx(path: &Path) -> bool {
+    has_extension(path, "docx")
+}
+
+/// Whether `path` names a Fountain screenplay.
+pub fn is_fountain(path: &Path) -> bool {
+    has_extension(path, "fountain")
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// The text of an input file: a Word manuscript as Markdown-style text
+/// (see [`Manuscript::to_text`]), a screenplay as tagged dialogue with
+/// everything read (see [`Screenplay::to_text`]), anything else as it is.
+/// Text files are decoded from whatever encoding they are in.
+pub fn read_text(path: &Path) -> Result<String, IngestError> {
+    if is_docx(path) {
+        Ok(Manuscript::read(path)?.to_text())
+    } else if is_fountain(path) {
+        let script = read_decoded(path)?;
+        Ok(Screenplay::parse(&script).to_text(&ScreenplayOptions::default()))
+    } else {
+        read_decoded(path)
+    }
+}
+
+/// The text of chapter `chapter` (counting from 1) of a Word manuscript.
+pub fn read_chapter(path: &Path, chapter: usize) -> Result<String, IngestError> {
+    if !is_docx(path) {
+        return Err(IngestError::NotAManuscript);
+    }
+    let chapters = Manuscript::read(path)?.chapters();
+    chapter
+        .checked_sub(1)
+        .and_then(|i| chapters.get(i))
+        .map(Manuscript::to_text)
+        .ok_or(IngestError::NoChapter {
+            chapter,
+            found: chapters.len(),
+        })
+}
@@ -0,0 +1,384 @@
+//! Reading the article of a web page.
+//!
+//! Extraction follows the readability approach: paragraphs score the
+//! elements that contain them by their length and commas, containers whose
+//! class or id marks them as navigation, comments, or ads are skipped, and
+//! the best-scoring container, less its link-heavy parts, is the article.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use ego_tree::NodeId;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use super::IngestError;
+
+/// Longest wait for a page.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Paragraphs shorter than this, in characters, do not score.
+const MIN_PARAGRAPH: usize = 25;
+
+/// Siblings of the best container scoring at least this fraction of it are
+/// part of the article too.
+const SIBLING_SHARE: f64 = 0.2;
+
+/// Blocks more than this fraction link text are menus, not prose.
+const MAX_LINK_DENSITY: f64 = 0.5;
+
+/// Elements never part of an article.
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "nav", "header", "footer", "aside", "form", "button", "svg",
+    "iframe", "template",
+];
+
+/// Blocks read as paragraphs.
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "blockquote",
+    "pre",
+    "figcaption",
+];
+
+/// Class or id words of containers that are not the article.
+static UNLIKELY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)banner|breadcrumb|comment|community|cookie|disqus|footer|menu|modal|nav|newsletter|popup|promo|related|share|sidebar|social|sponsor|subscribe|\bads?\b|advert",
+    )
+    .unwrap()
+});
+
+/// Class or id words of containers that likely are.
+static LIKELY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)article|body|content|entry|main|post|story|text").unwrap());
+
+/// The readable part of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Article {
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    /// Paragraphs separated by blank lines.
+    pub text: String,
+}
+
+impl Article {
+    /// The title, byline, and text, ready to be read aloud.
+    pub fn to_speech(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(title) = &self.title {
+            parts.push(end_sentence(title));
+        }
+        if let Some(byline) = &self.byline {
+            parts.push(end_sentence(&format!("By {byline}")));
+        }
+        parts.push(self.text.clone());
+        parts.join("\n\n")
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.text.split_whitespace().count()
+    }
+}
+
+/// Fetch `url` and extract its article.
+pub fn fetch_article(url: &str) -> Result<Article, IngestError> {
+    let failed = |message: String| IngestError::Fetch {
+        url: url.to_string(),
+        message,
+    };
+    let client = reqwest::blocking::Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent(concat!("open-tts-rs/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| failed(e.to_string()))?;
+    let response = client.get(url).send().map_err(|e| failed(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(failed(format!("status {}", response.status())));
+    }
+    if let Some(kind) = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        && !kind.contains("html")
+    {
+        return Err(failed(format!("not a web page ({kind})")));
+    }
+    let html = response.text().map_err(|e| failed(e.to_string()))?;
+
+    extract_article(&html).ok_or_else(|| IngestError::NoArticle(url.to_string()))
+}
+
+/// Extract the article of an HTML page, or `None` if it has no prose.
+pub fn extract_article(html: &str) -> Option<Article> {
+    let document = Html::parse_document(html);
+    let scores = score_containers(document.root_element());
+
+    let mut blocks: Vec<String> = Vec::new();
+    for part in article_parts(&document, &scores)? {
+        collect_blocks(part, &mut blocks);
+    }
+    if blocks.is_empty() {
+        return None;
+    }
+
+    Some(Article {
+        title: title(&document),
+        byline: meta(&document, r#"meta[name="author"]"#),
+        text: blocks.join("\n\n"),
+    })
+}
+
+/// Score the elements holding paragraphs under `root` by how much prose
+/// they hold.
+fn score_containers(root: ElementRef) -> HashMap<NodeId, f64> {
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+    for paragraph in root
+        .descendent_elements()
+        .filter(|e| matches!(e.value().name(), "p" | "pre" | "td"))
+        .filter(|e| !is_skipped(e))
+    {
+        let text = normalize(&paragraph.text().collect::<String>());
+        let length = text.chars().count();
+        if length < MIN_PARAGRAPH {
+            continue;
+        }
+        let score = 1.0 + text.matches(',').count() as f64 + (length as f64 / 100.0).min(3.0);
+
+        // The container gets the full score, its parent half
+        let containers = paragraph.ancestors().filter_map(ElementRef::wrap).take(2);
+        for (level, container) in containers.enumerate() {
+            let share = if level == 0 { 1.0 } else { 0.5 };
+            *scores
+                .entry(container.id())
+                .or_insert_with(|| class_weight(&container)) += score * share;
+        }
+    }
+    scores
+}
+
+/// The best-scoring container, less its links, with the siblings that
+/// score close to it, in document order.
+fn article_parts<'a>(
+    document: &'a Html,
+    scores: &HashMap<NodeId, f64>,
+) -> Option<Vec<ElementRef<'a>>> {
+    let element = |id: NodeId| document.tree.get(id).and_then(ElementRef::wrap);
+    let adjusted =
+        |id: NodeId, score: f64| element(id).map_or(0.0, |e| score * (1.0 - link_density(&e)));
+    let (best, best_score) = scores
+        .iter()
+        .map(|(&id, &score)| (id, adjusted(id, score)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let best = element(best)?;
+
+    // Paragraphs split across sibling containers are kept together
+    let Some(parent) = best.parent().and_then(ElementRef::wrap) else {
+        return Some(vec![best]);
+    };
+    let parts = parent
+        .child_elements()
+        .filter(|sibling| {
+            sibling.id() == best.id()
+                || scores
+                    .get(&sibling.id())
+                    .is_some_and(|&s| adjusted(sibling.id(), s) >= best_score * SIBLING_SHARE)
+        })
+        .collect();
+    Some(parts)
+}
+
+/// Text of the readable blocks under `element`, in order. An element with
+/// no block inside it is a block itself.
+fn collect_blocks(element: ElementRef, blocks: &mut Vec<String>) {
+    if is_skipped(&element) || link_density(&element) > MAX_LINK_DENSITY {
+        return;
+    }
+    let has_blocks = element
+        .descendent_elements()
+        .skip(1)
+        .any(|e| BLOCK_TAGS.contains(&e.value().name()));
+    if !has_blocks {
+        let text = normalize(&element.text().collect::<String>());
+        if !text.is_empty() {
+            blocks.push(text);
+        }
+        return;
+    }
+    for child in element.child_elements() {
+        collect_blocks(child, blocks);
+    }
+}
+
+/// Whether `element` is, or is inside, something that is not article.
+/// Classes on the page itself, like `sidebar-open`, describe its state.
+fn is_skipped(element: &ElementRef) -> bool {
+    std::iter::once(*element)
+        .chain(element.ancestors().filter_map(ElementRef::wrap))
+        .any(|e| match e.value().name() {
+            "html" | "body" => false,
+            name if SKIPPED_TAGS.contains(&name) => true,
+            _ => {
+                let names = class_and_id(&e);
+                UNLIKELY.is_match(&names) && !LIKELY.is_match(&names)
+            }
+        })
+}
+
+/// Starting score of a container from its tag, class, and id.
+fn class_weight(element: &ElementRef) -> f64 {
+    let names = class_and_id(element);
+    let mut weight = match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" | "section" => 5.0,
+        "ol" | "ul" | "li" | "form" => -3.0,
+        _ => 0.0,
+    };
+    if LIKELY.is_match(&names) {
+        weight += 25.0;
+    }
+    if UNLIKELY.is_match(&names) {
+        weight -= 25.0;
+    }
+    weight
+}
+
+fn class_and_id(element: &ElementRef) -> String {
+    let value = element.value();
+    format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.id().unwrap_or_default()
+    )
+}
+
+/// Fraction of the text of `element` that is link text.
+fn link_density(element: &ElementRef) -> f64 {
+    let total: usize = element.text().map(|t| t.trim().len()).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let links: usize = element
+        .descendent_elements()
+        .filter(|e| e.value().name() == "a")
+        .flat_map(|a| a.text())
+        .map(|t| t.trim().len())
+        .sum();
+    links as f64 / total as f64
+}
+
+/// The page title, preferring the Open Graph one, which leaves out the
+/// site name.
+fn title(document: &Html) -> Option<String> {
+    meta(document, r#"meta[property="og:title"]"#).or_else(|| {
+        ["title", "h1"].iter().find_map(|tag| {
+            let selector = Selector::parse(tag).ok()?;
+            let text = normalize(
+                &document
+                    .select(&selector)
+                    .next()?
+                    .text()
+                    .collect::<String>(),
+            );
+            (!text.is_empty()).then_some(text)
+        })
+    })
+}
+
+fn meta(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    let content = normalize(document.select(&selector).next()?.attr("content")?);
+    (!content.is_empty()).then_some(content)
+}
+
+/// Collapse runs of whitespace, including line breaks, to single spaces.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Headings and bylines have no final punctuation; add it so they are read
+/// as sentences of their own.
+fn end_sentence(text: &str) -> String {
+    if text.ends_with(['.', '!', '?', ':']) {
+        text.to_string()
+    } else {
+        format!("{text}.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Why Rivers Bend | Example Blog</title>
+  <meta property="og:title" content="Why Rivers Bend">
+  <meta name="author" content="Ada Example">
+</head>
+<body>
+  <nav><ul><li><a href="/">Home</a></li><li><a href="/about">About</a></li></ul></nav>
+  <div class="sidebar"><p>Subscribe to our newsletter for weekly updates, tips, and more.</p></div>
+  <div id="main-content">
+    <article class="post">
+      <h2>Meanders</h2>
+      <p>Rivers rarely run straight. Water on the outside of a bend moves faster,
+         carving the bank away, while slower water on the inside drops its sand.</p>
+      <p>Over centuries, this slow exchange pushes each loop wider, until the river
+         cuts through the neck and leaves an oxbow lake behind.</p>
+      <div class="share-links"><a href="/x">Share on X</a> <a href="/fb">Share</a></div>
+    </article>
+  </div>
+  <section class="comments">
+    <p>Great post, thanks for sharing this with everyone, really interesting!</p>
+  </section>
+  <footer><p>Copyright 2025 Example Blog. All rights reserved, everywhere.</p></footer>
+  <script>var tracking = "Rivers rarely run straight, but scripts do, always.";</script>
+</body>
+</html>"#;
+
+    #[test]
+    fn test_extract_article_keeps_prose_only() {
+        let article = extract_article(PAGE).unwrap();
+        assert_eq!(article.title.as_deref(), Some("Why Rivers Bend"));
+        assert_eq!(article.byline.as_deref(), Some("Ada Example"));
+
+        let paragraphs: Vec<&str> = article.text.split("\n\n").collect();
+        assert_eq!(paragraphs.len(), 3, "{}", article.text);
+        assert_eq!(paragraphs[0], "Meanders");
+        assert!(paragraphs[1].starts_with("Rivers rarely run straight. Water on the outside"));
+        assert!(paragraphs[2].ends_with("leaves an oxbow lake behind."));
+        for absent in ["newsletter", "Great post", "Copyright", "Share", "tracking"] {
+            assert!(!article.text.contains(absent), "{absent}");
+        }
+    }
+
+    #[test]
+    fn test_article_reads_title_and_byline_first() {
+        let article = extract_article(PAGE).unwrap();
+        let speech = article.to_speech();
+        assert!(speech.starts_with("Why Rivers Bend.\n\nBy Ada Example.\n\nMeanders\n\n"));
+        assert_eq!(article.word_count(), 49);
+    }
+
+    #[test]
+    fn test_page_classes_do_not_hide_the_article() {
+        let html = PAGE.replace("<body>", r#"<body class="nav-collapsed sidebar-open">"#);
+        assert_eq!(extract_article(&html), extract_article(PAGE));
+    }
+
+    #[test]
+    fn test_extract_article_without_prose() {
+        let html = "<html><body><nav><a href='/'>Home</a></nav><p>Hi.</p></body></html>";
+        assert_eq!(extract_article(html), None);
+    }
+}
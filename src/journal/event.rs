@@ -0,0 +1,222 @@
+//! Journal events.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Something that happened during an invocation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The invocation began, with its command-line arguments.
+    Invoked {
+        args: Vec<String>,
+    },
+    /// A new batch job was created.
+    JobStarted {
+        job: String,
+        output: PathBuf,
+        chunks: usize,
+        model: String,
+    },
+    /// An interrupted batch job was continued.
+    JobResumed {
+        job: String,
+        done: usize,
+        chunks: usize,
+        model: String,
+    },
+    /// A chunk of a finished job was reopened to be synthesized again.
+    ChunkReopened {
+        job: String,
+        chunk: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+    },
+    ChunkDone {
+        job: String,
+        chunk: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+    },
+    /// A synthesis attempt failed and will be tried again.
+    ChunkRetrying {
+        job: String,
+        chunk: usize,
+        error: String,
+    },
+    /// A chunk's audio was anomalous and was synthesized again.
+    ChunkResynthesized {
+        job: String,
+        chunk: usize,
+        anomaly: String,
+        attempts: usize,
+        resolved: bool,
+    },
+    /// A chunk failed and was replaced with silence.
+    ChunkSkipped {
+        job: String,
+        chunk: usize,
+        error: String,
+    },
+    ChunkFailed {
+        job: String,
+        chunk: usize,
+        error: String,
+    },
+    /// The disk was full or nearly so, and the job waited for space.
+    DiskFull {
+        job: String,
+        path: PathBuf,
+        free: u64,
+    },
+    OutputWritten {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        job: Option<String>,
+        file: PathBuf,
+    },
+    JobStopped {
+        job: String,
+        error: String,
+    },
+    JobFinished {
+        job: String,
+    },
+}
+
+impl Event {
+    /// The batch job the event is about, if any.
+    pub fn job(&self) -> Option<&str> {
+        match self {
+            Self::Invoked { .. } => None,
+            Self::OutputWritten { job, .. } => job.as_deref(),
+            Self::JobStarted { job, .. }
+            | Self::JobResumed { job, .. }
+            | Self::ChunkReopened { job, .. }
+            | Self::ChunkDone { job, .. }
+            | Self::ChunkRetrying { job, .. }
+            | Self::ChunkResynthesized { job, .. }
+            | Self::ChunkSkipped { job, .. }
+            | Self::ChunkFailed { job, .. }
+            | Self::DiskFull { job, .. }
+            | Self::JobStopped { job, .. }
+            | Self::JobFinished { job } => Some(job),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invoked { args } => write!(f, "open-tts-rs {}", args.join(" ")),
+            Self::JobStarted {
+                job,
+                output,
+                chunks,
+                model,
+            } => job_started(f, job, output, *chunks, model),
+            Self::JobResumed {
+                job,
+                done,
+                chunks,
+                model,
+            } => write!(
+                f,
+                "job {job} resumed with {model}: {done}/{chunks} chunks done"
+            ),
+            Self::ChunkReopened { job, chunk, seed } => chunk_reopened(f, job, *chunk, *seed),
+            Self::ChunkDone {
+                chunk, request_id, ..
+            } => chunk_done(f, *chunk, request_id.as_deref()),
+            Self::ChunkRetrying { chunk, error, .. } => {
+                write!(f, "chunk {chunk} failed, retrying: {error}")
+            }
+            Self::ChunkResynthesized {
+                chunk,
+                anomaly,
+                attempts,
+                resolved,
+                ..
+            } => chunk_resynthesized(f, *chunk, anomaly, *attempts, *resolved),
+            Self::ChunkSkipped { chunk, error, .. } => {
+                write!(f, "chunk {chunk} replaced with silence: {error}")
+            }
+            Self::ChunkFailed { chunk, error, .. } => write!(f, "chunk {chunk} failed: {error}"),
+            Self::DiskFull { path, free, .. } => disk_full(f, path, *free),
+            Self::OutputWritten { file, .. } => write!(f, "wrote {}", file.display()),
+            Self::JobStopped { job, error } => write!(f, "job {job} stopped: {error}"),
+            Self::JobFinished { job } => write!(f, "job {job} finished"),
+        }
+    }
+}
+
+fn job_started(
+    f: &mut fmt::Formatter<'_>,
+    job: &str,
+    output: &Path,
+    chunks: usize,
+    model: &str,
+) -> fmt::Result {
+    write!(
+        f,
+        "job {job} started with {model}: {chunks} chunks to {}",
+        output.display()
+    )
+}
+
+fn chunk_reopened(
+    f: &mut fmt::Formatter<'_>,
+    job: &str,
+    chunk: usize,
+    seed: Option<u64>,
+) -> fmt::Result {
+    write!(f, "chunk {chunk} of job {job} reopened")?;
+    match seed {
+        Some(seed) => write!(f, " (seed {seed})"),
+        None => Ok(()),
+    }
+}
+
+fn chunk_done(f: &mut fmt::Formatter<'_>, chunk: usize, request_id: Option<&str>) -> fmt::Result {
+    write!(f, "chunk {chunk} done")?;
+    match request_id {
+        Some(id) => write!(f, " (request {id})"),
+        None => Ok(()),
+    }
+}
+
+fn chunk_resynthesized(
+    f: &mut fmt::Formatter<'_>,
+    chunk: usize,
+    anomaly: &str,
+    attempts: usize,
+    resolved: bool,
+) -> fmt::Result {
+    let outcome = match resolved {
+        true => "fixed",
+        false => "still anomalous",
+    };
+    write!(
+        f,
+        "chunk {chunk} re-synthesized: {anomaly} ({outcome} after {attempts} attempts)"
+    )
+}
+
+fn disk_full(f: &mut fmt::Formatter<'_>, path: &Path, free: u64) -> fmt::Result {
+    write!(
+        f,
+        "waited for disk space in {} ({} free)",
+        path.display(),
+        crate::batch::format_size(free)
+    )
+}
+
+/// One line of a journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
@@ -0,0 +1,162 @@
+//! Per-invocation event journals.
+//!
+//! Each synthesis run of the CLI appends what happens to a [`Journal`] of
+//! its own: the arguments it was given, the jobs it started or resumed,
+//! each chunk finished, retried, or skipped, and the files it wrote. The
+//! journals live in `~/.open-tts-rs/logs/` ([`Journals`]), so a run that
+//! failed overnight can be read back with `open-tts-rs logs show`, and
+//! resuming or re-rendering a job consults its [`JobHistory`].
+
+mod event;
+mod store;
+
+pub use event::{Entry, Event};
+pub use store::{JobHistory, Journal, Journals, Outcome, Run};
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Errors that can occur when writing or reading journals.
+#[derive(Error, Debug)]
+pub enum JournalError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid journal entry in {} on line {line}: {source}", path.display())]
+    InvalidEntry {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn started(job: &str, model: &str) -> Event {
+        Event::JobStarted {
+            job: job.to_string(),
+            output: PathBuf::from("book.wav"),
+            chunks: 3,
+            model: model.to_string(),
+        }
+    }
+
+    fn done(job: &str, chunk: usize) -> Event {
+        Event::ChunkDone {
+            job: job.to_string(),
+            chunk,
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn test_journal_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let journals = Journals::with_dir(temp_dir.path().join("logs"));
+        assert_eq!(journals.latest().unwrap(), None);
+
+        let journal = journals.start(args("-i book.txt")).unwrap();
+        journal.record(started("job-1", "OpenF5-TTS"));
+        journal.record(Event::ChunkRetrying {
+            job: "job-1".to_string(),
+            chunk: 0,
+            error: "Backend busy".to_string(),
+        });
+        journal.record(Event::ChunkDone {
+            job: "job-1".to_string(),
+            chunk: 0,
+            request_id: Some("4121-1-0".to_string()),
+        });
+
+        let content = std::fs::read_to_string(journal.path()).unwrap();
+        assert!(
+            content
+                .lines()
+                .nth(1)
+                .unwrap()
+                .contains(r#""event":"job_started""#)
+        );
+
+        let run = journals.latest().unwrap().unwrap();
+        assert_eq!(run.path, journal.path());
+        assert_eq!(run.entries.len(), 4);
+        assert_eq!(
+            run.entries[0].event,
+            Event::Invoked {
+                args: args("-i book.txt")
+            }
+        );
+        assert_eq!(
+            run.entries[3].event.to_string(),
+            "chunk 0 done (request 4121-1-0)"
+        );
+        let table = run.to_table();
+        assert!(table.contains("chunk 0 failed, retrying: Backend busy"));
+        assert!(table.contains("job job-1 started with OpenF5-TTS: 3 chunks to book.wav"));
+    }
+
+    #[test]
+    fn test_job_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let journals = Journals::with_dir(temp_dir.path());
+
+        let first = journals.start(args("-i book.txt")).unwrap();
+        first.record(started("job-1", "OpenF5-TTS"));
+        first.record(done("job-1", 0));
+        first.record(Event::JobStopped {
+            job: "job-1".to_string(),
+            error: "Connection refused".to_string(),
+        });
+
+        let other = journals.start(args("-i other.txt")).unwrap();
+        other.record(started("job-2", "OpenVoice V2"));
+
+        let history = journals.job_history("job-1").unwrap();
+        assert_eq!(history.runs.len(), 1);
+        assert_eq!(history.model(), Some("OpenF5-TTS"));
+        assert_eq!(
+            history.outcome(),
+            Some(Outcome::Stopped("Connection refused".to_string()))
+        );
+
+        let resumed = journals.start(args("--resume job-1")).unwrap();
+        resumed.record(Event::JobResumed {
+            job: "job-1".to_string(),
+            done: 1,
+            chunks: 3,
+            model: "OpenVoice V2".to_string(),
+        });
+        resumed.record(done("job-1", 1));
+        resumed.record(Event::ChunkReopened {
+            job: "job-1".to_string(),
+            chunk: 1,
+            seed: Some(7),
+        });
+
+        let history = journals.job_history("job-1").unwrap();
+        assert_eq!(history.runs.len(), 2);
+        assert_eq!(history.model(), Some("OpenVoice V2"));
+        assert_eq!(history.outcome(), Some(Outcome::Interrupted(Some(1))));
+        assert_eq!(history.reopen_seeds(1), vec![7]);
+        assert!(history.reopen_seeds(0).is_empty());
+        assert!(
+            history
+                .runs
+                .iter()
+                .flat_map(|run| &run.entries)
+                .all(|entry| entry.event.job().is_none_or(|job| job == "job-1"))
+        );
+        assert_eq!(
+            journals.job_history("job-3").unwrap(),
+            JobHistory::default()
+        );
+    }
+}
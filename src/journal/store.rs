@@ -0,0 +1,257 @@
+//! Journal files.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use super::JournalError;
+use super::event::{Entry, Event};
+
+/// The journal of the running invocation, appended to as events happen.
+///
+/// Each event is written as it is recorded, so a run that is killed
+/// leaves a journal up to its last event. The journal is a diagnostic;
+/// failing to write it never stops synthesis, so write errors are
+/// ignored.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl Journal {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event, timestamped now.
+    pub fn record(&self, event: Event) {
+        let entry = Entry {
+            at: Utc::now(),
+            event,
+        };
+        if let Ok(mut line) = serde_json::to_string(&entry) {
+            line.push('\n');
+            // One write, so events from worker threads do not interleave
+            let _ = self.file.lock().unwrap().write_all(line.as_bytes());
+        }
+    }
+}
+
+/// The events of one invocation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Run {
+    pub path: PathBuf,
+    pub entries: Vec<Entry>,
+}
+
+impl Run {
+    /// Read a journal file.
+    pub fn load(path: &Path) -> Result<Self, JournalError> {
+        let content = std::fs::read_to_string(path)?;
+        let entries = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|source| JournalError::InvalidEntry {
+                    path: path.to_path_buf(),
+                    line: i + 1,
+                    source,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Whether any event of the run is about `job`.
+    pub fn mentions(&self, job: &str) -> bool {
+        self.entries.iter().any(|e| e.event.job() == Some(job))
+    }
+
+    /// The run's events, one per line with its time.
+    pub fn to_table(&self) -> String {
+        let mut table = format!("{}\n", self.path.display());
+        for entry in &self.entries {
+            table.push_str(&format!(
+                "  {}  {}\n",
+                entry.at.format("%Y-%m-%d %H:%M:%S"),
+                entry.event
+            ));
+        }
+        table
+    }
+}
+
+/// The journals of past invocations, one JSON-lines file each, in
+/// `~/.open-tts-rs/logs/`, named by the time the invocation started.
+#[derive(Debug, Clone)]
+pub struct Journals {
+    dir: PathBuf,
+}
+
+impl Default for Journals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Journals {
+    /// The journals at the default location.
+    pub fn new() -> Self {
+        Self::with_dir(
+            dirs::home_dir()
+                .expect("Could not find home directory")
+                .join(".open-tts-rs")
+                .join("logs"),
+        )
+    }
+
+    /// The journals in a custom directory.
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Start the journal of this invocation, recording its arguments.
+    pub fn start(&self, args: Vec<String>) -> Result<Journal, JournalError> {
+        std::fs::create_dir_all(&self.dir)?;
+        let base = format!(
+            "{}-{}",
+            Utc::now().format("%Y%m%d-%H%M%S%.6f"),
+            std::process::id()
+        );
+        let mut path = self.dir.join(format!("{base}.jsonl"));
+        let mut suffix = 1;
+        let file = loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break file,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    suffix += 1;
+                    path = self.dir.join(format!("{base}-{suffix}.jsonl"));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let journal = Journal {
+            path,
+            file: Arc::new(Mutex::new(file)),
+        };
+        journal.record(Event::Invoked { args });
+        Ok(journal)
+    }
+
+    /// Every journal file, oldest first. A missing directory has none.
+    pub fn files(&self) -> Result<Vec<PathBuf>, JournalError> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
+                files.push(path);
+            }
+        }
+        // Names start with the time, so they sort by it
+        files.sort();
+        Ok(files)
+    }
+
+    /// The most recent run, if any.
+    pub fn latest(&self) -> Result<Option<Run>, JournalError> {
+        self.files()?.last().map(|path| Run::load(path)).transpose()
+    }
+
+    /// Every run that worked on `job`, oldest first, with only its
+    /// invocation and the job's events.
+    pub fn job_history(&self, job: &str) -> Result<JobHistory, JournalError> {
+        let mut runs = Vec::new();
+        for path in self.files()? {
+            let mut run = Run::load(&path)?;
+            if !run.mentions(job) {
+                continue;
+            }
+            run.entries.retain(|entry| match &entry.event {
+                Event::Invoked { .. } => true,
+                event => event.job() == Some(job),
+            });
+            runs.push(run);
+        }
+        Ok(JobHistory { runs })
+    }
+}
+
+/// How the last run of a job ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Finished,
+    /// It stopped on an error.
+    Stopped(String),
+    /// It ended without finishing or stopping, as when killed, after
+    /// finishing the given chunk, if any.
+    Interrupted(Option<usize>),
+}
+
+/// The runs of one job, oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobHistory {
+    pub runs: Vec<Run>,
+}
+
+impl JobHistory {
+    fn events(&self) -> impl DoubleEndedIterator<Item = &Event> {
+        self.runs
+            .iter()
+            .flat_map(|run| run.entries.iter().map(|entry| &entry.event))
+    }
+
+    /// The model the job was last started or resumed with.
+    pub fn model(&self) -> Option<&str> {
+        self.events().rev().find_map(|event| match event {
+            Event::JobStarted { model, .. } | Event::JobResumed { model, .. } => {
+                Some(model.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    /// How the most recent run ended, if there was one.
+    pub fn outcome(&self) -> Option<Outcome> {
+        let run = self.runs.last()?;
+        let mut last_chunk = None;
+        let mut outcome = None;
+        for entry in &run.entries {
+            match &entry.event {
+                Event::ChunkDone { chunk, .. } => last_chunk = Some(*chunk),
+                Event::JobStopped { error, .. } => outcome = Some(Outcome::Stopped(error.clone())),
+                Event::JobFinished { .. } => outcome = Some(Outcome::Finished),
+                _ => {}
+            }
+        }
+        Some(outcome.unwrap_or(Outcome::Interrupted(last_chunk)))
+    }
+
+    /// Seeds chunk `chunk` was reopened with before, oldest first.
+    pub fn reopen_seeds(&self, chunk: usize) -> Vec<u64> {
+        self.events()
+            .filter_map(|event| match event {
+                Event::ChunkReopened { chunk: c, seed, .. } if *c == chunk => *seed,
+                _ => None,
+            })
+            .collect()
+    }
+}
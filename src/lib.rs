@@ -2,8 +2,72 @@
 //!
 //! This crate provides a command-line interface for text-to-speech generation
 //! using open-source, commercially licensed TTS models (OpenVoice V2 and OpenF5-TTS).
+//!
+//! Applications can embed the engine directly; [`TTSEngine::builder`] does
+//! the wiring the CLI does:
+//!
+//! ```no_run
+//! use open_tts_rs::{Client, Model, chunk_text};
+//!
+//! let client: Client = Client::builder().model(Model::OpenVoice).build()?;
+//! let chunks = chunk_text("Hello. [pause:500ms] Goodbye.", Some("narrator"), 1.0)?;
+//! std::fs::write("hello.wav", client.synthesize_chunks(&chunks)?)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
+// Only the backend protocol and text handling build for wasm32; everything
+// else needs a file system, sockets, or audio devices.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod align;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod audio;
 pub mod backend;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod config;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod engine;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ingest;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod journal;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod manifest;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod monitor;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod paths;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod project;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod relay;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod scratch;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+pub mod text;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod usage;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod voice;
+
+pub use backend::{
+    Backend, BackendError, Capabilities, DynBackend, Model, SynthesisEvent, WebBackend,
+};
+pub use text::{Chunk, chunk_text};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use audio::{AudioBuffer, AudioError, AudioSink};
+#[cfg(not(target_arch = "wasm32"))]
+pub use backend::{BackendRegistry, HttpBackend, RequestIds};
+#[cfg(not(target_arch = "wasm32"))]
+pub use engine::{TTSEngine, TTSEngineBuilder, TTSError};
+#[cfg(not(target_arch = "wasm32"))]
+pub use voice::{VoiceError, VoiceManager};
+
+/// An engine connected to one of the built-in model servers over HTTP.
+#[cfg(not(target_arch = "wasm32"))]
+pub type Client = TTSEngine<RequestIds<HttpBackend>>;
@@ -1,133 +1,268 @@
 //! open-tts-rs CLI entry point.
 
-use std::fs;
-use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use open_tts_rs::backend::create_backend;
-use open_tts_rs::cli::{Args, Reference};
+use open_tts_rs::audio::{IcecastTarget, Watermark};
+use open_tts_rs::backend::{Backend, BackendRegistry};
+use open_tts_rs::cli::commands::{Forward, PostProcess, SheetBatch};
+use open_tts_rs::cli::{Args, Command, commands};
+use open_tts_rs::config::Config;
 use open_tts_rs::engine::TTSEngine;
-use open_tts_rs::voice::VoiceManager;
+use open_tts_rs::journal::Journal;
+use open_tts_rs::project::{BuildOptions, Project};
+use open_tts_rs::scratch::Scratch;
+use open_tts_rs::server::default_socket_path;
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
 
-    // Create voice manager and backend
-    let voice_manager = VoiceManager::new();
-    let backend = create_backend(args.model, &args.host);
-    let engine = TTSEngine::new(backend, voice_manager);
+    let (config_path, config) = load_config(&args)?;
+    // Removed when main returns; Ctrl-C removes it itself
+    let _scratch = start_scratch(&args, &config)?;
+    commands::suggest_setup(&config_path, &args);
 
-    // Handle utility commands first
-    if args.list_voices {
-        return list_voices(&engine);
+    let profile = config
+        .profile(args.profile.as_deref())?
+        .cloned()
+        .unwrap_or_default();
+    commands::apply_profile(&mut args, &profile)?;
+    let project = commands::command_project(&mut args)?;
+    commands::read_input(&mut args)?;
+    let icecast = commands::icecast_output(&args)?;
+
+    let registry = BackendRegistry::builtin();
+    if run_offline(&args, &config, &registry)? {
+        return Ok(());
     }
 
-    if let Some(name) = &args.delete_voice {
-        return delete_voice(&engine, name);
+    let socket = args
+        .daemon_socket
+        .clone()
+        .unwrap_or_else(default_socket_path);
+    if run_daemon_command(&args, &registry, &config_path, &socket)? {
+        return Ok(());
     }
 
-    // Parse reference if provided (extract voice)
-    if let Some(ref_str) = &args.reference {
-        let reference = Reference::parse(ref_str)?;
+    let voice_manager = commands::open_voice_manager(args.encrypt)?;
+    let target = commands::backend_target(&args, &profile);
+    let engine =
+        commands::connect_engine(&registry, &target, &profile, voice_manager, &args, &config)?;
+    if commands::is_service(&args) {
+        return commands::run_service(engine, &args, &config);
+    }
+    if run_voice_command(&engine, &args, &config, &config_path)? {
+        return Ok(());
+    }
+    // Results go to stdout, so no progress is printed there
+    if let Some((input, options)) = commands::stream_options(&args, &config)? {
+        return commands::run_job_stream(&engine, input, &options, &args);
+    }
 
-        let voice_info = engine
-            .extract_voice(
-                &reference.audio_path,
-                &reference.transcript,
-                args.name.clone(),
-            )
-            .context("Failed to extract voice from reference audio")?;
+    // Everything below synthesizes for someone watching the terminal
+    let canceller = commands::connect_backend(&registry, &target)?;
+    let engine = commands::show_progress_of(engine.with_seed(args.seed), canceller)?;
+    let daemon = commands::daemon_forward(&args, &config, &socket, &target);
+    synthesize(engine, args, &config, project.as_ref(), daemon, &icecast)
+}
 
-        println!("Voice extracted: {}", voice_info.name);
-        println!("  Transcript: {}", voice_info.transcript);
-        println!("  Model: {}", voice_info.model);
-        if let Some(duration) = voice_info.duration {
-            println!("  Duration: {:.2}s", duration);
-        }
+/// Synthesize what `args` asks for: a project, sheet, prompt set, or job,
+/// or else whatever [`speak_or_extract`] finds to do.
+fn synthesize(
+    engine: TTSEngine,
+    args: Args,
+    config: &Config,
+    project: Option<&Project>,
+    daemon: Option<Forward>,
+    icecast: &Option<IcecastTarget>,
+) -> Result<()> {
+    let post = PostProcess::from_args(&args, config)?;
+    check_engine(&engine, &args)?;
+    let journal = commands::open_journal();
+    let journal = journal.as_ref();
 
-        // If no generate flag, just extract and exit
-        if args.generate.is_none() {
-            return Ok(());
+    if let Some(project) = project {
+        return build(&engine, project, &args);
+    }
+    if let Some(sheet) = SheetBatch::from_command(args.command.as_ref()) {
+        return commands::run_sheet_batch(&engine, &sheet, &args, config);
+    }
+    if let Some((file, options)) = commands::prompt_options(&args, config) {
+        return commands::run_prompt_set(&engine, file, &options, &args, config);
+    }
+    if let Some(job_id) = &args.resume {
+        let (store, mut job) = commands::load_resumed(job_id, &args, journal)?;
+        return commands::run_batch(&engine, &store, &mut job, &args, config, &post, journal);
+    }
+    match &args.command {
+        Some(Command::Merge { shards }) => {
+            return commands::merge(&engine, shards, &args, config, &post, journal);
         }
-    } else if let Some(name) = &args.name {
-        // Load existing voice (just verify it exists)
-        let manager = VoiceManager::new();
-        manager
-            .load_metadata(name)
-            .with_context(|| format!("Voice '{}' not found", name))?;
-        println!("Using voice: {name}");
+        Some(Command::Rerender { job, chunk, seed }) => {
+            let seed = seed.or(args.seed);
+            let (store, mut job) = commands::reopen_chunk(job, *chunk, seed, journal)?;
+            let engine = engine.with_seed(seed);
+            return commands::run_batch(&engine, &store, &mut job, &args, config, &post, journal);
+        }
+        _ => {}
     }
 
-    // Generate speech if requested
-    if let Some(text) = &args.generate {
-        return generate_speech(&engine, text, args.name, args.speed, &args.output);
+    speak_or_extract(engine, args, config, &post, daemon, icecast, journal)
+}
+
+/// Extract or check the voice, then speak `-g` text or synthesize an `-i`
+/// file with it.
+fn speak_or_extract(
+    engine: TTSEngine,
+    mut args: Args,
+    config: &Config,
+    post: &PostProcess,
+    daemon: Option<Forward>,
+    icecast: &Option<IcecastTarget>,
+    journal: Option<&Journal>,
+) -> Result<()> {
+    if !commands::prepare_voice(&engine, &mut args)? {
+        return Ok(());
+    }
+    if args.generate.is_some() {
+        return commands::generate(
+            engine,
+            daemon.as_ref(),
+            &args,
+            config,
+            post,
+            icecast,
+            journal,
+        );
+    }
+    if let Some(path) = &args.input_file {
+        return commands::synthesize_file(&engine, path, &args, config, post, journal);
     }
 
-    // No action specified
-    if args.reference.is_none() && args.generate.is_none() {
+    if args.reference.is_none() {
         eprintln!("No action specified. Use -r to extract a voice or -g to generate speech.");
         eprintln!("Run with --help for usage information.");
     }
-
     Ok(())
 }
 
-fn list_voices<B: open_tts_rs::backend::Backend>(engine: &TTSEngine<B>) -> Result<()> {
-    let voices = engine.list_voices().context("Failed to list voices")?;
-
-    if voices.is_empty() {
-        println!("No voices found.");
-        return Ok(());
+/// Build `project` now, or on the schedule `schedule` gives.
+fn build<B: Backend>(engine: &TTSEngine<B>, project: &Project, args: &Args) -> Result<()> {
+    match commands::ScheduledBuild::from_command(project, args.command.as_ref())? {
+        Some(scheduled) => commands::run_schedule(engine, project, &scheduled, args),
+        None => commands::run_build(engine, project, args, &BuildOptions::default(), |_| {}),
     }
+}
 
-    println!("Available voices:");
-    for voice in voices {
-        println!("  {} ({})", voice.name, voice.model);
-        println!("    Transcript: {}", voice.transcript);
-        if let Some(duration) = voice.duration {
-            println!("    Duration: {:.2}s", duration);
-        }
+/// Warn about parameters the backend ignores, and fail before the first
+/// request rather than on every row of a batch if it cannot do `--speed`.
+fn check_engine<B: Backend>(engine: &TTSEngine<B>, args: &Args) -> Result<()> {
+    for dropped in engine.dropped_parameters() {
+        eprintln!("Warning: {dropped}");
     }
-
+    engine.check_speed(args.speed)?;
     Ok(())
 }
 
-fn delete_voice<B: open_tts_rs::backend::Backend>(engine: &TTSEngine<B>, name: &str) -> Result<()> {
-    engine
-        .delete_voice(name)
-        .with_context(|| format!("Failed to delete voice '{}'", name))?;
+/// Load the config file `--config` names, or the default one.
+fn load_config(args: &Args) -> Result<(PathBuf, Config)> {
+    let config_path = args.config.clone().unwrap_or_else(Config::default_path);
+    let config = Config::load(&config_path)
+        .with_context(|| format!("Failed to load config: {}", config_path.display()))?;
+    Ok((config_path, config))
+}
 
-    println!("Voice '{}' deleted.", name);
-    Ok(())
+/// Start the scratch directory for temporary files, under `--temp-dir` or
+/// the config's `temp_dir` when set.
+fn start_scratch(args: &Args, config: &Config) -> Result<Scratch> {
+    let temp_dir = args.temp_dir.clone().or(config.temp_dir.clone());
+    Scratch::start(temp_dir.as_deref()).with_context(|| {
+        format!(
+            "Failed to create a temp directory in {}",
+            temp_dir.unwrap_or_else(std::env::temp_dir).display()
+        )
+    })
 }
 
-fn generate_speech<B: open_tts_rs::backend::Backend>(
-    engine: &TTSEngine<B>,
-    text: &str,
-    voice_name: Option<String>,
-    speed: f32,
-    output: &std::path::Path,
-) -> Result<()> {
-    println!("Generating speech...");
-    if let Some(ref name) = voice_name {
-        println!("  Voice: {}", name);
+/// Run a command that works through the daemon or sets up a config rather
+/// than synthesizing here. Returns false if `args` asks for something else.
+fn run_daemon_command(
+    args: &Args,
+    registry: &BackendRegistry,
+    config_path: &Path,
+    socket: &Path,
+) -> Result<bool> {
+    match args.command {
+        Some(Command::Daemon) => commands::run_daemon(
+            registry.clone(),
+            commands::open_voice_manager(args.encrypt)?,
+            socket,
+        )?,
+        Some(Command::Pause | Command::Resume) => {
+            commands::set_daemon_paused(socket, args.command == Some(Command::Pause))?
+        }
+        Some(Command::Setup) => commands::setup(
+            registry,
+            commands::open_voice_manager(args.encrypt)?,
+            config_path,
+            args,
+        )?,
+        _ => return Ok(false),
     }
-    println!("  Speed: {:.1}x", speed);
-
-    let audio_data = engine
-        .synthesize(text, voice_name, speed)
-        .context("Failed to synthesize speech")?;
-
-    // Write audio to file
-    let mut file = fs::File::create(output)
-        .with_context(|| format!("Failed to create output file: {}", output.display()))?;
-
-    file.write_all(&audio_data)
-        .with_context(|| format!("Failed to write audio to: {}", output.display()))?;
+    Ok(true)
+}
 
-    println!("Audio saved to: {}", output.display());
-    println!("  Size: {} bytes", audio_data.len());
+/// Run a command that needs no backend. Returns false if `args` asks for
+/// something else.
+fn run_offline(args: &Args, config: &Config, registry: &BackendRegistry) -> Result<bool> {
+    match &args.command {
+        Some(Command::Backends) => commands::list_backends(registry),
+        Some(Command::Usage { command }) => commands::usage(command)?,
+        Some(Command::Logs { command }) => commands::logs(command)?,
+        Some(Command::Lint { files, dir, format }) => {
+            commands::lint(files, dir.clone(), *format, args, config)?
+        }
+        Some(Command::Estimate { files, format }) => {
+            commands::estimate(files, *format, args, config)?
+        }
+        Some(Command::Diff {
+            a,
+            b,
+            threshold,
+            format,
+        }) => commands::diff(a, b, *threshold, *format)?,
+        Some(Command::VerifyWatermark { file }) => {
+            let key = args
+                .watermark_key
+                .as_deref()
+                .or(config.watermark_key.as_deref());
+            let watermark = key
+                .map(Watermark::new)
+                .context("verify-watermark needs --watermark-key or watermark_key in config")?;
+            commands::verify_watermark(&watermark, file)?
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
 
-    Ok(())
+/// Run a voice library command. Returns false if `args` asks for
+/// something else.
+fn run_voice_command<B: Backend>(
+    engine: &TTSEngine<B>,
+    args: &Args,
+    config: &Config,
+    config_path: &Path,
+) -> Result<bool> {
+    if args.list_voices {
+        commands::list_voices(engine)?;
+    } else if let Some(name) = &args.delete_voice {
+        commands::delete_voice(engine, name, commands::trash_retention(config))?;
+    } else if let Some(Command::Voices { command }) = &args.command {
+        commands::voices(engine, command, config, config_path, args.seed)?;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
 }
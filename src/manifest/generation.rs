@@ -0,0 +1,211 @@
+//! Generation manifest types.
+
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{ManifestError, ShardPlan};
+use crate::audio::{AudioBuffer, QaReport};
+use crate::batch::{ChunkStatus, Job, PromptResult, RowResult};
+use crate::text::Chunk;
+
+/// One generated audio file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file: PathBuf,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    /// Audio length in seconds.
+    pub duration: f64,
+    /// Hex-encoded SHA-256 of the file contents.
+    pub sha256: String,
+    /// Thresholds the file violated when checked with `--qa-report`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub qa_failures: Vec<String>,
+    /// Request ID the file was synthesized under, for finding it in
+    /// backend logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Index of the job chunk the file holds, in a shard's manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<usize>,
+}
+
+impl ManifestEntry {
+    /// Describe a WAV file on disk, computing its duration and checksum.
+    pub fn from_wav(file: &Path, text: &str, voice: Option<String>) -> Result<Self, ManifestError> {
+        let data = std::fs::read(file)?;
+        let duration = AudioBuffer::from_wav_bytes(&data)?.duration().as_secs_f64();
+        Ok(Self::new(file, &data, duration, text, voice))
+    }
+
+    /// Describe a file of any format with the given contents and duration.
+    pub fn new(file: &Path, data: &[u8], duration: f64, text: &str, voice: Option<String>) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            text: text.to_string(),
+            voice,
+            duration,
+            sha256: sha256_hex(data),
+            qa_failures: Vec::new(),
+            request_id: None,
+            chunk: None,
+        }
+    }
+}
+
+/// Machine-readable index of the files written by a generation run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub generated_at: String,
+    pub model: String,
+    /// Model the backend reported loaded, with its version when it gives
+    /// one; see [`HealthResponse::loaded_model`](crate::backend::HealthResponse::loaded_model).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// The final assembled output, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<ManifestEntry>,
+    /// Individual files, in generation order.
+    pub files: Vec<ManifestEntry>,
+    /// The job a shard belongs to, in a shard's manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<ShardPlan>,
+}
+
+impl Manifest {
+    /// Create an empty manifest for the given model.
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            generated_at: Utc::now().to_rfc3339(),
+            model: model.into(),
+            backend_model: None,
+            job_id: None,
+            output: None,
+            files: Vec::new(),
+            shard: None,
+        }
+    }
+
+    /// Build a manifest for a completed batch job.
+    ///
+    /// Lists every synthesized chunk file plus the assembled output.
+    /// Skipped chunks have no file and are omitted.
+    pub fn for_job(job: &Job, model: impl Into<String>) -> Result<Self, ManifestError> {
+        let mut manifest = Self::new(model);
+        manifest.job_id = Some(job.id.clone());
+        manifest.backend_model = job.backend_model.clone();
+
+        let mut texts = Vec::new();
+        let mut voices = Vec::new();
+
+        for entry in &job.chunks {
+            let Chunk::Speech { text, voice, .. } = &entry.chunk else {
+                continue;
+            };
+            texts.push(text.as_str());
+            if !voices.contains(voice) {
+                voices.push(voice.clone());
+            }
+
+            if let (ChunkStatus::Done, Some(file)) = (entry.status, &entry.output) {
+                let mut file = ManifestEntry::from_wav(file, text, voice.clone())?;
+                file.request_id = entry.request_id.clone();
+                manifest.files.push(file);
+            }
+        }
+
+        // Only attribute the output to a voice when every chunk used it
+        let voice = match voices.as_slice() {
+            [single] => single.clone(),
+            _ => None,
+        };
+        manifest.output = Some(ManifestEntry::from_wav(
+            &job.output,
+            &texts.join(" "),
+            voice,
+        )?);
+
+        Ok(manifest)
+    }
+
+    /// Build a manifest for the rows of a sheet, listing each row that was
+    /// written.
+    pub fn for_rows(
+        results: &[RowResult],
+        model: impl Into<String>,
+    ) -> Result<Self, ManifestError> {
+        let mut manifest = Self::new(model);
+        for result in results.iter().filter(|r| r.is_done()) {
+            manifest.files.push(ManifestEntry::from_wav(
+                &result.output,
+                &result.text,
+                result.voice.clone(),
+            )?);
+        }
+        Ok(manifest)
+    }
+
+    /// Build a manifest for a prompt set, listing every file of each
+    /// prompt that was written. Raw prompt files have no header, so their
+    /// duration is the prompt's.
+    pub fn for_prompts(
+        results: &[PromptResult],
+        model: impl Into<String>,
+    ) -> Result<Self, ManifestError> {
+        let mut manifest = Self::new(model);
+        for result in results.iter().filter(|r| r.is_done()) {
+            for file in &result.files {
+                manifest.files.push(ManifestEntry::new(
+                    file,
+                    &std::fs::read(file)?,
+                    result.seconds,
+                    &result.text,
+                    result.voice.clone(),
+                ));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Attach QA failures to the entries for the reported files.
+    pub fn annotate_qa(&mut self, reports: &[QaReport]) {
+        let entries = self.files.iter_mut().chain(self.output.as_mut());
+        for entry in entries {
+            if let Some(report) = reports.iter().find(|r| r.file == entry.file) {
+                entry.qa_failures = report.failures.clone();
+            }
+        }
+    }
+
+    /// Default manifest location next to an output file (`out.wav` ->
+    /// `out.manifest.json`).
+    pub fn default_path(output: &Path) -> PathBuf {
+        output.with_extension("manifest.json")
+    }
+
+    /// Write the manifest as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<(), ManifestError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read a manifest from disk.
+    pub fn load(path: &Path) -> Result<Self, ManifestError> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Hex-encoded SHA-256 digest.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
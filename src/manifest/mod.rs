@@ -0,0 +1,309 @@
+//! Generation manifests.
+//!
+//! After a multi-file generation a JSON manifest is written listing each
+//! output file with its source text, voice, duration, and checksum, so
+//! downstream pipelines and QA scripts can index the results.
+//!
+//! A shard of a job split across machines writes its chunk audio and a
+//! manifest carrying the whole job's plan to a directory of its own.
+//! [`merge_shards`] checks that a set of such manifests covers the job
+//! and turns them back into one job to assemble.
+
+mod generation;
+mod shard;
+
+pub use generation::{Manifest, ManifestEntry, sha256_hex};
+pub use shard::{SHARD_MANIFEST, ShardPlan, merge_shards, write_shard};
+
+use thiserror::Error;
+
+use crate::audio::AudioError;
+use crate::batch::BatchError;
+
+/// Errors that can occur when building or writing a manifest.
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Audio processing error: {0}")]
+    AudioError(#[from] AudioError),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Cannot merge shards: {0}")]
+    Merge(String),
+
+    #[error("Batch error: {0}")]
+    BatchError(#[from] BatchError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioBuffer, QaReport, QaThresholds};
+    use crate::batch::{ChunkStatus, Job, JobStore, RowResult};
+    use crate::text::Chunk;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn write_wav(path: &Path, samples: usize) {
+        let wav = AudioBuffer::new(vec![0.1; samples], 1000, 1)
+            .to_wav_bytes()
+            .unwrap();
+        std::fs::write(path, wav).unwrap();
+    }
+
+    fn speech(text: &str, voice: Option<&str>) -> Chunk {
+        Chunk::Speech {
+            text: text.to_string(),
+            voice: voice.map(str::to_string),
+            speed: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_manifest_default_path() {
+        assert_eq!(
+            Manifest::default_path(Path::new("/out/book.wav")),
+            PathBuf::from("/out/book.manifest.json")
+        );
+    }
+
+    #[test]
+    fn test_manifest_entry_from_wav() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.wav");
+        write_wav(&path, 1500);
+
+        let entry = ManifestEntry::from_wav(&path, "Hello", Some("amy".to_string())).unwrap();
+        assert_eq!(entry.text, "Hello");
+        assert_eq!(entry.voice.as_deref(), Some("amy"));
+        assert!((entry.duration - 1.5).abs() < 1e-9);
+        assert_eq!(entry.sha256, sha256_hex(&std::fs::read(&path).unwrap()));
+    }
+
+    #[test]
+    fn test_manifest_for_job() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("out.wav");
+        let chunk0 = temp_dir.path().join("chunk-0.wav");
+        write_wav(&chunk0, 1000);
+        write_wav(&output, 2000);
+
+        let mut job = Job::new(
+            "job-1",
+            vec![speech("One.", Some("amy")), speech("Two.", Some("bob"))],
+            output.clone(),
+        );
+        job.chunks[0].status = ChunkStatus::Done;
+        job.chunks[0].output = Some(chunk0.clone());
+        job.chunks[0].request_id = Some("4121-1-0".to_string());
+        job.chunks[1].status = ChunkStatus::Skipped;
+        job.backend_model = Some("openvoice_v2 (checkpoints_v2)".to_string());
+
+        let manifest = Manifest::for_job(&job, "OpenVoice V2").unwrap();
+        assert_eq!(manifest.job_id.as_deref(), Some("job-1"));
+        assert_eq!(manifest.model, "OpenVoice V2");
+        assert_eq!(
+            manifest.backend_model.as_deref(),
+            Some("openvoice_v2 (checkpoints_v2)")
+        );
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].file, chunk0);
+        assert_eq!(manifest.files[0].request_id.as_deref(), Some("4121-1-0"));
+
+        let output_entry = manifest.output.unwrap();
+        assert_eq!(output_entry.text, "One. Two.");
+        assert_eq!(output_entry.voice, None);
+        assert!((output_entry.duration - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_manifest_for_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let written = temp_dir.path().join("menu.wav");
+        write_wav(&written, 500);
+
+        let row = |output: &Path, error: Option<&str>| RowResult {
+            output: output.to_path_buf(),
+            text: "Press 1.".to_string(),
+            voice: Some("amy".to_string()),
+            chunks: Vec::new(),
+            seconds: 0.5,
+            error: error.map(str::to_string),
+        };
+        let results = [
+            row(&written, None),
+            row(&temp_dir.path().join("failed.wav"), Some("down")),
+        ];
+
+        let manifest = Manifest::for_rows(&results, "OpenF5-TTS").unwrap();
+        assert_eq!(manifest.output, None);
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].file, written);
+        assert_eq!(manifest.files[0].voice.as_deref(), Some("amy"));
+    }
+
+    #[test]
+    fn test_manifest_write_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("m.json");
+
+        let manifest = Manifest::new("OpenF5-TTS");
+        manifest.write(&path).unwrap();
+
+        assert_eq!(Manifest::load(&path).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_manifest_annotate_qa() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.wav");
+        write_wav(&path, 1000);
+
+        let mut manifest = Manifest::new("OpenVoice V2");
+        manifest
+            .files
+            .push(ManifestEntry::from_wav(&path, "Hi", None).unwrap());
+
+        let buffer = AudioBuffer::new(vec![1.0; 1000], 1000, 1);
+        let report = QaReport::new(&path, &buffer, &QaThresholds::default());
+        manifest.annotate_qa(&[report]);
+
+        assert!(
+            manifest.files[0]
+                .qa_failures
+                .iter()
+                .any(|f| f.contains("clipped"))
+        );
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert!(json.contains("qa_failures"));
+    }
+
+    /// Shard `index` of 2 of a job, with its chunks synthesized unless
+    /// `unfinished`, written to its directory. Returns the directory.
+    fn shard_of(dir: &Path, index: usize, unfinished: bool) -> PathBuf {
+        use crate::batch::{JobChapter, Shard};
+
+        let chunks = vec![
+            speech("One.", None),
+            Chunk::Pause(std::time::Duration::from_millis(50)),
+            speech("Two.", Some("amy")),
+            speech("Three.", None),
+        ];
+        let shard = Shard { index, count: 2 };
+        let mut job = Job::new(format!("shard-{index}"), chunks, dir.join("book.wav"));
+        job.shard = Some(shard);
+        job.backend_model = Some("openvoice_v2".to_string());
+        job.chapters = vec![JobChapter {
+            title: "Chapter 1".to_string(),
+            start: 0,
+        }];
+        let owned = shard.chunks(
+            &job.chunks
+                .iter()
+                .map(|c| c.chunk.clone())
+                .collect::<Vec<_>>(),
+        );
+        for i in owned.into_iter().skip(usize::from(unfinished)) {
+            let audio = dir.join(format!("{index}-{i}.wav"));
+            write_wav(&audio, 100 * (i + 1));
+            job.chunks[i].status = ChunkStatus::Done;
+            job.chunks[i].output = Some(audio);
+        }
+        write_shard(&job, shard, "OpenVoice V2")
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf()
+    }
+
+    #[test]
+    fn test_merge_shards() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let first = shard_of(temp_dir.path(), 1, false);
+        let second = shard_of(temp_dir.path(), 2, false);
+        assert_eq!(first, temp_dir.path().join("book.shard-1-of-2"));
+
+        let manifest = Manifest::load(&first.join(SHARD_MANIFEST)).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.files[1].file, PathBuf::from("chunk-00003.wav"));
+        assert_eq!(manifest.files[1].chunk, Some(3));
+
+        let output = temp_dir.path().join("merged.wav");
+        let job = merge_shards(
+            &[second.clone(), first.join(SHARD_MANIFEST)],
+            &store,
+            &output,
+        )
+        .unwrap();
+        assert_eq!(job.output, output);
+        assert_eq!(job.chapters.len(), 1);
+        assert_eq!(job.shard, None);
+        assert_eq!(job.backend_model.as_deref(), Some("openvoice_v2"));
+        let statuses: Vec<ChunkStatus> = job.chunks.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ChunkStatus::Done,
+                ChunkStatus::Pending,
+                ChunkStatus::Done,
+                ChunkStatus::Done
+            ]
+        );
+        let chunk = job.chunks[2].output.as_ref().unwrap();
+        assert!(chunk.starts_with(store.job_dir(&job.id)));
+        assert_eq!(
+            std::fs::read(chunk).unwrap(),
+            std::fs::read(second.join("chunk-00002.wav")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merge_shards_checks_coverage() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = JobStore::with_dir(temp_dir.path().join("jobs"));
+        let output = temp_dir.path().join("merged.wav");
+        let first = shard_of(temp_dir.path(), 1, false);
+
+        let merge = |shards: &[PathBuf]| match merge_shards(shards, &store, &output) {
+            Err(ManifestError::Merge(message)) => message,
+            other => panic!("expected a merge error, got {other:?}"),
+        };
+        assert_eq!(merge(std::slice::from_ref(&first)), "missing shard(s) 2/2");
+        assert_eq!(
+            merge(&[first.clone(), first.clone()]),
+            "shard 1/2 is given twice"
+        );
+
+        let second = shard_of(temp_dir.path(), 2, true);
+        assert!(
+            merge(&[first.clone(), second.clone()])
+                .starts_with("shard 2/2 has no audio for chunk(s) 2")
+        );
+
+        shard_of(temp_dir.path(), 2, false);
+        let mut manifest = Manifest::load(&second.join(SHARD_MANIFEST)).unwrap();
+        manifest.backend_model = Some("openf5_tts".to_string());
+        manifest.write(&second.join(SHARD_MANIFEST)).unwrap();
+        assert_eq!(
+            merge(&[first.clone(), second.clone()]),
+            "shard 2/2 was synthesized with openf5_tts but shard 1/2 with openvoice_v2"
+        );
+
+        shard_of(temp_dir.path(), 2, false);
+        write_wav(&second.join("chunk-00002.wav"), 10);
+        assert!(merge(&[first, second]).contains("does not match its checksum"));
+    }
+}
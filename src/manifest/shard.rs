@@ -0,0 +1,241 @@
+//! Shard manifests and merging them into a job.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Manifest, ManifestEntry, ManifestError, sha256_hex};
+use crate::batch::{ChunkStatus, Job, JobChapter, JobStore, Shard};
+use crate::text::Chunk;
+
+/// File name of the manifest in a shard's directory.
+pub const SHARD_MANIFEST: &str = "manifest.json";
+
+/// The whole job a shard was cut from, so the shards can be checked
+/// against each other and merged without the input text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardPlan {
+    pub shard: Shard,
+    pub chunks: Vec<Chunk>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<JobChapter>,
+}
+
+/// Copy the audio of a shard's finished chunks into its directory (see
+/// [`Shard::dir`]) and write its manifest there, naming each file
+/// relative to the directory so it can be moved to the machine that
+/// merges. Returns the manifest's path.
+pub fn write_shard(
+    job: &Job,
+    shard: Shard,
+    model: impl Into<String>,
+) -> Result<PathBuf, ManifestError> {
+    let dir = shard.dir(&job.output);
+    std::fs::create_dir_all(&dir)?;
+
+    let mut manifest = Manifest::new(model);
+    manifest.job_id = Some(job.id.clone());
+    manifest.backend_model = job.backend_model.clone();
+    let chunks: Vec<Chunk> = job.chunks.iter().map(|c| c.chunk.clone()).collect();
+    for i in shard.chunks(&chunks) {
+        let entry = &job.chunks[i];
+        let (Chunk::Speech { text, voice, .. }, ChunkStatus::Done, Some(audio)) =
+            (&entry.chunk, entry.status, &entry.output)
+        else {
+            continue;
+        };
+        let name = PathBuf::from(format!("chunk-{i:05}.wav"));
+        std::fs::copy(audio, dir.join(&name))?;
+        let mut file = ManifestEntry::from_wav(&dir.join(&name), text, voice.clone())?;
+        file.file = name;
+        file.chunk = Some(i);
+        file.request_id = entry.request_id.clone();
+        manifest.files.push(file);
+    }
+    manifest.shard = Some(ShardPlan {
+        shard,
+        chunks,
+        chapters: job.chapters.clone(),
+    });
+
+    let path = dir.join(SHARD_MANIFEST);
+    manifest.write(&path)?;
+    Ok(path)
+}
+
+/// A shard manifest read for merging, with the path it was read from.
+struct LoadedShard {
+    path: PathBuf,
+    manifest: Manifest,
+    plan: ShardPlan,
+}
+
+/// Chunk audio found in the shards, by chunk index: the file and the
+/// backend's request ID.
+type ShardAudio = BTreeMap<usize, (PathBuf, Option<String>)>;
+
+/// Create a job for `output` from the manifests of every shard of one
+/// job, given as manifest files or shard directories. Each chunk's audio
+/// is checked against its checksum and copied into the job, so running
+/// the job only assembles it.
+pub fn merge_shards(
+    paths: &[PathBuf],
+    store: &JobStore,
+    output: &Path,
+) -> Result<Job, ManifestError> {
+    let mut shards: BTreeMap<usize, LoadedShard> = BTreeMap::new();
+    for path in paths {
+        let shard = load_shard(path)?;
+        check_matches(&shard, &shards)?;
+        shards.insert(shard.plan.shard.index, shard);
+    }
+    check_complete(&shards)?;
+
+    let mut audio = ShardAudio::new();
+    for shard in shards.values() {
+        audio.extend(shard_audio(shard)?);
+    }
+    write_merged(&shards, audio, store, output)
+}
+
+/// Read the shard manifest at `path`, or in the shard directory `path`.
+fn load_shard(path: &Path) -> Result<LoadedShard, ManifestError> {
+    let path = if path.is_dir() {
+        path.join(SHARD_MANIFEST)
+    } else {
+        path.to_path_buf()
+    };
+    let manifest = Manifest::load(&path)?;
+    let plan = manifest.shard.clone().ok_or_else(|| {
+        ManifestError::Merge(format!("{} is not a shard manifest", path.display()))
+    })?;
+    Ok(LoadedShard {
+        path,
+        manifest,
+        plan,
+    })
+}
+
+/// Check that `shard` was cut from the same job and synthesized with the
+/// same model as the shards already loaded, and is not one of them.
+fn check_matches(
+    shard: &LoadedShard,
+    shards: &BTreeMap<usize, LoadedShard>,
+) -> Result<(), ManifestError> {
+    let Some(other) = shards.values().next() else {
+        return Ok(());
+    };
+    let plan = &shard.plan;
+    if plan.shard.count != other.plan.shard.count || plan.chunks != other.plan.chunks {
+        return Err(ManifestError::Merge(format!(
+            "{} is from a different job than {}; every shard must read the same input with \
+             the same options",
+            shard.path.display(),
+            other.path.display()
+        )));
+    }
+    if shard.manifest.backend_model != other.manifest.backend_model {
+        let model = |m: &Manifest| {
+            m.backend_model
+                .clone()
+                .unwrap_or_else(|| "an unknown model".to_string())
+        };
+        return Err(ManifestError::Merge(format!(
+            "shard {} was synthesized with {} but shard {} with {}",
+            plan.shard,
+            model(&shard.manifest),
+            other.plan.shard,
+            model(&other.manifest)
+        )));
+    }
+    if shards.contains_key(&plan.shard.index) {
+        return Err(ManifestError::Merge(format!(
+            "shard {} is given twice",
+            plan.shard
+        )));
+    }
+    Ok(())
+}
+
+/// Check that `shards` is not empty and has every shard of the job.
+fn check_complete(shards: &BTreeMap<usize, LoadedShard>) -> Result<(), ManifestError> {
+    let Some(shard) = shards.values().next() else {
+        return Err(ManifestError::Merge("no shard manifests given".to_string()));
+    };
+    let count = shard.plan.shard.count;
+    let missing: Vec<String> = (1..=count)
+        .filter(|index| !shards.contains_key(index))
+        .map(|index| format!("{index}/{count}"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(ManifestError::Merge(format!(
+            "missing shard(s) {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// The audio of each of `shard`'s chunks, checked against its checksum.
+/// Fails if any chunk of the shard has none.
+fn shard_audio(shard: &LoadedShard) -> Result<ShardAudio, ManifestError> {
+    let LoadedShard {
+        path,
+        manifest,
+        plan,
+    } = shard;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut audio = ShardAudio::new();
+    let mut unfinished = Vec::new();
+    for i in plan.shard.chunks(&plan.chunks) {
+        let Some(entry) = manifest.files.iter().find(|f| f.chunk == Some(i)) else {
+            unfinished.push(i.to_string());
+            continue;
+        };
+        let file = dir.join(&entry.file);
+        if sha256_hex(&std::fs::read(&file)?) != entry.sha256 {
+            return Err(ManifestError::Merge(format!(
+                "{} does not match its checksum in {}",
+                file.display(),
+                path.display()
+            )));
+        }
+        audio.insert(i, (file, entry.request_id.clone()));
+    }
+    if !unfinished.is_empty() {
+        let job = manifest.job_id.as_deref().unwrap_or("?");
+        return Err(ManifestError::Merge(format!(
+            "shard {} has no audio for chunk(s) {}; finish it with --resume {job}",
+            plan.shard,
+            unfinished.join(", ")
+        )));
+    }
+    Ok(audio)
+}
+
+/// Create the merged job in `store`, with every chunk done and its audio
+/// copied in.
+fn write_merged(
+    shards: &BTreeMap<usize, LoadedShard>,
+    audio: ShardAudio,
+    store: &JobStore,
+    output: &Path,
+) -> Result<Job, ManifestError> {
+    let plan = &shards.values().next().expect("checked complete").plan;
+    let mut job = store.create(plan.chunks.clone(), output)?;
+    job.chapters = plan.chapters.clone();
+    job.backend_model = shards
+        .values()
+        .find_map(|shard| shard.manifest.backend_model.clone());
+    for (i, (file, request_id)) in audio {
+        let path = store.chunk_path(&job.id, i);
+        std::fs::copy(&file, &path)?;
+        let entry = &mut job.chunks[i];
+        entry.output = Some(path);
+        entry.status = ChunkStatus::Done;
+        entry.request_id = request_id;
+    }
+    store.save(&job)?;
+    Ok(job)
+}
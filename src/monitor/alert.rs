@@ -0,0 +1,150 @@
+//! Choosing which log lines are spoken, and how.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+/// Shortest time between two spoken alerts by default.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a line is not repeated by default.
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(60);
+
+/// Longest alert spoken by default, in characters.
+pub const DEFAULT_MAX_CHARS: usize = 200;
+
+/// A timestamp at the start of a line: ISO 8601 (optionally bracketed) or
+/// syslog's `Mar  4 12:00:01`.
+static LEADING_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:\[?\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?\]?|[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})\s*",
+    )
+    .unwrap()
+});
+
+static DIGITS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+").unwrap());
+
+/// Which lines become alerts.
+#[derive(Debug, Clone)]
+pub struct AlertOptions {
+    /// Only lines matching this are spoken; all lines when unset.
+    pub filter: Option<Regex>,
+    /// Lines arriving sooner than this after the last alert are held back.
+    pub min_interval: Duration,
+    /// A line like one spoken within this long is skipped.
+    pub dedup_window: Duration,
+    /// Alerts are cut to this many characters, at a word.
+    pub max_chars: usize,
+}
+
+impl Default for AlertOptions {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            min_interval: DEFAULT_MIN_INTERVAL,
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            max_chars: DEFAULT_MAX_CHARS,
+        }
+    }
+}
+
+/// What became of one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Speak this text.
+    Speak(String),
+    /// The line does not match the filter.
+    Filtered,
+    /// A line like it was spoken recently.
+    Duplicate,
+    /// Too soon after the last alert; counted towards the next one.
+    RateLimited,
+}
+
+/// Turns log lines into alerts, keeping the rate limit and the recently
+/// spoken lines.
+#[derive(Debug)]
+pub struct Alerter {
+    options: AlertOptions,
+    last_alert: Option<Instant>,
+    /// When each line, numbers removed, was last spoken.
+    recent: HashMap<String, Instant>,
+    /// Lines held back by the rate limit since the last alert.
+    held_back: usize,
+}
+
+impl Alerter {
+    pub fn new(options: AlertOptions) -> Self {
+        Self {
+            options,
+            last_alert: None,
+            recent: HashMap::new(),
+            held_back: 0,
+        }
+    }
+
+    /// Decide what to do with `line`, arriving at `now`.
+    ///
+    /// Lines that differ only in their numbers (timestamps, IDs, counts)
+    /// count as the same line. An alert after some were held back by the
+    /// rate limit says how many.
+    pub fn check(&mut self, line: &str, now: Instant) -> Decision {
+        let line = line.trim();
+        if line.is_empty()
+            || self
+                .options
+                .filter
+                .as_ref()
+                .is_some_and(|filter| !filter.is_match(line))
+        {
+            return Decision::Filtered;
+        }
+
+        let window = self.options.dedup_window;
+        self.recent
+            .retain(|_, spoken| now.duration_since(*spoken) < window);
+        let key = DIGITS.replace_all(line, "#").into_owned();
+        if self.recent.contains_key(&key) {
+            return Decision::Duplicate;
+        }
+        if self
+            .last_alert
+            .is_some_and(|last| now.duration_since(last) < self.options.min_interval)
+        {
+            self.held_back += 1;
+            return Decision::RateLimited;
+        }
+
+        self.recent.insert(key, now);
+        self.last_alert = Some(now);
+        let mut text = spoken_text(line, self.options.max_chars);
+        let held_back = std::mem::take(&mut self.held_back);
+        if held_back > 0 {
+            if !text.ends_with(['.', '!', '?']) {
+                text.push('.');
+            }
+            match held_back {
+                1 => text.push_str(" And 1 more alert."),
+                n => text.push_str(&format!(" And {n} more alerts.")),
+            }
+        }
+        Decision::Speak(text)
+    }
+}
+
+/// The part of a log line worth hearing: no leading timestamp, and at
+/// most `max_chars` characters.
+pub fn spoken_text(line: &str, max_chars: usize) -> String {
+    let text = LEADING_TIMESTAMP.replace(line.trim(), "");
+    if text.chars().count() <= max_chars {
+        return text.into_owned();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) if end > 0 => &cut[..end],
+        _ => &cut,
+    };
+    format!("{}...", cut.trim_end())
+}
@@ -0,0 +1,131 @@
+//! Reading lines as they are appended to a file, like `tail -F`.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Follows a growing file by path.
+///
+/// A file that shrinks was truncated and is read again from the start; a
+/// file replaced by log rotation is reopened. A missing file is waited for.
+#[derive(Debug)]
+pub struct LogFollower {
+    path: PathBuf,
+    file: Option<File>,
+    /// Bytes of the current file read so far.
+    position: u64,
+    /// Identity of the open file, to notice rotation.
+    id: Option<FileId>,
+    /// An unfinished last line, completed by the next write.
+    partial: Vec<u8>,
+}
+
+impl LogFollower {
+    /// Follow `path`, reading only what is written from now on, or the
+    /// whole file first when `from_start` is set.
+    pub fn open(path: &Path, from_start: bool) -> io::Result<Self> {
+        let mut follower = Self {
+            path: path.to_path_buf(),
+            file: None,
+            position: 0,
+            id: None,
+            partial: Vec::new(),
+        };
+        match follower.reopen() {
+            Ok(()) if !from_start => {
+                let file = follower.file.as_mut().expect("just opened");
+                follower.position = file.seek(SeekFrom::End(0))?;
+            }
+            Ok(()) => {}
+            // Not created yet; everything it will contain is new
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(follower)
+    }
+
+    /// Complete lines written since the last poll, without their line
+    /// endings.
+    pub fn poll(&mut self) -> io::Result<Vec<String>> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            // Rotated away and not recreated yet
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        if self.file.is_none() || self.id != Some(FileId::of(&metadata)) {
+            let mut lines = self.drain()?;
+            self.reopen()?;
+            lines.extend(self.drain()?);
+            return Ok(lines);
+        }
+        if metadata.len() < self.position {
+            self.position = 0;
+            self.partial.clear();
+            self.file
+                .as_mut()
+                .expect("checked above")
+                .seek(SeekFrom::Start(0))?;
+        }
+        self.drain()
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        let file = File::open(&self.path)?;
+        self.id = Some(FileId::of(&file.metadata()?));
+        self.file = Some(file);
+        self.position = 0;
+        self.partial.clear();
+        Ok(())
+    }
+
+    /// Read the open file to its end, splitting off complete lines.
+    fn drain(&mut self) -> io::Result<Vec<String>> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(Vec::new());
+        };
+        let mut bytes = std::mem::take(&mut self.partial);
+        self.position += file.read_to_end(&mut bytes)? as u64;
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while let Some(end) = bytes[start..].iter().position(|&b| b == b'\n') {
+            let line = &bytes[start..start + end];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            lines.push(String::from_utf8_lossy(line).into_owned());
+            start += end + 1;
+        }
+        self.partial = bytes.split_off(start);
+        Ok(lines)
+    }
+}
+
+/// What tells one file from another at the same path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileId {
+    #[cfg(unix)]
+    inode: u64,
+    #[cfg(unix)]
+    device: u64,
+    /// Elsewhere only truncation is noticed, through the length.
+    #[cfg(not(unix))]
+    unknown: (),
+}
+
+impl FileId {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Self {
+                inode: metadata.ino(),
+                device: metadata.dev(),
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = metadata;
+            Self { unknown: () }
+        }
+    }
+}
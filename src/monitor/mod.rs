@@ -0,0 +1,139 @@
+//! Spoken alerts from log files.
+//!
+//! `open-tts-rs tail --speak` follows a log file as it grows, through
+//! truncation and rotation, and speaks the lines matching a filter. An
+//! [`Alerter`] keeps a burst of errors from turning into a backlog of
+//! speech: alerts are spaced out, and lines like one spoken recently are
+//! skipped.
+
+mod alert;
+mod follow;
+
+pub use alert::{
+    AlertOptions, Alerter, DEFAULT_DEDUP_WINDOW, DEFAULT_MAX_CHARS, DEFAULT_MIN_INTERVAL, Decision,
+    spoken_text,
+};
+pub use follow::LogFollower;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    // ===========================================
+    // LogFollower tests
+    // ===========================================
+
+    fn append(path: &std::path::Path, text: &str) {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(text.as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_follower_reads_new_complete_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.log");
+        append(&path, "old line\n");
+
+        let mut follower = LogFollower::open(&path, false).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+
+        append(&path, "ERROR one\r\nERROR tw");
+        assert_eq!(follower.poll().unwrap(), ["ERROR one"]);
+        append(&path, "o\n");
+        assert_eq!(follower.poll().unwrap(), ["ERROR two"]);
+
+        assert_eq!(
+            LogFollower::open(&path, true).unwrap().poll().unwrap(),
+            ["old line", "ERROR one", "ERROR two"]
+        );
+    }
+
+    #[test]
+    fn test_follower_survives_truncation_and_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.log");
+
+        let mut follower = LogFollower::open(&path, false).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+        append(&path, "created later\n");
+        assert_eq!(follower.poll().unwrap(), ["created later"]);
+
+        std::fs::write(&path, "").unwrap();
+        append(&path, "truncated\n");
+        assert_eq!(follower.poll().unwrap(), ["truncated"]);
+
+        append(&path, "last before rotation\n");
+        std::fs::rename(&path, temp_dir.path().join("app.log.1")).unwrap();
+        append(&path, "first after rotation\n");
+        assert_eq!(
+            follower.poll().unwrap(),
+            ["last before rotation", "first after rotation"]
+        );
+    }
+
+    // ===========================================
+    // Alerter tests
+    // ===========================================
+
+    #[test]
+    fn test_alerter_filters_dedups_and_rate_limits() {
+        let mut alerter = Alerter::new(AlertOptions {
+            filter: Some(Regex::new("ERROR").unwrap()),
+            ..AlertOptions::default()
+        });
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(alerter.check("INFO all good", at(0)), Decision::Filtered);
+        assert_eq!(
+            alerter.check("2024-05-01T12:00:00Z ERROR disk 91% full", at(0)),
+            Decision::Speak("ERROR disk 91% full".to_string())
+        );
+        // Only the numbers differ
+        assert_eq!(
+            alerter.check("2024-05-01T12:00:01Z ERROR disk 92% full", at(1)),
+            Decision::Duplicate
+        );
+        assert_eq!(
+            alerter.check("ERROR database down", at(2)),
+            Decision::RateLimited
+        );
+        assert_eq!(
+            alerter.check("ERROR cache down", at(3)),
+            Decision::RateLimited
+        );
+        assert_eq!(
+            alerter.check("ERROR queue stalled", at(10)),
+            Decision::Speak("ERROR queue stalled. And 2 more alerts.".to_string())
+        );
+        assert_eq!(
+            alerter.check("ERROR disk 95% full", at(70)),
+            Decision::Speak("ERROR disk 95% full".to_string())
+        );
+    }
+
+    #[test]
+    fn test_spoken_text() {
+        assert_eq!(
+            spoken_text("Mar  4 12:00:01 host app[42]: started", 200),
+            "host app[42]: started"
+        );
+        assert_eq!(
+            spoken_text("[2024-05-01 12:00:00,123] WARN slow", 200),
+            "WARN slow"
+        );
+        assert_eq!(
+            spoken_text("ERROR connection refused by upstream", 20),
+            "ERROR connection..."
+        );
+    }
+}
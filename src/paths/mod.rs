@@ -0,0 +1,159 @@
+//! Paths and file names across platforms.
+//!
+//! Voice names become file names, reference clips are uploaded under their
+//! own names, and outputs are named after their inputs. The helpers here
+//! keep that working on Windows, which reserves device names like `CON`
+//! and `LPT1` and limits paths to `MAX_PATH` unless told otherwise, and
+//! for file names that are not UTF-8.
+
+mod portable;
+
+pub use portable::{
+    check_file_name, check_local_name, long_path, stem_with, upload_name, with_stem_suffix,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    // =========================================================================
+    // File names
+    // =========================================================================
+
+    #[test]
+    fn test_check_file_name_accepts_ordinary_names() {
+        for name in [
+            "narrator",
+            "Amy Smith",
+            "café",
+            "v2.final",
+            "console",
+            "com10",
+            ".hidden",
+        ] {
+            assert!(check_file_name(name).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_check_file_name_rejects_reserved_device_names() {
+        for name in [
+            "CON",
+            "con",
+            "Aux",
+            "nul.wav",
+            "COM1",
+            "lpt9.json",
+            "prn .txt",
+        ] {
+            let err = check_file_name(name).unwrap_err();
+            assert!(err.contains("reserved device name"), "{name}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_check_file_name_rejects_what_windows_cannot_store() {
+        for name in [
+            "",
+            ".",
+            "..",
+            "a/b",
+            r"a\b",
+            "a:b",
+            "what?",
+            "x*",
+            "tab\there",
+            "dot.",
+            "space ",
+        ] {
+            assert!(check_file_name(name).is_err(), "{name:?}");
+        }
+        assert!(check_file_name(&"a".repeat(255)).is_ok());
+        assert!(check_file_name(&"a".repeat(256)).is_err());
+    }
+
+    #[test]
+    fn test_check_local_name() {
+        assert!(check_local_name("narrator").is_ok());
+        assert!(check_local_name("a/b").is_err());
+        assert!(check_local_name("..").is_err());
+        assert_eq!(check_local_name("con").is_err(), cfg!(windows));
+    }
+
+    // =========================================================================
+    // Derived names
+    // =========================================================================
+
+    #[test]
+    fn test_upload_name_keeps_the_extension() {
+        assert_eq!(upload_name(Path::new("/refs/amy.mp3")), "amy.mp3");
+        assert_eq!(upload_name(Path::new("/refs/café.wav")), "café.wav");
+        assert_eq!(upload_name(Path::new("say \"hi\".wav")), "say _hi_.wav");
+        assert_eq!(upload_name(Path::new("/")), "audio.wav");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_names_survive() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/refs/voz-\xe9.flac"));
+        assert_eq!(upload_name(path), "voz-_.flac");
+        assert_eq!(
+            with_stem_suffix(path, ".take2").as_os_str().as_bytes(),
+            b"/refs/voz-\xe9.take2.flac"
+        );
+    }
+
+    #[test]
+    fn test_with_stem_suffix() {
+        assert_eq!(
+            with_stem_suffix(Path::new("out/book.wav"), ".take2"),
+            PathBuf::from("out/book.take2.wav")
+        );
+        assert_eq!(
+            with_stem_suffix(Path::new("book"), "-01"),
+            PathBuf::from("book-01")
+        );
+        assert_eq!(
+            stem_with(Path::new("ch.md"), ".shard-1-of-2"),
+            "ch.shard-1-of-2"
+        );
+    }
+
+    // =========================================================================
+    // Long paths
+    // =========================================================================
+
+    #[test]
+    fn test_verbatim_prefixes_long_windows_paths() {
+        let long = format!(r"C:\Users\amy\{}\book.wav", "a".repeat(260));
+        assert_eq!(portable::verbatim(&long), Some(format!(r"\\?\{long}")));
+
+        let share = format!(r"\\nas\audio\{}.wav", "b".repeat(260));
+        assert_eq!(
+            portable::verbatim(&share),
+            Some(format!(r"\\?\UNC\nas\audio\{}.wav", "b".repeat(260)))
+        );
+
+        let mixed = format!("C:/out/{}.wav", "c".repeat(260));
+        assert_eq!(
+            portable::verbatim(&mixed),
+            Some(format!(r"\\?\C:\out\{}.wav", "c".repeat(260)))
+        );
+    }
+
+    #[test]
+    fn test_verbatim_leaves_other_paths_alone() {
+        assert_eq!(portable::verbatim(r"C:\short\book.wav"), None);
+        let prefixed = format!(r"\\?\C:\{}", "a".repeat(260));
+        assert_eq!(portable::verbatim(&prefixed), None);
+        assert_eq!(portable::verbatim(&"relative\\".repeat(40)), None);
+        if !cfg!(windows) {
+            let long = PathBuf::from(format!("/tmp/{}", "a".repeat(300)));
+            assert_eq!(long_path(&long), long.as_path());
+        }
+    }
+}
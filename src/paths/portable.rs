@@ -0,0 +1,136 @@
+//! File names and paths that hold on every platform.
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Longest file name most file systems accept, in bytes.
+const MAX_NAME: usize = 255;
+
+/// Longest path Windows programs accept without the `\\?\` prefix,
+/// counting the terminating NUL.
+const MAX_PATH: usize = 260;
+
+/// Device names Windows reserves in every directory, whatever the
+/// extension: `con.wav` opens the console, not a file.
+const RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Characters Windows does not allow in a file name.
+const FORBIDDEN: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Check that `name` can be used as a file name on Windows, macOS, and
+/// Linux alike, returning why not otherwise.
+pub fn check_file_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("name cannot be empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("`{name}` is not a file name"));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| FORBIDDEN.contains(c) || c.is_control())
+    {
+        return Err(format!("name cannot contain {c:?}"));
+    }
+    if name.ends_with(['.', ' ']) {
+        return Err("name cannot end with a dot or a space".to_string());
+    }
+    // Windows ignores the extension and trailing spaces when matching
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+        return Err(format!("`{stem}` is a reserved device name on Windows"));
+    }
+    if name.len() > MAX_NAME {
+        return Err(format!("name is longer than {MAX_NAME} bytes"));
+    }
+    Ok(())
+}
+
+/// Check that `name` is a single file name that can be opened on this
+/// platform. Stricter on Windows, where [`check_file_name`] applies; a
+/// name that is valid elsewhere keeps working on the platform it was made.
+pub fn check_local_name(name: &str) -> Result<(), String> {
+    if cfg!(windows) {
+        return check_file_name(name);
+    }
+    if name.is_empty() {
+        return Err("name cannot be empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("`{name}` is not a file name"));
+    }
+    if name.contains(['/', '\\', '\0']) {
+        return Err("name cannot contain path separators".to_string());
+    }
+    Ok(())
+}
+
+/// The file name of `path` for an upload form. Bytes that are not UTF-8
+/// and characters that break a `Content-Disposition` header become `_`,
+/// so the extension the server goes by is kept.
+pub fn upload_name(path: &Path) -> String {
+    let Some(name) = path.file_name() else {
+        return "audio.wav".to_string();
+    };
+    name.to_string_lossy()
+        .chars()
+        .map(|c| match c {
+            char::REPLACEMENT_CHARACTER | '"' | '\\' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// The file stem of `path` followed by `suffix`, without going through a
+/// string, so a stem that is not UTF-8 comes through unchanged.
+pub fn stem_with(path: &Path, suffix: &str) -> OsString {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    name
+}
+
+/// `path` with `suffix` between its stem and extension: `book.wav` and
+/// `.take2` give `book.take2.wav`.
+pub fn with_stem_suffix(path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = stem_with(path, suffix);
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// `path` in a form an external program can open however long it is. On
+/// Windows an absolute path past `MAX_PATH` gets the `\\?\` prefix (or
+/// `\\?\UNC\` for a network share); elsewhere, and for shorter paths, it
+/// is unchanged.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    if cfg!(windows)
+        && let Ok(absolute) = std::path::absolute(path)
+        && let Some(verbatim) = absolute.to_str().and_then(verbatim)
+    {
+        return Cow::Owned(verbatim.into());
+    }
+    Cow::Borrowed(path)
+}
+
+/// The `\\?\` form of a Windows path too long for `MAX_PATH`, or `None`
+/// if it needs none or cannot take one.
+pub(crate) fn verbatim(path: &str) -> Option<String> {
+    if path.len() < MAX_PATH || path.starts_with(r"\\?\") {
+        return None;
+    }
+    // Verbatim paths are not normalised, so only backslashes will do
+    let path = path.replace('/', r"\");
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return Some(format!(r"\\?\UNC\{share}"));
+    }
+    let drive = path.as_bytes();
+    let absolute = drive.len() > 2 && drive[0].is_ascii_alphabetic() && &drive[1..3] == br":\";
+    absolute.then(|| format!(r"\\?\{path}"))
+}
@@ -0,0 +1,327 @@
+//! Building a project's chapters, reusing cached chunk audio.
+//!
+//! Each speech chunk is cached under a key covering everything that shapes
+//! its audio: model, language, voice (and when that voice was extracted),
+//! speed, and text. A chapter is only rebuilt when the keys of its chunks
+//! change, and then only the changed chunks are synthesized again.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::ProjectError;
+use super::workspace::{ChapterSource, Project};
+use crate::audio::{AudioBuffer, Segment, assemble};
+use crate::backend::{Backend, Model};
+use crate::batch::in_parallel;
+use crate::engine::TTSEngine;
+use crate::text::{Chunk, Document, Preprocessor, chunk_text, pace};
+
+/// What a build did with one chapter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChapterReport {
+    pub output: PathBuf,
+    /// False when the output was already up to date.
+    pub built: bool,
+    /// Speech chunks synthesized by this build.
+    pub synthesized: Vec<Chunk>,
+    /// Seconds of audio synthesized by this build.
+    pub seconds: f64,
+    /// Speech chunks taken from the cache.
+    pub cached: usize,
+}
+
+impl ChapterReport {
+    fn new(output: PathBuf, built: bool) -> Self {
+        Self {
+            output,
+            built,
+            synthesized: Vec::new(),
+            seconds: 0.0,
+            cached: 0,
+        }
+    }
+}
+
+/// How a build runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuildOptions {
+    /// Sentences synthesized at once.
+    pub workers: usize,
+    /// Start no chapter after this; the rest are left for the next build.
+    pub until: Option<Instant>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            workers: 1,
+            until: None,
+        }
+    }
+}
+
+/// Cache key each output was last built from, keyed by output path.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BuildState {
+    outputs: BTreeMap<PathBuf, String>,
+}
+
+impl BuildState {
+    fn path(project: &Project) -> PathBuf {
+        project.cache_dir().join("build.json")
+    }
+
+    fn load(project: &Project) -> Result<Self, ProjectError> {
+        match std::fs::read_to_string(Self::path(project)) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, project: &Project) -> Result<(), ProjectError> {
+        std::fs::create_dir_all(project.cache_dir())?;
+        std::fs::write(Self::path(project), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Build every chapter of `project` whose sources or settings changed
+/// since the last build, calling `on_chapter` as each one finishes.
+/// Chapters not reached by [`BuildOptions::until`] get no report.
+pub fn build_project<B: Backend>(
+    engine: &TTSEngine<B>,
+    project: &Project,
+    model: Model,
+    options: &BuildOptions,
+    mut on_chapter: impl FnMut(&ChapterReport),
+) -> Result<Vec<ChapterReport>, ProjectError> {
+    let preprocessor = project.preprocessor()?;
+    let mut state = BuildState::load(project)?;
+    let mut reports = Vec::new();
+
+    for chapter in &project.file().chapters {
+        if options.until.is_some_and(|until| Instant::now() >= until) {
+            break;
+        }
+        let text = project.source_text(chapter)?;
+        let document = Document::parse(&text)?;
+        let chunks = chapter_chunks(project, chapter, &document, &preprocessor)?;
+        engine.check_chunks(&chunks)?;
+
+        let keys = chunks
+            .iter()
+            .map(|chunk| chunk_key(engine, project, model, chunk))
+            .collect::<Result<Vec<_>, _>>()?;
+        let chapter_key = hex(keys.join("\n").as_bytes());
+
+        let output = project.output_path(chapter);
+        let relative = output
+            .strip_prefix(project.root())
+            .unwrap_or(&output)
+            .to_path_buf();
+        let report = if output.exists() && state.outputs.get(&relative) == Some(&chapter_key) {
+            ChapterReport::new(output, false)
+        } else {
+            let report = build_chapter(engine, project, &chunks, &keys, output, options.workers)?;
+            state.outputs.insert(relative, chapter_key);
+            state.save(project)?;
+            report
+        };
+
+        on_chapter(&report);
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+/// Split a chapter into sentence chunks, paced and with project voices
+/// resolved. The chapter's voice comes first, then its front matter's,
+/// then the project's.
+fn chapter_chunks(
+    project: &Project,
+    chapter: &ChapterSource,
+    document: &Document,
+    preprocessor: &Preprocessor,
+) -> Result<Vec<Chunk>, ProjectError> {
+    let file = project.file();
+    let voice = chapter
+        .voice
+        .as_deref()
+        .or(document.voice.as_deref())
+        .or(file.voice.as_deref());
+    let text = preprocessor.process(&document.text);
+    let chunks = chunk_text(&text, voice, file.speed.unwrap_or(1.0))?;
+
+    Ok(pace(chunks, file.pacing()?)
+        .into_iter()
+        .map(|chunk| match chunk {
+            Chunk::Speech { text, voice, speed } => Chunk::Speech {
+                text,
+                voice: voice.map(|v| project.resolve_voice(&v)),
+                speed,
+            },
+            pause => pause,
+        })
+        .collect())
+}
+
+/// Key identifying the audio a chunk produces.
+fn chunk_key<B: Backend>(
+    engine: &TTSEngine<B>,
+    project: &Project,
+    model: Model,
+    chunk: &Chunk,
+) -> Result<String, ProjectError> {
+    let (text, voice, speed) = match chunk {
+        Chunk::Speech { text, voice, speed } => (text, voice, speed),
+        Chunk::Pause(duration) => return Ok(format!("pause:{}", duration.as_millis())),
+        Chunk::Bleep(duration) => return Ok(format!("bleep:{}", duration.as_millis())),
+    };
+
+    // Re-extracting a voice under the same name changes its creation time
+    let extracted = match voice {
+        Some(name) => {
+            engine
+                .voice_manager()
+                .load_metadata(name)
+                .map_err(|_| ProjectError::VoiceNotFound(name.clone()))?
+                .created_at
+        }
+        None => String::new(),
+    };
+    let fields = [
+        model.as_str(),
+        project.file().language.as_deref().unwrap_or_default(),
+        voice.as_deref().unwrap_or_default(),
+        &extracted,
+        &format!("{speed:.3}"),
+        text,
+    ];
+    Ok(hex(fields.join("\0").as_bytes()))
+}
+
+/// Synthesize the chunks of a chapter missing from the cache, assemble
+/// the chapter from fresh and cached clips, and write it to `output`.
+fn build_chapter<B: Backend>(
+    engine: &TTSEngine<B>,
+    project: &Project,
+    chunks: &[Chunk],
+    keys: &[String],
+    output: PathBuf,
+    workers: usize,
+) -> Result<ChapterReport, ProjectError> {
+    let cache = project.cache_dir().join("chunks");
+    std::fs::create_dir_all(&cache)?;
+    let mut fresh = synthesize_missing(engine, chunks, keys, &cache, workers)?;
+
+    let mut report = ChapterReport::new(output, true);
+    let segments = chunks
+        .iter()
+        .zip(keys)
+        .enumerate()
+        .map(|(i, (chunk, key))| {
+            chunk_segment(
+                chunk,
+                fresh.remove(&i),
+                &cached_clip(&cache, key),
+                &mut report,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(dir) = report.output.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    write_atomic(&report.output, &assemble(segments)?.to_wav_bytes()?)?;
+    Ok(report)
+}
+
+/// Where the clip with cache key `key` is kept.
+fn cached_clip(cache: &Path, key: &str) -> PathBuf {
+    cache.join(format!("{key}.wav"))
+}
+
+/// Synthesize the speech chunks missing from `cache`, each text once,
+/// `workers` at a time, and cache them. Returns the clips by chunk index.
+fn synthesize_missing<B: Backend>(
+    engine: &TTSEngine<B>,
+    chunks: &[Chunk],
+    keys: &[String],
+    cache: &Path,
+    workers: usize,
+) -> Result<BTreeMap<usize, AudioBuffer>, ProjectError> {
+    let mut seen = BTreeSet::new();
+    let missing: Vec<(usize, &Chunk)> = chunks
+        .iter()
+        .enumerate()
+        .filter(|(i, chunk)| {
+            matches!(chunk, Chunk::Speech { .. })
+                && !cached_clip(cache, &keys[*i]).exists()
+                && seen.insert(&keys[*i])
+        })
+        .collect();
+    let clips = in_parallel(&missing, workers, |&(i, chunk)| {
+        let Chunk::Speech { text, voice, speed } = chunk else {
+            return Ok(None);
+        };
+        let wav = engine.synthesize(text, voice.clone(), *speed)?;
+        let clip = AudioBuffer::from_wav_bytes(&wav)?;
+        write_atomic(&cached_clip(cache, &keys[i]), &wav)?;
+        Ok::<_, ProjectError>(Some(clip))
+    });
+
+    let mut fresh = BTreeMap::new();
+    for ((i, _), clip) in missing.into_iter().zip(clips) {
+        if let Some(clip) = clip? {
+            fresh.insert(i, clip);
+        }
+    }
+    Ok(fresh)
+}
+
+/// The segment `chunk` contributes to its chapter: its `fresh` clip when
+/// this build synthesized it, or else the one at `cached`. Counted in
+/// `report` either way.
+fn chunk_segment(
+    chunk: &Chunk,
+    fresh: Option<AudioBuffer>,
+    cached: &Path,
+    report: &mut ChapterReport,
+) -> Result<Segment, ProjectError> {
+    match chunk {
+        Chunk::Pause(duration) => return Ok(Segment::Silence(*duration)),
+        Chunk::Bleep(duration) => return Ok(Segment::Bleep(*duration)),
+        Chunk::Speech { .. } => {}
+    }
+    let clip = match fresh {
+        Some(clip) => {
+            report.synthesized.push(chunk.clone());
+            report.seconds += clip.duration().as_secs_f64();
+            clip
+        }
+        None => {
+            report.cached += 1;
+            AudioBuffer::from_wav_bytes(&std::fs::read(cached)?)?
+        }
+    };
+    Ok(Segment::Clip(clip))
+}
+
+/// Write through a temporary file, so an interrupted build never leaves a
+/// truncated file that looks complete.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<(), ProjectError> {
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, data)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+fn hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
@@ -0,0 +1,550 @@
+//! Project workspaces.
+//!
+//! A project is a directory with a `project.toml` pinning the model,
+//! voices, lexicon, normalization rules, and chapter sources of a long
+//! piece of work such as an audiobook. `open-tts-rs build` regenerates
+//! only the chapters whose inputs changed, synthesizing only the changed
+//! sentences, so rebuilding after an edit is quick and reproducible.
+//! `open-tts-rs schedule` runs builds at off-hours on a [`Schedule`].
+
+mod build;
+mod schedule;
+mod workspace;
+
+pub use build::{BuildOptions, ChapterReport, build_project};
+pub use schedule::{BuildReport, ChapterSummary, Schedule, ScheduleConfig, parse_time_of_day};
+pub use workspace::{ChapterSource, PROJECT_FILE, Project, ProjectFile};
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::audio::AudioError;
+use crate::engine::TTSError;
+use crate::ingest::IngestError;
+use crate::text::TextError;
+
+/// Errors that can occur when loading or building a project.
+#[derive(Error, Debug)]
+pub enum ProjectError {
+    #[error("No project.toml in {0} or its parents")]
+    NotFound(PathBuf),
+
+    #[error("Invalid project file: {0}")]
+    ParseError(#[from] toml::de::Error),
+
+    #[error("Invalid project file: {0}")]
+    Invalid(String),
+
+    #[error("Failed to read chapter source {0}: {1}")]
+    Source(PathBuf, IngestError),
+
+    #[error("Voice not found: {0} (map it under [voices] in project.toml)")]
+    VoiceNotFound(String),
+
+    #[error("Text error: {0}")]
+    TextError(#[from] TextError),
+
+    #[error("Synthesis error: {0}")]
+    EngineError(#[from] TTSError),
+
+    #[error("Audio processing error: {0}")]
+    AudioError(#[from] AudioError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+    use crate::backend::{MockBackend, Model, mock_backend};
+    use crate::engine::TTSEngine;
+    use crate::voice::{VoiceManager, VoiceMetadata};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    const PROJECT: &str = r#"
+name = "moby"
+model = "of"
+voice = "narrator"
+speed = 1.1
+
+[voices]
+narrator = "amy-2024"
+
+[lexicon]
+Ahab = "Ay-hab"
+
+[glossary]
+USS = "U S S"
+
+[[chapter]]
+source = "text/01.txt"
+
+[[chapter]]
+source = "text/02.txt"
+output = "two/chapter-2.wav"
+"#;
+
+    fn write_project(root: &Path, project: &str) {
+        std::fs::create_dir_all(root.join("text")).unwrap();
+        std::fs::write(root.join(PROJECT_FILE), project).unwrap();
+        std::fs::write(root.join("text/01.txt"), "Call me Ishmael. Ahab waits.").unwrap();
+        std::fs::write(root.join("text/02.txt"), "The whale.").unwrap();
+    }
+
+    fn save_voice(manager: &VoiceManager, name: &str, created_at: &str) {
+        manager
+            .save_metadata(&VoiceMetadata {
+                name: name.to_string(),
+                transcript: "Reference".to_string(),
+                model: "openf5".to_string(),
+                created_at: created_at.to_string(),
+                audio_path: None,
+                language: None,
+                consent: None,
+                locked: false,
+                allowed_uses: Vec::new(),
+                synthetic: false,
+                license: None,
+            })
+            .unwrap();
+    }
+
+    /// An engine whose backend returns 0.1 s of audio and logs each text.
+    fn engine(temp_dir: &TempDir, texts: Arc<Mutex<Vec<String>>>) -> TTSEngine<MockBackend> {
+        let mut backend = mock_backend();
+        backend.expect_synthesize().returning(move |req| {
+            texts.lock().unwrap().push(req.text.clone());
+            Ok(AudioBuffer::new(vec![0.1; 100], 1000, 1)
+                .to_wav_bytes()
+                .unwrap())
+        });
+        let voices = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        save_voice(&voices, "amy-2024", "2024-01-01T00:00:00Z");
+        TTSEngine::new(backend, voices)
+    }
+
+    // ===========================================
+    // Project file tests
+    // ===========================================
+
+    #[test]
+    fn test_project_parse() {
+        let file = Project::parse(PROJECT).unwrap();
+        assert_eq!(file.model, Some(Model::OpenF5));
+        assert_eq!(file.voices["narrator"], "amy-2024");
+        assert_eq!(file.chapters.len(), 2);
+        assert_eq!(file.chapters[1].output, Some("two/chapter-2.wav".into()));
+    }
+
+    #[test]
+    fn test_project_parse_rejects_unknown_keys_and_bad_speed() {
+        assert!(matches!(
+            Project::parse("voise = \"amy\""),
+            Err(ProjectError::ParseError(_))
+        ));
+        assert!(matches!(
+            Project::parse("speed = 3.0"),
+            Err(ProjectError::Invalid(_))
+        ));
+        assert!(matches!(
+            Project::parse("[glossary]\n\" \" = \"blank\""),
+            Err(ProjectError::TextError(_))
+        ));
+        assert!(matches!(
+            Project::parse("paragraph_pause = \"soon\""),
+            Err(ProjectError::TextError(_))
+        ));
+    }
+
+    #[test]
+    fn test_project_find_searches_parents() {
+        let temp_dir = TempDir::new().unwrap();
+        write_project(temp_dir.path(), PROJECT);
+
+        let project = Project::find(&temp_dir.path().join("text")).unwrap();
+        assert_eq!(project.root(), temp_dir.path());
+        assert_eq!(project.name().as_deref(), Some("moby"));
+
+        let elsewhere = TempDir::new().unwrap();
+        assert!(matches!(
+            Project::find(elsewhere.path()),
+            Err(ProjectError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_project_paths_and_preprocessor() {
+        let temp_dir = TempDir::new().unwrap();
+        write_project(temp_dir.path(), PROJECT);
+        let project = Project::open(temp_dir.path()).unwrap();
+        let chapters = &project.file().chapters;
+
+        let build = temp_dir.path().join("build");
+        assert_eq!(project.output_path(&chapters[0]), build.join("01.wav"));
+        assert_eq!(
+            project.output_path(&chapters[1]),
+            build.join("two/chapter-2.wav")
+        );
+        assert_eq!(project.resolve_voice("narrator"), "amy-2024");
+        assert_eq!(project.resolve_voice("bob"), "bob");
+        assert_eq!(
+            project.preprocessor().unwrap().process("Ahab's leg"),
+            "Ay-hab's leg"
+        );
+        assert_eq!(
+            project.preprocessor().unwrap().process("The USS Pequod"),
+            "The U S S Pequod"
+        );
+    }
+
+    // ===========================================
+    // build_project tests
+    // ===========================================
+
+    #[test]
+    fn test_build_project_rebuilds_only_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("moby");
+        write_project(&root, PROJECT);
+        let project = Project::open(&root).unwrap();
+        let texts = Arc::new(Mutex::new(Vec::new()));
+        let engine = engine(&temp_dir, texts.clone());
+
+        let reports = build_project(
+            &engine,
+            &project,
+            Model::OpenF5,
+            &BuildOptions::default(),
+            |_| {},
+        )
+        .unwrap();
+        assert!(reports.iter().all(|r| r.built));
+        assert_eq!(
+            *texts.lock().unwrap(),
+            vec!["Call me Ishmael.", "Ay-hab waits.", "The whale."]
+        );
+        assert!(root.join("build/two/chapter-2.wav").exists());
+        let audio = AudioBuffer::from_wav_bytes(&std::fs::read(root.join("build/01.wav")).unwrap())
+            .unwrap();
+        assert_eq!(audio.samples.len(), 200);
+
+        // Nothing changed
+        texts.lock().unwrap().clear();
+        let reports = build_project(
+            &engine,
+            &project,
+            Model::OpenF5,
+            &BuildOptions::default(),
+            |_| {},
+        )
+        .unwrap();
+        assert!(reports.iter().all(|r| !r.built));
+        assert!(texts.lock().unwrap().is_empty());
+
+        // One sentence edited: only it is synthesized
+        std::fs::write(root.join("text/01.txt"), "Call me Ishmael. Ahab rages.").unwrap();
+        let chapters = AtomicUsize::new(0);
+        let reports = build_project(
+            &engine,
+            &project,
+            Model::OpenF5,
+            &BuildOptions::default(),
+            |_| {
+                chapters.fetch_add(1, Ordering::Relaxed);
+            },
+        )
+        .unwrap();
+        assert_eq!(chapters.load(Ordering::Relaxed), 2);
+        assert_eq!(*texts.lock().unwrap(), vec!["Ay-hab rages."]);
+        assert!(reports[0].built);
+        assert_eq!(reports[0].cached, 1);
+        assert_eq!(reports[0].synthesized.len(), 1);
+        assert!((reports[0].seconds - 0.1).abs() < 1e-9);
+        assert!(!reports[1].built);
+    }
+
+    #[test]
+    fn test_build_project_rebuilds_when_voice_is_re_extracted() {
+        let temp_dir = TempDir::new().unwrap();
+        write_project(temp_dir.path(), PROJECT);
+        let project = Project::open(temp_dir.path()).unwrap();
+        let texts = Arc::new(Mutex::new(Vec::new()));
+        let engine = engine(&temp_dir, texts.clone());
+
+        build_project(
+            &engine,
+            &project,
+            Model::OpenF5,
+            &BuildOptions::default(),
+            |_| {},
+        )
+        .unwrap();
+        save_voice(engine.voice_manager(), "amy-2024", "2025-06-01T00:00:00Z");
+        texts.lock().unwrap().clear();
+
+        let reports = build_project(
+            &engine,
+            &project,
+            Model::OpenF5,
+            &BuildOptions::default(),
+            |_| {},
+        )
+        .unwrap();
+        assert!(reports.iter().all(|r| r.built && r.cached == 0));
+        assert_eq!(texts.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_build_project_unknown_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        write_project(
+            temp_dir.path(),
+            &PROJECT.replace("narrator = \"amy-2024\"", "narrator = \"nobody\""),
+        );
+        let project = Project::open(temp_dir.path()).unwrap();
+        let engine = engine(&temp_dir, Arc::new(Mutex::new(Vec::new())));
+
+        let err = build_project(
+            &engine,
+            &project,
+            Model::OpenF5,
+            &BuildOptions::default(),
+            |_| {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ProjectError::VoiceNotFound(name) if name == "nobody"));
+    }
+
+    #[test]
+    fn test_build_project_in_parallel_until_a_deadline() {
+        let temp_dir = TempDir::new().unwrap();
+        write_project(temp_dir.path(), PROJECT);
+        std::fs::write(
+            temp_dir.path().join("text/01.txt"),
+            "One. Two. Three. Two. Four.",
+        )
+        .unwrap();
+        let project = Project::open(temp_dir.path()).unwrap();
+        let texts = Arc::new(Mutex::new(Vec::new()));
+        let engine = engine(&temp_dir, texts.clone());
+
+        // The window has already closed: no chapter is started
+        let closed = BuildOptions {
+            workers: 2,
+            until: Some(std::time::Instant::now()),
+        };
+        let reports = build_project(&engine, &project, Model::OpenF5, &closed, |_| {}).unwrap();
+        assert!(reports.is_empty());
+        assert!(texts.lock().unwrap().is_empty());
+
+        let options = BuildOptions {
+            workers: 2,
+            until: None,
+        };
+        let reports = build_project(&engine, &project, Model::OpenF5, &options, |_| {}).unwrap();
+        assert_eq!(reports.len(), 2);
+        // The repeated sentence is synthesized once
+        let mut synthesized = texts.lock().unwrap().clone();
+        synthesized.sort();
+        assert_eq!(
+            synthesized,
+            vec!["Four.", "One.", "The whale.", "Three.", "Two."]
+        );
+        let audio =
+            AudioBuffer::from_wav_bytes(&std::fs::read(&reports[0].output).unwrap()).unwrap();
+        assert_eq!(audio.samples.len(), 500);
+    }
+
+    // ===========================================
+    // Schedule tests
+    // ===========================================
+
+    fn at(date: &str) -> chrono::NaiveDateTime {
+        chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_schedule_next_after() {
+        // 2026-10-16 is a Friday
+        let weekdays: Schedule = "0 2 * * 1-5".parse().unwrap();
+        assert_eq!(
+            weekdays.next_after(at("2026-10-15 23:00")),
+            Some(at("2026-10-16 02:00"))
+        );
+        assert_eq!(
+            weekdays.next_after(at("2026-10-16 02:00")),
+            Some(at("2026-10-19 02:00"))
+        );
+
+        let quarters: Schedule = "*/15 22-23 * * *".parse().unwrap();
+        assert_eq!(
+            quarters.next_after(at("2026-10-16 22:07")),
+            Some(at("2026-10-16 22:15"))
+        );
+        assert_eq!(
+            quarters.next_after(at("2026-10-16 23:45")),
+            Some(at("2026-10-17 22:00"))
+        );
+
+        // Day of month or day of week, as in cron; 7 is Sunday
+        let either: Schedule = "30 3 1 * 7".parse().unwrap();
+        assert_eq!(
+            either.next_after(at("2026-10-16 12:00")),
+            Some(at("2026-10-18 03:30"))
+        );
+        assert_eq!(
+            either.next_after(at("2026-10-25 12:00")),
+            Some(at("2026-11-01 03:30"))
+        );
+
+        let daily = Schedule::daily(parse_time_of_day("02:00").unwrap());
+        assert_eq!(daily, "0 2 * * *".parse().unwrap());
+        assert_eq!(
+            "@weekly".parse::<Schedule>().unwrap().to_string(),
+            "@weekly"
+        );
+        assert_eq!(
+            "0 0 30 2 *"
+                .parse::<Schedule>()
+                .unwrap()
+                .next_after(at("2026-01-01 00:00")),
+            None
+        );
+
+        for bad in [
+            "0 2 * *",
+            "60 2 * * *",
+            "0 2 * * 8",
+            "0 5-2 * * *",
+            "*/0 * * * *",
+        ] {
+            assert!(bad.parse::<Schedule>().is_err(), "{bad}");
+        }
+        assert!(parse_time_of_day("2am").is_err());
+    }
+
+    #[test]
+    fn test_project_schedule_config() {
+        let file =
+            Project::parse("[schedule]\nat = \"02:00\"\nuntil = \"06:00\"\nworkers = 2\n").unwrap();
+        assert_eq!(
+            file.schedule.schedule().unwrap(),
+            Some("0 2 * * *".parse().unwrap())
+        );
+        assert_eq!(
+            file.schedule.until().unwrap(),
+            parse_time_of_day("06:00").ok()
+        );
+        assert_eq!(file.schedule.workers, Some(2));
+        assert_eq!(Project::parse("").unwrap().schedule.schedule(), Ok(None));
+
+        for bad in [
+            "[schedule]\nat = \"02:00\"\ncron = \"@daily\"",
+            "[schedule]\ncron = \"0 2 * *\"",
+            "[schedule]\nuntil = \"dawn\"",
+        ] {
+            assert!(
+                matches!(Project::parse(bad), Err(ProjectError::Invalid(_))),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = ChapterReport {
+            output: temp_dir.path().join("build/01.wav"),
+            built: true,
+            synthesized: Vec::new(),
+            cached: 2,
+            seconds: 1.5,
+        };
+        let summary = ChapterSummary::new(&report, temp_dir.path());
+        assert_eq!(summary.output, Path::new("build/01.wav"));
+
+        let report = BuildReport {
+            project: Some("moby".to_string()),
+            started_at: "2026-10-16T02:00:00+00:00".to_string(),
+            finished_at: "2026-10-16T02:10:00+00:00".to_string(),
+            chapters: vec![
+                summary.clone(),
+                ChapterSummary {
+                    built: false,
+                    seconds: 0.0,
+                    ..summary
+                },
+            ],
+            deferred: vec!["build/03.wav".into()],
+            error: None,
+        };
+        assert_eq!(report.built(), 1);
+        assert!((report.seconds() - 1.5).abs() < 1e-9);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("error"));
+        assert_eq!(serde_json::from_str::<BuildReport>(&json).unwrap(), report);
+    }
+
+    #[test]
+    fn test_project_chapters_of_a_manuscript() {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file(
+            "word/document.xml",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        let paragraphs: String = ["Chapter 1", "Call me Ishmael.", "Chapter 2", "The whale."]
+            .iter()
+            .map(|text| format!("<w:p><w:r><w:t>{text}</w:t></w:r></w:p>"))
+            .collect();
+        write!(
+            zip,
+            r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{paragraphs}</w:body></w:document>"#
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("moby.docx"),
+            zip.finish().unwrap().into_inner(),
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join(PROJECT_FILE),
+            "[[chapter]]\nsource = \"moby.docx\"\nchapter = 2\n\n\
+             [[chapter]]\nsource = \"moby.docx\"\nchapter = 3\n",
+        )
+        .unwrap();
+        let project = Project::open(temp_dir.path()).unwrap();
+        let chapters = &project.file().chapters;
+
+        assert_eq!(
+            project.output_path(&chapters[0]),
+            temp_dir.path().join("build/moby-02.wav")
+        );
+        assert_eq!(
+            project.source_text(&chapters[0]).unwrap(),
+            "# Chapter 2\n\nThe whale."
+        );
+        assert!(matches!(
+            project.source_text(&chapters[1]),
+            Err(ProjectError::Source(
+                _,
+                IngestError::NoChapter {
+                    chapter: 3,
+                    found: 2
+                }
+            ))
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+//! Scheduled builds: when they run and what they report.
+//!
+//! `open-tts-rs schedule` waits for each time a [`Schedule`] names, builds
+//! the project, and writes a [`BuildReport`]. Times are local, so a build
+//! set for `02:00` runs at two in the morning wherever the machine is.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+use serde::{Deserialize, Serialize};
+
+use super::ChapterReport;
+
+/// Times of a cron expression: minute, hour, day of month, month, and day
+/// of week, each `*`, a number, a range `a-b`, a step `*/n` or `a-b/n`, or
+/// a comma-separated list of those. `@hourly`, `@daily`, `@weekly`, and
+/// `@monthly` are accepted too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    spec: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of month or the day of week was restricted; when
+    /// both are, a day matching either runs, as in cron.
+    days_set: bool,
+    weekdays_set: bool,
+}
+
+impl Schedule {
+    /// Every day at `time`.
+    pub fn daily(time: NaiveTime) -> Self {
+        format!("{} {} * * *", time.minute(), time.hour())
+            .parse()
+            .expect("a daily schedule is a valid cron expression")
+    }
+
+    /// The first time after `after` the schedule names, to the minute, if
+    /// any within the next four years.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let end = after + TimeDelta::days(4 * 366);
+        while time < end {
+            if !self.matches_day(time) {
+                time = time.date().succ_opt()?.and_time(NaiveTime::MIN);
+            } else if !bit(self.hours, time.hour()) {
+                time = time.with_minute(0)? + TimeDelta::hours(1);
+            } else if !bit(self.minutes, time.minute()) {
+                time += TimeDelta::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, time: NaiveDateTime) -> bool {
+        if !bit(self.months, time.month()) {
+            return false;
+        }
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+        match (self.days_set, self.weekdays_set) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+fn bit(set: u64, n: u32) -> bool {
+    set & (1 << n) != 0
+}
+
+/// Parse one cron field into a bit set of the values it names.
+fn field(spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|&s| s > 0)
+                    .ok_or_else(|| format!("invalid step in '{part}'"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let number = |s: &str| -> Result<u32, String> {
+            s.parse()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("'{s}' is not a number from {min} to {max}"))
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (number(first)?, number(last)?),
+            // A single value with a step runs from it to the end
+            None if step > 1 => (number(range)?, max),
+            None => {
+                let n = number(range)?;
+                (n, n)
+            }
+        };
+        if first > last {
+            return Err(format!("range '{range}' runs backwards"));
+        }
+        for n in (first..=last).step_by(step as usize) {
+            set |= 1 << n;
+        }
+    }
+    Ok(set)
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spec = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            spec => spec,
+        };
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields (minute hour day month weekday), got '{s}'"
+            ));
+        };
+        let mut weekdays = field(weekday, 0, 7)?;
+        // 7 is Sunday too
+        if bit(weekdays, 7) {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            spec: s.trim().to_string(),
+            minutes: field(minute, 0, 59)?,
+            hours: field(hour, 0, 23)?,
+            days: field(day, 1, 31)?,
+            months: field(month, 1, 12)?,
+            weekdays,
+            days_set: day != "*",
+            weekdays_set: weekday != "*",
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+/// Parse a local time of day such as `02:00`.
+pub fn parse_time_of_day(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| format!("expected a time such as 02:00, got '{s}'"))
+}
+
+/// `[schedule]` in `project.toml`: when `open-tts-rs schedule` builds the
+/// project. Command-line options override it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    /// Cron expression, such as `0 2 * * 1-5`.
+    pub cron: Option<String>,
+
+    /// Local time to build every day, such as `02:00`.
+    pub at: Option<String>,
+
+    /// Local time after which no more chapters are started, such as
+    /// `06:00`; the rest wait for the next build.
+    pub until: Option<String>,
+
+    /// Sentences synthesized at once.
+    pub workers: Option<usize>,
+}
+
+impl ScheduleConfig {
+    /// The schedule `cron` or `at` gives, if either.
+    pub fn schedule(&self) -> Result<Option<Schedule>, String> {
+        match (&self.cron, &self.at) {
+            (Some(_), Some(_)) => Err("give cron or at, not both".to_string()),
+            (Some(cron), None) => cron.parse().map(Some),
+            (None, Some(at)) => parse_time_of_day(at).map(|time| Some(Schedule::daily(time))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The time `until` gives, if set.
+    pub fn until(&self) -> Result<Option<NaiveTime>, String> {
+        self.until.as_deref().map(parse_time_of_day).transpose()
+    }
+}
+
+/// What one chapter of a scheduled build did.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterSummary {
+    /// Output file, relative to the project.
+    pub output: PathBuf,
+    /// False when the output was already up to date.
+    pub built: bool,
+    pub synthesized: usize,
+    pub cached: usize,
+    /// Seconds of audio synthesized.
+    pub seconds: f64,
+}
+
+impl ChapterSummary {
+    /// Summarize `report`, naming its output relative to `root`.
+    pub fn new(report: &ChapterReport, root: &Path) -> Self {
+        Self {
+            output: report
+                .output
+                .strip_prefix(root)
+                .unwrap_or(&report.output)
+                .to_path_buf(),
+            built: report.built,
+            synthesized: report.synthesized.len(),
+            cached: report.cached,
+            seconds: report.seconds,
+        }
+    }
+}
+
+/// The completion report of a scheduled build.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildReport {
+    pub project: Option<String>,
+    /// RFC 3339 times the build started and finished.
+    pub started_at: String,
+    pub finished_at: String,
+    pub chapters: Vec<ChapterSummary>,
+    /// Chapters left for the next build because the window closed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deferred: Vec<PathBuf>,
+    /// Why the build stopped, if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BuildReport {
+    /// Chapters built, rather than found up to date.
+    pub fn built(&self) -> usize {
+        self.chapters.iter().filter(|c| c.built).count()
+    }
+
+    /// Seconds of audio synthesized across the build.
+    pub fn seconds(&self) -> f64 {
+        self.chapters.iter().map(|c| c.seconds).sum()
+    }
+}
@@ -0,0 +1,252 @@
+//! The `project.toml` file and the settings it pins.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::ProjectError;
+use super::schedule::ScheduleConfig;
+use crate::backend::Model;
+use crate::ingest::{read_chapter, read_text};
+use crate::text::{
+    EmojiMode, Glossary, Locale, MarkupOptions, Pacing, Preprocessor, ReplaceRule, ReplaceRules,
+    TextError, parse_duration,
+};
+
+/// Name of the file marking a project directory.
+pub const PROJECT_FILE: &str = "project.toml";
+
+/// Settings read from `project.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectFile {
+    /// Project name, also used to record usage.
+    pub name: Option<String>,
+
+    /// Model every chapter is synthesized with.
+    pub model: Option<Model>,
+
+    /// Voice for text without a `[voice:...]` tag.
+    pub voice: Option<String>,
+
+    /// Speed multiplier for text without a `[speed:...]` tag.
+    pub speed: Option<f32>,
+
+    /// Language code such as `EN` or `ZH`.
+    pub language: Option<String>,
+
+    /// Locale numbers, currencies, and units are read in, such as `de-DE`.
+    pub locale: Option<Locale>,
+
+    /// Silence between sentences, such as `250ms`.
+    pub sentence_pause: Option<String>,
+
+    /// Silence after each paragraph and heading, such as `700ms`.
+    pub paragraph_pause: Option<String>,
+
+    /// Directory chapter outputs are written to, relative to the project.
+    pub output_dir: Option<PathBuf>,
+
+    /// Saved voice used for each voice name in the sources, so chapters can
+    /// say `[voice:narrator]` while the project decides who narrates.
+    pub voices: BTreeMap<String, String>,
+
+    /// Spoken forms of words the model mispronounces, matched as whole
+    /// words after the `replace` rules.
+    pub lexicon: BTreeMap<String, String>,
+
+    /// Spoken forms of acronyms and jargon, such as `K8s = "kubernetes"`,
+    /// applied after the lexicon and matching plurals too.
+    pub glossary: BTreeMap<String, String>,
+
+    /// Text substitution rules in sed syntax.
+    pub replace: Vec<String>,
+
+    /// Strip markdown syntax, code fences, and URLs from sources.
+    pub strip_markup: bool,
+
+    /// Emoji handling: "keep", "strip", or "verbalize".
+    pub emoji: Option<EmojiMode>,
+
+    /// When `open-tts-rs schedule` builds the project.
+    pub schedule: ScheduleConfig,
+
+    /// Text files to synthesize, in order (`[[chapter]]` tables).
+    #[serde(rename = "chapter")]
+    pub chapters: Vec<ChapterSource>,
+}
+
+impl ProjectFile {
+    /// Pauses from `sentence_pause` and `paragraph_pause`.
+    pub fn pacing(&self) -> Result<Pacing, TextError> {
+        let pause = |pause: &Option<String>| {
+            pause
+                .as_deref()
+                .map(parse_duration)
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+        Ok(Pacing {
+            sentence: pause(&self.sentence_pause)?,
+            paragraph: pause(&self.paragraph_pause)?,
+        })
+    }
+}
+
+/// One `[[chapter]]` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChapterSource {
+    /// Text file or Word manuscript, relative to the project.
+    pub source: PathBuf,
+
+    /// Chapter of a Word manuscript to read, counting from 1 [default:
+    /// the whole manuscript].
+    pub chapter: Option<usize>,
+
+    /// Output file, relative to the output directory [default: the
+    /// source's file name with a `.wav` extension, and the chapter number
+    /// appended for a chapter of a manuscript].
+    pub output: Option<PathBuf>,
+
+    /// Voice for this chapter, overriding the project voice.
+    pub voice: Option<String>,
+}
+
+/// A project directory and its parsed `project.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    root: PathBuf,
+    file: ProjectFile,
+}
+
+impl Project {
+    /// Output directory used when `output_dir` is not set.
+    pub const DEFAULT_OUTPUT_DIR: &str = "build";
+
+    /// Directory of cached chunk audio and build state.
+    pub const CACHE_DIR: &str = ".open-tts-cache";
+
+    /// Load the project in `root`.
+    pub fn open(root: &Path) -> Result<Self, ProjectError> {
+        let path = root.join(PROJECT_FILE);
+        if !path.exists() {
+            return Err(ProjectError::NotFound(root.to_path_buf()));
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            file: Self::parse(&std::fs::read_to_string(&path)?)?,
+        })
+    }
+
+    /// Load the project containing `dir`, looking in its parents too.
+    pub fn find(dir: &Path) -> Result<Self, ProjectError> {
+        match dir.ancestors().find(|d| d.join(PROJECT_FILE).exists()) {
+            Some(root) => Self::open(root),
+            None => Err(ProjectError::NotFound(dir.to_path_buf())),
+        }
+    }
+
+    /// Parse `project.toml` contents.
+    pub fn parse(contents: &str) -> Result<ProjectFile, ProjectError> {
+        let file: ProjectFile = toml::from_str(contents)?;
+        if let Some(speed) = file.speed
+            && !(0.5..=2.0).contains(&speed)
+        {
+            return Err(ProjectError::Invalid(format!(
+                "speed {speed} is outside 0.5 to 2.0"
+            )));
+        }
+        file.pacing()?;
+        Glossary::from_entries(&file.glossary)?;
+        file.schedule.schedule().map_err(ProjectError::Invalid)?;
+        file.schedule.until().map_err(ProjectError::Invalid)?;
+        Ok(file)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn file(&self) -> &ProjectFile {
+        &self.file
+    }
+
+    /// Name to record usage under: `name`, else the directory name.
+    pub fn name(&self) -> Option<String> {
+        self.file.name.clone().or_else(|| {
+            self.root
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        })
+    }
+
+    pub fn output_dir(&self) -> PathBuf {
+        self.root.join(
+            self.file
+                .output_dir
+                .as_deref()
+                .unwrap_or(Path::new(Self::DEFAULT_OUTPUT_DIR)),
+        )
+    }
+
+    pub fn cache_dir(&self) -> PathBuf {
+        self.root.join(Self::CACHE_DIR)
+    }
+
+    pub fn source_path(&self, chapter: &ChapterSource) -> PathBuf {
+        self.root.join(&chapter.source)
+    }
+
+    pub fn output_path(&self, chapter: &ChapterSource) -> PathBuf {
+        let output = match (&chapter.output, chapter.chapter) {
+            (Some(output), _) => output.clone(),
+            (None, Some(n)) => PathBuf::from(crate::paths::stem_with(
+                &chapter.source,
+                &format!("-{n:02}.wav"),
+            )),
+            (None, None) => {
+                Path::new(chapter.source.file_name().unwrap_or_default()).with_extension("wav")
+            }
+        };
+        self.output_dir().join(output)
+    }
+
+    /// The text of a chapter's source.
+    pub fn source_text(&self, chapter: &ChapterSource) -> Result<String, ProjectError> {
+        let path = self.source_path(chapter);
+        match chapter.chapter {
+            Some(n) => read_chapter(&path, n),
+            None => read_text(&path),
+        }
+        .map_err(|e| ProjectError::Source(path, e))
+    }
+
+    /// The saved voice a voice name in the sources refers to.
+    pub fn resolve_voice(&self, voice: &str) -> String {
+        self.file
+            .voices
+            .get(voice)
+            .cloned()
+            .unwrap_or_else(|| voice.to_string())
+    }
+
+    /// The normalization rules, lexicon and glossary included, as a
+    /// preprocessor.
+    pub fn preprocessor(&self) -> Result<Preprocessor, ProjectError> {
+        let mut rules = ReplaceRules::parse(&self.file.replace)?;
+        for (word, spoken) in &self.file.lexicon {
+            rules.push(ReplaceRule::word(word, spoken)?);
+        }
+        let markup = MarkupOptions {
+            strip_markdown: self.file.strip_markup,
+            emoji: self.file.emoji.unwrap_or_default(),
+        };
+        Ok(Preprocessor::new()
+            .with_markup(markup)
+            .with_rules(rules)
+            .with_glossary(Glossary::from_entries(&self.file.glossary)?)
+            .with_locale(self.file.locale))
+    }
+}
@@ -0,0 +1,86 @@
+//! Speech recognition through a Whisper server.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::RelayError;
+
+/// Default address of a local Whisper server.
+pub const DEFAULT_ASR_URL: &str = "http://localhost:8000";
+
+/// Turns recorded speech into text.
+pub trait Transcriber {
+    /// Transcribe one WAV clip; silence gives an empty string.
+    fn transcribe(&self, wav: &[u8]) -> Result<String, RelayError>;
+}
+
+/// A server speaking the OpenAI transcription API
+/// (`POST /v1/audio/transcriptions`), as faster-whisper-server, LocalAI,
+/// and whisper.cpp's server do.
+#[derive(Debug, Clone)]
+pub struct WhisperClient {
+    url: String,
+    model: String,
+    language: Option<String>,
+    client: reqwest::blocking::Client,
+}
+
+impl WhisperClient {
+    /// Connect to the server at `base_url` (e.g. `http://gpu-box:8000`).
+    pub fn new(base_url: &str, model: impl Into<String>) -> Self {
+        Self {
+            url: format!("{}/v1/audio/transcriptions", base_url.trim_end_matches('/')),
+            model: model.into(),
+            language: None,
+            client: reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Expect speech in `language` (e.g. `en`) rather than detecting it.
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language.map(|l| l.to_lowercase());
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct Transcription {
+    text: String,
+}
+
+impl Transcriber for WhisperClient {
+    fn transcribe(&self, wav: &[u8]) -> Result<String, RelayError> {
+        let file = reqwest::blocking::multipart::Part::bytes(wav.to_vec())
+            .file_name("speech.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| RelayError::Transcription(e.to_string()))?;
+        let mut form = reqwest::blocking::multipart::Form::new()
+            .part("file", file)
+            .text("model", self.model.clone())
+            .text("response_format", "json");
+        if let Some(language) = &self.language {
+            form = form.text("language", language.clone());
+        }
+
+        let response = self
+            .client
+            .post(&self.url)
+            .multipart(form)
+            .send()
+            .map_err(|e| RelayError::Transcription(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(RelayError::Transcription(format!(
+                "Status: {}",
+                response.status()
+            )));
+        }
+        let transcription: Transcription = response
+            .json()
+            .map_err(|e| RelayError::Transcription(e.to_string()))?;
+        Ok(transcription.text.trim().to_string())
+    }
+}
@@ -0,0 +1,139 @@
+//! Live speech relay: a voice changer built from the other pieces.
+//!
+//! `open-tts-rs relay` records the microphone in short segments, has a
+//! Whisper server transcribe each one, and speaks the text again in a
+//! cloned voice, to the speakers or the virtual microphone. Latency is
+//! about one segment plus transcription and synthesis time; words split
+//! across a segment boundary may be misheard.
+
+mod asr;
+mod pipeline;
+
+pub use asr::{DEFAULT_ASR_URL, Transcriber, WhisperClient};
+pub use pipeline::{
+    DEFAULT_MIN_PEAK, DEFAULT_SEGMENT, RelayEvent, RelayOptions, record_segments, relay,
+};
+
+use thiserror::Error;
+
+use crate::audio::AudioError;
+use crate::engine::TTSError;
+
+/// Errors that can occur while relaying speech.
+#[derive(Error, Debug)]
+pub enum RelayError {
+    #[error("Transcription failed: {0}")]
+    Transcription(String),
+
+    #[error("Capture failed: {0}")]
+    Capture(String),
+
+    #[error("Audio error: {0}")]
+    AudioError(#[from] AudioError),
+
+    #[error("Synthesis failed: {0}")]
+    Synthesis(#[from] TTSError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioBuffer, MemorySink};
+    use crate::backend::{BackendError, mock_backend};
+    use crate::engine::TTSEngine;
+    use crate::voice::VoiceManager;
+    use std::cell::RefCell;
+    use tempfile::TempDir;
+
+    /// Transcribes every clip as the next of a list of answers.
+    struct Script(RefCell<Vec<Result<String, RelayError>>>);
+
+    impl Transcriber for Script {
+        fn transcribe(&self, _wav: &[u8]) -> Result<String, RelayError> {
+            self.0.borrow_mut().remove(0)
+        }
+    }
+
+    fn clip(level: f32) -> Vec<u8> {
+        AudioBuffer::new(vec![level; 1600], 16000, 1)
+            .to_wav_bytes()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_relay_speaks_what_it_hears() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut backend = mock_backend();
+        backend
+            .expect_synthesize()
+            .withf(|r| r.text == "hello there" && r.speed == 1.5)
+            .times(1)
+            .returning(|_| Ok(clip(0.5)));
+        backend
+            .expect_synthesize()
+            .withf(|r| r.text == "goodbye")
+            .times(1)
+            .returning(|_| Err(BackendError::BackendError("500".to_string())));
+        let engine = TTSEngine::new(
+            backend,
+            VoiceManager::with_dir(temp_dir.path().to_path_buf()),
+        );
+
+        let transcriber = Script(RefCell::new(vec![
+            Ok("hello there".to_string()),
+            Err(RelayError::Transcription("timeout".to_string())),
+            Ok(String::new()),
+            Ok("goodbye".to_string()),
+        ]));
+        let mut segments = vec![clip(0.3), clip(0.0), clip(0.3), clip(0.3), clip(0.3)];
+        segments.reverse();
+        let capture = move || Ok(segments.pop());
+        let options = RelayOptions {
+            speed: 1.5,
+            ..RelayOptions::default()
+        };
+        let mut sink = MemorySink::default();
+        let mut events = Vec::new();
+
+        relay(&engine, &transcriber, &options, capture, &mut sink, |e| {
+            events.push(e)
+        })
+        .unwrap();
+
+        assert_eq!(
+            events[..3],
+            [
+                RelayEvent::Heard("hello there".to_string()),
+                RelayEvent::Spoke(std::time::Duration::from_millis(100)),
+                RelayEvent::Quiet,
+            ]
+        );
+        assert!(matches!(&events[3], RelayEvent::Failed(e) if e.contains("timeout")));
+        assert_eq!(events[4], RelayEvent::Quiet);
+        assert_eq!(events[5], RelayEvent::Heard("goodbye".to_string()));
+        assert!(matches!(&events[6], RelayEvent::Failed(_)));
+        assert!(!sink.wav.is_empty());
+    }
+
+    #[test]
+    fn test_relay_stops_when_capture_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = TTSEngine::new(
+            mock_backend(),
+            VoiceManager::with_dir(temp_dir.path().to_path_buf()),
+        );
+        let transcriber = Script(RefCell::new(Vec::new()));
+        let capture = || Err(RelayError::Capture("no microphone".to_string()));
+
+        let err = relay(
+            &engine,
+            &transcriber,
+            &RelayOptions::default(),
+            capture,
+            &mut MemorySink::default(),
+            |_| {},
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Capture failed: no microphone");
+    }
+}
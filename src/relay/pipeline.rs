@@ -0,0 +1,169 @@
+//! The capture, transcribe, and speak loop.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use super::RelayError;
+use super::asr::Transcriber;
+use crate::audio::{AudioBuffer, AudioError, AudioSink, record_file};
+use crate::backend::Backend;
+use crate::engine::TTSEngine;
+use crate::text::Chunk;
+
+/// Peak level below which a segment is taken as silence and not sent for
+/// transcription (about -34 dBFS).
+pub const DEFAULT_MIN_PEAK: f32 = 0.02;
+
+/// Length of each recorded segment by default.
+pub const DEFAULT_SEGMENT: Duration = Duration::from_secs(4);
+
+/// How heard speech is spoken again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayOptions {
+    /// Voice to speak in, or the backend's default.
+    pub voice: Option<String>,
+    pub speed: f32,
+    /// Segments peaking below this are skipped as silence.
+    pub min_peak: f32,
+}
+
+impl Default for RelayOptions {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            speed: 1.0,
+            min_peak: DEFAULT_MIN_PEAK,
+        }
+    }
+}
+
+/// What happened to one recorded segment.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelayEvent {
+    /// Nothing was said.
+    Quiet,
+    /// Speech was recognized and is being synthesized.
+    Heard(String),
+    /// The speech was played, lasting this long.
+    Spoke(Duration),
+    /// Transcription or synthesis failed; the relay carries on.
+    Failed(String),
+}
+
+/// Speak whatever `capture` records in the configured voice, until it
+/// returns `None`.
+///
+/// Recording runs on its own thread, so the next segment is captured while
+/// the last one is transcribed and synthesized. Each segment's audio goes
+/// to `sink` as it is generated. Failing to record or play stops the relay;
+/// failing to transcribe or synthesize one segment does not.
+pub fn relay<B: Backend>(
+    engine: &TTSEngine<B>,
+    transcriber: &dyn Transcriber,
+    options: &RelayOptions,
+    mut capture: impl FnMut() -> Result<Option<Vec<u8>>, RelayError> + Send,
+    sink: &mut dyn AudioSink,
+    mut on_event: impl FnMut(RelayEvent),
+) -> Result<(), RelayError> {
+    // One segment may wait while another is spoken; more would only lag
+    let (sender, receiver) = mpsc::sync_channel(1);
+
+    std::thread::scope(|scope| {
+        let recorder = scope.spawn(move || {
+            while let Some(wav) = capture()? {
+                if sender.send(wav).is_err() {
+                    break;
+                }
+            }
+            Ok::<_, RelayError>(())
+        });
+
+        let relayed = (move || {
+            for wav in receiver {
+                if let Some(text) = hear(transcriber, options, &wav, &mut on_event)? {
+                    on_event(speak(engine, options, text, sink)?);
+                }
+            }
+            Ok(())
+        })();
+
+        // The receiver is gone by now, so the recorder stops after its
+        // current segment
+        let recorded = recorder
+            .join()
+            .unwrap_or_else(|_| Err(RelayError::Capture("recorder panicked".to_string())));
+        relayed.and(recorded)
+    })
+}
+
+/// The text spoken in `wav`, or `None` after reporting why there is none.
+fn hear(
+    transcriber: &dyn Transcriber,
+    options: &RelayOptions,
+    wav: &[u8],
+    on_event: &mut impl FnMut(RelayEvent),
+) -> Result<Option<String>, RelayError> {
+    if is_quiet(wav, options.min_peak)? {
+        on_event(RelayEvent::Quiet);
+        return Ok(None);
+    }
+    match transcriber.transcribe(wav) {
+        Ok(text) if text.is_empty() => {
+            on_event(RelayEvent::Quiet);
+            Ok(None)
+        }
+        Ok(text) => {
+            on_event(RelayEvent::Heard(text.clone()));
+            Ok(Some(text))
+        }
+        Err(e) => {
+            on_event(RelayEvent::Failed(e.to_string()));
+            Ok(None)
+        }
+    }
+}
+
+/// Synthesize `text` into `sink` as it is generated. Only failing to play
+/// is an error; a failed synthesis is reported as an event.
+fn speak<B: Backend>(
+    engine: &TTSEngine<B>,
+    options: &RelayOptions,
+    text: String,
+    sink: &mut dyn AudioSink,
+) -> Result<RelayEvent, RelayError> {
+    let chunk = Chunk::Speech {
+        text,
+        voice: options.voice.clone(),
+        speed: options.speed,
+    };
+    let mut spoken = Duration::ZERO;
+    let mut played = Ok(());
+    let synthesized = engine.synthesize_streaming(&[chunk], |audio| {
+        spoken += audio.duration();
+        played = audio.to_wav_bytes().and_then(|wav| sink.write(&wav));
+        played.is_ok()
+    });
+    played?;
+    Ok(match synthesized {
+        Ok(()) => RelayEvent::Spoke(spoken),
+        Err(e) => RelayEvent::Failed(e.to_string()),
+    })
+}
+
+/// Record `segment`-long clips from the default microphone, for
+/// [`relay`]'s `capture`.
+pub fn record_segments(
+    segment: Duration,
+) -> impl FnMut() -> Result<Option<Vec<u8>>, RelayError> + Send {
+    let path = crate::scratch::scratch_file("relay.wav");
+    move || {
+        record_file(&path, segment)?;
+        let wav = std::fs::read(&path).map_err(AudioError::from)?;
+        Ok(Some(wav))
+    }
+}
+
+fn is_quiet(wav: &[u8], min_peak: f32) -> Result<bool, RelayError> {
+    let audio = AudioBuffer::from_wav_bytes(wav)?;
+    Ok(audio.samples.iter().all(|s| s.abs() < min_peak))
+}
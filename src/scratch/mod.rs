@@ -0,0 +1,60 @@
+//! Scratch files: intermediate artifacts kept in one managed directory.
+//!
+//! Decrypted and cleaned reference clips, partial downloads, audio staged
+//! for a player, and outputs being written all go to a [`Scratch`]
+//! directory of their own process, under the system temp directory or
+//! `--temp-dir`. It is removed when the process exits; one left behind by
+//! a crash is swept by the next process to start.
+
+mod session;
+
+pub use session::{Scratch, cleanup, persist, scratch_file, sweep};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scratch_is_removed_on_drop() {
+        let base = TempDir::new().unwrap();
+        let scratch = Scratch::new(Some(base.path())).unwrap();
+        let dir = scratch.dir().to_path_buf();
+        assert!(dir.starts_with(base.path()));
+        assert!(dir.ends_with(std::process::id().to_string()));
+        std::fs::write(dir.join("ref.wav"), b"RIFF").unwrap();
+
+        // A live process's directory is never swept, this one's included
+        assert!(sweep(dir.parent().unwrap()).unwrap().is_empty());
+        drop(scratch);
+        assert!(!dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sweep_removes_directories_of_exited_processes() {
+        let root = TempDir::new().unwrap();
+        let dead = root.path().join(i32::MAX.to_string());
+        std::fs::create_dir_all(&dead).unwrap();
+        std::fs::write(dead.join("clip.wav.download"), b"partial").unwrap();
+        let other = root.path().join("notes");
+        std::fs::create_dir_all(&other).unwrap();
+
+        assert_eq!(sweep(root.path()).unwrap(), vec![dead.clone()]);
+        assert!(!dead.exists());
+        assert!(other.exists());
+        assert!(sweep(&root.path().join("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_persist_replaces_the_whole_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("out.wav");
+        persist(&path, b"first").unwrap();
+        persist(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        assert!(persist(&dir.path().join("missing/out.wav"), b"x").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! The scratch directory of one process.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The scratch directory scratch files go to, once one is started.
+static SESSION: OnceLock<PathBuf> = OnceLock::new();
+
+/// Age after which a scratch directory is swept where it cannot be told
+/// whether its process is still running.
+#[cfg(not(unix))]
+const STALE_AFTER: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// A process's directory for intermediate files, at
+/// `<base>/open-tts-rs-<user>/<pid>`. It is removed when dropped, and the
+/// next process to start sweeps it if its process died first.
+#[derive(Debug)]
+pub struct Scratch {
+    dir: PathBuf,
+}
+
+impl Scratch {
+    /// Create this process's scratch directory under `base` [default: the
+    /// system temp directory], first sweeping those left by processes that
+    /// are no longer running.
+    pub fn new(base: Option<&Path>) -> std::io::Result<Self> {
+        let root = base
+            .map_or_else(std::env::temp_dir, Path::to_path_buf)
+            .join(root_name());
+        sweep(&root)?;
+
+        let dir = root.join(std::process::id().to_string());
+        // A directory of an earlier process with the same ID
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Create this process's scratch directory and send every later
+    /// [`scratch_file`] there.
+    pub fn start(base: Option<&Path>) -> std::io::Result<Self> {
+        let scratch = Self::new(base)?;
+        let _ = SESSION.set(scratch.dir.clone());
+        Ok(scratch)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// One directory per user, so a shared temp directory does not mix them.
+fn root_name() -> String {
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid cannot fail
+        format!("open-tts-rs-{}", unsafe { libc::getuid() })
+    }
+    #[cfg(not(unix))]
+    {
+        "open-tts-rs".to_string()
+    }
+}
+
+/// Remove the started scratch directory, for exits that skip destructors
+/// such as Ctrl-C.
+pub fn cleanup() {
+    if let Some(dir) = SESSION.get() {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+/// Path for a scratch file called `name`: in the started scratch
+/// directory, or before one is started, in the system temp directory
+/// prefixed with the process ID.
+pub fn scratch_file(name: &str) -> PathBuf {
+    match SESSION.get() {
+        Some(dir) => {
+            let _ = std::fs::create_dir_all(dir);
+            dir.join(name)
+        }
+        None => std::env::temp_dir().join(format!("open-tts-rs-{}-{name}", std::process::id())),
+    }
+}
+
+/// Write `contents` to `path` by way of a scratch file, so `path` holds
+/// either its old contents or all of the new ones, never part.
+pub fn persist(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    static STAGED: AtomicUsize = AtomicUsize::new(0);
+    let staged = scratch_file(&format!(
+        "output-{}.part",
+        STAGED.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = std::fs::write(&staged, contents).and_then(|()| {
+        // Across filesystems a rename fails; copy instead
+        std::fs::rename(&staged, path).or_else(|_| std::fs::copy(&staged, path).map(|_| ()))
+    });
+    let _ = std::fs::remove_file(&staged);
+    result
+}
+
+/// Remove the scratch directories under `root` whose processes are no
+/// longer running. Returns the directories removed.
+pub fn sweep(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut swept = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(pid) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid != std::process::id()
+            && abandoned(&path, pid)
+            && std::fs::remove_dir_all(&path).is_ok()
+        {
+            swept.push(path);
+        }
+    }
+    Ok(swept)
+}
+
+/// Whether the process that owned a scratch directory has exited.
+#[cfg(unix)]
+fn abandoned(_dir: &Path, pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return true;
+    };
+    // SAFETY: signal 0 only checks that the process exists
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return false;
+    }
+    std::io::Error::last_os_error().raw_os_error() != Some(libc::EPERM)
+}
+
+/// Whether a scratch directory is old enough that its process has surely
+/// exited.
+#[cfg(not(unix))]
+fn abandoned(dir: &Path, _pid: u32) -> bool {
+    std::fs::metadata(dir)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| std::time::SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
@@ -0,0 +1,385 @@
+//! Background daemon serving synthesis over a local control socket.
+//!
+//! Each connection carries one request line and one response line of JSON.
+//! The response is the completion notice, so unlike the `serve` job queue
+//! there are no callbacks.
+
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::ServerError;
+use crate::audio::Cleanup;
+use crate::backend::{Backend, BackendError, HealthWait, wait_for_health};
+use crate::cli::Model;
+use crate::engine::{Pause, Pressure, TTSEngine};
+use crate::text::Chunk;
+use crate::voice::VoiceManager;
+
+/// The backend server a request should be sent to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BackendTarget {
+    pub model: Model,
+    pub host: String,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Reject responses that differ from the expected schema.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+}
+
+/// How an engine is set up, the same in-process and in the daemon, so
+/// text forwarded to the daemon is split, checked, and retried as it
+/// would be without it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EngineOptions {
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub require_consent: bool,
+    /// Project the audio is for, checked against voice restrictions
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Allow locked voices
+    #[serde(default)]
+    pub unlock: bool,
+    /// Split requests longer than this many characters
+    #[serde(default)]
+    pub max_text_length: Option<usize>,
+    /// SPDX license recorded on extracted voices
+    #[serde(default)]
+    pub model_license: Option<String>,
+    /// Filters for reference clips
+    #[serde(default)]
+    pub cleanup: Option<Cleanup>,
+    /// How long to wait for the backend to pass its health check
+    #[serde(default)]
+    pub wait_for_backend: Option<Duration>,
+}
+
+impl EngineOptions {
+    /// Set `engine` up with these options, backing off under GPU memory
+    /// pressure. Waiting for the backend is left to the caller.
+    pub fn apply<B: Backend>(&self, engine: TTSEngine<B>) -> TTSEngine<B> {
+        engine
+            .with_language(self.language.clone())
+            .with_require_consent(self.require_consent)
+            .with_project(self.project.clone())
+            .with_unlocked(self.unlock)
+            .with_max_text_length(self.max_text_length)
+            .with_model_license(self.model_license.clone())
+            .with_cleanup(self.cleanup)
+            .with_pressure(Some(Pressure::new()))
+    }
+}
+
+/// A request sent to the daemon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    /// Check that the daemon is alive.
+    Ping,
+    /// Synthesize chunks and return the assembled WAV.
+    Synthesize {
+        target: BackendTarget,
+        chunks: Vec<Chunk>,
+        #[serde(default, flatten)]
+        options: EngineOptions,
+    },
+    /// Hold synthesis between chunks until resumed.
+    Pause,
+    /// Let paused synthesis continue.
+    Resume,
+}
+
+/// The daemon's answer to a [`DaemonRequest`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonResponse {
+    Pong {
+        pid: u32,
+    },
+    Audio {
+        #[serde(with = "base64_bytes")]
+        wav: Vec<u8>,
+    },
+    /// Whether synthesis is paused, after a pause or resume.
+    Paused {
+        paused: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+type Connect<B> = dyn Fn(&BackendTarget) -> Result<B, BackendError> + Send + Sync;
+
+/// Synthesis service that keeps one backend client per target alive.
+///
+/// Backends are created on first use with `connect` and shared by every
+/// later request for the same target; the voice store is opened once.
+/// Every request waits on one [`Pause`], so pausing the daemon holds all
+/// of its clients between chunks.
+pub struct Daemon<B: Backend> {
+    voice_manager: VoiceManager,
+    connect: Box<Connect<B>>,
+    backends: Mutex<HashMap<BackendTarget, Arc<B>>>,
+    pause: Pause,
+}
+
+impl<B: Backend> Daemon<B> {
+    /// Create a daemon serving voices from `voice_manager`.
+    pub fn new(
+        voice_manager: VoiceManager,
+        connect: impl Fn(&BackendTarget) -> Result<B, BackendError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            voice_manager,
+            connect: Box::new(connect),
+            backends: Mutex::new(HashMap::new()),
+            pause: Pause::new(),
+        }
+    }
+
+    /// The pause every request waits on.
+    pub fn pause(&self) -> &Pause {
+        &self.pause
+    }
+
+    /// Answer one request.
+    pub fn handle(&self, request: DaemonRequest) -> DaemonResponse {
+        match request {
+            DaemonRequest::Ping => DaemonResponse::Pong {
+                pid: std::process::id(),
+            },
+            DaemonRequest::Synthesize {
+                target,
+                chunks,
+                options,
+            } => match self.synthesize(&target, &chunks, &options) {
+                Ok(wav) => DaemonResponse::Audio { wav },
+                Err(message) => DaemonResponse::Error { message },
+            },
+            DaemonRequest::Pause => {
+                self.pause.pause();
+                DaemonResponse::Paused { paused: true }
+            }
+            DaemonRequest::Resume => {
+                self.pause.resume();
+                DaemonResponse::Paused { paused: false }
+            }
+        }
+    }
+
+    fn synthesize(
+        &self,
+        target: &BackendTarget,
+        chunks: &[Chunk],
+        options: &EngineOptions,
+    ) -> Result<Vec<u8>, String> {
+        let backend = self.backend(target).map_err(|e| e.to_string())?;
+        if let Some(timeout) = options.wait_for_backend {
+            wait_for_health(backend.as_ref(), &HealthWait::new(timeout), |_, _| {}).map_err(
+                |e| {
+                    format!(
+                        "backend at {}:{} was not ready after {}s: {e}",
+                        target.host,
+                        target.port,
+                        timeout.as_secs_f32()
+                    )
+                },
+            )?;
+        }
+        options
+            .apply(TTSEngine::new(backend, self.voice_manager.clone()))
+            .with_pause(Some(self.pause.clone()))
+            .synthesize_chunks(chunks)
+            .map_err(|e| e.to_string())
+    }
+
+    fn backend(&self, target: &BackendTarget) -> Result<Arc<B>, BackendError> {
+        let mut backends = self.backends.lock().unwrap();
+        if let Some(backend) = backends.get(target) {
+            return Ok(backend.clone());
+        }
+
+        let backend = Arc::new((self.connect)(target)?);
+        backends.insert(target.clone(), backend.clone());
+        Ok(backend)
+    }
+}
+
+#[cfg(unix)]
+impl<B: Backend + 'static> Daemon<B> {
+    /// Listen on `socket`, replacing a stale socket file from an earlier run.
+    ///
+    /// The socket is only accessible to the current user.
+    pub fn bind(&self, socket: &Path) -> Result<std::os::unix::net::UnixListener, ServerError> {
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(ServerError::Remote(format!(
+                    "a daemon is already listening on {}",
+                    socket.display()
+                )));
+            }
+            std::fs::remove_file(socket)?;
+        }
+        if let Some(parent) = socket.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let listener = UnixListener::bind(socket)?;
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+        Ok(listener)
+    }
+
+    /// Serve connections until the listener fails, one thread per client.
+    pub fn serve(self, listener: std::os::unix::net::UnixListener) -> Result<(), ServerError> {
+        let daemon = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let daemon = daemon.clone();
+            std::thread::spawn(move || {
+                // A client that disconnects early only loses its own answer
+                let _ = daemon.answer(stream);
+            });
+        }
+        Ok(())
+    }
+
+    fn answer(&self, stream: std::os::unix::net::UnixStream) -> Result<(), ServerError> {
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => self.handle(request),
+            Err(e) => DaemonResponse::Error {
+                message: format!("invalid request: {e}"),
+            },
+        };
+        write_line(&stream, &response)
+    }
+}
+
+#[cfg(not(unix))]
+impl<B: Backend + 'static> Daemon<B> {
+    /// Unix domain sockets are unavailable on this platform.
+    pub fn bind(&self, _socket: &Path) -> Result<std::convert::Infallible, ServerError> {
+        Err(ServerError::Unsupported)
+    }
+
+    /// Unix domain sockets are unavailable on this platform.
+    pub fn serve(self, listener: std::convert::Infallible) -> Result<(), ServerError> {
+        match listener {}
+    }
+}
+
+/// Client side of the control socket.
+#[derive(Debug, Clone)]
+pub struct DaemonClient {
+    socket: std::path::PathBuf,
+}
+
+impl DaemonClient {
+    /// Find a daemon answering on `socket`.
+    ///
+    /// Returns `None` when nothing is listening, so callers can fall back
+    /// to synthesizing in-process.
+    pub fn detect(socket: &Path) -> Option<Self> {
+        let client = Self {
+            socket: socket.to_path_buf(),
+        };
+        match client.request(&DaemonRequest::Ping) {
+            Ok(DaemonResponse::Pong { .. }) => Some(client),
+            _ => None,
+        }
+    }
+
+    /// Synthesize chunks in the daemon, returning WAV bytes.
+    pub fn synthesize(
+        &self,
+        target: BackendTarget,
+        chunks: Vec<Chunk>,
+        options: EngineOptions,
+    ) -> Result<Vec<u8>, ServerError> {
+        let request = DaemonRequest::Synthesize {
+            target,
+            chunks,
+            options,
+        };
+        match self.request(&request)? {
+            DaemonResponse::Audio { wav } => Ok(wav),
+            DaemonResponse::Error { message } => Err(ServerError::Remote(message)),
+            other => Err(ServerError::Remote(format!(
+                "unexpected response: {other:?}"
+            ))),
+        }
+    }
+
+    /// Pause or resume synthesis in the daemon, returning whether it is
+    /// now paused.
+    pub fn set_paused(&self, paused: bool) -> Result<bool, ServerError> {
+        let request = match paused {
+            true => DaemonRequest::Pause,
+            false => DaemonRequest::Resume,
+        };
+        match self.request(&request)? {
+            DaemonResponse::Paused { paused } => Ok(paused),
+            DaemonResponse::Error { message } => Err(ServerError::Remote(message)),
+            other => Err(ServerError::Remote(format!(
+                "unexpected response: {other:?}"
+            ))),
+        }
+    }
+
+    /// Send one request and wait for the response.
+    #[cfg(unix)]
+    pub fn request(&self, request: &DaemonRequest) -> Result<DaemonResponse, ServerError> {
+        let stream = std::os::unix::net::UnixStream::connect(&self.socket)?;
+        write_line(&stream, request)?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    /// Unix domain sockets are unavailable on this platform.
+    #[cfg(not(unix))]
+    pub fn request(&self, _request: &DaemonRequest) -> Result<DaemonResponse, ServerError> {
+        Err(ServerError::Unsupported)
+    }
+}
+
+#[cfg(unix)]
+fn write_line(mut writer: impl Write, message: &impl Serialize) -> Result<(), ServerError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Binary payloads travel as base64 strings rather than JSON number arrays.
+mod base64_bytes {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
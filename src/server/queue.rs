@@ -0,0 +1,260 @@
+//! Persistent, prioritized synthesis queue for `serve` mode.
+//!
+//! Each job is stored as `<id>.json` (plus `<id>.wav` once finished) in the
+//! queue directory, so queued work survives a restart. Jobs that were
+//! running when the server stopped are queued again on load.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::ServerError;
+
+/// Scheduling priority; higher priorities always run first.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Progress of a queued job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+/// Body of a job submission.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmitRequest {
+    pub text: String,
+    #[serde(default)]
+    pub voice: Option<String>,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub priority: Priority,
+    /// URL POSTed a [`super::JobNotice`] when the job finishes or fails.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback: Option<String>,
+}
+
+/// A job in the queue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub id: String,
+    /// Submitting client, used for per-client concurrency limits.
+    pub client: String,
+    pub status: QueueStatus,
+    pub submitted_at: String,
+    /// Submission order, for first-in first-out within a priority.
+    pub seq: u64,
+    #[serde(flatten)]
+    pub request: SubmitRequest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    /// Length of the finished audio.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seconds: Option<f64>,
+    /// Why the completion callback could not be delivered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_error: Option<String>,
+}
+
+/// Disk-backed job queue shared by the HTTP handlers and the workers.
+pub struct JobQueue {
+    dir: PathBuf,
+    max_per_client: usize,
+    jobs: Mutex<HashMap<String, QueuedJob>>,
+    ready: Condvar,
+}
+
+impl JobQueue {
+    /// Open the queue in `dir`, loading any jobs left from an earlier run.
+    ///
+    /// A client never has more than `max_per_client` jobs running at once
+    /// (at least one).
+    pub fn open(dir: &Path, max_per_client: usize) -> Result<Self, ServerError> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut jobs = HashMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let mut job: QueuedJob = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            if job.status == QueueStatus::Running {
+                job.status = QueueStatus::Queued;
+            }
+            jobs.insert(job.id.clone(), job);
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_per_client: max_per_client.max(1),
+            jobs: Mutex::new(jobs),
+            ready: Condvar::new(),
+        })
+    }
+
+    /// Default queue directory (`~/.open-tts-rs/queue`).
+    pub fn default_dir() -> PathBuf {
+        dirs::home_dir()
+            .expect("Could not find home directory")
+            .join(".open-tts-rs")
+            .join("queue")
+    }
+
+    /// Add a job and wake a waiting worker.
+    pub fn submit(&self, client: &str, request: SubmitRequest) -> Result<QueuedJob, ServerError> {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        let seq = jobs.values().map(|j| j.seq + 1).max().unwrap_or(0);
+        let job = QueuedJob {
+            id: format!("{}-{seq}", Utc::now().format("%Y%m%d-%H%M%S")),
+            client: client.to_string(),
+            status: QueueStatus::Queued,
+            submitted_at: Utc::now().to_rfc3339(),
+            seq,
+            request,
+            error: None,
+            finished_at: None,
+            seconds: None,
+            callback_error: None,
+        };
+        self.save(&job)?;
+        jobs.insert(job.id.clone(), job.clone());
+
+        self.ready.notify_all();
+        Ok(job)
+    }
+
+    /// Look up a job by ID.
+    pub fn get(&self, id: &str) -> Option<QueuedJob> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// Path of a job's audio, once it is done.
+    pub fn audio_path(&self, id: &str) -> Option<PathBuf> {
+        self.get(id)
+            .filter(|job| job.status == QueueStatus::Done)
+            .map(|job| self.dir.join(format!("{}.wav", job.id)))
+    }
+
+    /// Claim the next runnable job without waiting.
+    ///
+    /// The highest priority wins, then the oldest submission, skipping
+    /// clients already at their concurrency limit.
+    pub fn try_next(&self) -> Result<Option<QueuedJob>, ServerError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.claim(&mut jobs)
+    }
+
+    /// Claim the next runnable job, waiting until one is available.
+    pub fn next(&self) -> Result<QueuedJob, ServerError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        loop {
+            if let Some(job) = self.claim(&mut jobs)? {
+                return Ok(job);
+            }
+            jobs = self.ready.wait(jobs).unwrap();
+        }
+    }
+
+    /// Record the result of a running job.
+    pub fn finish(&self, id: &str, result: Result<Vec<u8>, String>) -> Result<(), ServerError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(id) else {
+            return Ok(());
+        };
+
+        match result {
+            Ok(wav) => {
+                job.seconds = wav_seconds(&wav);
+                std::fs::write(self.dir.join(format!("{id}.wav")), wav)?;
+                job.status = QueueStatus::Done;
+            }
+            Err(message) => {
+                job.status = QueueStatus::Failed;
+                job.error = Some(message);
+            }
+        }
+        job.finished_at = Some(Utc::now().to_rfc3339());
+        self.save(job)?;
+
+        // A finished job may let another job from the same client run
+        self.ready.notify_all();
+        Ok(())
+    }
+
+    /// Record that a job's completion callback could not be delivered.
+    pub fn callback_failed(&self, id: &str, message: String) -> Result<(), ServerError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(id) else {
+            return Ok(());
+        };
+        job.callback_error = Some(message);
+        self.save(job)
+    }
+
+    fn claim(
+        &self,
+        jobs: &mut HashMap<String, QueuedJob>,
+    ) -> Result<Option<QueuedJob>, ServerError> {
+        let mut running: HashMap<&str, usize> = HashMap::new();
+        for job in jobs.values().filter(|j| j.status == QueueStatus::Running) {
+            *running.entry(job.client.as_str()).or_default() += 1;
+        }
+
+        let next = jobs
+            .values()
+            .filter(|j| j.status == QueueStatus::Queued)
+            .filter(|j| running.get(j.client.as_str()).copied().unwrap_or(0) < self.max_per_client)
+            .max_by(|a, b| {
+                a.request
+                    .priority
+                    .cmp(&b.request.priority)
+                    .then(b.seq.cmp(&a.seq))
+            })
+            .map(|j| j.id.clone());
+
+        let Some(id) = next else {
+            return Ok(None);
+        };
+        let job = jobs.get_mut(&id).expect("claimed job exists");
+        job.status = QueueStatus::Running;
+        self.save(job)?;
+        Ok(Some(job.clone()))
+    }
+
+    fn save(&self, job: &QueuedJob) -> Result<(), ServerError> {
+        let path = self.dir.join(format!("{}.json", job.id));
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(job)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+fn wav_seconds(wav: &[u8]) -> Option<f64> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(wav)).ok()?;
+    Some(reader.duration() as f64 / reader.spec().sample_rate as f64)
+}
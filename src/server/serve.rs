@@ -0,0 +1,273 @@
+//! HTTP service behind `open-tts-rs serve`.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use tiny_http::{Header, Method, Request, Response, StatusCode};
+use tungstenite::WebSocket;
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+
+use super::ServerError;
+use super::queue::{JobQueue, Priority, QueueStatus, SubmitRequest};
+use super::stream::stream_session;
+use super::webhook::{JobNotice, deliver, validate_callback};
+use crate::backend::Backend;
+use crate::engine::TTSEngine;
+use crate::text::chunk_text;
+
+/// Default `serve` listen address.
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9300";
+
+/// Which peers and callback hosts the server trusts.
+#[derive(Debug, Default)]
+struct Access {
+    trusted_proxies: Vec<IpAddr>,
+    callback_hosts: Vec<String>,
+}
+
+/// HTTP server sharing one engine between all clients.
+///
+/// Routes:
+/// - `GET /health`: backend health as JSON
+/// - `GET /stream`: WebSocket PCM streaming (see [`super::stream`])
+/// - `POST /jobs`, `GET /jobs/<id>`, `GET /jobs/<id>/audio`: the job
+///   queue, when enabled with [`Server::with_queue`]
+pub struct Server<B: Backend> {
+    engine: Arc<TTSEngine<B>>,
+    queue: Option<Arc<JobQueue>>,
+    workers: usize,
+    access: Access,
+}
+
+impl<B: Backend + 'static> Server<B> {
+    pub fn new(engine: TTSEngine<B>) -> Self {
+        Self {
+            engine: Arc::new(engine),
+            queue: None,
+            workers: 0,
+            access: Access::default(),
+        }
+    }
+
+    /// Accept queued jobs, run by `workers` background threads.
+    ///
+    /// One worker per GPU backend keeps jobs from competing for it.
+    pub fn with_queue(mut self, queue: JobQueue, workers: usize) -> Self {
+        self.queue = Some(Arc::new(queue));
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Trust `X-Client-Id` and job priorities from requests that come
+    /// from `proxies`, which are expected to set them per user. Everyone
+    /// else is told apart by address and runs at normal priority or lower.
+    pub fn with_trusted_proxies(mut self, proxies: Vec<IpAddr>) -> Self {
+        self.access.trusted_proxies = proxies;
+        self
+    }
+
+    /// Allow job callbacks to `hosts` even when they resolve to loopback,
+    /// private, or link-local addresses.
+    pub fn with_callback_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.access.callback_hosts = hosts;
+        self
+    }
+
+    /// Listen on `addr` (`host:port`; port 0 picks a free port).
+    pub fn bind(&self, addr: &str) -> Result<tiny_http::Server, ServerError> {
+        tiny_http::Server::http(addr).map_err(|e| ServerError::Bind(e.to_string()))
+    }
+
+    /// Handle requests until the listener shuts down, one thread per request.
+    pub fn serve(self, http: tiny_http::Server) {
+        let access = Arc::new(self.access);
+        if let Some(queue) = &self.queue {
+            for _ in 0..self.workers {
+                let engine = self.engine.clone();
+                let queue = queue.clone();
+                let access = access.clone();
+                std::thread::spawn(move || run_worker(&engine, &queue, &access));
+            }
+        }
+
+        for request in http.incoming_requests() {
+            let engine = self.engine.clone();
+            let queue = self.queue.clone();
+            let access = access.clone();
+            std::thread::spawn(move || route(&engine, queue.as_deref(), &access, request));
+        }
+    }
+}
+
+/// Run queued jobs one at a time until the queue fails.
+fn run_worker<B: Backend>(engine: &TTSEngine<B>, queue: &Arc<JobQueue>, access: &Arc<Access>) {
+    while let Ok(job) = queue.next() {
+        let request = &job.request;
+        let result = chunk_text(&request.text, request.voice.as_deref(), request.speed)
+            .map_err(|e| e.to_string())
+            .and_then(|chunks| engine.synthesize_chunks(&chunks).map_err(|e| e.to_string()));
+        if queue.finish(&job.id, result).is_err() {
+            break;
+        }
+        if let Some(url) = job.request.callback.clone() {
+            notify(queue.clone(), access.clone(), job.id, url);
+        }
+    }
+}
+
+/// Deliver a job's completion callback in the background, so a slow
+/// receiver does not hold up the next job.
+///
+/// The URL is checked again here, since its host may resolve differently
+/// than when the job was submitted.
+fn notify(queue: Arc<JobQueue>, access: Arc<Access>, id: String, url: String) {
+    std::thread::spawn(move || {
+        let Some(job) = queue.get(&id) else {
+            return;
+        };
+        let notice = JobNotice::new(job, queue.audio_path(&id));
+        let result = validate_callback(&url, &access.callback_hosts)
+            .and_then(|()| deliver(&url, &notice).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            // Failing to record this only loses the diagnostic
+            let _ = queue.callback_failed(&id, e);
+        }
+    });
+}
+
+fn route<B: Backend>(
+    engine: &TTSEngine<B>,
+    queue: Option<&JobQueue>,
+    access: &Access,
+    request: Request,
+) {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if let (Some(queue), Some(rest)) = (queue, path.strip_prefix("/jobs")) {
+        // The client hung up before reading the response
+        let trusted = request
+            .remote_addr()
+            .is_some_and(|addr| access.trusted_proxies.contains(&addr.ip()));
+        let _ = route_jobs(queue, rest, trusted, &access.callback_hosts, request);
+        return;
+    }
+
+    let result = match (request.method(), path.as_str()) {
+        (Method::Get, "/health") => match engine.health_check() {
+            Ok(health) => request.respond(json(StatusCode(200), &health)),
+            Err(e) => request.respond(error(StatusCode(502), &e.to_string())),
+        },
+        (Method::Get, "/stream") => {
+            upgrade_websocket(engine, request);
+            Ok(())
+        }
+        _ => request.respond(error(StatusCode(404), "not found")),
+    };
+    // The client hung up before reading the response
+    let _ = result;
+}
+
+/// Handle `/jobs` requests; `trusted` says they came from a trusted proxy.
+fn route_jobs(
+    queue: &JobQueue,
+    path: &str,
+    trusted: bool,
+    callback_hosts: &[String],
+    mut request: Request,
+) -> std::io::Result<()> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match (request.method(), segments.as_slice()) {
+        (Method::Post, []) => {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body)?;
+            let mut submit: SubmitRequest = match serde_json::from_str(&body) {
+                Ok(submit) => submit,
+                Err(e) => return request.respond(error(StatusCode(400), &e.to_string())),
+            };
+            let callback = submit.callback.as_deref();
+            if let Some(Err(message)) = callback.map(|url| validate_callback(url, callback_hosts)) {
+                return request.respond(error(StatusCode(400), &message));
+            }
+            if !trusted {
+                submit.priority = submit.priority.min(Priority::Normal);
+            }
+            match queue.submit(&client_id(&request, trusted), submit) {
+                Ok(job) => request.respond(json(StatusCode(202), &job)),
+                Err(e) => request.respond(error(StatusCode(500), &e.to_string())),
+            }
+        }
+        (Method::Get, [id]) => match queue.get(id) {
+            Some(job) => request.respond(json(StatusCode(200), &job)),
+            None => request.respond(error(StatusCode(404), "no such job")),
+        },
+        (Method::Get, [id, "audio"]) => match (queue.get(id), queue.audio_path(id)) {
+            (_, Some(path)) => {
+                let file = std::fs::File::open(path)?;
+                let content_type = Header::from_bytes("Content-Type", "audio/wav")
+                    .expect("static header is valid");
+                request.respond(Response::from_file(file).with_header(content_type))
+            }
+            (Some(job), None) if job.status == QueueStatus::Failed => {
+                let message = job.error.unwrap_or_default();
+                request.respond(error(StatusCode(500), &message))
+            }
+            (Some(_), None) => request.respond(error(StatusCode(409), "job is not finished")),
+            (None, None) => request.respond(error(StatusCode(404), "no such job")),
+        },
+        _ => request.respond(error(StatusCode(404), "not found")),
+    }
+}
+
+/// Client identity for concurrency limits: the peer address, or the
+/// `X-Client-Id` header when a trusted proxy sent it.
+fn client_id(request: &Request, trusted: bool) -> String {
+    header(request, "X-Client-Id")
+        .filter(|_| trusted)
+        .or_else(|| request.remote_addr().map(|addr| addr.ip().to_string()))
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+fn upgrade_websocket<B: Backend>(engine: &TTSEngine<B>, request: Request) {
+    let key = header(&request, "Sec-WebSocket-Key");
+    let wants_upgrade =
+        header(&request, "Upgrade").is_some_and(|u| u.eq_ignore_ascii_case("websocket"));
+    let Some(key) = key.filter(|_| wants_upgrade) else {
+        let _ = request.respond(error(StatusCode(426), "expected a WebSocket upgrade"));
+        return;
+    };
+
+    let accept = Header::from_bytes("Sec-WebSocket-Accept", derive_accept_key(key.as_bytes()))
+        .expect("accept key is a valid header");
+    let response = Response::empty(StatusCode(101)).with_header(accept);
+    let stream = request.upgrade("websocket", response);
+
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    // Streaming errors mean the client went away; nothing is left to report
+    let _ = stream_session(engine, &mut socket);
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+fn json(status: StatusCode, body: &impl serde::Serialize) -> Response<std::io::Cursor<Vec<u8>>> {
+    let body = serde_json::to_vec(body).expect("responses always serialize");
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(
+            Header::from_bytes("Content-Type", "application/json").expect("static header is valid"),
+        )
+}
+
+fn error(status: StatusCode, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    json(status, &serde_json::json!({ "error": message }))
+}
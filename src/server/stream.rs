@@ -0,0 +1,142 @@
+//! WebSocket streaming of synthesized PCM.
+//!
+//! A client sends one text message with a [`StreamRequest`]. The server
+//! answers with a `start` message carrying the audio format, binary
+//! messages of interleaved 16-bit little-endian PCM as each chunk finishes,
+//! and a final `done` or `error` message.
+
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message, WebSocket};
+
+use super::ServerError;
+use crate::audio::AudioBuffer;
+use crate::backend::Backend;
+use crate::engine::TTSEngine;
+use crate::text::{chunk_text, split_sentences};
+
+/// Frames per binary message (about 85 ms at 24 kHz).
+const FRAMES_PER_MESSAGE: usize = 2048;
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+/// Text to stream, as sent by the client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamRequest {
+    pub text: String,
+    #[serde(default)]
+    pub voice: Option<String>,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+}
+
+/// Control messages sent to the client as text frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// Sent before the first audio frame.
+    Start {
+        sample_rate: u32,
+        channels: u16,
+    },
+    /// All audio has been sent.
+    Done {
+        seconds: f64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Encode samples as interleaved 16-bit little-endian PCM messages.
+pub fn pcm_messages(audio: &AudioBuffer) -> Vec<Vec<u8>> {
+    let per_message = FRAMES_PER_MESSAGE * audio.channels.max(1) as usize;
+    audio
+        .samples
+        .chunks(per_message)
+        .map(|samples| {
+            samples
+                .iter()
+                .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+                .collect()
+        })
+        .collect()
+}
+
+/// Run one streaming session: read the request, then stream until the text
+/// is spoken or the client disconnects.
+pub fn stream_session<B: Backend, S: Read + Write>(
+    engine: &TTSEngine<B>,
+    socket: &mut WebSocket<S>,
+) -> Result<(), ServerError> {
+    let request = loop {
+        match socket.read().map_err(websocket_error)? {
+            Message::Text(text) => break serde_json::from_str::<StreamRequest>(&text),
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        }
+    };
+
+    let result = request
+        .map_err(|e| format!("invalid request: {e}"))
+        .and_then(|request| {
+            chunk_text(&request.text, request.voice.as_deref(), request.speed)
+                .map_err(|e| e.to_string())
+        })
+        .and_then(|chunks| {
+            let mut seconds = 0.0;
+            let mut started = false;
+            let mut failed = None;
+            let outcome = engine.synthesize_streaming(&split_sentences(chunks), |audio| {
+                if !started {
+                    started = true;
+                    let start = StreamEvent::Start {
+                        sample_rate: audio.sample_rate,
+                        channels: audio.channels,
+                    };
+                    if let Err(e) = send_event(socket, &start) {
+                        failed = Some(e);
+                        return false;
+                    }
+                }
+                seconds += audio.duration().as_secs_f64();
+                for message in pcm_messages(&audio) {
+                    if let Err(e) = socket.send(Message::Binary(message)) {
+                        failed = Some(websocket_error(e));
+                        return false;
+                    }
+                }
+                true
+            });
+            match (failed, outcome) {
+                (Some(e), _) => Err(e.to_string()),
+                (None, Err(e)) => Err(e.to_string()),
+                (None, Ok(())) => Ok(seconds),
+            }
+        });
+
+    let event = match result {
+        Ok(seconds) => StreamEvent::Done { seconds },
+        Err(message) => StreamEvent::Error { message },
+    };
+    send_event(socket, &event)?;
+    socket.close(None).map_err(websocket_error)?;
+    // Drain until the client acknowledges the close
+    while socket.read().is_ok() {}
+    Ok(())
+}
+
+fn send_event<S: Read + Write>(
+    socket: &mut WebSocket<S>,
+    event: &StreamEvent,
+) -> Result<(), ServerError> {
+    let text = serde_json::to_string(event)?;
+    socket.send(Message::Text(text)).map_err(websocket_error)
+}
+
+fn websocket_error(e: tungstenite::Error) -> ServerError {
+    ServerError::WebSocket(e.to_string())
+}
@@ -0,0 +1,125 @@
+//! Completion callbacks for queued jobs.
+//!
+//! A job submitted with a `callback` URL gets a JSON POST when it finishes
+//! or fails, so publishing pipelines need not poll.
+//!
+//! Anyone who can submit a job picks the URL, so callbacks to loopback,
+//! private, and link-local addresses are refused unless the host is
+//! allowlisted; otherwise a job could make the server probe its own
+//! network or a cloud metadata endpoint.
+
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::ServerError;
+use super::queue::{QueueStatus, QueuedJob};
+
+/// Delivery attempts before giving up.
+const ATTEMPTS: u32 = 3;
+
+/// Body POSTed to a job's callback URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobNotice {
+    #[serde(flatten)]
+    pub job: QueuedJob,
+    /// Route serving the audio, relative to the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_url: Option<String>,
+    /// Location of the audio on the server's disk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_path: Option<PathBuf>,
+}
+
+impl JobNotice {
+    /// Describe a finished job whose audio (if any) is at `audio_path`.
+    pub fn new(job: QueuedJob, audio_path: Option<PathBuf>) -> Self {
+        let audio_url =
+            (job.status == QueueStatus::Done).then(|| format!("/jobs/{}/audio", job.id));
+        Self {
+            job,
+            audio_url,
+            audio_path,
+        }
+    }
+}
+
+/// Check that a callback URL can be delivered to, and that its host
+/// resolves only to public addresses unless it is one of `allowed_hosts`.
+pub fn validate_callback(url: &str, allowed_hosts: &[String]) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid callback URL: {e}"))?;
+    match parsed.scheme() {
+        "http" | "https" => {}
+        scheme => return Err(format!("callback URL must be http or https, not {scheme}")),
+    }
+    let host = parsed
+        .host_str()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+        .ok_or_else(|| "callback URL has no host".to_string())?;
+    if allowed_hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+        return Ok(());
+    }
+
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("cannot resolve callback host {host}: {e}"))?;
+    for addr in addrs {
+        if is_internal(addr.ip()) {
+            return Err(format!(
+                "callback host {host} resolves to internal address {}",
+                addr.ip()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `ip` is loopback, private, link-local, or otherwise not a
+/// public unicast address.
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_internal(IpAddr::V4(v4)),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+            }
+        },
+    }
+}
+
+/// POST `notice` to `url`, retrying with backoff when the receiver is down
+/// or answers with an error status.
+pub fn deliver(url: &str, notice: &JobNotice) -> Result<(), ServerError> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| ServerError::Callback(e.to_string()))?;
+
+    let mut last_error = String::new();
+    for attempt in 0..ATTEMPTS {
+        if attempt > 0 {
+            std::thread::sleep(Duration::from_secs(1 << (attempt - 1)));
+        }
+        match client.post(url).json(notice).send() {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => last_error = format!("receiver answered {}", response.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(ServerError::Callback(last_error))
+}
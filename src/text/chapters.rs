@@ -0,0 +1,61 @@
+//! Chapter boundaries in plain text.
+//!
+//! A chapter starts at a short line of its own, after a blank line, that
+//! matches a pattern such as `Chapter 12` or `PROLOGUE`. A Markdown `#`
+//! before it is ignored, so headings of converted manuscripts match too.
+
+use regex::Regex;
+
+/// Heading lines that start a chapter unless a pattern is configured.
+pub const DEFAULT_CHAPTER_PATTERN: &str = r"(?i)^(chapter|prologue|epilogue)\b";
+
+/// Longest line taken as a chapter heading; longer lines are prose.
+const MAX_HEADING_CHARS: usize = 80;
+
+/// A chapter's heading and text, which starts with the heading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChapterText {
+    pub title: String,
+    pub text: String,
+}
+
+/// Split `text` before each heading line matching `pattern`. Text before
+/// the first heading, such as a title page, belongs to the first chapter.
+/// Without a matching heading the whole text is one untitled chapter.
+pub fn split_chapters(text: &str, pattern: &Regex) -> Vec<ChapterText> {
+    let mut chapters: Vec<ChapterText> = Vec::new();
+    let mut preamble = String::new();
+    let mut after_blank = true;
+
+    for line in text.split_inclusive('\n') {
+        let heading = line.trim().trim_start_matches('#').trim();
+        let is_heading = after_blank
+            && !heading.is_empty()
+            && heading.chars().count() <= MAX_HEADING_CHARS
+            && pattern.is_match(heading);
+        after_blank = line.trim().is_empty();
+
+        if is_heading {
+            let text = match chapters.is_empty() {
+                true => std::mem::take(&mut preamble),
+                false => String::new(),
+            };
+            chapters.push(ChapterText {
+                title: heading.to_string(),
+                text,
+            });
+        }
+        match chapters.last_mut() {
+            Some(chapter) => chapter.text.push_str(line),
+            None => preamble.push_str(line),
+        }
+    }
+
+    if chapters.is_empty() {
+        chapters.push(ChapterText {
+            title: String::new(),
+            text: preamble,
+        });
+    }
+    chapters
+}
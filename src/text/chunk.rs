@@ -0,0 +1,565 @@
+//! Splitting input text into synthesis chunks.
+//!
+//! Inline tags switch voice or speed for the text that follows them, or
+//! insert a pause or a bleep:
+//!
+//! ```text
+//! [voice:alice]Hello Bob.[pause:500ms][voice:bob][speed:0.9]Hi Alice.
+//! What the [bleep:300ms] was that?
+//! ```
+//!
+//! `[voice:default]` and `[speed:default]` restore the run's settings.
+//! Phoneme segments (`[[ph: ...]]`) stay in the text and are checked here.
+//! Text is normalized with [`normalize_text`] before it is split.
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::TextError;
+use super::normalize::{normalize_text, same_direction};
+use super::phoneme::check_phonemes;
+use super::segment::sentences;
+
+static TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[(voice|pause|bleep|speed):\s*([^\]]*?)\s*\]").unwrap());
+static CLAUSE_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[,;:\u{2013}\u{2014}\u{060C}\u{061B}]\s+").unwrap());
+
+/// A unit of work produced by the chunker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Chunk {
+    /// Text to synthesize with a specific voice and speed.
+    Speech {
+        text: String,
+        voice: Option<String>,
+        speed: f32,
+    },
+    /// Silence of the given length.
+    Pause(Duration),
+    /// A tone of the given length, masking a word.
+    Bleep(Duration),
+}
+
+/// Typical narration rate used for duration estimates (150 words/minute).
+const WORDS_PER_SECOND: f64 = 2.5;
+
+impl Chunk {
+    /// Rough spoken duration of this chunk.
+    ///
+    /// Speech is estimated from its word count at a typical narration rate
+    /// scaled by speed; pauses and bleeps are exact.
+    pub fn estimated_duration(&self) -> Duration {
+        match self {
+            Chunk::Speech { text, speed, .. } => {
+                let words = text.split_whitespace().count() as f64;
+                let speed = f64::from(*speed).max(0.1);
+                Duration::from_secs_f64(words / WORDS_PER_SECOND / speed)
+            }
+            Chunk::Pause(duration) | Chunk::Bleep(duration) => *duration,
+        }
+    }
+
+    /// Name of the per-voice track this chunk is placed on.
+    ///
+    /// Speech without a voice goes on the `default` track; pauses and
+    /// bleeps belong to no track and return an empty name.
+    pub fn track(&self) -> &str {
+        match self {
+            Chunk::Speech { voice, .. } => voice.as_deref().unwrap_or("default"),
+            Chunk::Pause(_) | Chunk::Bleep(_) => "",
+        }
+    }
+}
+
+/// Parse text with inline tags into chunks.
+///
+/// `voice` and `speed` are the defaults in effect before any tag.
+/// Whitespace-only text between tags produces no chunk.
+pub fn chunk_text(text: &str, voice: Option<&str>, speed: f32) -> Result<Vec<Chunk>, TextError> {
+    let text = &normalize_text(text);
+    check_phonemes(text)?;
+    let mut chunks = Vec::new();
+    let mut current_voice = voice.map(str::to_string);
+    let mut current_speed = speed;
+    let mut last = 0;
+
+    for caps in TAG.captures_iter(text) {
+        let tag = caps.get(0).unwrap();
+        push_speech(
+            &mut chunks,
+            &text[last..tag.start()],
+            &current_voice,
+            current_speed,
+        );
+        last = tag.end();
+
+        let value = &caps[2];
+        match &caps[1] {
+            "voice" if value == "default" => current_voice = voice.map(str::to_string),
+            "voice" if value.is_empty() => {
+                return Err(TextError::InvalidTag(tag.as_str().to_string()));
+            }
+            "voice" => current_voice = Some(value.to_string()),
+            "speed" if value == "default" => current_speed = speed,
+            "speed" => {
+                current_speed = parse_speed(value)
+                    .map_err(|_| TextError::InvalidTag(tag.as_str().to_string()))?;
+            }
+            kind => {
+                let duration = parse_duration(value)
+                    .map_err(|_| TextError::InvalidTag(tag.as_str().to_string()))?;
+                chunks.push(match kind {
+                    "bleep" => Chunk::Bleep(duration),
+                    _ => Chunk::Pause(duration),
+                });
+            }
+        }
+    }
+
+    push_speech(&mut chunks, &text[last..], &current_voice, current_speed);
+    Ok(chunks)
+}
+
+/// Split speech chunks into one chunk per sentence.
+///
+/// Sentences are found by [`sentences`](super::sentences). Voice and speed
+/// carry over to every resulting chunk.
+pub fn split_sentences(chunks: Vec<Chunk>) -> Vec<Chunk> {
+    pace(chunks, Pacing::default())
+}
+
+/// Silence inserted between the sentences of a text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pacing {
+    /// Pause between sentences of a paragraph.
+    pub sentence: Duration,
+    /// Pause after a paragraph or heading.
+    pub paragraph: Duration,
+}
+
+/// Split speech chunks into sentences like [`split_sentences`], with the
+/// pauses of `pacing` between them.
+///
+/// Paragraphs are separated by blank lines; a markdown heading (`# ...`)
+/// is a paragraph of its own. Pauses are only inserted inside a speech
+/// chunk, so `[pause:...]` tags are never lengthened.
+pub fn pace(chunks: Vec<Chunk>, pacing: Pacing) -> Vec<Chunk> {
+    let mut output = Vec::with_capacity(chunks.len());
+
+    for chunk in chunks {
+        let Chunk::Speech { text, voice, speed } = chunk else {
+            output.push(chunk);
+            continue;
+        };
+
+        for (p, paragraph) in paragraphs(&text).iter().enumerate() {
+            if p > 0 {
+                push_pause(&mut output, pacing.paragraph);
+            }
+            for (s, sentence) in sentences(paragraph).into_iter().enumerate() {
+                if s > 0 {
+                    push_pause(&mut output, pacing.sentence);
+                }
+                push_speech(&mut output, sentence, &voice, speed);
+            }
+        }
+    }
+
+    output
+}
+
+/// Non-blank paragraphs of `text`, with each heading line on its own.
+fn paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        let heading = line.trim_start().starts_with('#');
+        if line.trim().is_empty() || heading {
+            paragraphs.push(std::mem::take(&mut current));
+        }
+        if heading {
+            paragraphs.push(line.to_string());
+        } else if !line.trim().is_empty() {
+            current.push_str(line);
+            current.push('\n');
+        }
+    }
+    paragraphs.push(current);
+    paragraphs.retain(|p| !p.trim().is_empty());
+    paragraphs
+}
+
+fn push_pause(chunks: &mut Vec<Chunk>, duration: Duration) {
+    if !duration.is_zero() {
+        chunks.push(Chunk::Pause(duration));
+    }
+}
+
+/// Split text into pieces of at most `max` characters.
+///
+/// Pieces break at sentence ends where possible, then at clause
+/// punctuation, then between words; only a single word longer than `max`
+/// is cut mid-word. Neighbouring sentences are packed into the same piece
+/// while they fit, so as few requests as possible are made, but a sentence
+/// in a right-to-left script is not packed with one in a left-to-right
+/// script: each piece is sent in one base direction.
+pub fn split_to_length(text: &str, max: usize) -> Vec<String> {
+    split_at(text.trim(), max.max(1), Boundary::Sentence)
+}
+
+/// Where text is broken, from the most to the least natural.
+#[derive(Debug, Clone, Copy)]
+enum Boundary {
+    Sentence,
+    Clause,
+    Word,
+    Character,
+}
+
+fn split_at(text: &str, max: usize, boundary: Boundary) -> Vec<String> {
+    if text.chars().count() <= max {
+        return vec![text.to_string()];
+    }
+
+    let (parts, next) = match boundary {
+        Boundary::Sentence => (sentences(text), Boundary::Clause),
+        Boundary::Clause => (split_after(text, &CLAUSE_END), Boundary::Word),
+        Boundary::Word => (text.split_whitespace().collect(), Boundary::Character),
+        Boundary::Character => {
+            let chars: Vec<char> = text.chars().collect();
+            return chars.chunks(max).map(|c| c.iter().collect()).collect();
+        }
+    };
+
+    let mut pieces: Vec<String> = Vec::new();
+    for part in parts.into_iter().flat_map(|part| split_at(part, max, next)) {
+        match pieces.last_mut() {
+            Some(last)
+                if last.chars().count() + 1 + part.chars().count() <= max
+                    && (!matches!(boundary, Boundary::Sentence) || same_direction(last, &part)) =>
+            {
+                last.push(' ');
+                last.push_str(&part);
+            }
+            _ => pieces.push(part),
+        }
+    }
+    pieces
+}
+
+/// Trimmed, non-empty pieces of `text`, each ending with a match of `end`.
+fn split_after<'a>(text: &'a str, end: &Regex) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut last = 0;
+    for found in end.find_iter(text) {
+        parts.push(text[last..found.end()].trim());
+        last = found.end();
+    }
+    parts.push(text[last..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn push_speech(chunks: &mut Vec<Chunk>, text: &str, voice: &Option<String>, speed: f32) {
+    let text = text.trim();
+    if !text.is_empty() {
+        chunks.push(Chunk::Speech {
+            text: text.to_string(),
+            voice: voice.clone(),
+            speed,
+        });
+    }
+}
+
+/// Parse a duration such as `500ms`, `1.5s`, `2m`, or `2` (seconds).
+pub fn parse_duration(input: &str) -> Result<Duration, TextError> {
+    let input = input.trim();
+    let invalid = || TextError::InvalidDuration(input.to_string());
+
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    let value: f64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" | "min" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(invalid()),
+    };
+
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Parse a speed multiplier such as `0.85`, or a percentage of normal
+/// speed such as `85%`.
+///
+/// Only the form is checked; which speeds a backend accepts is up to its
+/// [`Capabilities`](crate::backend::Capabilities).
+pub fn parse_speed(input: &str) -> Result<f32, TextError> {
+    let input = input.trim();
+    let invalid = || TextError::InvalidSpeed(input.to_string());
+
+    let speed = match input.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().map_err(|_| invalid())? / 100.0,
+        None => input.parse::<f32>().map_err(|_| invalid())?,
+    };
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(invalid());
+    }
+    Ok(speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speech(text: &str, voice: Option<&str>, speed: f32) -> Chunk {
+        Chunk::Speech {
+            text: text.to_string(),
+            voice: voice.map(str::to_string),
+            speed,
+        }
+    }
+
+    #[test]
+    fn test_chunk_plain_text_is_single_chunk() {
+        let chunks = chunk_text("Hello world.", Some("narrator"), 1.0).unwrap();
+        assert_eq!(chunks, vec![speech("Hello world.", Some("narrator"), 1.0)]);
+    }
+
+    #[test]
+    fn test_chunk_voice_switch() {
+        let chunks = chunk_text("Intro. [voice:bob] Hi there.", None, 1.0).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                speech("Intro.", None, 1.0),
+                speech("Hi there.", Some("bob"), 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_voice_default_restores() {
+        let chunks = chunk_text("[voice:bob]One[voice:default]Two", Some("amy"), 1.0).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                speech("One", Some("bob"), 1.0),
+                speech("Two", Some("amy"), 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_pause() {
+        let chunks = chunk_text("Wait[pause:500ms]for it", None, 1.0).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                speech("Wait", None, 1.0),
+                Chunk::Pause(Duration::from_millis(500)),
+                speech("for it", None, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_speed() {
+        let chunks = chunk_text("[speed:0.9]Slow[speed:default]Normal", None, 1.2).unwrap();
+        assert_eq!(
+            chunks,
+            vec![speech("Slow", None, 0.9), speech("Normal", None, 1.2)]
+        );
+    }
+
+    #[test]
+    fn test_chunk_leaves_other_brackets() {
+        let chunks = chunk_text("See [note: 3] below", None, 1.0).unwrap();
+        assert_eq!(chunks, vec![speech("See [note: 3] below", None, 1.0)]);
+    }
+
+    #[test]
+    fn test_chunk_invalid_speed() {
+        let result = chunk_text("[speed:fast]Hi", None, 1.0);
+        assert!(matches!(result.unwrap_err(), TextError::InvalidTag(_)));
+
+        assert!(chunk_text("[speed:0]Hi", None, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_chunk_invalid_pause() {
+        assert!(chunk_text("[pause:soon]", None, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_chunk_empty_voice() {
+        assert!(chunk_text("[voice:]Hi", None, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_chunk_empty_text() {
+        assert!(chunk_text("   ", None, 1.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        let chunks = vec![
+            speech("One. Two! Three?\nFour", Some("amy"), 1.1),
+            Chunk::Pause(Duration::from_secs(1)),
+            speech("Five.", None, 1.0),
+        ];
+
+        assert_eq!(
+            split_sentences(chunks),
+            vec![
+                speech("One.", Some("amy"), 1.1),
+                speech("Two!", Some("amy"), 1.1),
+                speech("Three?", Some("amy"), 1.1),
+                speech("Four", Some("amy"), 1.1),
+                Chunk::Pause(Duration::from_secs(1)),
+                speech("Five.", None, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_sentences_keeps_decimals() {
+        let chunks = split_sentences(vec![speech("Pi is 3.14 today.", None, 1.0)]);
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_pace_inserts_sentence_and_paragraph_pauses() {
+        let pacing = Pacing {
+            sentence: Duration::from_millis(250),
+            paragraph: Duration::from_millis(700),
+        };
+        let chunks = vec![
+            speech(
+                "# Chapter One\nIt was dark. It rained.\n\nMorning came.",
+                Some("amy"),
+                1.0,
+            ),
+            Chunk::Pause(Duration::from_secs(1)),
+            speech("The end.", None, 1.0),
+        ];
+
+        assert_eq!(
+            pace(chunks.clone(), pacing),
+            vec![
+                speech("# Chapter One", Some("amy"), 1.0),
+                Chunk::Pause(Duration::from_millis(700)),
+                speech("It was dark.", Some("amy"), 1.0),
+                Chunk::Pause(Duration::from_millis(250)),
+                speech("It rained.", Some("amy"), 1.0),
+                Chunk::Pause(Duration::from_millis(700)),
+                speech("Morning came.", Some("amy"), 1.0),
+                Chunk::Pause(Duration::from_secs(1)),
+                speech("The end.", None, 1.0),
+            ]
+        );
+        assert_eq!(
+            pace(chunks.clone(), Pacing::default()),
+            split_sentences(chunks)
+        );
+    }
+
+    #[test]
+    fn test_split_to_length_packs_sentences() {
+        let text = "One. Two. Three is longer.";
+        assert_eq!(split_to_length(text, 100), vec![text]);
+        assert_eq!(
+            split_to_length(text, 10),
+            vec!["One. Two.", "Three is", "longer."]
+        );
+        assert_eq!(
+            split_to_length(text, 18),
+            vec!["One. Two.", "Three is longer."]
+        );
+    }
+
+    #[test]
+    fn test_split_to_length_keeps_directions_apart() {
+        let text = "Read this aloud. שלום עולם. מה שלומך? 42. Then English again.";
+        assert_eq!(
+            split_to_length(text, 40),
+            vec![
+                "Read this aloud.",
+                "שלום עולם. מה שלומך? 42.",
+                "Then English again."
+            ]
+        );
+        // Arabic clauses break at the Arabic comma
+        assert_eq!(
+            split_to_length("ذهبت إلى السوق، واشتريت خبزا", 16),
+            vec!["ذهبت إلى السوق،", "واشتريت خبزا"]
+        );
+    }
+
+    #[test]
+    fn test_split_to_length_prefers_clauses_then_words() {
+        assert_eq!(
+            split_to_length("Well, if you insist, we go now", 20),
+            vec!["Well, if you insist,", "we go now"]
+        );
+        assert_eq!(
+            split_to_length("Supercalifragilistic", 8),
+            vec!["Supercal", "ifragili", "stic"]
+        );
+        for piece in split_to_length(&"word ".repeat(50), 17) {
+            assert!(piece.chars().count() <= 17);
+        }
+    }
+
+    #[test]
+    fn test_estimated_duration() {
+        let chunk = speech("one two three four five", None, 1.0);
+        assert_eq!(chunk.estimated_duration(), Duration::from_secs(2));
+
+        let fast = speech("one two three four five", None, 2.0);
+        assert_eq!(fast.estimated_duration(), Duration::from_secs(1));
+
+        let pause = Chunk::Pause(Duration::from_millis(300));
+        assert_eq!(pause.estimated_duration(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("5 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_parse_speed_forms() {
+        assert_eq!(parse_speed("0.85").unwrap(), 0.85);
+        assert_eq!(parse_speed("85%").unwrap(), 0.85);
+        assert_eq!(parse_speed(" 120 % ").unwrap(), 1.2);
+        for invalid in ["", "%", "fast", "0", "-1", "0%", "inf", "NaN"] {
+            assert!(
+                matches!(parse_speed(invalid), Err(TextError::InvalidSpeed(_))),
+                "{invalid}"
+            );
+        }
+
+        let chunks = chunk_text("[speed:90%]Slow", None, 1.0).unwrap();
+        assert_eq!(chunks, vec![speech("Slow", None, 0.9)]);
+    }
+}
@@ -0,0 +1,108 @@
+//! Voice assignment from front matter and annotations.
+//!
+//! A source file may start with YAML front matter naming its default voice
+//! and the saved voice behind each character name:
+//!
+//! ```text
+//! ---
+//! title: Moby-Dick
+//! voice: narrator
+//! voices:
+//!   narrator: amy-2024
+//!   ahab: gravel
+//! ---
+//! ```
+//!
+//! and switch voices with HTML comments, which markdown renderers hide:
+//! `<!-- voice: ahab -->`. Annotations become inline tags in place, so
+//! `speed` and `pause` annotations work too, and one placed after a
+//! heading hands the section below it to that voice. Other comments are
+//! removed, since they are never meant to be read aloud.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use super::TextError;
+
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<!--\s*(?:(voice|speed|pause)\s*:\s*([^\s>][^>]*?)\s*|.*?)-->").unwrap()
+});
+static VOICE_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[voice:\s*([^\]]*?)\s*\]").unwrap());
+
+/// Settings read from a source's front matter. Other keys, such as
+/// `title`, are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+struct FrontMatter {
+    voice: Option<String>,
+    voices: BTreeMap<String, String>,
+}
+
+/// Source text with its front matter and annotations applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Document {
+    /// Saved voice for text before any voice switch.
+    pub voice: Option<String>,
+    /// The body, with annotations turned into inline tags and character
+    /// names in voice tags replaced by saved voices.
+    pub text: String,
+}
+
+impl Document {
+    /// Parse a source; text without front matter or annotations is
+    /// returned unchanged.
+    pub fn parse(source: &str) -> Result<Self, TextError> {
+        let (front_matter, body) = match split_front_matter(source) {
+            Some((yaml, body)) => (
+                serde_yaml_ng::from_str::<Option<FrontMatter>>(yaml)
+                    .map_err(|e| TextError::InvalidFrontMatter(e.to_string()))?
+                    .unwrap_or_default(),
+                body,
+            ),
+            None => (FrontMatter::default(), source),
+        };
+        let resolve = |name: &str| {
+            front_matter
+                .voices
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| name.to_string())
+        };
+
+        let text = COMMENT.replace_all(body, |caps: &Captures| match (caps.get(1), caps.get(2)) {
+            (Some(key), Some(value)) => format!("[{}:{}]", key.as_str(), value.as_str()),
+            _ => String::new(),
+        });
+        let text = VOICE_TAG.replace_all(&text, |caps: &Captures| match &caps[1] {
+            "default" => caps[0].to_string(),
+            name => format!("[voice:{}]", resolve(name)),
+        });
+
+        Ok(Self {
+            voice: front_matter.voice.as_deref().map(resolve),
+            text: text.into_owned(),
+        })
+    }
+}
+
+/// The YAML between a leading `---` line and the next `---` or `...` line,
+/// and the text after it.
+fn split_front_matter(source: &str) -> Option<(&str, &str)> {
+    let rest = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let rest = rest
+        .strip_prefix("---\n")
+        .or_else(|| rest.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
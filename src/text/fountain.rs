@@ -0,0 +1,314 @@
+//! Fountain screenplays, read as a table read.
+//!
+//! [Fountain](https://fountain.io) is plain text: scene headings start
+//! with `INT.` or `EXT.`, a character cue is a line in capitals followed
+//! by the character's dialogue, and a line in parentheses under the cue
+//! is a parenthetical such as `(whispering)`. A [`Screenplay`] turns a
+//! script into inline-tagged text for the dialogue engine: each speech
+//! in its character's voice, and scene headings and action in the
+//! narrator's, the default voice of the run.
+//!
+//! Parentheticals are direction, not dialogue, and are never read. Those
+//! about pacing become tags: `(beat)` and `(pause)` insert silence, and
+//! `(quickly)` or `(slowly)` change the speed of the rest of the speech.
+//! The backends have no control for delivery such as `(angrily)`, so
+//! those are dropped.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+static SCENE_HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(int\.?/ext|i/e|int|ext|est)[. ]\s*(.*)$").unwrap());
+static SCENE_NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s*#[\w.\-]+#\s*$").unwrap());
+static EXTENSION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\([^)]*\)").unwrap());
+static TITLE_KEY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z][\w ]*:").unwrap());
+static BONEYARD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)/\*.*?\*/").unwrap());
+static NOTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)\[\[.*?\]\]").unwrap());
+static EMPHASIS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\?[*_]").unwrap());
+
+/// One element of a screenplay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptElement {
+    /// A scene heading, as it should be read: `Interior. Kitchen, night.`
+    SceneHeading(String),
+    Action(String),
+    /// A character's speech: their name as cued, without extensions such
+    /// as `(V.O.)`, and the lines and parentheticals in order.
+    Dialogue {
+        character: String,
+        lines: Vec<ScriptLine>,
+    },
+}
+
+/// Part of a speech.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptLine {
+    Spoken(String),
+    /// Direction in parentheses, without them.
+    Parenthetical(String),
+}
+
+/// What is read besides dialogue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenplayOptions {
+    /// Saved voice for each character, matched case-insensitively.
+    /// Characters not listed keep their name as the voice name, to be
+    /// resolved later, for example by a project's `[voices]`.
+    pub cast: BTreeMap<String, String>,
+    pub scene_headings: bool,
+    pub action: bool,
+}
+
+impl Default for ScreenplayOptions {
+    fn default() -> Self {
+        Self {
+            cast: BTreeMap::new(),
+            scene_headings: true,
+            action: true,
+        }
+    }
+}
+
+/// A parsed Fountain script.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Screenplay {
+    pub elements: Vec<ScriptElement>,
+}
+
+impl Screenplay {
+    /// Parse a Fountain script. The title page, notes, sections,
+    /// synopses, transitions, and page breaks are not read and are left
+    /// out.
+    pub fn parse(source: &str) -> Self {
+        let source = source.replace("\r\n", "\n");
+        let source = BONEYARD.replace_all(&source, "");
+        let source = NOTE.replace_all(&source, "");
+        let mut lines: Vec<&str> = source.lines().map(str::trim_end).collect();
+        skip_title_page(&mut lines);
+
+        let mut elements = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i].trim();
+            let after_blank = i == 0 || lines[i - 1].trim().is_empty();
+            let next = lines.get(i + 1).map(|l| l.trim()).unwrap_or_default();
+
+            if line.is_empty() || is_skipped(line) {
+                i += 1;
+            } else if after_blank && let Some(heading) = scene_heading(line) {
+                elements.push(ScriptElement::SceneHeading(heading));
+                i += 1;
+            } else if after_blank
+                && !next.is_empty()
+                && let Some(character) = character(line)
+            {
+                let mut speech = Vec::new();
+                i += 1;
+                while i < lines.len() && !lines[i].trim().is_empty() {
+                    let text = lines[i].trim();
+                    match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+                        Some(direction) => {
+                            speech.push(ScriptLine::Parenthetical(direction.to_string()))
+                        }
+                        None => {
+                            speech.push(ScriptLine::Spoken(plain(text.trim_start_matches('~'))))
+                        }
+                    }
+                    i += 1;
+                }
+                elements.push(ScriptElement::Dialogue {
+                    character,
+                    lines: speech,
+                });
+            } else {
+                // Action runs to the next blank line
+                let mut paragraph = Vec::new();
+                while i < lines.len() && !lines[i].trim().is_empty() {
+                    paragraph.push(action_line(lines[i].trim()));
+                    i += 1;
+                }
+                let text = paragraph.join(" ");
+                if !text.trim().is_empty() {
+                    elements.push(ScriptElement::Action(text));
+                }
+            }
+        }
+        Self { elements }
+    }
+
+    /// Every character with dialogue, in order of first appearance.
+    pub fn characters(&self) -> Vec<&str> {
+        let mut characters: Vec<&str> = Vec::new();
+        for element in &self.elements {
+            if let ScriptElement::Dialogue { character, .. } = element
+                && !characters.contains(&character.as_str())
+            {
+                characters.push(character);
+            }
+        }
+        characters
+    }
+
+    /// The script as inline-tagged text: one paragraph per element, each
+    /// speech in its character's voice and narration in the default voice.
+    pub fn to_text(&self, options: &ScreenplayOptions) -> String {
+        let mut paragraphs = Vec::new();
+        for element in &self.elements {
+            match element {
+                ScriptElement::SceneHeading(heading) if options.scene_headings => {
+                    paragraphs.push(format!("[voice:default]{heading}"));
+                }
+                ScriptElement::Action(text) if options.action => {
+                    paragraphs.push(format!("[voice:default]{text}"));
+                }
+                ScriptElement::Dialogue { character, lines } => {
+                    let voice = options
+                        .cast
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(character))
+                        .map_or(character.as_str(), |(_, voice)| voice.as_str());
+                    let mut speech = format!("[voice:{voice}]");
+                    let mut paced = false;
+                    for line in lines {
+                        match line {
+                            ScriptLine::Spoken(text) => {
+                                speech.push_str(text);
+                                speech.push(' ');
+                            }
+                            ScriptLine::Parenthetical(direction) => {
+                                if let Some(tag) = pacing_tag(direction) {
+                                    paced |= tag.starts_with("[speed");
+                                    speech.push_str(tag);
+                                }
+                            }
+                        }
+                    }
+                    let mut speech = speech.trim_end().to_string();
+                    if paced {
+                        speech.push_str("[speed:default]");
+                    }
+                    paragraphs.push(speech);
+                }
+                _ => {}
+            }
+        }
+        paragraphs.join("\n\n")
+    }
+}
+
+/// Drop the title page: `Key: value` lines up to the first blank line.
+fn skip_title_page(lines: &mut Vec<&str>) {
+    let first = lines.iter().position(|l| !l.trim().is_empty());
+    if let Some(first) = first
+        && TITLE_KEY.is_match(lines[first])
+        && !lines[first].trim_end().ends_with("TO:")
+    {
+        let end = lines[first..]
+            .iter()
+            .position(|l| l.trim().is_empty())
+            .map_or(lines.len(), |n| first + n);
+        lines.drain(..end);
+    }
+}
+
+/// Sections (`#`), synopses (`=`), page breaks (`===`), transitions
+/// (`CUT TO:`, `> FADE OUT`), and lone parentheticals.
+fn is_skipped(line: &str) -> bool {
+    line.starts_with('#')
+        || line.starts_with('=')
+        || (line.starts_with('>') && !line.ends_with('<'))
+        || (line.ends_with("TO:") && line == line.to_uppercase())
+        || (line.starts_with('(') && line.ends_with(')'))
+}
+
+/// A scene heading written out to be read: `INT. KITCHEN - NIGHT #12#`
+/// becomes `Interior. Kitchen, night.`
+fn scene_heading(line: &str) -> Option<String> {
+    let line = SCENE_NUMBER.replace(line, "");
+    let (setting, place) = if let Some(forced) = line.strip_prefix('.') {
+        if forced.starts_with('.') {
+            return None;
+        }
+        (None, forced.to_string())
+    } else {
+        let caps = SCENE_HEADING.captures(&line)?;
+        let setting = match caps[1].to_lowercase().as_str() {
+            "int" => "Interior.",
+            "ext" => "Exterior.",
+            "est" => "Establishing.",
+            _ => "Interior, exterior.",
+        };
+        (Some(setting), caps[2].to_string())
+    };
+
+    let place = place
+        .split(" - ")
+        .map(|part| capitalize(&part.trim().to_lowercase()))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let place = if place.is_empty() || place.ends_with(['.', '!', '?']) {
+        place
+    } else {
+        format!("{place}.")
+    };
+    Some(
+        [setting.unwrap_or_default(), place.as_str()]
+            .join(" ")
+            .trim()
+            .to_string(),
+    )
+}
+
+/// The character named by a cue: a line in capitals, or forced with `@`,
+/// without extensions like `(V.O.)` or the dual-dialogue `^`.
+fn character(line: &str) -> Option<String> {
+    let (forced, name) = match line.strip_prefix('@') {
+        Some(name) => (true, name),
+        None => (false, line),
+    };
+    let name = name.trim_end_matches('^');
+    let bare = EXTENSION.replace_all(name, "");
+    let bare = bare.trim();
+    let is_cue = forced
+        || (bare.chars().any(char::is_alphabetic)
+            && !bare.chars().any(char::is_lowercase)
+            && !line.starts_with('!'));
+    (is_cue && !bare.is_empty()).then(|| bare.to_string())
+}
+
+/// An action line without forcing marks, centering, or emphasis.
+fn action_line(line: &str) -> String {
+    let line = line.strip_prefix('!').unwrap_or(line);
+    let line = match line.strip_prefix('>').and_then(|l| l.strip_suffix('<')) {
+        Some(centered) => centered.trim(),
+        None => line,
+    };
+    plain(line.trim_start_matches('~'))
+}
+
+/// Text without Fountain's emphasis marks.
+fn plain(text: &str) -> String {
+    EMPHASIS.replace_all(text, "").trim().to_string()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The inline tag a parenthetical about pacing stands for.
+fn pacing_tag(direction: &str) -> Option<&'static str> {
+    match direction.trim().to_lowercase().as_str() {
+        "beat" | "a beat" | "pause" | "a pause" => Some("[pause:1s]"),
+        "long beat" | "long pause" => Some("[pause:2s]"),
+        "quickly" | "fast" | "rapidly" | "hurried" | "hurriedly" | "rushed" => Some("[speed:1.15]"),
+        "slowly" | "slow" | "deliberately" => Some("[speed:0.85]"),
+        _ => None,
+    }
+}
@@ -0,0 +1,182 @@
+//! Acronym and jargon glossary.
+//!
+//! A glossary maps terms such as `K8s` or `SQL` to how they are said
+//! (`kubernetes`, `sequel`, or `S Q L`). Terms match as whole words and
+//! case-sensitively, together with their plural and possessive forms, and
+//! the text is rewritten in one pass so a spoken form is never expanded
+//! again.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+use super::TextError;
+
+/// Endings a term keeps after expansion, as in `APIs` or `SQL's`.
+const SUFFIXES: &[&str] = &["'s", "’s", "s"];
+
+/// Terms and their spoken forms.
+#[derive(Debug, Clone, Default)]
+pub struct Glossary {
+    entries: BTreeMap<String, String>,
+    pattern: OnceLock<Option<Regex>>,
+}
+
+/// A glossary entry that will not be applied as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlossaryConflict {
+    /// A later glossary gave the term another spoken form, which is used.
+    Redefined {
+        term: String,
+        previous: String,
+        spoken: String,
+    },
+    /// A replace rule or lexicon entry rewrites the term before the
+    /// glossary sees it.
+    Shadowed { term: String, rewritten: String },
+}
+
+impl fmt::Display for GlossaryConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Redefined {
+                term,
+                previous,
+                spoken,
+            } => write!(
+                f,
+                "glossary term '{term}' is redefined as '{spoken}', replacing '{previous}'"
+            ),
+            Self::Shadowed { term, rewritten } => write!(
+                f,
+                "glossary term '{term}' is rewritten to '{rewritten}' by a replace rule or \
+                 lexicon entry, so the glossary never sees it"
+            ),
+        }
+    }
+}
+
+impl Glossary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a glossary from `term = spoken` entries, as in a `[glossary]`
+    /// table.
+    pub fn from_entries(entries: &BTreeMap<String, String>) -> Result<Self, TextError> {
+        let mut glossary = Self::new();
+        for (term, spoken) in entries {
+            glossary.insert(term, spoken)?;
+        }
+        Ok(glossary)
+    }
+
+    /// Read a TOML file of `term = "spoken"` lines.
+    pub fn load(path: &Path) -> Result<Self, TextError> {
+        let invalid =
+            |e: &dyn fmt::Display| TextError::InvalidGlossary(format!("{}: {e}", path.display()));
+        let contents = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+        let entries: BTreeMap<String, String> =
+            toml::from_str(&contents).map_err(|e| invalid(&e))?;
+        Self::from_entries(&entries).map_err(|e| invalid(&e))
+    }
+
+    /// Add a term, returning the spoken form it replaces, if any.
+    pub fn insert(&mut self, term: &str, spoken: &str) -> Result<Option<String>, TextError> {
+        let term = term.trim();
+        if term.is_empty() {
+            return Err(TextError::InvalidGlossary(format!(
+                "empty term for '{spoken}'"
+            )));
+        }
+        self.pattern = OnceLock::new();
+        Ok(self.entries.insert(term.to_string(), spoken.to_string()))
+    }
+
+    /// Add every entry of `other`, which wins where both define a term.
+    pub fn merge(&mut self, other: &Self) -> Vec<GlossaryConflict> {
+        let mut conflicts = Vec::new();
+        for (term, spoken) in &other.entries {
+            self.pattern = OnceLock::new();
+            match self.entries.insert(term.clone(), spoken.clone()) {
+                Some(previous) if previous != *spoken => {
+                    conflicts.push(GlossaryConflict::Redefined {
+                        term: term.clone(),
+                        previous,
+                        spoken: spoken.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        conflicts
+    }
+
+    pub fn get(&self, term: &str) -> Option<&str> {
+        self.entries.get(term).map(String::as_str)
+    }
+
+    /// Terms and spoken forms, in term order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(t, s)| (t.as_str(), s.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replace every term in `text` with its spoken form.
+    pub fn apply(&self, text: &str) -> String {
+        let Some(pattern) = self.pattern.get_or_init(|| self.compile()) else {
+            return text.to_string();
+        };
+        pattern
+            .replace_all(text, |caps: &Captures| {
+                let found = &caps[0];
+                if let Some(spoken) = self.get(found) {
+                    return spoken.to_string();
+                }
+                SUFFIXES
+                    .iter()
+                    .find_map(|suffix| {
+                        let spoken = self.get(found.strip_suffix(suffix)?)?;
+                        // A spelled-out acronym takes an apostrophe: "A P I's"
+                        let spelled = *suffix == "s" && spoken.ends_with(char::is_uppercase);
+                        Some(format!("{spoken}{}", if spelled { "'s" } else { suffix }))
+                    })
+                    .unwrap_or_else(|| found.to_string())
+            })
+            .into_owned()
+    }
+
+    /// One pattern matching every term, longest first so that a term is
+    /// preferred over its prefix.
+    fn compile(&self) -> Option<Regex> {
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let mut terms: Vec<&String> = self.entries.keys().collect();
+        terms.sort_by_key(|term| std::cmp::Reverse(term.chars().count()));
+
+        let alternatives: Vec<String> = terms
+            .iter()
+            .map(|term| {
+                let start = if is_word(term.chars().next()) {
+                    r"\b"
+                } else {
+                    ""
+                };
+                if is_word(term.chars().last()) {
+                    format!(r"{start}{}(?:'s|’s|s)?\b", regex::escape(term))
+                } else {
+                    format!("{start}{}", regex::escape(term))
+                }
+            })
+            .collect();
+        if alternatives.is_empty() {
+            return None;
+        }
+        Some(Regex::new(&alternatives.join("|")).expect("escaped terms form a valid pattern"))
+    }
+}
@@ -0,0 +1,444 @@
+//! Pronunciation lint: words a model is likely to get wrong.
+//!
+//! Without a model's vocabulary at hand the checks are heuristics, meant to
+//! surface words worth a listen before hours of rendering:
+//!
+//! - unusual spellings: letters outside a-z, inner capitals, no vowels, or
+//!   consonant clusters English does not start a word with,
+//! - numbers other than one- and two-digit counts, since years, decimals,
+//!   times, and ordinals have several readings,
+//! - acronyms, which may be spelled out or read as a word,
+//! - names seen with more than one spelling, which are then pronounced
+//!   inconsistently.
+//!
+//! Words appearing in `known`, such as lexicon spoken forms, are not
+//! reported, so a lexicon entry mapping a word to itself marks it checked.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+static TOKEN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\p{L}\p{N}]+(?:['’.,:/\-][\p{L}\p{N}]+)*").unwrap());
+static TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[(?:voice|speed|pause|bleep):[^\]]*\]|\[\[ph:[^\]]*\]\]").unwrap()
+});
+
+/// Consonant pairs and triples English words start with.
+const ONSETS: &[&str] = &[
+    "bl", "br", "ch", "cl", "cr", "dr", "dw", "fl", "fr", "gh", "gl", "gn", "gr", "gw", "kl", "kn",
+    "kr", "kw", "mn", "ph", "pl", "pn", "pr", "ps", "pt", "rh", "sc", "sh", "sk", "sl", "sm", "sn",
+    "sp", "sq", "st", "sw", "th", "tr", "tw", "wh", "wr", "chl", "chr", "phl", "phr", "sch", "scr",
+    "shr", "sph", "spl", "spr", "str", "thr", "thw",
+];
+
+/// What makes a word worth checking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintKind {
+    /// A name spelled more than one way.
+    Variant,
+    /// A spelling unlike English words.
+    Unusual,
+    /// Capitals that may be spelled out or read as a word.
+    Acronym,
+    /// A number with more than one reading.
+    Number,
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Variant => "variant",
+            Self::Unusual => "unusual",
+            Self::Acronym => "acronym",
+            Self::Number => "number",
+        })
+    }
+}
+
+/// One word to check, with where it was seen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintFinding {
+    pub kind: LintKind,
+    /// The word; for a variant, its most frequent spelling.
+    pub word: String,
+    /// Times the word was seen, all spellings included.
+    pub count: usize,
+    /// Sources the word was seen in.
+    pub sources: BTreeSet<String>,
+    /// Other spellings of a variant, with their counts.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, usize>,
+    /// Suggested spoken form for a lexicon entry, when one can be guessed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+/// Findings from every source a [`Linter`] was given.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LintReport {
+    pub findings: Vec<LintFinding>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Tally {
+    count: usize,
+    sources: BTreeSet<String>,
+}
+
+impl Tally {
+    fn add(&mut self, source: &str) {
+        self.count += 1;
+        if !self.sources.contains(source) {
+            self.sources.insert(source.to_string());
+        }
+    }
+}
+
+/// Collects words to check across the sources of a project.
+#[derive(Debug, Clone, Default)]
+pub struct Linter {
+    known: HashSet<String>,
+    words: BTreeMap<(LintKind, String), Tally>,
+    /// Capitalized words, which are names unless also seen in lower case.
+    capitalized: BTreeMap<String, Tally>,
+    lowercase: HashSet<String>,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Words never reported, such as the spoken forms of a lexicon.
+    pub fn with_known<S: AsRef<str>>(mut self, texts: impl IntoIterator<Item = S>) -> Self {
+        for text in texts {
+            for token in TOKEN.find_iter(text.as_ref()) {
+                self.known.insert(token.as_str().to_string());
+            }
+        }
+        self
+    }
+
+    /// Check the text of one source, named `source` in the report. Inline
+    /// tags are skipped.
+    pub fn add(&mut self, source: &str, text: &str) {
+        let text = TAG.replace_all(text, " ");
+        for line in text.lines() {
+            let tokens: Vec<&str> = TOKEN
+                .find_iter(line)
+                .filter(|m| !is_abbreviation(m.as_str(), &line[m.end()..]))
+                .map(|m| m.as_str())
+                .collect();
+            let capitals = tokens.iter().filter(|t| is_acronym(t)).count();
+            // A line in capitals is a shouted heading, not a run of acronyms
+            let shouted = capitals >= 3 && capitals * 2 > tokens.len();
+
+            for token in tokens {
+                if self.known.contains(token) {
+                    continue;
+                }
+                if token.chars().any(|c| c.is_numeric()) {
+                    if !is_count(token) {
+                        self.tally(LintKind::Number, token, source);
+                    }
+                    continue;
+                }
+                for word in token.split(['-', '/', ',', ':']) {
+                    self.add_word(word, source, shouted);
+                }
+            }
+        }
+    }
+
+    fn add_word(&mut self, word: &str, source: &str, shouted: bool) {
+        let word = word
+            .strip_suffix("'s")
+            .or_else(|| word.strip_suffix("’s"))
+            .unwrap_or(word);
+        if word.chars().count() < 2 || self.known.contains(word) {
+            return;
+        }
+
+        if is_acronym(word) && !shouted {
+            if !word.chars().all(|c| matches!(c, 'I' | 'V' | 'X')) {
+                self.tally(LintKind::Acronym, word, source);
+            }
+            return;
+        }
+        if is_unusual(word) {
+            self.tally(LintKind::Unusual, word, source);
+        }
+
+        let mut chars = word.chars();
+        if chars.next().is_some_and(char::is_uppercase) && chars.any(char::is_lowercase) {
+            self.capitalized
+                .entry(word.to_string())
+                .or_default()
+                .add(source);
+        } else if !word.chars().any(char::is_uppercase) {
+            self.lowercase.insert(word.to_string());
+        }
+    }
+
+    fn tally(&mut self, kind: LintKind, word: &str, source: &str) {
+        self.words
+            .entry((kind, word.to_string()))
+            .or_default()
+            .add(source);
+    }
+
+    /// Everything found so far, by kind and then most frequent first.
+    pub fn report(&self) -> LintReport {
+        let mut findings: Vec<LintFinding> = self
+            .words
+            .iter()
+            .map(|((kind, word), tally)| LintFinding {
+                kind: *kind,
+                word: word.clone(),
+                count: tally.count,
+                sources: tally.sources.clone(),
+                variants: BTreeMap::new(),
+                suggestion: (*kind == LintKind::Acronym).then(|| spell_out(word)),
+            })
+            .collect();
+        findings.extend(self.variants());
+        findings.sort_by(|a, b| (a.kind, b.count, &a.word).cmp(&(b.kind, a.count, &b.word)));
+        LintReport { findings }
+    }
+
+    /// Names whose spellings differ only in vowels or doubled letters.
+    fn variants(&self) -> Vec<LintFinding> {
+        let mut groups: BTreeMap<String, Vec<(&String, &Tally)>> = BTreeMap::new();
+        for (name, tally) in &self.capitalized {
+            if self.lowercase.contains(&name.to_lowercase()) {
+                continue;
+            }
+            let key = skeleton(name);
+            if key.chars().count() >= 3 {
+                groups.entry(key).or_default().push((name, tally));
+            }
+        }
+
+        groups
+            .into_values()
+            .filter(|names| names.len() > 1)
+            .map(|mut names| {
+                names.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+                let (word, _) = names[0];
+                LintFinding {
+                    kind: LintKind::Variant,
+                    word: word.clone(),
+                    count: names.iter().map(|(_, t)| t.count).sum(),
+                    sources: names.iter().flat_map(|(_, t)| t.sources.clone()).collect(),
+                    variants: names[1..]
+                        .iter()
+                        .map(|(name, tally)| ((*name).clone(), tally.count))
+                        .collect(),
+                    suggestion: Some(word.clone()),
+                }
+            })
+            .collect()
+    }
+}
+
+impl LintReport {
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Render as aligned text columns.
+    pub fn to_table(&self) -> String {
+        let mut lines = vec![[
+            "KIND".to_string(),
+            "WORD".to_string(),
+            "COUNT".to_string(),
+            "SOURCES".to_string(),
+            "SUGGESTION".to_string(),
+        ]];
+        for finding in &self.findings {
+            let mut word = finding.word.clone();
+            for variant in finding.variants.keys() {
+                word.push('/');
+                word.push_str(variant);
+            }
+            let mut sources: Vec<&str> =
+                finding.sources.iter().map(String::as_str).take(3).collect();
+            let more = format!("+{} more", finding.sources.len().saturating_sub(3));
+            if finding.sources.len() > 3 {
+                sources.push(&more);
+            }
+            lines.push([
+                finding.kind.to_string(),
+                word,
+                finding.count.to_string(),
+                sources.join(", "),
+                finding.lexicon_entries().join(", "),
+            ]);
+        }
+
+        let widths: Vec<usize> = (0..5)
+            .map(|col| {
+                lines
+                    .iter()
+                    .map(|l| l[col].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut table = String::new();
+        for line in &lines {
+            let row = format!(
+                "{:<w0$}  {:<w1$}  {:>w2$}  {:<w3$}  {}",
+                line[0],
+                line[1],
+                line[2],
+                line[3],
+                line[4],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+            );
+            table.push_str(row.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+
+    /// Suggested entries for a `[lexicon]` table, to be checked by ear.
+    pub fn lexicon(&self) -> String {
+        let mut lexicon = String::new();
+        for finding in &self.findings {
+            for entry in finding.lexicon_entries() {
+                lexicon.push_str(&entry);
+                lexicon.push('\n');
+            }
+        }
+        lexicon
+    }
+}
+
+impl LintFinding {
+    /// `word = "spoken"` lines for the suggestion: a variant's other
+    /// spellings map to its main one.
+    fn lexicon_entries(&self) -> Vec<String> {
+        let Some(suggestion) = &self.suggestion else {
+            return Vec::new();
+        };
+        let entry = |word: &str| format!("{} = {}", toml_key(word), toml_string(suggestion));
+        if self.variants.is_empty() {
+            vec![entry(&self.word)]
+        } else {
+            self.variants.keys().map(|variant| entry(variant)).collect()
+        }
+    }
+}
+
+fn toml_key(word: &str) -> String {
+    if word
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        word.to_string()
+    } else {
+        toml_string(word)
+    }
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// Capitals only, as in `NASA`.
+fn is_acronym(word: &str) -> bool {
+    word.chars().count() >= 2 && word.chars().all(|c| c.is_alphabetic() && c.is_uppercase())
+}
+
+/// Whether a token is an abbreviation such as `U.S.` or `Mr.`, which are
+/// left to the sentence splitter.
+fn is_abbreviation(token: &str, after: &str) -> bool {
+    if token.chars().any(|c| c.is_numeric()) {
+        return false;
+    }
+    token.contains('.')
+        || (after.starts_with('.')
+            && token.starts_with(char::is_uppercase)
+            && !token.to_lowercase().chars().any(is_vowel))
+}
+
+/// A number of one or two digits, which every model reads the same way.
+fn is_count(token: &str) -> bool {
+    token.len() <= 2 && token.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+fn is_unusual(word: &str) -> bool {
+    let letters: String = word.chars().filter(|c| !matches!(c, '\'' | '’')).collect();
+    if !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return true;
+    }
+    // Checked after a Scottish or Irish prefix, as in McDonald or O'Brien
+    let letters = ["Mac", "Mc", "O"]
+        .iter()
+        .find_map(|prefix| {
+            letters
+                .strip_prefix(prefix)
+                .filter(|rest| rest.starts_with(|c: char| c.is_ascii_uppercase()))
+        })
+        .unwrap_or(&letters);
+    let mixed = letters
+        .as_bytes()
+        .windows(2)
+        .any(|pair| pair[0].is_ascii_lowercase() && pair[1].is_ascii_uppercase());
+    if mixed {
+        return true;
+    }
+
+    let lower = letters.to_ascii_lowercase();
+    if !lower.chars().any(is_vowel) {
+        return true;
+    }
+    let onset: String = lower
+        .chars()
+        .enumerate()
+        .take_while(|&(i, c)| !is_vowel(c) || (i == 0 && c == 'y'))
+        .map(|(_, c)| c)
+        .collect();
+    if onset.len() >= 4 || (onset.len() >= 2 && !ONSETS.contains(&onset.as_str())) {
+        return true;
+    }
+    // A plural s may end a long cluster, as in "strengths"
+    let stem = lower.strip_suffix('s').unwrap_or(&lower);
+    let mut run = 0;
+    stem.chars().any(|c| {
+        run = if is_vowel(c) { 0 } else { run + 1 };
+        run >= 5
+    })
+}
+
+/// Letters spelled one by one, as in `N A S A`.
+fn spell_out(word: &str) -> String {
+    word.chars().map(String::from).collect::<Vec<_>>().join(" ")
+}
+
+/// A name's first letter and its consonants, doubled letters once, so
+/// `Tolkien` and `Tolkein` match.
+fn skeleton(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let mut chars = lower.chars();
+    let mut key: String = chars.next().into_iter().collect();
+    for c in chars {
+        if !is_vowel(c) && !key.ends_with(c) && c.is_alphabetic() {
+            key.push(c);
+        }
+    }
+    key
+}
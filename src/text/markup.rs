@@ -0,0 +1,192 @@
+//! Markdown, code fence, and emoji stripping.
+
+use std::sync::LazyLock;
+
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// How emoji in the input text are handled.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmojiMode {
+    /// Leave emoji untouched
+    #[default]
+    Keep,
+    /// Remove emoji
+    Strip,
+    /// Replace common emoji with a spoken description
+    Verbalize,
+}
+
+/// Options controlling markup removal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarkupOptions {
+    /// Strip markdown syntax, code fences, HTML tags, and bare URLs.
+    pub strip_markdown: bool,
+    /// Emoji handling.
+    pub emoji: EmojiMode,
+}
+
+static CODE_FENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?ms)^[ \t]*(```|~~~).*?^[ \t]*(```|~~~)[ \t]*$\n?").unwrap());
+static IMAGE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!\[([^\]]*)\]\([^)]*\)").unwrap());
+static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\]]+)\]\([^)]*\)").unwrap());
+static BARE_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<?(https?://|www\.)[^\s>)]+>?").unwrap());
+static HTML_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"</?[A-Za-z][A-Za-z0-9-]*(\s[^<>]*)?/?>").unwrap());
+static INLINE_CODE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`([^`]*)`").unwrap());
+static HEADING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^[ \t]*#{1,6}[ \t]+").unwrap());
+static BLOCKQUOTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^[ \t]*>[ \t]?").unwrap());
+static LIST_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^[ \t]*([-*+]|\d+[.)])[ \t]+").unwrap());
+static RULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^[ \t]*([-*_][ \t]*){3,}$\n?").unwrap());
+static STRONG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\*\*|__)([^\s*_](?:.*?[^\s*_])?)(\*\*|__)").unwrap());
+static EMPHASIS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(^|[^\w*])[*_]([^\s*_](?:[^*_]*?[^\s*_])?)[*_]").unwrap());
+static STRIKE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"~~(.+?)~~").unwrap());
+
+/// Spoken names for common emoji.
+const EMOJI_NAMES: &[(char, &str)] = &[
+    ('\u{1F600}', "grinning face"),
+    ('\u{1F602}', "face with tears of joy"),
+    ('\u{1F60A}', "smiling face"),
+    ('\u{1F642}', "slightly smiling face"),
+    ('\u{1F609}', "winking face"),
+    ('\u{1F60D}', "heart eyes"),
+    ('\u{1F622}', "crying face"),
+    ('\u{1F62D}', "loudly crying face"),
+    ('\u{1F621}', "angry face"),
+    ('\u{1F914}', "thinking face"),
+    ('\u{1F44D}', "thumbs up"),
+    ('\u{1F44E}', "thumbs down"),
+    ('\u{1F44F}', "clapping hands"),
+    ('\u{1F64F}', "folded hands"),
+    ('\u{1F44B}', "waving hand"),
+    ('\u{2764}', "red heart"),
+    ('\u{1F525}', "fire"),
+    ('\u{1F389}', "party popper"),
+    ('\u{1F680}', "rocket"),
+    ('\u{2705}', "check mark"),
+    ('\u{274C}', "cross mark"),
+    ('\u{26A0}', "warning"),
+    ('\u{2B50}', "star"),
+    ('\u{1F4A1}', "light bulb"),
+    ('\u{1F4AF}', "hundred points"),
+];
+
+/// Strip markup from text according to the given options.
+pub fn strip_markup(text: &str, options: &MarkupOptions) -> String {
+    let mut output = text.to_string();
+
+    if options.strip_markdown {
+        output = strip_markdown(&output);
+    }
+
+    match options.emoji {
+        EmojiMode::Keep => output,
+        EmojiMode::Strip => replace_emoji(&output, |_| None),
+        EmojiMode::Verbalize => replace_emoji(&output, emoji_name),
+    }
+}
+
+fn strip_markdown(text: &str) -> String {
+    let text = CODE_FENCE.replace_all(text, "");
+    let text = IMAGE.replace_all(&text, "$1");
+    let text = LINK.replace_all(&text, "$1");
+    let text = BARE_URL.replace_all(&text, "");
+    let text = HTML_TAG.replace_all(&text, "");
+    let text = INLINE_CODE.replace_all(&text, "$1");
+    let text = RULE.replace_all(&text, "");
+    let text = HEADING.replace_all(&text, "");
+    let text = BLOCKQUOTE.replace_all(&text, "");
+    let text = LIST_MARKER.replace_all(&text, "");
+    let text = STRONG.replace_all(&text, "$2");
+    let text = EMPHASIS.replace_all(&text, "$1$2");
+    let text = STRIKE.replace_all(&text, "$1");
+    text.into_owned()
+}
+
+fn emoji_name(c: char) -> Option<&'static str> {
+    EMOJI_NAMES
+        .iter()
+        .find(|(emoji, _)| *emoji == c)
+        .map(|(_, name)| *name)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F300..=0x1FAFF   // pictographs, emoticons, transport, supplemental
+        | 0x1F1E6..=0x1F1FF // regional indicators (flags)
+        | 0x2600..=0x27BF   // misc symbols and dingbats
+        | 0x2B50 | 0x2B55 | 0x2B06 | 0x2B07 | 0x2B05 | 0x2B1B | 0x2B1C)
+}
+
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xFE0E | 0xFE0F     // variation selectors
+        | 0x200D            // zero-width joiner
+        | 0x1F3FB
+            ..=0x1F3FF // skin tone modifiers
+        | 0x20E3
+    ) // combining keycap
+}
+
+/// Replace emoji with the name returned by `describe` (or nothing).
+fn replace_emoji(text: &str, describe: impl Fn(char) -> Option<&'static str>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if is_emoji_modifier(c) {
+            continue;
+        }
+        if !is_emoji(c) {
+            output.push(c);
+            continue;
+        }
+
+        // Collapse modifier and ZWJ sequences so one cluster yields one description
+        loop {
+            match chars.peek() {
+                Some('\u{200D}') => {
+                    chars.next();
+                    chars.next_if(|&n| is_emoji(n));
+                }
+                Some(&n) if is_emoji_modifier(n) => {
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+
+        if let Some(name) = describe(c) {
+            if !output.is_empty() && !output.ends_with(char::is_whitespace) {
+                output.push(' ');
+            }
+            output.push_str(name);
+            if chars.peek().is_some_and(|n| !n.is_whitespace()) {
+                output.push(' ');
+            }
+        }
+    }
+
+    collapse_spaces(&output)
+}
+
+/// Collapse runs of spaces left behind by removed symbols.
+fn collapse_spaces(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            line.split(' ')
+                .filter(|w| !w.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,1291 @@
+//! Text preprocessing applied before synthesis.
+//!
+//! Input text passes through user-configured transformations (template
+//! variables, front matter and voice annotations, screenplays, markup stripping, regex
+//! substitution rules, the acronym glossary, locale number reading, profanity masking) and is then split
+//! into chunks and sentences before it is sent to the backend. Unicode is normalized
+//! first, and invisible characters are removed (see [`normalize_text`]).
+
+mod chapters;
+mod chunk;
+mod document;
+mod fountain;
+mod glossary;
+mod lint;
+mod markup;
+mod normalize;
+mod numbers;
+mod ordinal;
+mod phoneme;
+mod preprocess;
+mod profanity;
+mod replace;
+mod segment;
+mod template;
+
+pub use chapters::{ChapterText, DEFAULT_CHAPTER_PATTERN, split_chapters};
+pub use chunk::{
+    Chunk, Pacing, chunk_text, pace, parse_duration, parse_speed, split_sentences, split_to_length,
+};
+pub use document::Document;
+pub use fountain::{Screenplay, ScreenplayOptions, ScriptElement, ScriptLine};
+pub use glossary::{Glossary, GlossaryConflict};
+pub use lint::{LintFinding, LintKind, LintReport, Linter};
+pub use markup::{EmojiMode, MarkupOptions, strip_markup};
+pub use normalize::normalize_text;
+pub use numbers::{Locale, verbalize_numbers};
+pub use ordinal::verbalize_ordinals;
+pub use phoneme::{render_phonemes, respell};
+pub use preprocess::Preprocessor;
+pub use profanity::{DEFAULT_SUBSTITUTE, Profanity, ProfanityMask};
+pub use replace::{ReplaceRule, ReplaceRules};
+pub use segment::sentences;
+pub use template::Variables;
+
+use thiserror::Error;
+
+/// Errors that can occur during text preprocessing.
+#[derive(Error, Debug)]
+pub enum TextError {
+    #[error("Invalid replacement rule: {0}")]
+    InvalidRule(String),
+
+    #[error("Invalid inline tag: {0}")]
+    InvalidTag(String),
+
+    #[error("Invalid duration: {0}")]
+    InvalidDuration(String),
+
+    #[error("Invalid speed: {0} (use a multiplier such as 0.85 or a percentage such as 85%)")]
+    InvalidSpeed(String),
+
+    #[error("Invalid variable: {0}")]
+    InvalidVariable(String),
+
+    #[error("Undefined template variable: {0}")]
+    UndefinedVariable(String),
+
+    #[error("Invalid template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Invalid front matter: {0}")]
+    InvalidFrontMatter(String),
+
+    #[error("Invalid lexicon: {0}")]
+    InvalidLexicon(String),
+
+    #[error("Invalid glossary: {0}")]
+    InvalidGlossary(String),
+
+    #[error("Invalid profanity list: {0}")]
+    InvalidProfanity(String),
+
+    #[error("Unsupported locale: {0}")]
+    UnsupportedLocale(String),
+
+    #[error("Invalid phonemes: {0}")]
+    InvalidPhonemes(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===========================================
+    // ReplaceRule tests
+    // ===========================================
+
+    #[test]
+    fn test_replace_rule_simple() {
+        let rule = ReplaceRule::parse("s/GmbH/gee em be ha/").unwrap();
+        assert_eq!(rule.apply("Acme GmbH"), "Acme gee em be ha");
+    }
+
+    #[test]
+    fn test_replace_rule_replaces_all_matches() {
+        let rule = ReplaceRule::parse("s/a/o/").unwrap();
+        assert_eq!(rule.apply("banana"), "bonono");
+    }
+
+    #[test]
+    fn test_replace_rule_case_insensitive() {
+        let rule = ReplaceRule::parse("s/gmbh/GmbH/gi").unwrap();
+        assert_eq!(rule.apply("GMBH gmbh"), "GmbH GmbH");
+    }
+
+    #[test]
+    fn test_replace_rule_alternate_delimiter() {
+        let rule = ReplaceRule::parse("s|https?://\\S+|link|").unwrap();
+        assert_eq!(rule.apply("see https://x.io now"), "see link now");
+    }
+
+    #[test]
+    fn test_replace_rule_escaped_delimiter() {
+        let rule = ReplaceRule::parse("s/and\\/or/and or/").unwrap();
+        assert_eq!(rule.apply("this and/or that"), "this and or that");
+    }
+
+    #[test]
+    fn test_replace_rule_backreferences() {
+        let rule = ReplaceRule::parse(r"s/(\d+)%/\1 percent/").unwrap();
+        assert_eq!(rule.apply("50% off"), "50 percent off");
+
+        let rule = ReplaceRule::parse("s/[A-Z]{3}/<&>/").unwrap();
+        assert_eq!(rule.apply("the API"), "the <API>");
+    }
+
+    #[test]
+    fn test_replace_rule_literal_dollar() {
+        let rule = ReplaceRule::parse("s/USD/$/").unwrap();
+        assert_eq!(rule.apply("5 USD"), "5 $");
+    }
+
+    #[test]
+    fn test_replace_rule_strip_markup() {
+        let rule = ReplaceRule::parse(r"s/\*\*//g").unwrap();
+        assert_eq!(rule.apply("**bold** text"), "bold text");
+    }
+
+    #[test]
+    fn test_replace_rule_invalid_prefix() {
+        let result = ReplaceRule::parse("y/a/b/");
+        assert!(matches!(result.unwrap_err(), TextError::InvalidRule(_)));
+    }
+
+    #[test]
+    fn test_replace_rule_missing_parts() {
+        assert!(ReplaceRule::parse("s/a").is_err());
+        assert!(ReplaceRule::parse("s").is_err());
+    }
+
+    #[test]
+    fn test_replace_rule_invalid_regex() {
+        assert!(ReplaceRule::parse("s/(unclosed/x/").is_err());
+    }
+
+    #[test]
+    fn test_replace_rule_unknown_flag() {
+        assert!(ReplaceRule::parse("s/a/b/x").is_err());
+    }
+
+    #[test]
+    fn test_replace_rule_word_matches_whole_words() {
+        let rule = ReplaceRule::word("GmbH", "gee em be ha").unwrap();
+        assert_eq!(
+            rule.apply("Acme GmbH, not GmbHs"),
+            "Acme gee em be ha, not GmbHs"
+        );
+
+        let rule = ReplaceRule::word("C++", "C plus plus $1").unwrap();
+        assert_eq!(rule.apply("I like C++."), "I like C plus plus $1.");
+    }
+
+    #[test]
+    fn test_replace_rules_apply_in_order() {
+        let rules = ReplaceRules::parse(&["s/cat/dog/", "s/dog/wolf/"]).unwrap();
+        assert_eq!(rules.apply("cat"), "wolf");
+    }
+
+    #[test]
+    fn test_replace_rules_empty() {
+        let rules = ReplaceRules::parse::<&str>(&[]).unwrap();
+        assert!(rules.is_empty());
+        assert_eq!(rules.apply("unchanged"), "unchanged");
+    }
+
+    #[test]
+    fn test_replace_rules_load_lexicon() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("names.toml");
+        std::fs::write(&path, "Tolkien = \"tol keen\"\n").unwrap();
+
+        let mut rules = ReplaceRules::parse(&["s/Mr\\./Mister/"]).unwrap();
+        rules.load_lexicon(&path).unwrap();
+        assert_eq!(
+            rules.apply("Mr. Tolkien, not Tolkiens."),
+            "Mister tol keen, not Tolkiens."
+        );
+
+        std::fs::write(&path, "Tolkien = 3\n").unwrap();
+        assert!(matches!(
+            rules.load_lexicon(&path),
+            Err(TextError::InvalidLexicon(_))
+        ));
+    }
+
+    // ===========================================
+    // Markup stripping tests
+    // ===========================================
+
+    fn markdown_only() -> MarkupOptions {
+        MarkupOptions {
+            strip_markdown: true,
+            emoji: EmojiMode::Keep,
+        }
+    }
+
+    #[test]
+    fn test_strip_markup_disabled_is_identity() {
+        let text = "**bold** and `code` \u{1F525}";
+        assert_eq!(strip_markup(text, &MarkupOptions::default()), text);
+    }
+
+    #[test]
+    fn test_strip_markdown_emphasis() {
+        let text = "This is **bold**, _italic_, *also* and ~~gone~~.";
+        assert_eq!(
+            strip_markup(text, &markdown_only()),
+            "This is bold, italic, also and gone."
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown_keeps_snake_case() {
+        let text = "Call my_function_name now";
+        assert_eq!(strip_markup(text, &markdown_only()), text);
+    }
+
+    #[test]
+    fn test_strip_markdown_links_and_images() {
+        let text = "See [the docs](https://example.com/docs) and ![a cat](cat.png).";
+        assert_eq!(
+            strip_markup(text, &markdown_only()),
+            "See the docs and a cat."
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown_bare_urls() {
+        let text = "Visit https://example.com/page?x=1 today";
+        assert_eq!(strip_markup(text, &markdown_only()), "Visit  today");
+    }
+
+    #[test]
+    fn test_strip_markdown_code_fences() {
+        let text = "Before\n```rust\nfn main() {}\n```\nAfter `inline` text";
+        assert_eq!(
+            strip_markup(text, &markdown_only()),
+            "Before\nAfter inline text"
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown_block_syntax() {
+        let text = "# Title\n> quoted\n- item one\n2. item two\n---\nend";
+        assert_eq!(
+            strip_markup(text, &markdown_only()),
+            "Title\nquoted\nitem one\nitem two\nend"
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown_html_tags() {
+        let text = "Hello <b>world</b><br/>";
+        assert_eq!(strip_markup(text, &markdown_only()), "Hello world");
+    }
+
+    #[test]
+    fn test_emoji_strip() {
+        let options = MarkupOptions {
+            strip_markdown: false,
+            emoji: EmojiMode::Strip,
+        };
+        assert_eq!(
+            strip_markup("Great job \u{1F44D}\u{1F3FD} team \u{1F389}", &options),
+            "Great job team"
+        );
+    }
+
+    #[test]
+    fn test_emoji_verbalize() {
+        let options = MarkupOptions {
+            strip_markdown: false,
+            emoji: EmojiMode::Verbalize,
+        };
+        assert_eq!(
+            strip_markup("Ship it\u{1F680}now \u{2764}\u{FE0F}", &options),
+            "Ship it rocket now red heart"
+        );
+    }
+
+    #[test]
+    fn test_emoji_verbalize_unknown_is_dropped() {
+        let options = MarkupOptions {
+            strip_markdown: false,
+            emoji: EmojiMode::Verbalize,
+        };
+        assert_eq!(strip_markup("Hi \u{1F9A9} there", &options), "Hi there");
+    }
+
+    #[test]
+    fn test_emoji_zwj_sequence_is_one_cluster() {
+        let options = MarkupOptions {
+            strip_markdown: false,
+            emoji: EmojiMode::Verbalize,
+        };
+        // "man technologist" = man + ZWJ + laptop; neither is in the table
+        assert_eq!(
+            strip_markup("A \u{1F468}\u{200D}\u{1F4BB} coder", &options),
+            "A coder"
+        );
+    }
+
+    // ===========================================
+    // Preprocessor tests
+    // ===========================================
+
+    #[test]
+    fn test_preprocessor_default_is_identity() {
+        let preprocessor = Preprocessor::new();
+        assert_eq!(preprocessor.process("**as is**"), "**as is**");
+    }
+
+    #[test]
+    fn test_preprocessor_normalizes_before_rules() {
+        let preprocessor =
+            Preprocessor::new().with_rules(ReplaceRules::parse(&["s/café/coffee shop/"]).unwrap());
+        assert_eq!(
+            preprocessor.process("the cafe\u{301}\u{200B} opens"),
+            "the coffee shop opens"
+        );
+    }
+
+    #[test]
+    fn test_preprocessor_strips_markup_before_rules() {
+        let preprocessor = Preprocessor::new()
+            .with_markup(markdown_only())
+            .with_rules(ReplaceRules::parse(&["s/^bold$/strong/"]).unwrap());
+        assert_eq!(preprocessor.process("**bold**"), "strong");
+    }
+
+    // ===========================================
+    // Template variable tests
+    // ===========================================
+
+    #[test]
+    fn test_variables_render() {
+        let variables = Variables::parse(&["name=Alice", "product=Widget=Pro"]).unwrap();
+        assert_eq!(
+            variables
+                .render("Hi {{name}}, your {{ product }} shipped.")
+                .unwrap(),
+            "Hi Alice, your Widget=Pro shipped."
+        );
+        assert_eq!(
+            variables.render(r#"Literal {{ "{{name}}" }}"#).unwrap(),
+            "Literal {{name}}"
+        );
+    }
+
+    #[test]
+    fn test_variables_render_errors() {
+        let variables = Variables::parse(&["name=Alice"]).unwrap();
+        assert!(matches!(
+            variables.render("Hi {{nmae}}"),
+            Err(TextError::UndefinedVariable(name)) if name == "nmae"
+        ));
+        assert!(matches!(
+            variables.render("Hi {{name"),
+            Err(TextError::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            variables.render("Hi {{ name | upper }}"),
+            Err(TextError::InvalidTemplate(_))
+        ));
+    }
+
+    #[test]
+    fn test_variables_parse_invalid() {
+        assert!(Variables::parse(&["name"]).is_err());
+        assert!(Variables::parse(&["1st=x"]).is_err());
+        assert!(Variables::parse(&["first name=x"]).is_err());
+    }
+
+    #[test]
+    fn test_variables_from_csv_rows_override_defaults() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("rows.csv");
+        std::fs::write(&path, "name,product\nAlice,Widget\n\"Bob, Jr.\",Gadget\n").unwrap();
+
+        let rows = Variables::from_csv(&path).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].get("name"), Some("Bob, Jr."));
+
+        let defaults = Variables::parse(&["product=Thing", "company=Acme"]).unwrap();
+        let merged = defaults.merged(&rows[0]);
+        assert_eq!(
+            merged
+                .render("{{name}}: {{product}} by {{company}}")
+                .unwrap(),
+            "Alice: Widget by Acme"
+        );
+    }
+
+    // ===========================================
+    // Sentence segmentation tests
+    // ===========================================
+
+    #[test]
+    fn test_sentences_basic() {
+        assert_eq!(
+            sentences("One. Two! Three?\nFour"),
+            vec!["One.", "Two!", "Three?", "Four"]
+        );
+        assert!(sentences("  \n ").is_empty());
+    }
+
+    #[test]
+    fn test_sentences_skip_abbreviations_and_initials() {
+        assert_eq!(
+            sentences(
+                "Dr. Smith met Mrs. Jones, e.g. at noon. J. R. R. Tolkien wrote it. So did I. Done."
+            ),
+            vec![
+                "Dr. Smith met Mrs. Jones, e.g. at noon.",
+                "J. R. R. Tolkien wrote it.",
+                "So did I.",
+                "Done."
+            ]
+        );
+        assert_eq!(
+            sentences("See No. 5 for details. The answer is no. Then we left."),
+            vec![
+                "See No. 5 for details.",
+                "The answer is no.",
+                "Then we left."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sentences_keep_decimals_and_ellipses() {
+        assert_eq!(
+            sentences("Pi is 3.14 today. Wait... what was that... Nothing."),
+            vec!["Pi is 3.14 today.", "Wait... what was that...", "Nothing."]
+        );
+        assert_eq!(sentences("Well… Fine."), vec!["Well…", "Fine."]);
+    }
+
+    #[test]
+    fn test_sentences_keep_german_dates() {
+        assert_eq!(
+            sentences("Am 3. Mai kam er. Er blieb bis zum 10. Juni."),
+            vec!["Am 3. Mai kam er.", "Er blieb bis zum 10. Juni."]
+        );
+        assert_eq!(
+            sentences("Er kam in 1990. April war kalt."),
+            vec!["Er kam in 1990.", "April war kalt."]
+        );
+    }
+
+    #[test]
+    fn test_sentences_quoted_dialogue() {
+        assert_eq!(
+            sentences(
+                r#""Help!" she cried. "Is anyone there?" No answer came. He said, "Go." (Then he left.) Fin."#
+            ),
+            vec![
+                r#""Help!" she cried."#,
+                r#""Is anyone there?""#,
+                "No answer came.",
+                r#"He said, "Go.""#,
+                "(Then he left.)",
+                "Fin."
+            ]
+        );
+        assert_eq!(sentences("“Really?” “Yes.”"), vec!["“Really?”", "“Yes.”"]);
+    }
+
+    #[test]
+    fn test_sentences_cjk_full_stops() {
+        assert_eq!(sentences("你好。再见！"), vec!["你好。", "再见！"]);
+    }
+
+    #[test]
+    fn test_sentences_arabic_and_urdu_stops() {
+        assert_eq!(
+            sentences("كيف حالك؟ أنا بخير. شكرا"),
+            vec!["كيف حالك؟", "أنا بخير.", "شكرا"]
+        );
+        assert_eq!(sentences("آپ کیسے ہیں۔ ٹھیک"), vec!["آپ کیسے ہیں۔", "ٹھیک"]);
+    }
+
+    // ===========================================
+    // Profanity masking tests
+    // ===========================================
+
+    fn profanity(mask: ProfanityMask) -> Profanity {
+        let mut profanity = Profanity::new(mask);
+        profanity.insert("damn", "darn").unwrap();
+        profanity.insert("frak*", "").unwrap();
+        profanity
+    }
+
+    #[test]
+    fn test_profanity_bleep() {
+        assert_eq!(
+            profanity(ProfanityMask::Bleep).apply("Damn it, you frakking toaster! Damnation."),
+            "[bleep:280ms] it, you [bleep:560ms] toaster! Damnation."
+        );
+        let chunks = chunk_text("Oh [bleep:300ms] no", None, 1.0).unwrap();
+        assert_eq!(
+            chunks[1],
+            Chunk::Bleep(std::time::Duration::from_millis(300))
+        );
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn test_profanity_skip_and_substitute() {
+        let text = "Well damn, that frakking ship is DAMN fast.";
+        assert_eq!(
+            profanity(ProfanityMask::Skip).apply(text),
+            "Well, that ship is fast."
+        );
+        assert_eq!(
+            profanity(ProfanityMask::Substitute).apply(text),
+            "Well darn, that beep ship is darn fast."
+        );
+        assert_eq!(Profanity::new(ProfanityMask::Skip).apply(text), text);
+    }
+
+    #[test]
+    fn test_profanity_rejects_non_words() {
+        let mut profanity = Profanity::new(ProfanityMask::Bleep);
+        assert!(matches!(
+            profanity.insert("*", "").unwrap_err(),
+            TextError::InvalidProfanity(_)
+        ));
+        assert!(profanity.insert("two words", "").is_err());
+        assert!(profanity.is_empty());
+    }
+
+    #[test]
+    fn test_profanity_load() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"heck = \"gosh\"\n").unwrap();
+        let mut profanity = Profanity::new(ProfanityMask::Substitute);
+        profanity.load(file.path()).unwrap();
+        assert_eq!(profanity.apply("What the heck"), "What the gosh");
+
+        std::io::Write::write_all(&mut file, b"not toml").unwrap();
+        assert!(matches!(
+            profanity.load(file.path()),
+            Err(TextError::InvalidProfanity(_))
+        ));
+    }
+
+    #[test]
+    fn test_preprocessor_masks_profanity_last() {
+        let mut glossary = Glossary::new();
+        glossary.insert("WTF", "what the frak").unwrap();
+        let preprocessor = Preprocessor::new()
+            .with_glossary(glossary)
+            .with_profanity(Some(profanity(ProfanityMask::Substitute)));
+        assert_eq!(preprocessor.process("WTF?"), "what the beep?");
+    }
+
+    // ===========================================
+    // Unicode normalization tests
+    // ===========================================
+
+    #[test]
+    fn test_normalize_text_composes_and_strips_invisibles() {
+        assert!(matches!(
+            normalize_text("Plain text.\n"),
+            std::borrow::Cow::Borrowed(_)
+        ));
+        assert_eq!(normalize_text("Cafe\u{301}"), "Café");
+        assert_eq!(
+            normalize_text("\u{FEFF}zero\u{200B}width soft\u{AD}hyphen\u{7}"),
+            "zerowidth softhyphen"
+        );
+        assert_eq!(normalize_text("a\tb\r\nc"), "a\tb\r\nc");
+    }
+
+    #[test]
+    fn test_normalize_text_removes_bidi_controls_and_keeps_joiners() {
+        assert_eq!(
+            normalize_text("He said \u{2067}שלום\u{2069} and \u{202B}مرحبا\u{202C}\u{200F}."),
+            "He said שלום and مرحبا."
+        );
+        // The zero-width non-joiner is part of Persian spelling
+        assert_eq!(normalize_text("می\u{200C}خواهم"), "می\u{200C}خواهم");
+    }
+
+    #[test]
+    fn test_chunk_text_normalizes() {
+        let chunks =
+            chunk_text("\u{202E}Hi\u{202C} [pause:1s] e\u{301}te\u{301}", None, 1.0).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                Chunk::Speech {
+                    text: "Hi".to_string(),
+                    voice: None,
+                    speed: 1.0
+                },
+                Chunk::Pause(std::time::Duration::from_secs(1)),
+                Chunk::Speech {
+                    text: "été".to_string(),
+                    voice: None,
+                    speed: 1.0
+                },
+            ]
+        );
+    }
+
+    // ===========================================
+    // Document tests
+    // ===========================================
+
+    #[test]
+    fn test_document_without_front_matter_is_unchanged() {
+        let document = Document::parse("Hello --- there.\n---\nMore.").unwrap();
+        assert_eq!(document.voice, None);
+        assert_eq!(document.text, "Hello --- there.\n---\nMore.");
+    }
+
+    #[test]
+    fn test_document_front_matter_voices() {
+        let source = "---\ntitle: Moby-Dick\nvoice: narrator\nvoices:\n  narrator: amy\n  ahab: gravel\n---\nCall me Ishmael.\n[voice:ahab] Hast seen the White Whale? [voice:default] [voice:bob] Hi.";
+        let document = Document::parse(source).unwrap();
+        assert_eq!(document.voice.as_deref(), Some("amy"));
+        assert_eq!(
+            document.text,
+            "Call me Ishmael.\n[voice:gravel] Hast seen the White Whale? [voice:default] [voice:bob] Hi."
+        );
+    }
+
+    #[test]
+    fn test_document_annotations_become_tags() {
+        let source = "---\nvoices:\n  ahab: gravel\n...\n# Chapter 1 <!-- voice: ahab -->\nAye. <!-- pause: 1s --><!-- a note\nfor editors -->Done.<!--speed:1.2--> Fast.";
+        let document = Document::parse(source).unwrap();
+        assert_eq!(document.voice, None);
+        assert_eq!(
+            document.text,
+            "# Chapter 1 [voice:gravel]\nAye. [pause:1s]Done.[speed:1.2] Fast."
+        );
+
+        let chunks = chunk_text(&document.text, Some("narrator"), 1.0).unwrap();
+        assert!(matches!(
+            &chunks[0],
+            Chunk::Speech { voice: Some(v), .. } if v == "narrator"
+        ));
+        assert!(matches!(
+            &chunks[1],
+            Chunk::Speech { voice: Some(v), .. } if v == "gravel"
+        ));
+    }
+
+    #[test]
+    fn test_document_invalid_front_matter() {
+        assert!(matches!(
+            Document::parse("---\nvoices: [a, b]\n---\nHi."),
+            Err(TextError::InvalidFrontMatter(_))
+        ));
+        assert_eq!(Document::parse("---\n---\nHi.").unwrap().text, "Hi.");
+    }
+
+    // ===========================================
+    // Linter tests
+    // ===========================================
+
+    fn lint(text: &str) -> Vec<(LintKind, String)> {
+        let mut linter = Linter::new();
+        linter.add("a.txt", text);
+        linter
+            .report()
+            .findings
+            .into_iter()
+            .map(|f| (f.kind, f.word))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_ordinary_text_is_clean() {
+        assert!(
+            lint("The strengths of 12 old sailors, Dr. Jones said, were twofold.\nMr. Smith's square rhythm didn't help.")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_lint_unusual_spellings() {
+        let words = lint("Nguyen met Przybylski and Zoë. Hmm, an iPhone. McDonald sang.");
+        let unusual: Vec<&str> = words
+            .iter()
+            .filter(|(kind, _)| *kind == LintKind::Unusual)
+            .map(|(_, word)| word.as_str())
+            .collect();
+        assert_eq!(
+            unusual,
+            vec!["Hmm", "Nguyen", "Przybylski", "Zoë", "iPhone"]
+        );
+    }
+
+    #[test]
+    fn test_lint_numbers_and_acronyms() {
+        assert_eq!(
+            lint("In 1984 NASA and the FBI met at 3:30 on the 21st. Chapter IV has 7 pages."),
+            vec![
+                (LintKind::Acronym, "FBI".to_string()),
+                (LintKind::Acronym, "NASA".to_string()),
+                (LintKind::Number, "1984".to_string()),
+                (LintKind::Number, "21st".to_string()),
+                (LintKind::Number, "3:30".to_string()),
+            ]
+        );
+        // A heading in capitals is not a run of acronyms
+        assert!(lint("THE WHITE WHALE RETURNS").is_empty());
+        // Inline tags are not text
+        assert!(lint("[voice:alice] Hello. [pause:500ms] Bye.").is_empty());
+    }
+
+    #[test]
+    fn test_lint_name_variants() {
+        let mut linter = Linter::new();
+        linter.add("01.txt", "Tolkien wrote. Tolkien slept.");
+        linter.add("02.txt", "Tolkein woke. The hobbit ate. Hobbit holes.");
+        let report = linter.report();
+
+        assert_eq!(report.findings.len(), 1);
+        let finding = &report.findings[0];
+        assert_eq!(finding.kind, LintKind::Variant);
+        assert_eq!(finding.word, "Tolkien");
+        assert_eq!(finding.count, 3);
+        assert_eq!(finding.variants.get("Tolkein"), Some(&1));
+        assert_eq!(finding.sources.len(), 2);
+        assert_eq!(report.lexicon(), "Tolkein = \"Tolkien\"\n");
+    }
+
+    #[test]
+    fn test_lint_known_words_and_report() {
+        let mut linter = Linter::new().with_known(["Nguyen", "N A S A"]);
+        linter.add("a.txt", "Nguyen and NASA met Przybylski. NASA again.");
+        let report = linter.report();
+
+        assert_eq!(report.findings.len(), 2);
+        assert_eq!(report.findings[0].word, "Przybylski");
+        assert_eq!(report.findings[1].word, "NASA");
+        assert_eq!(report.findings[1].count, 2);
+        assert_eq!(report.lexicon(), "NASA = \"N A S A\"\n");
+
+        let table = report.to_table();
+        assert!(table.starts_with("KIND"));
+        assert!(table.contains("acronym  NASA"));
+        assert!(!table.lines().any(|line| line.ends_with(' ')));
+    }
+
+    // ===========================================
+    // Glossary tests
+    // ===========================================
+
+    fn glossary(entries: &[(&str, &str)]) -> Glossary {
+        let mut glossary = Glossary::new();
+        for (term, spoken) in entries {
+            glossary.insert(term, spoken).unwrap();
+        }
+        glossary
+    }
+
+    #[test]
+    fn test_glossary_expands_whole_terms() {
+        let glossary = glossary(&[("K8s", "kubernetes"), ("SQL", "sequel"), ("API", "A P I")]);
+        assert_eq!(
+            glossary.apply("Run K8s with SQL and NoSQL, not sql."),
+            "Run kubernetes with sequel and NoSQL, not sql."
+        );
+        assert_eq!(
+            glossary.apply("Two APIs, the API's docs, SQL's planner."),
+            "Two A P I's, the A P I's docs, sequel's planner."
+        );
+        assert_eq!(glossary.apply("No terms here."), "No terms here.");
+        assert_eq!(Glossary::new().apply("SQL"), "SQL");
+    }
+
+    #[test]
+    fn test_glossary_single_pass_and_longest_term() {
+        let glossary = glossary(&[("C", "see"), ("C++", "see plus plus"), ("see", "SEE")]);
+        assert_eq!(
+            glossary.apply("C++ and C, see?"),
+            "see plus plus and see, SEE?"
+        );
+    }
+
+    #[test]
+    fn test_glossary_merge_reports_redefinitions() {
+        let mut base = glossary(&[("SQL", "sequel"), ("K8s", "kubernetes")]);
+        let conflicts = base.merge(&glossary(&[("SQL", "S Q L"), ("K8s", "kubernetes")]));
+
+        assert_eq!(
+            conflicts,
+            vec![GlossaryConflict::Redefined {
+                term: "SQL".to_string(),
+                previous: "sequel".to_string(),
+                spoken: "S Q L".to_string(),
+            }]
+        );
+        assert_eq!(base.apply("SQL"), "S Q L");
+        assert!(conflicts[0].to_string().contains("'SQL'"));
+    }
+
+    #[test]
+    fn test_glossary_load_and_invalid_entries() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            "K8s = \"kubernetes\"\n\"C#\" = \"see sharp\"\n",
+        )
+        .unwrap();
+        let glossary = Glossary::load(file.path()).unwrap();
+        assert_eq!(glossary.get("C#"), Some("see sharp"));
+
+        std::fs::write(file.path(), "K8s = 8\n").unwrap();
+        assert!(matches!(
+            Glossary::load(file.path()),
+            Err(TextError::InvalidGlossary(_))
+        ));
+        assert!(Glossary::new().insert(" ", "nothing").is_err());
+    }
+
+    #[test]
+    fn test_preprocessor_applies_glossary_after_rules() {
+        let preprocessor = Preprocessor::new()
+            .with_rules(
+                ReplaceRules::parse(&["s/Postgres/PostgreSQL/", "s/GPU/graphics card/"]).unwrap(),
+            )
+            .with_glossary(glossary(&[
+                ("PostgreSQL", "postgres Q L"),
+                ("GPU", "G P U"),
+            ]));
+
+        assert_eq!(
+            preprocessor.process("Postgres on a GPU."),
+            "postgres Q L on a graphics card."
+        );
+        assert_eq!(
+            preprocessor.glossary_conflicts(),
+            vec![GlossaryConflict::Shadowed {
+                term: "GPU".to_string(),
+                rewritten: "graphics card".to_string(),
+            }]
+        );
+    }
+
+    // ===========================================
+    // Number verbalization tests
+    // ===========================================
+
+    #[test]
+    fn test_locale_parse() {
+        assert_eq!("de-DE".parse::<Locale>().unwrap(), Locale::DeDe);
+        assert_eq!("de_at".parse::<Locale>().unwrap(), Locale::DeDe);
+        assert_eq!("en".parse::<Locale>().unwrap(), Locale::EnUs);
+        assert_eq!("en-GB".parse::<Locale>().unwrap(), Locale::EnGb);
+        assert!(matches!(
+            "fr-FR".parse::<Locale>(),
+            Err(TextError::UnsupportedLocale(_))
+        ));
+        assert_eq!(Locale::DeDe.to_string(), "de-DE");
+    }
+
+    #[test]
+    fn test_verbalize_german_numbers() {
+        let de = |text| verbalize_numbers(text, Locale::DeDe);
+        assert_eq!(de("3,5 km"), "drei Komma fünf Kilometer");
+        assert_eq!(de("1 km, 1 kWh"), "ein Kilometer, eine Kilowattstunde");
+        assert_eq!(de("1 min, 2 min"), "eine Minute, zwei Minuten");
+        assert_eq!(de("Es sind 21 Grad."), "Es sind einundzwanzig Grad.");
+        assert_eq!(de("101"), "einhunderteins");
+        assert_eq!(
+            de("1.234.567"),
+            "eine Million zweihundertvierunddreißigtausendfünfhundertsiebenundsechzig"
+        );
+        assert_eq!(de("2.000.000"), "zwei Millionen");
+        assert_eq!(de("Im Jahr 1984"), "Im Jahr neunzehnhundertvierundachtzig");
+        assert_eq!(de("2024"), "zweitausendvierundzwanzig");
+        assert_eq!(de("-7 °C"), "minus sieben Grad Celsius");
+        assert_eq!(de("50 %"), "fünfzig Prozent");
+    }
+
+    #[test]
+    fn test_verbalize_german_currency() {
+        let de = |text| verbalize_numbers(text, Locale::DeDe);
+        assert_eq!(de("3,50 €"), "drei Euro fünfzig");
+        assert_eq!(de("1 €"), "ein Euro");
+        assert_eq!(de("0,01 EUR"), "ein Cent");
+        assert_eq!(de("€ 1.000"), "eintausend Euro");
+        assert_eq!(de("$20"), "zwanzig Dollar");
+    }
+
+    #[test]
+    fn test_verbalize_english_numbers() {
+        let us = |text| verbalize_numbers(text, Locale::EnUs);
+        let gb = |text| verbalize_numbers(text, Locale::EnGb);
+        assert_eq!(us("3.5 km"), "three point five kilometers");
+        assert_eq!(gb("3.5 km"), "three point five kilometres");
+        assert_eq!(us("1 l"), "one liter");
+        assert_eq!(us("105"), "one hundred five");
+        assert_eq!(gb("1,005"), "one thousand and five");
+        assert_eq!(
+            us("1,234,567"),
+            "one million two hundred thirty-four thousand five hundred sixty-seven"
+        );
+        assert_eq!(
+            us("In 1984 and 1905"),
+            "In nineteen eighty-four and nineteen oh five"
+        );
+        assert_eq!(
+            us("It costs $3.50."),
+            "It costs three dollars and fifty cents."
+        );
+        assert_eq!(us("$1"), "one dollar");
+        assert_eq!(us("$0.99"), "ninety-nine cents");
+        assert_eq!(us("£2.01"), "two pounds and one penny");
+        assert_eq!(us("3.14159"), "three point one four one five nine");
+        assert_eq!(us("Agent 007"), "Agent zero zero seven");
+    }
+
+    #[test]
+    fn test_verbalize_leaves_ambiguous_numbers() {
+        let us = |text| verbalize_numbers(text, Locale::EnUs);
+        let de = |text| verbalize_numbers(text, Locale::DeDe);
+        assert_eq!(
+            us("At 3:30 on 12/05, v1.2.3 of K8s, the 21st."),
+            "At 3:30 on 12/05, v1.2.3 of K8s, the 21st."
+        );
+        assert_eq!(de("Am 24.12.2024"), "Am 24.12.2024");
+        assert_eq!(us("1,5"), "1,5");
+        assert_eq!(us("5 great"), "five great");
+    }
+
+    #[test]
+    fn test_verbalize_years_only_in_context() {
+        let us = |text| verbalize_numbers(text, Locale::EnUs);
+        let de = |text| verbalize_numbers(text, Locale::DeDe);
+        assert_eq!(
+            us("The town has 1,234 residents."),
+            "The town has one thousand two hundred thirty-four residents."
+        );
+        assert_eq!(
+            us("I have 1234 apples."),
+            "I have one thousand two hundred thirty-four apples."
+        );
+        assert_eq!(
+            de("Das Dorf hat 1.234 Einwohner."),
+            "Das Dorf hat eintausendzweihundertvierunddreißig Einwohner."
+        );
+        assert_eq!(us("since 1850"), "since eighteen fifty");
+        assert_eq!(us("May 3, 1984"), "May three, nineteen eighty-four");
+        assert_eq!(us("in 1,984"), "in one thousand nine hundred eighty-four");
+        assert_eq!(
+            de("seit Mai 1984"),
+            "seit Mai neunzehnhundertvierundachtzig"
+        );
+    }
+
+    #[test]
+    fn test_preprocessor_locale() {
+        let preprocessor = Preprocessor::new()
+            .with_glossary(glossary(&[("K8s", "kubernetes")]))
+            .with_locale(Some(Locale::DeDe));
+        assert_eq!(
+            preprocessor.process("K8s auf 2 Knoten"),
+            "kubernetes auf zwei Knoten"
+        );
+        assert_eq!(preprocessor.process("Der 1. Platz."), "Der erste Platz.");
+        assert_eq!(Preprocessor::new().process("2 Knoten"), "2 Knoten");
+    }
+
+    // ===========================================
+    // Ordinal and roman numeral tests
+    // ===========================================
+
+    #[test]
+    fn test_verbalize_english_ordinals() {
+        let us = |text| verbalize_ordinals(text, Locale::EnUs);
+        assert_eq!(
+            us("The 1st, 2nd, 3rd, 4th, 11th, 12th, 21st, 40th, and 101st."),
+            "The first, second, third, fourth, eleventh, twelfth, twenty-first, fortieth, and one hundred first."
+        );
+        assert_eq!(us("K1st and 3rdly"), "K1st and 3rdly");
+    }
+
+    #[test]
+    fn test_verbalize_roman_numerals_in_context() {
+        let us = |text| verbalize_ordinals(text, Locale::EnUs);
+        assert_eq!(us("Chapter IV begins."), "Chapter four begins.");
+        assert_eq!(
+            us("After World War II, vol. XII"),
+            "After World War two, vol. twelve"
+        );
+        assert_eq!(us("Henry VIII's wives"), "Henry the Eighth's wives");
+        assert_eq!(
+            us("Louis XIV and Pius XII."),
+            "Louis the Fourteenth and Pius the Twelfth."
+        );
+        assert_eq!(us("IX\n\nIt was night."), "nine\n\nIt was night.");
+        assert_eq!(us("Chapter I"), "Chapter one");
+    }
+
+    #[test]
+    fn test_verbalize_roman_numerals_ambiguous() {
+        let us = |text| verbalize_ordinals(text, Locale::EnUs);
+        // The pronoun I
+        assert_eq!(
+            us("Then I left, and Mary and I laughed. Am I?"),
+            "Then I left, and Mary and I laughed. Am I?"
+        );
+        assert_eq!(
+            us("Elizabeth I of England"),
+            "Elizabeth the First of England"
+        );
+        assert_eq!(us("Then I'm off."), "Then I'm off.");
+        assert_eq!(us("It was Edward I."), "It was Edward the First.");
+        assert_eq!(us("Ask Tom. Did I?"), "Ask Tom. Did I?");
+        // Acronyms, single letters, and unusual spellings
+        assert_eq!(
+            us("Malcolm X played a CD MIX with DJ IIII."),
+            "Malcolm X played a CD MIX with DJ IIII."
+        );
+        assert_eq!(us("Vitamin C, Type VX"), "Vitamin C, Type VX");
+    }
+
+    #[test]
+    fn test_verbalize_german_roman_numerals() {
+        let de = |text| verbalize_ordinals(text, Locale::DeDe);
+        assert_eq!(
+            de("Heinrich VIII. heiratete oft."),
+            "Heinrich der Achte heiratete oft."
+        );
+        assert_eq!(de("Es war Ludwig XIV."), "Es war Ludwig der Vierzehnte.");
+        assert_eq!(de("Kapitel III"), "Kapitel drei");
+        assert_eq!(de("Papst Benedikt XVI"), "Papst Benedikt der Sechzehnte");
+    }
+
+    #[test]
+    fn test_verbalize_german_ordinals() {
+        let de = |text| verbalize_ordinals(text, Locale::DeDe);
+        assert_eq!(de("Am 3. Mai"), "Am dritten Mai");
+        assert_eq!(
+            de("Der 1. Januar ist der 1. von 365 Tagen."),
+            "Der erste Januar ist der erste von 365 Tagen."
+        );
+        assert_eq!(de("Bis zum 21. März."), "Bis zum einundzwanzigsten März.");
+        assert_eq!(de("Es waren 3. Dann kam er."), "Es waren 3. Dann kam er.");
+        assert_eq!(de("Am 3.5. Mai"), "Am 3.5. Mai");
+        assert_eq!(de("Der 1. Platz."), "Der erste Platz.");
+        assert_eq!(de("Zur 2. Runde"), "Zur zweiten Runde");
+    }
+
+    #[test]
+    fn test_preprocessor_reads_ordinals() {
+        let preprocessor = Preprocessor::new().with_locale(Some(Locale::EnUs));
+        assert_eq!(
+            preprocessor.process("Chapter XXI: the 21st of 2 kings, Henry II."),
+            "Chapter twenty-one: the twenty-first of two kings, Henry the Second."
+        );
+    }
+
+    // ===========================================
+    // Phoneme segment tests
+    // ===========================================
+
+    #[test]
+    fn test_respell_ipa() {
+        assert_eq!(respell("ˈkwɒləti").unwrap(), "KWOL-uh-tee");
+        assert_eq!(respell("/ˈsiːən/").unwrap(), "SEE-uhn");
+        assert_eq!(respell("ˌɛkˈspaɪə.ɹi").unwrap(), "ek-SPY-uh-ree");
+        assert_eq!(respell("ˈnaɪt͡ʃə").unwrap(), "NY-chuh");
+        assert_eq!(respell("ʒɑ̃ ˈpɔl").unwrap(), "zhah PAWL");
+        // Input is normalized to NFC, which composes some marked letters
+        assert_eq!(respell("ʒ\u{e3}").unwrap(), respell("ʒa\u{303}").unwrap());
+        assert!(respell("ç").is_ok());
+    }
+
+    #[test]
+    fn test_respell_arpabet() {
+        assert_eq!(respell("K W AA1 L AH0 T IY0").unwrap(), "KWAH-luh-tee");
+        assert_eq!(respell("N IY1 CH AH0").unwrap(), "NEE-chuh");
+        assert_eq!(respell("B AH1 T ER0").unwrap(), "BUT-ur");
+    }
+
+    #[test]
+    fn test_respell_rejects_unknown_symbols() {
+        assert!(matches!(
+            respell("ˈkw§ti").unwrap_err(),
+            TextError::InvalidPhonemes(_)
+        ));
+        assert!(respell("").is_err());
+        // Not every symbol is ARPABET, so this is read as IPA
+        assert!(respell("K W AA1 Q").is_err());
+    }
+
+    #[test]
+    fn test_render_phonemes() {
+        let text = "Say [[ph: ˈkwɒləti]] and [[ph: N IY1 CH AH0]].";
+        assert_eq!(
+            render_phonemes(text, false),
+            "Say KWOL-uh-tee and NEE-chuh."
+        );
+        assert_eq!(
+            render_phonemes(text, true),
+            r#"Say <phoneme alphabet="ipa" ph="ˈkwɒləti">KWOL-uh-tee</phoneme> and <phoneme alphabet="arpabet" ph="N IY1 CH AH0">NEE-chuh</phoneme>."#
+        );
+        assert_eq!(render_phonemes("[[ph: §]]", false), "[[ph: §]]");
+    }
+
+    #[test]
+    fn test_chunk_text_checks_phonemes() {
+        let chunks = chunk_text("The [[ph: ˈkwɒləti]].", None, 1.0).unwrap();
+        assert_eq!(
+            chunks,
+            vec![Chunk::Speech {
+                text: "The [[ph: ˈkwɒləti]].".to_string(),
+                voice: None,
+                speed: 1.0,
+            }]
+        );
+        assert!(matches!(
+            chunk_text("The [[ph: §]].", None, 1.0).unwrap_err(),
+            TextError::InvalidPhonemes(_)
+        ));
+    }
+
+    #[test]
+    fn test_preprocessor_keeps_phoneme_segments() {
+        let preprocessor = Preprocessor::new()
+            .with_markup(MarkupOptions {
+                strip_markdown: true,
+                ..MarkupOptions::default()
+            })
+            .with_locale(Some(Locale::EnUs));
+        let text = "**Meet** [[ph: ˈnaɪt͡ʃə]] and [[ph: K W AA1 L AH0 T IY0]] at 5.";
+        assert_eq!(
+            preprocessor.process(text),
+            "Meet [[ph: ˈnaɪt͡ʃə]] and [[ph: K W AA1 L AH0 T IY0]] at five."
+        );
+        assert!(lint(text).is_empty());
+    }
+
+    // ===========================================
+    // Screenplay tests
+    // ===========================================
+
+    const SCRIPT: &str = "Title: Moby-Dick
+Credit: Written by
+Author: Herman Melville
+
+INT. PEQUOD - CAPTAIN'S CABIN - NIGHT #12#
+
+Ahab studies a chart. /* cut: He mutters. */
+
+AHAB (V.O.)
+(quietly)
+Hast seen the *White Whale*?
+(beat)
+Aye.
+
+STARBUCK ^
+(quickly)
+Nay, sir. [[check this line]]
+
+CUT TO:
+
+.THE OPEN SEA
+
+!THE END comes quickly.
+";
+
+    #[test]
+    fn test_screenplay_parse() {
+        let screenplay = Screenplay::parse(SCRIPT);
+        assert_eq!(
+            screenplay.elements,
+            vec![
+                ScriptElement::SceneHeading(
+                    "Interior. Pequod, Captain's cabin, Night.".to_string()
+                ),
+                ScriptElement::Action("Ahab studies a chart.".to_string()),
+                ScriptElement::Dialogue {
+                    character: "AHAB".to_string(),
+                    lines: vec![
+                        ScriptLine::Parenthetical("quietly".to_string()),
+                        ScriptLine::Spoken("Hast seen the White Whale?".to_string()),
+                        ScriptLine::Parenthetical("beat".to_string()),
+                        ScriptLine::Spoken("Aye.".to_string()),
+                    ],
+                },
+                ScriptElement::Dialogue {
+                    character: "STARBUCK".to_string(),
+                    lines: vec![
+                        ScriptLine::Parenthetical("quickly".to_string()),
+                        ScriptLine::Spoken("Nay, sir.".to_string()),
+                    ],
+                },
+                ScriptElement::SceneHeading("The open sea.".to_string()),
+                ScriptElement::Action("THE END comes quickly.".to_string()),
+            ]
+        );
+        assert_eq!(screenplay.characters(), vec!["AHAB", "STARBUCK"]);
+    }
+
+    #[test]
+    fn test_screenplay_to_text() {
+        let screenplay = Screenplay::parse(SCRIPT);
+        let options = ScreenplayOptions {
+            cast: std::collections::BTreeMap::from([("Ahab".to_string(), "gravel".to_string())]),
+            ..ScreenplayOptions::default()
+        };
+        assert_eq!(
+            screenplay.to_text(&options),
+            "[voice:default]Interior. Pequod, Captain's cabin, Night.\n\n\
+             [voice:default]Ahab studies a chart.\n\n\
+             [voice:gravel]Hast seen the White Whale? [pause:1s]Aye.\n\n\
+             [voice:STARBUCK][speed:1.15]Nay, sir.[speed:default]\n\n\
+             [voice:default]The open sea.\n\n\
+             [voice:default]THE END comes quickly."
+        );
+
+        let dialogue_only = ScreenplayOptions {
+            scene_headings: false,
+            action: false,
+            ..options
+        };
+        let text = screenplay.to_text(&dialogue_only);
+        assert!(text.starts_with("[voice:gravel]"));
+        assert!(!text.contains("[voice:default]"));
+        // Parses as inline tags, with the speed restored after the speech
+        let chunks = chunk_text(&text, Some("narrator"), 1.0).unwrap();
+        assert_eq!(chunks.len(), 4);
+    }
+
+    // ===========================================
+    // Chapter tests
+    // ===========================================
+
+    #[test]
+    fn test_split_chapters() {
+        let text = "MOBY-DICK\n\nCHAPTER 1. Loomings.\nCall me Ishmael.\n\n\
+                    Chapters were short then.\n\n# Chapter 2\n\nThe Carpet-Bag.\n";
+        let pattern = regex::Regex::new(DEFAULT_CHAPTER_PATTERN).unwrap();
+        let chapters = split_chapters(text, &pattern);
+
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title, "CHAPTER 1. Loomings.");
+        assert!(chapters[0].text.starts_with("MOBY-DICK\n"));
+        assert!(chapters[0].text.contains("Chapters were short then."));
+        assert_eq!(chapters[1].title, "Chapter 2");
+        assert_eq!(chapters[1].text, "# Chapter 2\n\nThe Carpet-Bag.\n");
+
+        let custom = regex::Regex::new("^Kapitel").unwrap();
+        assert_eq!(
+            split_chapters(text, &custom),
+            vec![ChapterText {
+                title: String::new(),
+                text: text.to_string()
+            }]
+        );
+    }
+}
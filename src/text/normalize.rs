@@ -0,0 +1,66 @@
+//! Unicode cleanup applied to all input text.
+//!
+//! Text is put in NFC, so a letter typed as a base letter and a combining
+//! mark matches the precomposed letter in substitution rules and the
+//! glossary, and reaches the backend in the form models are trained on.
+//!
+//! Characters that are never spoken are removed: zero-width spaces, soft
+//! hyphens, byte order marks, control characters other than tabs and line
+//! breaks, and the bidi formatting characters that only steer how text is
+//! displayed. Left in, an embedding opened in one chunk and closed in the
+//! next would reorder the second chunk's text. Zero-width joiners and
+//! non-joiners stay, since they change how Persian, Arabic, and Indic
+//! words are spelled.
+
+use std::borrow::Cow;
+
+use unicode_bidi::Direction;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::{IsNormalized, is_nfc_quick};
+
+/// `text` in NFC with unspoken characters removed.
+pub fn normalize_text(text: &str) -> Cow<'_, str> {
+    let clean = !text.chars().any(is_unspoken);
+    if clean && is_nfc_quick(text.chars()) == IsNormalized::Yes {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.chars().filter(|&c| !is_unspoken(c)).nfc().collect())
+}
+
+/// Invisible characters that carry nothing to say.
+fn is_unspoken(c: char) -> bool {
+    match c {
+        '\t' | '\n' | '\r' => false,
+        c if c.is_control() => true,
+        // Zero-width space, word joiner, invisible operators, byte order
+        // mark, soft hyphen, and the Mongolian vowel separator
+        '\u{200B}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}' | '\u{00AD}' | '\u{180E}' => true,
+        c => is_bidi_control(c),
+    }
+}
+
+/// The explicit directional marks, embeddings, overrides, and isolates.
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200E}' | '\u{200F}' | '\u{061C}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// The direction of the first strongly directional character of `text`,
+/// or `None` for text of digits, punctuation, and spaces only.
+pub(super) fn direction(text: &str) -> Option<Direction> {
+    match unicode_bidi::get_base_direction(text) {
+        Direction::Mixed => None,
+        direction => Some(direction),
+    }
+}
+
+/// Whether two neighbouring pieces of text run the same way, so they can
+/// be sent as one. Neutral text goes either way.
+pub(super) fn same_direction(a: &str, b: &str) -> bool {
+    match (direction(a), direction(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
@@ -0,0 +1,100 @@
+//! German number words.
+
+pub(super) const ONES: [&str; 20] = [
+    "null",
+    "eins",
+    "zwei",
+    "drei",
+    "vier",
+    "fünf",
+    "sechs",
+    "sieben",
+    "acht",
+    "neun",
+    "zehn",
+    "elf",
+    "zwölf",
+    "dreizehn",
+    "vierzehn",
+    "fünfzehn",
+    "sechzehn",
+    "siebzehn",
+    "achtzehn",
+    "neunzehn",
+];
+const TENS: [&str; 10] = [
+    "", "", "zwanzig", "dreißig", "vierzig", "fünfzig", "sechzig", "siebzig", "achtzig", "neunzig",
+];
+const SCALES: [(u64, &str, &str); 3] = [
+    (1_000_000_000_000, "Billion", "Billionen"),
+    (1_000_000_000, "Milliarde", "Milliarden"),
+    (1_000_000, "Million", "Millionen"),
+];
+
+pub(super) fn cardinal(n: u64) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut parts = Vec::new();
+    let mut rest = n;
+    for (scale, one, many) in SCALES {
+        let count = rest / scale;
+        if count == 1 {
+            parts.push(format!("eine {one}"));
+        } else if count > 1 {
+            parts.push(format!("{} {many}", below_1000(count, false)));
+        }
+        rest %= scale;
+    }
+
+    let (thousands, rest) = (rest / 1000, rest % 1000);
+    let mut low = String::new();
+    if thousands > 0 {
+        low.push_str(&below_1000(thousands, false));
+        low.push_str("tausend");
+    }
+    if rest > 0 {
+        low.push_str(&below_1000(rest, true));
+    }
+    if !low.is_empty() {
+        parts.push(low);
+    }
+    parts.join(" ")
+}
+
+/// German words below a thousand; `last` says whether the number ends
+/// here, where one is `eins` rather than `ein`.
+fn below_1000(n: u64, last: bool) -> String {
+    let (hundreds, rest) = (n / 100, n % 100);
+    let mut words = String::new();
+    if hundreds > 0 {
+        words.push_str(&below_100(hundreds, false));
+        words.push_str("hundert");
+    }
+    if rest > 0 {
+        words.push_str(&below_100(rest, last));
+    }
+    words
+}
+
+fn below_100(n: u64, last: bool) -> String {
+    let n = n as usize;
+    match (n, n % 10) {
+        (1, _) if !last => "ein".to_string(),
+        (n, _) if n < 20 => ONES[n].to_string(),
+        (n, 0) => TENS[n / 10].to_string(),
+        (n, 1) => format!("einund{}", TENS[n / 10]),
+        (n, ones) => format!("{}und{}", ONES[ones], TENS[n / 10]),
+    }
+}
+
+/// "neunzehnhundertvierundachtzig".
+pub(super) fn read_year(year: u64) -> String {
+    let (century, rest) = (year / 100, year % 100);
+    let rest = if rest == 0 {
+        String::new()
+    } else {
+        below_100(rest, true)
+    };
+    format!("{}hundert{rest}", below_100(century, false))
+}
@@ -0,0 +1,88 @@
+//! English number words.
+
+pub(super) const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: [(u64, &str); 4] = [
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+pub(super) fn cardinal(n: u64, british: bool) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut parts = Vec::new();
+    let mut rest = n;
+    for (scale, name) in SCALES {
+        if rest >= scale {
+            parts.push(format!("{} {name}", below_1000(rest / scale, british)));
+            rest %= scale;
+        }
+    }
+    if rest > 0 {
+        // British "one thousand and five"
+        if british && !parts.is_empty() && rest < 100 {
+            parts.push("and".to_string());
+        }
+        parts.push(below_1000(rest, british));
+    }
+    parts.join(" ")
+}
+
+fn below_1000(n: u64, british: bool) -> String {
+    let (hundreds, rest) = (n / 100, n % 100);
+    let mut parts = Vec::new();
+    if hundreds > 0 {
+        parts.push(format!("{} hundred", ONES[hundreds as usize]));
+    }
+    if rest > 0 {
+        if british && hundreds > 0 {
+            parts.push("and".to_string());
+        }
+        parts.push(below_100(rest));
+    }
+    parts.join(" ")
+}
+
+fn below_100(n: u64) -> String {
+    match (n as usize, n % 10) {
+        (n, _) if n < 20 => ONES[n].to_string(),
+        (n, 0) => TENS[n / 10].to_string(),
+        (n, ones) => format!("{}-{}", TENS[n / 10], ONES[ones as usize]),
+    }
+}
+
+/// "nineteen eighty-four", "nineteen oh five".
+pub(super) fn read_year(year: u64) -> String {
+    let (century, rest) = (year / 100, year % 100);
+    match rest {
+        0 => format!("{} hundred", below_100(century)),
+        1..=9 => format!("{} oh {}", below_100(century), ONES[rest as usize]),
+        _ => format!("{} {}", below_100(century), below_100(rest)),
+    }
+}
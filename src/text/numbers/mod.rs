@@ -0,0 +1,335 @@
+//! Locale-aware reading of numbers, currencies, and units.
+//!
+//! With a locale set, `3,5 km` in German becomes `drei Komma fünf
+//! Kilometer` and `$3.50` in English `three dollars and fifty cents`.
+//! Digits are read with the locale's decimal and grouping separators, so a
+//! number that does not parse in the locale, such as a date, a time, or a
+//! version number, is left as written, as is a number joined to letters.
+
+mod de;
+mod en;
+mod units;
+mod year;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use super::TextError;
+use units::{CURRENCIES, Currency, UNITS, Unit};
+use year::{read_year, year};
+
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| {
+    let mut units: Vec<&str> = UNITS.iter().map(|u| u.symbol).collect();
+    units.extend(CURRENCIES.iter().map(|c| c.code));
+    units.sort_by_key(|u| std::cmp::Reverse(u.len()));
+    let units: Vec<String> = units.iter().map(|u| regex::escape(u)).collect();
+    Regex::new(&format!(
+        r"(?:^|(?P<lead>[^\p{{L}}\p{{N}}_.,:/]))(?P<sign>[-−])?(?:(?P<pre>[€$£]|(?:EUR|USD|GBP)\s)\s?)?(?P<num>\d(?:[\d.,]*\d)?)(?P<post>\s?(?:[€$£%]|(?:{})\b))?",
+        units.join("|")
+    ))
+    .unwrap()
+});
+
+/// Conventions for reading numbers aloud.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Locale {
+    /// American English: `1,000.5`, "one hundred five".
+    EnUs,
+    /// British English: `1,000.5`, "one hundred and five", "metre".
+    EnGb,
+    /// German: `1.000,5`, "einhundertfünf".
+    DeDe,
+}
+
+impl Locale {
+    const ALL: [Self; 3] = [Self::EnUs, Self::EnGb, Self::DeDe];
+
+    /// Thousands and decimal separators.
+    fn separators(self) -> (char, char) {
+        match self {
+            Self::EnUs | Self::EnGb => (',', '.'),
+            Self::DeDe => ('.', ','),
+        }
+    }
+
+    pub(super) fn is_german(self) -> bool {
+        self == Self::DeDe
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::EnUs => "en-US",
+            Self::EnGb => "en-GB",
+            Self::DeDe => "de-DE",
+        })
+    }
+}
+
+impl FromStr for Locale {
+    type Err = TextError;
+
+    /// Parse a tag such as `de-DE`, `de_AT`, or `en`; a bare language
+    /// means its main locale.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tag = s.trim().replace('_', "-").to_lowercase();
+        match tag.as_str() {
+            "en" | "en-us" => Ok(Self::EnUs),
+            "en-gb" => Ok(Self::EnGb),
+            "de" | "de-de" | "de-at" => Ok(Self::DeDe),
+            _ => {
+                let supported: Vec<String> = Self::ALL.iter().map(Self::to_string).collect();
+                Err(TextError::UnsupportedLocale(format!(
+                    "{s} (supported: {})",
+                    supported.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Locale {
+    type Error = TextError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Locale> for String {
+    fn from(locale: Locale) -> Self {
+        locale.to_string()
+    }
+}
+
+/// A number as written, split at the decimal separator.
+struct Number {
+    negative: bool,
+    int: String,
+    frac: Option<String>,
+    /// Written with group separators, as in `1,234`.
+    grouped: bool,
+}
+
+impl Number {
+    fn parse(sign: bool, text: &str, locale: Locale) -> Option<Self> {
+        let (group, decimal) = locale.separators();
+        let (int, frac) = match text.split_once(decimal) {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (text, None),
+        };
+        if frac.is_some_and(|f| !f.chars().all(|c| c.is_ascii_digit())) {
+            return None;
+        }
+
+        let mut groups = int.split(group);
+        let first = groups.next()?;
+        let rest: Vec<&str> = groups.collect();
+        let valid = first.chars().all(|c| c.is_ascii_digit())
+            && (rest.is_empty()
+                || ((1..=3).contains(&first.len())
+                    && rest
+                        .iter()
+                        .all(|g| g.len() == 3 && g.chars().all(|c| c.is_ascii_digit()))));
+        if !valid {
+            return None;
+        }
+
+        Some(Self {
+            negative: sign,
+            grouped: !rest.is_empty(),
+            int: std::iter::once(first).chain(rest).collect(),
+            frac: frac.map(str::to_string),
+        })
+    }
+
+    fn is_one(&self) -> bool {
+        self.int == "1" && self.frac.is_none()
+    }
+
+    /// The number alone, or before a noun that takes `ein`/`eine` for one.
+    fn words(&self, locale: Locale, one: Option<&str>) -> String {
+        let mut words = match (self.is_one(), one, locale.is_german()) {
+            (true, Some(one), true) => one.to_string(),
+            _ => cardinal(&self.int, locale),
+        };
+        if let Some(frac) = &self.frac {
+            let point = if locale.is_german() { "Komma" } else { "point" };
+            words = format!("{words} {point} {}", digits(frac, locale));
+        }
+        if self.negative {
+            words = format!("minus {words}");
+        }
+        words
+    }
+}
+
+/// Read the numbers in `text` aloud in `locale`.
+pub fn verbalize_numbers(text: &str, locale: Locale) -> String {
+    NUMBER
+        .replace_all(text, |caps: &Captures| {
+            let whole = caps.get(0).unwrap();
+            let lead = caps.name("lead").map_or("", |m| m.as_str());
+            let before = &text[..caps.name("num").unwrap().start()];
+            let spoken = verbalize(caps, before, &text[whole.end()..], locale);
+            match spoken {
+                Some(spoken) => format!("{lead}{spoken}"),
+                None => whole.as_str().to_string(),
+            }
+        })
+        .into_owned()
+}
+
+fn verbalize(caps: &Captures, before: &str, after: &str, locale: Locale) -> Option<String> {
+    let post = caps.name("post").map(|m| m.as_str().trim());
+    if post.is_none() {
+        // A number running into letters or another number is not read
+        let mut next = after.chars();
+        match next.next() {
+            Some(c) if c.is_alphanumeric() || c == '_' => return None,
+            Some(':' | '/') if next.next().is_some_and(|c| c.is_ascii_digit()) => return None,
+            _ => {}
+        }
+    }
+
+    let number = Number::parse(caps.name("sign").is_some(), &caps["num"], locale)?;
+    let pre = caps.name("pre").map(|m| m.as_str().trim());
+    let currency = |symbol: &str| {
+        CURRENCIES
+            .iter()
+            .find(|c| c.symbol == symbol || c.code == symbol)
+    };
+
+    match (pre, post) {
+        (Some(pre), None) => Some(money(&number, currency(pre)?, locale)),
+        (Some(_), Some(_)) => None,
+        (None, Some(post)) => match currency(post) {
+            Some(currency) => Some(money(&number, currency, locale)),
+            None => {
+                let unit = UNITS.iter().find(|u| u.symbol == post)?;
+                Some(measure(&number, unit, locale))
+            }
+        },
+        (None, None) => Some(match year(&number, before, after) {
+            Some(year) => read_year(year, locale),
+            None => number.words(locale, None),
+        }),
+    }
+}
+
+fn money(number: &Number, currency: &Currency, locale: Locale) -> String {
+    let cents = number
+        .frac
+        .as_deref()
+        .filter(|f| f.len() == 2)
+        .and_then(|f| f.parse::<u64>().ok());
+    let major = Number {
+        negative: false,
+        int: number.int.clone(),
+        frac: None,
+        grouped: false,
+    };
+    let minus = if number.negative { "minus " } else { "" };
+
+    let Some(cents) = cents.or(number.frac.is_none().then_some(0)) else {
+        // Fractions of a cent read as a decimal amount
+        let name = if locale.is_german() {
+            currency.de[0]
+        } else {
+            currency.en[1]
+        };
+        return format!("{} {name}", number.words(locale, None));
+    };
+
+    let no_major = major.int.trim_start_matches('0').is_empty();
+    let mut parts = Vec::new();
+    if !no_major || cents == 0 {
+        let name = if locale.is_german() {
+            currency.de[0]
+        } else if major.is_one() {
+            currency.en[0]
+        } else {
+            currency.en[1]
+        };
+        parts.push(format!("{} {name}", major.words(locale, Some("ein"))));
+    }
+    if cents > 0 {
+        parts.push(read_cents(cents, no_major, currency, locale));
+    }
+    let joiner = if locale.is_german() { " " } else { " and " };
+    format!("{minus}{}", parts.join(joiner))
+}
+
+/// The cents of an amount; German names them only with no whole units.
+fn read_cents(cents: u64, no_major: bool, currency: &Currency, locale: Locale) -> String {
+    let cents_words = cardinal(&cents.to_string(), locale);
+    match (locale.is_german(), no_major) {
+        // "drei Euro fünfzig"
+        (true, false) => cents_words,
+        (true, true) => {
+            let cents_words = if cents == 1 { "ein" } else { &cents_words };
+            format!("{cents_words} {}", currency.de[1])
+        }
+        (false, _) => {
+            let name = if cents == 1 {
+                currency.en[2]
+            } else {
+                currency.en[3]
+            };
+            format!("{cents_words} {name}")
+        }
+    }
+}
+
+fn measure(number: &Number, unit: &Unit, locale: Locale) -> String {
+    let plural = usize::from(!number.is_one());
+    if locale.is_german() {
+        let one = if unit.feminine { "eine" } else { "ein" };
+        format!("{} {}", number.words(locale, Some(one)), unit.de[plural])
+    } else {
+        let mut name = unit.en[plural].to_string();
+        if locale == Locale::EnGb {
+            name = name.replace("meter", "metre").replace("liter", "litre");
+        }
+        format!("{} {name}", number.words(locale, None))
+    }
+}
+
+/// Digits one by one, as after a decimal point.
+fn digits(text: &str, locale: Locale) -> String {
+    let names = if locale.is_german() {
+        &de::ONES
+    } else {
+        &en::ONES
+    };
+    text.chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| names[d as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whole-number words; leading zeros and numbers past the trillions are
+/// read digit by digit.
+fn cardinal(text: &str, locale: Locale) -> String {
+    let n = match text.parse::<u64>() {
+        Ok(n) if n < 1_000_000_000_000_000 && !(text.len() > 1 && text.starts_with('0')) => n,
+        _ => return digits(text, locale),
+    };
+    number_words(n, locale)
+}
+
+/// Words for a whole number below a quadrillion.
+pub(super) fn number_words(n: u64, locale: Locale) -> String {
+    match locale {
+        Locale::EnUs => en::cardinal(n, false),
+        Locale::EnGb => en::cardinal(n, true),
+        Locale::DeDe => de::cardinal(n),
+    }
+}
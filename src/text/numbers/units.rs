@@ -0,0 +1,117 @@
+//! The currencies and units a number can be read with.
+
+pub(super) struct Currency {
+    pub(super) symbol: &'static str,
+    pub(super) code: &'static str,
+    /// English singular and plural, then the minor unit's.
+    pub(super) en: [&'static str; 4],
+    /// German names, which do not change in the plural.
+    pub(super) de: [&'static str; 2],
+}
+
+pub(super) const CURRENCIES: &[Currency] = &[
+    Currency {
+        symbol: "€",
+        code: "EUR",
+        en: ["euro", "euros", "cent", "cents"],
+        de: ["Euro", "Cent"],
+    },
+    Currency {
+        symbol: "$",
+        code: "USD",
+        en: ["dollar", "dollars", "cent", "cents"],
+        de: ["Dollar", "Cent"],
+    },
+    Currency {
+        symbol: "£",
+        code: "GBP",
+        en: ["pound", "pounds", "penny", "pence"],
+        de: ["Pfund", "Pence"],
+    },
+];
+
+pub(super) struct Unit {
+    pub(super) symbol: &'static str,
+    pub(super) en: [&'static str; 2],
+    pub(super) de: [&'static str; 2],
+    /// Takes `eine` rather than `ein` in German.
+    pub(super) feminine: bool,
+}
+
+const fn unit(symbol: &'static str, en: [&'static str; 2], de: [&'static str; 2]) -> Unit {
+    Unit {
+        symbol,
+        en,
+        de,
+        feminine: false,
+    }
+}
+
+pub(super) const UNITS: &[Unit] = &[
+    unit("%", ["percent", "percent"], ["Prozent", "Prozent"]),
+    unit(
+        "km/h",
+        ["kilometer per hour", "kilometers per hour"],
+        ["Kilometer pro Stunde", "Kilometer pro Stunde"],
+    ),
+    unit(
+        "mph",
+        ["mile per hour", "miles per hour"],
+        ["Meile pro Stunde", "Meilen pro Stunde"],
+    ),
+    unit(
+        "km",
+        ["kilometer", "kilometers"],
+        ["Kilometer", "Kilometer"],
+    ),
+    unit(
+        "cm",
+        ["centimeter", "centimeters"],
+        ["Zentimeter", "Zentimeter"],
+    ),
+    unit(
+        "mm",
+        ["millimeter", "millimeters"],
+        ["Millimeter", "Millimeter"],
+    ),
+    unit("m", ["meter", "meters"], ["Meter", "Meter"]),
+    unit("kg", ["kilogram", "kilograms"], ["Kilogramm", "Kilogramm"]),
+    unit(
+        "mg",
+        ["milligram", "milligrams"],
+        ["Milligramm", "Milligramm"],
+    ),
+    unit("g", ["gram", "grams"], ["Gramm", "Gramm"]),
+    unit(
+        "ml",
+        ["milliliter", "milliliters"],
+        ["Milliliter", "Milliliter"],
+    ),
+    unit("l", ["liter", "liters"], ["Liter", "Liter"]),
+    unit(
+        "°C",
+        ["degree Celsius", "degrees Celsius"],
+        ["Grad Celsius", "Grad Celsius"],
+    ),
+    unit(
+        "°F",
+        ["degree Fahrenheit", "degrees Fahrenheit"],
+        ["Grad Fahrenheit", "Grad Fahrenheit"],
+    ),
+    Unit {
+        feminine: true,
+        ..unit(
+            "kWh",
+            ["kilowatt hour", "kilowatt hours"],
+            ["Kilowattstunde", "Kilowattstunden"],
+        )
+    },
+    unit("kW", ["kilowatt", "kilowatts"], ["Kilowatt", "Kilowatt"]),
+    unit("MB", ["megabyte", "megabytes"], ["Megabyte", "Megabyte"]),
+    unit("GB", ["gigabyte", "gigabytes"], ["Gigabyte", "Gigabyte"]),
+    unit("TB", ["terabyte", "terabytes"], ["Terabyte", "Terabyte"]),
+    Unit {
+        feminine: true,
+        ..unit("min", ["minute", "minutes"], ["Minute", "Minuten"])
+    },
+];
@@ -0,0 +1,86 @@
+//! Four-digit numbers read as years where the words around them say so.
+
+use super::{Locale, Number, de, en};
+use crate::text::ordinal::DE_MONTHS;
+
+/// A plain four-digit number from 1100 to 1999 where the words around it
+/// make it a year: "in 1984", "seit 1984", "May 3, 1984". Elsewhere, as in
+/// "1234 apples", it is a cardinal.
+pub(super) fn year(number: &Number, before: &str, after: &str) -> Option<u64> {
+    if number.negative || number.grouped || number.frac.is_some() || number.int.len() != 4 {
+        return None;
+    }
+    let next = after.split_whitespace().next().unwrap_or_default();
+    if !is_year_context(before) && !is_month(next) {
+        return None;
+    }
+    number
+        .int
+        .parse()
+        .ok()
+        .filter(|n| (1100..=1999).contains(n))
+}
+
+/// Words a year is read after, compared in lower case.
+const YEAR_WORDS: &[&str] = &[
+    "in", "since", "until", "year", "im", "seit", "bis", "jahr", "anno",
+];
+
+/// Words that join a year to the one before, as in "in 1984 and 1905".
+const YEAR_JOINERS: &[&str] = &["and", "or", "to", "und", "oder"];
+
+const EN_MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Whether the words ending `before` lead into a year: a word such as
+/// `in`, a month with or without its day, or an earlier year and `and`.
+fn is_year_context(before: &str) -> bool {
+    let digits = |w: &str, len: std::ops::RangeInclusive<usize>| {
+        len.contains(&w.len()) && w.chars().all(|c| c.is_ascii_digit())
+    };
+    let mut words = before.split_whitespace().rev().map(|w| {
+        w.trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+    });
+    while let Some(word) = words.next() {
+        if YEAR_WORDS.contains(&word.as_str()) || is_month(&word) {
+            return true;
+        }
+        // "May 3, 1984"
+        if digits(&word, 1..=2) {
+            return words.next().is_some_and(|w| is_month(&w));
+        }
+        // "in 1984 and 1905" counts if the earlier year does
+        let joined = YEAR_JOINERS.contains(&word.as_str())
+            && words.next().is_some_and(|w| digits(&w, 4..=4));
+        if !joined {
+            return false;
+        }
+    }
+    false
+}
+
+fn is_month(word: &str) -> bool {
+    let word = word.to_lowercase();
+    EN_MONTHS.contains(&word.as_str()) || DE_MONTHS.iter().any(|m| m.to_lowercase() == word)
+}
+
+pub(super) fn read_year(year: u64, locale: Locale) -> String {
+    if locale.is_german() {
+        de::read_year(year)
+    } else {
+        en::read_year(year)
+    }
+}
@@ -0,0 +1,326 @@
+//! Ordinals and roman numerals.
+//!
+//! English ordinals such as `21st` are read as words, and so is a German
+//! `3.` where a month name or a lower-case word follows ("am 3. Mai" is "am
+//! dritten Mai"), or an article comes before ("der 1. Platz" is "der erste
+//! Platz"); elsewhere the dot more likely ends a sentence. A roman
+//! numeral is only read where its context makes it one:
+//!
+//! - after a word such as `Chapter`, `Part`, or `War` it is a number
+//!   ("Chapter IV", "World War II"),
+//! - alone on a line, as a section heading, it is a number,
+//! - after a name it is a regnal ordinal ("Henry VIII" is "Henry the
+//!   Eighth", "Heinrich VIII." is "Heinrich der Achte").
+//!
+//! Elsewhere capitals are left alone, since `MIX` and `CD` are words or
+//! acronyms. After a name only numerals of two or more letters from `I`,
+//! `V`, and `X` count, so "Malcolm X" keeps its letter, and the pronoun
+//! `I` only counts when `of` follows, or punctuation follows a name inside
+//! a sentence ("Elizabeth I of England", but "Then I left" and "Am I?").
+
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use super::numbers::{Locale, number_words};
+
+static ROMAN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?P<num>[IVXLCDM]+)\b(?P<poss>'s|’s)?(?P<dot>\.)?").unwrap());
+static ORDINAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?P<n>\d+)(?:st|nd|rd|th)\b").unwrap());
+static DE_ORDINAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?P<n>\d+)\. (?P<next>\p{L}+)").unwrap());
+
+/// German month names, which a day's ordinal dot comes before.
+pub(super) const DE_MONTHS: &[&str] = &[
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+
+/// Words after which a German ordinal takes the weak `-en` ending, as in
+/// "am dritten", compared in lower case.
+const DE_WEAK: &[&str] = &["am", "im", "vom", "zum", "zur", "beim", "dem", "den", "des"];
+
+/// Articles after which a German `3.` is an ordinal before any word, as in
+/// "der 1. Platz", compared in lower case.
+const DE_ARTICLES: &[&str] = &["der", "die", "das"];
+
+/// Words a roman numeral counts after, compared in lower case.
+const SECTION_WORDS: &[&str] = &[
+    "chapter",
+    "book",
+    "part",
+    "volume",
+    "vol",
+    "act",
+    "scene",
+    "section",
+    "appendix",
+    "article",
+    "canto",
+    "psalm",
+    "phase",
+    "stage",
+    "episode",
+    "season",
+    "round",
+    "war",
+    "bowl",
+    "kapitel",
+    "buch",
+    "teil",
+    "band",
+    "akt",
+    "szene",
+    "abschnitt",
+    "anhang",
+    "artikel",
+    "folge",
+    "staffel",
+];
+
+/// How a roman numeral is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reading {
+    /// "four"
+    Number,
+    /// "the Fourth"
+    Regnal,
+}
+
+/// Read ordinals and roman numerals in `text` aloud in `locale`.
+pub fn verbalize_ordinals(text: &str, locale: Locale) -> String {
+    let text = if locale.is_german() {
+        DE_ORDINAL
+            .replace_all(text, |caps: &Captures| de_ordinal_before(caps, text))
+            .into_owned()
+    } else {
+        ORDINAL
+            .replace_all(text, |caps: &Captures| match caps["n"].parse::<u64>() {
+                Ok(n) if n < 1_000_000_000_000_000 => ordinal_words(n, locale),
+                _ => caps[0].to_string(),
+            })
+            .into_owned()
+    };
+
+    ROMAN
+        .replace_all(&text, |caps: &Captures| {
+            let num = caps.name("num").unwrap();
+            let reading = reading(&text[..num.start()], num.as_str(), &text[num.end()..]);
+            match (reading, parse_roman(num.as_str())) {
+                (Some(reading), Some(value)) => spoken(caps, &text, reading, value, locale),
+                _ => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// "am 3. Mai" as "am dritten Mai"; a dot before anything but a month or
+/// a lower-case word is left unless an article comes first, since it may
+/// end the sentence.
+fn de_ordinal_before(caps: &Captures, text: &str) -> String {
+    let whole = caps.get(0).unwrap();
+    let next = &caps["next"];
+    let before = &text[..whole.start()];
+    let prev = before
+        .trim_end()
+        .rsplit(' ')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let after_article = DE_WEAK.contains(&prev.as_str()) || DE_ARTICLES.contains(&prev.as_str());
+    let is_ordinal =
+        (DE_MONTHS.contains(&next) || next.starts_with(char::is_lowercase) || after_article)
+            && !before.ends_with(['.', ','])
+            && caps["n"].len() <= 15;
+    if !is_ordinal {
+        return caps[0].to_string();
+    }
+    let mut words = de_ordinal(caps["n"].parse().unwrap());
+    if DE_WEAK.contains(&prev.as_str()) {
+        words.push('n');
+    }
+    format!("{words} {next}")
+}
+
+fn spoken(caps: &Captures, text: &str, reading: Reading, value: u64, locale: Locale) -> String {
+    let mut words = match reading {
+        Reading::Number => number_words(value, locale),
+        Reading::Regnal => {
+            let article = if locale.is_german() { "der" } else { "the" };
+            format!("{article} {}", capitalize(&ordinal_words(value, locale)))
+        }
+    };
+    if let Some(poss) = caps.name("poss") {
+        words.push_str(poss.as_str());
+    }
+    if let Some(dot) = caps.name("dot") {
+        // The German ordinal dot is dropped where the sentence goes on
+        let continues = text[dot.end()..]
+            .strip_prefix(' ')
+            .is_some_and(|rest| rest.starts_with(char::is_lowercase));
+        if !(locale.is_german() && reading == Reading::Regnal && continues) {
+            words.push('.');
+        }
+    }
+    words
+}
+
+/// How the numeral `num` between `before` and `after` is read, if it is
+/// one.
+fn reading(before: &str, num: &str, after: &str) -> Option<Reading> {
+    let line_before = before.rsplit('\n').next().unwrap_or_default();
+    let line_after = after.split('\n').next().unwrap_or_default();
+    if line_before.trim().is_empty() && matches!(line_after.trim(), "" | ".") {
+        return Some(Reading::Number);
+    }
+
+    let prev = line_before.strip_suffix(' ')?;
+    let word_start = prev
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| c.is_alphabetic() || c == '.')
+        .last()
+        .map(|(i, _)| i)?;
+    let word = &prev[word_start..];
+
+    if SECTION_WORDS.contains(&word.trim_end_matches('.').to_lowercase().as_str()) {
+        return Some(Reading::Number);
+    }
+    let is_name = word.starts_with(char::is_uppercase) && word.chars().all(char::is_alphabetic);
+    let regnal =
+        num.chars().all(|c| matches!(c, 'I' | 'V' | 'X')) && (num.len() >= 2 || num == "I");
+    if !is_name || !regnal {
+        return None;
+    }
+    if num == "I" && !after.starts_with(" of ") {
+        // Otherwise only after a name inside a sentence, not "Am I?"
+        let sentence_start = prev[..word_start]
+            .trim_end()
+            .chars()
+            .last()
+            .is_none_or(|c| matches!(c, '.' | '!' | '?' | '"' | '“'));
+        let ends = after.is_empty() || after.starts_with(['.', ',', ';', ':', '!', '?', ')']);
+        if sentence_start || !ends {
+            return None;
+        }
+    }
+    Some(Reading::Regnal)
+}
+
+/// The value of a numeral in its usual form, from 1 to 3999.
+fn parse_roman(numeral: &str) -> Option<u64> {
+    let value = |c: char| match c {
+        'I' => 1,
+        'V' => 5,
+        'X' => 10,
+        'L' => 50,
+        'C' => 100,
+        'D' => 500,
+        'M' => 1000,
+        _ => 0,
+    };
+    let values: Vec<u64> = numeral.chars().map(value).collect();
+    let total = values.iter().enumerate().fold(0, |total, (i, &v)| {
+        if values.get(i + 1).is_some_and(|&next| next > v) {
+            total - v as i64
+        } else {
+            total + v as i64
+        }
+    });
+    let total = u64::try_from(total)
+        .ok()
+        .filter(|t| (1..4000).contains(t))?;
+    // Only the usual spelling, so "IIII" or "VX" is not a numeral
+    (to_roman(total) == numeral).then_some(total)
+}
+
+fn to_roman(mut n: u64) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut roman = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            roman.push_str(numeral);
+            n -= value;
+        }
+    }
+    roman
+}
+
+/// "twenty-first", "einundzwanzigste".
+fn ordinal_words(n: u64, locale: Locale) -> String {
+    if locale.is_german() {
+        return de_ordinal(n);
+    }
+    let cardinal = number_words(n, locale);
+    let split = cardinal.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (head, last) = cardinal.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word => match word.strip_suffix('y') {
+            Some(stem) => format!("{stem}ieth"),
+            None => format!("{word}th"),
+        },
+    };
+    format!("{head}{last}")
+}
+
+fn de_ordinal(n: u64) -> String {
+    let small = n % 100;
+    if !(1..20).contains(&small) {
+        return format!("{}ste", number_words(n, Locale::DeDe));
+    }
+    let last = match small {
+        1 => "erste".to_string(),
+        3 => "dritte".to_string(),
+        7 => "siebte".to_string(),
+        8 => "achte".to_string(),
+        _ => format!("{}te", number_words(small, Locale::DeDe)),
+    };
+    match n - small {
+        0 => last,
+        head => {
+            let head = number_words(head, Locale::DeDe);
+            let space = if head.contains(' ') { " " } else { "" };
+            format!("{head}{space}{last}")
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,358 @@
+//! Phoneme segments.
+//!
+//! A name no respelling can fix may be written directly in IPA or ARPABET:
+//!
+//! ```text
+//! The [[ph: ˈkwɒləti]] of [[ph: N IY1 CH AH0]].
+//! ```
+//!
+//! A segment whose every symbol is an upper-case ARPABET phone (with an
+//! optional stress digit) is ARPABET; anything else is IPA. Backends that
+//! accept phoneme input receive an SSML `<phoneme>` tag; the rest get a
+//! respelling such as `KWOL-uh-tee`, with the stressed syllable in capitals.
+
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use unicode_normalization::UnicodeNormalization;
+
+use super::TextError;
+
+static PHONEME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\[ph:\s*([^\]]*?)\s*\]\]").unwrap());
+
+/// IPA symbols: symbol, respelling, vowel, short vowel. Two-letter symbols
+/// come first so they are matched before their parts.
+const IPA: &[(&str, &str, bool, bool)] = &[
+    ("aɪ", "eye", true, false),
+    ("aʊ", "ow", true, false),
+    ("eɪ", "ay", true, false),
+    ("oʊ", "oh", true, false),
+    ("əʊ", "oh", true, false),
+    ("ɔɪ", "oy", true, false),
+    ("ɪə", "eer", true, false),
+    ("ɛə", "air", true, false),
+    ("eə", "air", true, false),
+    ("ʊə", "oor", true, false),
+    ("tʃ", "ch", false, false),
+    ("dʒ", "j", false, false),
+    ("a", "a", true, true),
+    ("æ", "a", true, true),
+    ("ɑ", "ah", true, false),
+    ("ɒ", "o", true, true),
+    ("ɔ", "aw", true, false),
+    ("ə", "uh", true, false),
+    ("ɐ", "uh", true, false),
+    ("ʌ", "u", true, true),
+    ("ɛ", "e", true, true),
+    ("e", "eh", true, false),
+    ("ɪ", "i", true, true),
+    ("ɨ", "i", true, true),
+    ("i", "ee", true, false),
+    ("ʊ", "uu", true, true),
+    ("u", "oo", true, false),
+    ("ʉ", "oo", true, false),
+    ("ɜ", "ur", true, false),
+    ("ɝ", "ur", true, false),
+    ("ɚ", "er", true, false),
+    ("o", "oh", true, false),
+    ("ø", "ur", true, false),
+    ("œ", "ur", true, false),
+    ("y", "ew", true, false),
+    ("p", "p", false, false),
+    ("b", "b", false, false),
+    ("t", "t", false, false),
+    ("d", "d", false, false),
+    ("k", "k", false, false),
+    ("g", "g", false, false),
+    ("ɡ", "g", false, false),
+    ("f", "f", false, false),
+    ("v", "v", false, false),
+    ("θ", "th", false, false),
+    ("ð", "dh", false, false),
+    ("s", "s", false, false),
+    ("z", "z", false, false),
+    ("ʃ", "sh", false, false),
+    ("ʒ", "zh", false, false),
+    ("h", "h", false, false),
+    ("x", "kh", false, false),
+    ("χ", "kh", false, false),
+    ("ç", "kh", false, false),
+    ("m", "m", false, false),
+    ("n", "n", false, false),
+    ("ŋ", "ng", false, false),
+    ("ɲ", "ny", false, false),
+    ("l", "l", false, false),
+    ("ɫ", "l", false, false),
+    ("r", "r", false, false),
+    ("ɹ", "r", false, false),
+    ("ɾ", "r", false, false),
+    ("ʁ", "r", false, false),
+    ("j", "y", false, false),
+    ("w", "w", false, false),
+    ("ʍ", "wh", false, false),
+    ("ʔ", "", false, false),
+];
+
+/// ARPABET phones: symbol, respelling, vowel, short vowel.
+const ARPABET: &[(&str, &str, bool, bool)] = &[
+    ("AA", "ah", true, false),
+    ("AE", "a", true, true),
+    ("AH", "uh", true, false),
+    ("AO", "aw", true, false),
+    ("AW", "ow", true, false),
+    ("AY", "eye", true, false),
+    ("EH", "e", true, true),
+    ("ER", "ur", true, false),
+    ("EY", "ay", true, false),
+    ("IH", "i", true, true),
+    ("IY", "ee", true, false),
+    ("OW", "oh", true, false),
+    ("OY", "oy", true, false),
+    ("UH", "uu", true, true),
+    ("UW", "oo", true, false),
+    ("B", "b", false, false),
+    ("CH", "ch", false, false),
+    ("D", "d", false, false),
+    ("DH", "dh", false, false),
+    ("F", "f", false, false),
+    ("G", "g", false, false),
+    ("HH", "h", false, false),
+    ("JH", "j", false, false),
+    ("K", "k", false, false),
+    ("L", "l", false, false),
+    ("M", "m", false, false),
+    ("N", "n", false, false),
+    ("NG", "ng", false, false),
+    ("P", "p", false, false),
+    ("R", "r", false, false),
+    ("S", "s", false, false),
+    ("SH", "sh", false, false),
+    ("T", "t", false, false),
+    ("TH", "th", false, false),
+    ("V", "v", false, false),
+    ("W", "w", false, false),
+    ("Y", "y", false, false),
+    ("Z", "z", false, false),
+    ("ZH", "zh", false, false),
+];
+
+/// Length marks, aspiration, tie bars, and diacritics, which a respelling
+/// cannot show.
+const IGNORED: &[char] = &['ː', 'ˑ', 'ʰ', 'ʲ', 'ʷ', '/', '[', ']'];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Stress {
+    #[default]
+    None,
+    Secondary,
+    Primary,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Phone {
+    spelling: &'static str,
+    vowel: bool,
+    short: bool,
+    stress: Stress,
+    /// A syllable break was written before this phone.
+    syllable_start: bool,
+}
+
+/// Replace every phoneme segment in `text` with an SSML `<phoneme>` tag
+/// when `passthrough` is set, or with its respelling otherwise.
+///
+/// Segments that do not parse are left as written; [`chunk_text`]
+/// rejects them.
+///
+/// [`chunk_text`]: super::chunk_text
+pub fn render_phonemes(text: &str, passthrough: bool) -> String {
+    PHONEME
+        .replace_all(text, |caps: &Captures| {
+            let phonemes = &caps[1];
+            let Ok(respelling) = respell(phonemes) else {
+                return caps[0].to_string();
+            };
+            if !passthrough {
+                return respelling;
+            }
+            let alphabet = if is_arpabet(phonemes) {
+                "arpabet"
+            } else {
+                "ipa"
+            };
+            format!(
+                r#"<phoneme alphabet="{alphabet}" ph="{}">{respelling}</phoneme>"#,
+                escape(phonemes)
+            )
+        })
+        .into_owned()
+}
+
+/// Check every phoneme segment in `text`.
+pub(super) fn check_phonemes(text: &str) -> Result<(), TextError> {
+    for caps in PHONEME.captures_iter(text) {
+        respell(&caps[1])?;
+    }
+    Ok(())
+}
+
+/// Spell IPA or ARPABET phonemes the way they sound, as in `KWOL-uh-tee`.
+pub fn respell(phonemes: &str) -> Result<String, TextError> {
+    let words = if is_arpabet(phonemes) {
+        vec![parse_arpabet(phonemes)?]
+    } else {
+        phonemes
+            .split_whitespace()
+            .map(parse_ipa)
+            .collect::<Result<_, _>>()?
+    };
+    if words.is_empty() {
+        return Err(TextError::InvalidPhonemes("empty phoneme segment".into()));
+    }
+    Ok(words
+        .iter()
+        .map(|word| respell_word(word))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+fn is_arpabet(phonemes: &str) -> bool {
+    let mut symbols = phonemes.split_whitespace().peekable();
+    symbols.peek().is_some()
+        && symbols.all(|symbol| {
+            let phone = symbol.trim_end_matches(['0', '1', '2']);
+            ARPABET.iter().any(|&(s, ..)| s == phone)
+        })
+}
+
+fn parse_arpabet(phonemes: &str) -> Result<Vec<Phone>, TextError> {
+    phonemes
+        .split_whitespace()
+        .map(|symbol| {
+            let phone = symbol.trim_end_matches(['0', '1', '2']);
+            let &(_, spelling, vowel, short) = ARPABET
+                .iter()
+                .find(|&&(s, ..)| s == phone)
+                .ok_or_else(|| TextError::InvalidPhonemes(format!("unknown ARPABET '{symbol}'")))?;
+            let stress = match &symbol[phone.len()..] {
+                "1" => Stress::Primary,
+                "2" => Stress::Secondary,
+                _ => Stress::None,
+            };
+            // Stressed AH is the vowel of "strut", not a schwa
+            let (spelling, short) = match (phone, stress) {
+                ("AH", Stress::Primary | Stress::Secondary) => ("u", true),
+                _ => (spelling, short),
+            };
+            Ok(Phone {
+                spelling,
+                vowel,
+                short,
+                stress,
+                syllable_start: false,
+            })
+        })
+        .collect()
+}
+
+fn parse_ipa(word: &str) -> Result<Vec<Phone>, TextError> {
+    // Diacritics are dropped from precomposed letters too, except the
+    // cedilla of `ç`, a symbol of its own
+    let cleaned: String = word
+        .nfd()
+        .filter(|&c| {
+            !IGNORED.contains(&c) && (c == '\u{327}' || !('\u{300}'..='\u{36f}').contains(&c))
+        })
+        .nfc()
+        .collect();
+    let mut phones = Vec::new();
+    let mut stress = Stress::None;
+    let mut syllable_start = false;
+    let mut rest = cleaned.as_str();
+
+    while let Some(c) = rest.chars().next() {
+        let mark = match c {
+            'ˈ' | '\'' => Some(Stress::Primary),
+            'ˌ' => Some(Stress::Secondary),
+            '.' => Some(Stress::None),
+            _ => None,
+        };
+        if let Some(mark) = mark {
+            stress = stress.max(mark);
+            syllable_start = true;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        let &(symbol, spelling, vowel, short) = IPA
+            .iter()
+            .find(|&&(symbol, ..)| rest.starts_with(symbol))
+            .ok_or_else(|| TextError::InvalidPhonemes(format!("unknown IPA symbol '{c}'")))?;
+        phones.push(Phone {
+            spelling,
+            vowel,
+            short,
+            stress: Stress::None,
+            syllable_start,
+        });
+        syllable_start = false;
+        // A stress mark belongs to the syllable it starts
+        if vowel {
+            phones.last_mut().unwrap().stress = std::mem::take(&mut stress);
+        }
+        rest = &rest[symbol.len()..];
+    }
+    Ok(phones)
+}
+
+/// Split a word into syllables at its written breaks, or else before the
+/// consonant ahead of each vowel, keeping a consonant after a stressed
+/// short vowel in its syllable ("KWOL", not "KWO").
+fn respell_word(phones: &[Phone]) -> String {
+    let vowels: Vec<usize> = (0..phones.len()).filter(|&i| phones[i].vowel).collect();
+    let mut starts = vec![0];
+    for pair in vowels.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let start = (a + 1..=b)
+            .find(|&i| phones[i].syllable_start)
+            .unwrap_or(match b - a - 1 {
+                0 => b,
+                1 if phones[a].short && phones[a].stress > Stress::None => b,
+                1 => a + 1,
+                _ => a + 2,
+            });
+        starts.push(start);
+    }
+    starts.push(phones.len());
+
+    starts
+        .windows(2)
+        .map(|range| {
+            let syllable = &phones[range[0]..range[1]];
+            let onset = syllable.iter().take_while(|p| !p.vowel).count() > 0;
+            let stress = syllable.iter().map(|p| p.stress).max().unwrap_or_default();
+            let spelled: String = syllable
+                .iter()
+                .map(|p| match p.spelling {
+                    // "my" is "MY", not "MEYE"
+                    "eye" if onset => "y",
+                    spelling => spelling,
+                })
+                .collect();
+            if stress == Stress::Primary {
+                spelled.to_uppercase()
+            } else {
+                spelled
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,91 @@
+//! Preprocessing pipeline combining all text transformations.
+
+use super::glossary::{Glossary, GlossaryConflict};
+use super::markup::{MarkupOptions, strip_markup};
+use super::normalize::normalize_text;
+use super::numbers::{Locale, verbalize_numbers};
+use super::ordinal::verbalize_ordinals;
+use super::profanity::Profanity;
+use super::replace::ReplaceRules;
+
+/// Ordered text transformations applied before synthesis.
+///
+/// Unicode is normalized and markup stripped first so that substitution
+/// rules see plain text, then the glossary and number reading run, and
+/// profanity is masked last, in whatever the other steps spelled out.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    markup: MarkupOptions,
+    rules: ReplaceRules,
+    glossary: Glossary,
+    locale: Option<Locale>,
+    profanity: Option<Profanity>,
+}
+
+impl Preprocessor {
+    /// Create an empty preprocessor that only normalizes Unicode.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set markup stripping options.
+    pub fn with_markup(mut self, markup: MarkupOptions) -> Self {
+        self.markup = markup;
+        self
+    }
+
+    /// Set regex substitution rules.
+    pub fn with_rules(mut self, rules: ReplaceRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Set the acronym glossary.
+    pub fn with_glossary(mut self, glossary: Glossary) -> Self {
+        self.glossary = glossary;
+        self
+    }
+
+    /// Read numbers, ordinals, roman numerals, currencies, and units aloud
+    /// in `locale`; `None`
+    /// leaves them as written.
+    pub fn with_locale(mut self, locale: Option<Locale>) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Mask the words of `profanity`; `None` leaves them as written.
+    pub fn with_profanity(mut self, profanity: Option<Profanity>) -> Self {
+        self.profanity = profanity;
+        self
+    }
+
+    /// Glossary terms the substitution rules rewrite before the glossary
+    /// can expand them.
+    pub fn glossary_conflicts(&self) -> Vec<GlossaryConflict> {
+        self.glossary
+            .iter()
+            .filter_map(|(term, _)| {
+                let rewritten = self.rules.apply(term);
+                (rewritten != term).then(|| GlossaryConflict::Shadowed {
+                    term: term.to_string(),
+                    rewritten,
+                })
+            })
+            .collect()
+    }
+
+    /// Run all transformations over the text.
+    pub fn process(&self, text: &str) -> String {
+        let text = strip_markup(&normalize_text(text), &self.markup);
+        let text = self.glossary.apply(&self.rules.apply(&text));
+        let text = match self.locale {
+            Some(locale) => verbalize_numbers(&verbalize_ordinals(&text, locale), locale),
+            None => text,
+        };
+        match &self.profanity {
+            Some(profanity) => profanity.apply(&text),
+            None => text,
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Masking profanity before synthesis.
+//!
+//! A [`Profanity`] list holds the words to mask, each matched as a whole
+//! word and ignoring case; a word ending in `*` also matches every word
+//! that starts with it (`frak*` catches `frakking`). How a match is masked
+//! is set by [`ProfanityMask`]: bleeping it replaces the word with a
+//! `[bleep:...]` tag about as long as the word would take to say, which
+//! the chunker turns into a tone.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use super::TextError;
+
+/// Said instead of a word that has no substitute of its own.
+pub const DEFAULT_SUBSTITUTE: &str = "beep";
+
+/// Length of a bleep per letter of the word it masks, in milliseconds,
+/// and the shortest and longest bleep.
+const BLEEP_MS_PER_LETTER: u64 = 70;
+const BLEEP_MS: (u64, u64) = (250, 800);
+
+/// How a word on the list is masked.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfanityMask {
+    /// Replace the word with a tone.
+    #[default]
+    Bleep,
+    /// Leave the word out.
+    Skip,
+    /// Say the word's substitute, or "beep" if it has none.
+    Substitute,
+}
+
+/// Words to mask and what to say instead of each.
+#[derive(Debug, Clone, Default)]
+pub struct Profanity {
+    mask: ProfanityMask,
+    /// Lowercased words, `*` kept, to their substitutes ("" for the default).
+    words: BTreeMap<String, String>,
+    pattern: OnceLock<Option<Regex>>,
+}
+
+impl Profanity {
+    pub fn new(mask: ProfanityMask) -> Self {
+        Self {
+            mask,
+            ..Self::default()
+        }
+    }
+
+    pub fn mask(&self) -> ProfanityMask {
+        self.mask
+    }
+
+    /// Add words from `word = substitute` entries, as in a `[profanity]`
+    /// table; an empty substitute means [`DEFAULT_SUBSTITUTE`].
+    pub fn extend(&mut self, entries: &BTreeMap<String, String>) -> Result<(), TextError> {
+        for (word, substitute) in entries {
+            self.insert(word, substitute)?;
+        }
+        Ok(())
+    }
+
+    /// Add the words of a TOML file of `word = "substitute"` lines.
+    pub fn load(&mut self, path: &Path) -> Result<(), TextError> {
+        let invalid =
+            |e: &dyn fmt::Display| TextError::InvalidProfanity(format!("{}: {e}", path.display()));
+        let contents = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+        let entries: BTreeMap<String, String> =
+            toml::from_str(&contents).map_err(|e| invalid(&e))?;
+        self.extend(&entries).map_err(|e| invalid(&e))
+    }
+
+    /// Add a word; later additions of the same word win.
+    pub fn insert(&mut self, word: &str, substitute: &str) -> Result<(), TextError> {
+        let word = word.trim().to_lowercase();
+        let stem = word.strip_suffix('*').unwrap_or(&word);
+        if stem.is_empty() || !stem.chars().all(|c| c.is_alphanumeric() || c == '\'') {
+            return Err(TextError::InvalidProfanity(format!(
+                "'{word}' is not a word"
+            )));
+        }
+        self.pattern = OnceLock::new();
+        self.words.insert(word, substitute.trim().to_string());
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Mask every listed word in `text`.
+    pub fn apply(&self, text: &str) -> String {
+        let Some(pattern) = self.pattern.get_or_init(|| self.compile()) else {
+            return text.to_string();
+        };
+        pattern
+            .replace_all(text, |caps: &Captures| {
+                let (space, word) = (&caps[1], &caps[2]);
+                match self.mask {
+                    // The space before the word goes with it
+                    ProfanityMask::Skip => String::new(),
+                    ProfanityMask::Bleep => {
+                        let letters = word.chars().count() as u64;
+                        let ms = (letters * BLEEP_MS_PER_LETTER).clamp(BLEEP_MS.0, BLEEP_MS.1);
+                        format!("{space}[bleep:{ms}ms]")
+                    }
+                    ProfanityMask::Substitute => format!("{space}{}", self.substitute(word)),
+                }
+            })
+            .into_owned()
+    }
+
+    /// What to say instead of `word`: the substitute of the word itself,
+    /// else of the longest prefix entry matching it.
+    fn substitute(&self, word: &str) -> &str {
+        let word = word.to_lowercase();
+        let substitute = self.words.get(&word).or_else(|| {
+            self.words
+                .iter()
+                .filter(|(entry, _)| {
+                    entry
+                        .strip_suffix('*')
+                        .is_some_and(|stem| word.starts_with(stem))
+                })
+                .max_by_key(|(entry, _)| entry.len())
+                .map(|(_, substitute)| substitute)
+        });
+        match substitute {
+            Some(substitute) if !substitute.is_empty() => substitute,
+            _ => DEFAULT_SUBSTITUTE,
+        }
+    }
+
+    /// One pattern matching every word with the spaces before it.
+    fn compile(&self) -> Option<Regex> {
+        let alternatives: Vec<String> = self
+            .words
+            .keys()
+            .map(|word| match word.strip_suffix('*') {
+                Some(stem) => format!(r"{}\w*", regex::escape(stem)),
+                None => regex::escape(word),
+            })
+            .collect();
+        if alternatives.is_empty() {
+            return None;
+        }
+        let pattern = format!(r"(?i)([ \t]*)\b((?:{}))\b", alternatives.join("|"));
+        Some(Regex::new(&pattern).expect("escaped words form a valid pattern"))
+    }
+}
@@ -0,0 +1,182 @@
+//! Regex substitution rules in sed syntax.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+
+use super::TextError;
+
+/// A single `s/pattern/replacement/flags` substitution rule.
+///
+/// Rules always replace every match; the `g` flag is accepted for sed
+/// compatibility. The `i` flag makes the pattern case-insensitive.
+#[derive(Debug, Clone)]
+pub struct ReplaceRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl ReplaceRule {
+    /// Parse a rule from sed syntax, e.g. `s/GmbH/gee em be ha/`.
+    ///
+    /// Any character may be used as the delimiter, and a delimiter can be
+    /// escaped with a backslash. Back-references use `\1` or `&` as in sed.
+    pub fn parse(spec: &str) -> Result<Self, TextError> {
+        let invalid = |reason: &str| TextError::InvalidRule(format!("{spec}: {reason}"));
+
+        let rest = spec
+            .strip_prefix('s')
+            .ok_or_else(|| invalid("rule must start with 's'"))?;
+        let delimiter = rest
+            .chars()
+            .next()
+            .ok_or_else(|| invalid("missing delimiter"))?;
+        if delimiter.is_alphanumeric() || delimiter == '\\' {
+            return Err(invalid("delimiter must be a punctuation character"));
+        }
+
+        let parts = split_unescaped(&rest[delimiter.len_utf8()..], delimiter);
+        if parts.len() != 3 {
+            return Err(invalid("expected s/pattern/replacement/flags"));
+        }
+
+        let mut builder = RegexBuilder::new(&parts[0]);
+        for flag in parts[2].chars() {
+            match flag {
+                'g' => {}
+                'i' => {
+                    builder.case_insensitive(true);
+                }
+                other => return Err(invalid(&format!("unknown flag '{other}'"))),
+            }
+        }
+
+        let pattern = builder.build().map_err(|e| invalid(&e.to_string()))?;
+
+        Ok(Self {
+            pattern,
+            replacement: translate_replacement(&parts[1]),
+        })
+    }
+
+    /// Replace `word` with `spoken` wherever it stands as a whole word, as
+    /// for a pronunciation lexicon entry. Matching is case-sensitive.
+    pub fn word(word: &str, spoken: &str) -> Result<Self, TextError> {
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let start = if is_word(word.chars().next()) {
+            r"\b"
+        } else {
+            ""
+        };
+        let end = if is_word(word.chars().last()) {
+            r"\b"
+        } else {
+            ""
+        };
+        let pattern = Regex::new(&format!("{start}{}{end}", regex::escape(word)))
+            .map_err(|e| TextError::InvalidRule(format!("{word}: {e}")))?;
+
+        Ok(Self {
+            pattern,
+            replacement: spoken.replace('$', "$$"),
+        })
+    }
+
+    /// Apply this rule to the given text.
+    pub fn apply(&self, text: &str) -> String {
+        self.pattern
+            .replace_all(text, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+/// An ordered list of substitution rules.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceRules {
+    rules: Vec<ReplaceRule>,
+}
+
+impl ReplaceRules {
+    /// Parse a list of rules in sed syntax.
+    pub fn parse<S: AsRef<str>>(specs: &[S]) -> Result<Self, TextError> {
+        let rules = specs
+            .iter()
+            .map(|s| ReplaceRule::parse(s.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Add a rule after the existing ones.
+    pub fn push(&mut self, rule: ReplaceRule) {
+        self.rules.push(rule);
+    }
+
+    /// Add the entries of a TOML lexicon file of `word = "spoken"` lines,
+    /// each matched as a whole word.
+    pub fn load_lexicon(&mut self, path: &Path) -> Result<(), TextError> {
+        let invalid =
+            |e: &dyn fmt::Display| TextError::InvalidLexicon(format!("{}: {e}", path.display()));
+        let contents = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+        let entries: BTreeMap<String, String> =
+            toml::from_str(&contents).map_err(|e| invalid(&e))?;
+        for (word, spoken) in &entries {
+            self.push(ReplaceRule::word(word, spoken).map_err(|e| invalid(&e))?);
+        }
+        Ok(())
+    }
+
+    /// Returns true if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply all rules in order.
+    pub fn apply(&self, text: &str) -> String {
+        self.rules
+            .iter()
+            .fold(text.to_string(), |acc, rule| rule.apply(&acc))
+    }
+}
+
+/// Split on a delimiter, honoring backslash escapes of the delimiter.
+fn split_unescaped(input: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&delimiter) {
+            parts.last_mut().unwrap().push(delimiter);
+            chars.next();
+        } else if c == delimiter {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+
+    parts
+}
+
+/// Convert a sed replacement string to `regex` replacement syntax.
+fn translate_replacement(input: &str) -> String {
+    let mut output = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(d) if d.is_ascii_digit() => output.push_str(&format!("${{{d}}}")),
+                Some('n') => output.push('\n'),
+                Some(other) => output.push(other),
+                None => output.push('\\'),
+            },
+            '&' => output.push_str("${0}"),
+            '$' => output.push_str("$$"),
+            other => output.push(other),
+        }
+    }
+
+    output
+}
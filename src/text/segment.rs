@@ -0,0 +1,143 @@
+//! Sentence segmentation.
+//!
+//! A sentence ends at `.`, `!`, `?`, or an ellipsis followed by whitespace,
+//! together with any closing quotes or brackets, or at a line break. Not
+//! every period ends a sentence, so a candidate end is skipped when:
+//!
+//! - it follows a title or Latin abbreviation such as `Dr.` or `e.g.`,
+//! - it follows a single capital letter other than `I`, as in the initials
+//!   `J. R. Tolkien`,
+//! - the next word starts in lower case, as in `"Help!" she cried.` or
+//!   `Wait... what?`,
+//! - it follows a day and comes before a German month, as in `Am 3. Mai`.
+//!
+//! A period between digits (`3.14`) is never followed by whitespace and so
+//! never ends a sentence. CJK full stops end a sentence without whitespace,
+//! and the Arabic question mark and the Urdu full stop end one like `?`.
+
+use super::ordinal::DE_MONTHS;
+
+/// Abbreviations that are normally followed by more of the same sentence,
+/// compared without their final period and ignoring case.
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "rev", "hon", "gen", "col", "capt",
+    "lt", "sgt", "vs", "cf", "approx", "e.g", "i.e",
+];
+
+/// Abbreviations that only continue the sentence before a number, since
+/// `no.` may also end one.
+const NUMBERED: &[&str] = &["no", "nos", "vol", "fig", "p", "pp"];
+
+/// Split text into trimmed, non-empty sentences.
+pub fn sentences(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (at, c) = chars[i];
+        if c == '\n' {
+            push(&mut sentences, &text[start..at]);
+            start = at + 1;
+            i += 1;
+            continue;
+        }
+        if !is_terminator(c) {
+            i += 1;
+            continue;
+        }
+
+        let mut end = i;
+        while end < chars.len() && is_terminator(chars[end].1) {
+            end += 1;
+        }
+        let run = end - i;
+        while end < chars.len() && is_closing(chars[end].1) {
+            end += 1;
+        }
+        let end_byte = chars.get(end).map_or(text.len(), |&(b, _)| b);
+
+        let spaced = chars.get(end).is_none_or(|&(_, c)| c.is_whitespace());
+        let full_width = matches!(chars[end - 1].1, '。' | '！' | '？');
+        let ends = (spaced || full_width)
+            && !starts_lowercase(&chars[end..])
+            && !(c == '.'
+                && run == 1
+                && (is_abbreviation(&text[..at], &chars[end..])
+                    || is_day_of_month(&text[..at], &text[end_byte..])));
+
+        if ends {
+            push(&mut sentences, &text[start..end_byte]);
+            start = end_byte;
+        }
+        i = end;
+    }
+
+    push(&mut sentences, &text[start..]);
+    sentences
+}
+
+fn push<'a>(sentences: &mut Vec<&'a str>, sentence: &'a str) {
+    let sentence = sentence.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(
+        c,
+        '.' | '!' | '?' | '…' | '。' | '！' | '？' | '\u{061F}' | '\u{06D4}'
+    )
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | '”' | '’' | '»' | ')' | ']' | '」' | '』')
+}
+
+/// Whether the next word, past spaces and opening quotes, is lower case.
+fn starts_lowercase(rest: &[(usize, char)]) -> bool {
+    next_letter(rest).is_some_and(char::is_lowercase)
+}
+
+/// First character of the next word on the same line.
+fn next_letter(rest: &[(usize, char)]) -> Option<char> {
+    rest.iter()
+        .map(|&(_, c)| c)
+        .take_while(|&c| c != '\n')
+        .find(|&c| !c.is_whitespace() && !matches!(c, '"' | '\'' | '“' | '‘' | '«' | '(' | '['))
+}
+
+/// Whether `before`, the text up to a period, ends in an abbreviation or
+/// an initial.
+fn is_abbreviation(before: &str, rest: &[(usize, char)]) -> bool {
+    let word = before
+        .rsplit(|c: char| !(c.is_alphabetic() || c == '.'))
+        .next()
+        .unwrap_or_default();
+    let mut letters = word.chars();
+    if let (Some(letter), None) = (letters.next(), letters.next())
+        && letter.is_uppercase()
+        && letter != 'I'
+    {
+        return true;
+    }
+    let is = |list: &[&str]| list.iter().any(|a| word.eq_ignore_ascii_case(a));
+    is(ABBREVIATIONS) || (is(NUMBERED) && next_letter(rest).is_some_and(|c| c.is_ascii_digit()))
+}
+
+/// Whether `before`, the text up to a period, ends in a day of the month
+/// and `after` starts with a German month, as in `3. Mai`.
+fn is_day_of_month(before: &str, after: &str) -> bool {
+    let day = before
+        .rsplit(|c: char| !c.is_ascii_digit())
+        .next()
+        .unwrap_or_default();
+    let month = after
+        .trim_start_matches([' ', '\t'])
+        .split(|c: char| !c.is_alphabetic())
+        .next()
+        .unwrap_or_default();
+    matches!(day.len(), 1 | 2) && DE_MONTHS.contains(&month)
+}
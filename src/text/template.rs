@@ -0,0 +1,125 @@
+//! `{{ name }}` placeholders filled from variables.
+//!
+//! The syntax is the variable subset of Jinja: `{{ name }}` is replaced by
+//! the variable's value and `{{ "text" }}` by the literal text, which is how
+//! braces are written. Using a variable that was not given is an error, so a
+//! typo never ends up spoken.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::TextError;
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\{\{\s*(?:([A-Za-z_][A-Za-z0-9_]*)|"([^"]*)")\s*\}\}"#).unwrap()
+});
+
+/// Named values for template placeholders.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables(BTreeMap<String, String>);
+
+impl Variables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `name=value` assignments, as given to `--var`.
+    pub fn parse<S: AsRef<str>>(specs: &[S]) -> Result<Self, TextError> {
+        let mut variables = Self::new();
+        for spec in specs {
+            let spec = spec.as_ref();
+            let (name, value) = spec.split_once('=').ok_or_else(|| {
+                TextError::InvalidVariable(format!("{spec}: expected NAME=VALUE"))
+            })?;
+            variables.set(name.trim(), value)?;
+        }
+        Ok(variables)
+    }
+
+    /// Read one set of variables per row of a CSV file whose header names
+    /// the variables.
+    pub fn from_csv(path: &Path) -> Result<Vec<Self>, TextError> {
+        let invalid =
+            |e: csv::Error| TextError::InvalidVariable(format!("{}: {e}", path.display()));
+        let mut reader = csv::Reader::from_path(path).map_err(invalid)?;
+        let headers = reader.headers().map_err(invalid)?.clone();
+
+        reader
+            .records()
+            .map(|record| {
+                let record = record.map_err(invalid)?;
+                let mut variables = Self::new();
+                for (name, value) in headers.iter().zip(record.iter()) {
+                    variables.set(name.trim(), value)?;
+                }
+                Ok(variables)
+            })
+            .collect()
+    }
+
+    /// Set a variable. Names are letters, digits, and underscores, not
+    /// starting with a digit.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), TextError> {
+        let valid = name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(TextError::InvalidVariable(format!(
+                "{name}: names are letters, digits, and underscores"
+            )));
+        }
+        self.0.insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// Variables from `other` added, replacing any of the same name.
+    pub fn merged(&self, other: &Self) -> Self {
+        let mut merged = self.clone();
+        merged.0.extend(other.0.clone());
+        merged
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Fill the placeholders in `template`.
+    pub fn render(&self, template: &str) -> Result<String, TextError> {
+        let mut rendered = String::with_capacity(template.len());
+        let mut last = 0;
+        for captures in PLACEHOLDER.captures_iter(template) {
+            let whole = captures.get(0).unwrap();
+            rendered.push_str(check_text(&template[last..whole.start()])?);
+            match (captures.get(1), captures.get(2)) {
+                (Some(name), _) => rendered.push_str(
+                    self.get(name.as_str())
+                        .ok_or_else(|| TextError::UndefinedVariable(name.as_str().to_string()))?,
+                ),
+                (None, Some(literal)) => rendered.push_str(literal.as_str()),
+                (None, None) => unreachable!("the pattern has a name or a literal"),
+            }
+            last = whole.end();
+        }
+        rendered.push_str(check_text(&template[last..])?);
+        Ok(rendered)
+    }
+}
+
+/// Text between placeholders, rejected if it opens one that never matched.
+fn check_text(text: &str) -> Result<&str, TextError> {
+    match text.find("{{") {
+        Some(at) => {
+            let snippet: String = text[at..].chars().take(20).collect();
+            Err(TextError::InvalidTemplate(format!(
+                "unclosed or malformed placeholder at '{snippet}'"
+            )))
+        }
+        None => Ok(text),
+    }
+}
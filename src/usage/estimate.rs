@@ -0,0 +1,132 @@
+//! Duration and generation time estimates from past usage.
+//!
+//! Each voice's speaking rate is its characters per second of audio over
+//! the ledger, and a model's real-time factor (RTF) is the seconds it spent
+//! generating per second of audio. A voice with no history on the model
+//! falls back to the voice on any model, then any voice on the model, then
+//! the whole ledger, then [`Chunk::estimated_duration`]. Without a timed
+//! synthesis in the ledger there is no generation estimate.
+
+use serde::Serialize;
+
+use super::UsageRecord;
+use crate::text::Chunk;
+
+/// Which ledger records a rate was measured from, most specific first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Basis {
+    /// The same voice on the same model.
+    VoiceAndModel,
+    /// The same voice on any model.
+    Voice,
+    /// Any voice on the same model.
+    Model,
+    /// Every record in the ledger.
+    Ledger,
+    /// No history; a typical narration rate.
+    Default,
+}
+
+/// Predicted cost of synthesizing some text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Estimate {
+    /// Characters of speech.
+    pub characters: usize,
+    /// Seconds of audio, pauses included.
+    pub audio_seconds: f64,
+    /// Seconds the backend will spend generating, if the model was timed.
+    pub generation_seconds: Option<f64>,
+    /// Least specific basis of any speaking rate used.
+    pub rate_basis: Basis,
+    /// Basis of the real-time factor.
+    pub rtf_basis: Basis,
+}
+
+impl Estimate {
+    /// Estimate `chunks` on `model` from ledger `records`.
+    pub fn new(records: &[UsageRecord], chunks: &[Chunk], model: &str) -> Self {
+        let mut estimate = Self {
+            characters: 0,
+            audio_seconds: 0.0,
+            generation_seconds: None,
+            rate_basis: Basis::VoiceAndModel,
+            rtf_basis: Basis::Default,
+        };
+        let mut speech_seconds = 0.0;
+
+        for chunk in chunks {
+            let Chunk::Speech { text, voice, speed } = chunk else {
+                estimate.audio_seconds += chunk.estimated_duration().as_secs_f64();
+                continue;
+            };
+            let characters = text.chars().count();
+            let seconds = match speaking_rate(records, voice.as_deref(), model) {
+                Some((rate, basis)) => {
+                    estimate.rate_basis = estimate.rate_basis.max(basis);
+                    characters as f64 / rate / f64::from(*speed).max(0.1)
+                }
+                None => {
+                    estimate.rate_basis = Basis::Default;
+                    chunk.estimated_duration().as_secs_f64()
+                }
+            };
+            estimate.characters += characters;
+            estimate.audio_seconds += seconds;
+            speech_seconds += seconds;
+        }
+
+        // Pauses are inserted on the client, so only speech is generated
+        if let Some((rtf, basis)) = real_time_factor(records, model) {
+            estimate.generation_seconds = Some(speech_seconds * rtf);
+            estimate.rtf_basis = basis;
+        }
+        estimate
+    }
+}
+
+/// Characters per second of audio for `voice` on `model`.
+fn speaking_rate(
+    records: &[UsageRecord],
+    voice: Option<&str>,
+    model: &str,
+) -> Option<(f64, Basis)> {
+    let same_voice = |r: &&UsageRecord| r.voice.as_deref() == voice;
+    [
+        (
+            Basis::VoiceAndModel,
+            filter(records, |r| same_voice(r) && r.model == model),
+        ),
+        (Basis::Voice, filter(records, same_voice)),
+        (Basis::Model, filter(records, |r| r.model == model)),
+        (Basis::Ledger, filter(records, |_| true)),
+    ]
+    .into_iter()
+    .find_map(|(basis, records)| {
+        let seconds: f64 = records.iter().map(|r| r.seconds).sum();
+        let characters: usize = records.iter().map(|r| r.characters).sum();
+        (seconds > 0.0 && characters > 0).then(|| (characters as f64 / seconds, basis))
+    })
+}
+
+/// Seconds of generation per second of audio on `model`.
+fn real_time_factor(records: &[UsageRecord], model: &str) -> Option<(f64, Basis)> {
+    let timed = |r: &&UsageRecord| r.elapsed.is_some();
+    [
+        (
+            Basis::Model,
+            filter(records, |r| timed(r) && r.model == model),
+        ),
+        (Basis::Ledger, filter(records, timed)),
+    ]
+    .into_iter()
+    .find_map(|(basis, records)| {
+        let seconds: f64 = records.iter().map(|r| r.seconds).sum();
+        let elapsed: f64 = records.iter().filter_map(|r| r.elapsed).sum();
+        (seconds > 0.0).then(|| (elapsed / seconds, basis))
+    })
+}
+
+fn filter(records: &[UsageRecord], keep: impl Fn(&&UsageRecord) -> bool) -> Vec<&UsageRecord> {
+    records.iter().filter(keep).collect()
+}
@@ -0,0 +1,321 @@
+//! The usage ledger file and reports drawn from it.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{Estimate, UsageError};
+use crate::batch::{ChunkStatus, Job};
+use crate::text::Chunk;
+
+/// Speech generated with one voice for one project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    pub model: String,
+    /// Characters of text sent for synthesis.
+    pub characters: usize,
+    /// Seconds of audio generated.
+    pub seconds: f64,
+    /// Seconds the backend spent generating it, when timed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed: Option<f64>,
+}
+
+impl UsageRecord {
+    /// Usage of one synthesis of `chunks` that produced `seconds` of audio
+    /// in `elapsed`, with a record per voice.
+    ///
+    /// Voices are credited with a share of the audio and time proportional
+    /// to their share of the text, since a mixdown has no per-voice timing.
+    pub fn for_chunks(
+        chunks: &[Chunk],
+        seconds: f64,
+        elapsed: Option<Duration>,
+        model: &str,
+        project: Option<&str>,
+    ) -> Vec<Self> {
+        let spoken: Vec<(&Option<String>, usize)> = chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                Chunk::Speech { text, voice, .. } => Some((voice, text.chars().count())),
+                Chunk::Pause(_) | Chunk::Bleep(_) => None,
+            })
+            .collect();
+        let total: usize = spoken.iter().map(|(_, n)| n).sum();
+
+        tally(
+            spoken.into_iter().map(|(voice, characters)| {
+                let share = if total == 0 {
+                    0.0
+                } else {
+                    characters as f64 / total as f64
+                };
+                let elapsed = elapsed.map(|e| e.as_secs_f64() * share);
+                (voice.clone(), characters, seconds * share, elapsed)
+            }),
+            model,
+            project,
+        )
+    }
+
+    /// Usage of the chunks of `job` at `indices` that have been synthesized,
+    /// with a record per voice. Durations are read from the chunk files.
+    pub fn for_job(
+        job: &Job,
+        indices: &[usize],
+        model: &str,
+        project: Option<&str>,
+    ) -> Result<Vec<Self>, UsageError> {
+        let mut spoken = Vec::new();
+        for entry in indices.iter().filter_map(|&i| job.chunks.get(i)) {
+            let (Chunk::Speech { text, voice, .. }, ChunkStatus::Done, Some(file)) =
+                (&entry.chunk, entry.status, &entry.output)
+            else {
+                continue;
+            };
+            let reader = hound::WavReader::open(file)?;
+            let seconds = reader.duration() as f64 / reader.spec().sample_rate as f64;
+            spoken.push((voice.clone(), text.chars().count(), seconds, None));
+        }
+        Ok(tally(spoken, model, project))
+    }
+}
+
+/// Sum (voice, characters, seconds, elapsed) by voice, in first-seen order.
+fn tally(
+    items: impl IntoIterator<Item = (Option<String>, usize, f64, Option<f64>)>,
+    model: &str,
+    project: Option<&str>,
+) -> Vec<UsageRecord> {
+    let at = Utc::now();
+    let mut records: Vec<UsageRecord> = Vec::new();
+    for (voice, characters, seconds, elapsed) in items {
+        match records.iter_mut().find(|r| r.voice == voice) {
+            Some(record) => {
+                record.characters += characters;
+                record.seconds += seconds;
+                record.elapsed = record.elapsed.zip(elapsed).map(|(a, b)| a + b);
+            }
+            None => records.push(UsageRecord {
+                at,
+                project: project.map(str::to_string),
+                voice,
+                model: model.to_string(),
+                characters,
+                seconds,
+                elapsed,
+            }),
+        }
+    }
+    records
+}
+
+/// An append-only log of [`UsageRecord`]s, one JSON object per line, at
+/// `~/.open-tts-rs/usage.jsonl`.
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    path: PathBuf,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ledger {
+    /// Open the ledger at the default location.
+    pub fn new() -> Self {
+        Self::with_path(Self::default_path())
+    }
+
+    /// Open the ledger at a custom location.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.open-tts-rs/usage.jsonl`
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir()
+            .expect("Could not find home directory")
+            .join(".open-tts-rs")
+            .join("usage.jsonl")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append records, creating the ledger if needed.
+    pub fn record(&self, records: &[UsageRecord]) -> Result<(), UsageError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        // One write, so concurrent runs do not interleave partial lines
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    /// Every record, oldest first. A missing ledger is empty.
+    pub fn load(&self) -> Result<Vec<UsageRecord>, UsageError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|source| UsageError::InvalidRecord {
+                    line: i + 1,
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Estimate `chunks` on `model` from every record.
+    pub fn estimate(&self, chunks: &[Chunk], model: &str) -> Result<Estimate, UsageError> {
+        Ok(Estimate::new(&self.load()?, chunks, model))
+    }
+
+    /// Totals per project and voice, counting records on or after `since`.
+    pub fn report(&self, since: Option<NaiveDate>) -> Result<UsageReport, UsageError> {
+        Ok(UsageReport::new(&self.load()?, since))
+    }
+}
+
+/// Usage totals for one project and voice.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageRow {
+    pub project: Option<String>,
+    pub voice: Option<String>,
+    /// Number of ledger records (syntheses or batch runs).
+    pub syntheses: usize,
+    pub characters: usize,
+    pub seconds: f64,
+}
+
+/// Usage grouped by project and voice.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    pub since: Option<NaiveDate>,
+    /// Sorted by project, then voice.
+    pub rows: Vec<UsageRow>,
+    pub total: UsageRow,
+}
+
+impl UsageReport {
+    /// Group `records` dated on or after `since` (UTC).
+    pub fn new(records: &[UsageRecord], since: Option<NaiveDate>) -> Self {
+        let mut groups: BTreeMap<(Option<&str>, Option<&str>), UsageRow> = BTreeMap::new();
+        let mut total = UsageRow::default();
+
+        let counted = records
+            .iter()
+            .filter(|r| since.is_none_or(|since| r.at.date_naive() >= since));
+        for record in counted {
+            let key = (record.project.as_deref(), record.voice.as_deref());
+            let row = groups.entry(key).or_insert_with(|| UsageRow {
+                project: record.project.clone(),
+                voice: record.voice.clone(),
+                ..UsageRow::default()
+            });
+            for row in [row, &mut total] {
+                row.syntheses += 1;
+                row.characters += record.characters;
+                row.seconds += record.seconds;
+            }
+        }
+
+        Self {
+            since,
+            rows: groups.into_values().collect(),
+            total,
+        }
+    }
+
+    /// Render as an aligned text table with a total line.
+    pub fn to_table(&self) -> String {
+        let name = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let mut lines = vec![[
+            "PROJECT".to_string(),
+            "VOICE".to_string(),
+            "SYNTHESES".to_string(),
+            "CHARACTERS".to_string(),
+            "SECONDS".to_string(),
+        ]];
+        for row in &self.rows {
+            lines.push([
+                name(&row.project),
+                name(&row.voice),
+                row.syntheses.to_string(),
+                row.characters.to_string(),
+                format!("{:.1}", row.seconds),
+            ]);
+        }
+        lines.push([
+            "TOTAL".to_string(),
+            String::new(),
+            self.total.syntheses.to_string(),
+            self.total.characters.to_string(),
+            format!("{:.1}", self.total.seconds),
+        ]);
+
+        let widths: Vec<usize> = (0..5)
+            .map(|col| {
+                lines
+                    .iter()
+                    .map(|l| l[col].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut table = String::new();
+        for line in &lines {
+            // Names left-aligned, numbers right-aligned
+            let row = format!(
+                "{:<w0$}  {:<w1$}  {:>w2$}  {:>w3$}  {:>w4$}",
+                line[0],
+                line[1],
+                line[2],
+                line[3],
+                line[4],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+                w4 = widths[4],
+            );
+            table.push_str(row.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+}
@@ -0,0 +1,246 @@
+//! Usage accounting.
+//!
+//! Every synthesis run from the CLI appends the characters sent and seconds
+//! of audio generated, per voice and project, to a local [`Ledger`], so
+//! narration work can be billed per client. `open-tts-rs usage report`
+//! summarizes it, and `open-tts-rs estimate` predicts the length and
+//! generation time of new text from it.
+
+mod estimate;
+mod ledger;
+
+pub use estimate::{Basis, Estimate};
+pub use ledger::{Ledger, UsageRecord, UsageReport, UsageRow};
+
+use thiserror::Error;
+
+/// Errors that can occur when reading or writing the usage ledger.
+#[derive(Error, Debug)]
+pub enum UsageError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Audio error: {0}")]
+    AudioError(#[from] hound::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid usage record on line {line}: {source}")]
+    InvalidRecord {
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+    use crate::batch::{ChunkStatus, Job};
+    use crate::text::Chunk;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn speech(text: &str, voice: Option<&str>) -> Chunk {
+        Chunk::Speech {
+            text: text.to_string(),
+            voice: voice.map(str::to_string),
+            speed: 1.0,
+        }
+    }
+
+    fn record(day: u32, project: &str, voice: &str, characters: usize) -> UsageRecord {
+        UsageRecord {
+            at: Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap(),
+            project: Some(project.to_string()),
+            voice: Some(voice.to_string()),
+            model: "OpenVoice V2".to_string(),
+            characters,
+            seconds: characters as f64 / 10.0,
+            elapsed: None,
+        }
+    }
+
+    // ===========================================
+    // UsageRecord tests
+    // ===========================================
+
+    #[test]
+    fn test_for_chunks_splits_audio_by_text_share() {
+        let chunks = vec![
+            speech("Hello", Some("amy")),
+            Chunk::Pause(Duration::from_millis(500)),
+            speech("Hi there, Amy", Some("bob")),
+            speech("Bye!!", Some("amy")),
+        ];
+
+        let records = UsageRecord::for_chunks(
+            &chunks,
+            20.0,
+            Some(Duration::from_secs(10)),
+            "OpenF5-TTS",
+            Some("acme"),
+        );
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].voice.as_deref(), Some("amy"));
+        assert_eq!(records[0].characters, 10);
+        assert!((records[0].seconds - 8.7).abs() < 0.1);
+        assert_eq!(records[1].characters, 13);
+        assert!((records[0].seconds + records[1].seconds - 20.0).abs() < 1e-9);
+        assert!((records[0].elapsed.unwrap() - records[0].seconds / 2.0).abs() < 1e-9);
+        assert!(records.iter().all(|r| r.project.as_deref() == Some("acme")));
+    }
+
+    #[test]
+    fn test_for_job_counts_only_finished_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let chunk_file = temp_dir.path().join("chunk-0.wav");
+        let wav = AudioBuffer::new(vec![0.1; 1500], 1000, 1)
+            .to_wav_bytes()
+            .unwrap();
+        std::fs::write(&chunk_file, wav).unwrap();
+
+        let mut job = Job::new(
+            "job-1",
+            vec![speech("One.", Some("amy")), speech("Two.", Some("amy"))],
+            temp_dir.path().join("out.wav"),
+        );
+        job.chunks[0].status = ChunkStatus::Done;
+        job.chunks[0].output = Some(chunk_file);
+        job.chunks[1].status = ChunkStatus::Skipped;
+
+        let records = UsageRecord::for_job(&job, &[0, 1], "OpenVoice V2", None).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].characters, 4);
+        assert!((records[0].seconds - 1.5).abs() < 1e-9);
+
+        assert!(
+            UsageRecord::for_job(&job, &[1], "OpenVoice V2", None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    // ===========================================
+    // Ledger tests
+    // ===========================================
+
+    #[test]
+    fn test_ledger_appends_and_loads() {
+        let temp_dir = TempDir::new().unwrap();
+        let ledger = Ledger::with_path(temp_dir.path().join("nested").join("usage.jsonl"));
+        assert!(ledger.load().unwrap().is_empty());
+
+        ledger.record(&[record(1, "acme", "amy", 100)]).unwrap();
+        ledger
+            .record(&[record(2, "acme", "bob", 50), record(3, "zeta", "amy", 10)])
+            .unwrap();
+
+        let loaded = ledger.load().unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[2], record(3, "zeta", "amy", 10));
+    }
+
+    #[test]
+    fn test_ledger_reports_invalid_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.jsonl");
+        let ledger = Ledger::with_path(&path);
+        ledger.record(&[record(1, "acme", "amy", 100)]).unwrap();
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str("not json\n");
+        std::fs::write(&path, content).unwrap();
+
+        let err = ledger.load().unwrap_err();
+        assert!(matches!(err, UsageError::InvalidRecord { line: 2, .. }));
+    }
+
+    // ===========================================
+    // UsageReport tests
+    // ===========================================
+
+    #[test]
+    fn test_report_groups_by_project_and_voice_since_date() {
+        let records = vec![
+            record(1, "zeta", "amy", 999),
+            record(5, "zeta", "amy", 10),
+            record(6, "acme", "bob", 20),
+            record(7, "zeta", "amy", 30),
+        ];
+        let since = NaiveDate::from_ymd_opt(2024, 1, 5);
+
+        let report = UsageReport::new(&records, since);
+        assert_eq!(report.rows.len(), 2);
+        assert_eq!(report.rows[0].project.as_deref(), Some("acme"));
+        assert_eq!(report.rows[1].syntheses, 2);
+        assert_eq!(report.rows[1].characters, 40);
+        assert_eq!(report.total.characters, 60);
+        assert!((report.total.seconds - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_table() {
+        let report = UsageReport::new(&[record(1, "acme", "amy", 1234)], None);
+        let table = report.to_table();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("PROJECT  VOICE"));
+        assert!(lines[1].starts_with("acme"));
+        assert!(lines[1].ends_with("1234    123.4"));
+        assert!(lines[2].starts_with("TOTAL"));
+    }
+
+    // ===========================================
+    // Estimate tests
+    // ===========================================
+
+    fn timed(voice: &str, model: &str, characters: usize, seconds: f64) -> UsageRecord {
+        UsageRecord {
+            model: model.to_string(),
+            seconds,
+            elapsed: Some(seconds / 4.0),
+            ..record(1, "acme", voice, characters)
+        }
+    }
+
+    #[test]
+    fn test_estimate_uses_voice_rate_and_model_rtf() {
+        let records = vec![
+            timed("amy", "OpenVoice V2", 200, 10.0),
+            record(2, "acme", "amy", 1000),
+            timed("bob", "OpenF5-TTS", 100, 10.0),
+        ];
+        let chunks = vec![
+            speech(&"a".repeat(40), Some("amy")),
+            Chunk::Pause(Duration::from_secs(1)),
+        ];
+
+        let estimate = Estimate::new(&records, &chunks, "OpenVoice V2");
+        assert_eq!(estimate.characters, 40);
+        // amy speaks 1200 characters in 110 seconds on OpenVoice V2
+        assert!((estimate.audio_seconds - (40.0 * 110.0 / 1200.0 + 1.0)).abs() < 1e-9);
+        assert_eq!(estimate.rate_basis, Basis::VoiceAndModel);
+        // Only the timed record counts toward the RTF, and pauses are free
+        let speech_seconds = estimate.audio_seconds - 1.0;
+        assert!((estimate.generation_seconds.unwrap() - speech_seconds / 4.0).abs() < 1e-9);
+        assert_eq!(estimate.rtf_basis, Basis::Model);
+    }
+
+    #[test]
+    fn test_estimate_falls_back_without_history() {
+        let chunks = vec![speech("one two three four five", Some("amy"))];
+        let estimate = Estimate::new(&[], &chunks, "OpenVoice V2");
+        assert_eq!(estimate.rate_basis, Basis::Default);
+        assert!((estimate.audio_seconds - 2.0).abs() < 1e-9);
+        assert_eq!(estimate.generation_seconds, None);
+
+        let records = vec![timed("bob", "OpenF5-TTS", 100, 10.0)];
+        let estimate = Estimate::new(&records, &chunks, "OpenVoice V2");
+        assert_eq!(estimate.rate_basis, Basis::Ledger);
+        assert_eq!(estimate.rtf_basis, Basis::Ledger);
+        assert!((estimate.audio_seconds - 2.3).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,387 @@
+//! Backups of the whole voice library.
+//!
+//! A backup is a tar archive, zstd-compressed when its name ends in `.zst`,
+//! holding a `backup.json` manifest, the voice store under `voices/`
+//! (metadata, reference clips, and the encryption key check), the config
+//! file with its replace rules and glossary as `config.toml`, and the
+//! lexicon, glossary, and profanity files the config lists under
+//! `lexicon/`, `glossary/`, and `profanity/`. Reference clips kept outside
+//! the store are copied in, so a backup is complete on a machine without
+//! the original recordings.
+//!
+//! The manifest lists every file with its SHA-256, and a restore verifies
+//! them all before anything is written.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::{VoiceError, VoiceManager};
+use crate::config::Config;
+use crate::manifest::sha256_hex;
+
+/// Name of the manifest inside a backup.
+pub const BACKUP_MANIFEST: &str = "backup.json";
+
+/// Archive path of the config file.
+const CONFIG_FILE: &str = "config.toml";
+
+/// Archive directory of the voice store.
+const VOICES_DIR: &str = "voices/";
+
+/// zstd frame magic, little-endian `0xFD2FB528`.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// One file in a backup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// Path inside the archive.
+    pub path: String,
+    /// Hex-encoded SHA-256 of the contents.
+    pub sha256: String,
+}
+
+/// Contents of `backup.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub created_at: String,
+    /// Version of open-tts-rs that wrote the backup.
+    pub version: String,
+    pub files: Vec<BackupFile>,
+}
+
+impl BackupManifest {
+    /// Names of the voices in the backup.
+    pub fn voices(&self) -> Vec<&str> {
+        voice_names(self.files.iter().map(|f| &f.path))
+    }
+}
+
+/// What a restore wrote and what it left alone.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RestoreReport {
+    /// Files written, by archive path.
+    pub restored: Vec<String>,
+    /// Files already present, which were kept.
+    pub skipped: Vec<String>,
+}
+
+impl RestoreReport {
+    /// Names of the voices whose metadata was restored.
+    pub fn voices(&self) -> Vec<&str> {
+        voice_names(&self.restored)
+    }
+}
+
+/// Archive contents by path.
+type Files = BTreeMap<String, Vec<u8>>;
+
+/// Write the voice store of `manager`, the config file at `config`, if it
+/// exists, and the word lists it names to `output`.
+pub fn backup(
+    manager: &VoiceManager,
+    config: &Path,
+    output: &Path,
+) -> Result<BackupManifest, VoiceError> {
+    let mut files = store_files(manager)?;
+    if config.exists() {
+        files.extend(config_files(config)?);
+    }
+
+    let manifest = BackupManifest {
+        created_at: Utc::now().to_rfc3339(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        files: files
+            .iter()
+            .map(|(path, data)| BackupFile {
+                path: path.clone(),
+                sha256: sha256_hex(data),
+            })
+            .collect(),
+    };
+    write_archive(output, &manifest, &files)?;
+    Ok(manifest)
+}
+
+/// The files of `manager`'s voice store, plus reference clips kept
+/// outside it.
+fn store_files(manager: &VoiceManager) -> Result<Files, VoiceError> {
+    let dir = manager.voices_dir();
+    let mut files = Files::new();
+    if dir.exists() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if path.is_file() && is_store_file(name) {
+                files.insert(format!("{VOICES_DIR}{name}"), std::fs::read(&path)?);
+            }
+        }
+    }
+
+    // Metadata that cannot be read (a locked store) always has its clip in
+    // the store, since encrypted stores keep a copy of every reference
+    for metadata in manager.list_local()? {
+        let clip = format!("{VOICES_DIR}{}.wav", metadata.name);
+        if let Some(source) = metadata.audio_path
+            && !files.contains_key(&clip)
+            && source.exists()
+        {
+            files.insert(clip, std::fs::read(source)?);
+        }
+    }
+    Ok(files)
+}
+
+/// The config file at `config` and the word lists it names.
+fn config_files(config: &Path) -> Result<Files, VoiceError> {
+    let mut files = Files::new();
+    files.insert(CONFIG_FILE.to_string(), std::fs::read(config)?);
+    for (kind, paths) in load_config(config)?.word_lists() {
+        for path in paths.iter().filter(|path| path.is_file()) {
+            if let Some(name) = path.file_name().and_then(OsStr::to_str) {
+                files.insert(format!("{kind}/{name}"), std::fs::read(path)?);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Write `manifest` and `files` to a tar archive at `output`, compressed
+/// when its name ends in `.zst`.
+fn write_archive(
+    output: &Path,
+    manifest: &BackupManifest,
+    files: &Files,
+) -> Result<(), VoiceError> {
+    let mut tar = tar::Builder::new(Vec::new());
+    let mut append = |path: &str, data: &[u8]| -> std::io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o600);
+        header.set_cksum();
+        tar.append_data(&mut header, path, data)
+    };
+    append(BACKUP_MANIFEST, &serde_json::to_vec_pretty(manifest)?)?;
+    for (path, data) in files {
+        append(path, data)?;
+    }
+    let archive = tar.into_inner()?;
+
+    let compressed = output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zst"));
+    if compressed {
+        std::fs::write(output, zstd::encode_all(archive.as_slice(), 0)?)?;
+    } else {
+        std::fs::write(output, archive)?;
+    }
+    Ok(())
+}
+
+/// Restore a backup into the voice store of `manager` and the config file
+/// at `config`.
+///
+/// Word lists are written where the config in place afterwards names them,
+/// or next to it when it does not. Existing voices, config, and word lists
+/// are kept. A backup whose store was
+/// encrypted with another key than the local store's is refused, since
+/// its files could not be read alongside the local ones.
+pub fn restore_backup(
+    manager: &VoiceManager,
+    config: &Path,
+    archive: &Path,
+) -> Result<(BackupManifest, RestoreReport), VoiceError> {
+    let data = std::fs::read(archive)?;
+    let data = if data.starts_with(&ZSTD_MAGIC) {
+        zstd::decode_all(data.as_slice())?
+    } else {
+        data
+    };
+    let (manifest, mut files) = unpack(&data)?;
+
+    let dir = manager.voices_dir();
+    check_key(&dir, &files)?;
+    std::fs::create_dir_all(&dir)?;
+    let mut report = RestoreReport::default();
+    for file in &manifest.files {
+        let target = match file.path.strip_prefix(VOICES_DIR) {
+            Some(name) => dir.join(name),
+            None if file.path == CONFIG_FILE => config.to_path_buf(),
+            // Word lists wait for the config that says where they go
+            None => continue,
+        };
+        restore_file(&target, &file.path, &mut files, &mut report)?;
+    }
+    restore_word_lists(&manifest, config, &mut files, &mut report)?;
+    relink_clips(manager, &report)?;
+    Ok((manifest, report))
+}
+
+/// Refuse a backup whose store was encrypted with another key than the
+/// store in `dir`.
+fn check_key(dir: &Path, files: &Files) -> Result<(), VoiceError> {
+    let key_check = format!("{VOICES_DIR}.encryption");
+    if let Some(keys) = files.get(&key_check)
+        && let Ok(local) = std::fs::read(dir.join(".encryption"))
+        && local != *keys
+    {
+        return Err(VoiceError::Backup(
+            "the backup was encrypted with another passphrase than this voice store".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Write the backup's word lists where the config at `config` lists them,
+/// or next to it.
+fn restore_word_lists(
+    manifest: &BackupManifest,
+    config: &Path,
+    files: &mut Files,
+    report: &mut RestoreReport,
+) -> Result<(), VoiceError> {
+    let lists = load_config(config)?;
+    for file in &manifest.files {
+        let Some((kind, name)) = word_list(&file.path) else {
+            continue;
+        };
+        let target = lists
+            .word_lists()
+            .into_iter()
+            .filter(|(listed, _)| *listed == kind)
+            .flat_map(|(_, paths)| paths)
+            .find(|path| path.file_name() == Some(OsStr::new(name)))
+            .cloned()
+            .unwrap_or_else(|| config.with_file_name(name));
+        restore_file(&target, &file.path, files, report)?;
+    }
+    Ok(())
+}
+
+/// Point restored voices at their clips in this store, wherever the
+/// references were on the machine the backup came from.
+fn relink_clips(manager: &VoiceManager, report: &RestoreReport) -> Result<(), VoiceError> {
+    let dir = manager.voices_dir();
+    for name in report.voices() {
+        let clip = dir.join(format!("{name}.wav"));
+        if !clip.exists() {
+            continue;
+        }
+        if let Ok(mut metadata) = manager.load_metadata(name)
+            && metadata.audio_path.as_ref() != Some(&clip)
+        {
+            metadata.audio_path = Some(clip);
+            manager.save_metadata(&metadata)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write an archive file to `target` unless something is already there.
+fn restore_file(
+    target: &Path,
+    path: &str,
+    files: &mut Files,
+    report: &mut RestoreReport,
+) -> Result<(), VoiceError> {
+    if target.exists() {
+        report.skipped.push(path.to_string());
+        return Ok(());
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(target, files.remove(path).unwrap_or_default())?;
+    report.restored.push(path.to_string());
+    Ok(())
+}
+
+fn load_config(path: &Path) -> Result<Config, VoiceError> {
+    Config::load(path).map_err(|e| VoiceError::Backup(format!("{}: {e}", path.display())))
+}
+
+/// Read every file of a tar archive and check it against the manifest.
+fn unpack(archive: &[u8]) -> Result<(BackupManifest, Files), VoiceError> {
+    let mut files = Files::new();
+    let mut tar = tar::Archive::new(archive);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()?
+            .to_string_lossy()
+            .trim_start_matches("./")
+            .to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        files.insert(path, data);
+    }
+
+    let manifest: BackupManifest = serde_json::from_slice(
+        &files
+            .remove(BACKUP_MANIFEST)
+            .ok_or_else(|| VoiceError::Backup(format!("missing {BACKUP_MANIFEST}")))?,
+    )?;
+
+    for file in &manifest.files {
+        let safe = file.path == CONFIG_FILE
+            || word_list(&file.path).is_some()
+            || file
+                .path
+                .strip_prefix(VOICES_DIR)
+                .is_some_and(is_store_file);
+        if !safe {
+            return Err(VoiceError::Backup(format!("unexpected file {}", file.path)));
+        }
+        let data = files
+            .get(&file.path)
+            .ok_or_else(|| VoiceError::Backup(format!("missing {}", file.path)))?;
+        if sha256_hex(data) != file.sha256.to_lowercase() {
+            return Err(VoiceError::Backup(format!(
+                "checksum mismatch for {}",
+                file.path
+            )));
+        }
+    }
+    Ok((manifest, files))
+}
+
+fn voice_names<'a>(paths: impl IntoIterator<Item = &'a String>) -> Vec<&'a str> {
+    paths
+        .into_iter()
+        .filter_map(|path| path.strip_prefix(VOICES_DIR)?.strip_suffix(".json"))
+        .collect()
+}
+
+/// Voice metadata, reference clips, and the encryption key check, with
+/// no path components.
+fn is_store_file(name: &str) -> bool {
+    let safe = crate::paths::check_local_name(name).is_ok() && !name.contains("..");
+    safe && (name == ".encryption" || name.ends_with(".json") || name.ends_with(".wav"))
+}
+
+/// The kind and file name of a word list's archive path, such as
+/// `lexicon/names.toml`.
+fn word_list(path: &str) -> Option<(&str, &str)> {
+    let (kind, name) = path.split_once('/')?;
+    let known = Config::default()
+        .word_lists()
+        .iter()
+        .any(|(listed, _)| *listed == kind);
+    let safe = crate::paths::check_local_name(name).is_ok() && !name.contains("..");
+    (known && safe).then_some((kind, name))
+}
+
+/// Default file name for a backup made today, as in
+/// `voices-2024-06-01.tar.zst`.
+pub fn default_backup_path() -> PathBuf {
+    PathBuf::from(format!("voices-{}.tar.zst", Utc::now().format("%Y-%m-%d")))
+}
@@ -0,0 +1,51 @@
+//! Consent attestations for cloned voices.
+
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::VoiceError;
+use crate::manifest::sha256_hex;
+
+/// Record that the speaker of a cloned voice agreed to its use.
+///
+/// The signed attestation itself stays wherever the user keeps it; its
+/// checksum is stored so an audit can confirm the file was not altered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Consent {
+    /// Signed attestation document.
+    pub file: PathBuf,
+    /// Hex-encoded SHA-256 of the attestation at the time it was recorded.
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// License or usage terms granted by the speaker.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    pub recorded_at: String,
+}
+
+impl Consent {
+    /// Build a consent record from an attestation file.
+    pub fn from_file(
+        file: &Path,
+        speaker: Option<String>,
+        license: Option<String>,
+    ) -> Result<Self, VoiceError> {
+        let data = std::fs::read(file)?;
+
+        Ok(Self {
+            file: std::path::absolute(file)?,
+            sha256: sha256_hex(&data),
+            speaker,
+            license,
+            recorded_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// Returns true if the attestation file still matches its checksum.
+    pub fn verify(&self) -> bool {
+        std::fs::read(&self.file).is_ok_and(|data| sha256_hex(&data) == self.sha256)
+    }
+}
@@ -0,0 +1,72 @@
+//! Encryption of voice data at rest.
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+use super::VoiceError;
+
+/// Prefix identifying an encrypted file.
+const MAGIC: &[u8] = b"OTTSENC1";
+const NONCE_LEN: usize = 24;
+
+/// Length of the key-derivation salt.
+pub const SALT_LEN: usize = 16;
+
+/// XChaCha20-Poly1305 cipher keyed from a passphrase with Argon2id.
+///
+/// Encrypted files are `MAGIC || nonce || ciphertext`, so plaintext and
+/// encrypted files can live side by side while a store is migrated.
+#[derive(Clone)]
+pub struct VoiceCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl VoiceCipher {
+    /// Derive a cipher from a passphrase and the store's salt.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, VoiceError> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| VoiceError::Crypto(e.to_string()))?;
+
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(&key.into()),
+        })
+    }
+
+    /// Generate a random salt for a new store.
+    pub fn generate_salt() -> [u8; SALT_LEN] {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    }
+
+    /// Returns true if the data was produced by [`VoiceCipher::encrypt`].
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    /// Encrypt data with a fresh random nonce.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, VoiceError> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|e| VoiceError::Crypto(e.to_string()))?;
+
+        Ok([MAGIC, nonce.as_slice(), &ciphertext].concat())
+    }
+
+    /// Decrypt data produced by [`VoiceCipher::encrypt`].
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, VoiceError> {
+        if !Self::is_encrypted(data) || data.len() < MAGIC.len() + NONCE_LEN {
+            return Err(VoiceError::Crypto("not an encrypted file".to_string()));
+        }
+
+        let (nonce, ciphertext) = data[MAGIC.len()..].split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| VoiceError::Crypto("wrong passphrase or corrupted file".to_string()))
+    }
+}
@@ -0,0 +1,93 @@
+//! Finding voices cloned from the same recording.
+//!
+//! A reference clip is a duplicate of another voice's when their bytes
+//! hash the same, or, for a re-encoded or re-trimmed copy, when their
+//! lengths nearly match and their spectrograms are almost identical.
+//! Reusing the existing voice saves a second clone on the backend.
+
+use std::path::Path;
+
+use super::{VoiceError, VoiceManager};
+use crate::audio::{AudioBuffer, AudioDiff};
+use crate::manifest::sha256_hex;
+
+/// Spectral similarity at which two clips count as the same recording.
+pub const NEAR_DUPLICATE_SIMILARITY: f64 = 0.98;
+
+/// Clips whose lengths differ by more seconds than this are never
+/// near-identical, which spares decoding most of the library.
+const MAX_DURATION_DELTA: f64 = 0.5;
+
+/// A saved voice whose reference matches a new clip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateReference {
+    pub voice: String,
+    /// The clips are byte for byte the same.
+    pub identical: bool,
+    /// Spectral similarity from 0.0 to 1.0; 1.0 when identical.
+    pub similarity: f64,
+}
+
+/// Find the saved voice, other than `except`, whose reference is the same
+/// recording as the clip at `reference`.
+///
+/// Voices whose reference audio is gone or unreadable are skipped. An
+/// identical clip wins over near-identical ones, which are ranked by
+/// similarity.
+pub fn find_duplicate(
+    manager: &VoiceManager,
+    reference: &Path,
+    except: Option<&str>,
+) -> Result<Option<DuplicateReference>, VoiceError> {
+    let clip = std::fs::read(reference)?;
+    let digest = sha256_hex(&clip);
+    // Only WAV clips can be compared by ear; others match by hash alone
+    let audio = AudioBuffer::from_wav_bytes(&clip).ok();
+
+    let mut voices = manager.list_local()?;
+    voices.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut best: Option<DuplicateReference> = None;
+    for metadata in voices {
+        if Some(metadata.name.as_str()) == except {
+            continue;
+        }
+        let Some(data) = metadata
+            .audio_path
+            .as_deref()
+            .and_then(|path| manager.read_audio(path).ok())
+        else {
+            continue;
+        };
+
+        if sha256_hex(&data) == digest {
+            return Ok(Some(DuplicateReference {
+                voice: metadata.name,
+                identical: true,
+                similarity: 1.0,
+            }));
+        }
+
+        let Some(audio) = &audio else {
+            continue;
+        };
+        let Ok(other) = AudioBuffer::from_wav_bytes(&data) else {
+            continue;
+        };
+        let delta = other.duration().as_secs_f64() - audio.duration().as_secs_f64();
+        if delta.abs() > MAX_DURATION_DELTA {
+            continue;
+        }
+        let similarity = AudioDiff::compare(audio, &other).similarity;
+        if similarity >= NEAR_DUPLICATE_SIMILARITY
+            && best.as_ref().is_none_or(|b| similarity > b.similarity)
+        {
+            best = Some(DuplicateReference {
+                voice: metadata.name,
+                identical: false,
+                similarity,
+            });
+        }
+    }
+    Ok(best)
+}
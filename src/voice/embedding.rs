@@ -0,0 +1,106 @@
+//! Speaker embedding export.
+
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+use super::VoiceError;
+
+/// Where a speaker embedding came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingSource {
+    /// The model's own speaker embedding, fetched from the backend.
+    Backend,
+    /// A spectral voiceprint computed locally from the reference audio.
+    Voiceprint,
+}
+
+/// A speaker embedding as a flat array of floats with its tensor shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerEmbedding {
+    pub name: String,
+    pub source: EmbeddingSource,
+    pub shape: Vec<usize>,
+    pub values: Vec<f32>,
+}
+
+impl SpeakerEmbedding {
+    /// Decode base64-encoded little-endian float32 values.
+    pub fn from_base64(
+        name: impl Into<String>,
+        shape: Vec<usize>,
+        data: &str,
+    ) -> Result<Self, VoiceError> {
+        let bytes = STANDARD
+            .decode(data)
+            .map_err(|e| VoiceError::InvalidEmbedding(e.to_string()))?;
+        if bytes.len() % 4 != 0 {
+            return Err(VoiceError::InvalidEmbedding(format!(
+                "{} bytes is not a whole number of float32 values",
+                bytes.len()
+            )));
+        }
+
+        let values: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        if shape.iter().product::<usize>() != values.len() {
+            return Err(VoiceError::InvalidEmbedding(format!(
+                "shape {shape:?} does not match {} values",
+                values.len()
+            )));
+        }
+
+        Ok(Self {
+            name: name.into(),
+            source: EmbeddingSource::Backend,
+            shape,
+            values,
+        })
+    }
+
+    /// Encode as a NumPy `.npy` file (format 1.0, little-endian float32).
+    pub fn to_npy(&self) -> Vec<u8> {
+        let shape = match self.shape.as_slice() {
+            [single] => format!("({single},)"),
+            dims => format!(
+                "({})",
+                dims.iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {shape}, }}");
+        // Magic (6) + version (2) + length (2) + header must align to 64 bytes
+        let padding = (64 - (11 + header.len()) % 64) % 64;
+        header.push_str(&" ".repeat(padding));
+        header.push('\n');
+
+        let mut out = Vec::with_capacity(10 + header.len() + self.values.len() * 4);
+        out.extend_from_slice(b"\x93NUMPY\x01\x00");
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        for value in &self.values {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out
+    }
+
+    /// Write the embedding, choosing `.npy` or `.json` from the extension.
+    pub fn write(&self, path: &Path) -> Result<(), VoiceError> {
+        let data = match path.extension().and_then(|e| e.to_str()) {
+            Some("npy") => self.to_npy(),
+            Some("json") => serde_json::to_vec_pretty(self)?,
+            _ => {
+                return Err(VoiceError::UnsupportedFormat(path.display().to_string()));
+            }
+        };
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
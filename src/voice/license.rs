@@ -0,0 +1,294 @@
+//! Licenses behind each voice, and what they oblige a user of it to do.
+//!
+//! A voice involves two licensed works: the model that speaks it and the
+//! reference clip it was cloned from. The speaker's own terms are part of
+//! their [`Consent`](super::Consent). The report gathers all three for the
+//! voices a manifest used, so a commercial user can show what they rely on.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use super::{VoiceError, VoiceManager, VoiceMetadata};
+use crate::backend::Model;
+
+/// Licenses recorded for a voice.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoiceLicense {
+    /// License of the model the voice was extracted with, e.g. `MIT`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// License of the reference clip, e.g. `CC-BY-4.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+/// License of the model a voice was saved by, from the model name a
+/// backend reported, such as `openvoice_v2`. Composite names like
+/// `OpenF5-TTS + OpenVoice V2` combine their parts.
+pub fn model_license(model: &str) -> Option<String> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let mut licenses: Vec<&str> = Vec::new();
+    for part in model.split('+') {
+        let known = Model::value_variants()
+            .iter()
+            .find(|m| normalize(m.name()) == normalize(part))?;
+        if !licenses.contains(&known.license()) {
+            licenses.push(known.license());
+        }
+    }
+    licenses.sort_unstable();
+    Some(licenses.join(" AND "))
+}
+
+/// One voice's licenses and obligations.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LicenseEntry {
+    pub voice: String,
+    pub model: String,
+    pub model_license: Option<String>,
+    pub reference_license: Option<String>,
+    /// Terms the speaker granted with their consent.
+    pub speaker_terms: Option<String>,
+    pub consent: bool,
+    pub synthetic: bool,
+    /// What using the voice requires.
+    pub obligations: Vec<String>,
+    /// Gaps in the record that should be closed before relying on it.
+    pub warnings: Vec<String>,
+}
+
+impl LicenseEntry {
+    fn new(metadata: &VoiceMetadata) -> Self {
+        let recorded = metadata.license.clone().unwrap_or_default();
+        let model_license = recorded.model.or_else(|| model_license(&metadata.model));
+        let reference_license = recorded.reference;
+        let speaker_terms = metadata.consent.as_ref().and_then(|c| c.license.clone());
+
+        let mut obligations = Vec::new();
+        let mut warnings = Vec::new();
+        match &model_license {
+            Some(license) => obligations.extend(model_obligations(license)),
+            None => warnings.push(format!("model license unknown for '{}'", metadata.model)),
+        }
+        match &reference_license {
+            Some(license) => {
+                let (duties, caveats) = reference_obligations(license);
+                obligations.extend(duties);
+                warnings.extend(caveats);
+            }
+            None if !metadata.synthetic => {
+                warnings.push("reference clip license not recorded".to_string())
+            }
+            None => {}
+        }
+        if let Some(terms) = &speaker_terms {
+            obligations.push(format!("follow the speaker's terms: {terms}"));
+        }
+        if metadata.consent.is_none() && !metadata.synthetic {
+            warnings.push("no speaker consent recorded".to_string());
+        }
+
+        Self {
+            voice: metadata.name.clone(),
+            model: metadata.model.clone(),
+            model_license,
+            reference_license,
+            speaker_terms,
+            consent: metadata.consent.is_some(),
+            synthetic: metadata.synthetic,
+            obligations,
+            warnings,
+        }
+    }
+
+    /// The voice's row of [`LicenseReport::to_table`].
+    fn row(&self) -> [String; 4] {
+        let unknown = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let reference = match (&self.reference_license, self.synthetic) {
+            (None, true) => "synthetic".to_string(),
+            (license, _) => unknown(license),
+        };
+        let consent = match (self.consent, self.synthetic) {
+            (true, _) => "yes",
+            (false, true) => "not needed",
+            (false, false) => "no",
+        };
+        [
+            self.voice.clone(),
+            unknown(&self.model_license),
+            reference,
+            consent.to_string(),
+        ]
+    }
+}
+
+/// Licenses of a set of voices.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LicenseReport {
+    pub voices: Vec<LicenseEntry>,
+    /// Voices named but not in the local store.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
+
+impl LicenseReport {
+    /// Report on the voices named, or every saved voice if `names` is
+    /// `None`.
+    pub fn build(manager: &VoiceManager, names: Option<&[String]>) -> Result<Self, VoiceError> {
+        let mut report = Self::default();
+        match names {
+            Some(names) => {
+                for name in names {
+                    match manager.load_metadata(name) {
+                        Ok(metadata) => report.voices.push(LicenseEntry::new(&metadata)),
+                        Err(VoiceError::NotFound(_)) => report.missing.push(name.clone()),
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+            None => {
+                for metadata in manager.list_local()? {
+                    report.voices.push(LicenseEntry::new(&metadata));
+                }
+            }
+        }
+        report.voices.sort_by(|a, b| a.voice.cmp(&b.voice));
+        Ok(report)
+    }
+
+    /// Every obligation across the voices, each listed once.
+    pub fn obligations(&self) -> Vec<String> {
+        let mut all: Vec<String> = Vec::new();
+        for obligation in self.voices.iter().flat_map(|v| &v.obligations) {
+            if !all.contains(obligation) {
+                all.push(obligation.clone());
+            }
+        }
+        all
+    }
+
+    /// Whether any voice has a gap in its record, or is missing.
+    pub fn has_warnings(&self) -> bool {
+        !self.missing.is_empty() || self.voices.iter().any(|v| !v.warnings.is_empty())
+    }
+
+    /// A per-voice table followed by the combined obligations.
+    pub fn to_table(&self) -> String {
+        let header = ["VOICE", "MODEL LICENSE", "REFERENCE", "CONSENT"].map(String::from);
+        let rows: Vec<[String; 4]> = std::iter::once(header)
+            .chain(self.voices.iter().map(LicenseEntry::row))
+            .collect();
+        let mut table = align(&rows);
+        table.push_str(&list_section("Obligations", &self.obligations()));
+        table.push_str(&list_section("Warnings", &self.warnings()));
+        table
+    }
+
+    /// Each voice's warnings and each missing voice, by voice name.
+    fn warnings(&self) -> Vec<String> {
+        self.voices
+            .iter()
+            .flat_map(|v| v.warnings.iter().map(move |w| format!("{}: {w}", v.voice)))
+            .chain(
+                self.missing
+                    .iter()
+                    .map(|name| format!("{name}: not in the local voice store")),
+            )
+            .collect()
+    }
+}
+
+/// Lay `rows` out in columns padded to their widest cell.
+fn align(rows: &[[String; 4]]) -> String {
+    let widths: Vec<usize> = (0..4)
+        .map(|col| {
+            rows.iter()
+                .map(|l| l[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut table = String::new();
+    for line in rows {
+        let row = format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+            line[0],
+            line[1],
+            line[2],
+            line[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+        );
+        table.push_str(row.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// `items` as a bulleted list under `title`, or nothing if there are none.
+fn list_section(title: &str, items: &[String]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    let mut section = format!("\n{title}:\n");
+    for item in items {
+        section.push_str(&format!("  - {item}\n"));
+    }
+    section
+}
+
+/// What redistributing a model under `license` requires. The licenses of
+/// the models this crate drives place no conditions on generated audio.
+fn model_obligations(license: &str) -> Vec<String> {
+    license
+        .split(" AND ")
+        .map(|part| match part {
+            "MIT" => "MIT (model): keep its copyright and license notice with any copy of the \
+                      model"
+                .to_string(),
+            "Apache-2.0" => "Apache-2.0 (model): keep its license and NOTICE with any copy of \
+                             the model, and mark changes to it"
+                .to_string(),
+            other => format!("{other} (model): follow its terms"),
+        })
+        .collect()
+}
+
+/// Obligations and caveats of a reference clip's license, for Creative
+/// Commons licenses by their elements and for anything else as stated.
+fn reference_obligations(license: &str) -> (Vec<String>, Vec<String>) {
+    let upper = license.to_ascii_uppercase();
+    let mut duties = Vec::new();
+    let mut caveats = Vec::new();
+    if upper.starts_with("CC0") || upper == "PUBLIC DOMAIN" {
+        return (duties, caveats);
+    }
+    if !upper.starts_with("CC-BY") {
+        duties.push(format!("{license} (reference): follow its terms"));
+        return (duties, caveats);
+    }
+
+    duties.push(format!(
+        "{license} (reference): credit the reference recording's author"
+    ));
+    if upper.contains("-NC") {
+        duties.push(format!("{license} (reference): non-commercial use only"));
+    }
+    if upper.contains("-SA") {
+        duties.push(format!(
+            "{license} (reference): share adaptations under the same license"
+        ));
+    }
+    if upper.contains("-ND") {
+        caveats.push(format!(
+            "{license} forbids adaptations, and a cloned voice may be one"
+        ));
+    }
+    (duties, caveats)
+}
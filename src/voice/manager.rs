@@ -1,10 +1,16 @@
 //! Voice manager for local storage operations.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Duration, Utc};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::crypto::{SALT_LEN, VoiceCipher};
+use super::{Consent, VoiceLicense};
+
 /// Errors that can occur during voice management.
 #[derive(Error, Debug)]
 pub enum VoiceError {
@@ -14,13 +20,42 @@ pub enum VoiceError {
     #[error("Invalid voice name: {0}")]
     InvalidName(String),
 
+    #[error("A voice named '{0}' already exists")]
+    AlreadyExists(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid speaker embedding: {0}")]
+    InvalidEmbedding(String),
+
+    #[error("Unsupported embedding format (use .npy or .json): {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Voice store is encrypted; a passphrase is required")]
+    Locked,
+
+    #[error("Encryption error: {0}")]
+    Crypto(String),
+
+    #[error("Remote store error: {0}")]
+    Remote(String),
+
+    #[error("Invalid voice pack: {0}")]
+    Pack(String),
+
+    #[error("Invalid voice library backup: {0}")]
+    Backup(String),
+    #[error("Failed to fetch reference: {0}")]
+    Fetch(String),
 }
 
+/// Days a deleted voice stays in the trash unless configured otherwise.
+pub const DEFAULT_TRASH_DAYS: u32 = 30;
+
 /// Metadata for a saved voice.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VoiceMetadata {
@@ -31,11 +66,63 @@ pub struct VoiceMetadata {
     /// Original audio path (for Gradio backends that need re-upload)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub audio_path: Option<PathBuf>,
+    /// Language the voice was recorded in, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Speaker consent attestation, if recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent: Option<Consent>,
+    /// Synthesizing with this voice requires an explicit unlock
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// Projects this voice may be used in; empty allows any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_uses: Vec<String>,
+    /// Drawn at random rather than cloned, so no speaker has to consent
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub synthetic: bool,
+    /// Licenses of the model and reference clip, if recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<VoiceLicense>,
 }
 
 /// Manages local voice storage.
+///
+/// When unlocked with a passphrase, metadata and stored reference audio
+/// are encrypted on write and decrypted transparently on read.
+#[derive(Clone)]
 pub struct VoiceManager {
     voices_dir: PathBuf,
+    cipher: Option<VoiceCipher>,
+}
+
+/// A reference audio file readable by backends.
+///
+/// Encrypted clips are decrypted to a private temporary file that is
+/// removed when this value is dropped.
+#[derive(Debug)]
+pub enum ReferenceAudio {
+    Plain(PathBuf),
+    Decrypted(PathBuf),
+    /// A processed copy, removed like a decrypted one.
+    Temporary(PathBuf),
+}
+
+impl ReferenceAudio {
+    /// Path of the readable audio file.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Plain(path) | Self::Decrypted(path) | Self::Temporary(path) => path,
+        }
+    }
+}
+
+impl Drop for ReferenceAudio {
+    fn drop(&mut self) {
+        if let Self::Decrypted(path) | Self::Temporary(path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
 }
 
 impl VoiceManager {
@@ -46,12 +133,83 @@ impl VoiceManager {
             .join(".open-tts-rs")
             .join("voices");
 
-        Self { voices_dir }
+        Self::with_dir(voices_dir)
     }
 
     /// Create a new VoiceManager with a custom directory.
     pub fn with_dir(voices_dir: PathBuf) -> Self {
-        Self { voices_dir }
+        Self {
+            voices_dir,
+            cipher: None,
+        }
+    }
+
+    /// Returns true if writes are being encrypted.
+    pub fn is_unlocked(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Returns true if the store has been set up for encryption.
+    pub fn is_encrypted(&self) -> bool {
+        self.keyfile_path().exists()
+    }
+
+    /// Enable encryption with a passphrase.
+    ///
+    /// The first unlock creates the store's salt and key check; later
+    /// unlocks fail with [`VoiceError::Crypto`] on a wrong passphrase.
+    pub fn unlock(mut self, passphrase: &str) -> Result<Self, VoiceError> {
+        let path = self.keyfile_path();
+
+        if path.exists() {
+            let data = std::fs::read(&path)?;
+            if data.len() < SALT_LEN {
+                return Err(VoiceError::Crypto("corrupted key file".to_string()));
+            }
+            let (salt, check) = data.split_at(SALT_LEN);
+            let cipher = VoiceCipher::from_passphrase(passphrase, salt)?;
+            cipher.decrypt(check)?;
+            self.cipher = Some(cipher);
+        } else {
+            let salt = VoiceCipher::generate_salt();
+            let cipher = VoiceCipher::from_passphrase(passphrase, &salt)?;
+            let check = cipher.encrypt(KEY_CHECK)?;
+            std::fs::create_dir_all(&self.voices_dir)?;
+            std::fs::write(&path, [salt.as_slice(), &check].concat())?;
+            self.cipher = Some(cipher);
+        }
+
+        Ok(self)
+    }
+
+    /// Encrypt any plaintext metadata and stored audio left in the store.
+    ///
+    /// Returns the number of files rewritten.
+    pub fn encrypt_all(&self) -> Result<usize, VoiceError> {
+        let Some(cipher) = &self.cipher else {
+            return Err(VoiceError::Locked);
+        };
+        if !self.voices_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        for entry in std::fs::read_dir(&self.voices_dir)? {
+            let path = entry?.path();
+            if !path
+                .extension()
+                .is_some_and(|ext| ext == "json" || ext == "wav")
+            {
+                continue;
+            }
+            let data = std::fs::read(&path)?;
+            if !VoiceCipher::is_encrypted(&data) {
+                std::fs::write(&path, cipher.encrypt(&data)?)?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
     }
 
     /// Get the voices directory path.
@@ -72,7 +230,18 @@ impl VoiceManager {
             ));
         }
 
-        Ok(())
+        crate::paths::check_local_name(name).map_err(VoiceError::InvalidName)
+    }
+
+    /// Validate the name of a voice about to be created, which must also
+    /// be usable on other platforms so the library can be synced or
+    /// restored there. Existing voices keep working under their names.
+    fn validate_new_name(&self, name: &str) -> Result<(), VoiceError> {
+        Self::validate_name(name)?;
+        if self.metadata_path(name).exists() {
+            return Ok(());
+        }
+        crate::paths::check_file_name(name).map_err(VoiceError::InvalidName)
     }
 
     /// Get the metadata file path for a voice.
@@ -80,16 +249,107 @@ impl VoiceManager {
         self.voices_dir.join(format!("{}.json", name))
     }
 
+    /// Get the stored reference audio path for a voice.
+    fn audio_path(&self, name: &str) -> PathBuf {
+        self.voices_dir.join(format!("{name}.wav"))
+    }
+
+    /// Where deleted voices are kept until purged.
+    fn trash_dir(&self) -> PathBuf {
+        self.voices_dir.join(".trash")
+    }
+
+    /// Metadata and reference audio paths of a trashed voice.
+    fn trashed_paths(&self, name: &str) -> (PathBuf, PathBuf) {
+        let trash = self.trash_dir();
+        (
+            trash.join(format!("{name}.json")),
+            trash.join(format!("{name}.wav")),
+        )
+    }
+
+    fn keyfile_path(&self) -> PathBuf {
+        self.voices_dir.join(".encryption")
+    }
+
+    /// Read a file, decrypting it if needed.
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, VoiceError> {
+        let data = std::fs::read(path)?;
+        if !VoiceCipher::is_encrypted(&data) {
+            return Ok(data);
+        }
+        self.cipher
+            .as_ref()
+            .ok_or(VoiceError::Locked)?
+            .decrypt(&data)
+    }
+
+    /// Write a file, encrypting it when the store is unlocked.
+    fn write_file(&self, path: &Path, data: &[u8]) -> Result<(), VoiceError> {
+        match &self.cipher {
+            Some(cipher) => std::fs::write(path, cipher.encrypt(data)?)?,
+            None => std::fs::write(path, data)?,
+        }
+        Ok(())
+    }
+
+    /// Copy a reference clip into the store, encrypted if unlocked.
+    pub fn store_audio(&self, name: &str, source: &Path) -> Result<PathBuf, VoiceError> {
+        self.save_audio(name, &std::fs::read(source)?)
+    }
+
+    /// Write reference audio into the store, encrypted if unlocked.
+    pub fn save_audio(&self, name: &str, data: &[u8]) -> Result<PathBuf, VoiceError> {
+        self.validate_new_name(name)?;
+        std::fs::create_dir_all(&self.voices_dir)?;
+
+        let path = self.audio_path(name);
+        self.write_file(&path, data)?;
+        Ok(path)
+    }
+
+    /// Read reference audio, decrypting it if needed.
+    pub fn read_audio(&self, path: &Path) -> Result<Vec<u8>, VoiceError> {
+        self.read_file(path)
+    }
+
+    /// Make reference audio available as a plain file for backends.
+    ///
+    /// Unreadable paths are passed through for the backend to report, since
+    /// not every backend needs the reference clip.
+    pub fn reference_audio(&self, path: &Path) -> Result<ReferenceAudio, VoiceError> {
+        let data = match std::fs::read(path) {
+            Ok(data) if VoiceCipher::is_encrypted(&data) => data,
+            _ => return Ok(ReferenceAudio::Plain(path.to_path_buf())),
+        };
+
+        let audio = self
+            .cipher
+            .as_ref()
+            .ok_or(VoiceError::Locked)?
+            .decrypt(&data)?;
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("voice");
+        let temp = crate::scratch::scratch_file(&format!("{stem}.wav"));
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        std::io::Write::write_all(&mut options.open(&temp)?, &audio)?;
+
+        Ok(ReferenceAudio::Decrypted(temp))
+    }
+
     /// Save voice metadata to local storage.
     pub fn save_metadata(&self, metadata: &VoiceMetadata) -> Result<(), VoiceError> {
-        Self::validate_name(&metadata.name)?;
+        self.validate_new_name(&metadata.name)?;
 
         // Ensure directory exists
         std::fs::create_dir_all(&self.voices_dir)?;
 
         let path = self.metadata_path(&metadata.name);
         let json = serde_json::to_string_pretty(metadata)?;
-        std::fs::write(path, json)?;
+        self.write_file(&path, json.as_bytes())?;
 
         Ok(())
     }
@@ -104,8 +364,7 @@ impl VoiceManager {
             return Err(VoiceError::NotFound(name.to_string()));
         }
 
-        let json = std::fs::read_to_string(path)?;
-        let metadata = serde_json::from_str(&json)?;
+        let metadata = serde_json::from_slice(&self.read_file(&path)?)?;
 
         Ok(metadata)
     }
@@ -122,9 +381,122 @@ impl VoiceManager {
 
         std::fs::remove_file(path)?;
 
+        let audio = self.audio_path(name);
+        if audio.exists() {
+            std::fs::remove_file(audio)?;
+        }
+
         Ok(())
     }
 
+    /// Move a voice's metadata and reference audio to the trash, from
+    /// where [`restore`](Self::restore) brings it back.
+    ///
+    /// A reference kept outside the store is copied in, so the voice can
+    /// be restored after the original recording is gone. A voice trashed
+    /// earlier under the same name is replaced.
+    pub fn trash(&self, name: &str) -> Result<(), VoiceError> {
+        Self::validate_name(name)?;
+
+        let path = self.metadata_path(name);
+        if !path.exists() {
+            return Err(VoiceError::NotFound(name.to_string()));
+        }
+
+        let trash = self.trash_dir();
+        std::fs::create_dir_all(&trash)?;
+        let (trashed, trashed_audio) = self.trashed_paths(name);
+        let audio = self.audio_path(name);
+        let external = self
+            .load_metadata(name)
+            .ok()
+            .and_then(|m| m.audio_path)
+            .filter(|source| source.exists());
+        if audio.exists() {
+            std::fs::rename(audio, &trashed_audio)?;
+        } else if let Some(source) = external {
+            self.write_file(&trashed_audio, &std::fs::read(source)?)?;
+        } else if trashed_audio.exists() {
+            std::fs::remove_file(&trashed_audio)?;
+        }
+
+        std::fs::rename(path, &trashed)?;
+        // The trash is purged by when voices were deleted, not last edited
+        std::fs::File::options()
+            .write(true)
+            .open(&trashed)?
+            .set_modified(SystemTime::now())?;
+        Ok(())
+    }
+
+    /// Move a trashed voice back into the store.
+    ///
+    /// A reference copied into the trash is restored as the voice's stored
+    /// reference audio.
+    pub fn restore(&self, name: &str) -> Result<VoiceMetadata, VoiceError> {
+        Self::validate_name(name)?;
+
+        let (trashed, trashed_audio) = self.trashed_paths(name);
+        if !trashed.exists() {
+            return Err(VoiceError::NotFound(name.to_string()));
+        }
+        if self.metadata_path(name).exists() {
+            return Err(VoiceError::AlreadyExists(name.to_string()));
+        }
+
+        std::fs::rename(&trashed, self.metadata_path(name))?;
+        let mut metadata = self.load_metadata(name)?;
+        if trashed_audio.exists() {
+            let audio = self.audio_path(name);
+            std::fs::rename(&trashed_audio, &audio)?;
+            if metadata.audio_path.as_ref() != Some(&audio) {
+                metadata.audio_path = Some(audio);
+                self.save_metadata(&metadata)?;
+            }
+        }
+        Ok(metadata)
+    }
+
+    /// Trashed voice names with when they were deleted, oldest first.
+    pub fn list_trash(&self) -> Result<Vec<(String, DateTime<Utc>)>, VoiceError> {
+        let trash = self.trash_dir();
+        if !trash.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut voices = Vec::new();
+        for entry in std::fs::read_dir(&trash)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(name) = path.file_stem().and_then(|s| s.to_str())
+            {
+                let deleted = std::fs::metadata(&path)?.modified()?;
+                voices.push((name.to_string(), DateTime::<Utc>::from(deleted)));
+            }
+        }
+        voices.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(voices)
+    }
+
+    /// Permanently delete voices trashed more than `retention` ago,
+    /// returning their names.
+    pub fn purge_trash(&self, retention: Duration) -> Result<Vec<String>, VoiceError> {
+        let cutoff = Utc::now() - retention;
+        let mut purged = Vec::new();
+        for (name, deleted) in self.list_trash()? {
+            if deleted > cutoff {
+                continue;
+            }
+            let (trashed, trashed_audio) = self.trashed_paths(&name);
+            std::fs::remove_file(trashed)?;
+            if trashed_audio.exists() {
+                std::fs::remove_file(trashed_audio)?;
+            }
+            purged.push(name);
+        }
+        Ok(purged)
+    }
+
     /// List all locally stored voice metadata.
     pub fn list_local(&self) -> Result<Vec<VoiceMetadata>, VoiceError> {
         if !self.voices_dir.exists() {
@@ -138,8 +510,10 @@ impl VoiceManager {
             let path = entry.path();
 
             if path.extension().is_some_and(|ext| ext == "json") {
-                let json = std::fs::read_to_string(&path)?;
-                if let Ok(metadata) = serde_json::from_str::<VoiceMetadata>(&json) {
+                let Ok(data) = self.read_file(&path) else {
+                    continue;
+                };
+                if let Ok(metadata) = serde_json::from_slice::<VoiceMetadata>(&data) {
                     voices.push(metadata);
                 }
             }
@@ -149,6 +523,9 @@ impl VoiceManager {
     }
 }
 
+/// Known plaintext stored encrypted to validate a passphrase.
+const KEY_CHECK: &[u8] = b"open-tts-rs voice store";
+
 impl Default for VoiceManager {
     fn default() -> Self {
         Self::new()
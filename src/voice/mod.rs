@@ -3,14 +3,37 @@
 //! This module handles saving, loading, and managing voice references
 //! that are synchronized with the TTS backend servers.
 
+mod backup;
+mod consent;
+mod crypto;
+mod dedup;
+mod embedding;
+mod license;
 mod manager;
+mod pack;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "ytdlp")]
+pub mod ytdlp;
 
-pub use manager::{VoiceError, VoiceManager, VoiceMetadata};
+pub use backup::{
+    BACKUP_MANIFEST, BackupFile, BackupManifest, RestoreReport, backup, default_backup_path,
+    restore_backup,
+};
+pub use consent::Consent;
+pub use crypto::VoiceCipher;
+pub use dedup::{DuplicateReference, NEAR_DUPLICATE_SIMILARITY, find_duplicate};
+pub use embedding::{EmbeddingSource, SpeakerEmbedding};
+pub use license::{LicenseEntry, LicenseReport, VoiceLicense, model_license};
+pub use manager::{DEFAULT_TRASH_DAYS, ReferenceAudio, VoiceError, VoiceManager, VoiceMetadata};
+pub use pack::{
+    PACK_MANIFEST, PackManifest, PackVoice, VoicePack, expected_checksum, fetch, verify_checksum,
+};
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use tempfile::TempDir;
 
     // ===========================================
@@ -54,6 +77,12 @@ mod tests {
             model: "openvoice_v2".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
 
         manager.save_metadata(&metadata).unwrap();
@@ -83,6 +112,12 @@ mod tests {
             model: "openvoice_v2".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
 
         manager.save_metadata(&metadata).unwrap();
@@ -103,6 +138,12 @@ mod tests {
             model: "openvoice_v2".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
 
         let metadata2 = VoiceMetadata {
@@ -111,6 +152,12 @@ mod tests {
             model: "openf5_tts".to_string(),
             created_at: "2024-01-02T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
 
         manager.save_metadata(&metadata1).unwrap();
@@ -134,9 +181,750 @@ mod tests {
             model: "openvoice_v2".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
         };
 
         let result = manager.save_metadata(&metadata);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_new_voices_need_portable_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+
+        for name in ["con", "LPT1", "aux.old", "what?"] {
+            let err = manager.save_audio(name, b"RIFF").unwrap_err();
+            assert!(matches!(err, VoiceError::InvalidName(_)), "{name}");
+        }
+        assert!(manager.save_audio("narrator", b"RIFF").is_ok());
+
+        // A voice made before the check, on a platform that allowed its
+        // name, can still be updated there
+        if !cfg!(windows) {
+            std::fs::write(temp_dir.path().join("con.json"), b"{}").unwrap();
+            assert!(manager.save_audio("con", b"RIFF").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_voice_metadata_without_language() {
+        // Metadata saved before languages were recorded still loads
+        let json = r#"{
+            "name": "old",
+            "transcript": "Hello",
+            "model": "openvoice_v2",
+            "created_at": "2024-01-01T00:00:00Z"
+        }"#;
+
+        let metadata: VoiceMetadata = serde_json::from_str(json).unwrap();
+        assert_eq!(metadata.language, None);
+        assert!(
+            !serde_json::to_string(&metadata)
+                .unwrap()
+                .contains("language")
+        );
+    }
+
+    // ===========================================
+    // SpeakerEmbedding tests
+    // ===========================================
+
+    fn embedding(shape: Vec<usize>, values: Vec<f32>) -> SpeakerEmbedding {
+        SpeakerEmbedding {
+            name: "amy".to_string(),
+            source: EmbeddingSource::Backend,
+            shape,
+            values,
+        }
+    }
+
+    #[test]
+    fn test_embedding_from_base64() {
+        // 1.0f32 and -2.0f32, little-endian
+        let parsed = SpeakerEmbedding::from_base64("amy", vec![1, 2], "AACAPwAAAMA=").unwrap();
+        assert_eq!(parsed, embedding(vec![1, 2], vec![1.0, -2.0]));
+    }
+
+    #[test]
+    fn test_embedding_from_base64_shape_mismatch() {
+        let result = SpeakerEmbedding::from_base64("amy", vec![1, 3], "AACAPwAAAMA=");
+        assert!(matches!(result, Err(VoiceError::InvalidEmbedding(_))));
+    }
+
+    #[test]
+    fn test_embedding_to_npy() {
+        let npy = embedding(vec![1, 2], vec![1.0, -2.0]).to_npy();
+
+        assert!(npy.starts_with(b"\x93NUMPY\x01\x00"));
+        let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+
+        let header = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+        assert!(header.contains("'descr': '<f4'"));
+        assert!(header.contains("'shape': (1, 2)"));
+        assert!(header.ends_with('\n'));
+        assert_eq!(
+            &npy[10 + header_len..],
+            [1.0f32.to_le_bytes(), (-2.0f32).to_le_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn test_embedding_to_npy_one_dimensional() {
+        let npy = embedding(vec![3], vec![0.0; 3]).to_npy();
+        let header = String::from_utf8_lossy(&npy[10..]);
+        assert!(header.contains("'shape': (3,)"));
+    }
+
+    #[test]
+    fn test_embedding_write_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let emb = embedding(vec![2], vec![0.5, 0.25]);
+
+        let json_path = temp_dir.path().join("amy.json");
+        emb.write(&json_path).unwrap();
+        let loaded: SpeakerEmbedding =
+            serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(loaded, emb);
+
+        let result = emb.write(&temp_dir.path().join("amy.wav"));
+        assert!(matches!(result, Err(VoiceError::UnsupportedFormat(_))));
+    }
+
+    // ===========================================
+    // Trash tests
+    // ===========================================
+
+    #[test]
+    fn test_trash_and_restore_voice() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        let stored = manager.save_audio("amy", b"RIFF amy").unwrap();
+        manager
+            .save_metadata(&VoiceMetadata {
+                audio_path: Some(stored.clone()),
+                ..sample_metadata("amy")
+            })
+            .unwrap();
+
+        manager.trash("amy").unwrap();
+        assert!(manager.load_metadata("amy").is_err());
+        assert!(!stored.exists());
+        let trash = manager.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].0, "amy");
+        assert!(matches!(
+            manager.trash("amy").unwrap_err(),
+            VoiceError::NotFound(_)
+        ));
+
+        let restored = manager.restore("amy").unwrap();
+        assert_eq!(restored.audio_path, Some(stored.clone()));
+        assert_eq!(std::fs::read(&stored).unwrap(), b"RIFF amy");
+        assert!(manager.list_trash().unwrap().is_empty());
+        assert!(matches!(
+            manager.restore("amy").unwrap_err(),
+            VoiceError::NotFound(_)
+        ));
+    }
+
+    #[test]
+    fn test_trash_keeps_external_reference() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        let source = temp_dir.path().join("recording.wav");
+        std::fs::write(&source, b"RIFF recording").unwrap();
+        manager
+            .save_metadata(&VoiceMetadata {
+                audio_path: Some(source.clone()),
+                ..sample_metadata("amy")
+            })
+            .unwrap();
+
+        // The original recording is lost after deleting the voice
+        manager.trash("amy").unwrap();
+        std::fs::remove_file(&source).unwrap();
+
+        manager.save_metadata(&sample_metadata("amy")).unwrap();
+        assert!(matches!(
+            manager.restore("amy").unwrap_err(),
+            VoiceError::AlreadyExists(_)
+        ));
+        manager.delete_local("amy").unwrap();
+
+        let restored = manager.restore("amy").unwrap();
+        let stored = restored.audio_path.unwrap();
+        assert_eq!(stored, temp_dir.path().join("voices").join("amy.wav"));
+        assert_eq!(std::fs::read(stored).unwrap(), b"RIFF recording");
+        assert_eq!(
+            manager.load_metadata("amy").unwrap().transcript,
+            "Secret transcript"
+        );
+    }
+
+    #[test]
+    fn test_purge_trash_by_age() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        for name in ["old", "new"] {
+            manager.save_audio(name, b"RIFF").unwrap();
+            manager.save_metadata(&sample_metadata(name)).unwrap();
+            manager.trash(name).unwrap();
+        }
+        let trashed = temp_dir.path().join(".trash").join("old.json");
+        let long_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(40 * 86400);
+        std::fs::File::options()
+            .write(true)
+            .open(&trashed)
+            .unwrap()
+            .set_modified(long_ago)
+            .unwrap();
+
+        let purged = manager.purge_trash(chrono::Duration::days(30)).unwrap();
+        assert_eq!(purged, vec!["old"]);
+        assert!(!temp_dir.path().join(".trash").join("old.wav").exists());
+        let trash = manager.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].0, "new");
+        assert!(
+            manager
+                .purge_trash(chrono::Duration::days(30))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    // ===========================================
+    // Consent tests
+    // ===========================================
+
+    #[test]
+    fn test_consent_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("signed.txt");
+        std::fs::write(&file, "I agree.").unwrap();
+
+        let consent =
+            Consent::from_file(&file, Some("Amy".to_string()), Some("CC-BY".to_string())).unwrap();
+        assert_eq!(consent.file, file);
+        assert_eq!(consent.sha256, crate::manifest::sha256_hex(b"I agree."));
+        assert_eq!(consent.speaker.as_deref(), Some("Amy"));
+        assert!(consent.verify());
+
+        std::fs::write(&file, "I do not agree.").unwrap();
+        assert!(!consent.verify());
+    }
+
+    #[test]
+    fn test_consent_from_missing_file() {
+        let result = Consent::from_file(Path::new("/nonexistent/signed.txt"), None, None);
+        assert!(matches!(result, Err(VoiceError::IoError(_))));
+    }
+
+    // ===========================================
+    // Encryption tests
+    // ===========================================
+
+    fn sample_metadata(name: &str) -> VoiceMetadata {
+        VoiceMetadata {
+            name: name.to_string(),
+            transcript: "Secret transcript".to_string(),
+            model: "openvoice_v2".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license: None,
+        }
+    }
+
+    #[test]
+    fn test_cipher_roundtrip_and_wrong_key() {
+        let salt = VoiceCipher::generate_salt();
+        let cipher = VoiceCipher::from_passphrase("correct horse", &salt).unwrap();
+        let encrypted = cipher.encrypt(b"hello").unwrap();
+
+        assert!(VoiceCipher::is_encrypted(&encrypted));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"hello");
+
+        let wrong = VoiceCipher::from_passphrase("battery staple", &salt).unwrap();
+        assert!(matches!(
+            wrong.decrypt(&encrypted),
+            Err(VoiceError::Crypto(_))
+        ));
+    }
+
+    #[test]
+    fn test_encrypted_metadata_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        let manager = VoiceManager::with_dir(dir.clone()).unlock("pw").unwrap();
+        manager.save_metadata(&sample_metadata("amy")).unwrap();
+
+        let raw = std::fs::read(dir.join("amy.json")).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("Secret"));
+        assert_eq!(
+            manager.load_metadata("amy").unwrap(),
+            sample_metadata("amy")
+        );
+        assert_eq!(manager.list_local().unwrap().len(), 1);
+
+        // Without the passphrase the store stays closed
+        let locked = VoiceManager::with_dir(dir.clone());
+        assert!(locked.is_encrypted());
+        assert!(matches!(
+            locked.load_metadata("amy"),
+            Err(VoiceError::Locked)
+        ));
+        assert!(matches!(
+            VoiceManager::with_dir(dir).unlock("wrong"),
+            Err(VoiceError::Crypto(_))
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_all_migrates_plaintext() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().to_path_buf();
+        VoiceManager::with_dir(dir.clone())
+            .save_metadata(&sample_metadata("amy"))
+            .unwrap();
+
+        let manager = VoiceManager::with_dir(dir.clone()).unlock("pw").unwrap();
+        assert_eq!(manager.encrypt_all().unwrap(), 1);
+        assert_eq!(manager.encrypt_all().unwrap(), 0);
+        assert!(VoiceCipher::is_encrypted(
+            &std::fs::read(dir.join("amy.json")).unwrap()
+        ));
+        assert_eq!(
+            manager.load_metadata("amy").unwrap(),
+            sample_metadata("amy")
+        );
+    }
+
+    #[test]
+    fn test_encrypted_reference_audio() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("ref.wav");
+        std::fs::write(&source, b"RIFF audio").unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().join("voices"))
+            .unlock("pw")
+            .unwrap();
+
+        let stored = manager.store_audio("amy", &source).unwrap();
+        assert_ne!(std::fs::read(&stored).unwrap(), b"RIFF audio");
+        assert_eq!(manager.read_audio(&stored).unwrap(), b"RIFF audio");
+
+        let reference = manager.reference_audio(&stored).unwrap();
+        let temp = reference.path().to_path_buf();
+        assert_eq!(std::fs::read(&temp).unwrap(), b"RIFF audio");
+        drop(reference);
+        assert!(!temp.exists());
+
+        // Plain files are used in place
+        let plain = manager.reference_audio(&source).unwrap();
+        assert_eq!(plain.path(), source);
+    }
+
+    // ===========================================
+    // Voice pack tests
+    // ===========================================
+
+    fn tar_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn pack_json(clip_sha: &str) -> Vec<u8> {
+        format!(
+            r#"{{"name": "narrators", "version": "1.0", "voices": [
+                {{"name": "amy", "transcript": "Hello.", "file": "clips/amy.wav",
+                  "sha256": "{clip_sha}", "language": "EN"}}
+            ]}}"#
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_voice_pack_from_archive() {
+        let clip = b"RIFF amy";
+        let archive = tar_archive(&[
+            (
+                PACK_MANIFEST,
+                &pack_json(&crate::manifest::sha256_hex(clip)),
+            ),
+            ("clips/amy.wav", clip),
+        ]);
+
+        let pack = VoicePack::from_archive(&archive).unwrap();
+        assert_eq!(pack.manifest.name, "narrators");
+        assert_eq!(pack.manifest.voices[0].language.as_deref(), Some("EN"));
+        assert_eq!(pack.clip("amy"), Some(clip.as_slice()));
+    }
+
+    #[test]
+    fn test_voice_pack_rejects_tampered_clip() {
+        let archive = tar_archive(&[
+            (
+                PACK_MANIFEST,
+                &pack_json(&crate::manifest::sha256_hex(b"original")),
+            ),
+            ("clips/amy.wav", b"tampered"),
+        ]);
+
+        assert!(matches!(
+            VoicePack::from_archive(&archive),
+            Err(VoiceError::Pack(msg)) if msg.contains("checksum")
+        ));
+    }
+
+    #[test]
+    fn test_voice_pack_missing_manifest_or_clip() {
+        let no_manifest = tar_archive(&[("clips/amy.wav", b"RIFF")]);
+        assert!(matches!(
+            VoicePack::from_archive(&no_manifest),
+            Err(VoiceError::Pack(_))
+        ));
+
+        let no_clip = tar_archive(&[(PACK_MANIFEST, &pack_json("00"))]);
+        assert!(matches!(
+            VoicePack::from_archive(&no_clip),
+            Err(VoiceError::Pack(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let digest = crate::manifest::sha256_hex(b"pack");
+        assert!(verify_checksum(b"pack", &digest).is_ok());
+        assert!(
+            verify_checksum(
+                b"pack",
+                &format!("{}  narrators.ottsvpack\n", digest.to_uppercase())
+            )
+            .is_ok()
+        );
+        assert!(verify_checksum(b"other", &digest).is_err());
+    }
+
+    #[test]
+    fn test_expected_checksum_sidecar_only_for_local_packs() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack = dir.path().join("narrators.ottsvpack");
+        let pack = pack.to_str().unwrap();
+        assert!(expected_checksum(pack, None).is_err());
+        std::fs::write(format!("{pack}.sha256"), "abc123  narrators.ottsvpack\n").unwrap();
+        assert!(expected_checksum(pack, None).unwrap().starts_with("abc123"));
+        assert_eq!(expected_checksum(pack, Some("def")).unwrap(), "def");
+
+        let url = "https://example.com/narrators.ottsvpack";
+        assert!(matches!(
+            expected_checksum(url, None),
+            Err(VoiceError::Pack(msg)) if msg.contains("publisher")
+        ));
+        assert_eq!(expected_checksum(url, Some("def")).unwrap(), "def");
+    }
+
+    // ===========================================
+    // Library backup tests
+    // ===========================================
+
+    /// A store with one voice whose reference lives outside it, and a config.
+    fn library(root: &Path) -> (VoiceManager, PathBuf) {
+        let manager = VoiceManager::with_dir(root.join("voices"));
+        let source = root.join("recording.wav");
+        std::fs::write(&source, b"RIFF recording").unwrap();
+        manager
+            .save_metadata(&VoiceMetadata {
+                audio_path: Some(source),
+                ..sample_metadata("amy")
+            })
+            .unwrap();
+        let config = root.join("config.toml");
+        std::fs::write(&config, "[glossary]\nGIF = \"jif\"\n").unwrap();
+        (manager, config)
+    }
+
+    #[test]
+    fn test_backup_round_trip() {
+        let source = TempDir::new().unwrap();
+        let (manager, config) = library(source.path());
+        let archive = source.path().join("library.tar.zst");
+        let manifest = backup(&manager, &config, &archive).unwrap();
+        assert_eq!(manifest.voices(), vec!["amy"]);
+        assert_eq!(manifest.files.len(), 3);
+        assert!(
+            std::fs::read(&archive)
+                .unwrap()
+                .starts_with(&[0x28, 0xb5, 0x2f, 0xfd])
+        );
+
+        let target = TempDir::new().unwrap();
+        let restored_manager = VoiceManager::with_dir(target.path().join("voices"));
+        let restored_config = target.path().join("config.toml");
+        let (_, report) = restore_backup(&restored_manager, &restored_config, &archive).unwrap();
+        assert_eq!(report.voices(), vec!["amy"]);
+        assert!(report.skipped.is_empty());
+
+        let amy = restored_manager.load_metadata("amy").unwrap();
+        let clip = target.path().join("voices").join("amy.wav");
+        assert_eq!(amy.audio_path, Some(clip.clone()));
+        assert_eq!(std::fs::read(clip).unwrap(), b"RIFF recording");
+        assert_eq!(
+            std::fs::read(restored_config).unwrap(),
+            std::fs::read(config).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_backup_restores_lexicon_named_by_config() {
+        let source = TempDir::new().unwrap();
+        let (manager, config) = library(source.path());
+        std::fs::write(&config, "lexicon_files = [\"lists/names.toml\"]\n").unwrap();
+        std::fs::create_dir(source.path().join("lists")).unwrap();
+        std::fs::write(
+            source.path().join("lists/names.toml"),
+            "Tolkien = \"tol keen\"\n",
+        )
+        .unwrap();
+        let archive = source.path().join("library.tar");
+        let manifest = backup(&manager, &config, &archive).unwrap();
+        assert!(
+            manifest
+                .files
+                .iter()
+                .any(|f| f.path == "lexicon/names.toml")
+        );
+
+        let target = TempDir::new().unwrap();
+        let restored_manager = VoiceManager::with_dir(target.path().join("voices"));
+        let restored_config = target.path().join("config.toml");
+        let (_, report) = restore_backup(&restored_manager, &restored_config, &archive).unwrap();
+        assert!(report.restored.contains(&"lexicon/names.toml".to_string()));
+        assert_eq!(
+            std::fs::read_to_string(target.path().join("lists/names.toml")).unwrap(),
+            "Tolkien = \"tol keen\"\n"
+        );
+    }
+
+    #[test]
+    fn test_restore_backup_keeps_existing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let (manager, config) = library(temp_dir.path());
+        let archive = temp_dir.path().join("library.tar");
+        backup(&manager, &config, &archive).unwrap();
+        std::fs::write(&config, "trash_days = 7\n").unwrap();
+
+        let (_, report) = restore_backup(&manager, &config, &archive).unwrap();
+        assert!(report.voices().is_empty());
+        assert_eq!(report.skipped, vec!["config.toml", "voices/amy.json"]);
+        assert_eq!(report.restored, vec!["voices/amy.wav"]);
+        assert_eq!(
+            std::fs::read_to_string(&config).unwrap(),
+            "trash_days = 7\n"
+        );
+    }
+
+    #[test]
+    fn test_restore_backup_rejects_tampered_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let (manager, config) = library(temp_dir.path());
+        let archive = temp_dir.path().join("library.tar");
+        backup(&manager, &config, &archive).unwrap();
+
+        // Same length, so the tar headers stay valid
+        let data = std::fs::read(&archive).unwrap();
+        let at = data
+            .windows(14)
+            .position(|w| w == b"RIFF recording")
+            .unwrap();
+        let mut tampered = data.clone();
+        tampered[at + 5..at + 14].copy_from_slice(b"tampered!");
+        std::fs::write(&archive, tampered).unwrap();
+
+        let target = TempDir::new().unwrap();
+        let restored_manager = VoiceManager::with_dir(target.path().join("voices"));
+        let err = restore_backup(
+            &restored_manager,
+            &target.path().join("config.toml"),
+            &archive,
+        )
+        .unwrap_err();
+        assert!(matches!(err, VoiceError::Backup(msg) if msg.contains("voices/amy.wav")));
+        assert!(!target.path().join("voices").exists());
+    }
+
+    // ===========================================
+    // Duplicate reference tests
+    // ===========================================
+
+    /// One second of a tone at `hz`, scaled by `gain`.
+    fn tone(hz: f32, gain: f32) -> Vec<u8> {
+        let samples = (0..16_000)
+            .map(|i| gain * 0.5 * (i as f32 * hz * std::f32::consts::TAU / 16_000.0).sin())
+            .collect();
+        crate::audio::AudioBuffer::new(samples, 16_000, 1)
+            .to_wav_bytes()
+            .unwrap()
+    }
+
+    fn voice_with_clip(manager: &VoiceManager, name: &str, clip: &[u8]) {
+        let stored = manager.save_audio(name, clip).unwrap();
+        manager
+            .save_metadata(&VoiceMetadata {
+                audio_path: Some(stored),
+                ..sample_metadata(name)
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_find_duplicate_identical_clip() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        voice_with_clip(&manager, "amy", &tone(440.0, 1.0));
+        let clip = temp_dir.path().join("amy-again.wav");
+        std::fs::write(&clip, tone(440.0, 1.0)).unwrap();
+
+        let duplicate = find_duplicate(&manager, &clip, None).unwrap().unwrap();
+        assert_eq!(duplicate.voice, "amy");
+        assert!(duplicate.identical);
+
+        // Re-extracting a voice from its own clip is not a duplicate
+        assert_eq!(find_duplicate(&manager, &clip, Some("amy")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_duplicate_near_identical_clip() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().join("voices"));
+        voice_with_clip(&manager, "amy", &tone(440.0, 1.0));
+        voice_with_clip(&manager, "bob", &tone(2_000.0, 1.0));
+
+        // The same recording, normalized quieter
+        let clip = temp_dir.path().join("quieter.wav");
+        std::fs::write(&clip, tone(440.0, 0.7)).unwrap();
+        let duplicate = find_duplicate(&manager, &clip, None).unwrap().unwrap();
+        assert_eq!(duplicate.voice, "amy");
+        assert!(!duplicate.identical);
+        assert!(duplicate.similarity >= NEAR_DUPLICATE_SIMILARITY);
+
+        let other = temp_dir.path().join("other.wav");
+        std::fs::write(&other, tone(800.0, 1.0)).unwrap();
+        assert_eq!(find_duplicate(&manager, &other, None).unwrap(), None);
+    }
+
+    // ===========================================
+    // License tests
+    // ===========================================
+
+    fn licensed_voice(name: &str, model: &str, license: Option<VoiceLicense>) -> VoiceMetadata {
+        VoiceMetadata {
+            name: name.to_string(),
+            transcript: "Hello".to_string(),
+            model: model.to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            audio_path: None,
+            language: None,
+            consent: None,
+            locked: false,
+            allowed_uses: Vec::new(),
+            synthetic: false,
+            license,
+        }
+    }
+
+    #[test]
+    fn test_model_license_from_reported_name() {
+        assert_eq!(model_license("openvoice_v2").as_deref(), Some("MIT"));
+        assert_eq!(model_license("OpenF5-TTS").as_deref(), Some("Apache-2.0"));
+        assert_eq!(
+            model_license("OpenF5-TTS + OpenVoice V2").as_deref(),
+            Some("Apache-2.0 AND MIT")
+        );
+        assert_eq!(model_license("mystery"), None);
+    }
+
+    #[test]
+    fn test_license_report_obligations_and_gaps() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = VoiceManager::with_dir(temp_dir.path().to_path_buf());
+        let mut narrator = licensed_voice(
+            "narrator",
+            "openvoice_v2",
+            Some(VoiceLicense {
+                model: Some("MIT".to_string()),
+                reference: Some("CC-BY-NC-4.0".to_string()),
+            }),
+        );
+        narrator.consent = Some(Consent {
+            file: PathBuf::from("consent.pdf"),
+            sha256: "ab".to_string(),
+            speaker: None,
+            license: Some("audiobooks only".to_string()),
+            recorded_at: "2024-01-01T00:00:00Z".to_string(),
+        });
+        manager.save_metadata(&narrator).unwrap();
+        // Saved before licenses were recorded
+        manager
+            .save_metadata(&licensed_voice("old", "openf5_tts", None))
+            .unwrap();
+        let mut stranger = licensed_voice("stranger", "openvoice_v2", None);
+        stranger.synthetic = true;
+        manager.save_metadata(&stranger).unwrap();
+
+        let names = ["narrator", "old", "stranger", "gone"].map(String::from);
+        let report = LicenseReport::build(&manager, Some(&names)).unwrap();
+        assert_eq!(report.missing, vec!["gone".to_string()]);
+
+        let narrator = &report.voices[0];
+        assert!(narrator.warnings.is_empty());
+        assert!(narrator.obligations.iter().any(|o| o.contains("credit")));
+        assert!(
+            narrator
+                .obligations
+                .iter()
+                .any(|o| o.contains("non-commercial"))
+        );
+        assert!(
+            narrator
+                .obligations
+                .iter()
+                .any(|o| o.contains("audiobooks only"))
+        );
+
+        let old = &report.voices[1];
+        assert_eq!(old.model_license.as_deref(), Some("Apache-2.0"));
+        assert_eq!(old.warnings.len(), 2);
+
+        let stranger = &report.voices[2];
+        assert!(stranger.warnings.is_empty());
+        assert!(report.has_warnings());
+
+        // The MIT notice is listed once for both OpenVoice voices
+        let obligations = report.obligations();
+        assert_eq!(
+            obligations.iter().filter(|o| o.starts_with("MIT")).count(),
+            1
+        );
+        let table = report.to_table();
+        assert!(table.contains("stranger  MIT            synthetic     not needed"));
+        assert!(table.contains("gone: not in the local voice store"));
+    }
 }
@@ -0,0 +1,155 @@
+//! Voice packs: distributable archives of ready-made voices.
+//!
+//! A pack (`.ottsvpack`) is a tar archive holding a `pack.json` manifest
+//! and one reference clip per voice. The archive is verified against a
+//! published SHA-256 and each clip against the checksum in the manifest.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use super::VoiceError;
+use crate::manifest::sha256_hex;
+
+/// Name of the manifest inside a pack.
+pub const PACK_MANIFEST: &str = "pack.json";
+
+/// One voice in a pack.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackVoice {
+    pub name: String,
+    pub transcript: String,
+    /// Reference clip path inside the archive.
+    pub file: String,
+    /// Hex-encoded SHA-256 of the clip.
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// Contents of `pack.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    pub voices: Vec<PackVoice>,
+}
+
+/// A verified, unpacked voice pack.
+#[derive(Debug, Clone)]
+pub struct VoicePack {
+    pub manifest: PackManifest,
+    clips: HashMap<String, Vec<u8>>,
+}
+
+impl VoicePack {
+    /// Unpack an archive and verify every clip against the manifest.
+    pub fn from_archive(archive: &[u8]) -> Result<Self, VoiceError> {
+        let mut files = HashMap::new();
+        let mut tar = tar::Archive::new(archive);
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry
+                .path()?
+                .to_string_lossy()
+                .trim_start_matches("./")
+                .to_string();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            files.insert(path, data);
+        }
+
+        let manifest: PackManifest = serde_json::from_slice(
+            files
+                .get(PACK_MANIFEST)
+                .ok_or_else(|| VoiceError::Pack(format!("missing {PACK_MANIFEST}")))?,
+        )?;
+
+        let mut clips = HashMap::new();
+        for voice in &manifest.voices {
+            let data = files
+                .remove(&voice.file)
+                .ok_or_else(|| VoiceError::Pack(format!("missing clip {}", voice.file)))?;
+            if sha256_hex(&data) != voice.sha256.to_lowercase() {
+                return Err(VoiceError::Pack(format!(
+                    "checksum mismatch for {}",
+                    voice.file
+                )));
+            }
+            clips.insert(voice.name.clone(), data);
+        }
+
+        Ok(Self { manifest, clips })
+    }
+
+    /// Reference clip of a voice in the pack.
+    pub fn clip(&self, name: &str) -> Option<&[u8]> {
+        self.clips.get(name).map(Vec::as_slice)
+    }
+}
+
+/// Check an archive against its published SHA-256.
+///
+/// Accepts either a bare hex digest or `sha256sum` output.
+pub fn verify_checksum(archive: &[u8], expected: &str) -> Result<(), VoiceError> {
+    let expected = expected
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let actual = sha256_hex(archive);
+    if actual != expected {
+        return Err(VoiceError::Pack(format!(
+            "archive checksum mismatch: expected {expected}, got {actual}"
+        )));
+    }
+    Ok(())
+}
+
+/// The SHA-256 a pack must match: `given` when passed, otherwise the
+/// `.sha256` file published next to a local pack.
+///
+/// A checksum downloaded from the server that serves the archive only
+/// shows the download is intact; whoever can replace one can replace the
+/// other. Packs fetched over `http(s)://` therefore need `given`.
+pub fn expected_checksum(source: &str, given: Option<&str>) -> Result<String, VoiceError> {
+    if let Some(hex) = given {
+        return Ok(hex.to_string());
+    }
+    if is_url(source) {
+        return Err(VoiceError::Pack(format!(
+            "{source} is downloaded, so its checksum must come from the publisher"
+        )));
+    }
+    let sidecar = format!("{source}.sha256");
+    std::fs::read_to_string(&sidecar)
+        .map_err(|e| VoiceError::Pack(format!("no checksum at {sidecar}: {e}")))
+}
+
+/// Read a pack from an `http(s)://` URL or a local path.
+pub fn fetch(source: &str) -> Result<Vec<u8>, VoiceError> {
+    if !is_url(source) {
+        return Ok(std::fs::read(source)?);
+    }
+
+    let response = reqwest::blocking::get(source).map_err(|e| VoiceError::Pack(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(VoiceError::Pack(format!(
+            "{source}: status {}",
+            response.status()
+        )));
+    }
+    Ok(response
+        .bytes()
+        .map_err(|e| VoiceError::Pack(e.to_string()))?
+        .to_vec())
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
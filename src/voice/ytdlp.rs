@@ -0,0 +1,221 @@
+//! Reference clips cut from online videos with yt-dlp and ffmpeg.
+//!
+//! A source is written `ytdlp:VIDEO_ID@START-END`, e.g.
+//! `ytdlp:dQw4w9WgXcQ@01:12-01:25`. Only the section is downloaded, then
+//! converted to a mono WAV that is kept, so the voice can be re-extracted
+//! later without fetching it again.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use super::VoiceError;
+use crate::paths::long_path;
+
+/// Prefix marking a reference as a video section rather than a file.
+pub const YTDLP_PREFIX: &str = "ytdlp:";
+
+/// Longest section fetched; cloning needs seconds, not minutes.
+const MAX_SECTION: Duration = Duration::from_secs(120);
+
+/// Sample rate of the saved clip.
+const SAMPLE_RATE: u32 = 24000;
+
+/// A section of a video to use as reference audio.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoClip {
+    pub video_id: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+impl VideoClip {
+    /// Parse `VIDEO_ID@START-END`, with or without the `ytdlp:` prefix.
+    /// Times are `SS`, `MM:SS`, or `HH:MM:SS`, with optional fractions.
+    pub fn parse(spec: &str) -> Result<Self, VoiceError> {
+        let invalid = |why: &str| VoiceError::Fetch(format!("'{spec}': {why}"));
+        let spec = spec.trim();
+        let spec = spec.strip_prefix(YTDLP_PREFIX).unwrap_or(spec);
+
+        let (video_id, range) = spec
+            .split_once('@')
+            .ok_or_else(|| invalid("expected VIDEO_ID@START-END"))?;
+        // IDs are passed to yt-dlp inside a URL, never as an argument
+        if video_id.is_empty()
+            || !video_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(invalid("not a video ID"));
+        }
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| invalid("expected a START-END range"))?;
+        let start = parse_timestamp(start).ok_or_else(|| invalid("bad start time"))?;
+        let end = parse_timestamp(end).ok_or_else(|| invalid("bad end time"))?;
+        if end <= start {
+            return Err(invalid("the section ends before it starts"));
+        }
+        if end - start > MAX_SECTION {
+            return Err(invalid(&format!(
+                "sections are limited to {} seconds",
+                MAX_SECTION.as_secs()
+            )));
+        }
+
+        Ok(Self {
+            video_id: video_id.to_string(),
+            start,
+            end,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://www.youtube.com/watch?v={}", self.video_id)
+    }
+
+    /// File name of the clip, unique per video and section.
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}_{}-{}.wav",
+            self.video_id,
+            self.start.as_millis(),
+            self.end.as_millis()
+        )
+    }
+
+    /// Download the section into `dir` and convert it to a mono WAV,
+    /// unless an earlier download is there. Returns the WAV's path.
+    pub fn fetch(&self, dir: &Path) -> Result<PathBuf, VoiceError> {
+        let output = dir.join(self.file_name());
+        if output.exists() {
+            return Ok(output);
+        }
+        std::fs::create_dir_all(dir)?;
+
+        let partial = crate::scratch::scratch_file(&format!("{}.download", self.file_name()));
+        let section = format!(
+            "*{:.3}-{:.3}",
+            self.start.as_secs_f64(),
+            self.end.as_secs_f64()
+        );
+        // Paths go to the tools as they are, not as lossy strings, so
+        // non-UTF-8 and long Windows paths reach them intact
+        run(Command::new("yt-dlp")
+            .args([
+                "--quiet",
+                "--no-playlist",
+                "--format",
+                "bestaudio",
+                "--download-sections",
+                &section,
+                "--force-keyframes-at-cuts",
+                "--force-overwrites",
+                "--output",
+            ])
+            .arg(long_path(&partial).as_os_str())
+            .args(["--", &self.url()]))?;
+
+        let converted = run(Command::new("ffmpeg")
+            .args(["-nostdin", "-loglevel", "error", "-y", "-i"])
+            .arg(long_path(&partial).as_os_str())
+            .args(["-ac", "1", "-ar", &SAMPLE_RATE.to_string()])
+            .args(["-c:a", "pcm_s16le"])
+            .arg(long_path(&output).as_os_str()));
+        let _ = std::fs::remove_file(&partial);
+        if converted.is_err() {
+            let _ = std::fs::remove_file(&output);
+        }
+        converted.map(|()| output)
+    }
+}
+
+/// Run a tool to completion, reporting its error output on failure.
+fn run(command: &mut Command) -> Result<(), VoiceError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                VoiceError::Fetch(format!("{program} is not installed"))
+            }
+            _ => VoiceError::Fetch(format!("failed to run {program}: {e}")),
+        })?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(VoiceError::Fetch(format!(
+        "{program} failed ({}): {}",
+        output.status,
+        stderr.trim()
+    )))
+}
+
+/// Parse `SS`, `MM:SS`, or `HH:MM:SS`, the seconds allowing a fraction.
+fn parse_timestamp(input: &str) -> Option<Duration> {
+    let parts: Vec<&str> = input.trim().split(':').collect();
+    let (seconds, larger) = parts.split_last()?;
+    if larger.len() > 2 {
+        return None;
+    }
+    let seconds: f64 = seconds.parse().ok()?;
+    if !seconds.is_finite() || seconds < 0.0 || (!larger.is_empty() && seconds >= 60.0) {
+        return None;
+    }
+
+    // Minutes, then hours
+    let mut total = seconds;
+    for (i, (part, unit)) in larger.iter().rev().zip([60.0, 3600.0]).enumerate() {
+        let value: u32 = part.parse().ok()?;
+        let is_minutes_below_hours = i == 0 && larger.len() == 2;
+        if is_minutes_below_hours && value >= 60 {
+            return None;
+        }
+        total += f64::from(value) * unit;
+    }
+    Some(Duration::from_secs_f64(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_video_clip() {
+        let clip = VideoClip::parse("ytdlp:dQw4w9WgXcQ@01:12-01:25.5").unwrap();
+        assert_eq!(clip.video_id, "dQw4w9WgXcQ");
+        assert_eq!(clip.start, Duration::from_secs(72));
+        assert_eq!(clip.end, Duration::from_secs_f64(85.5));
+        assert_eq!(clip.url(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(clip.file_name(), "dQw4w9WgXcQ_72000-85500.wav");
+
+        let clip = VideoClip::parse("-abc_123@1:00:05-1:00:20").unwrap();
+        assert_eq!(clip.start, Duration::from_secs(3605));
+
+        for bad in [
+            "dQw4w9WgXcQ",
+            "dQw4w9WgXcQ@01:12",
+            "dQw4w9WgXcQ@01:25-01:12",
+            "dQw4w9WgXcQ@00:00-05:00",
+            "dQw4w9WgXcQ@01:75-01:80",
+            "dQw4w9WgXcQ@1:60:00-1:60:10",
+            "https://youtu.be/x@1-2",
+            "@1-2",
+        ] {
+            assert!(VideoClip::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_fetch_reuses_earlier_download() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let clip = VideoClip::parse("dQw4w9WgXcQ@10-20").unwrap();
+        std::fs::write(dir.path().join(clip.file_name()), b"RIFF").unwrap();
+        assert_eq!(
+            clip.fetch(dir.path()).unwrap(),
+            dir.path().join(clip.file_name())
+        );
+    }
+}